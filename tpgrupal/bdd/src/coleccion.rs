@@ -0,0 +1,323 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use protocolo::parser_cql::ast::{Asignacion, Termino};
+use protocolo::serial_deserial::tipo_columna::{
+    clave_y_valor, elementos_coleccion, TipoColumna, SEPARADOR_ELEMENTOS,
+};
+
+use crate::tabla::comparar;
+
+/// Estado de la colección, o del UDT sin frozen, de una columna de una fila, con el
+/// momento en que se escribió cada elemento o campo. Las escrituras se mezclan elemento
+/// por elemento: de cada uno gana la más reciente, así que dos réplicas que aplican las
+/// mismas escrituras en distinto orden terminan con los mismos elementos
+#[derive(Debug, Clone)]
+pub struct Coleccion {
+    borrado: i64, // Momento del último borrado o reemplazo de toda la colección
+    elementos: BTreeMap<String, (i64, Option<String>)>, // K = Elemento, clave, posición en la lista o campo, V = Momento y valor (None si se borró)
+}
+
+impl Coleccion {
+    /// Estado de una colección escrita en una celda en el momento indicado; las filas
+    /// leídas del disco se cargan con momento 0
+    pub fn desde_celda(tipo: &TipoColumna, celda: &str, momento: i64) -> Self {
+        let mut coleccion = Coleccion {
+            borrado: i64::MIN,
+            elementos: BTreeMap::new(),
+        };
+        coleccion.reemplazar(tipo, celda, momento);
+        coleccion
+    }
+
+    /// Reemplaza la colección por la de la celda, como un INSERT o un `c = [...]`
+    pub fn reemplazar(&mut self, tipo: &TipoColumna, celda: &str, momento: i64) {
+        self.borrar(momento - 1);
+        self.agregar(tipo, celda, momento, false);
+    }
+
+    /// Aplica la asignación de un UPDATE, o el borrado de un DELETE, a la colección
+    pub fn asignar(&mut self, tipo: &TipoColumna, asignacion: &Asignacion, momento: i64) {
+        let valor = match &asignacion.valor {
+            Termino::Nulo => None,
+            valor => Some(celda_valor(valor)),
+        };
+        if let Some(campo) = &asignacion.campo {
+            return self.poner(campo.to_string(), valor, momento);
+        }
+        match (&asignacion.elemento, &asignacion.valor) {
+            (Some(elemento), _) => self.asignar_elemento(tipo, elemento, valor, momento),
+            (None, Termino::Nulo) => self.borrar(momento),
+            (None, Termino::Agregado(_, literal)) => {
+                self.agregar(tipo, &celda_valor(literal), momento, false)
+            }
+            (None, Termino::Antepuesto(literal, _)) => {
+                self.agregar(tipo, &celda_valor(literal), momento, true)
+            }
+            (None, Termino::Quitado(_, literal)) => {
+                self.quitar(tipo, &celda_valor(literal), momento)
+            }
+            (None, literal) => self.reemplazar(tipo, &celda_valor(literal), momento),
+        }
+    }
+
+    // m['clave'] = valor o l[posición] = valor; una posición que no existe se ignora
+    fn asignar_elemento(
+        &mut self,
+        tipo: &TipoColumna,
+        elemento: &Termino,
+        valor: Option<String>,
+        momento: i64,
+    ) {
+        let clave = match tipo {
+            TipoColumna::List(_) => elemento
+                .to_string()
+                .parse::<usize>()
+                .ok()
+                .and_then(|posicion| self.vivos().nth(posicion))
+                .map(|(clave, _)| clave.to_string()),
+            _ => Some(elemento.to_string()),
+        };
+        if let Some(clave) = clave {
+            self.poner(clave, valor, momento);
+        }
+    }
+
+    // Borra todos los elementos escritos hasta el momento indicado
+    fn borrar(&mut self, momento: i64) {
+        self.borrado = self.borrado.max(momento);
+        let borrado = self.borrado;
+        self.elementos
+            .retain(|_, (escritura, _)| *escritura > borrado);
+    }
+
+    // Agrega los elementos de la celda; en una lista, al final o al principio. Los
+    // campos de un UDT que no tienen valor quedan borrados
+    fn agregar(&mut self, tipo: &TipoColumna, celda: &str, momento: i64, al_principio: bool) {
+        for (indice, elemento) in elementos_coleccion(celda).into_iter().enumerate() {
+            match tipo {
+                TipoColumna::List(_) => {
+                    let posicion = posicion_lista(momento, indice, al_principio);
+                    self.poner(posicion, Some(elemento.to_string()), momento);
+                }
+                TipoColumna::Map(_, _) | TipoColumna::Udt(..) => {
+                    if let Some((clave, valor)) = clave_y_valor(elemento) {
+                        let valor = (!valor.is_empty()).then(|| valor.to_string());
+                        self.poner(clave.to_string(), valor, momento);
+                    }
+                }
+                _ => self.poner(elemento.to_string(), Some(String::new()), momento),
+            }
+        }
+    }
+
+    // Quita los elementos de un set, las claves de un map o los valores de una lista
+    fn quitar(&mut self, tipo: &TipoColumna, celda: &str, momento: i64) {
+        for elemento in elementos_coleccion(celda) {
+            let claves = match tipo {
+                TipoColumna::List(_) => self
+                    .vivos()
+                    .filter(|(_, valor)| comparar(valor, elemento) == Some(Ordering::Equal))
+                    .map(|(clave, _)| clave.to_string())
+                    .collect(),
+                _ => vec![elemento.to_string()],
+            };
+            for clave in claves {
+                self.poner(clave, None, momento);
+            }
+        }
+    }
+
+    // Escribe el elemento si la escritura es más reciente que la que tiene; ante el
+    // mismo momento gana el borrado
+    fn poner(&mut self, clave: String, valor: Option<String>, momento: i64) {
+        if momento <= self.borrado {
+            return;
+        }
+        let vigente = self
+            .elementos
+            .get(&clave)
+            .is_some_and(|(escritura, actual)| {
+                *escritura > momento || (*escritura == momento && actual.is_none())
+            });
+        if !vigente {
+            self.elementos.insert(clave, (momento, valor));
+        }
+    }
+
+    // Elementos que no se borraron, con su valor, en el orden de sus claves
+    fn vivos(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.elementos
+            .iter()
+            .filter_map(|(clave, (_, valor))| Some((clave.as_str(), valor.as_deref()?)))
+    }
+
+    /// Celda con los elementos vigentes: [a;b], {a;b}, {clave:valor;...} o
+    /// {campo:valor;...}, con los de un set y las claves de un map ordenados y los campos
+    /// de un UDT en el orden del tipo. Una colección vacía es nula
+    pub fn valor(&self, tipo: &TipoColumna) -> String {
+        let mut vivos = self.vivos().collect::<Vec<(&str, &str)>>();
+        if vivos.is_empty() {
+            return String::new();
+        }
+        match tipo {
+            TipoColumna::List(_) => {}
+            TipoColumna::Udt(udt, _) => vivos.sort_by_key(|(campo, _)| {
+                udt.campos.iter().position(|(nombre, _)| nombre == campo)
+            }),
+            _ => {
+                vivos.sort_by(|(una, _), (otra, _)| comparar(una, otra).unwrap_or(Ordering::Equal))
+            }
+        }
+        let elementos = vivos.into_iter().map(|(clave, valor)| match tipo {
+            TipoColumna::List(_) => valor.to_string(),
+            TipoColumna::Map(_, _) | TipoColumna::Udt(..) => format!("{}:{}", clave, valor),
+            _ => clave.to_string(),
+        });
+        let elementos = elementos
+            .collect::<Vec<String>>()
+            .join(&SEPARADOR_ELEMENTOS.to_string());
+        match tipo {
+            TipoColumna::List(_) => format!("[{}]", elementos),
+            _ => format!("{{{}}}", elementos),
+        }
+    }
+}
+
+/// Texto con el que se guarda un valor en su celda: las colecciones como [a;b], {a;b}
+/// o {clave:valor;...}, las tuplas como (a;b), los UDT como {campo:valor;...}, null
+/// como una celda vacía y los demás como se escribieron
+pub fn celda_valor(literal: &Termino) -> String {
+    let unir = |elementos: Vec<String>| elementos.join(&SEPARADOR_ELEMENTOS.to_string());
+    let textos = |elementos: &[Termino]| elementos.iter().map(celda_valor).collect();
+    match literal {
+        Termino::Lista(elementos) => format!("[{}]", unir(textos(elementos))),
+        Termino::Conjunto(elementos) => format!("{{{}}}", unir(textos(elementos))),
+        Termino::Tupla(elementos) => format!("({})", unir(textos(elementos))),
+        Termino::Mapa(pares) => format!(
+            "{{{}}}",
+            unir(
+                pares
+                    .iter()
+                    .map(|(clave, valor)| format!("{}:{}", clave, celda_valor(valor)))
+                    .collect()
+            )
+        ),
+        Termino::Nulo => String::new(),
+        otro => otro.to_string(),
+    }
+}
+
+/// Literal CQL de la colección, la tupla o el UDT de una celda, con los elementos
+/// separados por comas y los que no tienen valor como null
+pub fn literal_celda(celda: &str) -> String {
+    let celda = celda.trim();
+    let literal = |valor: &str| match valor.is_empty() {
+        true => "null".to_string(),
+        false => valor.to_string(),
+    };
+    let con_claves = celda.starts_with('{');
+    let elementos = elementos_coleccion(celda)
+        .into_iter()
+        .map(|elemento| match clave_y_valor(elemento) {
+            Some((clave, valor)) if con_claves => format!("{}: {}", clave, literal(valor)),
+            _ => literal(elemento),
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    match celda.chars().next() {
+        Some('[') => format!("[{}]", elementos),
+        Some('(') => format!("({})", elementos),
+        _ => format!("{{{}}}", elementos),
+    }
+}
+
+// Clave que ordena los elementos de una lista: los agregados al final por el momento en
+// que se agregaron, y los agregados al principio antes que todos y en orden inverso
+fn posicion_lista(momento: i64, indice: usize, al_principio: bool) -> String {
+    match al_principio {
+        true => format!("0{:020}{:06}", i64::MAX - momento, indice),
+        false => format!("1{:020}{:06}", momento, indice),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocolo::parser_cql::ast::Sentencia;
+    use protocolo::parser_cql::parser::parsear;
+
+    // Asignación del SET de un UPDATE
+    fn asignacion(cambio: &str) -> Asignacion {
+        let consulta = format!("UPDATE VUELOS SET {} WHERE ID_VUELO = 1", cambio);
+        match parsear(&consulta) {
+            Ok(Sentencia::Update { asignaciones, .. }) => asignaciones[0].clone(),
+            _ => panic!("UPDATE inválido: {}", consulta),
+        }
+    }
+
+    // Aplica las escrituras en el orden indicado sobre la misma celda inicial
+    fn aplicar(tipo: &TipoColumna, escrituras: &[(&str, i64)], orden: &[usize]) -> String {
+        let mut coleccion = Coleccion::desde_celda(tipo, "", 0);
+        for indice in orden {
+            let (cambio, momento) = escrituras[*indice];
+            coleccion.asignar(tipo, &asignacion(cambio), momento);
+        }
+        coleccion.valor(tipo)
+    }
+
+    fn ordenes() -> [[usize; 3]; 6] {
+        [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ]
+    }
+
+    #[test]
+    fn test_set_mezcla_los_elementos_en_cualquier_orden() {
+        //Arrange
+        let tipo = TipoColumna::create("set<text>").unwrap();
+        let escrituras = [
+            ("PUERTAS = PUERTAS + {'A4', 'A5'}", 10),
+            ("PUERTAS = PUERTAS - {'A4'}", 20),
+            ("PUERTAS = PUERTAS + {'B1'}", 15),
+        ];
+
+        //Act
+        let valores = ordenes().map(|orden| aplicar(&tipo, &escrituras, &orden));
+
+        //Assert
+        assert!(valores.iter().all(|valor| *valor == "{'A5';'B1'}"));
+    }
+
+    #[test]
+    fn test_map_mezcla_los_elementos_en_cualquier_orden() {
+        //Arrange
+        let tipo = TipoColumna::create("map<text, float>").unwrap();
+        let escrituras = [
+            ("TRAMOS['AEP'] = 1.5", 10),
+            ("TRAMOS['AEP'] = null", 10),
+            ("TRAMOS = TRAMOS + {'EZE': 2.5}", 5),
+        ];
+        let reemplazos = [
+            ("TRAMOS['AEP'] = 1.5", 20),
+            ("TRAMOS = {'COR': 3.5}", 30),
+            ("TRAMOS['MDZ'] = 4.5", 40),
+        ];
+
+        //Act
+        let valores = ordenes().map(|orden| aplicar(&tipo, &escrituras, &orden));
+        let reemplazados = ordenes().map(|orden| aplicar(&tipo, &reemplazos, &orden));
+
+        //Assert
+        // Ante el mismo momento gana el borrado, y el reemplazo descarta lo anterior
+        assert!(valores.iter().all(|valor| *valor == "{'EZE':2.5}"));
+        assert!(reemplazados
+            .iter()
+            .all(|valor| *valor == "{'COR':3.5;'MDZ':4.5}"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+// Separadores del estado de un contador dentro de su celda, que no pueden ser
+// comas porque las filas se guardan con los valores separados por comas
+const SEPARADOR_SHARDS: char = ';';
+const SEPARADOR_CAMPOS: char = '/';
+// Celda de un contador que todavía no tiene shards
+const SIN_SHARDS: &str = "0";
+
+/// Estado de una columna counter en una réplica: un shard por cada nodo que lideró
+/// incrementos, con su reloj lógico y el total de los incrementos que aplicó. Cada
+/// nodo solo modifica su propio shard, así que las réplicas mezclan sus estados
+/// quedándose con el shard de reloj más alto de cada nodo, y el valor es la suma
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Contador {
+    shards: BTreeMap<String, (u64, i64)>, // K: ip del nodo; V: (reloj, valor)
+}
+
+impl Contador {
+    /// Lee el contador de la celda en la que se guarda, "ip/reloj/valor;ip/reloj/valor"
+    pub fn desde_celda(celda: &str) -> Self {
+        let mut shards = BTreeMap::new();
+        for shard in celda.trim().split(SEPARADOR_SHARDS) {
+            let campos = shard.split(SEPARADOR_CAMPOS).collect::<Vec<&str>>();
+            if let [ip, reloj, valor] = campos.as_slice() {
+                if let (Ok(reloj), Ok(valor)) = (reloj.parse::<u64>(), valor.parse::<i64>()) {
+                    shards.insert(ip.to_string(), (reloj, valor));
+                }
+            }
+        }
+        Contador { shards }
+    }
+
+    /// Celda con la que se guarda el contador en la fila
+    pub fn a_celda(&self) -> String {
+        if self.shards.is_empty() {
+            return SIN_SHARDS.to_string();
+        }
+        self.shards
+            .iter()
+            .map(|(ip, (reloj, valor))| {
+                format!("{ip}{SEPARADOR_CAMPOS}{reloj}{SEPARADOR_CAMPOS}{valor}")
+            })
+            .collect::<Vec<String>>()
+            .join(&SEPARADOR_SHARDS.to_string())
+    }
+
+    /// Suma el incremento al shard del nodo que lo lidera, avanzando su reloj
+    pub fn incrementar(&mut self, ip: &str, delta: i64) {
+        let (reloj, valor) = self.shards.entry(ip.to_string()).or_default();
+        *reloj += 1;
+        *valor += delta;
+    }
+
+    /// Mezcla el estado de otra réplica, quedándose con el shard más nuevo de cada nodo
+    pub fn mezclar(&mut self, otro: &Contador) {
+        for (ip, shard) in &otro.shards {
+            let actual = self.shards.entry(ip.to_string()).or_default();
+            if shard.0 > actual.0 {
+                *actual = *shard;
+            }
+        }
+    }
+
+    /// Valor del contador: la suma de los shards de todos los nodos
+    pub fn valor(&self) -> i64 {
+        self.shards.values().map(|(_, valor)| valor).sum()
+    }
+}
+
+/// Reconcilia la lectura de una tabla con contadores: mezcla los shards de las filas
+/// con la misma clave primaria, cuyas columnas están en las posiciones `claves` de las
+/// filas (la misma fila leída de distintas réplicas), y reemplaza cada contador, en las
+/// posiciones `contadores`, por la suma de sus shards
+pub fn reconciliar_contadores(
+    filas: Vec<String>,
+    claves: &[usize],
+    contadores: &[usize],
+) -> Vec<String> {
+    let mut mezcladas: Vec<(Vec<String>, Vec<String>, Vec<Contador>)> = vec![];
+    for fila in filas {
+        let celdas: Vec<String> = fila.split(",").map(|celda| celda.to_string()).collect();
+        let celda = |indice: &usize| celdas.get(*indice).cloned().unwrap_or_default();
+        let clave: Vec<String> = claves.iter().map(celda).collect();
+        let estados: Vec<Contador> = contadores
+            .iter()
+            .map(|indice| Contador::desde_celda(&celda(indice)))
+            .collect();
+        match mezcladas.iter_mut().find(|(otra, _, _)| *otra == clave) {
+            Some((_, _, existentes)) => {
+                for (existente, estado) in existentes.iter_mut().zip(&estados) {
+                    existente.mezclar(estado);
+                }
+            }
+            None => mezcladas.push((clave, celdas, estados)),
+        }
+    }
+    mezcladas
+        .into_iter()
+        .map(|(_, mut celdas, estados)| {
+            for (indice, estado) in contadores.iter().zip(estados) {
+                if let Some(celda) = celdas.get_mut(*indice) {
+                    *celda = estado.valor().to_string();
+                }
+            }
+            celdas.join(",")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mezclar_se_queda_con_el_shard_mas_nuevo_de_cada_nodo() {
+        //Arrange
+        let una = Contador::desde_celda("127.0.0.1/3/10;127.0.0.2/1/4");
+        let otra = Contador::desde_celda("127.0.0.1/2/7;127.0.0.2/2/6;127.0.0.3/1/1");
+
+        //Act
+        let mut primero_una = una.clone();
+        primero_una.mezclar(&otra);
+        let mut primero_otra = otra.clone();
+        primero_otra.mezclar(&una);
+
+        //Assert
+        assert_eq!(primero_una, primero_otra);
+        assert_eq!(primero_una.valor(), 17);
+        assert_eq!(
+            primero_una.a_celda(),
+            "127.0.0.1/3/10;127.0.0.2/2/6;127.0.0.3/1/1"
+        );
+    }
+
+    #[test]
+    fn test_incrementar_solo_modifica_el_shard_del_nodo() {
+        //Arrange
+        let mut contador = Contador::desde_celda(SIN_SHARDS);
+
+        //Act
+        contador.incrementar("127.0.0.1", 5);
+        contador.incrementar("127.0.0.2", -2);
+        contador.incrementar("127.0.0.1", 1);
+
+        //Assert
+        assert_eq!(contador.a_celda(), "127.0.0.1/2/6;127.0.0.2/1/-2");
+        assert_eq!(contador.valor(), 4);
+    }
+
+    #[test]
+    fn test_reconciliar_mezcla_las_filas_con_la_misma_clave_primaria() {
+        //Arrange
+        let filas = vec![
+            "AEP,1,127.0.0.1/2/5".to_string(),
+            "EZE,1,127.0.0.1/1/7".to_string(),
+            "AEP,1,127.0.0.1/1/3;127.0.0.2/1/4".to_string(),
+        ];
+
+        //Act
+        let reconciliadas = reconciliar_contadores(filas, &[0, 1], &[2]);
+
+        //Assert
+        assert_eq!(reconciliadas, ["AEP,1,9", "EZE,1,7"]);
+    }
+
+    #[test]
+    fn test_reconciliar_no_mezcla_filas_distintas_con_los_mismos_contadores() {
+        //Arrange
+        let filas = vec![
+            "127.0.0.1/1/5,AEP".to_string(),
+            "127.0.0.1/1/5,EZE".to_string(),
+            "127.0.0.2/1/2,AEP".to_string(),
+        ];
+
+        //Act
+        let reconciliadas = reconciliar_contadores(filas, &[1], &[0]);
+
+        //Assert
+        assert_eq!(reconciliadas, ["7,AEP", "5,EZE"]);
+    }
+}
@@ -0,0 +1,751 @@
+use std::{
+    collections::VecDeque,
+    env,
+    io::Write,
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use protocolo::parser_cql::condicion_lwt::CondicionLwt;
+use protocolo::serial_deserial::{
+    cassandra::eventos::Mutacion,
+    intra_nodos::{
+        cdc::leer_mutacion_cdc,
+        deserializador_nodo_respuesta::deserializar_respuesta_nodos,
+        paxos::{
+            campos_respuesta_paxos, serializar_paxos, ACEPTADA, CONFIRMAR, PREPARAR, PROPONER,
+        },
+        serializador_nodo_envio::serializar_envio_nodos,
+    },
+    level_consistency::{
+        escritura_fallida, lectura_fallida, tiempo_agotado_escritura, tiempo_agotado_lectura,
+        LevelConsistency, TIEMPO_AGOTADO_ESCRITURA,
+    },
+};
+use rand::Rng;
+use rustls::{ClientConnection, StreamOwned};
+use seguridad::{config_cliente_internodos, nombre_servidor};
+
+use crate::{
+    nodo::{obtener_nombre_servicio, DIGEST, LIDER_CONTADOR, PUERTO_INTERNODOS, REPLICA},
+    paxos::{mayor_rechazo, nuevo_ballot, resultado_lwt, Promesa},
+    procesamiento_data::hashear,
+};
+
+pub const VARIABLE_TIMEOUT_ESCRITURA: &str = "WRITE_REQUEST_TIMEOUT_MS";
+pub const VARIABLE_TIMEOUT_LECTURA: &str = "READ_REQUEST_TIMEOUT_MS";
+pub const VARIABLE_PERCENTIL_REINTENTO: &str = "SPECULATIVE_RETRY_PERCENTIL";
+const TIMEOUT_ESCRITURA_POR_DEFECTO: Duration = Duration::from_millis(2000);
+const TIMEOUT_LECTURA_POR_DEFECTO: Duration = Duration::from_millis(5000);
+const PERCENTIL_REINTENTO_POR_DEFECTO: usize = 99;
+// Mientras haya menos muestras que estas se usa el umbral por defecto
+const MUESTRAS_MINIMAS: usize = 10;
+const MUESTRAS_MAXIMAS: usize = 1000;
+const UMBRAL_REINTENTO_POR_DEFECTO: Duration = Duration::from_millis(100);
+// Rondas de Paxos que se intentan antes de dar por agotado el tiempo de una transacción
+const MAX_INTENTOS_PAXOS: u32 = 5;
+
+type TxLectura = Sender<(String, bool, Result<RespuestaLectura, String>)>;
+
+/// Tiempo que el coordinador espera los ACKs de una escritura (write_request_timeout).
+/// Se configura en milisegundos con la variable de entorno WRITE_REQUEST_TIMEOUT_MS
+pub fn timeout_escritura() -> Duration {
+    numero_de_entorno(VARIABLE_TIMEOUT_ESCRITURA)
+        .map(Duration::from_millis)
+        .unwrap_or(TIMEOUT_ESCRITURA_POR_DEFECTO)
+}
+
+/// Tiempo que el coordinador espera las respuestas de una lectura (read_request_timeout).
+/// Se configura en milisegundos con la variable de entorno READ_REQUEST_TIMEOUT_MS
+pub fn timeout_lectura() -> Duration {
+    numero_de_entorno(VARIABLE_TIMEOUT_LECTURA)
+        .map(Duration::from_millis)
+        .unwrap_or(TIMEOUT_LECTURA_POR_DEFECTO)
+}
+
+fn numero_de_entorno(variable: &str) -> Option<u64> {
+    env::var(variable).ok()?.trim().parse().ok()
+}
+
+/// Respuesta de una réplica a una lectura: las filas, si se le pidieron los datos
+/// completos, o solo su digest, junto con el timestamp del nodo para el read repair
+#[derive(Debug, Clone, PartialEq)]
+pub struct RespuestaLectura {
+    pub ip: String,
+    pub filas: Option<Vec<String>>,
+    pub digest: String,
+    pub timestamp: String,
+}
+
+impl RespuestaLectura {
+    pub fn con_datos(ip: &str, filas: Vec<String>, timestamp: String) -> Self {
+        RespuestaLectura {
+            ip: ip.to_string(),
+            digest: digest(&filas),
+            filas: Some(filas),
+            timestamp,
+        }
+    }
+}
+
+/// Digest de las filas que devuelve una réplica. No depende del orden de las filas,
+/// que puede variar entre réplicas según el orden en que les llegaron las escrituras
+pub fn digest(filas: &[String]) -> String {
+    let mut ordenadas = filas.to_vec();
+    ordenadas.sort();
+    ordenadas.dedup();
+    hashear(&ordenadas.join("\n"))
+        .map(|hash| hash.to_string())
+        .unwrap_or_default()
+}
+
+/// Latencias de las últimas lecturas de datos a réplicas, con las que se calcula a
+/// partir de cuánto tiempo sin respuesta se hace un speculative retry
+#[derive(Debug, Default)]
+pub struct LatenciasLectura {
+    muestras: VecDeque<Duration>,
+}
+
+impl LatenciasLectura {
+    pub fn registrar(&mut self, latencia: Duration) {
+        if self.muestras.len() >= MUESTRAS_MAXIMAS {
+            self.muestras.pop_front();
+        }
+        self.muestras.push_back(latencia);
+    }
+
+    /// Percentil de las latencias configurado en SPECULATIVE_RETRY_PERCENTIL (99 por
+    /// defecto), o un umbral fijo mientras no haya suficientes muestras
+    pub fn umbral_reintento(&self) -> Duration {
+        if self.muestras.len() < MUESTRAS_MINIMAS {
+            return UMBRAL_REINTENTO_POR_DEFECTO;
+        }
+        let percentil = numero_de_entorno(VARIABLE_PERCENTIL_REINTENTO)
+            .map(|percentil| percentil.clamp(1, 100) as usize)
+            .unwrap_or(PERCENTIL_REINTENTO_POR_DEFECTO);
+        let mut ordenadas: Vec<Duration> = self.muestras.iter().copied().collect();
+        ordenadas.sort();
+        let posicion = (ordenadas.len() * percentil).div_ceil(100).max(1) - 1;
+        ordenadas[posicion]
+    }
+}
+
+/// Lee de las réplicas hasta tener `requeridas` respuestas, una de ellas con los datos
+/// completos. Si `local` tiene la lectura de este nodo se la usa como dato y a las
+/// réplicas remotas solo se les pide el digest; si no, se le piden los datos a la
+/// primera y, si no responde dentro del umbral de `latencias`, se hace un speculative
+/// retry pidiéndoselos a otra. Devuelve un error ReadTimeout si no se reúnen a tiempo
+/// o ReadFailure si fallaron demasiadas réplicas
+pub fn leer_replicas(
+    remotas: &[String],
+    consulta: &str,
+    consistencia: &LevelConsistency,
+    (local, requeridas): (Option<RespuestaLectura>, usize),
+    latencias: &mut LatenciasLectura,
+) -> Result<Vec<RespuestaLectura>, String> {
+    let timeout = timeout_lectura();
+    let inicio = Instant::now();
+    let limite = inicio + timeout;
+    let (tx, rx) = mpsc::channel();
+    let mut pendientes = remotas.iter();
+    let mut en_curso = 0;
+    let mut respuestas: Vec<RespuestaLectura> = local.into_iter().collect();
+    let mut tiene_datos = !respuestas.is_empty();
+    let mut reintento = None;
+    if !tiene_datos {
+        if let Some(ip) = pendientes.next() {
+            pedir_lectura(&tx, ip, (consulta, consistencia), true, timeout);
+            en_curso += 1;
+            reintento = Some(inicio + latencias.umbral_reintento());
+        }
+    }
+    for ip in pendientes.by_ref().take(requeridas.saturating_sub(1)) {
+        pedir_lectura(&tx, ip, (consulta, consistencia), false, timeout);
+        en_curso += 1;
+    }
+
+    while !tiene_datos || respuestas.len() < requeridas {
+        let espera = reintento.map_or(limite, |reintento: Instant| reintento.min(limite));
+        match rx.recv_timeout(espera.saturating_duration_since(Instant::now())) {
+            Ok((_, datos, Ok(respuesta))) => {
+                en_curso -= 1;
+                if datos && !tiene_datos {
+                    latencias.registrar(inicio.elapsed());
+                    tiene_datos = true;
+                    reintento = None;
+                }
+                respuestas.push(respuesta);
+            }
+            Ok((ip, datos, Err(e))) => {
+                en_curso -= 1;
+                println!("La réplica {} no respondió la lectura: {}", ip, e);
+                // Se reemplaza por otra réplica, si queda alguna
+                let pedir_datos = datos && !tiene_datos;
+                match pendientes.next() {
+                    Some(ip) => {
+                        pedir_lectura(&tx, ip, (consulta, consistencia), pedir_datos, timeout);
+                        en_curso += 1;
+                    }
+                    None if en_curso == 0 => {
+                        return Err(lectura_fallida(respuestas.len(), requeridas))
+                    }
+                    None => {}
+                }
+            }
+            Err(RecvTimeoutError::Timeout) if Instant::now() < limite => {
+                reintento = None;
+                if let Some(ip) = pendientes.next() {
+                    println!(
+                        "Speculative retry: se le piden los datos a la réplica {}",
+                        ip
+                    );
+                    pedir_lectura(&tx, ip, (consulta, consistencia), true, timeout);
+                    en_curso += 1;
+                }
+            }
+            Err(_) => return Err(tiempo_agotado_lectura(respuestas.len(), requeridas)),
+        }
+    }
+    Ok(respuestas)
+}
+
+/// Pide los datos completos a las réplicas indicadas, en paralelo, devolviendo las
+/// respuestas de las que contestaron a tiempo
+pub fn pedir_datos(replicas: &[String], consulta: &str) -> Vec<RespuestaLectura> {
+    let timeout = timeout_lectura();
+    let (tx, rx) = mpsc::channel();
+    for ip in replicas {
+        pedir_lectura(&tx, ip, (consulta, &LevelConsistency::One), true, timeout);
+    }
+    drop(tx);
+    rx.into_iter()
+        .filter_map(|(_, _, respuesta)| respuesta.ok())
+        .collect()
+}
+
+fn pedir_lectura(
+    tx: &TxLectura,
+    ip: &str,
+    (consulta, consistencia): (&str, &LevelConsistency),
+    datos: bool,
+    timeout: Duration,
+) {
+    let tx = tx.clone();
+    let ip = ip.to_string();
+    let consulta = consulta.to_string();
+    let consistencia = consistencia.clone();
+    thread::spawn(move || {
+        let resultado = leer_de_replica(&ip, &consulta, consistencia, datos, timeout);
+        let _ = tx.send((ip, datos, resultado));
+    });
+}
+
+fn leer_de_replica(
+    ip_replica: &str,
+    consulta: &str,
+    consistencia: LevelConsistency,
+    datos: bool,
+    timeout: Duration,
+) -> Result<RespuestaLectura, String> {
+    let responsabilidad = if datos { REPLICA } else { DIGEST };
+    let mut respuesta = enviar_a_replica(
+        ip_replica,
+        &serializar_envio_nodos(consulta, consistencia, responsabilidad),
+        timeout,
+    )?;
+    // La réplica agrega su timestamp al final de la respuesta
+    let timestamp = respuesta
+        .pop()
+        .ok_or("Respuesta de la réplica vacía".to_string())?;
+    if datos {
+        return Ok(RespuestaLectura::con_datos(
+            ip_replica, respuesta, timestamp,
+        ));
+    }
+    Ok(RespuestaLectura {
+        ip: ip_replica.to_string(),
+        filas: None,
+        digest: respuesta.pop().unwrap_or_default(),
+        timestamp,
+    })
+}
+
+/// Lo que queda de una consulta después de ejecutar la parte que necesita al nodo
+pub enum Ejecucion {
+    /// La consulta terminó, con las filas del resultado si las tiene
+    Terminada(Option<Vec<String>>),
+    /// Falta enviar las escrituras a las demás réplicas
+    Pendiente(Vec<EscrituraPendiente>),
+    /// Falta que la réplica que lidera el incremento de contadores lo confirme
+    Reenviada(ReenvioLider),
+    /// Falta coordinar la transacción liviana con las réplicas de la partición
+    Transaccion(TransaccionPendiente),
+    /// Falta enviar la sentencia DDL o el TRUNCATE a los demás nodos
+    Difusion(DifusionPendiente),
+}
+
+impl Ejecucion {
+    /// Termina la consulta con los envíos que faltan a los demás nodos, que se hacen sin
+    /// tener el nodo bloqueado. Si la consulta es una transacción liviana, este nodo
+    /// atiende las fases de Paxos que le tocan como réplica con `atender_paxos`
+    pub fn terminar(
+        self,
+        atender_paxos: impl FnMut(u8, u64, &str) -> Result<Vec<String>, String>,
+    ) -> Result<Option<Vec<String>>, String> {
+        match self {
+            Ejecucion::Terminada(filas) => Ok(filas),
+            Ejecucion::Pendiente(escrituras) => {
+                for escritura in escrituras {
+                    escritura.enviar()?;
+                }
+                Ok(None)
+            }
+            Ejecucion::Reenviada(reenvio) => reenvio.enviar().map(|_| None),
+            Ejecucion::Transaccion(transaccion) => transaccion
+                .coordinar(atender_paxos)
+                .map(|fila| Some(vec![fila])),
+            Ejecucion::Difusion(difusion) => {
+                difusion.enviar();
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Escritura que el coordinador ya aplicó en sus tablas, si es réplica de la partition
+/// key, y que falta enviar a las réplicas remotas. Se envía sin tener el nodo
+/// bloqueado, así una réplica lenta no frena a las demás consultas mientras se esperan
+/// sus ACKs
+#[derive(Debug)]
+pub struct EscrituraPendiente {
+    pub remotas: Vec<String>,
+    pub consulta: String,
+    pub consistencia: LevelConsistency,
+    pub acks_locales: usize,
+    pub requeridas: usize,
+}
+
+impl EscrituraPendiente {
+    /// Envía la escritura a las réplicas remotas y espera los ACKs que faltan
+    pub fn enviar(&self) -> Result<(), String> {
+        enviar_mutacion(
+            &self.remotas,
+            &self.consulta,
+            &self.consistencia,
+            (self.acks_locales, self.requeridas),
+        )
+    }
+}
+
+/// Incremento de contadores que el coordinador, que no es réplica de la partition key,
+/// le reenvía a la primera réplica viva para que lo lidere
+#[derive(Debug)]
+pub struct ReenvioLider {
+    pub lider: String,
+    pub consulta: String,
+    pub consistencia: LevelConsistency,
+    pub requeridas: usize,
+}
+
+impl ReenvioLider {
+    pub fn enviar(&self) -> Result<(), String> {
+        println!(
+            "Se reenvía el incremento a la réplica {} para que lo lidere",
+            self.lider
+        );
+        reenviar_al_lider(
+            &self.lider,
+            &self.consulta,
+            self.consistencia.clone(),
+            self.requeridas,
+        )
+    }
+}
+
+/// Sentencia DDL o TRUNCATE que el coordinador ya aplicó y que falta enviar a los demás
+/// nodos. No se esperan todas las confirmaciones: los nodos que no la reciban obtienen
+/// las sentencias DDL al sincronizar su schema
+#[derive(Debug)]
+pub struct DifusionPendiente {
+    pub nodos: Vec<String>,
+    pub mensaje: Vec<u8>,
+    pub tipo: String,
+}
+
+impl DifusionPendiente {
+    pub fn enviar(&self) {
+        let confirmaron: Vec<String> =
+            enviar_a_replicas(&self.nodos, &self.mensaje, timeout_escritura())
+                .into_iter()
+                .filter(|(_, respuesta)| respuesta.contains(&"ACK".to_string()))
+                .map(|(ip, _)| ip)
+                .collect();
+        for ip in self.nodos.iter().filter(|ip| !confirmaron.contains(ip)) {
+            println!("El nodo {} no confirmó {}", ip, self.tipo);
+        }
+    }
+}
+
+/// Transacción liviana que el coordinador ya preparó con el nodo bloqueado: la sentencia
+/// sin su condición IF, las réplicas vivas de la partition key y las columnas de la
+/// tabla, con las que se evalúa la condición sobre la fila actual
+#[derive(Debug)]
+pub struct TransaccionPendiente {
+    pub rondas: RondasPaxos,
+    pub sentencia: String,
+    pub condicion: CondicionLwt,
+    pub headers: Vec<String>,
+}
+
+impl TransaccionPendiente {
+    /// Coordina la transacción con rondas de Paxos: la condición se evalúa sobre la fila
+    /// que devuelven las promesas y la escritura solo se confirma si se cumple. Devuelve
+    /// la columna [applied] y, si no se aplicó, la fila actual
+    pub fn coordinar(
+        &self,
+        mut atender_paxos: impl FnMut(u8, u64, &str) -> Result<Vec<String>, String>,
+    ) -> Result<String, String> {
+        let columnas = self.headers.len();
+        self.rondas.con_reintentos(|ballot| {
+            let promesas =
+                match self
+                    .rondas
+                    .preparar(ballot, &self.sentencia, &mut atender_paxos)?
+                {
+                    Ok(promesas) => promesas,
+                    Err(prometido) => return Ok(Err(prometido)),
+                };
+            // La fila actual es la versión de la fila escrita más recientemente entre
+            // las de las réplicas
+            let fila = promesas
+                .into_iter()
+                .max_by_key(|promesa| promesa.timestamp)
+                .and_then(|promesa| promesa.fila);
+            if !self.condicion.se_cumple(&self.headers, fila.as_deref()) {
+                return Ok(Ok(resultado_lwt(false, fila.as_deref(), columnas)));
+            }
+            Ok(self
+                .rondas
+                .proponer_y_confirmar(ballot, &self.sentencia, &mut atender_paxos)?
+                .map(|_| resultado_lwt(true, None, columnas)))
+        })
+    }
+}
+
+/// Réplicas vivas de una partición con las que el coordinador, de ip `ip`, ejecuta
+/// rondas de Paxos: las fases se aceptan en `quorum` réplicas y la escritura se
+/// confirma en `requeridas`
+#[derive(Debug, Clone)]
+pub struct RondasPaxos {
+    pub ip: String,
+    pub vivas: Vec<String>,
+    pub quorum: usize,
+    pub requeridas: usize,
+}
+
+impl RondasPaxos {
+    /// Ejecuta la ronda con ballots crecientes hasta que prospere, esperando un tiempo
+    /// aleatorio entre intentos para que los coordinadores que compiten no vuelvan a
+    /// chocar. La ronda devuelve Ok(Err(ballot)) si hay que reintentar con uno mayor
+    pub fn con_reintentos<T>(
+        &self,
+        mut ronda: impl FnMut(u64) -> Result<Result<T, u64>, String>,
+    ) -> Result<T, String> {
+        let mut minimo = 0;
+        for intento in 0..MAX_INTENTOS_PAXOS {
+            let ballot = nuevo_ballot(&self.ip, minimo);
+            match ronda(ballot)? {
+                Ok(resultado) => return Ok(resultado),
+                Err(prometido) => minimo = minimo.max(prometido).max(ballot),
+            }
+            let espera = rand::thread_rng().gen_range(0..10u64 << intento);
+            thread::sleep(Duration::from_millis(espera));
+        }
+        Err(format!(
+            "{}: no se pudo completar la ronda de Paxos en {} intentos.",
+            TIEMPO_AGOTADO_ESCRITURA, MAX_INTENTOS_PAXOS
+        ))
+    }
+
+    /// Fase de prepare/promise. Si alguna réplica tiene una propuesta aceptada más nueva
+    /// que la última confirmada, se la termina con este ballot y se pide reintentar
+    pub fn preparar(
+        &self,
+        ballot: u64,
+        sentencia: &str,
+        atender_paxos: &mut impl FnMut(u8, u64, &str) -> Result<Vec<String>, String>,
+    ) -> Result<Result<Vec<Promesa>, u64>, String> {
+        let respuestas = self.fase(PREPARAR, ballot, sentencia, atender_paxos);
+        let promesas: Vec<Promesa> = respuestas
+            .iter()
+            .filter_map(|campos| Promesa::desde_campos(campos))
+            .collect();
+        if promesas.len() < self.quorum {
+            return Ok(Err(mayor_rechazo(&respuestas)));
+        }
+        let confirmado = promesas
+            .iter()
+            .map(|promesa| promesa.confirmado)
+            .max()
+            .unwrap_or_default();
+        let en_curso = promesas
+            .iter()
+            .filter_map(|promesa| promesa.aceptado.clone())
+            .filter(|(aceptado, _)| *aceptado > confirmado)
+            .max_by_key(|(aceptado, _)| *aceptado);
+        if let Some((_, en_curso)) = en_curso {
+            println!("Se completa la transacción en curso: {}", en_curso);
+            if let Err(prometido) = self.proponer_y_confirmar(ballot, &en_curso, atender_paxos)? {
+                return Ok(Err(prometido));
+            }
+            return Ok(Err(ballot));
+        }
+        Ok(Ok(promesas))
+    }
+
+    /// Fases de propose/accept y commit. Si la propuesta no se acepta en QUORUM devuelve
+    /// el mayor ballot que la rechazó, y si no se confirma en las `requeridas` un error
+    /// WriteTimeout
+    pub fn proponer_y_confirmar(
+        &self,
+        ballot: u64,
+        sentencia: &str,
+        atender_paxos: &mut impl FnMut(u8, u64, &str) -> Result<Vec<String>, String>,
+    ) -> Result<Result<(), u64>, String> {
+        let respuestas = self.fase(PROPONER, ballot, sentencia, atender_paxos);
+        let aceptadas = respuestas
+            .iter()
+            .filter(|campos| campos.first().is_some_and(|campo| campo == ACEPTADA))
+            .count();
+        if aceptadas < self.quorum {
+            return Ok(Err(mayor_rechazo(&respuestas)));
+        }
+        let acks = self
+            .fase(CONFIRMAR, ballot, sentencia, atender_paxos)
+            .iter()
+            .filter(|campos| campos.first().is_some_and(|campo| campo == "ACK"))
+            .count();
+        if acks < self.requeridas {
+            return Err(tiempo_agotado_escritura(acks, self.requeridas));
+        }
+        Ok(Ok(()))
+    }
+
+    // Envía la fase a las réplicas vivas en paralelo y la atiende en este nodo si es
+    // una de ellas, sin pasar por la red
+    fn fase(
+        &self,
+        fase: u8,
+        ballot: u64,
+        sentencia: &str,
+        atender_paxos: &mut impl FnMut(u8, u64, &str) -> Result<Vec<String>, String>,
+    ) -> Vec<Vec<String>> {
+        let remotas: Vec<String> = self
+            .vivas
+            .iter()
+            .filter(|ip| **ip != self.ip)
+            .cloned()
+            .collect();
+        let mut respuestas: Vec<Vec<String>> = enviar_a_replicas(
+            &remotas,
+            &serializar_paxos(fase, ballot, sentencia),
+            timeout_escritura(),
+        )
+        .into_iter()
+        .map(|(_, respuesta)| campos_respuesta_paxos(&respuesta))
+        .collect();
+        if self.vivas.contains(&self.ip) {
+            match atender_paxos(fase, ballot, sentencia) {
+                Ok(campos) => respuestas.push(campos),
+                Err(e) => println!("No se pudo atender la fase de Paxos: {}", e),
+            }
+        }
+        respuestas
+    }
+}
+
+/// Envía la mutación a todas las réplicas en paralelo y espera hasta tener `requeridas`
+/// confirmaciones, contando las `acks_locales` de las escrituras que ya aplicó el
+/// coordinador. Si se cumple el timeout antes devuelve un error WriteTimeout, y si
+/// respondieron todas sin alcanzarlas un error WriteFailure. Las réplicas que no se
+/// esperan igual reciben la mutación
+pub fn enviar_mutacion(
+    replicas: &[String],
+    consulta: &str,
+    consistencia: &LevelConsistency,
+    (acks_locales, requeridas): (usize, usize),
+) -> Result<(), String> {
+    let timeout = timeout_escritura();
+    let (tx, rx) = mpsc::channel();
+    for ip_replica in replicas {
+        println!("Enviando a réplica: {}", ip_replica);
+        let tx = tx.clone();
+        let ip_replica = ip_replica.to_string();
+        let consulta = consulta.to_string();
+        let consistencia = consistencia.clone();
+        thread::spawn(move || {
+            let resultado = enviar_a_replica(
+                &ip_replica,
+                &serializar_envio_nodos(&consulta, consistencia, REPLICA),
+                timeout,
+            )
+            .and_then(|respuesta| match respuesta.contains(&"ACK".to_string()) {
+                true => Ok(()),
+                false => Err("No se recibió ACK".to_string()),
+            });
+            let _ = tx.send((ip_replica, resultado));
+        });
+    }
+    drop(tx); // Cerramos el sender para indicar que no habrá más envíos
+
+    let limite = Instant::now() + timeout;
+    let mut acks = acks_locales;
+    while acks < requeridas {
+        match rx.recv_timeout(limite.saturating_duration_since(Instant::now())) {
+            Ok((_, Ok(()))) => acks += 1,
+            Ok((ip_replica, Err(e))) => {
+                println!("La réplica {} no confirmó la escritura: {}", ip_replica, e)
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(tiempo_agotado_escritura(acks, requeridas))
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(escritura_fallida(acks, requeridas)),
+        }
+    }
+    println!(
+        "Se han obtenido {} ACKs: Se cumple el Consistency Level {}",
+        acks, consistencia
+    );
+    println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - -");
+    Ok(())
+}
+
+/// Reenvía un incremento de contadores a la réplica que lo va a liderar y espera su
+/// confirmación, que llega después de que esta reúna los ACKs de las demás réplicas.
+/// Si el líder no pudo cumplir el nivel de consistencia devuelve su error
+pub fn reenviar_al_lider(
+    ip_lider: &str,
+    consulta: &str,
+    consistencia: LevelConsistency,
+    requeridas: usize,
+) -> Result<(), String> {
+    // El líder a su vez espera a las demás réplicas, así que se le da el doble de tiempo
+    let respuesta = enviar_a_replica(
+        ip_lider,
+        &serializar_envio_nodos(consulta, consistencia, LIDER_CONTADOR),
+        timeout_escritura() * 2,
+    )
+    .map_err(|_| tiempo_agotado_escritura(0, requeridas))?;
+    match respuesta.first() {
+        Some(ack) if ack == "ACK" => Ok(()),
+        _ => Err(respuesta.join(",")),
+    }
+}
+
+/// Envía el mismo mensaje ya serializado a todas las réplicas en paralelo y devuelve
+/// las respuestas que llegaron antes del timeout, junto con la ip de cada réplica
+pub fn enviar_a_replicas(
+    replicas: &[String],
+    mensaje: &[u8],
+    timeout: Duration,
+) -> Vec<(String, Vec<String>)> {
+    let (tx, rx) = mpsc::channel();
+    for ip_replica in replicas {
+        let tx = tx.clone();
+        let ip_replica = ip_replica.to_string();
+        let mensaje = mensaje.to_vec();
+        thread::spawn(move || {
+            let resultado = enviar_a_replica(&ip_replica, &mensaje, timeout);
+            let _ = tx.send((ip_replica, resultado));
+        });
+    }
+    drop(tx);
+
+    let limite = Instant::now() + timeout;
+    let mut respuestas = vec![];
+    while let Ok((ip_replica, resultado)) =
+        rx.recv_timeout(limite.saturating_duration_since(Instant::now()))
+    {
+        match resultado {
+            Ok(respuesta) => respuestas.push((ip_replica, respuesta)),
+            Err(e) => println!("La réplica {} no respondió: {}", ip_replica, e),
+        }
+    }
+    respuestas
+}
+
+/// Pide a un nodo sus filas de una página de un recorrido de todas las particiones
+pub fn leer_pagina_de(ip: &str, pedido: &[u8]) -> Result<Vec<String>, String> {
+    enviar_a_replica(ip, pedido, timeout_lectura())
+}
+
+/// Se suscribe al log CDC de una tabla en otro nodo y envía por `tx` cada mutación que
+/// llega, hasta que el nodo cierre la conexión o se descarte el Receiver. Si el nodo
+/// está caído no se reintenta: sus escrituras también las registran sus réplicas
+pub fn suscribir_cdc_remoto(ip: &str, pedido: Vec<u8>, tx: Sender<Mutacion>) {
+    let ip = ip.to_string();
+    thread::spawn(move || {
+        let nombre_servicio = obtener_nombre_servicio(ip.clone());
+        let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
+        let Ok(socket) = conectar(&direccion, timeout_lectura()) else {
+            println!("No se pudo suscribir al CDC del nodo {}", ip);
+            return;
+        };
+        // Las mutaciones llegan cuando ocurren, así que se esperan sin límite
+        let _ = socket.set_read_timeout(None);
+        let client_conn = match (config_cliente_internodos(), nombre_servidor(&direccion)) {
+            (Ok(config), Ok(nombre)) => ClientConnection::new(config, nombre),
+            _ => return,
+        };
+        let Ok(client_conn) = client_conn else {
+            return;
+        };
+        let mut tls_stream = StreamOwned::new(client_conn, socket);
+        if tls_stream.write_all(&pedido).is_err() {
+            return;
+        }
+        while let Ok(mutacion) = leer_mutacion_cdc(&mut tls_stream) {
+            if tx.send(mutacion).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// Envía la consulta ya serializada a una réplica y devuelve su respuesta
+fn enviar_a_replica(
+    ip_replica: &str,
+    consulta_serializada: &[u8],
+    timeout: Duration,
+) -> Result<Vec<String>, String> {
+    let nombre_servicio = obtener_nombre_servicio(ip_replica.to_string());
+    let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
+    let socket = conectar(&direccion, timeout)?;
+    let client_conn =
+        ClientConnection::new(config_cliente_internodos()?, nombre_servidor(&direccion)?)
+            .map_err(|_| "Error al crear la conexión TLS del cliente.".to_string())?;
+    let mut tls_stream = StreamOwned::new(client_conn, socket);
+    tls_stream
+        .write_all(consulta_serializada)
+        .map_err(|_| "Error al enviar la consulta".to_string())?;
+    deserializar_respuesta_nodos(&mut tls_stream)
+}
+
+/// Se conecta a la dirección "host:puerto" esperando a lo sumo `timeout`, que también
+/// se usa como límite para cada lectura y escritura en el socket
+pub fn conectar(direccion: &str, timeout: Duration) -> Result<TcpStream, String> {
+    let direccion_socket = direccion
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut direcciones| direcciones.next())
+        .ok_or(format!("No se pudo resolver la dirección {}", direccion))?;
+    let socket = TcpStream::connect_timeout(&direccion_socket, timeout)
+        .map_err(|_| format!("No se pudo conectar con {}", direccion))?;
+    socket
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| socket.set_write_timeout(Some(timeout)))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
@@ -9,9 +9,10 @@ pub struct HeartbeatState {
 
 /// Definición de la estructura ApplicationState que se encarga
 /// de almacenar el estado de la aplicación en un nodo, por ejemplo
-/// cuando este está caído o normal
+/// cuando este está caído o normal, y la versión de su schema
 pub struct ApplicationState {
     pub status: NodeStatus,
+    pub schema: u32,
 }
 
 /// Definición de la estructura EndpointData que se encarga
@@ -23,14 +24,46 @@ pub struct EndpointData {
 }
 
 impl EndpointData {
-    /// Constructor de la estructura EndpointData 
+    /// Constructor de la estructura EndpointData
     pub fn new(generacion: f64, version: u32, estado: NodeStatus) -> Self {
         EndpointData {
             heartbeat_state: HeartbeatState {
                 generation: generacion,
                 version,
             },
-            application_state: ApplicationState { status: estado },
+            application_state: ApplicationState {
+                status: estado,
+                schema: 0,
+            },
         }
     }
+
+    /// Indica la versión del schema del endpoint
+    pub fn con_schema(mut self, schema: u32) -> Self {
+        self.application_state.schema = schema;
+        self
+    }
+
+    /// Estado del endpoint de IP `ip` tal como se envía en los mensajes de Gossip:
+    /// ip:generación:versión:estado:schema
+    pub fn estado_gossip(&self, ip: &str) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            ip,
+            self.heartbeat_state.generation,
+            self.heartbeat_state.version,
+            self.application_state.status,
+            self.application_state.schema
+        )
+    }
+}
+
+/// Versión del schema de un estado recibido por Gossip, que está en su quinto campo
+pub fn schema_gossip(partes: &[&str]) -> Result<u32, String> {
+    match partes.get(4) {
+        Some(schema) => schema
+            .parse()
+            .map_err(|_| "Error al parsear la versión del schema".to_string()),
+        None => Ok(0),
+    }
 }
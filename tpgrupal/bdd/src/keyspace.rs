@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use protocolo::serial_deserial::tipo_columna::TipoUsuario;
 
 use crate::tabla::Tabla;
 
@@ -10,6 +12,7 @@ pub struct Keyspace {
     pub tablas: HashMap<String, Tabla>, // K: nombre de tabla, V: Tabla
     pub nivel_replicacion: usize,       // Número de réplicas
     pub strategy: String,
+    pub tipos: BTreeMap<String, TipoUsuario>, // K: nombre del tipo, V: su definición
 }
 
 impl Keyspace {
@@ -23,6 +26,7 @@ impl Keyspace {
             tablas,
             nivel_replicacion,
             strategy,
+            tipos: BTreeMap::new(),
         }
     }
 
@@ -30,4 +34,17 @@ impl Keyspace {
     pub fn add_tablas(&mut self, tabla: HashMap<String, Tabla>) {
         self.tablas = tabla;
     }
+
+    /// Tipos definidos por el usuario en el keyspace, para resolver los tipos de las columnas
+    pub fn udts(&self) -> Vec<TipoUsuario> {
+        self.tipos.values().cloned().collect()
+    }
+
+    /// Sentencia CREATE KEYSPACE que define el keyspace, como la muestra un DESCRIBE
+    pub fn sentencia_create(&self) -> String {
+        format!(
+            "CREATE KEYSPACE {} WITH replication = {{'class': '{}', 'replication_factor': {}}};",
+            self.nombre, self.strategy, self.nivel_replicacion
+        )
+    }
 }
@@ -1,6 +1,16 @@
+pub mod coleccion;
+pub mod contador;
+pub mod coordinador;
 pub mod endpoint_data;
 pub mod keyspace;
+pub mod log_cdc;
 pub mod node_status;
 pub mod nodo;
+pub mod paxos;
 pub mod procesamiento_data;
+pub mod roles;
+pub mod schema;
+pub mod suscriptores_eventos;
 pub mod tabla;
+pub mod tablas_sistema;
+pub mod vista;
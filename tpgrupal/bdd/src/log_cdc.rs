@@ -0,0 +1,191 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use protocolo::serial_deserial::cassandra::eventos::{Mutacion, TipoMutacion};
+use protocolo::serial_deserial::tipo_columna::quitar_comillas;
+
+const CAPACIDAD_LOG: usize = 10000;
+
+/// Log CDC (change data capture) de una tabla: guarda en memoria las
+/// últimas mutaciones locales de sus filas, con el momento de su escritura
+/// como offset, y se las envía a los suscriptores a medida que ocurren.
+/// Cuando se llena se descartan las mutaciones más viejas
+#[derive(Default)]
+pub struct LogCDC {
+    mutaciones: VecDeque<Mutacion>,
+    suscriptores: Vec<Sender<Mutacion>>,
+}
+
+impl LogCDC {
+    /// Registra la mutación de una fila escrita en el momento indicado, con los
+    /// valores separados por ',' y en el mismo orden que las columnas, y se la
+    /// envía a los suscriptores
+    pub fn registrar(
+        &mut self,
+        tabla: &str,
+        (tipo, momento): (TipoMutacion, i64),
+        columnas: &[String],
+        fila: &str,
+    ) -> Mutacion {
+        let mutacion = Mutacion {
+            offset: momento.max(0) as u64,
+            tabla: tabla.to_string(),
+            tipo,
+            columnas: columnas.to_vec(),
+            valores: fila
+                .split(",")
+                .map(|valor| quitar_comillas(valor).to_string())
+                .collect(),
+        };
+        if self.mutaciones.len() == CAPACIDAD_LOG {
+            self.mutaciones.pop_front();
+        }
+        self.mutaciones.push_back(mutacion.clone());
+        self.suscriptores
+            .retain(|tx| tx.send(mutacion.clone()).is_ok());
+        mutacion
+    }
+
+    /// Devuelve las mutaciones del log escritas a partir del offset indicado, en el
+    /// orden en que se registraron
+    pub fn leer_desde(&self, offset: u64) -> Vec<Mutacion> {
+        self.mutaciones
+            .iter()
+            .filter(|mutacion| mutacion.offset >= offset)
+            .cloned()
+            .collect()
+    }
+
+    /// Suscribe a las mutaciones nuevas de la tabla. Si se indica un offset
+    /// también se devuelven las mutaciones del log a partir de él, de forma
+    /// que no se pierda ninguna entre la lectura y la suscripción
+    pub fn suscribir(&mut self, desde: Option<u64>) -> (Vec<Mutacion>, Receiver<Mutacion>) {
+        let pendientes = desde.map_or(vec![], |offset| self.leer_desde(offset));
+        let (tx, rx) = mpsc::channel();
+        self.suscriptores.push(tx);
+        (pendientes, rx)
+    }
+}
+
+/// Filtra las mutaciones repetidas de una suscripción a los logs CDC de todos los
+/// nodos, donde cada réplica envía la misma escritura. Recuerda los identificadores
+/// de las últimas mutaciones, tantas como entran en un log
+#[derive(Default)]
+pub struct Deduplicador {
+    vistas: HashSet<String>,
+    orden: VecDeque<String>,
+}
+
+impl Deduplicador {
+    /// Indica si la mutación no se había visto, y la recuerda
+    pub fn es_nueva(&mut self, mutacion: &Mutacion) -> bool {
+        let id = mutacion.id();
+        if !self.vistas.insert(id.clone()) {
+            return false;
+        }
+        self.orden.push_back(id);
+        if self.orden.len() > CAPACIDAD_LOG {
+            if let Some(vieja) = self.orden.pop_front() {
+                self.vistas.remove(&vieja);
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vuelos() -> Vec<String> {
+        vec!["ID_VUELO".to_string(), "ESTADO".to_string()]
+    }
+
+    #[test]
+    fn test_registrar_usa_el_momento_como_offset_y_quita_comillas() {
+        //Arrange
+        let mut log = LogCDC::default();
+
+        //Act
+        let mutacion = log.registrar(
+            "Aerolineas.VUELOS",
+            (TipoMutacion::Insert, 1_000),
+            &vuelos(),
+            "1,'En curso'",
+        );
+
+        //Assert
+        assert_eq!(mutacion.offset, 1_000);
+        assert_eq!(mutacion.valores, ["1", "En curso"]);
+        assert_eq!(mutacion.get("ESTADO"), Some("En curso"));
+    }
+
+    #[test]
+    fn test_suscribir_desde_un_offset_devuelve_las_pendientes_y_las_nuevas() {
+        //Arrange
+        let mut log = LogCDC::default();
+        for momento in [10, 20, 30] {
+            log.registrar(
+                "Aerolineas.VUELOS",
+                (TipoMutacion::Update, momento),
+                &vuelos(),
+                "1,Demorado",
+            );
+        }
+
+        //Act
+        let (pendientes, nuevas) = log.suscribir(Some(20));
+        log.registrar(
+            "Aerolineas.VUELOS",
+            (TipoMutacion::Delete, 40),
+            &vuelos(),
+            "1,Demorado",
+        );
+
+        //Assert
+        let offsets: Vec<u64> = pendientes.iter().map(|mutacion| mutacion.offset).collect();
+        assert_eq!(offsets, [20, 30]);
+        assert_eq!(nuevas.try_recv().unwrap().offset, 40);
+        assert!(nuevas.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_log_lleno_descarta_las_mutaciones_mas_viejas() {
+        //Arrange
+        let mut log = LogCDC::default();
+
+        //Act
+        for momento in 0..(CAPACIDAD_LOG as i64 + 5) {
+            log.registrar(
+                "Aerolineas.VUELOS",
+                (TipoMutacion::Insert, momento),
+                &vuelos(),
+                "1,Demorado",
+            );
+        }
+
+        //Assert
+        let mutaciones = log.leer_desde(0);
+        assert_eq!(mutaciones.len(), CAPACIDAD_LOG);
+        assert_eq!(mutaciones[0].offset, 5);
+    }
+
+    #[test]
+    fn test_deduplicador_descarta_la_misma_escritura_de_otra_replica() {
+        //Arrange
+        let mut una = LogCDC::default();
+        let mut otra = LogCDC::default();
+        let mut deduplicador = Deduplicador::default();
+        let tabla = "Aerolineas.VUELOS";
+
+        //Act
+        let original = una.registrar(tabla, (TipoMutacion::Update, 7), &vuelos(), "1,Demorado");
+        let replica = otra.registrar(tabla, (TipoMutacion::Update, 7), &vuelos(), "1,Demorado");
+        let posterior = otra.registrar(tabla, (TipoMutacion::Update, 8), &vuelos(), "1,Demorado");
+
+        //Assert
+        assert!(deduplicador.es_nueva(&original));
+        assert!(!deduplicador.es_nueva(&replica));
+        assert!(deduplicador.es_nueva(&posterior));
+    }
+}
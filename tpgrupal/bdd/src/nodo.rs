@@ -1,25 +1,50 @@
+use protocolo::parser_cql::opciones_rol::OpcionesRol;
 use protocolo::parser_cql::parseo_consulta::{
-    obtener_headers_table, obtener_tipo_strategy_y_replication, procesar_consulta,
+    obtener_headers_table, obtener_incrementos, obtener_tipo_strategy_y_replication,
+    obtener_tipos_table, procesar_consulta,
 };
+use protocolo::parser_cql::permisos::{Permiso, Recurso, SentenciaPermisos};
+use protocolo::serial_deserial::cassandra::autenticacion::{Credenciales, NO_AUTORIZADO};
+use protocolo::serial_deserial::cassandra::eventos::{
+    CambioEstado, CambioSchema, CambioTopologia, Evento, Mutacion, TipoMutacion,
+};
+use protocolo::serial_deserial::cassandra::metadata_rows::{ColumnaSpec, MetadataRows};
+use protocolo::serial_deserial::cassandra::paginacion::Pagina;
 use protocolo::serial_deserial::gossip::deserializador_gossip::deserializar_gossip;
 use protocolo::serial_deserial::gossip::serializador_gossip::serializar_gossip;
 use protocolo::serial_deserial::gossip::type_message::TypeGossip;
+use protocolo::serial_deserial::intra_nodos::cdc::serializar_suscripcion_cdc;
 use protocolo::serial_deserial::intra_nodos::deserializador_nodo_respuesta::deserializar_respuesta_nodos;
+use protocolo::serial_deserial::intra_nodos::pagina::serializar_pagina;
+use protocolo::serial_deserial::intra_nodos::paxos::{ACEPTADA, CONFIRMAR, PREPARAR, PROPONER};
+use protocolo::serial_deserial::intra_nodos::schema::{
+    mutaciones_respuesta_schema, serializar_mutacion_schema, serializar_pedido_schema,
+    MutacionSchema,
+};
 use protocolo::serial_deserial::intra_nodos::serializador_nodo_envio::serializar_envio_nodos;
 use protocolo::{
-    parser_cql::{condicion_where::CondicionWhere, consulta::Consulta, type_cql::TypeCQL},
-    serial_deserial::level_consistency::LevelConsistency,
+    parser_cql::{
+        ast::{
+            Asignacion, CambioTabla, DefinicionColumna, Descripcion, Seleccion, Sentencia, Termino,
+        },
+        condicion_where::CondicionWhere,
+        consulta::Consulta,
+        type_cql::TypeCQL,
+    },
+    serial_deserial::level_consistency::{no_disponible, LevelConsistency},
+    serial_deserial::tipo_columna::{TipoColumna, TipoUsuario},
 };
 
 use rand::Rng;
-use rustls::{ClientConnection, ServerName, StreamOwned};
-use seguridad::create_client_config;
+use rustls::{ClientConnection, StreamOwned};
+use seguridad::{config_cliente_internodos, nombre_servidor};
 use std::collections::BTreeMap;
 use std::fs::{self, OpenOptions};
+use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::MutexGuard;
 
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::mpsc::{self, Receiver};
 
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -32,25 +57,45 @@ use std::{
     net::TcpStream,
 };
 
-use crate::endpoint_data::EndpointData;
+use crate::contador::reconciliar_contadores;
+use crate::coordinador::{
+    digest, enviar_a_replicas, enviar_mutacion, leer_pagina_de, leer_replicas, pedir_datos,
+    suscribir_cdc_remoto, timeout_lectura, DifusionPendiente, Ejecucion, EscrituraPendiente,
+    LatenciasLectura, ReenvioLider, RespuestaLectura, RondasPaxos, TransaccionPendiente,
+};
+use crate::endpoint_data::{schema_gossip, EndpointData};
 use crate::keyspace::Keyspace;
+use crate::log_cdc::{Deduplicador, LogCDC};
 use crate::node_status::NodeStatus;
+use crate::paxos::{clave_particion, rechazo, Paxos, Promesa};
 use crate::procesamiento_data::*;
-use crate::tabla::Tabla;
-const PUERTO_INTERNODOS: &str = "9043";
+use crate::roles::{
+    columnas_list_permissions, consulta_algun_rol, consulta_delete_permisos, consulta_delete_rol,
+    consulta_insert_permisos, consulta_insert_rol, consulta_select_permisos, consulta_select_rol,
+    consulta_update_permisos, consulta_update_rol, crear_tabla_permisos, crear_tabla_roles,
+    tiene_permiso, CachePermisos, PermisosRecurso, Rol, KEYSPACE_AUTH, ROL_POR_DEFECTO,
+    TABLA_PERMISOS, TABLA_ROLES,
+};
+use crate::schema::HistorialSchema;
+use crate::suscriptores_eventos::SuscriptoresEventos;
+use crate::tabla::{ahora, invalida, termino_celda, Tabla};
+use crate::tablas_sistema::{
+    columnas_describe, es_keyspace_sistema, filas_sistema, keyspaces_sistema,
+    KEYSPACE_SYSTEM_SCHEMA,
+};
+use crate::vista::{fila_vista, insert_vista, mutaciones_vista, nueva_vista};
+const PUERTO_CLIENTE: &str = "9042";
+pub const PUERTO_INTERNODOS: &str = "9043";
 const PUERTO_GOSSIP: &str = "9044";
 const RESPONSABLE: u8 = 0;
-const REPLICA: u8 = 1;
-
-type TxRx = (Sender<Result<(), String>>, Receiver<Result<(), String>>);
-
-type TxRx2 = (
-    Sender<Result<(Vec<String>, String, String), String>>,
-    Receiver<Result<(Vec<String>, String, String), String>>,
-);
+pub const REPLICA: u8 = 1;
+// Lecturas en las que la réplica responde solo el digest de las filas
+pub const DIGEST: u8 = 2;
+// Incrementos de contadores que el coordinador le reenvía a la réplica que los lidera
+pub const LIDER_CONTADOR: u8 = 6;
 
 /// Estructura que representa un nodo del cluster, contiene
-/// todas las estructuras necesarias para almacenar toda la 
+/// todas las estructuras necesarias para almacenar toda la
 /// información que se necesite para el correcto funcionamiento
 /// de la base de datos
 pub struct Nodo {
@@ -58,11 +103,18 @@ pub struct Nodo {
     pub ip: String,
     pub keyspaces: HashMap<String, Keyspace>,
     pub token: u32,
-    pub replicas: Vec<String>,        // Vector de ips de nodos
-    pub nodos: BTreeMap<u32, String>, //K: ip hasheada; V: ip String
+    pub replicas: HashMap<String, Vec<String>>, // K: keyspace; V: ips de las réplicas del nodo según su factor de replicación
+    pub nodos: BTreeMap<u32, String>,           //K: ip hasheada; V: ip String
     pub timestamp: u32,
     pub gossip_recientes: Vec<String>,
     pub keyspace_actual: String,
+    pub suscriptores: SuscriptoresEventos,
+    pub logs_cdc: HashMap<String, LogCDC>, // K: nombre de la tabla calificado con su keyspace
+    pub latencias_lectura: LatenciasLectura,
+    pub paxos: Paxos,
+    pub historial_schema: HistorialSchema,
+    pub vistas_pendientes: Vec<String>, // Vistas sin las filas previas de su tabla base
+    pub cache_permisos: CachePermisos,
 }
 
 impl Nodo {
@@ -71,7 +123,7 @@ impl Nodo {
     pub fn new(ip: &String) -> Result<Self, String> {
         let mut ips = BTreeMap::new();
         let metadata_nodos = new_metadata(ip); // ---> Se crea la metadata del nodo actual y se lo inserta
-        let replicas: Vec<String> = Vec::new();
+        let replicas: HashMap<String, Vec<String>> = HashMap::new();
         let keyspaces: HashMap<String, Keyspace> = HashMap::new();
 
         let hash_result = hashear(ip)?;
@@ -87,45 +139,156 @@ impl Nodo {
             timestamp: 0,
             gossip_recientes: Vec::new(),
             keyspace_actual: "Aerolineas".to_string(),
+            suscriptores: SuscriptoresEventos::default(),
+            logs_cdc: HashMap::new(),
+            latencias_lectura: LatenciasLectura::default(),
+            paxos: Paxos::desde_archivo(&format!("bdd/src/paxos_{}.txt", ip)),
+            historial_schema: HistorialSchema::default(),
+            vistas_pendientes: Vec::new(),
+            cache_permisos: CachePermisos::default(),
         };
 
         nodo.load_data()?;
+        nodo.cargar_system_auth()?;
+        for keyspace in keyspaces_sistema() {
+            nodo.keyspaces.insert(keyspace.nombre.to_string(), keyspace);
+        }
 
         Ok(nodo)
     }
 
-    /// Método que inserta una nueva línea de información en una tabla del nodo
-    pub fn insertar_a_tabla(&mut self, nombre_tabla: String, row: String) {
-        if let Some(keyspace) = self.keyspaces.get_mut(&self.keyspace_actual) {
-            if let Some(tabla) = keyspace.tablas.get_mut(&nombre_tabla) {
-                tabla.insertar(row);
-            }
-        }
+    /// Método que inserta una nueva línea de información, escrita en el momento indicado,
+    /// en una tabla del nodo y la devuelve como quedó en la tabla si no estaba ya
+    pub fn insertar_a_tabla(
+        &mut self,
+        nombre_tabla: String,
+        row: String,
+        momento: i64,
+    ) -> Option<String> {
+        let tabla = self.get_tabla(&nombre_tabla).ok()??;
+        let fila = tabla.insertar(row, momento)?;
+        let headers = tabla.headers.clone();
+        self.registrar_mutacion(
+            &nombre_tabla,
+            (TipoMutacion::Insert, momento),
+            &headers,
+            &fila,
+        );
+        Some(fila)
     }
 
     /// Método que elimina una o más líneas de información en una tabla del nodo que
-    /// cumplan con la condición recibida
-    pub fn eliminar_en_tabla(&mut self, nombre_tabla: String, condicion: &CondicionWhere) {
-        if let Some(keyspace) = self.keyspaces.get_mut(&self.keyspace_actual) {
-            if let Some(tabla) = keyspace.tablas.get_mut(&nombre_tabla) {
-                tabla.eliminar(condicion);
+    /// cumplan con la condición recibida, con un DELETE escrito en el momento indicado
+    pub fn eliminar_en_tabla(
+        &mut self,
+        nombre_tabla: String,
+        condicion: &CondicionWhere,
+        momento: i64,
+    ) {
+        if let Ok(Some(tabla)) = self.get_tabla(&nombre_tabla) {
+            let eliminadas = tabla.eliminar(condicion);
+            let headers = tabla.headers.clone();
+            for fila in eliminadas {
+                let tipo = (TipoMutacion::Delete, momento);
+                self.registrar_mutacion(&nombre_tabla, tipo, &headers, &fila);
             }
         }
     }
 
     /// Método que actualiza una o más líneas de información en una tabla del nodo que
-    /// cumplan con la condición recibida
+    /// cumplan con la condición recibida, con asignaciones escritas en el momento indicado
     pub fn update_en_tabla(
         &mut self,
         nombre_tabla: String,
         condicion: &CondicionWhere,
-        query: &str,
+        asignaciones: &[Asignacion],
+        momento: i64,
+    ) {
+        if let Ok(Some(tabla)) = self.get_tabla(&nombre_tabla) {
+            let mut actualizadas = tabla.actualizar(condicion, asignaciones, momento);
+            let headers = tabla.headers.clone();
+            let indices = tabla.indices_contadores();
+            if !indices.is_empty() {
+                // El log CDC tiene el valor de los contadores y no sus shards
+                actualizadas =
+                    reconciliar_contadores(actualizadas, &tabla.indices_clave(), &indices);
+            }
+            for fila in actualizadas {
+                let tipo = (TipoMutacion::Update, momento);
+                self.registrar_mutacion(&nombre_tabla, tipo, &headers, &fila);
+            }
+        }
+    }
+
+    /// Método que registra la mutación de una fila en el log CDC de su tabla
+    /// y la notifica a los clientes registrados a ROW_CHANGE. El log es local
+    /// al nodo: solo contiene las escrituras que se aplicaron en él
+    fn registrar_mutacion(
+        &mut self,
+        nombre_tabla: &str,
+        tipo: (TipoMutacion, i64),
+        headers: &[String],
+        fila: &str,
     ) {
-        if let Some(keyspace) = self.keyspaces.get_mut(&self.keyspace_actual) {
-            if let Some(tabla) = keyspace.tablas.get_mut(&nombre_tabla) {
-                tabla.actualizar(condicion, query.to_string());
+        let nombre_tabla = self.nombre_calificado(nombre_tabla);
+        let mutacion = self
+            .logs_cdc
+            .entry(nombre_tabla.to_string())
+            .or_default()
+            .registrar(&nombre_tabla, tipo, headers, fila);
+        self.notificar_evento(Evento::RowChange(mutacion));
+    }
+
+    /// Método que suscribe a las mutaciones de una tabla en todo el cluster: a las del
+    /// log CDC de este nodo y, por el puerto entre nodos, a las de los demás, sin
+    /// repetir las escrituras que registra más de una réplica. Si se indica un offset
+    /// primero llegan las mutaciones de los logs a partir de él
+    pub fn suscribir_cdc(
+        &mut self,
+        nombre_tabla: &str,
+        desde: Option<u64>,
+    ) -> Result<Receiver<Mutacion>, String> {
+        let nombre_tabla = self.nombre_calificado(nombre_tabla);
+        let (pendientes, locales) = self.suscribir_cdc_local(&nombre_tabla, desde)?;
+        let (tx, todas) = mpsc::channel();
+        let pedido = serializar_suscripcion_cdc(&nombre_tabla, desde);
+        for ip in self.nodos.values().filter(|ip| **ip != self.ip) {
+            suscribir_cdc_remoto(ip, pedido.clone(), tx.clone());
+        }
+        let (tx_sin_repetir, sin_repetir) = mpsc::channel();
+        thread::spawn(move || {
+            for mutacion in pendientes.into_iter().chain(locales) {
+                if tx.send(mutacion).is_err() {
+                    break;
+                }
+            }
+        });
+        thread::spawn(move || {
+            let mut deduplicador = Deduplicador::default();
+            for mutacion in todas.iter().filter(|m| deduplicador.es_nueva(m)) {
+                if tx_sin_repetir.send(mutacion).is_err() {
+                    break;
+                }
             }
+        });
+        Ok(sin_repetir)
+    }
+
+    /// Método que suscribe a las mutaciones del log CDC de una tabla de este nodo,
+    /// devolviendo también las que siguen en el log a partir del offset indicado
+    pub fn suscribir_cdc_local(
+        &mut self,
+        nombre_tabla: &str,
+        desde: Option<u64>,
+    ) -> Result<(Vec<Mutacion>, Receiver<Mutacion>), String> {
+        if self.get_tabla(nombre_tabla)?.is_none() {
+            return Err(format!("La tabla {} no existe.", nombre_tabla));
         }
+        Ok(self
+            .logs_cdc
+            .entry(self.nombre_calificado(nombre_tabla))
+            .or_default()
+            .suscribir(desde))
     }
 
     fn get_nodo_responsable(&self, key_hash: u32) -> Option<String> {
@@ -141,295 +304,604 @@ impl Nodo {
     }
 
     /// Método que ejecuta la consulta recibida por el nodo (escrita en CQL)
-    /// recibiendo también la consistencia de la misma
+    /// recibiendo también la consistencia de la misma. Antes de ejecutarla
+    /// verifica que el rol de la sesión tenga permiso para hacerlo
     pub fn execute_query(
         &mut self,
         consulta: &mut Consulta,
         consistencia: LevelConsistency,
     ) -> Result<Option<Vec<String>>, String> {
-        let query = consulta.get_query();
-        let condicion = consulta.get_where();
+        self.preparar_consulta(consulta, consistencia)?
+            .terminar(|fase, ballot, sentencia| self.atender_paxos(fase, ballot, sentencia))
+    }
+
+    /// Método que ejecuta la consulta como `execute_query`, salvo lo que hay que enviarles
+    /// a las demás réplicas o nodos (las escrituras, los incrementos de contadores, las
+    /// rondas de Paxos y las sentencias DDL), que se devuelve pendiente para hacerlo
+    /// después de liberar el nodo
+    pub fn preparar_consulta(
+        &mut self,
+        consulta: &mut Consulta,
+        consistencia: LevelConsistency,
+    ) -> Result<Ejecucion, String> {
+        self.autorizar(consulta)?;
+        self.validar_consulta(consulta)?;
         let tipo_consulta = consulta.get_type();
         let tabla_consulta = consulta.get_tabla();
-        let consulta_explicita = consulta.get_consulta_explicita();
         let mut nivel_replicacion = 0;
-        let mut quorum = 0;
-        if let Some(keyspace) = self.keyspaces.get(&self.keyspace_actual) {
+        let (keyspace_consulta, _) = self.separar_keyspace(tabla_consulta);
+        if let Some(keyspace) = self.keyspaces.get(&keyspace_consulta) {
             nivel_replicacion = keyspace.nivel_replicacion;
-            quorum = (nivel_replicacion + 1) / 2;
         }
-        match tipo_consulta {
-            TypeCQL::Insert => {
-                let key_origen = obtener_hash_origen(query);
-
-                let ip_nodo_responsable =
-                    self.get_nodo_responsable(key_origen).ok_or_else(|| {
-                        "No se ha encontrado el nodo responsable para la key.".to_string()
-                    })?;
-                if ip_nodo_responsable == self.ip {
-                    // INSERTO EN ESTE NODO Y EN LAS REPLICAS
-                    self.insertar_a_tabla(tabla_consulta.to_string(), obtener_row(query));
-                    self.persistir_insert(tabla_consulta.to_string(), obtener_row(query));
-                    self.timestamp += 1;
-                    println!("Se ha recibido la consulta: {}", consulta_explicita);
-                    println!(
-                        "Se está insertando en el nodo responsable, en la tabla: {}",
-                        tabla_consulta
-                    );
-
-                    // Estando en el nodo coordinador voy a esperar tantos ACKs dependiendo del nivel de consistencia
-                    // Si es WEAK, espero 1 ACK
-                    // Si es STRONG, espero 2 ACKs
-                    // Igualmente se inserta en todos los nodos dependiendo del replication factor
+        if es_keyspace_sistema(&keyspace_consulta) && consulta.get_sentencia().tabla().is_some() {
+            return self
+                .consultar_tabla_sistema(consulta)
+                .map(Ejecucion::Terminada);
+        }
+        // Cantidad de réplicas que tienen que responder según el nivel de consistencia
+        let tabla_contadores = matches!(tipo_consulta, TypeCQL::Insert | TypeCQL::Update)
+            && self.tiene_contadores(tabla_consulta)?;
+        let requeridas = match tipo_consulta {
+            // Las transacciones livianas calculan sus réplicas en la ronda de Paxos
+            _ if consulta.get_condicion_lwt().is_some() => 0,
+            TypeCQL::Select => consistencia.requeridas_lectura(nivel_replicacion)?,
+            TypeCQL::Insert | TypeCQL::Update | TypeCQL::Delete => {
+                consistencia.requeridas_escritura(nivel_replicacion)?
+            }
+            _ => 0,
+        };
+        let resultado = match tipo_consulta {
+            TypeCQL::Insert | TypeCQL::Update | TypeCQL::Delete
+                if consulta.get_condicion_lwt().is_some() =>
+            {
+                return self
+                    .preparar_lwt(consulta, consistencia, nivel_replicacion)
+                    .map(Ejecucion::Transaccion);
+            }
+            TypeCQL::Insert if tabla_contadores => Err(format!(
+                "No se puede usar INSERT en la tabla de contadores {}, sus filas se crean con UPDATE.",
+                tabla_consulta
+            )),
+            TypeCQL::Update if tabla_contadores => {
+                return self.coordinar_contador(consulta, consistencia, nivel_replicacion, requeridas);
+            }
+            TypeCQL::Insert | TypeCQL::Update | TypeCQL::Delete => {
+                return self
+                    .preparar_escritura(consulta, consistencia, nivel_replicacion, requeridas)
+                    .map(|escritura| Ejecucion::Pendiente(vec![escritura]));
+            }
+            TypeCQL::Select => match self.lectura_contadores(consulta)? {
+                None => self.ejecutar_select(consulta, consistencia, nivel_replicacion, requeridas),
+                Some(lectura) => {
+                    // Se quitan las columnas de la clave primaria que se leyeron de más
+                    let columnas = consulta.get_seleccion().selectores.len();
+                    let filas =
+                        self.ejecutar_select(&lectura, consistencia, nivel_replicacion, requeridas)?;
+                    Ok(filas.map(|filas| {
+                        filas
+                            .iter()
+                            .map(|fila| fila.split(",").take(columnas).collect::<Vec<&str>>().join(","))
+                            .collect()
+                    }))
+                }
+            },
+            TypeCQL::CreateTable
+            | TypeCQL::CreateKeyspace
+            | TypeCQL::AlterTable
+            | TypeCQL::AlterKeyspace
+            | TypeCQL::DropTable
+            | TypeCQL::DropKeyspace
+            | TypeCQL::CreateType
+            | TypeCQL::DropType
+            | TypeCQL::CreateView
+            | TypeCQL::DropView
+            | TypeCQL::Truncate => return self.ejecutar_ddl(consulta, consistencia),
+            TypeCQL::Describe => match consulta.get_sentencia() {
+                Sentencia::Describe { descripcion } => self.describir(descripcion),
+                _ => Ok(None),
+            },
+            // El keyspace de la sesión lo guarda la conexión del cliente
+            TypeCQL::Use => Err("USE solo se puede ejecutar en la sesión de un cliente.".to_string()),
+            TypeCQL::CreateRole | TypeCQL::AlterRole | TypeCQL::DropRole => {
+                self.ejecutar_consulta_rol(consulta, consistencia)
+            }
+            TypeCQL::Grant | TypeCQL::Revoke | TypeCQL::ListPermissions => {
+                self.ejecutar_consulta_permisos(consulta, consistencia)
+            }
+        };
+        resultado.map(Ejecucion::Terminada)
+    }
 
-                    self.enviar_escrituras_replicas(consulta.clone(), consistencia, quorum)?;
-                } else {
-                    // Se le envia la consulta al nodo responsable.
-                    let nombre_servicio = obtener_nombre_servicio(ip_nodo_responsable.clone());
+    /// Método que resuelve una consulta sobre las tablas de system y system_schema. Son
+    /// virtuales: se arman con el estado de este nodo cada vez que se consultan, sin
+    /// pedirle nada a los demás nodos, y no se pueden modificar
+    fn consultar_tabla_sistema(
+        &mut self,
+        consulta: &Consulta,
+    ) -> Result<Option<Vec<String>>, String> {
+        if consulta.get_type() != &TypeCQL::Select {
+            return Err(invalida(&format!(
+                "las tablas de {} son de solo lectura",
+                self.separar_keyspace(consulta.get_tabla()).0
+            )));
+        }
+        for keyspace in keyspaces_sistema() {
+            self.keyspaces.insert(keyspace.nombre.to_string(), keyspace);
+        }
+        for (nombre, filas) in filas_sistema(self) {
+            if let Some(tabla) = self.get_tabla(&nombre)? {
+                for fila in filas {
+                    tabla.cargar(fila);
+                }
+            }
+        }
+        let filas = self
+            .get_tabla(consulta.get_tabla())?
+            .ok_or_else(|| invalida(&format!("la tabla {} no existe", consulta.get_tabla())))?
+            .select(consulta.get_where(), &consulta.get_seleccion());
+        Ok(Some(self.terminar_lectura(consulta, filas)?))
+    }
 
-                    let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                    println!("Se ha recibido una consulta: {}", consulta_explicita);
-                    println!(
-                        "El nodo no es responsable. Se la envía al responsable de IP: {}",
-                        ip_nodo_responsable
-                    );
-                    if send_and_deserial(
-                        direccion,
-                        consulta_explicita,
-                        LevelConsistency::Strong,
-                        RESPONSABLE,
-                        tipo_consulta,
+    /// Método que resuelve un DESCRIBE con la definición de los keyspaces, sus tipos, sus
+    /// tablas y sus vistas materializadas en este nodo. Cada fila tiene el keyspace, el
+    /// tipo y el nombre del objeto y, salvo al listar los keyspaces o las tablas, la
+    /// sentencia CREATE que lo define
+    fn describir(&mut self, descripcion: &Descripcion) -> Result<Option<Vec<String>>, String> {
+        let mut keyspaces: Vec<&Keyspace> = self.keyspaces.values().collect();
+        keyspaces.sort_by(|a, b| a.nombre.cmp(&b.nombre));
+        let filas = match descripcion {
+            Descripcion::Keyspaces => keyspaces
+                .iter()
+                .map(|keyspace| format!("{},keyspace,{}", keyspace.nombre, keyspace.nombre))
+                .collect(),
+            Descripcion::Tablas => keyspaces
+                .iter()
+                .flat_map(|keyspace| {
+                    tablas_ordenadas(keyspace).into_iter().map(|tabla| {
+                        format!("{},{},{}", keyspace.nombre, tipo_tabla(tabla), tabla.nombre)
+                    })
+                })
+                .collect(),
+            Descripcion::Keyspace(nombre) => {
+                let keyspace = self
+                    .keyspaces
+                    .get(nombre)
+                    .ok_or_else(|| invalida(&format!("el keyspace {} no existe", nombre)))?;
+                let mut filas = vec![format!(
+                    "{},keyspace,{},{}",
+                    nombre,
+                    nombre,
+                    keyspace.sentencia_create()
+                )];
+                filas.extend(keyspace.tipos.values().map(|tipo| {
+                    let create = tipo.sentencia_create();
+                    format!("{},type,{},{}", nombre, tipo.nombre, create)
+                }));
+                filas.extend(tablas_ordenadas(keyspace).into_iter().map(|tabla| {
+                    let create = tabla.sentencia_create(nombre);
+                    format!(
+                        "{},{},{},{}",
+                        nombre,
+                        tipo_tabla(tabla),
+                        tabla.nombre,
+                        create
                     )
-                    .is_err()
-                    {
-                        // Al igual que en el caso de una operación SELECT, si no pude conectarme al nodo responsable pero yo tampoco lo soy
-                        // entonces yo (el nodo coordinador) paso a ser un nuevo "nodo responsable" enviándole la consulta a las réplicas del
-                        // nodo responsable real y recibiendo las respuestas, luego actuar pero sin involucrar datos propios
-                        println!(
-                            "El nodo responsable está caído: Se le pasa la consulta a sus réplicas"
-                        );
-                        let replicas = get_replicas(
-                            &self.nodos,
-                            ip_nodo_responsable.to_string(),
-                            nivel_replicacion,
-                        );
+                }));
+                filas
+            }
+            Descripcion::Tabla(nombre) => {
+                let (keyspace, nombre_tabla) = self.separar_keyspace(nombre);
+                let tabla = self
+                    .keyspaces
+                    .get(&keyspace)
+                    .and_then(|keyspace| keyspace.tablas.get(nombre_tabla))
+                    .ok_or_else(|| invalida(&format!("la tabla {} no existe", nombre)))?;
+                let create = tabla.sentencia_create(&keyspace);
+                let tipo = tipo_tabla(tabla);
+                vec![format!("{},{},{},{}", keyspace, tipo, nombre_tabla, create)]
+            }
+        };
+        Ok(Some(filas))
+    }
 
-                        if consistencia == LevelConsistency::Weak {
-                            for ip_replica in replicas {
-                                println!("Se envía la consulta a la réplica de IP: {}", ip_replica);
-                                let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
-
-                                let direccion =
-                                    format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                                if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica) {
-                                    if endpoint_data.application_state.status == NodeStatus::Normal
-                                    {
-                                        let deserialize_response = send_and_deserial(
-                                            direccion,
-                                            consulta_explicita,
-                                            LevelConsistency::Weak,
-                                            REPLICA,
-                                            tipo_consulta,
-                                        )?;
-                                        if deserialize_response[0] == "ACK" {
-                                            // Se cumple la consistencia
-                                            println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - -");
-                                            return Ok(None);
-                                        }
-                                    }
-                                }
-                            }
-                            return Err("No se cumplió la consistencia de la consulta".to_string());
-                        }
-                        let mut exitos = 0;
-                        for ip_replica in replicas {
-                            let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
-
-                            let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                            if ip_replica == self.ip {
-                                println!("El nodo coordinador es réplica del nodo responsable caído. Se insertan los datos en sus tablas");
-                                self.insertar_a_tabla(
-                                    tabla_consulta.to_string(),
-                                    obtener_row(query),
-                                );
-                                self.persistir_insert(
-                                    tabla_consulta.to_string(),
-                                    obtener_row(query),
-                                );
-                                self.timestamp += 1;
-                                exitos += 1;
-                                if exitos >= quorum {
-                                    println!("Se han obtenido más de {} ACKs: Se cumple el Consistency Level", quorum);
-                                    println!(
-                                        "- - - - - - - - - - - - - - - - - - - - - - - - - - - -"
-                                    );
-                                    break;
-                                }
-                            } else if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica)
-                            {
-                                if endpoint_data.application_state.status == NodeStatus::Normal {
-                                    if let Ok(deserialize_response) = send_and_deserial(
-                                        direccion,
-                                        consulta_explicita,
-                                        LevelConsistency::Strong,
-                                        REPLICA,
-                                        tipo_consulta,
-                                    ) {
-                                        if deserialize_response[0] == "ACK" {
-                                            exitos += 1;
-                                            if exitos >= quorum {
-                                                println!("Se han obtenido más de {} ACKs: Se cumple el Consistency Level", quorum);
-                                                // Se cumple la consistencia
-                                                break;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        if exitos < quorum {
-                            return Err("No se cumplió la consistencia de la consulta".to_string());
-                        }
-                    }
-                    println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - -");
-                }
-                Ok(None)
-            }
-            TypeCQL::Select => {
-                if condicion.condicion1.is_empty() {
-                    //NO HAY WHERE
-                    //1ero buscar los datos de nuestro nodo
-                    let mut vector_datos: Vec<String> = Vec::new();
-                    if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                        vector_datos = tabla_elegida.select(condicion, query.to_string());
-                    }
-                    // Hacer un for de todo lo que hay en la tabla que fue solicitada, appendeamos los campos pedidos
-                    // Luego llamamos a otro nodo
-                    for (_, ip) in self.nodos.clone().iter() {
-                        if &self.ip == ip {
-                            continue;
-                        }
+    /// Método que coordina un INSERT, UPDATE o DELETE: calcula las réplicas vivas de la
+    /// partition key y lo aplica en este nodo si es una de ellas. Devuelve el envío a las
+    /// demás réplicas, que esperan tantos ACKs como pida el nivel de consistencia
+    fn preparar_escritura(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+        nivel_replicacion: usize,
+        requeridas: usize,
+    ) -> Result<EscrituraPendiente, String> {
+        // Todas las réplicas aplican la escritura con el mismo momento
+        let consulta = &consulta.clone().con_momento(ahora())?;
+        let hash = self.hash_particion(consulta)?;
+        let vivas = self.replicas_vivas(hash, nivel_replicacion, requeridas)?;
+        println!(
+            "Se ha recibido la consulta: {}",
+            consulta.get_consulta_explicita()
+        );
+        let mut acks_locales = 0;
+        if vivas.contains(&self.ip) {
+            println!("El nodo es réplica de la key: se aplica la escritura en sus tablas");
+            self.aplicar_escritura(consulta)?;
+            acks_locales = 1;
+        }
+        Ok(EscrituraPendiente {
+            remotas: vivas.into_iter().filter(|ip| *ip != self.ip).collect(),
+            consulta: consulta.get_consulta_explicita().to_string(),
+            consistencia,
+            acks_locales,
+            requeridas,
+        })
+    }
 
-                        let nombre_servicio = obtener_nombre_servicio(ip.clone());
+    /// Método que coordina un incremento de contadores (`c = c + n`): una réplica de la
+    /// partition key lo lidera sumándolo a su propio shard y les envía a las demás el
+    /// estado resultante de sus contadores, que lo mezclan con el suyo. Si este nodo no
+    /// es réplica le reenvía el incremento a la primera réplica viva para que lo lidere.
+    /// Los envíos se devuelven pendientes
+    fn coordinar_contador(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+        nivel_replicacion: usize,
+        requeridas: usize,
+    ) -> Result<Ejecucion, String> {
+        let incrementos = obtener_incrementos(consulta.get_query())?;
+        if incrementos.is_empty() {
+            return Err(
+                "Los contadores solo se pueden modificar con incrementos (c = c + n).".to_string(),
+            );
+        }
+        let hash = self.hash_particion(consulta)?;
+        let vivas = self.replicas_vivas(hash, nivel_replicacion, requeridas)?;
+        if !vivas.contains(&self.ip) {
+            return Ok(Ejecucion::Reenviada(ReenvioLider {
+                lider: vivas[0].to_string(),
+                consulta: consulta.get_consulta_explicita().to_string(),
+                consistencia,
+                requeridas,
+            }));
+        }
+        let filas = self.aplicar_incremento(consulta, &incrementos)?;
+        let remotas: Vec<String> = vivas.into_iter().filter(|ip| *ip != self.ip).collect();
+        let mut escrituras = vec![];
+        for fila in filas {
+            escrituras.push(EscrituraPendiente {
+                remotas: remotas.clone(),
+                consulta: self.consulta_estado_contadores(consulta.get_tabla(), &fila)?,
+                consistencia: consistencia.clone(),
+                acks_locales: 1,
+                requeridas,
+            });
+        }
+        Ok(Ejecucion::Pendiente(escrituras))
+    }
 
-                        let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                        if let Ok(deserialized_response) = send_and_deserial(
-                            direccion,
-                            consulta_explicita,
-                            LevelConsistency::Weak,
-                            RESPONSABLE,
-                            tipo_consulta,
-                        ) {
-                            for row in deserialized_response {
-                                if !vector_datos.contains(&row) {
-                                    vector_datos.push(row);
-                                }
-                            }
-                        } else {
-                            // Como en este caso las consultas no tienen WHERE es necesario pasar por todos los nodos para resolverlas
-                            // Si hay algún nodo que no esté disponible porque se cayó entonces prueba con sus réplicas, las cuales
-                            // corresponden a las siguientes ips luego del ip del nodo responsable
-
-                            let replicas =
-                                get_replicas(&self.nodos, ip.to_string(), nivel_replicacion);
-
-                            for ip_replica in replicas {
-                                let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
-
-                                let direccion =
-                                    format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                                if ip_replica == self.ip {
-                                    // Si bien la lógica es similar al caso en que no sea una réplica el que reciba
-                                    // la consulta, en este caso también es necesario devolver el timestamp del nodo
-                                    // ya que luego será usado para el read repair
-
-                                    if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                                        let auxiliar =
-                                            tabla_elegida.select(condicion, query.to_string());
-                                        for linea in auxiliar.iter() {
-                                            if !vector_datos.contains(linea) {
-                                                vector_datos.push(linea.to_string());
-                                            }
-                                        }
-                                    }
-                                    break;
-                                }
+    /// Método que suma los incrementos al shard de este nodo en los contadores de las
+    /// filas que cumplen la condición del UPDATE, lo persiste y devuelve las filas
+    /// actualizadas con sus shards
+    pub fn aplicar_incremento(
+        &mut self,
+        consulta: &Consulta,
+        incrementos: &[(String, i64)],
+    ) -> Result<Vec<String>, String> {
+        let nombre_tabla = consulta.get_tabla().to_string();
+        let ip = self.ip.clone();
+        let tabla = self
+            .get_tabla(&nombre_tabla)?
+            .ok_or(format!("La tabla {} no existe.", nombre_tabla))?;
+        let filas = tabla.incrementar(consulta.get_where(), incrementos, &ip);
+        let headers = tabla.headers.clone();
+        let (claves, indices) = (tabla.indices_clave(), tabla.indices_contadores());
+        // Cada réplica mezcla los shards en otro momento, así que sus mutaciones no se
+        // reconocen como la misma
+        let momento = ahora();
+        for fila in reconciliar_contadores(filas.clone(), &claves, &indices) {
+            let tipo = (TipoMutacion::Update, momento);
+            self.registrar_mutacion(&nombre_tabla, tipo, &headers, &fila);
+        }
+        self.persistir_update(nombre_tabla)?;
+        self.timestamp += 1;
+        Ok(filas)
+    }
 
-                                if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica) {
-                                    if endpoint_data.application_state.status == NodeStatus::Normal
-                                    {
-                                        if let Ok(mut deserialized_response) = send_and_deserial(
-                                            direccion,
-                                            consulta_explicita,
-                                            LevelConsistency::Strong,
-                                            REPLICA,
-                                            tipo_consulta,
-                                        ) {
-                                            deserialized_response.pop();
-                                            for row in deserialized_response {
-                                                if !vector_datos.contains(&row) {
-                                                    vector_datos.push(row);
-                                                }
-                                            }
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    return Ok(Some(vector_datos));
-                }
-                //HAY WHERE
-                let hash_valor = obtener_hash_key_select(condicion);
-                let ip_nodo_responsable =
-                    self.get_nodo_responsable(hash_valor).ok_or_else(|| {
-                        "No se ha encontrado el nodo responsable para la key.".to_string()
-                    })?;
-                if ip_nodo_responsable == self.ip {
-                    // Soy el nodo responsable
-                    let mut datos = Vec::new();
-                    if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                        datos = tabla_elegida.select(condicion, query.to_string());
-                    }
+    // UPDATE con el que las réplicas mezclan el estado de los contadores de la fila,
+    // que se identifica por su clave primaria
+    fn consulta_estado_contadores(
+        &mut self,
+        nombre_tabla: &str,
+        fila: &str,
+    ) -> Result<String, String> {
+        let tabla = self
+            .get_tabla(nombre_tabla)?
+            .ok_or(format!("La tabla {} no existe.", nombre_tabla))?;
+        let valores = fila.split(",").collect::<Vec<&str>>();
+        let asignaciones: Vec<String> = tabla
+            .indices_contadores()
+            .iter()
+            .filter_map(|indice| {
+                Some(format!(
+                    "{} = {}",
+                    tabla.headers.get(*indice)?,
+                    valores.get(*indice)?
+                ))
+            })
+            .collect();
+        Ok(format!(
+            "UPDATE {} SET {} WHERE {}",
+            nombre_tabla,
+            asignaciones.join(", "),
+            tabla.condicion_clave(fila)
+        ))
+    }
 
-                    // Considerando que un nodo puede caerse y reconectarse, aunque no tenga que recuperar los datos que tenía antes
-                    // ese nodo vuelve a estar presente y si era réplica de otro nodo, cuando se reconecte vuelve a serlo
+    // Hash del valor de la partition key de la sentencia
+    fn hash_particion(&mut self, consulta: &Consulta) -> Result<u32, String> {
+        let columna_particion = self.columna_particion(consulta.get_tabla())?;
+        if consulta.get_type() == &TypeCQL::Insert {
+            return obtener_hash_origen(consulta.get_sentencia(), &columna_particion);
+        }
+        obtener_hash_key_select(consulta.get_where(), &columna_particion)
+    }
 
-                    // En ese caso el SELECT a un nodo responsable también realiza SELECT a las réplicas, ya que para ese caso concreto
-                    // se obtendrán valores distintos para un dato (incluso la inexistencia de este en el nodo que se reconecta) y será
-                    // necesario hacer un read repair para que ese nodo tenga ese dato actualizado
+    // Nombre de la partition key de la tabla, vacío si la tabla no existe
+    fn columna_particion(&mut self, nombre_tabla: &str) -> Result<String, String> {
+        Ok(self
+            .get_tabla(nombre_tabla)?
+            .map(|tabla| tabla.clave_particion().to_string())
+            .unwrap_or_default())
+    }
 
-                    // En todos los demás casos esta operación se hace pero no produce cambios, pero para poder contemplar ese caso particular
-                    // es necesario hacer esto
+    // Verifica las relaciones del WHERE y las columnas del SELECT con la definición
+    // de la tabla
+    fn validar_consulta(&mut self, consulta: &Consulta) -> Result<(), String> {
+        if !matches!(
+            consulta.get_type(),
+            TypeCQL::Select | TypeCQL::Insert | TypeCQL::Update | TypeCQL::Delete
+        ) {
+            return Ok(());
+        }
+        let Some(tabla) = self.get_tabla(consulta.get_tabla())? else {
+            return Ok(());
+        };
+        if tabla.base.is_some() && consulta.get_type() != &TypeCQL::Select {
+            return Err(invalida(&format!(
+                "no se puede escribir en la vista materializada {}, se actualiza con las escrituras de su tabla base",
+                consulta.get_tabla()
+            )));
+        }
+        match consulta.get_sentencia() {
+            Sentencia::Select {
+                seleccion,
+                permitir_filtrado,
+                ..
+            } => tabla.validar_select(consulta.get_where(), seleccion, *permitir_filtrado),
+            Sentencia::Insert {
+                columnas, valores, ..
+            } => tabla.validar_valores(columnas, valores),
+            sentencia @ (Sentencia::Update { .. } | Sentencia::Delete { .. }) => {
+                tabla.validar_escritura(consulta.get_where())?;
+                tabla.validar_asignaciones(sentencia.asignaciones())
+            }
+            _ => Ok(()),
+        }
+    }
 
-                    // Enviar lecturas a réplicas y esperar respuestas
-                    let resultado_consistency = self.enviar_lecturas_replicas(
-                        consulta.clone(),
-                        consistencia,
-                        datos,
-                        tabla_consulta,
-                        quorum,
-                    )?;
-                    let mut aux: Vec<String> = vec![];
-                    for elem in resultado_consistency {
-                        if !aux.contains(&elem) {
-                            aux.push(elem);
-                        }
+    // Indica si la tabla tiene columnas counter
+    fn tiene_contadores(&mut self, nombre_tabla: &str) -> Result<bool, String> {
+        Ok(self
+            .get_tabla(nombre_tabla)?
+            .is_some_and(|tabla| !tabla.indices_contadores().is_empty()))
+    }
+
+    // En las tablas con contadores mezcla los shards de las filas leídas de las
+    // réplicas y reemplaza cada contador por su valor
+    fn reconciliar_lectura(
+        &mut self,
+        consulta: &Consulta,
+        filas: Vec<String>,
+    ) -> Result<Vec<String>, String> {
+        let Some(tabla) = self.get_tabla(consulta.get_tabla())? else {
+            return Ok(filas);
+        };
+        let seleccion = consulta.get_seleccion();
+        let indices: Vec<usize> = tabla
+            .columnas_seleccionadas(&seleccion)
+            .iter()
+            .enumerate()
+            .filter(|(_, columna)| columna.tipo == TipoColumna::Counter)
+            .map(|(indice, _)| indice)
+            .collect();
+        if indices.is_empty() {
+            return Ok(filas);
+        }
+        // La lectura incluye la clave primaria aunque el SELECT no la pida
+        let claves: Vec<usize> = tabla
+            .columnas_clave()
+            .iter()
+            .filter_map(|columna| tabla.posicion_seleccionada(&seleccion, columna))
+            .collect();
+        Ok(reconciliar_contadores(filas, &claves, &indices))
+    }
+
+    /// Método que prepara una transacción liviana (un INSERT, UPDATE o DELETE con
+    /// condición IF) para coordinarla con rondas de Paxos entre las réplicas vivas de la
+    /// partition key. SERIAL y LOCAL_SERIAL confirman la escritura en QUORUM
+    fn preparar_lwt(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+        nivel_replicacion: usize,
+    ) -> Result<TransaccionPendiente, String> {
+        let condicion = consulta
+            .get_condicion_lwt()
+            .cloned()
+            .ok_or("La consulta no tiene condición IF.".to_string())?;
+        let sentencia = consulta
+            .clone()
+            .con_momento(ahora())?
+            .sentencia_sin_condicion();
+        let quorum = LevelConsistency::Serial.requeridas_lectura(nivel_replicacion)?;
+        let requeridas = match consistencia {
+            LevelConsistency::Serial | LevelConsistency::LocalSerial => quorum,
+            _ => consistencia.requeridas_escritura(nivel_replicacion)?,
+        };
+        let hash = self.hash_particion(consulta)?;
+        let vivas = self.replicas_vivas(hash, nivel_replicacion, quorum.max(requeridas))?;
+        let headers = self
+            .get_tabla(consulta.get_tabla())?
+            .map(|tabla| tabla.headers.clone())
+            .ok_or(format!("La tabla {} no existe.", consulta.get_tabla()))?;
+        Ok(TransaccionPendiente {
+            rondas: RondasPaxos {
+                ip: self.ip.to_string(),
+                vivas,
+                quorum,
+                requeridas,
+            },
+            sentencia,
+            condicion,
+            headers,
+        })
+    }
+
+    // Una lectura SERIAL primero completa las transacciones que hayan quedado aceptadas
+    // sin confirmar en la partición, para no devolver un estado que después cambie
+    fn terminar_propuestas_en_curso(
+        &mut self,
+        consulta: &Consulta,
+        nivel_replicacion: usize,
+    ) -> Result<(), String> {
+        let quorum = LevelConsistency::Serial.requeridas_lectura(nivel_replicacion)?;
+        let hash = self.hash_particion(consulta)?;
+        let rondas = RondasPaxos {
+            ip: self.ip.to_string(),
+            vivas: self.replicas_vivas(hash, nivel_replicacion, quorum)?,
+            quorum,
+            requeridas: quorum,
+        };
+        let sentencia = consulta.get_consulta_explicita().to_string();
+        let mut atender_paxos =
+            |fase, ballot, sentencia: &str| self.atender_paxos(fase, ballot, sentencia);
+        rondas.con_reintentos(|ballot| {
+            Ok(rondas
+                .preparar(ballot, &sentencia, &mut atender_paxos)?
+                .map(|_| ()))
+        })
+    }
+
+    /// Método con el que una réplica atiende una fase de Paxos de la sentencia: en el
+    /// prepare promete el ballot, en el propose acepta la sentencia y en el commit la
+    /// aplica en sus tablas. Devuelve los campos de su respuesta al coordinador
+    pub fn atender_paxos(
+        &mut self,
+        fase: u8,
+        ballot: u64,
+        sentencia: &str,
+    ) -> Result<Vec<String>, String> {
+        let consulta = procesar_consulta(sentencia)?;
+        let columna_particion = self.columna_particion(consulta.get_tabla())?;
+        let clave = clave_particion(&consulta, &columna_particion);
+        match fase {
+            // La promesa y la aceptación se guardan antes de responder, para que la
+            // réplica las respete aunque se reinicie
+            PREPARAR => match self.paxos.preparar(&clave, ballot) {
+                Ok((aceptado, confirmado)) => {
+                    self.paxos.guardar()?;
+                    let (fila, timestamp) = self.fila_actual(&consulta)?.unzip();
+                    Ok(Promesa {
+                        aceptado,
+                        confirmado,
+                        timestamp: timestamp.unwrap_or_default(),
+                        fila,
                     }
-                    return Ok(Some(aux));
+                    .campos())
+                }
+                Err(prometido) => Ok(rechazo(prometido)),
+            },
+            PROPONER => match self.paxos.proponer(&clave, ballot, sentencia) {
+                Ok(()) => {
+                    self.paxos.guardar()?;
+                    Ok(vec![ACEPTADA.to_string()])
                 }
-                let mut vector_datos: Vec<String> = Vec::new();
-                // Se le envía la consulta al nodo responsable.
+                Err(prometido) => Ok(rechazo(prometido)),
+            },
+            CONFIRMAR => {
+                self.aplicar_escritura(&consulta)?;
+                self.paxos.confirmar(&clave, ballot);
+                self.paxos.guardar()?;
+                Ok(vec!["ACK".to_string()])
+            }
+            _ => Err(format!("Fase de Paxos desconocida: {}", fase)),
+        }
+    }
 
-                let nombre_servicio = obtener_nombre_servicio(ip_nodo_responsable.clone());
+    // Fila completa que la sentencia lee o modifica, si existe en este nodo, con el
+    // momento de su última escritura
+    fn fila_actual(&mut self, consulta: &Consulta) -> Result<Option<(String, u64)>, String> {
+        let Some(tabla) = self.get_tabla(consulta.get_tabla())? else {
+            return Ok(None);
+        };
+        let condicion = match consulta.get_type() {
+            TypeCQL::Insert => tabla.condicion_clave(&obtener_fila(consulta.get_sentencia())),
+            _ => consulta.get_where().clone(),
+        };
+        Ok(tabla
+            .select(&condicion, &Seleccion::default())
+            .into_iter()
+            .next()
+            .map(|fila| {
+                let momento = tabla.ultima_escritura(&fila).max(0) as u64;
+                (fila, momento)
+            }))
+    }
 
-                let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
+    /// Método que coordina un SELECT: si el WHERE indica las particiones las lee de sus
+    /// réplicas y si no recorre todos los nodos
+    fn ejecutar_select(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+        nivel_replicacion: usize,
+        requeridas: usize,
+    ) -> Result<Option<Vec<String>>, String> {
+        let seleccion = consulta.get_seleccion();
+        let condicion = consulta.get_where();
+        let tipo_consulta = consulta.get_type();
+        let tabla_consulta = consulta.get_tabla();
+        let consulta_explicita = consulta.get_consulta_explicita();
+        let columna_particion = self.columna_particion(tabla_consulta)?;
+        if condicion.valores(&columna_particion).is_none() {
+            // El WHERE no indica las particiones a leer
+            if let Some(pagina) = self.pagina_recorrido(consulta)? {
+                return Ok(Some(self.leer_pagina(
+                    consulta,
+                    &pagina,
+                    nivel_replicacion,
+                )?));
+            }
+            //1ero buscar los datos de nuestro nodo
+            let mut vector_datos: Vec<String> = Vec::new();
+            if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
+                vector_datos = tabla_elegida.select(condicion, &seleccion);
+            }
+            // Hacer un for de todo lo que hay en la tabla que fue solicitada, appendeamos los campos pedidos
+            // Luego llamamos a otro nodo
+            for (_, ip) in self.nodos.clone().iter() {
+                if &self.ip == ip {
+                    continue;
+                }
+
+                let nombre_servicio = obtener_nombre_servicio(ip.clone());
 
+                let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
                 if let Ok(deserialized_response) = send_and_deserial(
                     direccion,
                     consulta_explicita,
-                    LevelConsistency::Strong,
+                    LevelConsistency::One,
                     RESPONSABLE,
                     tipo_consulta,
                 ) {
@@ -438,23 +910,13 @@ impl Nodo {
                             vector_datos.push(row);
                         }
                     }
-                    return Ok(Some(vector_datos));
-                }
-                // En este caso no pude conectarme al nodo responsable pero yo (nodo coordinador) tampoco lo soy, y como hay
-                // un WHERE en la consulta es necesario enviársela a los nodos que les corresponda resolverla pero como no
-                // pude conectarme al nodo responsable y alguien tiene que hacerlo, entonces una de sus réplicas tiene que
-                // tomar su lugar como nodo responsable
-
-                // De nuevo aprovechando la implementación sé que las réplicas del nodo responsables son las siguientes ips en
-                // el cluster por lo que tengo también las ips de ellas, tengo que iterar hasta encontrar una réplica a la que
-                // me pueda conectar
-
-                let replicas = get_replicas(
-                    &self.nodos,
-                    ip_nodo_responsable.to_string(),
-                    nivel_replicacion,
-                );
-                if consistencia == LevelConsistency::Weak {
+                } else {
+                    // Como en este caso las consultas no tienen WHERE es necesario pasar por todos los nodos para resolverlas
+                    // Si hay algún nodo que no esté disponible porque se cayó entonces prueba con sus réplicas, las cuales
+                    // corresponden a las siguientes ips luego del ip del nodo responsable
+
+                    let replicas = get_replicas(&self.nodos, ip.to_string(), nivel_replicacion);
+
                     for ip_replica in replicas {
                         let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
 
@@ -465,424 +927,1354 @@ impl Nodo {
                             // ya que luego será usado para el read repair
 
                             if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                                let auxiliar = tabla_elegida.select(condicion, query.to_string());
+                                let auxiliar = tabla_elegida.select(condicion, &seleccion);
                                 for linea in auxiliar.iter() {
                                     if !vector_datos.contains(linea) {
                                         vector_datos.push(linea.to_string());
                                     }
                                 }
                             }
-                            return Ok(Some(vector_datos));
+                            break;
                         }
+
                         if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica) {
                             if endpoint_data.application_state.status == NodeStatus::Normal {
-                                let mut deserialized_response = send_and_deserial(
+                                if let Ok(mut deserialized_response) = send_and_deserial(
                                     direccion,
                                     consulta_explicita,
-                                    LevelConsistency::Strong,
+                                    LevelConsistency::Quorum,
                                     REPLICA,
                                     tipo_consulta,
-                                )?;
-                                deserialized_response.pop();
-                                return Ok(Some(deserialized_response));
+                                ) {
+                                    deserialized_response.pop();
+                                    for row in deserialized_response {
+                                        if !vector_datos.contains(&row) {
+                                            vector_datos.push(row);
+                                        }
+                                    }
+                                    break;
+                                }
                             }
                         }
                     }
-                } else {
-                    //SELECT con where pero no soy el nodo responsable CONSISTENCY STRONG
-                    let mut exitos = 0;
+                }
+            }
+            let filas = self.reconciliar_lectura(consulta, vector_datos)?;
+            return Ok(Some(self.terminar_lectura(consulta, filas)?));
+        }
+        self.leer_particiones(consulta, consistencia, nivel_replicacion, requeridas)
+    }
 
-                    // respuestas: Vec<(datos, timestamp, direccion_replica)>
-                    // Mapear los datos por timestamp
-                    let mut mapa_respuestas: HashMap<String, (Vec<String>, String)> =
-                        HashMap::new();
+    // Las filas de una tabla con contadores leídas de distintas réplicas se mezclan por su
+    // clave primaria, así que si el SELECT no la incluye se la lee también
+    fn lectura_contadores(&mut self, consulta: &Consulta) -> Result<Option<Consulta>, String> {
+        let seleccion = consulta.get_seleccion();
+        let Some(tabla) = self.get_tabla(consulta.get_tabla())? else {
+            return Ok(None);
+        };
+        if tabla.indices_contadores().is_empty()
+            || seleccion.selectores.is_empty()
+            || seleccion.agrupa()
+        {
+            return Ok(None);
+        }
+        let faltantes: Vec<String> = tabla
+            .columnas_clave()
+            .into_iter()
+            .filter(|columna| tabla.posicion_seleccionada(&seleccion, columna).is_none())
+            .collect();
+        if faltantes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(consulta.con_columnas(&faltantes)))
+    }
 
-                    for ip_replica in replicas {
-                        let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
+    // Página que se le pide a cada nodo en un recorrido paginado de todas las
+    // particiones. Con LIMIT, agregaciones o contadores el resultado depende de todas
+    // las filas, así que se junta completo en el coordinador y se pagina después
+    fn pagina_recorrido(&mut self, consulta: &Consulta) -> Result<Option<Pagina>, String> {
+        let seleccion = consulta.get_seleccion();
+        if seleccion.limite.is_some()
+            || seleccion.agrupa()
+            || self.tiene_contadores(consulta.get_tabla())?
+        {
+            return Ok(None);
+        }
+        Ok(consulta.get_pagina().cloned())
+    }
 
-                        let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                        if ip_replica == self.ip {
-                            let mut respuesta: Vec<String> = Vec::new();
-                            if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                                let auxiliar = tabla_elegida.select(condicion, query.to_string());
-                                for linea in auxiliar.iter() {
-                                    if !respuesta.contains(linea) {
-                                        respuesta.push(linea.to_string());
-                                    }
-                                }
-                            }
-                            mapa_respuestas
-                                .entry(self.timestamp.to_string())
-                                .or_insert((respuesta.clone(), direccion.to_string()))
-                                .0
-                                .extend(respuesta.into_iter());
-                            exitos += 1;
-                            if exitos >= quorum {
-                                break;
-                            }
-                        } else if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica) {
-                            if endpoint_data.application_state.status != NodeStatus::Normal {
-                                continue;
-                            }
-                            let mut deserialized_response = send_and_deserial(
-                                direccion.to_string(),
-                                consulta_explicita,
-                                LevelConsistency::Strong,
-                                REPLICA,
-                                tipo_consulta,
-                            )?;
-                            let timestamp = deserialized_response.pop().unwrap();
-
-                            mapa_respuestas
-                                .entry(timestamp)
-                                .or_insert((deserialized_response.clone(), direccion))
-                                .0
-                                .extend(deserialized_response.into_iter());
-                            exitos += 1;
-                            if exitos >= quorum {
-                                break;
-                            }
+    /// Método que coordina la lectura de una página de un SELECT que recorre todas las
+    /// particiones: cada nodo, o la primera de sus réplicas vivas si está caído, devuelve
+    /// solo sus filas de la página, y el coordinador se queda con las primeras de todas
+    fn leer_pagina(
+        &mut self,
+        consulta: &Consulta,
+        pagina: &Pagina,
+        nivel_replicacion: usize,
+    ) -> Result<Vec<String>, String> {
+        let mut filas = match self.get_tabla(consulta.get_tabla())? {
+            Some(tabla) => {
+                tabla.select_pagina(consulta.get_where(), &consulta.get_seleccion(), pagina)?
+            }
+            None => vec![],
+        };
+        let pedido = serializar_pagina(pagina, consulta.get_consulta_explicita());
+        for ip in self.nodos.values().filter(|ip| **ip != self.ip) {
+            let replicas = get_replicas(&self.nodos, ip.to_string(), nivel_replicacion);
+            for candidato in std::iter::once(ip).chain(replicas.iter()) {
+                // Las filas de este nodo ya están en la página
+                if *candidato == self.ip {
+                    break;
+                }
+                let vivo = self
+                    .metadata_nodos
+                    .get(candidato)
+                    .is_some_and(|endpoint_data| {
+                        endpoint_data.application_state.status == NodeStatus::Normal
+                    });
+                if candidato != ip && !vivo {
+                    continue;
+                }
+                if let Ok(respuesta) = leer_pagina_de(candidato, &pedido) {
+                    for fila in respuesta {
+                        if !filas.contains(&fila) {
+                            filas.push(fila);
                         }
                     }
-                    if exitos < quorum {
-                        return Err("No se cumplió la consistencia de la consulta".to_string());
-                    }
-
-                    // Se mapearon los datos por timestamp, ahora se debe elegir el más reciente
-                    let (timestamp_mas_reciente, datos_mas_recientes) = mapa_respuestas
-                        .iter()
-                        .max_by_key(|entry| entry.0)
-                        .ok_or_else(|| "No se encontraron datos en las respuestas".to_string())?;
-
-                    let dato_mas_reciente = &datos_mas_recientes;
-                    // Caso de diferentes timestamps entre las réplicas
-                    if mapa_respuestas.len() > 1 {
-                        // Hay discrepancias, realizar read repair
-                        self.read_repair(
-                            &mapa_respuestas,
-                            dato_mas_reciente,
-                            timestamp_mas_reciente,
-                            tabla_consulta,
-                        )?;
-                    }
-                    return Ok(Some(dato_mas_reciente.0.clone()));
+                    break;
                 }
-                Ok(None)
-            }
-            TypeCQL::Update => {
-                let hash_valor = obtener_hash_key_select(condicion);
-                let ip_nodo_responsable =
-                    self.get_nodo_responsable(hash_valor).ok_or_else(|| {
-                        "No se ha encontrado el nodo responsable para la key.".to_string()
-                    })?;
-                if ip_nodo_responsable == self.ip {
-                    // Soy el nodo responsable
-                    if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                        tabla_elegida.actualizar(condicion, query.to_string());
-                        self.persistir_update(tabla_consulta.to_string())?;
-                        self.timestamp += 1;
-                    }
-
-                    // Hay que ver si en este caso es necesario actualizar el dato en las réplicas o no
-                    // Ya que de eso puede encargarse el read repair al momento de hacer un SELECT
-                    // Por lo que entonces habría que modificar esa operación para que soporte esto => Sí
+            }
+        }
+        // Con una fila de más se sabe si el recorrido sigue después de la página
+        let (filas, _) = paginar_filas(filas, pagina.tamanio as i32 + 1, pagina.desde.as_ref())?;
+        Ok(filas)
+    }
 
-                    self.enviar_escrituras_replicas(consulta.clone(), consistencia, quorum)?;
-                } else {
-                    // Se le envía la consulta al nodo responsable.
+    /// Método que coordina un SELECT que restringe la partition key con = o IN: lee
+    /// cada partición por separado y junta sus filas
+    fn leer_particiones(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+        nivel_replicacion: usize,
+        requeridas: usize,
+    ) -> Result<Option<Vec<String>>, String> {
+        let columna_particion = self.columna_particion(consulta.get_tabla())?;
+        let valores = consulta
+            .get_where()
+            .valores(&columna_particion)
+            .unwrap_or_default();
+        if let [_] = valores.as_slice() {
+            return self.leer_particion(consulta, consistencia, nivel_replicacion, requeridas);
+        }
+        let mut filas = vec![];
+        for valor in valores {
+            let consulta_particion = consulta.en_particion(&columna_particion, &valor);
+            let leidas = self.leer_particion(
+                &consulta_particion,
+                consistencia.clone(),
+                nivel_replicacion,
+                requeridas,
+            )?;
+            filas.extend(leidas.unwrap_or_default());
+        }
+        Ok(Some(self.terminar_lectura(consulta, filas)?))
+    }
 
-                    let nombre_servicio = obtener_nombre_servicio(ip_nodo_responsable.clone());
+    // Las filas de cada partición ya vienen ordenadas, limitadas y, si la consulta
+    // agrupa, resumidas en resultados parciales: falta hacerlo con las de todas juntas
+    fn terminar_lectura(
+        &mut self,
+        consulta: &Consulta,
+        mut filas: Vec<String>,
+    ) -> Result<Vec<String>, String> {
+        let seleccion = consulta.get_seleccion();
+        if let Some(tabla) = self.get_tabla(consulta.get_tabla())? {
+            match seleccion.agrupa() {
+                true => filas = tabla.combinar_grupos(filas, &seleccion),
+                false => tabla.ordenar_resultado(&mut filas, &seleccion),
+            }
+        }
+        if let Some(limite) = seleccion.limite {
+            filas.truncate(limite);
+        }
+        Ok(filas)
+    }
 
-                    let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                    if send_and_not_deserial(
-                        direccion,
-                        consulta_explicita,
-                        LevelConsistency::Strong,
-                        RESPONSABLE,
-                    )
-                    .is_err()
-                    {
-                        // Al igual que en el caso de una operación SELECT, si no pude conectarme al nodo responsable pero yo tampoco lo soy
-                        // entonces yo (el nodo coordinador) paso a ser un nuevo "nodo responsable" enviándole la consulta a las réplicas del
-                        // nodo responsable real y recibiendo las respuestas, luego actuar pero sin involucrar datos propios
-
-                        let replicas = get_replicas(
-                            &self.nodos,
-                            ip_nodo_responsable.to_string(),
-                            nivel_replicacion,
-                        );
+    fn leer_particion(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+        nivel_replicacion: usize,
+        requeridas: usize,
+    ) -> Result<Option<Vec<String>>, String> {
+        if matches!(
+            consistencia,
+            LevelConsistency::Serial | LevelConsistency::LocalSerial
+        ) {
+            self.terminar_propuestas_en_curso(consulta, nivel_replicacion)?;
+        }
+        self.coordinar_lectura(consulta, consistencia, nivel_replicacion, requeridas)
+    }
 
-                        if consistencia == LevelConsistency::Weak {
-                            for ip_replica in replicas {
-                                if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica) {
-                                    if endpoint_data.application_state.status != NodeStatus::Normal
-                                    {
-                                        continue;
-                                    }
+    /// Método que coordina la lectura de una partición: lee los datos de una réplica de la
+    /// partition key (este nodo, si es una) y solo el digest de las demás hasta reunir
+    /// las que pide el nivel de consistencia. Si algún digest no coincide les pide los
+    /// datos completos, se queda con los más recientes y hace read repair
+    fn coordinar_lectura(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+        nivel_replicacion: usize,
+        requeridas: usize,
+    ) -> Result<Option<Vec<String>>, String> {
+        let hash = self.hash_particion(consulta)?;
+        let vivas = self.replicas_vivas(hash, nivel_replicacion, requeridas)?;
+        let local = match vivas.contains(&self.ip) {
+            true => Some(self.leer_local(consulta)?),
+            false => None,
+        };
+        let remotas: Vec<String> = vivas.into_iter().filter(|ip| *ip != self.ip).collect();
+        let mut respuestas = leer_replicas(
+            &remotas,
+            consulta.get_consulta_explicita(),
+            &consistencia,
+            (local, requeridas),
+            &mut self.latencias_lectura,
+        )?;
+        let datos = respuestas
+            .iter()
+            .find_map(|respuesta| respuesta.filas.clone())
+            .unwrap_or_default();
+        let digest_datos = digest(&datos);
+        if respuestas
+            .iter()
+            .all(|respuesta| respuesta.digest == digest_datos)
+        {
+            return Ok(Some(
+                self.reconciliar_lectura(consulta, sin_repetidas(datos))?,
+            ));
+        }
+        println!("Los digests de las réplicas no coinciden: se les piden los datos completos");
+        let solo_digest: Vec<String> = respuestas
+            .iter()
+            .filter(|respuesta| respuesta.filas.is_none())
+            .map(|respuesta| respuesta.ip.clone())
+            .collect();
+        respuestas.retain(|respuesta| respuesta.filas.is_some());
+        respuestas.extend(pedir_datos(&solo_digest, consulta.get_consulta_explicita()));
+        if self.tiene_contadores(consulta.get_tabla())? {
+            // Los contadores no se resuelven por timestamp: se mezclan los shards de todas
+            let filas = respuestas
+                .into_iter()
+                .flat_map(|respuesta| respuesta.filas.unwrap_or_default())
+                .collect();
+            return Ok(Some(self.reconciliar_lectura(consulta, filas)?));
+        }
+        self.resolver_respuestas(respuestas, consulta)
+    }
 
-                                    let nombre_servicio =
-                                        obtener_nombre_servicio(ip_replica.clone());
-
-                                    let direccion =
-                                        format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                                    if let Ok(deserialize_response) = send_and_deserial(
-                                        direccion,
-                                        consulta_explicita,
-                                        LevelConsistency::Strong,
-                                        REPLICA,
-                                        tipo_consulta,
-                                    ) {
-                                        if deserialize_response[0] == "ACK" {
-                                            // Se cumple la consistencia
-                                            return Ok(None);
-                                        }
-                                    }
-                                }
-                            }
-                            return Err("No se cumplió la consistencia de la consulta".to_string());
-                        }
-                        let mut exitos = 0;
-                        let replicas = get_replicas(
-                            &self.nodos,
-                            ip_nodo_responsable.to_string(),
-                            nivel_replicacion,
-                        );
+    // Lectura de los datos de este nodo, como la respondería una réplica
+    fn leer_local(&mut self, consulta: &Consulta) -> Result<RespuestaLectura, String> {
+        let mut filas = Vec::new();
+        if let Some(tabla) = self.get_tabla(consulta.get_tabla())? {
+            filas = tabla.select(consulta.get_where(), &consulta.get_seleccion());
+        }
+        Ok(RespuestaLectura::con_datos(
+            &self.ip,
+            filas,
+            self.timestamp.to_string(),
+        ))
+    }
 
-                        for ip_replica in replicas {
-                            if self.ip == ip_replica {
-                                if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                                    tabla_elegida.actualizar(condicion, query.to_string());
-                                    self.persistir_update(tabla_consulta.to_string())?;
-                                    self.timestamp += 1;
-                                    exitos += 1;
-                                    if exitos >= quorum {
-                                        break;
-                                    }
-                                }
-                                continue;
-                            }
-                            if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica) {
-                                if endpoint_data.application_state.status != NodeStatus::Normal {
-                                    continue;
-                                }
+    // Se queda con los datos de la réplica con el timestamp más reciente y hace read
+    // repair en las que respondieron otros
+    fn resolver_respuestas(
+        &mut self,
+        respuestas: Vec<RespuestaLectura>,
+        consulta: &Consulta,
+    ) -> Result<Option<Vec<String>>, String> {
+        // Mapear los datos por timestamp: (datos, direccion_replica)
+        let mut mapa_respuestas: HashMap<String, (Vec<String>, String)> = HashMap::new();
+        for respuesta in respuestas {
+            let nombre_servicio = obtener_nombre_servicio(respuesta.ip.clone());
+            let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
+            mapa_respuestas
+                .entry(respuesta.timestamp)
+                .or_insert((Vec::new(), direccion))
+                .0
+                .extend(respuesta.filas.unwrap_or_default());
+        }
+        let (timestamp_mas_reciente, dato_mas_reciente) = mapa_respuestas
+            .iter()
+            .max_by_key(|(timestamp, _)| timestamp.parse::<u64>().unwrap_or_default())
+            .map(|(timestamp, datos)| (timestamp.clone(), datos.clone()))
+            .ok_or_else(|| "No se encontraron datos en las respuestas".to_string())?;
+        // Solo se pueden reparar réplicas con filas completas: con columnas proyectadas
+        // o con WRITETIME los UPDATE no tendrían todos los campos de la tabla
+        if mapa_respuestas.len() > 1 && consulta.get_seleccion().filas_completas() {
+            // Hay discrepancias, realizar read repair
+            self.read_repair(
+                &mapa_respuestas,
+                &dato_mas_reciente,
+                &timestamp_mas_reciente,
+                consulta.get_tabla(),
+            )?;
+        }
+        Ok(Some(sin_repetidas(dato_mas_reciente.0)))
+    }
 
-                                let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
+    /// Método que aplica un INSERT, UPDATE o DELETE en las tablas del nodo y lo persiste.
+    /// Si la tabla tiene vistas materializadas les envía los cambios de sus filas
+    pub fn aplicar_escritura(&mut self, consulta: &Consulta) -> Result<(), String> {
+        let vistas = self.vistas_de(consulta.get_tabla());
+        if vistas.is_empty() {
+            return self.aplicar_en_tabla(consulta);
+        }
+        let condicion = match consulta.get_type() {
+            TypeCQL::Insert => self
+                .get_tabla(consulta.get_tabla())?
+                .map(|tabla| tabla.condicion_clave(&obtener_fila(consulta.get_sentencia())))
+                .unwrap_or_default(),
+            _ => consulta.get_where().clone(),
+        };
+        let antes = self.filas_tabla(consulta.get_tabla(), &condicion)?;
+        self.aplicar_en_tabla(consulta)?;
+        let despues = self.filas_tabla(consulta.get_tabla(), &condicion)?;
+        // La escritura ya se aplicó, así que si no se pueden actualizar las vistas no
+        // se la informa como fallida
+        self.actualizar_vistas(consulta, &vistas, (&antes, &despues));
+        Ok(())
+    }
 
-                                let direccion =
-                                    format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                                if let Ok(deserialize_response) = send_and_deserial(
-                                    direccion,
-                                    consulta_explicita,
-                                    LevelConsistency::Strong,
-                                    REPLICA,
-                                    tipo_consulta,
-                                ) {
-                                    if deserialize_response[0] == "ACK" {
-                                        exitos += 1;
-                                        if exitos >= quorum {
-                                            // Se cumple la consistencia
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        if exitos < quorum {
-                            return Err("No se cumplió la consistencia de la consulta".to_string());
-                        }
-                    }
+    fn aplicar_en_tabla(&mut self, consulta: &Consulta) -> Result<(), String> {
+        let tabla = consulta.get_tabla().to_string();
+        let momento = consulta.get_momento().unwrap_or_else(ahora);
+        let asignaciones = consulta.get_sentencia().asignaciones();
+        match consulta.get_type() {
+            TypeCQL::Insert => {
+                let fila = obtener_fila(consulta.get_sentencia());
+                if let Some(fila) = self.insertar_a_tabla(tabla.clone(), fila, momento) {
+                    self.persistir_insert(tabla, fila);
                 }
-                Ok(None)
+            }
+            // Un DELETE de columnas o de elementos de colecciones les asigna null
+            TypeCQL::Update | TypeCQL::Delete if !asignaciones.is_empty() => {
+                self.update_en_tabla(tabla.clone(), consulta.get_where(), asignaciones, momento);
+                self.persistir_update(tabla)?;
             }
             TypeCQL::Delete => {
-                let hash_valor = obtener_hash_key_select(condicion);
-                let ip_nodo_responsable =
-                    self.get_nodo_responsable(hash_valor).ok_or_else(|| {
-                        "No se ha encontrado el nodo responsable para la key.".to_string()
-                    })?;
-                if ip_nodo_responsable == self.ip {
-                    // Soy el nodo responsable
-                    if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                        tabla_elegida.eliminar(condicion);
-                        self.persistir_delete(tabla_consulta.to_string())?;
-                        self.timestamp += 1;
+                self.eliminar_en_tabla(tabla.clone(), consulta.get_where(), momento);
+                self.persistir_delete(tabla)?;
+            }
+            _ => return Err(format!("{} no es una escritura.", consulta.get_type())),
+        }
+        self.timestamp += 1;
+        Ok(())
+    }
+
+    // Filas completas de la tabla que cumplen la condición
+    fn filas_tabla(
+        &mut self,
+        nombre_tabla: &str,
+        condicion: &CondicionWhere,
+    ) -> Result<Vec<String>, String> {
+        Ok(self
+            .get_tabla(nombre_tabla)?
+            .map(|tabla| tabla.filas(condicion))
+            .unwrap_or_default())
+    }
+
+    // Envía a cada vista de la tabla de la escritura los cambios de sus filas, con el
+    // momento de la escritura. Las escrituras que no se pueden enviar solo se informan
+    // en el log
+    fn actualizar_vistas(
+        &mut self,
+        consulta: &Consulta,
+        vistas: &[String],
+        (antes, despues): (&[String], &[String]),
+    ) {
+        let momento = consulta.get_momento().unwrap_or_else(ahora);
+        let posicion = match self
+            .hash_particion(consulta)
+            .and_then(|hash| self.posicion_replica(consulta.get_tabla(), hash))
+        {
+            Ok(posicion) => posicion,
+            Err(e) => {
+                println!(
+                    "No se pudieron actualizar las vistas de {}: {}",
+                    consulta.get_tabla(),
+                    e
+                );
+                return;
+            }
+        };
+        for nombre_vista in vistas {
+            let mutaciones = match (
+                self.buscar_tabla(consulta.get_tabla()),
+                self.buscar_tabla(nombre_vista),
+            ) {
+                (Some(base), Some(vista)) => {
+                    mutaciones_vista(base, (nombre_vista, vista), antes, despues)
+                }
+                _ => continue,
+            };
+            for mutacion in mutaciones {
+                if let Err(e) = self.escribir_en_vista(&mutacion, posicion, momento) {
+                    println!("No se pudo escribir en la vista {}: {}", nombre_vista, e);
+                }
+            }
+        }
+    }
+
+    // Lugar de este nodo entre las réplicas de la partition key de la tabla con el hash
+    // indicado, o None si no es una de ellas
+    fn posicion_replica(&self, nombre_tabla: &str, hash: u32) -> Result<Option<usize>, String> {
+        let replicas = self.replicas_de(hash, self.nivel_replicacion(nombre_tabla))?;
+        Ok(replicas.iter().position(|ip| *ip == self.ip))
+    }
+
+    // Aplica la escritura de la vista en la réplica de su partition key que ocupa el mismo
+    // lugar que este nodo entre las de la fila base, así cada réplica de la vista recibe
+    // la escritura de una sola réplica base. Si este nodo no es réplica de la fila base
+    // la reciben todas. No se esperan las confirmaciones, como en Cassandra la vista se
+    // actualiza de forma asincrónica
+    fn escribir_en_vista(
+        &mut self,
+        mutacion: &str,
+        posicion: Option<usize>,
+        momento: i64,
+    ) -> Result<(), String> {
+        let consulta = procesar_consulta(mutacion)?.con_momento(momento)?;
+        let hash = self.hash_particion(&consulta)?;
+        let replicas = self.replicas_de(hash, self.nivel_replicacion(consulta.get_tabla()))?;
+        let destinos = match posicion.and_then(|posicion| replicas.get(posicion)) {
+            Some(ip) => vec![ip.to_string()],
+            None => replicas,
+        };
+        if destinos.contains(&self.ip) {
+            self.aplicar_escritura(&consulta)?;
+        }
+        let remotas: Vec<String> = destinos.into_iter().filter(|ip| *ip != self.ip).collect();
+        enviar_mutacion(
+            &remotas,
+            consulta.get_consulta_explicita(),
+            &LevelConsistency::One,
+            (0, 0),
+        )
+    }
+
+    /// Método que les agrega a las vistas creadas las filas que ya tenía su tabla base
+    /// en este nodo, una vez que todos los nodos vivos tienen la vista en su schema y
+    /// pueden recibirlas
+    pub fn construir_vistas_pendientes(&mut self) {
+        if self.vistas_pendientes.is_empty() || !self.schema_acordado() {
+            return;
+        }
+        for nombre_vista in std::mem::take(&mut self.vistas_pendientes) {
+            if let Err(e) = self.construir_vista(&nombre_vista) {
+                println!("No se pudo construir la vista {}: {}", nombre_vista, e);
+            }
+        }
+    }
+
+    fn construir_vista(&mut self, nombre_vista: &str) -> Result<(), String> {
+        let Some(nombre_base) = self.tabla_base(nombre_vista) else {
+            return Ok(());
+        };
+        let mut inserts = vec![];
+        if let (Some(base), Some(vista)) = (
+            self.buscar_tabla(&nombre_base),
+            self.buscar_tabla(nombre_vista),
+        ) {
+            // Cada fila va a las réplicas de su partición en la tabla base, cuyo valor es
+            // la clave con la que la tabla guarda sus filas
+            for (particion, filas) in base.datos.iter() {
+                let token = token_particion(particion)?;
+                for fila in filas {
+                    if let Some(fila_vista) = fila_vista(base, vista, fila) {
+                        let insert = insert_vista((nombre_vista, vista), &fila_vista);
+                        inserts.push((insert, token, base.ultima_escritura(fila)));
                     }
+                }
+            }
+        }
+        for (insert, hash, momento) in inserts {
+            let posicion = self.posicion_replica(&nombre_base, hash)?;
+            self.escribir_en_vista(&insert, posicion, momento)?;
+        }
+        Ok(())
+    }
 
-                    // En este caso al ser un DELETE también es necesario eliminar el dato en las réplicas
-                    // Ya que si no eso podría causar que se devuelva un dato que ya no existe
+    /// Método que ejecuta una sentencia DDL o un TRUNCATE: la aplica en este nodo y, si
+    /// cambió algo, devuelve pendiente su envío a los demás nodos del cluster para que
+    /// la apliquen también.
+    /// Las sentencias DDL se registran como mutaciones del schema, así que los nodos
+    /// que no las reciban ahora las obtienen luego al sincronizar su schema
+    fn ejecutar_ddl(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+    ) -> Result<Ejecucion, String> {
+        if !self.aplicar_ddl(consulta)? {
+            return Ok(Ejecucion::Terminada(None));
+        }
+        let mensaje = match consulta.get_type() {
+            TypeCQL::Truncate => {
+                serializar_envio_nodos(consulta.get_consulta_explicita(), consistencia, RESPONSABLE)
+            }
+            _ => {
+                let mutacion =
+                    MutacionSchema::new(ahora(), &self.ip, consulta.get_consulta_explicita());
+                self.registrar_mutacion_schema(mutacion.clone());
+                serializar_mutacion_schema(&mutacion)
+            }
+        };
+        let otros: Vec<String> = self
+            .nodos
+            .values()
+            .filter(|ip| **ip != self.ip)
+            .cloned()
+            .collect();
+        Ok(Ejecucion::Difusion(DifusionPendiente {
+            nodos: otros,
+            mensaje,
+            tipo: consulta.get_type().to_string(),
+        }))
+    }
 
-                    self.enviar_escrituras_replicas(consulta.clone(), consistencia, quorum)?;
-                } else {
-                    // Se le envía la consulta al nodo responsable.
+    // Registra una mutación que aplicó este nodo y anuncia por gossip la nueva versión
+    // del schema, aumentando la versión de su heartbeat para que se propague
+    fn registrar_mutacion_schema(&mut self, mutacion: MutacionSchema) {
+        if !self.historial_schema.registrar(mutacion) {
+            return;
+        }
+        let version = self.historial_schema.version();
+        if let Some(endpoint_data) = self.metadata_nodos.get_mut(&self.ip) {
+            endpoint_data.application_state.schema = version;
+            endpoint_data.heartbeat_state.version += 1;
+        }
+    }
+
+    /// Método que aplica una mutación del schema que coordinó otro nodo, si este todavía
+    /// no la aplicó. Queda registrada aunque falle, ya que la versión del schema depende
+    /// solo de las mutaciones registradas y así las de todos los nodos convergen
+    pub fn aplicar_mutacion_schema(&mut self, mutacion: MutacionSchema) -> Result<(), String> {
+        if self.historial_schema.contiene(&mutacion) {
+            return Ok(());
+        }
+        let resultado =
+            procesar_consulta(&mutacion.sentencia).and_then(|consulta| self.aplicar_ddl(&consulta));
+        self.registrar_mutacion_schema(mutacion);
+        resultado.map(|_| ())
+    }
 
-                    let nombre_servicio = obtener_nombre_servicio(ip_nodo_responsable.clone());
+    /// Método que indica si todos los nodos vivos anuncian por gossip la misma versión
+    /// del schema que este nodo
+    pub fn schema_acordado(&self) -> bool {
+        self.nodo_con_otro_schema().is_none()
+    }
 
-                    let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
+    // Algún nodo vivo que anuncia por gossip una versión del schema distinta de la propia
+    fn nodo_con_otro_schema(&self) -> Option<String> {
+        let version = self.historial_schema.version();
+        self.nodos
+            .values()
+            .find(|ip| {
+                self.metadata_nodos.get(*ip).is_some_and(|endpoint_data| {
+                    endpoint_data.application_state.status == NodeStatus::Normal
+                        && endpoint_data.application_state.schema != version
+                })
+            })
+            .cloned()
+    }
 
-                    if send_and_not_deserial(
-                        direccion,
-                        consulta_explicita,
-                        LevelConsistency::Strong,
-                        RESPONSABLE,
-                    )
-                    .is_err()
-                    {
-                        // Al igual que en el caso de una operación SELECT, si no pude conectarme al nodo responsable pero yo tampoco lo soy
-                        // entonces yo (el nodo coordinador) paso a ser un nuevo "nodo responsable" enviándole la consulta a las réplicas del
-                        // nodo responsable real y recibiendo las respuestas, luego actuar pero sin involucrar datos propios
-
-                        if consistencia == LevelConsistency::Weak {
-                            let replicas = get_replicas(
-                                &self.nodos,
-                                ip_nodo_responsable.to_string(),
-                                nivel_replicacion,
-                            );
-                            for ip_replica in replicas {
-                                let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
-
-                                let direccion =
-                                    format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                                if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica) {
-                                    if endpoint_data.application_state.status != NodeStatus::Normal
-                                    {
-                                        continue;
-                                    }
-                                    let deserialize_response = send_and_deserial(
-                                        direccion,
-                                        consulta_explicita,
-                                        LevelConsistency::Strong,
-                                        REPLICA,
-                                        tipo_consulta,
-                                    )?;
-                                    if deserialize_response[0] == "ACK" {
-                                        // Se cumple la consistencia
-                                        return Ok(None);
-                                    }
-                                }
-                            }
-                            return Err("No se cumplió la consistencia de la consulta".to_string());
-                        }
-                        let mut exitos = 0;
-                        let replicas = get_replicas(
-                            &self.nodos,
-                            ip_nodo_responsable.to_string(),
-                            nivel_replicacion,
-                        );
-                        for ip_replica in replicas {
-                            if self.ip == ip_replica {
-                                if let Some(tabla_elegida) = self.get_tabla(tabla_consulta)? {
-                                    tabla_elegida.eliminar(condicion);
-                                    self.timestamp += 1;
-                                    exitos += 1;
-                                    if exitos >= quorum {
-                                        break;
-                                    }
-                                }
-                            } else if let Some(endpoint_data) = self.metadata_nodos.get(&ip_replica)
-                            {
-                                if endpoint_data.application_state.status != NodeStatus::Normal {
-                                    continue;
-                                }
+    /// Método que, si algún nodo vivo anuncia por gossip una versión del schema distinta
+    /// de la de este, le pide su historial y aplica las mutaciones que le falten
+    pub fn sincronizar_schema(nodo: &Arc<Mutex<Self>>) {
+        let Some(ip) = nodo.lock().ok().and_then(|n| n.nodo_con_otro_schema()) else {
+            return;
+        };
+        // El pedido se hace sin mantener el bloqueo, ya que el otro nodo puede estar
+        // esperando a este para aplicar una mutación
+        let respuestas = enviar_a_replicas(
+            std::slice::from_ref(&ip),
+            &serializar_pedido_schema(),
+            timeout_lectura(),
+        );
+        let Some((_, respuesta)) = respuestas.first() else {
+            return;
+        };
+        let mutaciones = match mutaciones_respuesta_schema(respuesta) {
+            Ok(mutaciones) => mutaciones,
+            Err(e) => return println!("El nodo {} envió un schema inválido: {}", ip, e),
+        };
+        let Ok(mut nodo_lock) = nodo.lock() else {
+            return;
+        };
+        for mutacion in mutaciones {
+            if let Err(e) = nodo_lock.aplicar_mutacion_schema(mutacion) {
+                println!(
+                    "No se pudo aplicar una mutación del schema de {}: {}",
+                    ip, e
+                );
+            }
+        }
+    }
+
+    /// Método que aplica en este nodo una sentencia DDL o un TRUNCATE, persistiendo el
+    /// cambio en sus archivos y notificándolo a los clientes registrados. Devuelve si
+    /// hubo cambios, que no los hay si un IF [NOT] EXISTS indica que no corresponde
+    pub fn aplicar_ddl(&mut self, consulta: &Consulta) -> Result<bool, String> {
+        let aplicada = match consulta.get_sentencia() {
+            Sentencia::CreateKeyspace {
+                keyspace,
+                replicacion,
+                si_no_existe,
+            } => self.crear_keyspace(keyspace, replicacion, *si_no_existe)?,
+            Sentencia::AlterKeyspace {
+                keyspace,
+                replicacion,
+            } => self.alterar_keyspace(keyspace, replicacion)?,
+            Sentencia::Drop {
+                tipo: TypeCQL::DropKeyspace,
+                nombre,
+                si_existe,
+            } => self.eliminar_keyspace(nombre, *si_existe)?,
+            Sentencia::CreateTable { si_no_existe, .. } => {
+                self.crear_tabla(consulta, *si_no_existe)?
+            }
+            Sentencia::AlterTable { tabla, cambio } => self.alterar_tabla(tabla, cambio)?,
+            Sentencia::CreateType {
+                tipo,
+                campos,
+                si_no_existe,
+            } => self.crear_tipo(tipo, campos, *si_no_existe)?,
+            Sentencia::Drop {
+                tipo: TypeCQL::DropType,
+                nombre,
+                si_existe,
+            } => self.eliminar_tipo(nombre, *si_existe)?,
+            sentencia @ Sentencia::CreateView { .. } => self.crear_vista(sentencia)?,
+            Sentencia::Drop {
+                tipo: TypeCQL::DropView,
+                nombre,
+                si_existe,
+            } => self.eliminar_vista(nombre, *si_existe)?,
+            Sentencia::Drop {
+                nombre, si_existe, ..
+            } => self.eliminar_tabla(nombre, *si_existe)?,
+            Sentencia::Truncate { tabla } => self.truncar_tabla(tabla)?,
+            _ => return Err(format!("{} no es una sentencia DDL.", consulta.get_type())),
+        };
+        if aplicada {
+            self.timestamp += 1;
+        }
+        Ok(aplicada)
+    }
+
+    fn crear_keyspace(
+        &mut self,
+        keyspace: &str,
+        replicacion: &[(Termino, Termino)],
+        si_no_existe: bool,
+    ) -> Result<bool, String> {
+        if self.keyspaces.contains_key(keyspace) {
+            let error = invalida(&format!("el keyspace {} ya existe", keyspace));
+            return sin_cambios(si_no_existe, error);
+        }
+        let (strategy, replication_factor) = replicacion_keyspace(replicacion)?;
+        let k = Keyspace::new(keyspace.to_string(), replication_factor, strategy);
+        self.keyspaces.insert(keyspace.to_string(), k);
+        let _ = fs::create_dir(format!("bdd/src/{}", keyspace));
+        self.actualizar_replicas();
+        self.notificar_schema(CambioSchema::Created, keyspace, None);
+        Ok(true)
+    }
+
+    fn alterar_keyspace(
+        &mut self,
+        nombre: &str,
+        replicacion: &[(Termino, Termino)],
+    ) -> Result<bool, String> {
+        if es_keyspace_sistema(nombre) {
+            return Err(invalida(&format!(
+                "no se puede modificar el keyspace {}",
+                nombre
+            )));
+        }
+        let (strategy, replication_factor) = replicacion_keyspace(replicacion)?;
+        let keyspace = self
+            .keyspaces
+            .get_mut(nombre)
+            .ok_or_else(|| invalida(&format!("el keyspace {} no existe", nombre)))?;
+        keyspace.nivel_replicacion = replication_factor;
+        keyspace.strategy = strategy;
+        self.actualizar_replicas();
+        self.notificar_schema(CambioSchema::Updated, nombre, None);
+        Ok(true)
+    }
+
+    // Elimina el keyspace con sus tablas y los archivos de este nodo. El directorio
+    // del keyspace lo borra el último nodo en eliminar sus archivos
+    fn eliminar_keyspace(&mut self, nombre: &str, si_existe: bool) -> Result<bool, String> {
+        if nombre == KEYSPACE_AUTH || es_keyspace_sistema(nombre) {
+            return Err(invalida(&format!(
+                "no se puede eliminar el keyspace {}",
+                nombre
+            )));
+        }
+        let Some(keyspace) = self.keyspaces.remove(nombre) else {
+            let error = invalida(&format!("el keyspace {} no existe", nombre));
+            return sin_cambios(si_existe, error);
+        };
+        for tabla in keyspace.tablas.keys() {
+            let _ = fs::remove_file(self.ruta_tabla(&format!("{}.{}", nombre, tabla)));
+        }
+        let _ = fs::remove_dir(format!("bdd/src/{}", nombre));
+        self.actualizar_replicas();
+        self.notificar_schema(CambioSchema::Dropped, nombre, None);
+        Ok(true)
+    }
 
-                                let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
+    fn crear_tabla(&mut self, consulta: &Consulta, si_no_existe: bool) -> Result<bool, String> {
+        let (nombre_keyspace, tabla) = self.separar_keyspace(consulta.get_tabla());
+        let keyspace = self
+            .keyspaces
+            .get_mut(&nombre_keyspace)
+            .ok_or_else(|| invalida(&format!("el keyspace {} no existe", nombre_keyspace)))?;
+        let headers = obtener_headers_table(consulta.get_query());
+        let tipos = obtener_tipos_table(consulta.get_query(), &headers, &keyspace.udts())?;
+        let clustering = consulta.get_sentencia().clustering();
+        // Las columnas de la clave no pueden cambiar elemento a elemento
+        let coleccion_en_clave = headers.iter().zip(&tipos).any(|(header, tipo)| {
+            (*header == headers[0] || clustering.contains(header)) && tipo.mezcla_elementos()
+        });
+        if coleccion_en_clave {
+            return Err(invalida(
+                "las columnas de la clave primaria no pueden ser colecciones ni tipos sin frozen",
+            ));
+        }
+        if keyspace.tablas.contains_key(tabla) {
+            let error = invalida(&format!("la tabla {} ya existe", consulta.get_tabla()));
+            return sin_cambios(si_no_existe, error);
+        }
+        let tabla_nueva =
+            Tabla::new(tabla.to_string(), headers, tipos).con_clustering(clustering.to_vec());
+        keyspace.tablas.insert(tabla.to_string(), tabla_nueva);
+        self.persistir_tabla_nueva(self.ruta_tabla(consulta.get_tabla()));
+        self.notificar_schema(CambioSchema::Created, &nombre_keyspace, Some(tabla));
+        Ok(true)
+    }
+
+    // Las vistas materializadas no se modifican, y mientras una tabla tenga vistas no se
+    // le pueden quitar ni renombrar columnas
+    fn alterar_tabla(&mut self, nombre: &str, cambio: &CambioTabla) -> Result<bool, String> {
+        if self.tabla_base(nombre).is_some() {
+            return Err(invalida(&format!(
+                "no se puede modificar la vista materializada {}",
+                nombre
+            )));
+        }
+        if !matches!(cambio, CambioTabla::Agregar(_)) && !self.vistas_de(nombre).is_empty() {
+            return Err(invalida(&format!(
+                "no se pueden quitar ni renombrar columnas de la tabla {} mientras tenga vistas materializadas",
+                nombre
+            )));
+        }
+        let (keyspace, tabla) = self.separar_keyspace(nombre);
+        let udts = self
+            .keyspaces
+            .get(&keyspace)
+            .map(Keyspace::udts)
+            .unwrap_or_default();
+        self.get_tabla(nombre)?
+            .ok_or_else(|| invalida(&format!("la tabla {} no existe", nombre)))?
+            .alterar(cambio, &udts)?;
+        self.persistir_update(nombre.to_string())?;
+        self.notificar_schema(CambioSchema::Updated, &keyspace, Some(tabla));
+        Ok(true)
+    }
+
+    // Las vistas materializadas se eliminan con DROP MATERIALIZED VIEW, y antes que su
+    // tabla base
+    fn eliminar_tabla(&mut self, nombre: &str, si_existe: bool) -> Result<bool, String> {
+        if self.tabla_base(nombre).is_some() {
+            return Err(invalida(&format!(
+                "{} es una vista materializada, se elimina con DROP MATERIALIZED VIEW",
+                nombre
+            )));
+        }
+        if let Some(vista) = self.vistas_de(nombre).first() {
+            return Err(invalida(&format!(
+                "no se puede eliminar la tabla {} mientras tenga la vista materializada {}",
+                nombre, vista
+            )));
+        }
+        self.quitar_tabla(nombre, si_existe)
+    }
+
+    fn quitar_tabla(&mut self, nombre: &str, si_existe: bool) -> Result<bool, String> {
+        let (keyspace, tabla) = self.separar_keyspace(nombre);
+        let eliminada = self
+            .keyspaces
+            .get_mut(&keyspace)
+            .and_then(|keyspace| keyspace.tablas.remove(tabla));
+        if eliminada.is_none() {
+            let error = invalida(&format!("la tabla {} no existe", nombre));
+            return sin_cambios(si_existe, error);
+        }
+        let _ = fs::remove_file(self.ruta_tabla(nombre));
+        self.notificar_schema(CambioSchema::Dropped, &keyspace, Some(tabla));
+        Ok(true)
+    }
+
+    // La vista es una tabla más del keyspace de su tabla base. Las filas que ya tenía la
+    // tabla base se le agregan cuando todos los nodos tengan la vista en su schema
+    fn crear_vista(&mut self, sentencia: &Sentencia) -> Result<bool, String> {
+        let Sentencia::CreateView {
+            vista,
+            tabla_base,
+            columnas,
+            clave_particion,
+            clustering,
+            si_no_existe,
+        } = sentencia
+        else {
+            return Err(format!(
+                "{} no es un CREATE MATERIALIZED VIEW.",
+                sentencia.tipo()
+            ));
+        };
+        let (nombre_keyspace, nombre_vista) = self.separar_keyspace(vista);
+        let (keyspace_base, nombre_base) = self.separar_keyspace(tabla_base);
+        if keyspace_base != nombre_keyspace {
+            return Err(invalida(
+                "la vista materializada tiene que estar en el keyspace de su tabla base",
+            ));
+        }
+        let keyspace = self
+            .keyspaces
+            .get_mut(&nombre_keyspace)
+            .ok_or_else(|| invalida(&format!("el keyspace {} no existe", nombre_keyspace)))?;
+        if keyspace.tablas.contains_key(nombre_vista) {
+            let error = invalida(&format!("la tabla {} ya existe", vista));
+            return sin_cambios(*si_no_existe, error);
+        }
+        let base = keyspace
+            .tablas
+            .get(nombre_base)
+            .ok_or_else(|| invalida(&format!("la tabla {} no existe", tabla_base)))?;
+        let clave = (clave_particion.as_slice(), clustering.as_slice());
+        let nueva = nueva_vista(base, nombre_vista, columnas, clave)?;
+        keyspace.tablas.insert(nombre_vista.to_string(), nueva);
+        self.persistir_tabla_nueva(self.ruta_tabla(vista));
+        self.vistas_pendientes.push(vista.to_string());
+        self.notificar_schema(CambioSchema::Created, &nombre_keyspace, Some(nombre_vista));
+        Ok(true)
+    }
+
+    fn eliminar_vista(&mut self, nombre: &str, si_existe: bool) -> Result<bool, String> {
+        if self.buscar_tabla(nombre).is_some() && self.tabla_base(nombre).is_none() {
+            return Err(invalida(&format!(
+                "{} no es una vista materializada, se elimina con DROP TABLE",
+                nombre
+            )));
+        }
+        self.vistas_pendientes.retain(|vista| vista != nombre);
+        self.quitar_tabla(nombre, si_existe)
+    }
+
+    // Los campos del tipo pueden usar los tipos del mismo keyspace creados antes, siempre
+    // con frozen, pero no colecciones de ningún tipo
+    fn crear_tipo(
+        &mut self,
+        nombre: &str,
+        campos: &[DefinicionColumna],
+        si_no_existe: bool,
+    ) -> Result<bool, String> {
+        let (nombre_keyspace, nombre_tipo) = self.separar_keyspace(nombre);
+        let keyspace = self
+            .keyspaces
+            .get_mut(&nombre_keyspace)
+            .ok_or_else(|| invalida(&format!("el keyspace {} no existe", nombre_keyspace)))?;
+        if keyspace.tipos.contains_key(nombre_tipo) {
+            let error = invalida(&format!("el tipo {} ya existe", nombre));
+            return sin_cambios(si_no_existe, error);
+        }
+        let udts = keyspace.udts();
+        let mut campos_tipo = Vec::new();
+        for campo in campos {
+            let tipo = TipoColumna::create_con_udts(&campo.tipo, &udts)?;
+            if tipo.mezcla_elementos() || tipo == TipoColumna::Counter {
+                return Err(invalida(&format!(
+                    "el campo {} del tipo {} no puede ser de tipo {}",
+                    campo.nombre, nombre, tipo
+                )));
+            }
+            campos_tipo.push((campo.nombre.clone(), tipo));
+        }
+        let tipo = TipoUsuario {
+            keyspace: nombre_keyspace.clone(),
+            nombre: nombre_tipo.to_string(),
+            campos: campos_tipo,
+        };
+        keyspace.tipos.insert(nombre_tipo.to_string(), tipo);
+        self.notificar_schema(CambioSchema::Updated, &nombre_keyspace, None);
+        Ok(true)
+    }
+
+    // No se puede eliminar un tipo mientras lo use alguna tabla u otro tipo del keyspace
+    fn eliminar_tipo(&mut self, nombre: &str, si_existe: bool) -> Result<bool, String> {
+        let (nombre_keyspace, nombre_tipo) = self.separar_keyspace(nombre);
+        let Some(keyspace) = self.keyspaces.get_mut(&nombre_keyspace) else {
+            let error = invalida(&format!("el keyspace {} no existe", nombre_keyspace));
+            return sin_cambios(si_existe, error);
+        };
+        if !keyspace.tipos.contains_key(nombre_tipo) {
+            let error = invalida(&format!("el tipo {} no existe", nombre));
+            return sin_cambios(si_existe, error);
+        }
+        let en_tablas = keyspace
+            .tablas
+            .values()
+            .flat_map(|tabla| tabla.tipos.iter());
+        let en_tipos = keyspace
+            .tipos
+            .values()
+            .flat_map(|tipo| tipo.campos.iter().map(|(_, tipo)| tipo));
+        if en_tablas
+            .chain(en_tipos)
+            .any(|tipo| usa_tipo(tipo, nombre_tipo))
+        {
+            return Err(invalida(&format!("el tipo {} está en uso", nombre)));
+        }
+        keyspace.tipos.remove(nombre_tipo);
+        self.notificar_schema(CambioSchema::Updated, &nombre_keyspace, None);
+        Ok(true)
+    }
+
+    // Vacía también las vistas materializadas de la tabla, que no se truncan solas
+    fn truncar_tabla(&mut self, nombre: &str) -> Result<bool, String> {
+        if self.tabla_base(nombre).is_some() {
+            return Err(invalida(&format!(
+                "no se puede truncar la vista materializada {}",
+                nombre
+            )));
+        }
+        for tabla in [vec![nombre.to_string()], self.vistas_de(nombre)].concat() {
+            self.get_tabla(&tabla)?
+                .ok_or_else(|| invalida(&format!("la tabla {} no existe", tabla)))?
+                .vaciar();
+            self.persistir_tabla_nueva(self.ruta_tabla(&tabla));
+        }
+        Ok(true)
+    }
+
+    // Busca una tabla, o una vista, sin modificarla
+    fn buscar_tabla(&self, nombre: &str) -> Option<&Tabla> {
+        let (keyspace, tabla) = self.separar_keyspace(nombre);
+        self.keyspaces.get(&keyspace)?.tablas.get(tabla)
+    }
+
+    // Tabla base, calificada con su keyspace, si la tabla es una vista materializada
+    fn tabla_base(&self, nombre: &str) -> Option<String> {
+        let (keyspace, _) = self.separar_keyspace(nombre);
+        let base = self.buscar_tabla(nombre)?.base.as_ref()?;
+        Some(format!("{}.{}", keyspace, base))
+    }
+
+    // Vistas materializadas de la tabla, calificadas con su keyspace
+    fn vistas_de(&self, nombre: &str) -> Vec<String> {
+        let (nombre_keyspace, tabla) = self.separar_keyspace(nombre);
+        let Some(keyspace) = self.keyspaces.get(&nombre_keyspace) else {
+            return vec![];
+        };
+        let mut vistas: Vec<String> = keyspace
+            .tablas
+            .values()
+            .filter(|vista| vista.base.as_deref() == Some(tabla))
+            .map(|vista| format!("{}.{}", nombre_keyspace, vista.nombre))
+            .collect();
+        vistas.sort();
+        vistas
+    }
+
+    // Nivel de replicación del keyspace de la tabla, 1 si no existe
+    fn nivel_replicacion(&self, nombre_tabla: &str) -> usize {
+        let (keyspace, _) = self.separar_keyspace(nombre_tabla);
+        self.keyspaces
+            .get(&keyspace)
+            .map_or(1, |keyspace| keyspace.nivel_replicacion)
+    }
+
+    /// Método que ejecuta un CREATE ROLE, ALTER ROLE o DROP ROLE traduciéndolo a la
+    /// escritura correspondiente en system_auth.roles, que se replica como cualquier otra
+    fn ejecutar_consulta_rol(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+    ) -> Result<Option<Vec<String>>, String> {
+        let nombre = consulta.get_tabla();
+        if nombre.is_empty() || !nombre.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(format!("Nombre de rol inválido: {}", nombre));
+        }
+        let opciones = OpcionesRol::desde_consulta(consulta)?;
+        let existe = self.obtener_rol(nombre)?.is_some();
+        let consulta_cql = match consulta.get_type() {
+            TypeCQL::CreateRole if existe && opciones.si_corresponde => return Ok(None),
+            TypeCQL::CreateRole if existe => return Err(format!("El rol {} ya existe.", nombre)),
+            TypeCQL::CreateRole => consulta_insert_rol(nombre, &opciones)?,
+            _ if !existe && opciones.si_corresponde => return Ok(None),
+            _ if !existe => return Err(format!("El rol {} no existe.", nombre)),
+            TypeCQL::AlterRole => match consulta_update_rol(nombre, &opciones)? {
+                Some(consulta_update) => consulta_update,
+                None => return Ok(None),
+            },
+            _ => {
+                let mut delete_permisos =
+                    procesar_consulta(&consulta_delete_permisos(nombre, None))?;
+                self.execute_query(&mut delete_permisos, consistencia.clone())?;
+                consulta_delete_rol(nombre)
+            }
+        };
+        let resultado = self.execute_query(&mut procesar_consulta(&consulta_cql)?, consistencia);
+        // Las sesiones del rol se autorizan con el rol cambiado desde su próxima consulta
+        self.cache_permisos.invalidar(nombre);
+        resultado
+    }
+
+    /// Método que ejecuta un GRANT o REVOKE sumando o quitando permisos en la fila
+    /// del rol y el recurso en system_auth.role_permissions, o un LIST PERMISSIONS
+    fn ejecutar_consulta_permisos(
+        &mut self,
+        consulta: &Consulta,
+        consistencia: LevelConsistency,
+    ) -> Result<Option<Vec<String>>, String> {
+        let sentencia =
+            SentenciaPermisos::desde_consulta(consulta, self.keyspace_sesion(consulta))?;
+        if consulta.get_type() == &TypeCQL::ListPermissions {
+            return self.listar_permisos(&sentencia).map(Some);
+        }
+        let (Some(rol), Some(recurso)) = (sentencia.rol, sentencia.recurso) else {
+            return Err(format!(
+                "Falta el rol o el recurso del {}.",
+                consulta.get_type()
+            ));
+        };
+        if self.obtener_rol(&rol)?.is_none() {
+            return Err(format!("El rol {} no existe.", rol));
+        }
+        self.verificar_recurso(&recurso)?;
+        let actuales = self
+            .permisos_de_rol(Some(&rol))?
+            .into_iter()
+            .find(|permisos| permisos.recurso == recurso);
+        let existia = actuales.is_some();
+        let mut nuevos = actuales.unwrap_or(PermisosRecurso {
+            rol,
+            recurso,
+            permisos: vec![],
+        });
+        if consulta.get_type() == &TypeCQL::Grant {
+            nuevos.permisos.extend(sentencia.permisos);
+        } else {
+            nuevos
+                .permisos
+                .retain(|permiso| !sentencia.permisos.contains(permiso));
+        }
+        let consulta_cql = match (existia, nuevos.permisos.is_empty()) {
+            (false, true) => return Ok(None),
+            (false, false) => consulta_insert_permisos(&nuevos),
+            (true, true) => consulta_delete_permisos(&nuevos.rol, Some(&nuevos.recurso)),
+            (true, false) => consulta_update_permisos(&nuevos),
+        };
+        let resultado = self.execute_query(&mut procesar_consulta(&consulta_cql)?, consistencia);
+        // Las sesiones del rol se autorizan con los permisos nuevos desde su próxima consulta
+        self.cache_permisos.invalidar(&nuevos.rol);
+        resultado
+    }
 
-                                let direccion =
-                                    format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                                let deserialize_response = send_and_deserial(
-                                    direccion,
-                                    consulta_explicita,
-                                    LevelConsistency::Strong,
-                                    REPLICA,
-                                    tipo_consulta,
-                                )?;
-                                if deserialize_response[0] == "ACK" {
-                                    exitos += 1;
-                                    if exitos >= quorum {
-                                        // Se cumple la consistencia
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                        if exitos < quorum {
-                            return Err("No se cumplió la consistencia de la consulta".to_string());
-                        }
-                    }
+    /// Método que arma las filas (rol, recurso, permiso) de un LIST PERMISSIONS. Si
+    /// se indica un recurso se listan también los permisos heredados de los que lo contienen
+    fn listar_permisos(&mut self, sentencia: &SentenciaPermisos) -> Result<Vec<String>, String> {
+        let jerarquia = sentencia.recurso.as_ref().map(Recurso::jerarquia);
+        let mut filas = vec![];
+        for permisos in self.permisos_de_rol(sentencia.rol.as_deref())? {
+            if jerarquia
+                .as_ref()
+                .is_some_and(|jerarquia| !jerarquia.contains(&permisos.recurso))
+            {
+                continue;
+            }
+            for permiso in permisos.permisos {
+                if sentencia.permisos.contains(&permiso) {
+                    filas.push(format!(
+                        "{},{},{}",
+                        permisos.rol,
+                        permisos.recurso.nombre(),
+                        permiso.nombre()
+                    ));
                 }
-                Ok(None)
             }
-            TypeCQL::CreateTable => self.create_table(consulta, consistencia),
-            TypeCQL::CreateKeyspace => self.create_keyspace(consulta, consistencia),
         }
+        Ok(filas)
     }
 
-    fn create_table(
-        &mut self,
-        consulta: &mut Consulta,
-        consistencia: LevelConsistency,
-    ) -> Result<Option<Vec<String>>, String> {
-        let headers = obtener_headers_table(consulta.get_query());
-        let tabla = consulta.get_tabla();
-        let tabla_nueva = Tabla::new(tabla.to_string(), headers.clone());
+    /// Método que verifica que exista el keyspace o la tabla sobre la que se otorgan permisos
+    fn verificar_recurso(&mut self, recurso: &Recurso) -> Result<(), String> {
+        let existe = match recurso {
+            Recurso::TodosLosKeyspaces => true,
+            Recurso::Keyspace(keyspace) => self.keyspaces.contains_key(keyspace),
+            Recurso::Tabla(keyspace, tabla) => self
+                .get_tabla(&format!("{}.{}", keyspace, tabla))?
+                .is_some(),
+        };
+        if existe {
+            return Ok(());
+        }
+        Err(format!("El recurso {} no existe.", recurso.nombre()))
+    }
 
-        let keyspace = self.get_key()?;
-        keyspace.tablas.insert(tabla.to_string(), tabla_nueva);
-        let path = format!("bdd/src/{}/{}_{}.csv", self.keyspace_actual, tabla, self.ip);
-        self.persistir_tabla_nueva(path);
-        for (_, ip) in self.nodos.iter() {
-            if &self.ip != ip {
-                let nombre_servicio = obtener_nombre_servicio(ip.clone());
+    /// Método que busca en system_auth.role_permissions los permisos de un rol,
+    /// o los de todos los roles si no se indica ninguno
+    fn permisos_de_rol(&mut self, rol: Option<&str>) -> Result<Vec<PermisosRecurso>, String> {
+        let mut consulta = procesar_consulta(&consulta_select_permisos(rol))?;
+        let filas = self.execute_query(&mut consulta, LevelConsistency::One)?;
+        filas
+            .unwrap_or_default()
+            .iter()
+            .map(|fila| PermisosRecurso::desde_fila(fila))
+            .collect()
+    }
 
-                let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                send_and_deserial(
-                    direccion,
-                    consulta.get_consulta_explicita(),
-                    LevelConsistency::create(consistencia.valor()),
-                    RESPONSABLE,
-                    &TypeCQL::CreateTable,
-                )?;
+    /// Método que busca un rol en system_auth.roles. El rol por defecto se lee con
+    /// consistencia fuerte y el resto con consistencia débil, como en Cassandra
+    pub fn obtener_rol(&mut self, nombre: &str) -> Result<Option<Rol>, String> {
+        let consistencia = match nombre {
+            ROL_POR_DEFECTO => LevelConsistency::Quorum,
+            _ => LevelConsistency::One,
+        };
+        let mut consulta = procesar_consulta(&consulta_select_rol(nombre))?;
+        let filas = self.execute_query(&mut consulta, consistencia)?;
+        match filas.unwrap_or_default().first() {
+            Some(fila) => Rol::desde_fila(fila).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Método que verifica las credenciales de un cliente contra system_auth.roles,
+    /// el rol tiene que existir, poder loguearse y tener esa contraseña
+    pub fn autenticar(&mut self, credenciales: &Credenciales) -> Result<Rol, String> {
+        match self.obtener_rol(&credenciales.usuario)? {
+            Some(rol) if rol.puede_loguearse && rol.verificar_password(&credenciales.password) => {
+                Ok(rol)
+            }
+            _ => Err("Usuario o contraseña incorrectos.".to_string()),
+        }
+    }
+
+    /// Método que verifica que el rol de la sesión que envió la consulta pueda ejecutarla.
+    /// Las consultas sin rol (sin autenticación, o armadas por el propio nodo) no se
+    /// verifican y los superusuarios pueden ejecutar cualquiera
+    pub fn autorizar(&mut self, consulta: &Consulta) -> Result<(), String> {
+        let Some(usuario) = consulta.get_rol() else {
+            return Ok(());
+        };
+        let (rol, permisos) = self.rol_y_permisos(usuario)?;
+        if rol.is_some_and(|rol| rol.es_superusuario) {
+            return Ok(());
+        }
+        let autorizado = match consulta.get_type() {
+            // Sin ser superusuario solo se puede cambiar la contraseña propia
+            TypeCQL::AlterRole => {
+                consulta.get_tabla() == usuario
+                    && OpcionesRol::desde_consulta(consulta).is_ok_and(|opciones| {
+                        opciones.login.is_none() && opciones.superuser.is_none()
+                    })
+            }
+            TypeCQL::CreateRole | TypeCQL::DropRole => false,
+            // Sin ser superusuario solo se pueden listar los permisos propios
+            TypeCQL::ListPermissions => consulta.get_tabla() == usuario,
+            // Cualquier rol puede consultar el schema, como hacen los drivers al conectarse
+            TypeCQL::Describe => true,
+            TypeCQL::Select
+                if es_keyspace_sistema(&self.separar_keyspace(consulta.get_tabla()).0) =>
+            {
+                true
+            }
+            // Los roles y permisos solo se modifican con sus sentencias, escribir directo
+            // en system_auth permitiría, por ejemplo, hacerse superusuario
+            TypeCQL::Insert
+            | TypeCQL::Update
+            | TypeCQL::Delete
+            | TypeCQL::Truncate
+            | TypeCQL::AlterTable
+            | TypeCQL::DropTable
+                if self.separar_keyspace(consulta.get_tabla()).0 == KEYSPACE_AUTH =>
+            {
+                false
+            }
+            _ => {
+                let (permiso, recurso) = self.permiso_requerido(consulta)?;
+                tiene_permiso(&permisos, &permiso, &recurso)
             }
+        };
+        if autorizado {
+            return Ok(());
         }
-        Ok(None)
+        Err(format!(
+            "{}: el rol {} no tiene permiso para ejecutar {}.",
+            NO_AUTORIZADO,
+            usuario,
+            consulta.get_type()
+        ))
     }
 
-    fn create_keyspace(
+    /// Método que devuelve el rol y sus permisos con los que se autorizan sus consultas:
+    /// los de la cache si siguen valiendo o, si no, los que se leen de system_auth
+    fn rol_y_permisos(
         &mut self,
-        consulta: &mut Consulta,
-        consistencia: LevelConsistency,
-    ) -> Result<Option<Vec<String>>, String> {
-        let (strategy, replication_factor) =
-            obtener_tipo_strategy_y_replication(consulta.get_query());
-        if strategy != "SimpleStrategy" {
-            return Err("No pudo crearse el keyspace correctamente.".to_string());
-        }
-        let tabla = consulta.get_tabla();
-        let k = Keyspace::new(tabla.to_string(), replication_factor, strategy);
-        self.keyspaces.insert(tabla.to_string(), k);
-        let path = format!("bdd/src/{}", tabla);
-
-        let _ = fs::create_dir(path);
-        for (_, ip) in self.nodos.iter() {
-            if &self.ip != ip {
-                let nombre_servicio = obtener_nombre_servicio(ip.clone());
+        nombre: &str,
+    ) -> Result<(Option<Rol>, Vec<PermisosRecurso>), String> {
+        if let Some(cacheados) = self.cache_permisos.obtener(nombre) {
+            return Ok(cacheados);
+        }
+        let rol = self.obtener_rol(nombre)?;
+        let permisos = self.permisos_de_rol(Some(nombre))?;
+        self.cache_permisos
+            .guardar(nombre, rol.clone(), permisos.clone());
+        Ok((rol, permisos))
+    }
 
-                let direccion = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                send_and_deserial(
-                    direccion,
-                    consulta.get_consulta_explicita(),
-                    LevelConsistency::create(consistencia.valor()),
-                    RESPONSABLE,
-                    &TypeCQL::CreateKeyspace,
-                )?;
+    /// Método que verifica que el rol pueda leer la tabla, como si ejecutara un SELECT.
+    /// Se usa para las suscripciones a sus mutaciones, que envían los valores de las filas
+    pub fn autorizar_lectura(
+        &mut self,
+        nombre_tabla: &str,
+        rol: Option<&String>,
+    ) -> Result<(), String> {
+        let consulta =
+            procesar_consulta(&format!("SELECT * FROM {}", nombre_tabla))?.con_rol(rol.cloned());
+        self.autorizar(&consulta)
+    }
+
+    /// Método que devuelve el permiso que necesita una consulta y el recurso sobre el que lo necesita
+    fn permiso_requerido(&self, consulta: &Consulta) -> Result<(Permiso, Recurso), String> {
+        let keyspace = self.keyspace_sesion(consulta);
+        let tabla = Recurso::de_tabla(consulta.get_tabla(), keyspace);
+        // Los permisos de una vista materializada son los de su tabla base
+        let base = match consulta.get_sentencia().tabla_base() {
+            Some(base) => Recurso::de_tabla(base, keyspace),
+            None => match self.tabla_base(consulta.get_tabla()) {
+                Some(base) => Recurso::de_tabla(&base, keyspace),
+                None => tabla.clone(),
+            },
+        };
+        match consulta.get_type() {
+            TypeCQL::Select => Ok((Permiso::Select, base)),
+            TypeCQL::CreateView | TypeCQL::DropView => Ok((Permiso::Alter, base)),
+            TypeCQL::CreateTable => match tabla {
+                Recurso::Tabla(keyspace, _) => Ok((Permiso::Create, Recurso::Keyspace(keyspace))),
+                _ => Ok((Permiso::Create, Recurso::TodosLosKeyspaces)),
+            },
+            TypeCQL::CreateKeyspace => Ok((Permiso::Create, Recurso::TodosLosKeyspaces)),
+            TypeCQL::AlterTable => Ok((Permiso::Alter, tabla)),
+            TypeCQL::DropTable => Ok((Permiso::Drop, tabla)),
+            TypeCQL::AlterKeyspace => Ok((
+                Permiso::Alter,
+                Recurso::Keyspace(consulta.get_tabla().to_string()),
+            )),
+            TypeCQL::DropKeyspace => Ok((
+                Permiso::Drop,
+                Recurso::Keyspace(consulta.get_tabla().to_string()),
+            )),
+            // Los tipos pertenecen al keyspace, no tienen permisos propios
+            TypeCQL::CreateType | TypeCQL::DropType => {
+                let permiso = match consulta.get_type() {
+                    TypeCQL::CreateType => Permiso::Create,
+                    _ => Permiso::Drop,
+                };
+                match tabla {
+                    Recurso::Tabla(keyspace, _) => Ok((permiso, Recurso::Keyspace(keyspace))),
+                    _ => Ok((permiso, Recurso::TodosLosKeyspaces)),
+                }
+            }
+            TypeCQL::Grant | TypeCQL::Revoke => {
+                let sentencia = SentenciaPermisos::desde_consulta(consulta, keyspace)?;
+                let recurso = sentencia
+                    .recurso
+                    .ok_or(format!("Falta el recurso del {}.", consulta.get_type()))?;
+                Ok((Permiso::Authorize, recurso))
             }
+            _ => Ok((Permiso::Modify, tabla)),
+        }
+    }
+
+    /// Método que devuelve el keyspace de las tablas sin calificar de una consulta: el
+    /// elegido con USE en la sesión que la envió o, si no eligió ninguno, el del nodo
+    fn keyspace_sesion<'a>(&'a self, consulta: &'a Consulta) -> &'a str {
+        consulta.get_keyspace().unwrap_or(&self.keyspace_actual)
+    }
+
+    /// Método que carga el keyspace system_auth con las tablas de roles y de permisos
+    /// del nodo. Si el nodo todavía no tenía la de roles la crea con el rol por defecto
+    /// cassandra/cassandra, cada nodo lo hace por su cuenta para que exista antes de
+    /// que se arme el cluster
+    fn cargar_system_auth(&mut self) -> Result<(), String> {
+        let keyspace = Keyspace::new(KEYSPACE_AUTH.to_string(), 3, "SimpleStrategy".to_string());
+        self.keyspaces.insert(KEYSPACE_AUTH.to_string(), keyspace);
+        fs::create_dir_all(format!("bdd/src/{}", KEYSPACE_AUTH))
+            .map_err(|_| "No se pudo crear el keyspace system_auth.".to_string())?;
+        self.cargar_tabla_auth(TABLA_PERMISOS, crear_tabla_permisos()?);
+        self.cargar_tabla_auth(TABLA_ROLES, crear_tabla_roles()?);
+        Ok(())
+    }
+
+    /// Método que crea el rol por defecto cassandra/cassandra si no hay ningún rol en
+    /// todo el cluster: ni el rol por defecto, que se lee con quorum, ni ningún otro en
+    /// la tabla de roles. Se escribe con el momento 0, así si varios nodos lo crean a la
+    /// vez cualquier cambio posterior del rol gana, como en Cassandra. Devuelve Err si no
+    /// se pudo leer o escribir con quorum
+    pub fn crear_rol_por_defecto(&mut self) -> Result<(), String> {
+        if self.obtener_rol(ROL_POR_DEFECTO)?.is_some() {
+            return Ok(());
+        }
+        let mut consulta = procesar_consulta(&consulta_algun_rol())?;
+        let roles = self.execute_query(&mut consulta, LevelConsistency::Quorum)?;
+        if !roles.unwrap_or_default().is_empty() {
+            return Ok(());
+        }
+        let opciones = OpcionesRol {
+            password: Some(ROL_POR_DEFECTO.to_string()),
+            superuser: Some(true),
+            ..Default::default()
+        };
+        let mut insert =
+            procesar_consulta(&consulta_insert_rol(ROL_POR_DEFECTO, &opciones)?)?.con_momento(0)?;
+        self.execute_query(&mut insert, LevelConsistency::Quorum)?;
+        Ok(())
+    }
+
+    /// Método que carga una tabla de system_auth con las filas de su archivo, creándolo
+    /// si no existía
+    fn cargar_tabla_auth(&mut self, nombre: &str, mut tabla: Tabla) {
+        let ruta = self.ruta_tabla(nombre);
+        for fila in get_data(ruta.to_string()) {
+            tabla.cargar(fila);
+        }
+        if !Path::new(&ruta).exists() {
+            self.persistir_tabla_nueva(ruta);
+        }
+        if let Some(keyspace) = self.keyspaces.get_mut(KEYSPACE_AUTH) {
+            keyspace.tablas.insert(tabla.nombre.to_string(), tabla);
         }
-        Ok(None)
     }
 
     /// Método que persiste una fila nueva que se haya insertado en una tabla
     /// en el archivo específico del nodo
     pub fn persistir_insert(&self, tabla: String, row: String) {
-        let path = format!("bdd/src/{}/{}_{}.csv", self.keyspace_actual, tabla, self.ip);
+        let path = self.ruta_tabla(&tabla);
         let fila = row.clone() + "\n";
 
         if let Ok(contenido) = std::fs::read_to_string(&path) {
@@ -902,18 +2294,15 @@ impl Nodo {
     /// Método que persiste la actualización que se hizo en una o más filas de una tabla
     /// en el archivo específico del nodo
     pub fn persistir_update(&mut self, tabla: String) -> Result<(), String> {
-        let path = format!("bdd/src/{}/{}_{}.csv", self.keyspace_actual, tabla, self.ip);
+        let path = self.ruta_tabla(&tabla);
+        let (keyspace, _) = self.separar_keyspace(&tabla);
 
-        let path_temporal = format!(
-            "bdd/src/{}/archivo_update_{}.csv",
-            self.keyspace_actual, self.ip
-        );
+        let path_temporal = format!("bdd/src/{}/archivo_update_{}.csv", keyspace, self.ip);
         let archivo_temporal =
             File::create(&path_temporal).map_err(|_| "No se pudo persistir el update.")?;
         let mut escritor = io::BufWriter::new(&archivo_temporal);
 
-        let keyspace = self.get_key()?;
-        if let Some(tabla) = keyspace.tablas.get_mut(&tabla) {
+        if let Some(tabla) = self.get_tabla(&tabla)? {
             for datos in tabla.datos.values() {
                 for linea in datos {
                     let nueva_linea = format!("{}\n", linea);
@@ -929,18 +2318,15 @@ impl Nodo {
     /// Método que persiste la eliminación de una o más filas de una tabla
     /// en el archivo específico del nodo
     pub fn persistir_delete(&mut self, tabla: String) -> Result<(), String> {
-        let path = format!("bdd/src/{}/{}_{}.csv", self.keyspace_actual, tabla, self.ip);
+        let path = self.ruta_tabla(&tabla);
+        let (keyspace, _) = self.separar_keyspace(&tabla);
 
-        let path_delete = format!(
-            "bdd/src/{}/path_delete_{}.csv",
-            self.keyspace_actual, self.ip
-        );
+        let path_delete = format!("bdd/src/{}/path_delete_{}.csv", keyspace, self.ip);
         let archivo_temporal =
             File::create(&path_delete).map_err(|_| "No se pudo persistir el delete.")?;
         let mut escritor = io::BufWriter::new(archivo_temporal);
 
-        let keyspace = self.get_key()?;
-        if let Some(tabla) = keyspace.tablas.get_mut(&tabla) {
+        if let Some(tabla) = self.get_tabla(&tabla)? {
             for datos in tabla.datos.values() {
                 for linea in datos {
                     let nueva_linea = format!("{}\n", linea);
@@ -953,6 +2339,44 @@ impl Nodo {
         Ok(())
     }
 
+    /// Devuelve las réplicas vivas de la partition key con el hash indicado, empezando
+    /// por el nodo responsable, o un error Unavailable si no alcanzan las `requeridas`
+    fn replicas_vivas(
+        &self,
+        hash: u32,
+        nivel_replicacion: usize,
+        requeridas: usize,
+    ) -> Result<Vec<String>, String> {
+        let vivas: Vec<String> = self
+            .replicas_de(hash, nivel_replicacion)?
+            .into_iter()
+            .filter(|ip| self.esta_vivo(ip))
+            .collect();
+        if vivas.len() < requeridas {
+            return Err(no_disponible(requeridas, vivas.len()));
+        }
+        Ok(vivas)
+    }
+
+    // Réplicas de la partition key con el hash indicado, empezando por el nodo responsable
+    fn replicas_de(&self, hash: u32, nivel_replicacion: usize) -> Result<Vec<String>, String> {
+        let ip_nodo_responsable = self
+            .get_nodo_responsable(hash)
+            .ok_or_else(|| "No se ha encontrado el nodo responsable para la key.".to_string())?;
+        let mut replicas =
+            get_replicas(&self.nodos, ip_nodo_responsable.clone(), nivel_replicacion);
+        replicas.insert(0, ip_nodo_responsable);
+        Ok(replicas)
+    }
+
+    // Según el gossip, salvo este nodo que siempre está vivo
+    fn esta_vivo(&self, ip: &str) -> bool {
+        ip == self.ip
+            || self.metadata_nodos.get(ip).is_some_and(|endpoint_data| {
+                endpoint_data.application_state.status == NodeStatus::Normal
+            })
+    }
+
     /// Método que persiste la creación de una tabla nueva en el archivo específico del nodo,
     /// se recibe la ruta en que estará el archivo asociado con esa tabla
     pub fn persistir_tabla_nueva(&self, path: String) {
@@ -963,219 +2387,6 @@ impl Nodo {
             .open(path);
     }
 
-    fn enviar_escrituras_replicas(
-        &self,
-        consulta: Consulta,
-        consistencia: LevelConsistency,
-        quorum: usize,
-    ) -> Result<(), String> {
-        let (tx, rx): TxRx = mpsc::channel();
-
-        for ip_replica in &self.replicas {
-            println!("Enviando a réplica: {}", ip_replica);
-            let config = Arc::new(create_client_config()?);
-            let server_name = ServerName::try_from("localhost")
-                .map_err(|_| "Nombre de dominio inválido.".to_string())?;
-            let ip_replica = ip_replica.to_string();
-            let consulta = consulta.clone();
-            let nivel_consistencia = consistencia.clone();
-            let tx = tx.clone();
-
-            let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
-
-            thread::spawn(move || {
-                let direccion_replica = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-
-                let resultado = match TcpStream::connect(direccion_replica) {
-                    Ok(socket) => {
-                        let client_conn =
-                            ClientConnection::new(Arc::clone(&config), server_name).unwrap();
-                        let mut tls_stream = StreamOwned::new(client_conn, socket);
-                        let query_serializada = serializar_envio_nodos(
-                            consulta.get_consulta_explicita(),
-                            nivel_consistencia.clone(),
-                            REPLICA,
-                        );
-                        if tls_stream.write_all(&query_serializada).is_ok() {
-                            // Leer respuesta (ACK)
-                            if let Ok(respuesta) = deserializar_respuesta_nodos(&mut tls_stream) {
-                                if respuesta.contains(&"ACK".to_string()) {
-                                    Ok(())
-                                } else {
-                                    Err("No se recibió ACK".to_string())
-                                }
-                            } else {
-                                Err("Error al recibir la respuesta".to_string())
-                            }
-                        } else {
-                            Err("Error al enviar la consulta".to_string())
-                        }
-                    }
-                    Err(_) => Err("No se pudo conectar con la réplica".to_string()),
-                };
-                // Enviar el resultado al hilo principal
-                let _ = tx.send(resultado);
-            });
-        }
-        drop(tx); // Cerramos el sender para indicar que no habrá más envíos
-
-        let mut ack_count = 0;
-        let required_acks = match consistencia {
-            LevelConsistency::Weak => 1,
-            LevelConsistency::Strong => quorum - 1,
-        };
-
-        //Recibir resultados a medida que lleguen
-        for resultado in rx {
-            if resultado.is_ok() {
-                ack_count += 1;
-                if ack_count >= required_acks {
-                    println!(
-                        "Se han obtenido más de {} ACKs: Se cumple el Consistency Level",
-                        required_acks
-                    );
-                    // Alcanzamos el número requerido de ACKs
-                    println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - -");
-                    return Ok(());
-                }
-            }
-        }
-        // Si salimos del bucle, es que no alcanzamos el número requerido de ACKs
-        Err("No se alcanzó el nivel de consistencia requerido".to_string())
-    }
-
-    fn enviar_lecturas_replicas(
-        &mut self,
-        consulta: Consulta,
-        consistencia: LevelConsistency,
-        datos_responsable: Vec<String>,
-        nombre_tabla: &str, //nombre de la tabla de la consulta
-        quorum: usize,
-    ) -> Result<Vec<String>, String> {
-        let (tx, rx): TxRx2 = mpsc::channel();
-        let _ = io::stdout().flush();
-
-        for ip_replica in &self.replicas {
-            let server_name = ServerName::try_from("localhost")
-                .map_err(|_| "Nombre de dominio inválido.".to_string())?;
-            let ip_replica = ip_replica.clone();
-            let consulta = consulta.clone();
-
-            let nombre_servicio = obtener_nombre_servicio(ip_replica.clone());
-
-            let tx = tx.clone();
-            let nivel_consistencia = consistencia.clone();
-            thread::spawn(move || {
-                let config = Arc::new(create_client_config().unwrap());
-                let direccion_replica = format!("{}:{}", &nombre_servicio, PUERTO_INTERNODOS);
-                let direccion_replica2 = format!("{}:{}", &nombre_servicio, PUERTO_INTERNODOS);
-
-                let resultado = match TcpStream::connect(direccion_replica) {
-                    Ok(socket) => {
-                        let client_conn = ClientConnection::new(Arc::clone(&config), server_name)
-                            .map_err(|_| "Error al crear la conexión TLS del cliente.")
-                            .unwrap();
-                        let mut tls_stream = StreamOwned::new(client_conn, socket);
-                        let query_serializada = serializar_envio_nodos(
-                            consulta.get_consulta_explicita(),
-                            nivel_consistencia.clone(), // Usamos el nivel requerido
-                            REPLICA,
-                        );
-                        if tls_stream.write_all(&query_serializada).is_ok() {
-                            if let Ok(respuesta) = deserializar_respuesta_nodos(&mut tls_stream) {
-                                // Suponiendo que la respuesta es (datos, timestamp)
-                                let timestamp = respuesta.last().unwrap().clone(); // Obtener timestamp
-                                let datos = respuesta[..respuesta.len() - 1].to_vec();
-                                Ok((datos, timestamp, direccion_replica2))
-                            } else {
-                                Err("Error al recibir la respuesta".to_string())
-                            }
-                        } else {
-                            Err("Error al enviar la consulta".to_string())
-                        }
-                    }
-                    Err(_) => Err("No se pudo conectar con la réplica".to_string()),
-                };
-                let _ = tx.send(resultado);
-            });
-        }
-
-        drop(tx); // Cerramos el sender
-
-        let mut respuestas_replicas: Vec<(Vec<String>, String, String)> = Vec::new();
-        let mut exitos = 0;
-        let required_responses = match consistencia {
-            LevelConsistency::Weak => 1,
-            LevelConsistency::Strong => quorum - 1,
-        };
-
-        for resultado in rx.into_iter().flatten() {
-            let datos = resultado.0;
-            let timestamp = resultado.1;
-            let direccion_replica = resultado.2;
-            exitos += 1;
-            respuestas_replicas.push((datos.clone(), timestamp.clone(), direccion_replica.clone()));
-            if exitos >= required_responses {
-                break;
-            }
-        }
-        if respuestas_replicas.is_empty() {
-            return Err("No se pudo obtener el dato de ninguna réplica".to_string());
-        }
-        // Procesar las respuestas y realizar read repair si es necesario
-        let dato_mas_reciente =
-            self.realizar_read_repair(respuestas_replicas, datos_responsable, nombre_tabla)?;
-
-        Ok(dato_mas_reciente)
-    }
-
-    fn realizar_read_repair(
-        &mut self,
-        respuestas: Vec<(Vec<String>, String, String)>,
-        datos_responsable: Vec<String>,
-        tabla: &str,
-    ) -> Result<Vec<String>, String> {
-        // respuestas: Vec<(datos, timestamp, direccion_replica)>
-        // Mapear los datos por timestamp
-        let mut mapa_respuestas: HashMap<String, (Vec<String>, String)> = HashMap::new();
-
-        for (datos, timestamp, direccion_replica) in respuestas {
-            mapa_respuestas
-                .entry(timestamp)
-                .or_insert((Vec::new(), direccion_replica))
-                .0
-                .extend(datos.into_iter());
-        }
-
-        let nombre_servicio = obtener_nombre_servicio(self.ip.clone());
-
-        let direccion_responsable = format!("{}:{}", &nombre_servicio, PUERTO_INTERNODOS);
-        mapa_respuestas
-            .entry(self.timestamp.to_string())
-            .or_insert((Vec::new(), direccion_responsable))
-            .0
-            .extend(datos_responsable);
-        // Obtener el timestamp más alto
-        if let Some((timestamp_mas_reciente, datos_mas_recientes)) =
-            mapa_respuestas.iter().max_by_key(|entry| entry.0)
-        {
-            let dato_mas_reciente = &datos_mas_recientes;
-
-            // Caso de diferentes timestamps entre las réplicas
-            if mapa_respuestas.len() > 1 {
-                // Hay discrepancias, realizar read repair
-                self.read_repair(
-                    &mapa_respuestas,
-                    dato_mas_reciente,
-                    timestamp_mas_reciente,
-                    tabla,
-                )?;
-            }
-            return Ok(dato_mas_reciente.0.clone());
-        }
-        Err("No se encontraron datos en las respuestas".to_string())
-    }
-
     fn read_repair(
         &mut self,
         mapa_respuestas: &HashMap<String, (Vec<String>, String)>,
@@ -1192,29 +2403,36 @@ impl Nodo {
 
                 let direccion_responsable = format!("{}:{}", &nombre_servicio, PUERTO_INTERNODOS);
 
-                let keyspace = self.get_key()?;
-                let headers = keyspace.tablas.get(tabla).unwrap().headers.clone();
-                let tabla_cloned = tabla.to_string();
+                let nombre_tabla = self.nombre_calificado(tabla);
+                let tabla_reparada = self
+                    .get_tabla(tabla)?
+                    .ok_or(format!("No existe la tabla {}.", nombre_tabla))?;
                 let (dato_para_actualizar, _) = dato_mas_reciente.clone();
+                let consultas_update = dato_para_actualizar
+                    .iter()
+                    .map(|linea| {
+                        construir_update_todos_los_campos(&nombre_tabla, tabla_reparada, linea)
+                    })
+                    .collect::<Vec<String>>();
                 if *direccion != direccion_responsable {
                     thread::spawn(move || {
                         println!("Se realiza un read repair en la réplica {} del nodo responsable actual", direccion);
-                        let config = Arc::new(create_client_config().unwrap());
-                        let server_name = ServerName::try_from("localhost").unwrap();
+                        let (Ok(config), Ok(server_name)) =
+                            (config_cliente_internodos(), nombre_servidor(&direccion))
+                        else {
+                            return;
+                        };
                         if let Ok(socket) = TcpStream::connect(direccion) {
-                            let client_conn =
-                                ClientConnection::new(Arc::clone(&config), server_name).unwrap();
+                            let Ok(client_conn) =
+                                ClientConnection::new(Arc::clone(&config), server_name)
+                            else {
+                                return;
+                            };
                             let mut tls_stream = StreamOwned::new(client_conn, socket);
-                            for linea in dato_para_actualizar.iter() {
-                                let linea = linea.split(",").collect::<Vec<&str>>();
-                                let consulta_update = construir_update_todos_los_campos(
-                                    &tabla_cloned,
-                                    &headers,
-                                    linea,
-                                );
+                            for consulta_update in consultas_update.iter() {
                                 let query_serializada = serializar_envio_nodos(
-                                    &consulta_update,
-                                    LevelConsistency::Strong,
+                                    consulta_update,
+                                    LevelConsistency::Quorum,
                                     REPLICA,
                                 );
                                 let _ = tls_stream.write_all(&query_serializada);
@@ -1227,15 +2445,15 @@ impl Nodo {
                         "Se realiza un read repair en el nodo responsable actual {}",
                         direccion
                     );
-                    for linea in dato_para_actualizar.iter() {
-                        let linea = linea.split(",").collect::<Vec<&str>>();
-                        let consulta_update =
-                            construir_update_todos_los_campos(&tabla_cloned, &headers, linea);
-
-                        if let Ok(consulta) = procesar_consulta(&consulta_update) {
-                            if let Some(tabla_elegida) = self.get_tabla(tabla)? {
-                                tabla_elegida
-                                    .actualizar(consulta.get_where(), consulta_update.to_string());
+                    for consulta_update in consultas_update.iter() {
+                        if let Ok(consulta) = procesar_consulta(consulta_update) {
+                            if self.get_tabla(tabla)?.is_some() {
+                                self.update_en_tabla(
+                                    tabla.to_string(),
+                                    consulta.get_where(),
+                                    consulta.get_sentencia().asignaciones(),
+                                    ahora(),
+                                );
                                 self.persistir_update(tabla.to_string())?;
                                 self.timestamp += 1;
                             }
@@ -1251,6 +2469,7 @@ impl Nodo {
     /// con nadie pero progresivamente con los nodos que vaya conociendo
     pub fn iniciar_gossip(nodo: Arc<Mutex<Self>>) {
         let seeds = get_seeds();
+        let mut rol_por_defecto = false;
         thread::spawn(move || loop {
             println!("- - - - Inicia ronda de Gossip - - - -");
             let mut conectados: Vec<String> = vec![];
@@ -1296,8 +2515,18 @@ impl Nodo {
                 }
                 conectados.push(servicio);
             }
+            Self::sincronizar_schema(&nodo);
+            nodo.lock().unwrap().construir_vistas_pendientes();
             nodo.lock().unwrap().update_heartbeat();
             nodo.lock().unwrap().gossip_recientes.clear();
+            // El rol por defecto se crea cuando ya se conoce al resto del cluster, y se
+            // reintenta en la próxima ronda si todavía no hay quorum
+            if !rol_por_defecto {
+                match nodo.lock().unwrap().crear_rol_por_defecto() {
+                    Ok(()) => rol_por_defecto = true,
+                    Err(e) => eprintln!("No se pudo crear el rol por defecto: {}", e),
+                }
+            }
             println!("Réplicas del nodo: {:?}", nodo.lock().unwrap().replicas);
             thread::sleep(Duration::from_secs(5));
         });
@@ -1316,11 +2545,9 @@ impl Nodo {
         sending_ip: String,
     ) -> Result<(), String> {
         println!("Nodos del árbol: {:?}", nodo.lock().unwrap().nodos.values());
-        let config = Arc::new(create_client_config()?);
-        let server_name = ServerName::try_from("localhost")
-            .map_err(|_| "Nombre de dominio inválido.".to_string())?;
-
+        let config = config_cliente_internodos()?;
         let nombre_servicio = obtener_nombre_servicio(random_ip.clone());
+        let server_name = nombre_servidor(&nombre_servicio)?;
 
         let address = format!("{}:{}", nombre_servicio, PUERTO_GOSSIP);
         // Preparar el mensaje SYN sin mantener el bloqueo
@@ -1345,13 +2572,12 @@ impl Nodo {
                 .map_err(|_| "Error al crear la conexión TLS del cliente.")?;
             let mut tls_stream = StreamOwned::new(client_conn, socket);
             if let Ok(mut nodo_lock) = nodo.lock() {
-                if nodo_lock.metadata_nodos.contains_key(random_ip) {
-                    if let Some(endpoint_data) = nodo_lock.metadata_nodos.get_mut(random_ip) {
-                        if endpoint_data.application_state.status == NodeStatus::Down {
-                            println!("Marcando como reconectado un nodo que se había caido.");
-                            endpoint_data.application_state.status = NodeStatus::Normal;
-                            endpoint_data.heartbeat_state.version += 1;
-                        }
+                if let Some(endpoint_data) = nodo_lock.metadata_nodos.get_mut(random_ip) {
+                    if endpoint_data.application_state.status == NodeStatus::Down {
+                        println!("Marcando como reconectado un nodo que se había caido.");
+                        endpoint_data.application_state.status = NodeStatus::Normal;
+                        endpoint_data.heartbeat_state.version += 1;
+                        nodo_lock.notificar_estado_nodo(random_ip, &NodeStatus::Normal);
                     }
                 }
             }
@@ -1383,13 +2609,7 @@ impl Nodo {
                     let partes = desactualizado.split(":").collect::<Vec<&str>>();
                     let ip = partes[0];
                     if let Some(endpoint_data) = nodo_lock.metadata_nodos.get(ip) {
-                        ack2.push_str(&format!(
-                            "{}:{}:{}:{} ",
-                            ip,
-                            endpoint_data.heartbeat_state.generation,
-                            endpoint_data.heartbeat_state.version,
-                            endpoint_data.application_state.status
-                        ));
+                        ack2.push_str(&format!("{} ", endpoint_data.estado_gossip(ip)));
                     }
                 }
             }
@@ -1409,6 +2629,7 @@ impl Nodo {
                         .map_err(|_| "Error al parsear version".to_string())?;
                     let estado_str = partes[3];
                     let estado = NodeStatus::create(estado_str);
+                    let schema = schema_gossip(&partes)?;
                     let mut nodo_lock = nodo.lock().unwrap();
 
                     let hash_result = hashear(ip)?;
@@ -1417,44 +2638,26 @@ impl Nodo {
                     if let Some(endpoint_data) = nodo_lock.metadata_nodos.get_mut(ip) {
                         endpoint_data.heartbeat_state.generation = generacion;
                         endpoint_data.heartbeat_state.version = ver;
+                        endpoint_data.application_state.schema = schema;
 
                         let delete_ip = endpoint_data.application_state.status
                             == NodeStatus::Normal
                             && estado == NodeStatus::Down;
+                        let cambio_estado = endpoint_data.application_state.status != estado;
                         endpoint_data.application_state.status = estado.clone();
                         if nodo_lock.ip == ip {
                             continue;
                         }
+                        if cambio_estado {
+                            nodo_lock.notificar_estado_nodo(ip, &estado);
+                        }
                         if delete_ip {
                             //Hay que eliminar el nodo de la lista de nodos que ya tengo en mi metadata (nodo que envía)
                             println!("Marcando al nodo {} como caído y eliminandolo de la lista de nodos", ip);
                             let hash_result = hashear(ip)?;
                             nodo_lock.nodos.remove(&hash_result);
 
-                            if let Some(keyspace) =
-                                nodo_lock.keyspaces.get(&nodo_lock.keyspace_actual)
-                            {
-                                let tablas: Vec<String> = keyspace.tablas.keys().cloned().collect();
-                                let _ = keyspace;
-
-                                for tabla in tablas {
-                                    if tabla == "AEROPUERTOS" {
-                                        continue;
-                                    }
-
-                                    let path = format!(
-                                        "bdd/src/{}/{}_{}.csv",
-                                        nodo_lock.keyspace_actual, tabla, ip
-                                    );
-                                    if fs::metadata(&path).is_ok() {
-                                        OpenOptions::new()
-                                            .write(true)
-                                            .truncate(true)
-                                            .open(&path)
-                                            .map_err(|e| format!("Error al abrir el archivo: {}", e))?;
-                                    }
-                                }
-                            }
+                            nodo_lock.vaciar_archivos_nodo(ip)?;
 
                             //nodo_lock.metadata_nodos.remove(ip);
                             nodo_lock.actualizar_replicas();
@@ -1465,45 +2668,26 @@ impl Nodo {
                         continue;
                     }
 
-                    let endpoint = EndpointData::new(generacion, ver, estado);
+                    let endpoint = EndpointData::new(generacion, ver, estado).con_schema(schema);
                     nodo_lock.metadata_nodos.insert(ip.to_string(), endpoint);
                     let hash_result = hashear(ip)?;
                     nodo_lock.nodos.insert(hash_result, ip.to_string());
                     nodo_lock.actualizar_replicas();
-                    let keyspace_path = format!("bdd/src/{}", nodo_lock.keyspace_actual);
-                    if fs::metadata(keyspace_path).is_ok() {
-                        if let Some(keyspace) = nodo_lock.keyspaces.get(&nodo_lock.keyspace_actual)
-                        {
-                            for tabla in keyspace.tablas.keys() {
-                                let path = format!(
-                                    "bdd/src/{}/{}_{}.csv",
-                                    nodo_lock.keyspace_actual, tabla, ip
-                                );
-                                let ruta = Path::new(&path);
-                                if ruta.exists() {
-                                    continue;
-                                } else {
-                                    let archivo = File::create(&path);
-                                    if archivo.is_err() {
-                                        return Err(format!("Error al crear archivo: {}", path));
-                                    }
-                                }
-                            }
-                            let (ip_distribuidor, token_distribuidor) =
-                                obtener_distribuidor(&nodo_lock, ip.to_string());
-                            println!(
-                                "El nodo de IP {} debe distribuir los datos al nuevo nodo",
-                                ip_distribuidor
-                            );
-                            redistribuir(
-                                &mut nodo_lock,
-                                ip.to_string(),
-                                &ip_distribuidor.to_string(),
-                                token_distribuidor.to_string(),
-                            )?;
-                            println!("Reorganización completada");
-                        }
-                    }
+                    nodo_lock.notificar_nodo_nuevo(ip);
+                    nodo_lock.crear_archivos_nodo(ip)?;
+                    let (ip_distribuidor, token_distribuidor) =
+                        obtener_distribuidor(&nodo_lock, ip.to_string());
+                    println!(
+                        "El nodo de IP {} debe distribuir los datos al nuevo nodo",
+                        ip_distribuidor
+                    );
+                    redistribuir(
+                        &mut nodo_lock,
+                        ip.to_string(),
+                        &ip_distribuidor.to_string(),
+                        token_distribuidor.to_string(),
+                    )?;
+                    println!("Reorganización completada");
                 }
             }
 
@@ -1529,6 +2713,7 @@ impl Nodo {
                         );
                         endpoint_data.application_state.status = NodeStatus::Down;
                         endpoint_data.heartbeat_state.version += 1;
+                        nodo_lock.notificar_estado_nodo(random_ip, &NodeStatus::Down);
                     }
                     let hash_result = hashear(random_ip)?;
                     nodo_lock.nodos.remove(&hash_result);
@@ -1567,6 +2752,10 @@ impl Nodo {
                 // ---> Solo se ejecuta la lógica si el elemento es un directorio, en este caso el keyspace
                 if let Some(nombre) = ruta.file_name() {
                     let name = nombre.to_string_lossy().to_string();
+                    if name == KEYSPACE_AUTH {
+                        // Su única tabla, la de roles, se carga en cargar_system_auth
+                        continue;
+                    }
                     let mut k = Keyspace::new(name.to_string(), 3, "SimpleStrategy".to_string());
                     let tablas =
                         load_tablas(format!("{}/{}", path_bdd, name), self.ip.to_string())?;
@@ -1578,6 +2767,14 @@ impl Nodo {
         Ok(())
     }
 
+    /// Devuelve los keyspaces cuyas tablas se reparten entre los nodos y se guardan en
+    /// sus archivos: todos menos los de sistema, que cada nodo arma con su estado
+    pub fn keyspaces_replicados(&self) -> impl Iterator<Item = (&String, &Keyspace)> {
+        self.keyspaces
+            .iter()
+            .filter(|(nombre, _)| !es_keyspace_sistema(nombre))
+    }
+
     pub fn get_key(&mut self) -> Result<&mut Keyspace, String> {
         let keyspace = self
             .keyspaces
@@ -1586,21 +2783,160 @@ impl Nodo {
         Ok(keyspace)
     }
 
+    /// Busca una tabla por su nombre, que puede estar calificado con su keyspace
+    /// ("keyspace.tabla"). Si no lo está se la busca en el keyspace actual
     pub fn get_tabla(&mut self, tabla_buscada: &str) -> Result<Option<&mut Tabla>, String> {
-        let keyspace = self.get_key()?;
-        if let Some(tabla_encontrada) = keyspace.tablas.get_mut(tabla_buscada) {
-            return Ok(Some(tabla_encontrada));
+        let (keyspace, tabla_buscada) = self.separar_keyspace(tabla_buscada);
+        let keyspace = self
+            .keyspaces
+            .get_mut(&keyspace)
+            .ok_or("Keyspace no encontrado.")?;
+        Ok(keyspace.tablas.get_mut(tabla_buscada))
+    }
+
+    /// Separa el nombre de una tabla en su keyspace y el nombre de la tabla en
+    /// sí, si no está calificado con "keyspace.tabla" se usa el keyspace actual
+    pub fn separar_keyspace<'a>(&self, tabla: &'a str) -> (String, &'a str) {
+        match tabla.split_once('.') {
+            Some((keyspace, tabla)) => (keyspace.to_string(), tabla),
+            None => (self.keyspace_actual.to_string(), tabla),
+        }
+    }
+
+    /// Devuelve el nombre de la tabla calificado con su keyspace
+    pub fn nombre_calificado(&self, tabla: &str) -> String {
+        let (keyspace, tabla) = self.separar_keyspace(tabla);
+        format!("{}.{}", keyspace, tabla)
+    }
+
+    /// Devuelve la ruta del archivo en el que el nodo persiste la tabla
+    fn ruta_tabla(&self, tabla: &str) -> String {
+        let (keyspace, tabla) = self.separar_keyspace(tabla);
+        format!("bdd/src/{}/{}_{}.csv", keyspace, tabla, self.ip)
+    }
+
+    /// Método que arma la metadata (keyspace, tabla, nombre y tipo de cada columna)
+    /// del resultado de una consulta SELECT
+    pub fn metadata_consulta(&mut self, consulta: &Consulta) -> Result<MetadataRows, String> {
+        if consulta.get_type() == &TypeCQL::ListPermissions {
+            let (keyspace, tabla) = self.separar_keyspace(TABLA_PERMISOS);
+            return Ok(MetadataRows::new(
+                &keyspace,
+                tabla,
+                columnas_list_permissions(),
+            ));
+        }
+        if let Sentencia::Describe { descripcion } = consulta.get_sentencia() {
+            let con_definicion = matches!(
+                descripcion,
+                Descripcion::Keyspace(_) | Descripcion::Tabla(_)
+            );
+            return Ok(MetadataRows::new(
+                KEYSPACE_SYSTEM_SCHEMA,
+                "describe",
+                columnas_describe(con_definicion),
+            ));
+        }
+        let (keyspace, _) = self.separar_keyspace(consulta.get_tabla());
+        let tabla = self
+            .get_tabla(consulta.get_tabla())?
+            .ok_or(format!("La tabla {} no existe.", consulta.get_tabla()))?;
+        if consulta.get_condicion_lwt().is_some() {
+            let mut columnas = vec![ColumnaSpec {
+                nombre: "[applied]".to_string(),
+                tipo: TipoColumna::Boolean,
+            }];
+            columnas.extend(tabla.columnas_seleccionadas(&Seleccion::default()));
+            return Ok(MetadataRows::new(&keyspace, &tabla.nombre, columnas));
+        }
+        let columnas = tabla.columnas_seleccionadas(&consulta.get_seleccion());
+        Ok(MetadataRows::new(&keyspace, &tabla.nombre, columnas))
+    }
+
+    /// Método que envía un evento a los clientes registrados a su tipo
+    pub fn notificar_evento(&mut self, evento: Evento) {
+        self.suscriptores.notificar(evento);
+    }
+
+    /// Método que notifica a los clientes registrados que un nodo del cluster
+    /// pasó a estar disponible (UP) o caído (DOWN)
+    pub fn notificar_estado_nodo(&mut self, ip: &str, estado: &NodeStatus) {
+        let cambio = match estado {
+            NodeStatus::Normal => CambioEstado::Up,
+            NodeStatus::Down => CambioEstado::Down,
+            NodeStatus::Bootstrap => return,
+        };
+        if let Some(direccion) = direccion_cliente(ip) {
+            self.notificar_evento(Evento::StatusChange { cambio, direccion });
+        }
+    }
+
+    /// Método que notifica a los clientes registrados que se sumó un nodo al cluster
+    pub fn notificar_nodo_nuevo(&mut self, ip: &str) {
+        if let Some(direccion) = direccion_cliente(ip) {
+            self.notificar_evento(Evento::TopologyChange {
+                cambio: CambioTopologia::NewNode,
+                direccion,
+            });
         }
-        Ok(None)
     }
 
+    /// Método que notifica a los clientes registrados que se creó, modificó o eliminó
+    /// un keyspace o, si se indica la tabla, una tabla dentro del keyspace
+    pub fn notificar_schema(&mut self, cambio: CambioSchema, keyspace: &str, tabla: Option<&str>) {
+        self.notificar_evento(Evento::SchemaChange {
+            cambio,
+            keyspace: keyspace.to_string(),
+            tabla: tabla.map(|t| t.to_string()),
+        });
+    }
+
+    /// Recalcula las réplicas del nodo en cada keyspace, con el factor de replicación
+    /// de ese keyspace
     pub fn actualizar_replicas(&mut self) {
-        let mut nivel_replicacion = 3;
-        if let Some(keyspace) = self.keyspaces.get(&self.keyspace_actual) {
-            nivel_replicacion = keyspace.nivel_replicacion;
+        self.replicas = self
+            .keyspaces_replicados()
+            .map(|(nombre, keyspace)| {
+                let replicas =
+                    get_replicas(&self.nodos, self.ip.to_string(), keyspace.nivel_replicacion);
+                (nombre.to_string(), replicas)
+            })
+            .collect();
+    }
+
+    /// Vacía los archivos en los que este nodo guarda los datos del nodo que se cayó,
+    /// en las tablas de todos los keyspaces replicados
+    pub fn vaciar_archivos_nodo(&self, ip: &str) -> Result<(), String> {
+        for (nombre_keyspace, keyspace) in self.keyspaces_replicados() {
+            for tabla in keyspace.tablas.keys() {
+                let path = format!("bdd/src/{}/{}_{}.csv", nombre_keyspace, tabla, ip);
+                if fs::metadata(&path).is_ok() {
+                    OpenOptions::new()
+                        .write(true)
+                        .truncate(true)
+                        .open(&path)
+                        .map_err(|e| format!("Error al abrir el archivo: {}", e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Crea los archivos de las tablas de todos los keyspaces replicados para el nodo
+    /// que se unió al cluster, si todavía no existen
+    pub fn crear_archivos_nodo(&self, ip: &str) -> Result<(), String> {
+        for (nombre_keyspace, keyspace) in self.keyspaces_replicados() {
+            if fs::metadata(format!("bdd/src/{}", nombre_keyspace)).is_err() {
+                continue;
+            }
+            for tabla in keyspace.tablas.keys() {
+                let path = format!("bdd/src/{}/{}_{}.csv", nombre_keyspace, tabla, ip);
+                if !Path::new(&path).exists() && File::create(&path).is_err() {
+                    return Err(format!("Error al crear archivo: {}", path));
+                }
+            }
         }
-        let nuevas_replicas = get_replicas(&self.nodos, self.ip.to_string(), nivel_replicacion);
-        self.replicas = nuevas_replicas;
+        Ok(())
     }
 }
 
@@ -1625,82 +2961,76 @@ fn redistribuir(
     ip_redistribuidor: &String,
     token_nuevo: String,
 ) -> Result<(), String> {
-    let keyspace = nodo
-        .keyspaces
-        .get(&nodo.keyspace_actual)
-        .ok_or_else(String::new)?;
-    for (nombre_tabla, tabla) in keyspace.tablas.iter() {
-        for (partition_key, datos) in tabla.datos.iter() {
-            println!("Se redistribuirán los datos de la tabla {}", nombre_tabla);
-            let hash_result = hashear(partition_key)?;
-            if let Ok(token_u32) = token_nuevo.parse::<u32>() {
-                if hash_result <= token_u32 {
-                    for linea in datos.iter() {
-                        let linea = linea.split(",").collect::<Vec<&str>>();
-                        let columnas = tabla.headers.join(", ");
-                        let valores_formateados = linea.join(", ");
-
-                        let consulta = format!(
-                            "INSERT INTO {} ({}) VALUES ({})",
-                            nombre_tabla, columnas, valores_formateados
-                        );
-
-                        let consulta_insert = procesar_consulta(&consulta).unwrap();
-
-                        let nombre_servicio = obtener_nombre_servicio(ip_nuevo.clone());
-
-                        let address_nodo_responsable =
-                            format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
-                        let _ = send_and_not_deserial(
-                            address_nodo_responsable,
-                            consulta_insert.get_consulta_explicita(),
-                            LevelConsistency::Strong,
-                            REPLICA,
-                        );
-
-                        // eliminamos los datos nuestros y de mis réplicas
-                        let mut consulta_delete = format!(
-                            "DELETE FROM {} WHERE {} = {} AND ID_VUELO = {}",
-                            nombre_tabla, tabla.headers[0], linea[0], linea[2]
-                        );
-                        println!("Consulta delete: {}", consulta_delete);
-                        if nombre_tabla.contains("AEROPUERTOS") {
-                            consulta_delete = format!(
-                                "DELETE FROM {} WHERE ID_AEROPUERTO = {} AND NOMBRE = {}",
-                                nombre_tabla, linea[0], linea[1]
+    for (nombre_keyspace, keyspace) in nodo.keyspaces_replicados() {
+        for (nombre_tabla, tabla) in keyspace.tablas.iter() {
+            // Las vistas se actualizan en el nodo nuevo con las escrituras de su tabla base
+            if tabla.base.is_some() {
+                continue;
+            }
+            let nombre_tabla = format!("{}.{}", nombre_keyspace, nombre_tabla);
+            for (partition_key, datos) in tabla.datos.iter() {
+                println!("Se redistribuirán los datos de la tabla {}", nombre_tabla);
+                let hash_result = token_particion(partition_key)?;
+                if let Ok(token_u32) = token_nuevo.parse::<u32>() {
+                    if hash_result <= token_u32 {
+                        for linea in datos.iter() {
+                            let consulta = insert_vista((&nombre_tabla, tabla), linea);
+
+                            let consulta_insert = procesar_consulta(&consulta)?;
+
+                            let nombre_servicio = obtener_nombre_servicio(ip_nuevo.clone());
+
+                            let address_nodo_responsable =
+                                format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
+                            let _ = send_and_not_deserial(
+                                address_nodo_responsable,
+                                consulta_insert.get_consulta_explicita(),
+                                LevelConsistency::Quorum,
+                                REPLICA,
                             );
-                        }
-                        if nodo.replicas.is_empty() {
-                            continue;
-                        }
 
-                        let mut nivel_replicacion = 3;
-                        if let Some(keyspace) = nodo.keyspaces.get(&nodo.keyspace_actual) {
-                            nivel_replicacion = keyspace.nivel_replicacion;
-                        }
+                            // eliminamos los datos nuestros y de mis réplicas
+                            let consulta_delete = format!(
+                                "DELETE FROM {} WHERE {}",
+                                nombre_tabla,
+                                tabla.condicion_clave(linea)
+                            );
+                            println!("Consulta delete: {}", consulta_delete);
+                            if nodo
+                                .replicas
+                                .get(nombre_keyspace)
+                                .is_none_or(|replicas| replicas.is_empty())
+                            {
+                                continue;
+                            }
 
-                        let consulta_delete = procesar_consulta(&consulta_delete).unwrap();
-                        let replicas_redistribuidor = get_replicas(
-                            &nodo.nodos,
-                            ip_redistribuidor.to_string(),
-                            nivel_replicacion,
-                        );
-                        let ultima_replica =
-                            replicas_redistribuidor[nodo.replicas.len() - 1].to_string();
+                            let consulta_delete = procesar_consulta(&consulta_delete)?;
+                            let replicas_redistribuidor = get_replicas(
+                                &nodo.nodos,
+                                ip_redistribuidor.to_string(),
+                                keyspace.nivel_replicacion,
+                            );
+                            let Some(ultima_replica) = replicas_redistribuidor.last() else {
+                                continue;
+                            };
 
-                        println!("Se eliminarán los datos de la réplica {} dado que un nuevo nodo se ingresó al cluster", ultima_replica);
+                            println!("Se eliminarán los datos de la réplica {} dado que un nuevo nodo se ingresó al cluster", ultima_replica);
 
-                        let nombre_servicio = obtener_nombre_servicio(ultima_replica.clone());
+                            let nombre_servicio = obtener_nombre_servicio(ultima_replica.clone());
 
-                        let address_replica = format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
+                            let address_replica =
+                                format!("{}:{}", nombre_servicio, PUERTO_INTERNODOS);
 
-                        send_and_not_deserial(
-                            address_replica,
-                            consulta_delete.get_consulta_explicita(),
-                            LevelConsistency::Strong,
-                            REPLICA,
-                        )
-                        .map_err(|_| "No se pudo conectar con el nodo responsable.".to_string())?;
+                            send_and_not_deserial(
+                                address_replica,
+                                consulta_delete.get_consulta_explicita(),
+                                LevelConsistency::Quorum,
+                                REPLICA,
+                            )
+                            .map_err(|_| {
+                                "No se pudo conectar con el nodo responsable.".to_string()
+                            })?;
+                        }
                     }
                 }
             }
@@ -1722,7 +3052,7 @@ pub fn get_replicas(
             comienzo = true;
             continue;
         } else if comienzo {
-            if replicas.len() >= replication - 1 {
+            if replicas.len() >= replication.saturating_sub(1) {
                 break;
             }
             replicas.push(node_ip.to_string());
@@ -1732,7 +3062,7 @@ pub fn get_replicas(
         if *node_ip == ip_actual || replicas.contains(node_ip) {
             continue;
         }
-        if replicas.len() >= replication - 1 {
+        if replicas.len() >= replication.saturating_sub(1) {
             break;
         }
         replicas.push(node_ip.to_string());
@@ -1740,23 +3070,20 @@ pub fn get_replicas(
     replicas
 }
 
-fn construir_update_todos_los_campos(
-    tabla: &String,
-    headers: &[String],
-    linea: Vec<&str>,
-) -> String {
+// UPDATE de todas las columnas de la fila en la tabla, calificada con su keyspace para
+// que la réplica la encuentre aunque no esté en su keyspace actual
+fn construir_update_todos_los_campos(nombre_tabla: &str, tabla: &Tabla, linea: &str) -> String {
+    let valores = linea.split(",").collect::<Vec<&str>>();
     let mut campos_valores = Vec::new();
-    for i in 0..headers.len() {
-        campos_valores.push(format!("{} = {}", headers[i], linea[i]));
+    for ((header, tipo), valor) in tabla.headers.iter().zip(&tabla.tipos).zip(valores) {
+        campos_valores.push(format!("{} = {}", header, termino_celda(tipo, valor)));
     }
 
     let consulta_update = format!(
-        "UPDATE {} SET {} WHERE {} = {} AND ID_VUELO = {}",
-        tabla,
+        "UPDATE {} SET {} WHERE {}",
+        nombre_tabla,
         campos_valores.join(", "),
-        headers[0],
-        linea[0],
-        headers[2],
+        tabla.condicion_clave(linea),
     );
 
     consulta_update
@@ -1769,9 +3096,8 @@ fn send_and_deserial(
     responsabilidad: u8,
     tipo_consulta: &TypeCQL,
 ) -> Result<Vec<String>, String> {
-    let server_name =
-        ServerName::try_from("localhost").map_err(|_| "Nombre de dominio inválido.".to_string())?;
-    let config = Arc::new(create_client_config()?);
+    let server_name = nombre_servidor(&direccion)?;
+    let config = config_cliente_internodos()?;
     let mut res: Vec<String> = vec![];
     if let Ok(socket) = TcpStream::connect(direccion.to_string()) {
         let client_conn = ClientConnection::new(Arc::clone(&config), server_name)
@@ -1792,9 +3118,8 @@ fn send_and_not_deserial(
     consistencia: LevelConsistency,
     responsabilidad: u8,
 ) -> Result<(), String> {
-    let server_name =
-        ServerName::try_from("localhost").map_err(|_| "Nombre de dominio inválido.".to_string())?;
-    let config = Arc::new(create_client_config()?);
+    let server_name = nombre_servidor(&direccion)?;
+    let config = config_cliente_internodos()?;
     if let Ok(socket) = TcpStream::connect(direccion.to_string()) {
         let client_conn = ClientConnection::new(Arc::clone(&config), server_name)
             .map_err(|_| "Error al crear la conexión TLS del cliente.")?;
@@ -1805,6 +3130,71 @@ fn send_and_not_deserial(
     Ok(())
 }
 
+// Las tablas del keyspace ordenadas por nombre, como las muestra un DESCRIBE
+fn tablas_ordenadas(keyspace: &Keyspace) -> Vec<&Tabla> {
+    let mut tablas: Vec<&Tabla> = keyspace.tablas.values().collect();
+    tablas.sort_by(|a, b| a.nombre.cmp(&b.nombre));
+    tablas
+}
+
+// Tipo de objeto con el que un DESCRIBE muestra la tabla
+fn tipo_tabla(tabla: &Tabla) -> &str {
+    match tabla.base {
+        Some(_) => "materialized_view",
+        None => "table",
+    }
+}
+
+// Resultado de una sentencia con IF [NOT] EXISTS que no corresponde aplicar: sin
+// cambios si la sentencia lo indica, o el error en caso contrario
+fn sin_cambios(si_corresponde: bool, error: String) -> Result<bool, String> {
+    match si_corresponde {
+        true => Ok(false),
+        false => Err(error),
+    }
+}
+
+// Indica si un tipo de columna es el tipo definido por el usuario o lo contiene,
+// en una colección, una tupla o un campo de otro tipo
+fn usa_tipo(tipo: &TipoColumna, nombre: &str) -> bool {
+    match tipo {
+        TipoColumna::Udt(udt, _) => {
+            udt.nombre == nombre || udt.campos.iter().any(|(_, campo)| usa_tipo(campo, nombre))
+        }
+        TipoColumna::List(elemento) | TipoColumna::Set(elemento) => usa_tipo(elemento, nombre),
+        TipoColumna::Map(clave, valor) => usa_tipo(clave, nombre) || usa_tipo(valor, nombre),
+        TipoColumna::Tuple(elementos) => elementos.iter().any(|tipo| usa_tipo(tipo, nombre)),
+        _ => false,
+    }
+}
+
+// Estrategia y factor de replicación de un keyspace, solo se soporta SimpleStrategy
+fn replicacion_keyspace(replicacion: &[(Termino, Termino)]) -> Result<(String, usize), String> {
+    let (strategy, replication_factor) =
+        obtener_tipo_strategy_y_replication(replicacion).map_err(|error| invalida(&error))?;
+    if strategy != "SimpleStrategy" {
+        return Err(invalida(
+            "solo se soporta la estrategia de replicación SimpleStrategy",
+        ));
+    }
+    Ok((strategy, replication_factor))
+}
+
+fn sin_repetidas(filas: Vec<String>) -> Vec<String> {
+    let mut resultado: Vec<String> = Vec::new();
+    for fila in filas {
+        if !resultado.contains(&fila) {
+            resultado.push(fila);
+        }
+    }
+    resultado
+}
+
+// Dirección por la cual los clientes se conectan al nodo de la IP indicada
+fn direccion_cliente(ip: &str) -> Option<SocketAddr> {
+    format!("{}:{}", ip, PUERTO_CLIENTE).parse().ok()
+}
+
 pub fn obtener_nombre_servicio(ip: String) -> String {
     match ip.as_str() {
         "127.0.0.1" => "node1".to_string(),
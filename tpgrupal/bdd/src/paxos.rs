@@ -0,0 +1,371 @@
+use std::{
+    collections::HashMap,
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use protocolo::{
+    parser_cql::{consulta::Consulta, type_cql::TypeCQL},
+    serial_deserial::intra_nodos::paxos::{PROMESA, RECHAZO},
+};
+
+use crate::procesamiento_data::{hashear, valor_particion_insert};
+
+// Estado de Paxos de una partición en una réplica
+#[derive(Debug, Default)]
+struct EstadoPaxos {
+    prometido: u64,                  // Mayor ballot que la réplica prometió respetar
+    aceptado: Option<(u64, String)>, // Propuesta aceptada que todavía no se confirmó
+    confirmado: u64,                 // Ballot de la última propuesta confirmada
+}
+
+/// Estado de Paxos de las particiones de un nodo, por tabla y partition key. Si tiene
+/// un archivo asociado se persiste en él, para que una réplica que se reinicia no
+/// olvide lo que prometió ni la propuesta que aceptó
+#[derive(Debug, Default)]
+pub struct Paxos {
+    estados: HashMap<String, EstadoPaxos>,
+    archivo: Option<String>,
+}
+
+impl Paxos {
+    /// Carga el estado persistido en el archivo, que se crea al guardar si no existe.
+    /// Cada línea es una partición: prometido, confirmado, ballot aceptado (0 si no
+    /// hay), clave y sentencia aceptada, separados por tabulaciones
+    pub fn desde_archivo(archivo: &str) -> Self {
+        let estados = fs::read_to_string(archivo)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(estado_desde_linea)
+            .collect();
+        Paxos {
+            estados,
+            archivo: Some(archivo.to_string()),
+        }
+    }
+
+    /// Persiste el estado en el archivo asociado, reemplazándolo de una sola vez
+    pub fn guardar(&self) -> Result<(), String> {
+        let Some(archivo) = &self.archivo else {
+            return Ok(());
+        };
+        let contenido: String = self
+            .estados
+            .iter()
+            .map(|(clave, estado)| linea_estado(clave, estado))
+            .collect();
+        let temporal = format!("{}.tmp", archivo);
+        fs::write(&temporal, contenido)
+            .and_then(|_| fs::rename(&temporal, archivo))
+            .map_err(|_| "No se pudo persistir el estado de Paxos.".to_string())
+    }
+
+    /// Fase de prepare: si el ballot es mayor a todos los que prometió, promete no
+    /// aceptar propuestas con uno menor y devuelve la propuesta aceptada que tenga en
+    /// curso junto con el ballot de la última confirmada. Si no, devuelve el prometido
+    pub fn preparar(
+        &mut self,
+        clave: &str,
+        ballot: u64,
+    ) -> Result<(Option<(u64, String)>, u64), u64> {
+        let estado = self.estados.entry(clave.to_string()).or_default();
+        if ballot <= estado.prometido {
+            return Err(estado.prometido);
+        }
+        estado.prometido = ballot;
+        Ok((estado.aceptado.clone(), estado.confirmado))
+    }
+
+    /// Fase de propose: acepta la propuesta si no prometió un ballot mayor
+    pub fn proponer(&mut self, clave: &str, ballot: u64, sentencia: &str) -> Result<(), u64> {
+        let estado = self.estados.entry(clave.to_string()).or_default();
+        if ballot < estado.prometido {
+            return Err(estado.prometido);
+        }
+        estado.prometido = ballot;
+        estado.aceptado = Some((ballot, sentencia.to_string()));
+        Ok(())
+    }
+
+    /// Fase de commit: la propuesta ya se aplicó, así que deja de estar en curso
+    pub fn confirmar(&mut self, clave: &str, ballot: u64) {
+        let estado = self.estados.entry(clave.to_string()).or_default();
+        estado.confirmado = estado.confirmado.max(ballot);
+        if estado
+            .aceptado
+            .as_ref()
+            .is_some_and(|(aceptado, _)| *aceptado <= ballot)
+        {
+            estado.aceptado = None;
+        }
+    }
+}
+
+fn linea_estado(clave: &str, estado: &EstadoPaxos) -> String {
+    let (ballot, sentencia) = estado.aceptado.clone().unwrap_or_default();
+    format!(
+        "{}\t{}\t{}\t{}\t{}\n",
+        estado.prometido,
+        estado.confirmado,
+        ballot,
+        escapar(clave),
+        escapar(&sentencia)
+    )
+}
+
+fn estado_desde_linea(linea: &str) -> Option<(String, EstadoPaxos)> {
+    let campos: Vec<&str> = linea.splitn(5, '\t').collect();
+    if campos.len() < 5 {
+        return None;
+    }
+    let ballot = campos[2].parse::<u64>().ok()?;
+    let estado = EstadoPaxos {
+        prometido: campos[0].parse().ok()?,
+        confirmado: campos[1].parse().ok()?,
+        aceptado: (ballot > 0).then(|| (ballot, desescapar(campos[4]))),
+    };
+    Some((desescapar(campos[3]), estado))
+}
+
+// Las sentencias pueden tener saltos de línea y tabulaciones, que separan el archivo
+fn escapar(texto: &str) -> String {
+    texto
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn desescapar(texto: &str) -> String {
+    let mut resultado = String::new();
+    let mut caracteres = texto.chars();
+    while let Some(caracter) = caracteres.next() {
+        if caracter != '\\' {
+            resultado.push(caracter);
+            continue;
+        }
+        match caracteres.next() {
+            Some('n') => resultado.push('\n'),
+            Some('t') => resultado.push('\t'),
+            Some(otro) => resultado.push(otro),
+            None => {}
+        }
+    }
+    resultado
+}
+
+/// Promesa de una réplica en la fase de prepare: la propuesta aceptada que tenga en
+/// curso, el ballot de la última confirmada y la fila actual de la clave con el
+/// momento de su última escritura (0 si la réplica no la tiene)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Promesa {
+    pub aceptado: Option<(u64, String)>,
+    pub confirmado: u64,
+    pub timestamp: u64,
+    pub fila: Option<String>,
+}
+
+impl Promesa {
+    /// Campos con los que la réplica responde la promesa
+    pub fn campos(&self) -> Vec<String> {
+        let (ballot, sentencia) = self.aceptado.clone().unwrap_or_default();
+        vec![
+            PROMESA.to_string(),
+            ballot.to_string(),
+            self.confirmado.to_string(),
+            self.timestamp.to_string(),
+            sentencia,
+            self.fila.clone().unwrap_or_default(),
+        ]
+    }
+
+    /// Interpreta la respuesta de una réplica, que puede no ser una promesa
+    pub fn desde_campos(campos: &[String]) -> Option<Self> {
+        if campos.len() < 6 || campos[0] != PROMESA {
+            return None;
+        }
+        let ballot = campos[1].parse::<u64>().ok()?;
+        Some(Promesa {
+            aceptado: (ballot > 0).then(|| (ballot, campos[4].clone())),
+            confirmado: campos[2].parse().ok()?,
+            timestamp: campos[3].parse().ok()?,
+            fila: Some(campos[5].clone()).filter(|fila| !fila.is_empty()),
+        })
+    }
+}
+
+/// Respuesta de una réplica que rechaza una fase por haber prometido un ballot mayor
+pub fn rechazo(prometido: u64) -> Vec<String> {
+    vec![RECHAZO.to_string(), prometido.to_string()]
+}
+
+/// Mayor ballot prometido entre los rechazos de las réplicas
+pub fn mayor_rechazo(respuestas: &[Vec<String>]) -> u64 {
+    respuestas
+        .iter()
+        .filter(|campos| campos.first().is_some_and(|campo| campo == RECHAZO))
+        .filter_map(|campos| campos.get(1)?.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Ballot de una ronda de Paxos: los microsegundos actuales seguidos del último octeto
+/// de la ip del coordinador, para que dos coordinadores no generen el mismo. Siempre es
+/// mayor que `minimo`, el mayor ballot con el que rechazaron la ronda anterior
+pub fn nuevo_ballot(ip: &str, minimo: u64) -> u64 {
+    let micros = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duracion| duracion.as_micros() as u64)
+        .unwrap_or_default();
+    let octeto = ip
+        .rsplit('.')
+        .next()
+        .and_then(|octeto| octeto.parse::<u8>().ok())
+        .unwrap_or_else(|| hashear(ip).unwrap_or_default() as u8) as u64;
+    (micros << 8).max(((minimo >> 8) + 1) << 8) | octeto
+}
+
+/// Clave de la partición sobre la que opera una sentencia, que identifica su estado
+/// de Paxos: la tabla y el valor de la partition key
+pub fn clave_particion(consulta: &Consulta, columna_particion: &str) -> String {
+    let partition_key = match consulta.get_type() {
+        TypeCQL::Insert => {
+            valor_particion_insert(consulta.get_sentencia(), columna_particion).unwrap_or_default()
+        }
+        _ => consulta
+            .get_where()
+            .valor(columna_particion)
+            .unwrap_or_default(),
+    };
+    format!("{}:{}", consulta.get_tabla(), partition_key)
+}
+
+/// Fila del resultado de una transacción liviana: la columna [applied] y, si no se
+/// aplicó, la fila actual que hizo fallar la condición (vacía si no existe)
+pub fn resultado_lwt(aplicada: bool, fila: Option<&str>, columnas: usize) -> String {
+    match (aplicada, fila) {
+        (false, Some(fila)) => format!("false,{}", fila),
+        _ => format!("{}{}", aplicada, ",".repeat(columnas)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preparar_rechaza_ballots_no_mayores_al_prometido() {
+        //Arrange
+        let mut paxos = Paxos::default();
+        paxos.preparar("vuelos:1", 10).unwrap();
+
+        //Act
+        let igual = paxos.preparar("vuelos:1", 10);
+        let menor = paxos.preparar("vuelos:1", 5);
+        let otra_clave = paxos.preparar("vuelos:2", 5);
+
+        //Assert
+        assert_eq!(igual, Err(10));
+        assert_eq!(menor, Err(10));
+        assert_eq!(otra_clave, Ok((None, 0)));
+    }
+
+    #[test]
+    fn test_proponer_respeta_la_promesa() {
+        //Arrange
+        let mut paxos = Paxos::default();
+        paxos.preparar("vuelos:1", 10).unwrap();
+
+        //Act
+        let menor = paxos.proponer("vuelos:1", 5, "UPDATE a");
+        let prometido = paxos.proponer("vuelos:1", 10, "UPDATE b");
+        let promesa = paxos.preparar("vuelos:1", 20);
+
+        //Assert
+        assert_eq!(menor, Err(10));
+        assert_eq!(prometido, Ok(()));
+        assert_eq!(promesa, Ok((Some((10, "UPDATE b".to_string())), 0)));
+    }
+
+    #[test]
+    fn test_confirmar_deja_de_informar_la_propuesta_aceptada() {
+        //Arrange
+        let mut paxos = Paxos::default();
+        paxos.preparar("vuelos:1", 10).unwrap();
+        paxos.proponer("vuelos:1", 10, "UPDATE a").unwrap();
+
+        //Act
+        paxos.confirmar("vuelos:1", 10);
+        let promesa = paxos.preparar("vuelos:1", 20);
+
+        //Assert
+        assert_eq!(promesa, Ok((None, 10)));
+    }
+
+    #[test]
+    fn test_confirmar_un_ballot_viejo_no_borra_una_propuesta_nueva() {
+        //Arrange
+        let mut paxos = Paxos::default();
+        paxos.proponer("vuelos:1", 20, "UPDATE nuevo").unwrap();
+
+        //Act
+        paxos.confirmar("vuelos:1", 10);
+        let promesa = paxos.preparar("vuelos:1", 30);
+
+        //Assert
+        assert_eq!(promesa, Ok((Some((20, "UPDATE nuevo".to_string())), 10)));
+    }
+
+    #[test]
+    fn test_nuevo_ballot_supera_al_minimo_y_termina_en_el_octeto() {
+        //Arrange
+        let minimo = u64::MAX >> 2;
+
+        //Act
+        let ballot = nuevo_ballot("127.0.0.3", minimo);
+        let siguiente = nuevo_ballot("127.0.0.3", ballot);
+
+        //Assert
+        assert!(ballot > minimo);
+        assert!(siguiente > ballot);
+        assert_eq!(ballot & 0xff, 3);
+        assert_eq!(siguiente & 0xff, 3);
+    }
+
+    #[test]
+    fn test_estado_persistido_sobrevive_a_un_reinicio() {
+        //Arrange
+        let archivo = std::env::temp_dir().join(format!("paxos_test_{}.txt", std::process::id()));
+        let archivo = archivo.to_string_lossy().to_string();
+        let mut paxos = Paxos::desde_archivo(&archivo);
+        paxos.preparar("vuelos:1", 10).unwrap();
+        paxos
+            .proponer(
+                "vuelos:1",
+                10,
+                "UPDATE vuelos\tSET estado = 'a\\b'\nWHERE id = 1",
+            )
+            .unwrap();
+        paxos.preparar("vuelos:2", 7).unwrap();
+        paxos.proponer("vuelos:2", 7, "UPDATE b").unwrap();
+        paxos.confirmar("vuelos:2", 7);
+
+        //Act
+        paxos.guardar().unwrap();
+        let mut reiniciado = Paxos::desde_archivo(&archivo);
+        let _ = fs::remove_file(&archivo);
+
+        //Assert
+        assert_eq!(reiniciado.preparar("vuelos:1", 10), Err(10));
+        assert_eq!(
+            reiniciado.preparar("vuelos:1", 11),
+            Ok((
+                Some((
+                    10,
+                    "UPDATE vuelos\tSET estado = 'a\\b'\nWHERE id = 1".to_string()
+                )),
+                0
+            ))
+        );
+        assert_eq!(reiniciado.preparar("vuelos:2", 8), Ok((None, 7)));
+    }
+}
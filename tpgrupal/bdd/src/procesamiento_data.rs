@@ -7,9 +7,14 @@ use std::{
     path::Path,
 };
 
+use protocolo::parser_cql::ast::Sentencia;
 use protocolo::parser_cql::condicion_where::CondicionWhere;
+use protocolo::serial_deserial::cassandra::paginacion::EstadoPaginacion;
+use protocolo::serial_deserial::tipo_columna::{quitar_comillas, TipoColumna};
 
-use crate::{endpoint_data::EndpointData, node_status::NodeStatus, tabla::Tabla};
+use crate::{
+    coleccion::celda_valor, endpoint_data::EndpointData, node_status::NodeStatus, tabla::Tabla,
+};
 
 pub fn new_metadata(ip_nodo: &String) -> HashMap<String, EndpointData> {
     let mut metadata_nodos: HashMap<String, EndpointData> = HashMap::new();
@@ -26,31 +31,53 @@ pub fn new_metadata(ip_nodo: &String) -> HashMap<String, EndpointData> {
     metadata_nodos
 }
 
-pub fn obtener_hash_origen(query_insert: &str) -> u32 {
-    let columnas_y_valores = query_insert.split(" VALUES ").collect::<Vec<&str>>();
-    let columnas_sin_parentesis = columnas_y_valores[1].replace(['(', ')'], "");
-    let columnas = columnas_sin_parentesis.split(",").collect::<Vec<&str>>();
+/// Valor que un INSERT escribe en la partition key de la tabla, como se lo escribió
+/// en la consulta
+pub fn valor_particion_insert(
+    sentencia: &Sentencia,
+    columna_particion: &str,
+) -> Result<String, String> {
+    let Sentencia::Insert {
+        columnas, valores, ..
+    } = sentencia
+    else {
+        return Err("La sentencia no es un INSERT.".to_string());
+    };
+    columnas
+        .iter()
+        .position(|columna| columna == columna_particion)
+        .and_then(|indice| valores.get(indice))
+        .map(|valor| valor.to_string())
+        .ok_or(format!(
+            "El INSERT no indica el valor de la partition key {}.",
+            columna_particion
+        ))
+}
 
-    let origen = columnas[0].to_string();
-    hashear(&origen).unwrap()
+/// Token de la partición en la que escribe un INSERT
+pub fn obtener_hash_origen(sentencia: &Sentencia, columna_particion: &str) -> Result<u32, String> {
+    token_particion(&valor_particion_insert(sentencia, columna_particion)?)
 }
 
-pub fn obtener_hash_key_select(condicion: &CondicionWhere) -> u32 {
-    let valor1 = condicion.condicion1.to_string();
-    let partes = valor1.split(" = ").collect::<Vec<&str>>();
-    let nombre1 = partes[1].to_string();
-    hashear(&nombre1).unwrap()
+pub fn obtener_hash_key_select(
+    condicion: &CondicionWhere,
+    columna_particion: &str,
+) -> Result<u32, String> {
+    let valor = condicion.valor(columna_particion).unwrap_or_default();
+    token_particion(&valor)
 }
 
-pub fn obtener_row(query_insert: &str) -> String {
-    let columns_y_values_query = query_insert.split("VALUES").collect::<Vec<&str>>();
-    let values_query = columns_y_values_query[1].trim().replace(['(', ')'], "");
-    let values_separados = values_query.split(",").collect::<Vec<&str>>();
-    let mut v = vec![];
-    for e in values_separados {
-        v.push(e.trim());
-    }
-    v.join(",")
+/// Fila con los valores de un INSERT en el orden en que se escribieron, con las
+/// colecciones en el formato de sus celdas
+pub fn obtener_fila(sentencia: &Sentencia) -> String {
+    let Sentencia::Insert { valores, .. } = sentencia else {
+        return String::new();
+    };
+    valores
+        .iter()
+        .map(celda_valor)
+        .collect::<Vec<String>>()
+        .join(",")
 }
 
 pub fn hashear(dato: &str) -> Result<u32, String> {
@@ -61,6 +88,52 @@ pub fn hashear(dato: &str) -> Result<u32, String> {
     }
 }
 
+/// Token de una partition key: el hash de su valor sin las comillas con las que se
+/// haya escrito, así 'AEP' y AEP son la misma partición
+pub fn token_particion(valor: &str) -> Result<u32, String> {
+    hashear(quitar_comillas(valor.trim()))
+}
+
+/// Devuelve la posición de una fila dentro de un recorrido paginado: el token
+/// de su partition key (la primera columna) y la fila completa como desempate
+pub fn posicion_fila(row: &str) -> Result<EstadoPaginacion, String> {
+    let partition_key = row.split(",").next().unwrap_or_default();
+    Ok(EstadoPaginacion {
+        token: token_particion(partition_key)?,
+        clustering: row.to_string(),
+    })
+}
+
+/// Ordena las filas por token y devuelve las primeras `page_size` que están después
+/// de la posición `desde`, junto con la posición de la última fila enviada si
+/// todavía quedan filas por recorrer
+pub fn paginar_filas(
+    filas: Vec<String>,
+    page_size: i32,
+    desde: Option<&EstadoPaginacion>,
+) -> Result<(Vec<String>, Option<EstadoPaginacion>), String> {
+    let mut posiciones = Vec::with_capacity(filas.len());
+    for fila in filas {
+        let posicion = posicion_fila(&fila)?;
+        if desde.is_none_or(|desde| posicion > *desde) {
+            posiciones.push((posicion, fila));
+        }
+    }
+    posiciones.sort();
+
+    let page_size = page_size.max(1) as usize;
+    let quedan_filas = posiciones.len() > page_size;
+    posiciones.truncate(page_size);
+    let siguiente = match quedan_filas {
+        true => posiciones.last().map(|(posicion, _)| posicion.clone()),
+        false => None,
+    };
+    Ok((
+        posiciones.into_iter().map(|(_, fila)| fila).collect(),
+        siguiente,
+    ))
+}
+
 pub fn get_data(path: String) -> Vec<String> {
     let mut data: Vec<String> = vec![];
     if let Ok(f) = File::open(path) {
@@ -126,6 +199,34 @@ fn get_headers_vuelos(tipo: &str) -> Vec<String> {
     }
 }
 
+fn get_tipos_columnas(headers: &[String]) -> Vec<TipoColumna> {
+    headers
+        .iter()
+        .map(|header| match header.as_str() {
+            "ID_AEROPUERTO" => TipoColumna::Int,
+            "LATITUD" | "LONGITUD" | "VELOCIDAD_ACTUAL" | "ALTITUD_ACTUAL" | "LATITUD_ACTUAL"
+            | "LONGITUD_ACTUAL" | "COMBUSTIBLE" => TipoColumna::Float,
+            _ => TipoColumna::Text,
+        })
+        .collect()
+}
+
+// VUELOS_DESTINO es la vista materializada de VUELOS_ORIGEN con DESTINO como partition
+// key, que incluye en su clave la de VUELOS_ORIGEN
+fn nueva_tabla(nombre: &str, headers: Vec<String>) -> Tabla {
+    let tipos = get_tipos_columnas(&headers);
+    let clustering = match nombre {
+        "AEROPUERTOS" => vec!["NOMBRE".to_string()],
+        "VUELOS_DESTINO" => vec!["ID_VUELO".to_string(), "ORIGEN".to_string()],
+        _ => vec!["ID_VUELO".to_string()],
+    };
+    let tabla = Tabla::new(nombre.to_string(), headers, tipos).con_clustering(clustering);
+    match nombre {
+        "VUELOS_DESTINO" => tabla.con_base("VUELOS_ORIGEN"),
+        _ => tabla,
+    }
+}
+
 fn inicializar_headers() -> (Vec<String>, Vec<String>, Vec<String>) {
     let headers_aeropuerto = get_headers_aeropuertos();
 
@@ -138,7 +239,7 @@ fn inicializar_headers() -> (Vec<String>, Vec<String>, Vec<String>) {
 
 fn insert_data(data: Vec<String>, tabla: &mut Tabla) {
     for r in data {
-        tabla.insertar(r);
+        tabla.cargar(r);
     }
 }
 
@@ -148,13 +249,13 @@ fn crear_tabla(
     headers_origen: Vec<String>,
     headers_destino: Vec<String>,
 ) -> Tabla {
-    let mut tabla = Tabla::new("".to_string(), vec![]);
+    let mut tabla = Tabla::new("".to_string(), vec![], vec![]);
     if name.contains("AEROPUERTOS") {
-        tabla = Tabla::new("AEROPUERTOS".to_string(), headers_aeropuerto.clone());
+        tabla = nueva_tabla("AEROPUERTOS", headers_aeropuerto.clone());
     } else if name.contains("VUELOS_ORIGEN") {
-        tabla = Tabla::new("VUELOS_ORIGEN".to_string(), headers_origen.clone());
+        tabla = nueva_tabla("VUELOS_ORIGEN", headers_origen.clone());
     } else if name.contains("VUELOS_DESTINO") {
-        tabla = Tabla::new("VUELOS_DESTINO".to_string(), headers_destino.clone());
+        tabla = nueva_tabla("VUELOS_DESTINO", headers_destino.clone());
     }
     tabla
 }
@@ -196,11 +297,39 @@ fn new_tablas() -> HashMap<String, Tabla> {
 
     let (headers_aeropuerto, headers_origen, headers_destino) = inicializar_headers();
 
-    let tabla1 = Tabla::new("AEROPUERTOS".to_string(), headers_aeropuerto.clone());
-    let tabla2 = Tabla::new("VUELOS_ORIGEN".to_string(), headers_origen.clone());
-    let tabla3 = Tabla::new("VUELOS_DESTINO".to_string(), headers_destino.clone());
+    let tabla1 = nueva_tabla("AEROPUERTOS", headers_aeropuerto.clone());
+    let tabla2 = nueva_tabla("VUELOS_ORIGEN", headers_origen.clone());
+    let tabla3 = nueva_tabla("VUELOS_DESTINO", headers_destino.clone());
     tablas.insert(tabla1.nombre.to_string(), tabla1);
     tablas.insert(tabla2.nombre.to_string(), tabla2);
     tablas.insert(tabla3.nombre.to_string(), tabla3);
     tablas
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocolo::parser_cql::parseo_consulta::procesar_consulta;
+
+    #[test]
+    fn test_insert_y_select_de_la_misma_particion_tienen_el_mismo_token() {
+        //Arrange
+        let insert = procesar_consulta(
+            "INSERT INTO VUELOS (ID_VUELO, ORIGEN) VALUES (1, 'Aeroparque, Buenos Aires')",
+        )
+        .unwrap();
+        let select = procesar_consulta(
+            "SELECT * FROM VUELOS WHERE ORIGEN = 'Aeroparque, Buenos Aires' AND ID_VUELO = 1",
+        )
+        .unwrap();
+
+        //Act
+        let token_insert = obtener_hash_origen(insert.get_sentencia(), "ORIGEN").unwrap();
+        let token_select = obtener_hash_key_select(select.get_where(), "ORIGEN").unwrap();
+
+        //Assert
+        assert_eq!(token_insert, token_select);
+        assert_eq!(token_insert, hashear("Aeroparque, Buenos Aires").unwrap());
+        assert!(obtener_hash_origen(insert.get_sentencia(), "DESTINO").is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use protocolo::parser_cql::ast::Termino;
+use protocolo::parser_cql::opciones_rol::OpcionesRol;
+use protocolo::parser_cql::parseo_consulta::{obtener_headers_table, obtener_tipos_table};
+use protocolo::parser_cql::permisos::{Permiso, Recurso};
+use protocolo::serial_deserial::cassandra::metadata_rows::ColumnaSpec;
+use protocolo::serial_deserial::tipo_columna::{quitar_comillas, TipoColumna};
+use seguridad::contrasenias::{hashear_password, verificar_password};
+use std::collections::HashMap;
+use std::env;
+use std::time::{Duration, Instant};
+
+use crate::tabla::Tabla;
+
+pub const KEYSPACE_AUTH: &str = "system_auth";
+pub const TABLA_ROLES: &str = "system_auth.roles";
+pub const TABLA_PERMISOS: &str = "system_auth.role_permissions";
+pub const ROL_POR_DEFECTO: &str = "cassandra";
+
+const NOMBRE_TABLA_ROLES: &str = "roles";
+const DEFINICION_ROLES: &str =
+    "(role text, salted_hash text, is_superuser boolean, can_login boolean, PRIMARY KEY ((role))";
+const NOMBRE_TABLA_PERMISOS: &str = "role_permissions";
+// El recurso queda en la tercera columna porque es la que la tabla compara
+// contra la segunda condición del WHERE en los UPDATE y DELETE
+const DEFINICION_PERMISOS: &str =
+    "(role text, permissions text, resource text, PRIMARY KEY ((role), resource))";
+const SEPARADOR_PERMISOS: char = '|';
+pub const VARIABLE_VALIDEZ_PERMISOS: &str = "PERMISSIONS_VALIDITY_MS";
+const VALIDEZ_PERMISOS_POR_DEFECTO: Duration = Duration::from_millis(2000);
+
+/// Rol con el que un cliente se puede autenticar, tal como se guarda en la
+/// tabla system_auth.roles: de la contraseña solo se guarda su hash con salt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rol {
+    pub nombre: String,
+    pub salted_hash: String,
+    pub es_superusuario: bool,
+    pub puede_loguearse: bool,
+}
+
+impl Rol {
+    /// Crea el rol a partir de una fila de system_auth.roles
+    pub fn desde_fila(fila: &str) -> Result<Self, String> {
+        let valores = fila
+            .split(",")
+            .map(|valor| quitar_comillas(valor.trim()))
+            .collect::<Vec<&str>>();
+        let [nombre, salted_hash, es_superusuario, puede_loguearse] = valores[..] else {
+            return Err("Fila de la tabla de roles mal formada.".to_string());
+        };
+        Ok(Rol {
+            nombre: nombre.to_string(),
+            salted_hash: salted_hash.to_string(),
+            es_superusuario: es_superusuario == "true",
+            puede_loguearse: puede_loguearse == "true",
+        })
+    }
+
+    /// Verifica la contraseña contra el hash guardado del rol
+    pub fn verificar_password(&self, password: &str) -> bool {
+        verificar_password(password, &self.salted_hash)
+    }
+}
+
+/// Permisos que tiene un rol sobre un recurso, tal como se guardan en una
+/// fila de system_auth.role_permissions
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermisosRecurso {
+    pub rol: String,
+    pub recurso: Recurso,
+    pub permisos: Vec<Permiso>,
+}
+
+impl PermisosRecurso {
+    /// Crea los permisos a partir de una fila de system_auth.role_permissions
+    pub fn desde_fila(fila: &str) -> Result<Self, String> {
+        let valores = fila
+            .split(",")
+            .map(|valor| quitar_comillas(valor.trim()))
+            .collect::<Vec<&str>>();
+        let [rol, permisos, recurso] = valores[..] else {
+            return Err("Fila de la tabla de permisos mal formada.".to_string());
+        };
+        Ok(PermisosRecurso {
+            rol: rol.to_string(),
+            recurso: Recurso::desde_nombre(recurso)?,
+            permisos: permisos
+                .split(SEPARADOR_PERMISOS)
+                .filter(|permiso| !permiso.is_empty())
+                .map(Permiso::create)
+                .collect::<Result<Vec<Permiso>, String>>()?,
+        })
+    }
+
+    /// Devuelve los permisos separados por '|', en el orden de `Permiso::TODOS`
+    fn permisos_guardados(&self) -> String {
+        Permiso::TODOS
+            .iter()
+            .filter(|permiso| self.permisos.contains(permiso))
+            .map(|permiso| permiso.nombre())
+            .collect::<Vec<&str>>()
+            .join(&SEPARADOR_PERMISOS.to_string())
+    }
+}
+
+/// Devuelve si alguno de los permisos otorgados al rol incluye el permiso sobre el
+/// recurso, ya sea otorgado sobre el recurso o sobre alguno de los que lo contienen
+pub fn tiene_permiso(permisos: &[PermisosRecurso], permiso: &Permiso, recurso: &Recurso) -> bool {
+    let jerarquia = recurso.jerarquia();
+    permisos.iter().any(|permisos| {
+        jerarquia.contains(&permisos.recurso) && permisos.permisos.contains(permiso)
+    })
+}
+
+/// Rol y permisos leídos de system_auth con los que se autorizan las consultas de
+/// una sesión, y el momento en que se leyeron
+#[derive(Debug, Clone)]
+struct PermisosCacheados {
+    leidos: Instant,
+    rol: Option<Rol>,
+    permisos: Vec<PermisosRecurso>,
+}
+
+/// Cache de los roles y permisos con los que se autorizan las consultas, para no
+/// leerlos del cluster en cada una. Como permissions_validity en Cassandra, cada
+/// entrada vale un tiempo (PERMISSIONS_VALIDITY_MS, 2 segundos por defecto) y se
+/// invalida antes si el rol o sus permisos cambian en este nodo
+#[derive(Debug, Default)]
+pub struct CachePermisos {
+    entradas: HashMap<String, PermisosCacheados>, // K: nombre del rol
+}
+
+impl CachePermisos {
+    /// Devuelve el rol y sus permisos si se leyeron hace menos que la validez
+    pub fn obtener(&self, rol: &str) -> Option<(Option<Rol>, Vec<PermisosRecurso>)> {
+        self.entradas
+            .get(rol)
+            .filter(|cacheados| cacheados.leidos.elapsed() < validez_permisos())
+            .map(|cacheados| (cacheados.rol.clone(), cacheados.permisos.clone()))
+    }
+
+    pub fn guardar(&mut self, nombre: &str, rol: Option<Rol>, permisos: Vec<PermisosRecurso>) {
+        let cacheados = PermisosCacheados {
+            leidos: Instant::now(),
+            rol,
+            permisos,
+        };
+        self.entradas.insert(nombre.to_string(), cacheados);
+    }
+
+    /// Descarta el rol y los permisos cacheados de un rol que cambió
+    pub fn invalidar(&mut self, rol: &str) {
+        self.entradas.remove(rol);
+    }
+}
+
+// Tiempo durante el que valen los permisos cacheados, configurable en milisegundos
+fn validez_permisos() -> Duration {
+    env::var(VARIABLE_VALIDEZ_PERMISOS)
+        .ok()
+        .and_then(|validez| validez.trim().parse().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(VALIDEZ_PERMISOS_POR_DEFECTO)
+}
+
+/// Crea la tabla vacía de roles, con la misma definición que tendría
+/// si se la creara con CREATE TABLE
+pub fn crear_tabla_roles() -> Result<Tabla, String> {
+    crear_tabla(NOMBRE_TABLA_ROLES, DEFINICION_ROLES, &[])
+}
+
+/// Crea la tabla vacía de permisos de los roles
+pub fn crear_tabla_permisos() -> Result<Tabla, String> {
+    crear_tabla(NOMBRE_TABLA_PERMISOS, DEFINICION_PERMISOS, &["resource"])
+}
+
+fn crear_tabla(nombre: &str, definicion: &str, clustering: &[&str]) -> Result<Tabla, String> {
+    let headers = obtener_headers_table(definicion);
+    let tipos = obtener_tipos_table(definicion, &headers, &[])?;
+    let clustering = clustering
+        .iter()
+        .map(|columna| columna.to_string())
+        .collect();
+    Ok(Tabla::new(nombre.to_string(), headers, tipos).con_clustering(clustering))
+}
+
+/// Devuelve las columnas del resultado de un LIST PERMISSIONS
+pub fn columnas_list_permissions() -> Vec<ColumnaSpec> {
+    ["role", "resource", "permission"]
+        .iter()
+        .map(|nombre| ColumnaSpec {
+            nombre: nombre.to_string(),
+            tipo: TipoColumna::Text,
+        })
+        .collect()
+}
+
+/// Arma la consulta que busca un rol por su nombre
+pub fn consulta_select_rol(nombre: &str) -> String {
+    format!(
+        "SELECT * FROM {} WHERE role = {}",
+        TABLA_ROLES,
+        texto(nombre)
+    )
+}
+
+/// Arma la consulta que busca algún rol, para saber si la tabla de roles está vacía
+pub fn consulta_algun_rol() -> String {
+    format!("SELECT * FROM {} LIMIT 1", TABLA_ROLES)
+}
+
+/// Arma el INSERT de un rol nuevo, hasheando su contraseña. Por defecto
+/// los roles pueden loguearse y no son superusuarios
+pub fn consulta_insert_rol(nombre: &str, opciones: &OpcionesRol) -> Result<String, String> {
+    let salted_hash = match &opciones.password {
+        Some(password) => hashear_password(password)?,
+        None => String::new(),
+    };
+    Ok(format!(
+        "INSERT INTO {} (role, salted_hash, is_superuser, can_login) VALUES ({}, {}, {}, {})",
+        TABLA_ROLES,
+        texto(nombre),
+        texto(&salted_hash),
+        opciones.superuser.unwrap_or(false),
+        opciones.login.unwrap_or(true)
+    ))
+}
+
+/// Arma el UPDATE con las opciones que cambia un ALTER ROLE, o None si no cambia ninguna
+pub fn consulta_update_rol(nombre: &str, opciones: &OpcionesRol) -> Result<Option<String>, String> {
+    let mut cambios = vec![];
+    if let Some(password) = &opciones.password {
+        cambios.push(format!(
+            "salted_hash = {}",
+            texto(&hashear_password(password)?)
+        ));
+    }
+    if let Some(superuser) = opciones.superuser {
+        cambios.push(format!("is_superuser = {}", superuser));
+    }
+    if let Some(login) = opciones.login {
+        cambios.push(format!("can_login = {}", login));
+    }
+    if cambios.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "UPDATE {} SET {} WHERE role = {}",
+        TABLA_ROLES,
+        cambios.join(", "),
+        texto(nombre)
+    )))
+}
+
+/// Arma el DELETE de un rol
+pub fn consulta_delete_rol(nombre: &str) -> String {
+    format!("DELETE FROM {} WHERE role = {}", TABLA_ROLES, texto(nombre))
+}
+
+/// Arma la consulta que busca los permisos de un rol, o los de todos los roles
+pub fn consulta_select_permisos(rol: Option<&str>) -> String {
+    match rol {
+        Some(rol) => format!(
+            "SELECT * FROM {} WHERE role = {}",
+            TABLA_PERMISOS,
+            texto(rol)
+        ),
+        None => format!("SELECT * FROM {}", TABLA_PERMISOS),
+    }
+}
+
+/// Arma el INSERT de los permisos de un rol sobre un recurso que todavía no tenía ninguno
+pub fn consulta_insert_permisos(permisos: &PermisosRecurso) -> String {
+    format!(
+        "INSERT INTO {} (role, permissions, resource) VALUES ({}, {}, {})",
+        TABLA_PERMISOS,
+        texto(&permisos.rol),
+        texto(&permisos.permisos_guardados()),
+        texto(&permisos.recurso.nombre())
+    )
+}
+
+/// Arma el UPDATE de los permisos de un rol sobre un recurso
+pub fn consulta_update_permisos(permisos: &PermisosRecurso) -> String {
+    format!(
+        "UPDATE {} SET permissions = {} WHERE role = {} AND resource = {}",
+        TABLA_PERMISOS,
+        texto(&permisos.permisos_guardados()),
+        texto(&permisos.rol),
+        texto(&permisos.recurso.nombre())
+    )
+}
+
+/// Arma el DELETE de los permisos de un rol sobre un recurso, o sobre todos si no se indica
+pub fn consulta_delete_permisos(rol: &str, recurso: Option<&Recurso>) -> String {
+    match recurso {
+        Some(recurso) => format!(
+            "DELETE FROM {} WHERE role = {} AND resource = {}",
+            TABLA_PERMISOS,
+            texto(rol),
+            texto(&recurso.nombre())
+        ),
+        None => format!("DELETE FROM {} WHERE role = {}", TABLA_PERMISOS, texto(rol)),
+    }
+}
+
+// Literal de texto con las comillas internas escapadas, para que un nombre no pueda
+// cerrar el literal y cambiar la consulta
+fn texto(valor: &str) -> Termino {
+    Termino::Texto(valor.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocolo::parser_cql::parseo_consulta::procesar_consulta;
+
+    #[test]
+    fn test_nombre_de_rol_con_comillas_queda_dentro_del_literal() {
+        //Arrange
+        let nombre = "x' OR role = 'cassandra";
+
+        //Act
+        let select = consulta_select_rol(nombre);
+        let delete = consulta_delete_permisos(nombre, None);
+
+        //Assert
+        assert_eq!(
+            select,
+            "SELECT * FROM system_auth.roles WHERE role = 'x'' OR role = ''cassandra'"
+        );
+        let consulta = procesar_consulta(&delete).unwrap();
+        assert_eq!(consulta.get_where().relaciones.len(), 1);
+    }
+
+    #[test]
+    fn test_permisos_heredados_del_keyspace() {
+        //Arrange
+        let fila = "consola,SELECT|MODIFY,data/Aerolineas";
+        let permisos = vec![PermisosRecurso::desde_fila(fila).unwrap()];
+        let tabla = Recurso::de_tabla("VUELOS_ORIGEN", "Aerolineas");
+        let otra_tabla = Recurso::de_tabla("Otro.VUELOS_ORIGEN", "Aerolineas");
+
+        //Act
+        let puede_modificar = tiene_permiso(&permisos, &Permiso::Modify, &tabla);
+        let puede_borrar = tiene_permiso(&permisos, &Permiso::Drop, &tabla);
+        let puede_leer_otra = tiene_permiso(&permisos, &Permiso::Select, &otra_tabla);
+
+        //Assert
+        assert!(puede_modificar);
+        assert!(!puede_borrar);
+        assert!(!puede_leer_otra);
+    }
+
+    #[test]
+    fn test_cache_de_permisos_invalidada() {
+        //Arrange
+        let mut cache = CachePermisos::default();
+        let permisos = vec![PermisosRecurso::desde_fila("consola,SELECT,data").unwrap()];
+        cache.guardar("consola", None, permisos.clone());
+
+        //Act
+        let cacheados = cache.obtener("consola");
+        cache.invalidar("consola");
+
+        //Assert
+        assert_eq!(cacheados, Some((None, permisos)));
+        assert_eq!(cache.obtener("consola"), None);
+        assert_eq!(cache.obtener("otro"), None);
+    }
+}
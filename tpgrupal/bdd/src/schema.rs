@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+use protocolo::serial_deserial::intra_nodos::schema::MutacionSchema;
+
+use crate::procesamiento_data::hashear;
+
+/// Historial de las mutaciones del schema que aplicó el nodo, ordenadas por su
+/// identificador. La versión del schema es un hash del historial, así que los
+/// nodos que aplicaron las mismas mutaciones anuncian la misma versión por gossip
+#[derive(Debug, Default)]
+pub struct HistorialSchema {
+    mutaciones: BTreeMap<(i64, String), MutacionSchema>,
+    version: u32,
+}
+
+impl HistorialSchema {
+    /// Versión del schema, que cambia con cada mutación registrada
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn contiene(&self, mutacion: &MutacionSchema) -> bool {
+        self.mutaciones.contains_key(&mutacion.id())
+    }
+
+    /// Registra la mutación y recalcula la versión. Devuelve si no estaba registrada
+    pub fn registrar(&mut self, mutacion: MutacionSchema) -> bool {
+        if self.mutaciones.insert(mutacion.id(), mutacion).is_some() {
+            return false;
+        }
+        let historial = self
+            .mutaciones
+            .values()
+            .map(|mutacion| mutacion.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        self.version = hashear(&historial).unwrap_or_default();
+        true
+    }
+
+    /// Las mutaciones registradas, en el orden en que se aplicaron en el cluster
+    pub fn mutaciones(&self) -> Vec<MutacionSchema> {
+        self.mutaciones.values().cloned().collect()
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use protocolo::serial_deserial::cassandra::eventos::{Evento, TipoEvento};
+
+/// Estructura que contiene a los clientes registrados con REGISTER,
+/// cada uno con los tipos de eventos que quiere recibir y el canal
+/// por el cual se le envían a la conexión que lo atiende
+#[derive(Default)]
+pub struct SuscriptoresEventos {
+    suscriptores: Vec<(Vec<TipoEvento>, Sender<Evento>)>,
+}
+
+impl SuscriptoresEventos {
+    /// Registra un nuevo suscriptor a los tipos de eventos indicados y
+    /// devuelve el extremo del canal por el cual va a recibirlos
+    pub fn suscribir(&mut self, tipos: Vec<TipoEvento>) -> Receiver<Evento> {
+        let (tx, rx) = mpsc::channel();
+        self.suscriptores.push((tipos, tx));
+        rx
+    }
+
+    /// Envía el evento a los suscriptores registrados a su tipo, quitando
+    /// a aquellos cuya conexión ya se cerró
+    pub fn notificar(&mut self, evento: Evento) {
+        let tipo = evento.tipo();
+        self.suscriptores
+            .retain(|(tipos, tx)| !tipos.contains(&tipo) || tx.send(evento.clone()).is_ok());
+    }
+}
@@ -1,270 +1,1847 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use protocolo::parser_cql::condicion_where::CondicionWhere;
+use protocolo::parser_cql::ast::{
+    Asignacion, CambioTabla, DefinicionColumna, Expresion, Operador, Orden, Relacion, Seleccion,
+    Termino,
+};
+use protocolo::parser_cql::condicion_where::{CondicionWhere, CONSULTA_INVALIDA};
+use protocolo::serial_deserial::cassandra::metadata_rows::ColumnaSpec;
+use protocolo::serial_deserial::cassandra::paginacion::Pagina;
+use protocolo::serial_deserial::tipo_columna::{
+    clave_y_valor, elementos_coleccion, TipoColumna, TipoUsuario,
+};
 
-const IGUAL: &str = "=";
-const MAYOR: &str = ">";
-const MAYOR_IGUAL: &str = ">=";
-const MENOR: &str = "<";
-const MENOR_IGUAL: &str = "<=";
+use crate::coleccion::{celda_valor, literal_celda, Coleccion};
+use crate::contador::Contador;
+use crate::procesamiento_data::{paginar_filas, token_particion};
 
 #[derive(Debug)]
 pub struct Tabla {
     pub nombre: String,
     pub headers: Vec<String>,
+    pub tipos: Vec<TipoColumna>, // Tipo CQL de cada header, en el mismo orden
+    pub clustering: Vec<String>, // Columnas de clustering, en el orden de la clave primaria
     pub datos: HashMap<String, Vec<String>>, // K = Partition Key, V = Registro
+    pub escrituras: HashMap<String, Vec<i64>>, // K = Clave primaria de la fila, V = Momento de la última escritura de cada columna
+    pub colecciones: HashMap<String, HashMap<String, Coleccion>>, // K = Clave primaria de la fila, V = Estado de cada columna con una colección
+    pub base: Option<String>, // Tabla base, del mismo keyspace, si es una vista materializada
 }
 
 impl Tabla {
-    pub fn new(nombre: String, headers: Vec<String>) -> Self {
+    pub fn new(nombre: String, headers: Vec<String>, tipos: Vec<TipoColumna>) -> Self {
         Tabla {
             nombre,
             headers,
+            tipos,
+            clustering: vec![],
             datos: HashMap::new(),
+            escrituras: HashMap::new(),
+            colecciones: HashMap::new(),
+            base: None,
         }
     }
 
+    /// La misma tabla con las columnas de clustering de su clave primaria
+    pub fn con_clustering(mut self, clustering: Vec<String>) -> Self {
+        self.clustering = clustering;
+        self
+    }
+
+    /// La misma tabla como vista materializada de la tabla base indicada
+    pub fn con_base(mut self, base: &str) -> Self {
+        self.base = Some(base.to_string());
+        self
+    }
+
+    /// Devuelve el nombre y el tipo de las columnas que devuelve un SELECT
+    /// con la selección recibida, en el mismo orden que `select`
+    pub fn columnas_seleccionadas(&self, seleccion: &Seleccion) -> Vec<ColumnaSpec> {
+        if seleccion.selectores.is_empty() {
+            return self
+                .headers
+                .iter()
+                .map(|header| ColumnaSpec {
+                    nombre: header.to_string(),
+                    tipo: self.tipo(header),
+                })
+                .collect();
+        }
+        seleccion
+            .selectores
+            .iter()
+            .map(|selector| ColumnaSpec {
+                nombre: selector.nombre(),
+                tipo: match &selector.expresion {
+                    Expresion::WriteTime(_) | Expresion::Count(_) => TipoColumna::BigInt,
+                    Expresion::Ttl(_) => TipoColumna::Int,
+                    Expresion::Campo(columna, campo) => match self.tipo(columna) {
+                        TipoColumna::Udt(udt, _) => {
+                            udt.tipo_campo(campo).cloned().unwrap_or(TipoColumna::Text)
+                        }
+                        _ => TipoColumna::Text,
+                    },
+                    expresion => self.tipo(expresion.columna().unwrap_or_default()),
+                },
+            })
+            .collect()
+    }
+
+    fn tipo(&self, columna: &str) -> TipoColumna {
+        self.indice(columna)
+            .and_then(|indice| self.tipos.get(indice).cloned())
+            .unwrap_or(TipoColumna::Text)
+    }
+
+    fn indice(&self, columna: &str) -> Option<usize> {
+        self.headers.iter().position(|header| header == columna)
+    }
+
+    /// Inserta la fila escrita en el momento indicado, registrándolo en sus columnas, y
+    /// la devuelve como quedó en la tabla si no estaba ya. Las colecciones y los UDT sin
+    /// frozen reemplazan a los elementos escritos antes de ese momento
+    pub fn insertar(&mut self, row: String, momento: i64) -> Option<String> {
+        let mut valores = row.split(",").map(String::from).collect::<Vec<String>>();
+        let clave = self.clave_fila(&valores.iter().map(String::as_str).collect::<Vec<&str>>());
+        for (indice, valor) in valores.iter_mut().enumerate() {
+            let Some(tipo) = self
+                .tipos
+                .get(indice)
+                .filter(|tipo| tipo.mezcla_elementos())
+            else {
+                continue;
+            };
+            let tipo = tipo.clone();
+            let coleccion = self.coleccion(&clave, indice, "");
+            coleccion.reemplazar(&tipo, valor, momento);
+            *valor = coleccion.valor(&tipo);
+        }
+        self.escrituras.insert(clave, vec![momento; valores.len()]);
+        let fila = valores.join(",");
+        self.cargar(fila.clone()).then_some(fila)
+    }
+
+    // Estado de la colección de la columna en la fila, que se arma a partir de la celda
+    // si todavía no tiene, como en las filas leídas del disco
+    fn coleccion(&mut self, clave: &str, indice: usize, celda: &str) -> &mut Coleccion {
+        let tipo = &self.tipos[indice];
+        self.colecciones
+            .entry(clave.to_string())
+            .or_default()
+            .entry(self.headers[indice].to_string())
+            .or_insert_with(|| Coleccion::desde_celda(tipo, celda, 0))
+    }
+
     //Nuestro separador es: ','
-    pub fn insertar(&mut self, row: String) {
+    /// Agrega una fila leída del disco, de la que no se conoce el momento en que se
+    /// escribió, y devuelve si no estaba ya en la tabla
+    pub fn cargar(&mut self, row: String) -> bool {
         let registros = row.split(",").collect::<Vec<&str>>();
         let partition_key = registros[0].to_string();
         match self.datos.get_mut(&partition_key) {
             Some(vec) => {
                 if !vec.contains(&row) {
                     vec.push(row.clone());
+                    return true;
                 }
+                false
             }
             None => {
                 let vector_vacio: Vec<String> = vec![row.clone()];
                 self.datos.insert(partition_key, vector_vacio);
+                true
+            }
+        }
+    }
+
+    /// Elimina las filas que cumplen la condición y las devuelve
+    pub fn eliminar(&mut self, condicion: &CondicionWhere) -> Vec<String> {
+        let mut eliminadas = vec![];
+        for particion in self.particiones(condicion) {
+            let Some(filas) = self.datos.remove(&particion) else {
+                continue;
+            };
+            let (borradas, quedan): (Vec<String>, Vec<String>) = filas
+                .into_iter()
+                .partition(|fila| cumple(condicion, &self.headers, fila));
+            for fila in &borradas {
+                let clave = self.clave_fila(&fila.split(",").collect::<Vec<&str>>());
+                self.escrituras.remove(&clave);
+                self.colecciones.remove(&clave);
+            }
+            eliminadas.extend(borradas);
+            if !quedan.is_empty() {
+                self.datos.insert(particion, quedan);
             }
         }
+        eliminadas
     }
 
-    pub fn eliminar(&mut self, condicion: &CondicionWhere) {
-        let condicion_hash = &condicion.condicion1.split(" = ").collect::<Vec<&str>>();
-        let condicion_busqueda = &condicion.condicion2.split(" = ").collect::<Vec<&str>>();
+    /// Aplica las asignaciones, escritas en el momento indicado, a las filas que cumplen
+    /// la condición y las devuelve ya actualizadas. De cada columna queda la escritura
+    /// más reciente, y de cada elemento de una colección también
+    pub fn actualizar(
+        &mut self,
+        condicion: &CondicionWhere,
+        asignaciones: &[Asignacion],
+        momento: i64,
+    ) -> Vec<String> {
+        if !self.indices_contadores().is_empty() {
+            let query = asignaciones
+                .iter()
+                .map(|asignacion| asignacion.to_string())
+                .collect::<Vec<String>>();
+            return self.mezclar_contadores(condicion, &query.join(", "));
+        }
+        let mut actualizadas = vec![];
+        for particion in self.particiones(condicion) {
+            let Some(filas) = self.datos.remove(&particion) else {
+                continue;
+            };
+            let filas = filas
+                .into_iter()
+                .map(|fila| match cumple(condicion, &self.headers, &fila) {
+                    true => {
+                        let fila = self.actualizar_fila(&fila, asignaciones, momento);
+                        actualizadas.push(fila.clone());
+                        fila
+                    }
+                    false => fila,
+                })
+                .collect();
+            self.datos.insert(particion, filas);
+        }
+        actualizadas
+    }
 
-        let partition_key = condicion_hash[1].to_string();
+    fn actualizar_fila(&mut self, fila: &str, asignaciones: &[Asignacion], momento: i64) -> String {
+        let mut valores = fila.split(",").map(String::from).collect::<Vec<String>>();
+        let clave = self.clave_fila(&valores.iter().map(String::as_str).collect::<Vec<&str>>());
+        let escrituras = self.escrituras.get(&clave).cloned().unwrap_or_default();
+        let mut indices = vec![];
+        for asignacion in asignaciones {
+            let Some(indice) = self.indice(&asignacion.columna) else {
+                continue;
+            };
+            let tipo = self.tipos[indice].clone();
+            if tipo.mezcla_elementos() {
+                let coleccion = self.coleccion(&clave, indice, &valores[indice]);
+                coleccion.asignar(&tipo, asignacion, momento);
+                valores[indice] = coleccion.valor(&tipo);
+            } else if escrituras
+                .get(indice)
+                .is_none_or(|escritura| *escritura <= momento)
+            {
+                valores[indice] = celda_valor(&asignacion.valor);
+            } else {
+                continue;
+            }
+            indices.push(indice);
+        }
+        let fila = valores.join(",");
+        self.registrar_escritura(&fila, &indices, momento);
+        fila
+    }
 
-        let filas = self.datos.get_mut(&partition_key);
+    /// Momento de la última escritura de la fila, 0 si no se escribió desde que se leyó
+    /// del disco
+    pub fn ultima_escritura(&self, fila: &str) -> i64 {
+        let clave = self.clave_fila(&fila.split(",").collect::<Vec<&str>>());
+        self.escrituras
+            .get(&clave)
+            .and_then(|escrituras| escrituras.iter().max().copied())
+            .unwrap_or_default()
+    }
 
-        let mut id_index = 1;
-        if partition_key.parse::<usize>().is_err() {
-            id_index = 2;
+    // Registra el momento de la escritura de las columnas indicadas de la fila
+    fn registrar_escritura(&mut self, fila: &str, indices: &[usize], momento: i64) {
+        let valores = fila.split(",").collect::<Vec<&str>>();
+        let clave = self.clave_fila(&valores);
+        let escrituras = self.escrituras.entry(clave).or_default();
+        escrituras.resize(valores.len(), 0);
+        for indice in indices {
+            escrituras[*indice] = escrituras[*indice].max(momento);
         }
+    }
 
-        if let Some(filas) = filas {
-            for (index, fila) in filas.iter().enumerate() {
-                let fila_separada = fila.split(",").collect::<Vec<&str>>();
-                if fila_separada[id_index] == condicion_busqueda[1] {
-                    filas.remove(index);
-                    break;
-                }
+    // Valores de la clave primaria de la fila: la partition key y el clustering
+    fn clave_fila(&self, valores: &[&str]) -> String {
+        self.headers
+            .iter()
+            .zip(valores)
+            .filter(|(header, _)| self.es_clave(header))
+            .map(|(_, valor)| *valor)
+            .collect::<Vec<&str>>()
+            .join(",")
+    }
+
+    /// Posiciones de las columnas de la clave primaria en las filas de la tabla
+    pub fn indices_clave(&self) -> Vec<usize> {
+        self.columnas_clave()
+            .iter()
+            .filter_map(|columna| self.indice(columna))
+            .collect()
+    }
+
+    /// Posiciones de las columnas counter de la tabla
+    pub fn indices_contadores(&self) -> Vec<usize> {
+        self.tipos
+            .iter()
+            .enumerate()
+            .filter(|(_, tipo)| **tipo == TipoColumna::Counter)
+            .map(|(indice, _)| indice)
+            .collect()
+    }
+
+    /// Suma los incrementos al shard del nodo `ip` en los contadores de las filas que
+    /// cumplen la condición, creando la fila si no existe, y devuelve las filas actualizadas
+    pub fn incrementar(
+        &mut self,
+        condicion: &CondicionWhere,
+        incrementos: &[(String, i64)],
+        ip: &str,
+    ) -> Vec<String> {
+        self.modificar_contadores(condicion, |columna, contador| {
+            if let Some((_, delta)) = incrementos.iter().find(|(nombre, _)| nombre == columna) {
+                contador.incrementar(ip, *delta);
             }
-        } else {
-            println!("No se encontro la fila a eliminar.");
-        };
+        })
     }
 
-    pub fn actualizar(&mut self, condicion: &CondicionWhere, query: String) {
-        // En este caso se quieren actualizar todos los campos de una línea
-        let condicion_hash = &condicion.condicion1.split(" = ").collect::<Vec<&str>>();
-        let condicion_busqueda = &condicion.condicion2.split(" = ").collect::<Vec<&str>>();
+    // Mezcla en los contadores el estado ("columna = shards") que envía el nodo que
+    // lideró el incremento
+    fn mezclar_contadores(&mut self, condicion: &CondicionWhere, query: &str) -> Vec<String> {
+        let estados = query
+            .split(", ")
+            .filter_map(|asignacion| asignacion.split_once(" = "))
+            .map(|(columna, celda)| (columna.trim().to_string(), Contador::desde_celda(celda)))
+            .collect::<Vec<(String, Contador)>>();
+        self.modificar_contadores(condicion, |columna, contador| {
+            if let Some((_, estado)) = estados.iter().find(|(nombre, _)| nombre == columna) {
+                contador.mezclar(estado);
+            }
+        })
+    }
 
-        let partition_key = condicion_hash[1].to_string();
+    // Las filas de una tabla con contadores se crean con el primer incremento
+    fn modificar_contadores(
+        &mut self,
+        condicion: &CondicionWhere,
+        modificar: impl Fn(&str, &mut Contador),
+    ) -> Vec<String> {
+        // La partición puede estar escrita con o sin comillas
+        let valor = condicion.valor(self.clave_particion()).unwrap_or_default();
+        let partition_key = self
+            .datos
+            .keys()
+            .find(|particion| comparar(particion, &valor) == Some(Ordering::Equal))
+            .cloned()
+            .unwrap_or(valor);
+        let indices = self.indices_contadores();
+        let fila_nueva = self.fila_nueva(condicion, &indices);
+        let headers = &self.headers;
+        let filas = self.datos.entry(partition_key).or_default();
+        if !filas.iter().any(|fila| cumple(condicion, headers, fila)) {
+            filas.push(fila_nueva);
+        }
+        let mut actualizadas = vec![];
+        for fila in filas
+            .iter_mut()
+            .filter(|fila| cumple(condicion, headers, fila))
+        {
+            let mut valores = fila.split(",").map(String::from).collect::<Vec<String>>();
+            for indice in &indices {
+                let mut contador = Contador::desde_celda(&valores[*indice]);
+                modificar(&headers[*indice], &mut contador);
+                valores[*indice] = contador.a_celda();
+            }
+            *fila = valores.join(",");
+            actualizadas.push(fila.clone());
+        }
+        actualizadas
+    }
+
+    // Fila con las columnas de la clave que indica la condición y los contadores en cero
+    fn fila_nueva(&self, condicion: &CondicionWhere, indices: &[usize]) -> String {
+        self.headers
+            .iter()
+            .enumerate()
+            .map(|(indice, header)| match indices.contains(&indice) {
+                true => Contador::default().a_celda(),
+                false => condicion.valor(header).unwrap_or_default(),
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// Condición que identifica a la fila por su clave primaria: la partition key y
+    /// las columnas de clustering, con los valores escritos como literales de su tipo
+    pub fn condicion_clave(&self, row: &str) -> CondicionWhere {
+        let relaciones = self
+            .headers
+            .iter()
+            .zip(&self.tipos)
+            .zip(row.split(","))
+            .filter(|((header, _), _)| self.es_clave(header))
+            .map(|((header, tipo), valor)| Relacion {
+                columna: header.to_string(),
+                token: false,
+                operador: Operador::Igual,
+                valor: termino_celda(tipo, valor),
+            })
+            .collect();
+        CondicionWhere::new(relaciones)
+    }
+
+    /// Devuelve las filas que cumplen la condición con las columnas de la selección:
+    /// las particiones en el orden de su token y las filas de cada una en el de las
+    /// columnas de clustering (o el del ORDER BY), respetando DISTINCT y los límites.
+    /// Si la selección agrupa, devuelve el resultado parcial de cada grupo, que el
+    /// coordinador combina con `combinar_grupos`
+    pub fn select(&self, condicion: &CondicionWhere, seleccion: &Seleccion) -> Vec<String> {
+        self.select_en(self.particiones(condicion), condicion, seleccion)
+    }
 
-        let mut id_index = 0;
-        if partition_key.parse::<usize>().is_err() {
-            id_index = 2;
+    /// Página de un recorrido de todas las particiones: las primeras `tamanio` filas
+    /// del resultado que están después de la posición `desde`, más una si quedan otras,
+    /// para que el coordinador sepa si el recorrido sigue. Con las filas completas se
+    /// saltean las particiones cuyo token es anterior a la posición
+    pub fn select_pagina(
+        &self,
+        condicion: &CondicionWhere,
+        seleccion: &Seleccion,
+        pagina: &Pagina,
+    ) -> Result<Vec<String>, String> {
+        let mut particiones = self.particiones(condicion);
+        if let Some(desde) = pagina
+            .desde
+            .as_ref()
+            .filter(|_| seleccion.filas_completas())
+        {
+            particiones.retain(|particion| {
+                token_particion(particion).is_ok_and(|token| token >= desde.token)
+            });
         }
+        let filas = self.select_en(particiones, condicion, seleccion);
+        let (filas, _) = paginar_filas(filas, pagina.tamanio as i32 + 1, pagina.desde.as_ref())?;
+        Ok(filas)
+    }
 
-        let registros = query.split(", ").collect::<Vec<&str>>();
+    fn select_en(
+        &self,
+        mut particiones: Vec<String>,
+        condicion: &CondicionWhere,
+        seleccion: &Seleccion,
+    ) -> Vec<String> {
+        particiones.sort_by_key(|particion| token_particion(particion).unwrap_or_default());
+        let mut resultado = vec![];
 
-        if let Some(filas) = self.datos.get_mut(&partition_key) {
-            for fila in filas {
-                let fila_separada = fila.split(",").collect::<Vec<&str>>();
-                if fila_separada[id_index] == condicion_busqueda[1] {
-                    let mut fila_actualizada = fila_separada.clone();
-                    for registro in &registros {
-                        let registro_split = registro.trim().split(" = ").collect::<Vec<&str>>();
-                        let header_index = self
-                            .headers
+        for particion in particiones {
+            let mut filas = self
+                .datos
+                .get(&particion)
+                .into_iter()
+                .flatten()
+                .filter(|linea| cumple(condicion, &self.headers, linea))
+                .map(|linea| linea.split(",").collect::<Vec<&str>>())
+                .collect::<Vec<Vec<&str>>>();
+            filas.sort_by(|una, otra| self.comparar_filas(una, otra, &seleccion.orden));
+            if seleccion.distinct {
+                filas.truncate(1);
+            }
+            let mut proyectadas = match seleccion.agrupa() {
+                true => self.agrupar(&filas, seleccion),
+                false => filas
+                    .iter()
+                    .map(|fila| self.proyectar(fila, seleccion))
+                    .collect(),
+            };
+            if let Some(limite) = seleccion.limite_por_particion {
+                proyectadas.truncate(limite);
+            }
+            resultado.extend(proyectadas);
+        }
+        // Sin GROUP BY los resultados parciales son uno por partición y todos forman
+        // parte de la única fila que devuelve la consulta
+        if let Some(limite) = seleccion.limite.filter(|_| !seleccion.agrupa()) {
+            resultado.truncate(limite);
+        }
+        resultado
+    }
+
+    // Resultado parcial de cada grupo de filas de una partición: los valores de las
+    // columnas que identifican al grupo seguidos del estado de cada selector
+    fn agrupar(&self, filas: &[Vec<&str>], seleccion: &Seleccion) -> Vec<String> {
+        let indices = self
+            .columnas_grupo(seleccion)
+            .iter()
+            .filter_map(|columna| self.indice(columna))
+            .collect::<Vec<usize>>();
+        let mut grupos: Vec<(Vec<&str>, Vec<&[&str]>)> = vec![];
+        for fila in filas {
+            let clave = indices
+                .iter()
+                .map(|indice| fila.get(*indice).copied().unwrap_or_default())
+                .collect::<Vec<&str>>();
+            match grupos.iter_mut().find(|(otra, _)| *otra == clave) {
+                Some((_, filas_grupo)) => filas_grupo.push(fila),
+                None => grupos.push((clave, vec![fila])),
+            }
+        }
+        let expresiones = self.expresiones(seleccion);
+        grupos
+            .iter()
+            .map(|(clave, filas_grupo)| {
+                let estados = expresiones
+                    .iter()
+                    .map(|expresion| self.estado_parcial(expresion, filas_grupo));
+                let clave = clave.iter().map(|valor| valor.to_string());
+                clave.chain(estados).collect::<Vec<String>>().join(",")
+            })
+            .collect()
+    }
+
+    // Estado de un selector sobre las filas de un grupo: la cantidad de filas para
+    // COUNT, el mínimo o el máximo, la suma, la suma y la cantidad de valores para AVG
+    // y el valor de la primera fila para los demás
+    fn estado_parcial(&self, expresion: &Expresion, filas: &[&[&str]]) -> String {
+        let valores = expresion
+            .columna()
+            .and_then(|columna| self.indice(columna))
+            .map(|indice| {
+                filas
+                    .iter()
+                    .filter_map(|fila| fila.get(indice).copied())
+                    .filter(|valor| !valor.trim().is_empty())
+                    .collect::<Vec<&str>>()
+            })
+            .unwrap_or_default();
+        let tipo = self.tipo(expresion.columna().unwrap_or_default());
+        match expresion {
+            Expresion::Count(None) => filas.len().to_string(),
+            Expresion::Count(_) => valores.len().to_string(),
+            Expresion::Min(_) => extremo(&valores, Ordering::Less),
+            Expresion::Max(_) => extremo(&valores, Ordering::Greater),
+            Expresion::Sum(_) => sumar(&tipo, &valores),
+            Expresion::Avg(_) => format!("{},{}", sumar(&tipo, &valores), valores.len()),
+            _ => filas
+                .first()
+                .map(|fila| self.valor_selector(fila, expresion))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Combina los resultados parciales que el coordinador junta de las réplicas: se
+    /// queda con uno por grupo, ya que varias réplicas devuelven los de una misma
+    /// partición, y calcula el valor final de cada selector
+    pub fn combinar_grupos(&self, filas: Vec<String>, seleccion: &Seleccion) -> Vec<String> {
+        let largo_clave = self.columnas_grupo(seleccion).len();
+        let mut parciales: Vec<(String, Vec<String>)> = vec![];
+        for fila in filas {
+            let valores = fila.split(",").collect::<Vec<&str>>();
+            let (clave, estados) = valores.split_at(largo_clave.min(valores.len()));
+            let clave = clave.join(",");
+            if parciales.iter().all(|(otra, _)| *otra != clave) {
+                parciales.push((clave, estados.iter().map(|e| e.to_string()).collect()));
+            }
+        }
+        let estados = parciales.iter().map(|(_, estados)| estados);
+        if !seleccion.agrupamiento.is_empty() {
+            // Cada grupo está en una sola partición
+            return estados
+                .map(|estado| self.valores_finales(seleccion, &[estado]))
+                .collect();
+        }
+        vec![self.valores_finales(seleccion, &estados.collect::<Vec<&Vec<String>>>())]
+    }
+
+    // Valores de una fila del resultado a partir de los estados parciales de su grupo
+    fn valores_finales(&self, seleccion: &Seleccion, estados: &[&Vec<String>]) -> String {
+        let mut posicion = 0;
+        self.expresiones(seleccion)
+            .iter()
+            .map(|expresion| {
+                let parciales = estados
+                    .iter()
+                    .filter_map(|estado| estado.get(posicion))
+                    .map(String::as_str)
+                    .collect::<Vec<&str>>();
+                let tipo = self.tipo(expresion.columna().unwrap_or_default());
+                posicion += 1;
+                match expresion {
+                    Expresion::Count(_) => sumar(&TipoColumna::BigInt, &parciales),
+                    Expresion::Min(_) => extremo(&parciales, Ordering::Less),
+                    Expresion::Max(_) => extremo(&parciales, Ordering::Greater),
+                    Expresion::Sum(_) => sumar(&tipo, &parciales),
+                    Expresion::Avg(_) => {
+                        let cantidades = estados
                             .iter()
-                            .position(|h| h == registro_split[0])
-                            .unwrap();
-                        fila_actualizada[header_index] = registro_split[1];
+                            .filter_map(|estado| estado.get(posicion))
+                            .map(String::as_str)
+                            .collect::<Vec<&str>>();
+                        posicion += 1;
+                        promedio(&tipo, &parciales, &cantidades)
                     }
-                    let fila_actualizada = fila_actualizada.join(",");
-                    *fila = fila_actualizada;
+                    _ => parciales.first().unwrap_or(&"").to_string(),
                 }
-            }
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    // Columnas cuyos valores identifican a un grupo: las del GROUP BY o, si no hay,
+    // la partition key, para que el coordinador descarte los de réplicas repetidas
+    fn columnas_grupo(&self, seleccion: &Seleccion) -> Vec<String> {
+        match seleccion.agrupamiento.is_empty() {
+            true => vec![self.clave_particion().to_string()],
+            false => seleccion.agrupamiento.clone(),
         }
     }
 
-    pub fn select(&self, condicion: &CondicionWhere, query: String) -> Vec<String> {
-        let columnas_a_imprimir = detectar_columnas(&self.headers, query);
-        let mut rows_seleccionadas: Vec<Vec<&str>> = vec![];
+    // Expresiones de la selección, con todas las columnas de la tabla si es *
+    fn expresiones(&self, seleccion: &Seleccion) -> Vec<Expresion> {
+        match seleccion.selectores.is_empty() {
+            true => self
+                .headers
+                .iter()
+                .map(|header| Expresion::Columna(header.to_string()))
+                .collect(),
+            false => seleccion
+                .selectores
+                .iter()
+                .map(|selector| selector.expresion.clone())
+                .collect(),
+        }
+    }
 
-        if condicion.condicion1.is_empty() {
-            for (_particion, datos) in self.datos.iter() {
-                for linea in datos.iter() {
-                    let linea_separada = linea.split(",").collect::<Vec<&str>>();
-                    rows_seleccionadas.push(linea_separada);
-                }
-            }
-        } else {
-            for (_particion, datos) in self.datos.iter() {
-                for linea in datos.iter() {
-                    let linea_separada = linea.split(",").collect::<Vec<&str>>();
-                    if verificar_condiciones(
-                        &condicion.condicion1,
-                        &condicion.condicion2,
-                        &linea_separada,
-                        &self.headers,
-                    ) {
-                        rows_seleccionadas.push(linea_separada);
+    /// Ordena según el ORDER BY las filas ya proyectadas que el coordinador junta de
+    /// varias particiones
+    pub fn ordenar_resultado(&self, filas: &mut [String], seleccion: &Seleccion) {
+        let posiciones = seleccion
+            .orden
+            .iter()
+            .filter_map(|(columna, orden)| {
+                self.posicion_seleccionada(seleccion, columna)
+                    .map(|posicion| (posicion, *orden))
+            })
+            .collect::<Vec<(usize, Orden)>>();
+        filas.sort_by(|una, otra| {
+            let una = una.split(",").collect::<Vec<&str>>();
+            let otra = otra.split(",").collect::<Vec<&str>>();
+            posiciones
+                .iter()
+                .map(|(posicion, orden)| {
+                    let orden_valores = comparar_posicion(&una, &otra, *posicion);
+                    match orden {
+                        Orden::Ascendente => orden_valores,
+                        Orden::Descendente => orden_valores.reverse(),
                     }
+                })
+                .find(|orden| orden.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+
+    /// Posición de la columna en las filas del resultado del SELECT, si se la selecciona
+    pub fn posicion_seleccionada(&self, seleccion: &Seleccion, columna: &str) -> Option<usize> {
+        match seleccion.selectores.is_empty() {
+            true => self.indice(columna),
+            false => seleccion
+                .selectores
+                .iter()
+                .position(|selector| selector.expresion == Expresion::Columna(columna.to_string())),
+        }
+    }
+
+    // Las filas de una partición se ordenan por sus columnas de clustering, al revés
+    // si el ORDER BY es descendente
+    fn comparar_filas(&self, una: &[&str], otra: &[&str], orden: &[(String, Orden)]) -> Ordering {
+        let orden_clustering = self
+            .clustering
+            .iter()
+            .filter_map(|columna| self.indice(columna))
+            .map(|indice| comparar_posicion(una, otra, indice))
+            .find(|orden| orden.is_ne())
+            .unwrap_or(Ordering::Equal);
+        match orden.first() {
+            Some((_, Orden::Descendente)) => orden_clustering.reverse(),
+            _ => orden_clustering,
+        }
+    }
+
+    // Valores de la fila para cada selector
+    fn proyectar(&self, fila: &[&str], seleccion: &Seleccion) -> String {
+        if seleccion.selectores.is_empty() {
+            return fila.join(",");
+        }
+        seleccion
+            .selectores
+            .iter()
+            .map(|selector| self.valor_selector(fila, &selector.expresion))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    // WRITETIME es el momento en microsegundos de la última escritura de la columna y
+    // TTL es siempre nulo, porque las columnas no expiran. El campo de un UDT es nulo si
+    // el UDT no tiene valor en ese campo
+    fn valor_selector(&self, fila: &[&str], expresion: &Expresion) -> String {
+        let indice = expresion.columna().and_then(|columna| self.indice(columna));
+        match expresion {
+            Expresion::Campo(_, campo) => indice
+                .and_then(|indice| fila.get(indice))
+                .and_then(|valor| {
+                    elementos_coleccion(valor)
+                        .into_iter()
+                        .filter_map(clave_y_valor)
+                        .find(|(nombre, _)| nombre == campo)
+                })
+                .map(|(_, valor)| valor.to_string())
+                .unwrap_or_default(),
+            Expresion::WriteTime(_) => indice
+                .and_then(|indice| self.escrituras.get(&self.clave_fila(fila))?.get(indice))
+                .filter(|momento| **momento > 0)
+                .map(|momento| momento.to_string())
+                .unwrap_or_default(),
+            Expresion::Ttl(_) => String::new(),
+            _ => indice
+                .and_then(|indice| fila.get(indice))
+                .map(|valor| valor.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Verifica que el WHERE de un SELECT se pueda resolver leyendo solo las filas
+    /// que pide, o que tenga ALLOW FILTERING: la partition key restringida con = o
+    /// IN y las columnas de clustering en orden, sin saltear ninguna y con un rango
+    /// solo en la última que se restringe
+    pub fn validar_select(
+        &self,
+        condicion: &CondicionWhere,
+        seleccion: &Seleccion,
+        permitir_filtrado: bool,
+    ) -> Result<(), String> {
+        self.validar_columnas(condicion)?;
+        self.validar_seleccion(seleccion)?;
+        self.validar_agrupamiento(seleccion)?;
+        self.validar_orden(condicion, seleccion)?;
+        match self.columna_a_filtrar(condicion) {
+            Some(columna) if !permitir_filtrado => Err(invalida(&format!(
+                "la restricción sobre {} obliga a filtrar filas, lo que requiere ALLOW FILTERING",
+                columna
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // Las columnas de la selección tienen que existir; DISTINCT solo admite las de la
+    // partition key y WRITETIME y TTL solo las que no son de la clave primaria
+    fn validar_seleccion(&self, seleccion: &Seleccion) -> Result<(), String> {
+        if seleccion.distinct && seleccion.selectores.is_empty() {
+            return Err(invalida(
+                "SELECT DISTINCT tiene que indicar las columnas de la partition key",
+            ));
+        }
+        for selector in &seleccion.selectores {
+            let columna = selector
+                .expresion
+                .columna()
+                .unwrap_or(self.clave_particion());
+            if self.indice(columna).is_none() {
+                return Err(self.columna_inexistente(columna));
+            }
+            let es_clave = columna == self.clave_particion()
+                || self
+                    .clustering
+                    .iter()
+                    .any(|clustering| clustering == columna);
+            match &selector.expresion {
+                Expresion::Columna(columna) if columna == self.clave_particion() => {}
+                _ if seleccion.distinct => {
+                    return Err(invalida(
+                        "SELECT DISTINCT solo admite las columnas de la partition key",
+                    ))
+                }
+                Expresion::WriteTime(_) | Expresion::Ttl(_) if es_clave => {
+                    return Err(invalida(&format!(
+                        "no se puede pedir {} de una columna de la clave primaria",
+                        selector.expresion
+                    )))
                 }
+                Expresion::Campo(columna, campo) => {
+                    self.validar_campo(columna, campo)?;
+                }
+                _ => {}
             }
         }
-        imprimir_lineas(&columnas_a_imprimir, rows_seleccionadas)
+        Ok(())
     }
-}
 
-fn verificar_condiciones(
-    cond1: &str,
-    cond2: &str,
-    datos_linea: &[&str],
-    c_tabla: &[String],
-) -> bool {
-    if cond2.is_empty() {
-        verificar_condicion(cond1, datos_linea, c_tabla)
-    } else {
-        verificar_condicion(cond1, datos_linea, c_tabla)
-            && verificar_condicion(cond2, datos_linea, c_tabla)
+    // Verifica que la columna sea de un UDT que tiene el campo, y devuelve su tipo
+    fn validar_campo(&self, columna: &str, campo: &str) -> Result<TipoColumna, String> {
+        match self.tipo(columna) {
+            TipoColumna::Udt(udt, _) => udt.tipo_campo(campo).cloned().ok_or_else(|| {
+                invalida(&format!(
+                    "el tipo {} no tiene el campo {}",
+                    udt.nombre, campo
+                ))
+            }),
+            tipo => Err(invalida(&format!(
+                "la columna {} de tipo {} no tiene campos",
+                columna, tipo
+            ))),
+        }
+    }
+
+    // GROUP BY solo sobre un prefijo de la clave primaria, SUM y AVG solo sobre
+    // columnas numéricas y ninguna agregación en las tablas de contadores, cuyas
+    // filas se reparten en shards entre las réplicas
+    fn validar_agrupamiento(&self, seleccion: &Seleccion) -> Result<(), String> {
+        if !seleccion.agrupa() {
+            return Ok(());
+        }
+        if self.tipos.contains(&TipoColumna::Counter) {
+            return Err(invalida(
+                "las funciones de agregación y GROUP BY no se admiten en tablas de contadores",
+            ));
+        }
+        if !seleccion.orden.is_empty() {
+            return Err(invalida(
+                "ORDER BY no se admite con funciones de agregación ni GROUP BY",
+            ));
+        }
+        let clave_primaria = [self.clave_particion().to_string()]
+            .into_iter()
+            .chain(self.clustering.iter().cloned());
+        if seleccion.agrupamiento.len() > self.clustering.len() + 1
+            || !seleccion
+                .agrupamiento
+                .iter()
+                .zip(clave_primaria)
+                .all(|(a, b)| *a == b)
+        {
+            return Err(invalida("GROUP BY solo admite un prefijo de la clave primaria, empezando por la partition key"));
+        }
+        let no_numerica = seleccion.selectores.iter().find(|selector| {
+            matches!(selector.expresion, Expresion::Sum(_) | Expresion::Avg(_))
+                && !es_numerico(&self.tipo(selector.expresion.columna().unwrap_or_default()))
+        });
+        match no_numerica {
+            Some(selector) => Err(invalida(&format!(
+                "{} solo se puede calcular sobre columnas numéricas",
+                selector.expresion
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // ORDER BY solo sobre una partición o un IN de particiones, con las columnas de
+    // clustering en el orden de la clave primaria y todas en el mismo sentido
+    fn validar_orden(
+        &self,
+        condicion: &CondicionWhere,
+        seleccion: &Seleccion,
+    ) -> Result<(), String> {
+        if seleccion.orden.is_empty() {
+            return Ok(());
+        }
+        let Some(particiones) = condicion.valores(self.clave_particion()) else {
+            return Err(invalida(
+                "ORDER BY solo se admite si la partition key se restringe con = o IN",
+            ));
+        };
+        let en_orden = seleccion.orden.len() <= self.clustering.len()
+            && seleccion.orden.iter().zip(&self.clustering).all(
+                |((columna, orden), clustering)| {
+                    columna == clustering && *orden == seleccion.orden[0].1
+                },
+            );
+        if !en_orden {
+            return Err(invalida("ORDER BY solo admite las columnas de clustering en el orden de la clave primaria, todas ASC o todas DESC"));
+        }
+        let seleccionadas = seleccion.selectores.is_empty()
+            || seleccion.orden.iter().all(|(columna, _)| {
+                seleccion
+                    .selectores
+                    .iter()
+                    .any(|selector| selector.expresion == Expresion::Columna(columna.to_string()))
+            });
+        if particiones.len() > 1 && !seleccionadas {
+            return Err(invalida("con IN en la partition key las columnas del ORDER BY tienen que estar en el SELECT"));
+        }
+        Ok(())
     }
-}
 
-fn detectar_operador(condicion: &str) -> &str {
-    let caracteres: Vec<char> = condicion.chars().collect();
-    let mut operador: &str = "";
-    for (index, e) in caracteres.iter().enumerate() {
-        match e {
-            '=' => {
-                operador = IGUAL;
-                break;
+    /// Verifica que un UPDATE o DELETE restrinja la partition key a un único valor
+    pub fn validar_escritura(&self, condicion: &CondicionWhere) -> Result<(), String> {
+        self.validar_columnas(condicion)?;
+        if condicion.valor(self.clave_particion()).is_none()
+            || condicion.relaciones.iter().any(|relacion| relacion.token)
+        {
+            return Err(invalida(&format!(
+                "un UPDATE o DELETE debe restringir la partition key {} con =",
+                self.clave_particion()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Verifica que los valores de un INSERT para las colecciones, las tuplas y los UDT
+    /// sean literales de su tipo, con elementos que correspondan a los tipos de sus
+    /// elementos
+    pub fn validar_valores(&self, columnas: &[String], valores: &[Termino]) -> Result<(), String> {
+        for (columna, valor) in columnas.iter().zip(valores) {
+            let tipo = self.tipo(columna);
+            if !tipo.es_simple() {
+                validar_literal(&tipo, valor)?;
             }
-            '>' => {
-                match caracteres[index + 1] {
-                    '=' => operador = MAYOR_IGUAL,
-                    _ => operador = MAYOR,
+        }
+        Ok(())
+    }
+
+    /// Verifica que las asignaciones de un UPDATE, o lo que borra un DELETE, sean de
+    /// columnas de la tabla, que solo se asignen elementos de maps y listas y campos de
+    /// UDT sin frozen, y que las colecciones, las tuplas y los UDT reciban literales de
+    /// su tipo
+    pub fn validar_asignaciones(&self, asignaciones: &[Asignacion]) -> Result<(), String> {
+        if !self.indices_contadores().is_empty() {
+            return Ok(());
+        }
+        for asignacion in asignaciones {
+            let tipo = self
+                .indice(&asignacion.columna)
+                .map(|indice| &self.tipos[indice])
+                .ok_or_else(|| self.columna_inexistente(&asignacion.columna))?;
+            let (tipo_literal, literal) = match (&asignacion.elemento, &asignacion.valor, tipo) {
+                (Some(elemento), valor, tipo) => {
+                    validar_elemento(tipo, elemento, valor)?;
+                    continue;
                 }
-                break;
-            }
-            '<' => {
-                match caracteres[index + 1] {
-                    '=' => operador = MENOR_IGUAL,
-                    _ => operador = MENOR,
+                (None, valor, tipo) if asignacion.campo.is_some() => {
+                    self.validar_asignacion_campo(asignacion, tipo, valor)?;
+                    continue;
+                }
+                (None, Termino::Agregado(_, literal), tipo) if tipo.es_coleccion() => {
+                    (tipo.clone(), literal.as_ref())
+                }
+                (None, Termino::Antepuesto(literal, _), TipoColumna::List(_)) => {
+                    (tipo.clone(), literal.as_ref())
                 }
-                break;
+                (None, Termino::Quitado(_, literal), TipoColumna::Map(clave, _)) => {
+                    (TipoColumna::Set(clave.clone()), literal.as_ref())
+                }
+                (None, Termino::Quitado(_, literal), tipo) if tipo.es_coleccion() => {
+                    (tipo.clone(), literal.as_ref())
+                }
+                (
+                    None,
+                    Termino::Agregado(..) | Termino::Quitado(..) | Termino::Antepuesto(..),
+                    _,
+                ) => {
+                    return Err(invalida(&format!(
+                        "no se pueden agregar ni quitar elementos de la columna {} de tipo {}",
+                        asignacion.columna, tipo
+                    )))
+                }
+                (None, valor, tipo) if !tipo.es_simple() => (tipo.clone(), valor),
+                _ => continue,
+            };
+            validar_literal(&tipo_literal, literal)?;
+        }
+        Ok(())
+    }
+
+    // Solo se asignan o se borran campos de un UDT sin frozen, con valores de su tipo
+    fn validar_asignacion_campo(
+        &self,
+        asignacion: &Asignacion,
+        tipo: &TipoColumna,
+        valor: &Termino,
+    ) -> Result<(), String> {
+        let campo = asignacion.campo.as_deref().unwrap_or_default();
+        let tipo_campo = self.validar_campo(&asignacion.columna, campo)?;
+        if !tipo.mezcla_elementos() {
+            return Err(invalida(&format!(
+                "no se puede asignar el campo {} de la columna {} de tipo {}",
+                campo, asignacion.columna, tipo
+            )));
+        }
+        if *valor != Termino::Nulo {
+            tipo_campo
+                .serializar_valor(&valor.to_string())
+                .map_err(|error| invalida(&error))?;
+        }
+        Ok(())
+    }
+
+    fn validar_columnas(&self, condicion: &CondicionWhere) -> Result<(), String> {
+        for relacion in &condicion.relaciones {
+            if !self.headers.contains(&relacion.columna) {
+                return Err(self.columna_inexistente(&relacion.columna));
+            }
+            if relacion.token && relacion.columna != self.clave_particion() {
+                return Err(invalida(&format!(
+                    "token() solo se puede aplicar a la partition key {}",
+                    self.clave_particion()
+                )));
             }
-            _ => continue,
         }
+        Ok(())
     }
-    operador
-}
 
-fn verificar_condicion(cond: &str, datos_linea: &[&str], c_tabla: &[String]) -> bool {
-    let oper = detectar_operador(cond);
-    let mut cond_splited: Vec<&str> = cond.split(oper).collect();
-    cond_splited[0] = cond_splited[0].trim();
-    cond_splited[1] = cond_splited[1].trim();
+    fn columna_inexistente(&self, columna: &str) -> String {
+        invalida(&format!(
+            "la columna {} no existe en la tabla {}",
+            columna, self.nombre
+        ))
+    }
 
-    let mut algo_final: (&str, &str, &str) = ("", "", "");
-    algo_final.1 = oper;
-    for (index, e) in c_tabla.iter().enumerate() {
-        if e == cond_splited[0] {
-            algo_final.0 = datos_linea[index];
+    // Primera columna cuya restricción no se puede resolver con la clave primaria
+    fn columna_a_filtrar(&self, condicion: &CondicionWhere) -> Option<String> {
+        let clave = self.clave_particion();
+        let operadores = |columna: &str| {
+            condicion
+                .relaciones
+                .iter()
+                .filter(|relacion| relacion.columna == columna && !relacion.token)
+                .map(|relacion| relacion.operador)
+                .collect::<Vec<Operador>>()
+        };
+        let es_igualdad = |operador: &Operador| matches!(operador, Operador::Igual | Operador::In);
+        if !operadores(clave).iter().all(es_igualdad) {
+            return Some(clave.to_string());
         }
-        if e == cond_splited[1] {
-            algo_final.2 = datos_linea[index];
+        // Mientras las columnas anteriores estén fijadas se puede seguir restringiendo
+        let mut prefijo = condicion.valores(clave).is_some();
+        for columna in &self.clustering {
+            let operadores = operadores(columna);
+            if operadores.is_empty() {
+                prefijo = false;
+                continue;
+            }
+            let igualdad = operadores.iter().any(es_igualdad);
+            if !prefijo || !(igualdad || operadores.iter().all(Operador::es_rango)) {
+                return Some(columna.to_string());
+            }
+            prefijo = igualdad;
         }
+        condicion
+            .relaciones
+            .iter()
+            .find(|relacion| {
+                relacion.columna != clave && !self.clustering.contains(&relacion.columna)
+            })
+            .map(|relacion| relacion.columna.to_string())
     }
-    if algo_final.0.is_empty() {
-        algo_final.0 = cond_splited[0];
+
+    /// Nombre de la partition key, la primera columna de la tabla
+    pub fn clave_particion(&self) -> &str {
+        self.headers.first().map(String::as_str).unwrap_or_default()
     }
-    if algo_final.2.is_empty() {
-        algo_final.2 = cond_splited[1];
+
+    /// Columnas de la clave primaria: la partition key y las de clustering
+    pub fn columnas_clave(&self) -> Vec<String> {
+        let mut columnas = vec![self.clave_particion().to_string()];
+        columnas.extend(self.clustering.iter().cloned());
+        columnas
     }
-    check_info(algo_final)
-}
 
-fn check_info(info: (&str, &str, &str)) -> bool {
-    let _a = info.0.parse::<isize>().ok();
-    let _b = info.2.parse::<isize>().ok();
-    if _a.is_some() && _b.is_some() {
-        match info.1 {
-            IGUAL => _a == _b,
-            MAYOR => _a > _b,
-            MAYOR_IGUAL => _a >= _b,
-            MENOR => _a < _b,
-            MENOR_IGUAL => _a <= _b,
-            _ => false,
+    /// Indica si la columna es de la clave primaria: la partition key o una de clustering
+    pub fn es_clave(&self, columna: &str) -> bool {
+        columna == self.clave_particion() || self.clustering.iter().any(|c| c == columna)
+    }
+
+    /// Sentencia CREATE TABLE que define la tabla en el keyspace, como la muestra un
+    /// DESCRIBE, o CREATE MATERIALIZED VIEW si es una vista
+    pub fn sentencia_create(&self, keyspace: &str) -> String {
+        if let Some(base) = &self.base {
+            return self.sentencia_create_vista(keyspace, base);
         }
-    } else {
-        match info.1 {
-            IGUAL => info.0 == info.2,
-            MAYOR => info.0 > info.2,
-            MAYOR_IGUAL => info.0 >= info.2,
-            MENOR => info.0 < info.2,
-            MENOR_IGUAL => info.0 <= info.2,
-            _ => false,
+        let mut definiciones: Vec<String> = self
+            .headers
+            .iter()
+            .zip(&self.tipos)
+            .map(|(columna, tipo)| format!("    {} {}", columna, tipo))
+            .collect();
+        let clave = [format!("({})", self.clave_particion())]
+            .into_iter()
+            .chain(self.clustering.iter().cloned());
+        definiciones.push(format!(
+            "    PRIMARY KEY ({})",
+            clave.collect::<Vec<String>>().join(", ")
+        ));
+        format!(
+            "CREATE TABLE {}.{} (\n{}\n);",
+            keyspace,
+            self.nombre,
+            definiciones.join(",\n")
+        )
+    }
+
+    fn sentencia_create_vista(&self, keyspace: &str, base: &str) -> String {
+        let clave = [format!("({})", self.clave_particion())]
+            .into_iter()
+            .chain(self.clustering.iter().cloned())
+            .collect::<Vec<String>>();
+        let no_nulas = [self.clave_particion().to_string()]
+            .iter()
+            .chain(&self.clustering)
+            .map(|columna| format!("{} IS NOT NULL", columna))
+            .collect::<Vec<String>>();
+        format!(
+            "CREATE MATERIALIZED VIEW {}.{} AS\n    SELECT {}\n    FROM {}.{}\n    WHERE {}\n    PRIMARY KEY ({});",
+            keyspace,
+            self.nombre,
+            self.headers.join(", "),
+            keyspace,
+            base,
+            no_nulas.join(" AND "),
+            clave.join(", ")
+        )
+    }
+
+    /// Aplica el cambio de un ALTER TABLE a las columnas de la tabla y a sus filas. Las
+    /// columnas que se agregan pueden ser de los UDT indicados, los de su keyspace
+    pub fn alterar(&mut self, cambio: &CambioTabla, udts: &[TipoUsuario]) -> Result<(), String> {
+        match cambio {
+            CambioTabla::Agregar(columnas) => self.agregar_columnas(columnas, udts),
+            CambioTabla::Quitar(columnas) => self.quitar_columnas(columnas),
+            CambioTabla::Renombrar(renombres) => self.renombrar_columnas(renombres),
+        }
+    }
+
+    // Agrega las columnas al final de la tabla, sin valor en las filas que ya tenía. En
+    // una tabla de contadores todas las columnas fuera de la clave primaria son counter
+    fn agregar_columnas(
+        &mut self,
+        columnas: &[DefinicionColumna],
+        udts: &[TipoUsuario],
+    ) -> Result<(), String> {
+        let contadores = !self.indices_contadores().is_empty();
+        let mut nuevas = vec![];
+        for columna in columnas {
+            if self.indice(&columna.nombre).is_some() {
+                return Err(invalida(&format!(
+                    "la columna {} ya existe en la tabla {}",
+                    columna.nombre, self.nombre
+                )));
+            }
+            let tipo = TipoColumna::create_con_udts(&columna.tipo, udts)?;
+            if (tipo == TipoColumna::Counter) != contadores {
+                return Err(invalida(
+                    "una tabla no puede mezclar columnas counter con columnas de otros tipos",
+                ));
+            }
+            nuevas.push((columna.nombre.to_string(), tipo));
+        }
+        for filas in self.datos.values_mut() {
+            for fila in filas {
+                fila.push_str(&",".repeat(nuevas.len()));
+            }
+        }
+        for (columna, tipo) in nuevas {
+            self.headers.push(columna);
+            self.tipos.push(tipo);
+        }
+        Ok(())
+    }
+
+    // Quita las columnas, que no pueden ser de la clave primaria, y sus valores de las filas
+    fn quitar_columnas(&mut self, columnas: &[String]) -> Result<(), String> {
+        let mut indices = vec![];
+        for columna in columnas {
+            let indice = self.indice(columna).ok_or_else(|| {
+                invalida(&format!(
+                    "la columna {} no existe en la tabla {}",
+                    columna, self.nombre
+                ))
+            })?;
+            if self.es_clave(columna) {
+                return Err(invalida(&format!(
+                    "no se puede quitar la columna {} de la clave primaria",
+                    columna
+                )));
+            }
+            indices.push(indice);
+        }
+        for filas in self.datos.values_mut() {
+            for fila in filas {
+                let valores = sin_indices(&fila.split(",").collect::<Vec<&str>>(), &indices);
+                *fila = valores.join(",");
+            }
+        }
+        for escrituras in self.escrituras.values_mut() {
+            *escrituras = sin_indices(escrituras, &indices);
         }
+        for colecciones in self.colecciones.values_mut() {
+            colecciones.retain(|columna, _| !columnas.contains(columna));
+        }
+        self.headers = sin_indices(&self.headers, &indices);
+        self.tipos = sin_indices(&self.tipos, &indices);
+        Ok(())
+    }
+
+    // Renombra columnas de la clave primaria, las únicas que se pueden renombrar
+    fn renombrar_columnas(&mut self, renombres: &[(String, String)]) -> Result<(), String> {
+        let (mut headers, mut clustering) = (self.headers.clone(), self.clustering.clone());
+        for (columna, nueva) in renombres {
+            let indice = headers.iter().position(|header| header == columna);
+            let Some(indice) = indice.filter(|_| self.es_clave(columna)) else {
+                return Err(invalida(&format!(
+                    "solo se pueden renombrar las columnas de la clave primaria y {} no lo es",
+                    columna
+                )));
+            };
+            if headers.contains(nueva) {
+                return Err(invalida(&format!(
+                    "la columna {} ya existe en la tabla {}",
+                    nueva, self.nombre
+                )));
+            }
+            headers[indice] = nueva.to_string();
+            if let Some(posicion) = clustering.iter().position(|c| c == columna) {
+                clustering[posicion] = nueva.to_string();
+            }
+        }
+        (self.headers, self.clustering) = (headers, clustering);
+        Ok(())
+    }
+
+    /// Devuelve completas las filas que cumplen la condición
+    pub fn filas(&self, condicion: &CondicionWhere) -> Vec<String> {
+        self.particiones(condicion)
+            .iter()
+            .filter_map(|particion| self.datos.get(particion))
+            .flatten()
+            .filter(|fila| cumple(condicion, &self.headers, fila))
+            .cloned()
+            .collect()
+    }
+
+    /// Elimina todas las filas de la tabla, que conserva sus columnas
+    pub fn vaciar(&mut self) {
+        self.datos.clear();
+        self.escrituras.clear();
+        self.colecciones.clear();
     }
+
+    // Particiones en las que puede estar una fila que cumple la condición: las de los
+    // valores de la partition key si la restringe con = o IN, o todas si no
+    fn particiones(&self, condicion: &CondicionWhere) -> Vec<String> {
+        let valores = condicion.valores(self.clave_particion());
+        self.datos
+            .keys()
+            .filter(|particion| match &valores {
+                Some(valores) => valores
+                    .iter()
+                    .any(|valor| comparar(particion, valor) == Some(Ordering::Equal)),
+                None => true,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Indica si la fila cumple todas las relaciones de la condición
+pub fn cumple(condicion: &CondicionWhere, headers: &[String], fila: &str) -> bool {
+    let valores = fila.split(",").collect::<Vec<&str>>();
+    condicion.relaciones.iter().all(|relacion| {
+        headers
+            .iter()
+            .position(|header| *header == relacion.columna)
+            .and_then(|indice| valores.get(indice))
+            .is_some_and(|valor| cumple_relacion(relacion, valor))
+    })
+}
+
+fn cumple_relacion(relacion: &Relacion, valor: &str) -> bool {
+    let orden = |otro: &Termino| comparar(valor, &otro.to_string());
+    match (&relacion.operador, &relacion.valor) {
+        _ if relacion.token => match (
+            token_particion(valor),
+            relacion.valor.to_string().parse::<i64>(),
+        ) {
+            (Ok(token), Ok(limite)) => {
+                cumple_orden(&relacion.operador, (token as i64).cmp(&limite))
+            }
+            _ => false,
+        },
+        (Operador::In, Termino::Tupla(valores)) => valores
+            .iter()
+            .any(|otro| orden(otro) == Some(Ordering::Equal)),
+        (Operador::Contains, otro) => elementos_coleccion(valor)
+            .iter()
+            .any(|elemento| comparar(elemento, &otro.to_string()) == Some(Ordering::Equal)),
+        (Operador::ContainsKey, otro) => elementos_coleccion(valor)
+            .iter()
+            .filter_map(|elemento| clave_y_valor(elemento))
+            .any(|(clave, _)| comparar(clave, &otro.to_string()) == Some(Ordering::Equal)),
+        (operador, otro) => orden(otro).is_some_and(|orden| cumple_orden(operador, orden)),
+    }
+}
+
+fn cumple_orden(operador: &Operador, orden: Ordering) -> bool {
+    match operador {
+        Operador::Igual | Operador::In => orden == Ordering::Equal,
+        Operador::Distinto => orden != Ordering::Equal,
+        Operador::Menor => orden == Ordering::Less,
+        Operador::MenorIgual => orden != Ordering::Greater,
+        Operador::Mayor => orden == Ordering::Greater,
+        Operador::MayorIgual => orden != Ordering::Less,
+        Operador::Contains | Operador::ContainsKey => false,
+    }
+}
+
+// Compara los valores de dos filas en la misma posición
+fn comparar_posicion(una: &[&str], otra: &[&str], posicion: usize) -> Ordering {
+    let valor = |fila: &[&str]| {
+        fila.get(posicion)
+            .map(|valor| valor.to_string())
+            .unwrap_or_default()
+    };
+    comparar(&valor(una), &valor(otra)).unwrap_or(Ordering::Equal)
+}
+
+// El menor o el mayor de los valores no nulos, o nulo si no hay ninguno
+fn extremo(valores: &[&str], orden: Ordering) -> String {
+    valores
+        .iter()
+        .filter(|valor| !valor.trim().is_empty())
+        .fold(None, |elegido: Option<&str>, valor| match elegido {
+            Some(elegido) if comparar(valor, elegido) != Some(orden) => Some(elegido),
+            _ => Some(valor),
+        })
+        .unwrap_or_default()
+        .to_string()
 }
 
-fn detectar_columnas(c_tabla: &[String], columnas_query: String) -> Vec<usize> {
-    let z: Vec<&str> = columnas_query.split(',').collect();
-    let mut colums: Vec<String> = vec![];
-    for e in z.iter() {
-        if !e.is_empty() {
-            colums.push(e.trim().replace(['(', ')', ',', '\"', '\'', '\n'], ""));
+// Suma los valores como enteros o como decimales según el tipo de la columna
+fn sumar(tipo: &TipoColumna, valores: &[&str]) -> String {
+    let numeros = valores
+        .iter()
+        .map(|valor| valor.trim().trim_matches('\''))
+        .filter(|valor| !valor.is_empty());
+    match es_entero(tipo) {
+        true => numeros
+            .filter_map(|valor| valor.parse::<i64>().ok())
+            .sum::<i64>()
+            .to_string(),
+        false => numeros
+            .filter_map(|valor| valor.parse::<f64>().ok())
+            .sum::<f64>()
+            .to_string(),
+    }
+}
+
+// Promedio a partir de las sumas y las cantidades parciales, del mismo tipo que la
+// columna como en Cassandra: el de una columna entera se trunca
+fn promedio(tipo: &TipoColumna, sumas: &[&str], cantidades: &[&str]) -> String {
+    let suma = sumar(tipo, sumas);
+    let cantidad = cantidades
+        .iter()
+        .filter_map(|cantidad| cantidad.parse::<i64>().ok())
+        .sum::<i64>();
+    if cantidad == 0 {
+        return "0".to_string();
+    }
+    match es_entero(tipo) {
+        true => (suma.parse::<i64>().unwrap_or_default() / cantidad).to_string(),
+        false => (suma.parse::<f64>().unwrap_or_default() / cantidad as f64).to_string(),
+    }
+}
+
+fn es_entero(tipo: &TipoColumna) -> bool {
+    matches!(tipo, TipoColumna::Int | TipoColumna::BigInt)
+}
+
+fn es_numerico(tipo: &TipoColumna) -> bool {
+    es_entero(tipo) || matches!(tipo, TipoColumna::Float | TipoColumna::Double)
+}
+
+// Los elementos salvo los de las posiciones indicadas
+fn sin_indices<T: Clone>(elementos: &[T], indices: &[usize]) -> Vec<T> {
+    elementos
+        .iter()
+        .enumerate()
+        .filter(|(indice, _)| !indices.contains(indice))
+        .map(|(_, elemento)| elemento.clone())
+        .collect()
+}
+
+// Verifica que el literal sea una colección, una tupla o un UDT del tipo indicado, o
+// null; {} es un map vacío pero también sirve como set vacío y como UDT sin valores
+fn validar_literal(tipo: &TipoColumna, literal: &Termino) -> Result<(), String> {
+    let corresponde = match (tipo, literal) {
+        (TipoColumna::List(_), Termino::Lista(_))
+        | (TipoColumna::Set(_), Termino::Conjunto(_))
+        | (TipoColumna::Map(_, _), Termino::Mapa(_))
+        | (TipoColumna::Udt(..), Termino::Mapa(_))
+        | (TipoColumna::Tuple(_), Termino::Tupla(_))
+        | (_, Termino::Nulo) => true,
+        (TipoColumna::Set(_), Termino::Mapa(pares)) => pares.is_empty(),
+        _ => false,
+    };
+    if !corresponde {
+        return Err(invalida(&format!(
+            "el valor {} no corresponde al tipo {}",
+            literal, tipo
+        )));
+    }
+    tipo.serializar_valor(&celda_valor(literal))
+        .map(|_| ())
+        .map_err(|error| invalida(&error))
+}
+
+// Verifica la asignación de un elemento: la clave y el valor de un map, o la posición
+// y el valor de una lista
+fn validar_elemento(tipo: &TipoColumna, elemento: &Termino, valor: &Termino) -> Result<(), String> {
+    let (tipo_elemento, tipo_valor) = match tipo {
+        TipoColumna::Map(clave, valor) => (clave.as_ref(), valor.as_ref()),
+        TipoColumna::List(valor) => (&TipoColumna::Int, valor.as_ref()),
+        _ => {
+            return Err(invalida(&format!(
+                "solo se pueden asignar elementos de un map o de una lista, no de {}",
+                tipo
+            )))
         }
+    };
+    let mut valores = vec![(tipo_elemento, elemento)];
+    if *valor != Termino::Nulo {
+        valores.push((tipo_valor, valor));
+    }
+    for (tipo, valor) in valores {
+        tipo.serializar_valor(&valor.to_string())
+            .map_err(|error| invalida(&error))?;
     }
-    obtener_indices_y_nombres_columnas(colums, c_tabla)
+    Ok(())
 }
 
-fn imprimir_lineas(indices_colums: &[usize], lineas: Vec<Vec<&str>>) -> Vec<String> {
-    let mut res: Vec<String> = vec![];
-    for datos_linea in lineas {
-        let mut linea_imprimir: Vec<&str> = vec![];
-        for i in indices_colums {
-            if i < &datos_linea.len() {
-                linea_imprimir.push(datos_linea[*i]);
+/// Mensaje de error de una consulta inválida, que se le informa al cliente
+/// Literal CQL con el que se vuelve a escribir una celda de una columna del tipo
+/// indicado: null si está vacía, los textos entre comillas (con las comillas internas
+/// duplicadas, se hayan escrito o no entre comillas) y las colecciones, tuplas y UDT
+/// con sus elementos separados por comas
+pub fn termino_celda(tipo: &TipoColumna, celda: &str) -> Termino {
+    match celda.trim() {
+        "" => Termino::Nulo,
+        celda if !tipo.es_simple() => Termino::SinComillas(literal_celda(celda)),
+        celda if matches!(tipo, TipoColumna::Text | TipoColumna::Ascii) => {
+            match celda.len() > 1 && celda.starts_with('\'') && celda.ends_with('\'') {
+                true => Termino::Texto(celda[1..celda.len() - 1].replace("''", "'")),
+                false => Termino::Texto(celda.to_string()),
             }
         }
-        res.push(linea_imprimir.join(","));
+        celda => Termino::SinComillas(celda.to_string()),
     }
-    res
 }
 
-fn obtener_indices_y_nombres_columnas(colums: Vec<String>, c_tabla: &[String]) -> Vec<usize> {
-    let mut indices_colums: Vec<usize> = vec![];
-    let mut todas_las_columnas = false;
-    if colums.len() == 1 && &colums[0] == "*" {
-        todas_las_columnas = true;
-    }
-    for e in colums {
-        if c_tabla.contains(&e) || todas_las_columnas {
-            for (index, c) in c_tabla.iter().enumerate() {
-                if todas_las_columnas || *c == e {
-                    indices_colums.push(index);
-                }
+pub fn invalida(mensaje: &str) -> String {
+    format!("{}: {}.", CONSULTA_INVALIDA, mensaje)
+}
+
+// Momento actual en microsegundos, como los timestamps de escritura de Cassandra
+pub fn ahora() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duracion| duracion.as_micros() as i64)
+        .unwrap_or_default()
+}
+
+/// Compara los valores sin comillas y, si ambos son números, por su valor numérico
+pub fn comparar(valor: &str, otro: &str) -> Option<Ordering> {
+    let valor = valor.trim().trim_matches('\'');
+    let otro = otro.trim().trim_matches('\'');
+    match (valor.parse::<f64>(), otro.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b),
+        _ => Some(valor.cmp(otro)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocolo::parser_cql::ast::Sentencia;
+    use protocolo::parser_cql::parseo_consulta::procesar_consulta;
+    use protocolo::serial_deserial::cassandra::paginacion::EstadoPaginacion;
+
+    fn vuelos() -> Tabla {
+        let headers = ["ORIGEN", "ID_VUELO", "DESTINO"];
+        let tipos = vec![TipoColumna::Text, TipoColumna::Int, TipoColumna::Text];
+        let mut tabla = Tabla::new(
+            "VUELOS".to_string(),
+            headers.iter().map(|header| header.to_string()).collect(),
+            tipos,
+        )
+        .con_clustering(vec!["ID_VUELO".to_string()]);
+        for (indice, origen) in ["AEP", "EZE", "COR", "MDZ", "BRC"].iter().enumerate() {
+            for id in 0..3 {
+                tabla.insertar(format!("{},{},SCL", origen, indice * 3 + id), 1);
+            }
+        }
+        tabla
+    }
+
+    // Valida y ejecuta en la tabla un SELECT escrito en CQL, como lo hace una réplica
+    fn consultar(tabla: &Tabla, consulta_cql: &str) -> Result<Vec<String>, String> {
+        let consulta = procesar_consulta(consulta_cql)?;
+        let Sentencia::Select {
+            seleccion,
+            permitir_filtrado,
+            ..
+        } = consulta.get_sentencia()
+        else {
+            return Err("La consulta no es un SELECT.".to_string());
+        };
+        tabla.validar_select(consulta.get_where(), seleccion, *permitir_filtrado)?;
+        Ok(tabla.select(consulta.get_where(), seleccion))
+    }
+
+    #[test]
+    fn test_select_con_palabras_reservadas_dentro_de_los_valores_y_los_nombres() {
+        //Arrange
+        let mut tabla = Tabla::new(
+            "WHEREHOUSE".to_string(),
+            vec!["ORIGEN".to_string(), "ID_VUELO".to_string()],
+            vec![TipoColumna::Text, TipoColumna::Int],
+        )
+        .con_clustering(vec!["ID_VUELO".to_string()]);
+        tabla.insertar("SANDY AND CO,1".to_string(), 1);
+        tabla.insertar("SANDY,2".to_string(), 1);
+        let consulta_cql = "SELECT ID_VUELO FROM WHEREHOUSE WHERE ORIGEN = 'SANDY AND CO'";
+
+        //Act
+        let consulta = procesar_consulta(consulta_cql).unwrap();
+        let filas = consultar(&tabla, consulta_cql).unwrap();
+
+        //Assert
+        assert_eq!(consulta.get_tabla(), "WHEREHOUSE");
+        assert_eq!(filas, ["1"]);
+    }
+
+    #[test]
+    fn test_select_rango_sobre_el_clustering_de_una_particion() {
+        //Arrange
+        let tabla = vuelos();
+
+        //Act
+        let filas = consultar(
+            &tabla,
+            "SELECT * FROM VUELOS WHERE ORIGEN = 'EZE' AND ID_VUELO > 3 AND ID_VUELO <= 5",
+        )
+        .unwrap();
+
+        //Assert
+        assert_eq!(filas, ["EZE,4,SCL", "EZE,5,SCL"]);
+    }
+
+    #[test]
+    fn test_select_in_lee_cada_particion_y_cada_fila_pedida() {
+        //Arrange
+        let tabla = vuelos();
+
+        //Act
+        let mut particiones = consultar(
+            &tabla,
+            "SELECT * FROM VUELOS WHERE ORIGEN IN ('AEP', 'BRC', 'USH')",
+        )
+        .unwrap();
+        let filas = consultar(
+            &tabla,
+            "SELECT ID_VUELO FROM VUELOS WHERE ORIGEN = 'COR' AND ID_VUELO IN (8, 6)",
+        )
+        .unwrap();
+
+        //Assert
+        particiones.sort();
+        assert_eq!(
+            particiones,
+            [
+                "AEP,0,SCL",
+                "AEP,1,SCL",
+                "AEP,2,SCL",
+                "BRC,12,SCL",
+                "BRC,13,SCL",
+                "BRC,14,SCL"
+            ]
+        );
+        assert_eq!(filas, ["6", "8"]);
+    }
+
+    #[test]
+    fn test_select_que_filtra_filas_requiere_allow_filtering() {
+        //Arrange
+        let tabla = vuelos();
+
+        //Act
+        let sin_permiso = consultar(&tabla, "SELECT * FROM VUELOS WHERE DESTINO = 'SCL'");
+        let salteando_clustering = consultar(&tabla, "SELECT * FROM VUELOS WHERE ID_VUELO = 4");
+        let rango_de_particiones = consultar(&tabla, "SELECT * FROM VUELOS WHERE ORIGEN > 'AEP'");
+        let con_permiso = consultar(
+            &tabla,
+            "SELECT * FROM VUELOS WHERE DESTINO = 'SCL' ALLOW FILTERING",
+        );
+
+        //Assert
+        assert!(sin_permiso.is_err());
+        assert!(salteando_clustering.is_err());
+        assert!(rango_de_particiones.is_err());
+        assert_eq!(con_permiso.unwrap().len(), 15);
+    }
+
+    #[test]
+    fn test_order_by_desc_ordena_las_filas_de_varias_particiones() {
+        //Arrange
+        let tabla = vuelos();
+        let consulta_cql = "SELECT ORIGEN, ID_VUELO FROM VUELOS WHERE ORIGEN IN ('AEP', 'EZE') ORDER BY ID_VUELO DESC";
+        let seleccion = procesar_consulta(consulta_cql).unwrap().get_seleccion();
+
+        //Act
+        let mut filas = consultar(&tabla, consulta_cql).unwrap();
+        tabla.ordenar_resultado(&mut filas, &seleccion);
+
+        //Assert
+        assert_eq!(
+            filas,
+            ["EZE,5", "EZE,4", "EZE,3", "AEP,2", "AEP,1", "AEP,0"]
+        );
+        assert!(consultar(&tabla, "SELECT * FROM VUELOS ORDER BY ID_VUELO DESC").is_err());
+    }
+
+    #[test]
+    fn test_per_partition_limit_se_aplica_a_cada_particion() {
+        //Arrange
+        let tabla = vuelos();
+
+        //Act
+        let mut primeras =
+            consultar(&tabla, "SELECT ID_VUELO FROM VUELOS PER PARTITION LIMIT 1").unwrap();
+        let limitadas = consultar(
+            &tabla,
+            "SELECT ID_VUELO FROM VUELOS PER PARTITION LIMIT 2 LIMIT 3",
+        )
+        .unwrap();
+
+        //Assert
+        primeras.sort_by_key(|id| id.parse::<i32>().unwrap_or_default());
+        assert_eq!(primeras, ["0", "3", "6", "9", "12"]);
+        // Cada partición tiene tres vuelos seguidos: dos de la primera y uno de la siguiente
+        let particiones: Vec<i32> = limitadas
+            .iter()
+            .map(|id| id.parse::<i32>().unwrap_or_default() / 3)
+            .collect();
+        assert_eq!(particiones.len(), 3);
+        assert_eq!(particiones[0], particiones[1]);
+        assert_ne!(particiones[1], particiones[2]);
+    }
+
+    fn combustible() -> Tabla {
+        let headers = ["ORIGEN", "ID_VUELO", "COMBUSTIBLE"];
+        let tipos = vec![TipoColumna::Text, TipoColumna::Int, TipoColumna::Double];
+        let mut tabla = Tabla::new(
+            "VUELOS".to_string(),
+            headers.iter().map(|header| header.to_string()).collect(),
+            tipos,
+        )
+        .con_clustering(vec!["ID_VUELO".to_string()]);
+        for fila in ["AEP,1,10", "AEP,2,20", "AEP,3,30", "EZE,4,100"] {
+            tabla.insertar(fila.to_string(), 1);
+        }
+        tabla
+    }
+
+    // Resultados parciales de dos réplicas con las mismas particiones, en distinto orden
+    fn parciales_de_dos_replicas(tabla: &Tabla, consulta_cql: &str) -> (Vec<String>, Seleccion) {
+        let parciales = consultar(tabla, consulta_cql).unwrap();
+        let mut replicados = parciales.clone();
+        replicados.extend(parciales.into_iter().rev());
+        let seleccion = procesar_consulta(consulta_cql).unwrap().get_seleccion();
+        (replicados, seleccion)
+    }
+
+    #[test]
+    fn test_combinar_grupos_pondera_el_promedio_de_cada_particion() {
+        //Arrange
+        let tabla = combustible();
+        let (parciales, seleccion) = parciales_de_dos_replicas(
+            &tabla,
+            "SELECT COUNT(*), AVG(COMBUSTIBLE), AVG(ID_VUELO) FROM VUELOS",
+        );
+
+        //Act
+        let resultado = tabla.combinar_grupos(parciales, &seleccion);
+
+        //Assert
+        // (10 + 20 + 30 + 100) / 4, no el promedio de 20 y 100; el de enteros se trunca
+        assert_eq!(resultado, ["4,40,2"]);
+    }
+
+    #[test]
+    fn test_combinar_grupos_con_group_by_devuelve_una_fila_por_grupo() {
+        //Arrange
+        let tabla = combustible();
+        let (parciales, seleccion) = parciales_de_dos_replicas(
+            &tabla,
+            "SELECT ORIGEN, COUNT(COMBUSTIBLE), AVG(COMBUSTIBLE), MAX(COMBUSTIBLE) FROM VUELOS GROUP BY ORIGEN",
+        );
+
+        //Act
+        let mut resultado = tabla.combinar_grupos(parciales, &seleccion);
+
+        //Assert
+        resultado.sort();
+        assert_eq!(resultado, ["AEP,3,20,30", "EZE,1,100,100"]);
+    }
+
+    // Tabla con la posición de cada vuelo como UDT sin frozen (POS) y con frozen (ULTIMA)
+    fn posiciones() -> Tabla {
+        let udt = TipoUsuario {
+            keyspace: "Aerolineas".to_string(),
+            nombre: "posicion".to_string(),
+            campos: vec![
+                ("lat".to_string(), TipoColumna::Double),
+                ("lon".to_string(), TipoColumna::Double),
+            ],
+        };
+        let mut tabla = Tabla::new(
+            "POSICIONES".to_string(),
+            vec![
+                "ID_VUELO".to_string(),
+                "POS".to_string(),
+                "ULTIMA".to_string(),
+            ],
+            vec![
+                TipoColumna::Int,
+                TipoColumna::Udt(udt.clone(), false),
+                TipoColumna::Udt(udt, true),
+            ],
+        );
+        tabla.insertar("1,{lat:1.5;lon:2.5},{lat:1.5;lon:2.5}".to_string(), 1);
+        tabla
+    }
+
+    // Valida y aplica en la tabla un UPDATE escrito en CQL en el momento indicado
+    fn actualizar(tabla: &mut Tabla, consulta_cql: &str, momento: i64) -> Result<(), String> {
+        let consulta = procesar_consulta(consulta_cql)?;
+        let Sentencia::Update { asignaciones, .. } = consulta.get_sentencia() else {
+            return Err("La consulta no es un UPDATE.".to_string());
+        };
+        tabla.validar_asignaciones(asignaciones)?;
+        tabla.actualizar(consulta.get_where(), asignaciones, momento);
+        Ok(())
+    }
+
+    #[test]
+    fn test_udt_sin_frozen_se_actualiza_campo_por_campo() {
+        //Arrange
+        let mut tabla = posiciones();
+
+        //Act
+        actualizar(
+            &mut tabla,
+            "UPDATE POSICIONES SET POS.lat = 10.5 WHERE ID_VUELO = 1",
+            10,
+        )
+        .unwrap();
+        actualizar(
+            &mut tabla,
+            "UPDATE POSICIONES SET POS.lon = 20.5 WHERE ID_VUELO = 1",
+            5,
+        )
+        .unwrap();
+        actualizar(
+            &mut tabla,
+            "UPDATE POSICIONES SET POS.lat = 0.5 WHERE ID_VUELO = 1",
+            8,
+        )
+        .unwrap();
+
+        //Assert
+        // La escritura del campo lat en el momento 8 es anterior a la del momento 10
+        let filas = consultar(&tabla, "SELECT POS FROM POSICIONES WHERE ID_VUELO = 1").unwrap();
+        assert_eq!(filas, ["{lat:10.5;lon:20.5}"]);
+    }
+
+    #[test]
+    fn test_udt_frozen_solo_se_reemplaza_entero() {
+        //Arrange
+        let mut tabla = posiciones();
+
+        //Act
+        let campo = actualizar(
+            &mut tabla,
+            "UPDATE POSICIONES SET ULTIMA.lat = 10.5 WHERE ID_VUELO = 1",
+            10,
+        );
+        actualizar(
+            &mut tabla,
+            "UPDATE POSICIONES SET ULTIMA = {lat: 3.5} WHERE ID_VUELO = 1",
+            10,
+        )
+        .unwrap();
+        actualizar(
+            &mut tabla,
+            "UPDATE POSICIONES SET ULTIMA = {lat: 7.5, lon: 8.5} WHERE ID_VUELO = 1",
+            5,
+        )
+        .unwrap();
+
+        //Assert
+        // El reemplazo no conserva el campo lon, y el del momento 5 es anterior
+        let filas = consultar(&tabla, "SELECT ULTIMA FROM POSICIONES WHERE ID_VUELO = 1").unwrap();
+        assert!(campo.is_err());
+        assert_eq!(filas, ["{lat:3.5}"]);
+    }
+
+    #[test]
+    fn test_select_pagina_devuelve_a_lo_sumo_una_fila_de_mas() {
+        //Arrange
+        let tabla = vuelos();
+        let pagina = Pagina {
+            tamanio: 4,
+            desde: None,
+        };
+
+        //Act
+        let filas = tabla
+            .select_pagina(&CondicionWhere::default(), &Seleccion::default(), &pagina)
+            .unwrap();
+
+        //Assert
+        assert_eq!(filas.len(), 5);
+    }
+
+    #[test]
+    fn test_recorrer_por_paginas_devuelve_cada_fila_una_vez() {
+        //Arrange
+        let tabla = vuelos();
+        let mut desde: Option<EstadoPaginacion> = None;
+        let mut recorridas = vec![];
+
+        //Act
+        loop {
+            let pagina = Pagina {
+                tamanio: 4,
+                desde: desde.clone(),
+            };
+            let filas = tabla
+                .select_pagina(&CondicionWhere::default(), &Seleccion::default(), &pagina)
+                .unwrap();
+            let (filas, siguiente) = paginar_filas(filas, 4, desde.as_ref()).unwrap();
+            recorridas.extend(filas);
+            match siguiente {
+                Some(siguiente) => desde = Some(siguiente),
+                None => break,
             }
         }
+
+        //Assert
+        let mut esperadas = tabla.select(&CondicionWhere::default(), &Seleccion::default());
+        esperadas.sort();
+        recorridas.sort();
+        assert_eq!(recorridas, esperadas);
+        assert_eq!(recorridas.len(), 15);
     }
-    indices_colums
 }
 
 /* #[cfg(test)]
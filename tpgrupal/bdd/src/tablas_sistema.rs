@@ -0,0 +1,336 @@
+use protocolo::serial_deserial::cassandra::metadata_rows::ColumnaSpec;
+use protocolo::serial_deserial::tipo_columna::TipoColumna;
+
+use seguridad::certificados::VencimientoCertificado;
+use seguridad::recarga::vencimientos_certificados;
+
+use crate::keyspace::Keyspace;
+use crate::node_status::NodeStatus;
+use crate::nodo::Nodo;
+use crate::procesamiento_data::hashear;
+use crate::tabla::Tabla;
+
+pub const KEYSPACE_SYSTEM: &str = "system";
+pub const KEYSPACE_SYSTEM_SCHEMA: &str = "system_schema";
+
+const NOMBRE_CLUSTER: &str = "Test Cluster";
+const DATACENTER: &str = "datacenter1";
+const RACK: &str = "rack1";
+const PARTICIONADOR: &str = "Murmur3Partitioner";
+// Las tablas de sistema no se replican: cada nodo responde con su propio estado
+const ESTRATEGIA_LOCAL: &str = "LocalStrategy";
+
+type DefinicionTabla = (
+    &'static str,
+    &'static [(&'static str, &'static str)],
+    &'static [&'static str],
+);
+
+// Nombre, columnas con el nombre CQL de su tipo (la primera es la partition key) y
+// columnas de clustering
+const LOCAL: DefinicionTabla = (
+    "local",
+    &[
+        ("key", "text"),
+        ("cluster_name", "text"),
+        ("data_center", "text"),
+        ("rack", "text"),
+        ("release_version", "text"),
+        ("partitioner", "text"),
+        ("broadcast_address", "inet"),
+        ("listen_address", "inet"),
+        ("rpc_address", "inet"),
+        ("schema_version", "uuid"),
+        ("tokens", "set<text>"),
+        ("certificados", "map<text, int>"),
+    ],
+    &[],
+);
+const PEERS: DefinicionTabla = (
+    "peers",
+    &[
+        ("peer", "inet"),
+        ("data_center", "text"),
+        ("rack", "text"),
+        ("release_version", "text"),
+        ("rpc_address", "inet"),
+        ("schema_version", "uuid"),
+        ("status", "text"),
+        ("tokens", "set<text>"),
+    ],
+    &[],
+);
+const KEYSPACES: DefinicionTabla = (
+    "keyspaces",
+    &[
+        ("keyspace_name", "text"),
+        ("durable_writes", "boolean"),
+        ("replication", "map<text, text>"),
+    ],
+    &[],
+);
+const TABLES: DefinicionTabla = (
+    "tables",
+    &[("keyspace_name", "text"), ("table_name", "text")],
+    &["table_name"],
+);
+const VIEWS: DefinicionTabla = (
+    "views",
+    &[
+        ("keyspace_name", "text"),
+        ("view_name", "text"),
+        ("base_table_name", "text"),
+    ],
+    &["view_name"],
+);
+const COLUMNS: DefinicionTabla = (
+    "columns",
+    &[
+        ("keyspace_name", "text"),
+        ("table_name", "text"),
+        ("column_name", "text"),
+        ("clustering_order", "text"),
+        ("kind", "text"),
+        ("position", "int"),
+        ("type", "text"),
+    ],
+    &["table_name", "column_name"],
+);
+
+/// Indica si el keyspace es uno de los de sistema, cuyas tablas son de solo lectura
+pub fn es_keyspace_sistema(keyspace: &str) -> bool {
+    keyspace == KEYSPACE_SYSTEM || keyspace == KEYSPACE_SYSTEM_SCHEMA
+}
+
+/// Crea los keyspaces system y system_schema con sus tablas vacías
+pub fn keyspaces_sistema() -> Vec<Keyspace> {
+    [
+        (KEYSPACE_SYSTEM, vec![LOCAL, PEERS]),
+        (
+            KEYSPACE_SYSTEM_SCHEMA,
+            vec![KEYSPACES, TABLES, VIEWS, COLUMNS],
+        ),
+    ]
+    .into_iter()
+    .map(|(nombre, definiciones)| {
+        let mut keyspace = Keyspace::new(nombre.to_string(), 1, ESTRATEGIA_LOCAL.to_string());
+        for definicion in definiciones {
+            let tabla = crear_tabla(definicion);
+            keyspace.tablas.insert(tabla.nombre.to_string(), tabla);
+        }
+        keyspace
+    })
+    .collect()
+}
+
+fn crear_tabla((nombre, columnas, clustering): DefinicionTabla) -> Tabla {
+    let headers = columnas.iter().map(|(columna, _)| columna.to_string());
+    // Los tipos de las definiciones son válidos, así que nunca se usa el texto
+    let tipos = columnas
+        .iter()
+        .map(|(_, tipo)| TipoColumna::create(tipo).unwrap_or(TipoColumna::Text));
+    let clustering = clustering.iter().map(|columna| columna.to_string());
+    Tabla::new(nombre.to_string(), headers.collect(), tipos.collect())
+        .con_clustering(clustering.collect())
+}
+
+/// Devuelve las filas de cada tabla de sistema ("keyspace.tabla") según el estado
+/// actual del nodo: sus datos y los de los nodos que conoce por gossip, y la
+/// definición de sus keyspaces, tablas, vistas materializadas y columnas
+pub fn filas_sistema(nodo: &Nodo) -> Vec<(String, Vec<String>)> {
+    // Si no se pueden leer los certificados la columna queda vacía
+    let vencimientos = vencimientos_certificados().unwrap_or_default();
+    let local = fila_local(
+        &nodo.ip,
+        (nodo.historial_schema.version(), nodo.token),
+        &vencimientos,
+    );
+    let peers = nodo
+        .metadata_nodos
+        .iter()
+        .filter(|(ip, _)| **ip != nodo.ip)
+        .map(|(ip, endpoint_data)| {
+            let estado = match endpoint_data.application_state.status {
+                NodeStatus::Normal => "UP",
+                _ => "DOWN",
+            };
+            format!(
+                "{},{},{},{},{},{},{},{}",
+                ip,
+                DATACENTER,
+                RACK,
+                env!("CARGO_PKG_VERSION"),
+                ip,
+                uuid_schema(endpoint_data.application_state.schema),
+                estado,
+                tokens(hashear(ip).unwrap_or_default())
+            )
+        })
+        .collect();
+    let mut keyspaces = vec![];
+    let mut tablas = vec![];
+    let mut vistas = vec![];
+    let mut columnas = vec![];
+    for keyspace in nodo.keyspaces.values() {
+        keyspaces.push(format!(
+            "{},true,{{'class':'{}';'replication_factor':'{}'}}",
+            keyspace.nombre, keyspace.strategy, keyspace.nivel_replicacion
+        ));
+        for tabla in keyspace.tablas.values() {
+            match &tabla.base {
+                Some(base) => vistas.push(format!("{},{},{}", keyspace.nombre, tabla.nombre, base)),
+                None => tablas.push(format!("{},{}", keyspace.nombre, tabla.nombre)),
+            }
+            columnas.extend(filas_columnas(&keyspace.nombre, tabla));
+        }
+    }
+    vec![
+        (format!("{}.{}", KEYSPACE_SYSTEM, LOCAL.0), vec![local]),
+        (format!("{}.{}", KEYSPACE_SYSTEM, PEERS.0), peers),
+        (
+            format!("{}.{}", KEYSPACE_SYSTEM_SCHEMA, KEYSPACES.0),
+            keyspaces,
+        ),
+        (format!("{}.{}", KEYSPACE_SYSTEM_SCHEMA, TABLES.0), tablas),
+        (format!("{}.{}", KEYSPACE_SYSTEM_SCHEMA, VIEWS.0), vistas),
+        (
+            format!("{}.{}", KEYSPACE_SYSTEM_SCHEMA, COLUMNS.0),
+            columnas,
+        ),
+    ]
+}
+
+// La fila de system.local, con las direcciones, la versión del schema y el token del
+// nodo, y los días que faltan para que venza cada uno de sus certificados
+fn fila_local(
+    ip: &str,
+    (version, token): (u32, u32),
+    vencimientos: &[VencimientoCertificado],
+) -> String {
+    format!(
+        "local,{},{},{},{},{},{},{},{},{},{},{}",
+        NOMBRE_CLUSTER,
+        DATACENTER,
+        RACK,
+        env!("CARGO_PKG_VERSION"),
+        PARTICIONADOR,
+        ip,
+        ip,
+        ip,
+        uuid_schema(version),
+        tokens(token),
+        dias_certificados(vencimientos)
+    )
+}
+
+// La versión del schema como el uuid que esperan los drivers, con el hash del historial
+// en los primeros 4 bytes y los bits de versión (3, basado en un nombre) y de variante
+fn uuid_schema(version: u32) -> String {
+    format!("{:08x}-0000-3000-8000-000000000000", version)
+}
+
+// Los tokens de un nodo como set<text>: cada nodo tiene uno solo
+fn tokens(token: u32) -> String {
+    format!("{{'{}'}}", token)
+}
+
+// Los días restantes de cada certificado como map<text, int>, con su ruta como clave
+fn dias_certificados(vencimientos: &[VencimientoCertificado]) -> String {
+    let dias: Vec<String> = vencimientos
+        .iter()
+        .map(|vencimiento| format!("'{}':{}", vencimiento.ruta, vencimiento.dias_restantes()))
+        .collect();
+    format!("{{{}}}", dias.join(";"))
+}
+
+// Una fila por columna de la tabla, con su rol en la clave primaria y su posición en ella
+fn filas_columnas(keyspace: &str, tabla: &Tabla) -> Vec<String> {
+    tabla
+        .headers
+        .iter()
+        .zip(&tabla.tipos)
+        .map(|(columna, tipo)| {
+            let clustering = tabla.clustering.iter().position(|c| c == columna);
+            let (orden, tipo_columna, posicion) = match clustering {
+                _ if columna == tabla.clave_particion() => ("none", "partition_key", 0),
+                Some(posicion) => ("asc", "clustering", posicion as i32),
+                None => ("none", "regular", -1),
+            };
+            format!(
+                "{},{},{},{},{},{},{}",
+                keyspace, tabla.nombre, columna, orden, tipo_columna, posicion, tipo
+            )
+        })
+        .collect()
+}
+
+/// Columnas del resultado de un DESCRIBE: el keyspace, el tipo y el nombre de cada
+/// objeto y, si se pidió su definición, la sentencia CREATE que lo define
+pub fn columnas_describe(con_definicion: bool) -> Vec<ColumnaSpec> {
+    let columnas: &[&str] = match con_definicion {
+        true => &["keyspace_name", "type", "name", "create_statement"],
+        false => &["keyspace_name", "type", "name"],
+    };
+    columnas
+        .iter()
+        .map(|nombre| ColumnaSpec {
+            nombre: nombre.to_string(),
+            tipo: TipoColumna::Text,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+    use std::time::{Duration, SystemTime};
+
+    use protocolo::serial_deserial::cassandra::deserializador_server_cliente::deserializar_respuesta_tipada;
+    use protocolo::serial_deserial::cassandra::metadata_rows::MetadataRows;
+    use protocolo::serial_deserial::cassandra::serializador_server_cliente::result_to_bytes_server_client;
+    use protocolo::serial_deserial::valor_cql::ValorCQL;
+
+    use super::*;
+    use protocolo::parser_cql::ast::Seleccion;
+    use protocolo::parser_cql::condicion_where::CondicionWhere;
+
+    #[test]
+    fn test_system_local_se_lee_con_los_tipos_de_cassandra() {
+        //Arrange
+        let mut local = crear_tabla(LOCAL);
+        let vencimientos = [VencimientoCertificado {
+            ruta: "/certs/cert.pem".to_string(),
+            vence: SystemTime::now() + Duration::from_secs(30 * 86_400 + 60),
+        }];
+        local.cargar(fila_local("127.0.0.2", (42, 7), &vencimientos));
+        let filas = local.select(&CondicionWhere::default(), &Seleccion::default());
+        let columnas = local.columnas_seleccionadas(&Seleccion::default());
+        let metadata = MetadataRows::new(KEYSPACE_SYSTEM, LOCAL.0, columnas);
+
+        //Act
+        let filas = filas.iter().map(|fila| fila.as_str()).collect();
+        let bytes = result_to_bytes_server_client(filas, 0x0002, &metadata, None).unwrap();
+        let (resultado, _) = deserializar_respuesta_tipada(&bytes[..], None).unwrap();
+
+        //Assert
+        let fila = &resultado.filas[0];
+        let ip = ValorCQL::Inet(IpAddr::from([127, 0, 0, 2]));
+        assert_eq!(fila.get("broadcast_address"), Some(&ip));
+        assert_eq!(fila.get("rpc_address"), Some(&ip));
+        assert_eq!(
+            fila.get_string("schema_version"),
+            "0000002a-0000-3000-8000-000000000000"
+        );
+        assert_eq!(
+            fila.get("tokens"),
+            Some(&ValorCQL::Conjunto(vec![ValorCQL::Texto("7".to_string())]))
+        );
+        assert_eq!(
+            fila.get("certificados"),
+            Some(&ValorCQL::Mapa(vec![(
+                ValorCQL::Texto("/certs/cert.pem".to_string()),
+                ValorCQL::Int(30)
+            )]))
+        );
+    }
+}
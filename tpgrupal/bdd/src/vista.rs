@@ -0,0 +1,350 @@
+use std::collections::BTreeSet;
+
+use crate::tabla::{invalida, termino_celda, Tabla};
+
+/// Clave primaria de una vista: su partition key, que es una sola columna, y sus
+/// columnas de clustering
+pub type ClaveVista<'a> = (&'a [String], &'a [String]);
+
+/// Vista materializada de la tabla base con las columnas seleccionadas (todas si no
+/// se indica ninguna) y la clave primaria indicada, que tiene que incluir la de la
+/// tabla base y a lo sumo una columna más. Sus columnas son la partition key y luego
+/// las demás seleccionadas, en el orden de la selección
+pub fn nueva_vista(
+    base: &Tabla,
+    nombre: &str,
+    columnas: &[String],
+    (clave_particion, clustering): ClaveVista,
+) -> Result<Tabla, String> {
+    validar_base(base)?;
+    let [clave_particion] = clave_particion else {
+        return Err(invalida(
+            "la partition key de una vista materializada tiene que ser una sola columna",
+        ));
+    };
+    let columnas = match columnas.is_empty() {
+        true => &base.headers,
+        false => columnas,
+    };
+    let clave_primaria = [std::slice::from_ref(clave_particion), clustering].concat();
+    validar_clave(base, columnas, &clave_primaria)?;
+    let mut headers = vec![clave_particion.to_string()];
+    headers.extend(columnas.iter().filter(|c| *c != clave_particion).cloned());
+    let mut tipos = vec![];
+    for header in &headers {
+        let indice = base.headers.iter().position(|columna| columna == header);
+        let Some(indice) = indice else {
+            return Err(invalida(&format!(
+                "la columna {} no existe en la tabla {}",
+                header, base.nombre
+            )));
+        };
+        tipos.push(base.tipos[indice].clone());
+    }
+    Ok(Tabla::new(nombre.to_string(), headers, tipos)
+        .con_clustering(clustering.to_vec())
+        .con_base(&base.nombre))
+}
+
+// Las vistas se definen sobre tablas comunes, sin contadores
+fn validar_base(base: &Tabla) -> Result<(), String> {
+    if base.base.is_some() {
+        return Err(invalida(&format!(
+            "no se puede crear una vista de la vista materializada {}",
+            base.nombre
+        )));
+    }
+    if !base.indices_contadores().is_empty() {
+        return Err(invalida(&format!(
+            "no se puede crear una vista de la tabla de contadores {}",
+            base.nombre
+        )));
+    }
+    Ok(())
+}
+
+// La clave primaria de la vista tiene columnas seleccionadas, incluye la de la tabla
+// base y tiene a lo sumo una columna más, que no puede cambiar elemento a elemento
+fn validar_clave(base: &Tabla, columnas: &[String], clave: &[String]) -> Result<(), String> {
+    if let Some(columna) = clave.iter().find(|columna| !columnas.contains(columna)) {
+        return Err(invalida(&format!(
+            "la columna {} de la clave primaria de la vista tiene que estar seleccionada",
+            columna
+        )));
+    }
+    let mut clave_base = base.headers.iter().filter(|header| base.es_clave(header));
+    if let Some(columna) = clave_base.find(|columna| !clave.contains(columna)) {
+        return Err(invalida(&format!(
+            "la clave primaria de la vista tiene que incluir la columna {} de la clave de {}",
+            columna, base.nombre
+        )));
+    }
+    let agregadas = clave.iter().filter(|columna| !base.es_clave(columna));
+    if agregadas.clone().count() > 1 {
+        return Err(invalida(&format!(
+            "la clave primaria de la vista puede tener a lo sumo una columna fuera de la clave de {}",
+            base.nombre
+        )));
+    }
+    let mut indices =
+        agregadas.filter_map(|columna| base.headers.iter().position(|h| h == columna));
+    if indices.any(|indice| base.tipos[indice].mezcla_elementos()) {
+        return Err(invalida(
+            "las columnas de la clave primaria no pueden ser colecciones ni tipos sin frozen",
+        ));
+    }
+    Ok(())
+}
+
+/// Fila de la vista materializada con los valores de una fila de su tabla base, o None
+/// si a la fila base le falta el valor de alguna columna de la clave primaria de la
+/// vista, que entonces no la incluye
+pub fn fila_vista(base: &Tabla, vista: &Tabla, fila: &str) -> Option<String> {
+    let valores = fila.split(",").collect::<Vec<&str>>();
+    let mut fila_vista = vec![];
+    for header in &vista.headers {
+        let valor = base
+            .headers
+            .iter()
+            .position(|columna| columna == header)
+            .and_then(|indice| valores.get(indice))
+            .copied()
+            .unwrap_or_default();
+        if valor.is_empty() && vista.es_clave(header) {
+            return None;
+        }
+        fila_vista.push(valor);
+    }
+    Some(fila_vista.join(","))
+}
+
+/// Escrituras (INSERT, UPDATE y DELETE) que llevan la vista del estado que tenían las
+/// filas de la tabla base antes de una escritura al que tienen después. A cada fila
+/// base le corresponde a lo sumo una fila de la vista, que cambia de clave si la
+/// escritura modifica la columna de la clave de la vista que no es de la clave base
+pub fn mutaciones_vista(
+    base: &Tabla,
+    (nombre, vista): (&str, &Tabla),
+    antes: &[String],
+    despues: &[String],
+) -> Vec<String> {
+    let clave = |fila: &String| base.condicion_clave(fila).to_string();
+    let claves = antes.iter().chain(despues).map(clave);
+    let mut mutaciones = vec![];
+    for clave_base in claves.collect::<BTreeSet<String>>() {
+        let buscar = |filas: &[String]| {
+            filas
+                .iter()
+                .find(|fila| clave(fila) == clave_base)
+                .and_then(|fila| fila_vista(base, vista, fila))
+        };
+        let (anterior, nueva) = (buscar(antes), buscar(despues));
+        mutaciones.extend(mutaciones_fila((nombre, vista), anterior, nueva));
+    }
+    mutaciones
+}
+
+// Escrituras que reemplazan la fila anterior de la vista por la nueva
+fn mutaciones_fila(
+    (nombre, vista): (&str, &Tabla),
+    anterior: Option<String>,
+    nueva: Option<String>,
+) -> Vec<String> {
+    let clave = |fila: &String| vista.condicion_clave(fila).to_string();
+    match (anterior, nueva) {
+        (Some(anterior), Some(nueva)) if clave(&anterior) == clave(&nueva) => {
+            update_vista((nombre, vista), &anterior, &nueva)
+                .into_iter()
+                .collect()
+        }
+        (anterior, nueva) => {
+            let delete =
+                anterior.map(|fila| format!("DELETE FROM {} WHERE {}", nombre, clave(&fila)));
+            let insert = nueva.map(|fila| insert_vista((nombre, vista), &fila));
+            delete.into_iter().chain(insert).collect()
+        }
+    }
+}
+
+/// INSERT de una fila completa de la vista, con la partition key como primer valor
+pub fn insert_vista((nombre, vista): (&str, &Tabla), fila: &str) -> String {
+    let valores = vista
+        .tipos
+        .iter()
+        .zip(fila.split(","))
+        .map(|(tipo, celda)| termino_celda(tipo, celda).to_string());
+    format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        nombre,
+        vista.headers.join(", "),
+        valores.collect::<Vec<String>>().join(", ")
+    )
+}
+
+// UPDATE de las columnas que cambiaron en una fila que conserva su clave, o ninguno
+// si no cambió ninguna
+fn update_vista((nombre, vista): (&str, &Tabla), anterior: &str, nueva: &str) -> Option<String> {
+    let asignaciones = vista
+        .headers
+        .iter()
+        .zip(&vista.tipos)
+        .zip(anterior.split(",").zip(nueva.split(",")))
+        .filter(|((_, _), (antes, despues))| antes != despues)
+        .map(|((header, tipo), (_, despues))| {
+            format!("{} = {}", header, termino_celda(tipo, despues))
+        })
+        .collect::<Vec<String>>();
+    if asignaciones.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "UPDATE {} SET {} WHERE {}",
+        nombre,
+        asignaciones.join(", "),
+        vista.condicion_clave(nueva)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocolo::parser_cql::ast::{Sentencia, Termino};
+    use protocolo::parser_cql::parseo_consulta::procesar_consulta;
+    use protocolo::serial_deserial::tipo_columna::TipoColumna;
+
+    fn vuelos() -> Tabla {
+        let headers = ["ORIGEN", "ID_VUELO", "DESTINO", "ESTADO"];
+        let tipos = vec![
+            TipoColumna::Text,
+            TipoColumna::Int,
+            TipoColumna::Text,
+            TipoColumna::Text,
+        ];
+        Tabla::new(
+            "VUELOS".to_string(),
+            headers.iter().map(|header| header.to_string()).collect(),
+            tipos,
+        )
+        .con_clustering(vec!["ID_VUELO".to_string()])
+    }
+
+    fn por_destino(base: &Tabla) -> Tabla {
+        let clave = ["DESTINO".to_string()];
+        let clustering = ["ID_VUELO".to_string(), "ORIGEN".to_string()];
+        nueva_vista(base, "POR_DESTINO", &[], (&clave, &clustering)).unwrap()
+    }
+
+    #[test]
+    fn test_crear_vista_con_su_partition_key_como_primera_columna() {
+        //Arrange
+        let base = vuelos();
+
+        //Act
+        let vista = por_destino(&base);
+
+        //Assert
+        assert_eq!(vista.headers, ["DESTINO", "ORIGEN", "ID_VUELO", "ESTADO"]);
+        assert_eq!(vista.tipos[2], TipoColumna::Int);
+        assert_eq!(vista.base, Some("VUELOS".to_string()));
+    }
+
+    #[test]
+    fn test_crear_vista_sin_la_clave_de_la_tabla_base_es_invalido() {
+        //Arrange
+        let base = vuelos();
+        let clave = ["DESTINO".to_string()];
+        let clustering = ["ID_VUELO".to_string()];
+
+        //Act
+        let resultado = nueva_vista(&base, "POR_DESTINO", &[], (&clave, &clustering));
+
+        //Assert
+        assert!(resultado.unwrap_err().contains("ORIGEN"));
+    }
+
+    #[test]
+    fn test_fila_base_sin_la_clave_de_la_vista_no_aparece_en_ella() {
+        //Arrange
+        let base = vuelos();
+        let vista = por_destino(&base);
+
+        //Act
+        let con_destino = fila_vista(&base, &vista, "AEP,1,EZE,En curso");
+        let sin_destino = fila_vista(&base, &vista, "AEP,2,,En curso");
+
+        //Assert
+        assert_eq!(con_destino, Some("EZE,AEP,1,En curso".to_string()));
+        assert_eq!(sin_destino, None);
+    }
+
+    #[test]
+    fn test_cambiar_la_clave_de_la_vista_mueve_la_fila_de_particion() {
+        //Arrange
+        let base = vuelos();
+        let vista = por_destino(&base);
+        let antes = ["AEP,1,EZE,En curso".to_string()];
+        let despues = ["AEP,1,COR,En curso".to_string()];
+
+        //Act
+        let mutaciones = mutaciones_vista(&base, ("POR_DESTINO", &vista), &antes, &despues);
+
+        //Assert
+        assert_eq!(
+            mutaciones,
+            [
+                "DELETE FROM POR_DESTINO WHERE DESTINO = 'EZE' AND ORIGEN = 'AEP' AND ID_VUELO = 1",
+                "INSERT INTO POR_DESTINO (DESTINO, ORIGEN, ID_VUELO, ESTADO) VALUES ('COR', 'AEP', 1, 'En curso')",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_actualizar_la_vista_solo_asigna_las_columnas_que_cambiaron() {
+        //Arrange
+        let base = vuelos();
+        let vista = por_destino(&base);
+        let antes = [
+            "AEP,1,EZE,En curso".to_string(),
+            "AEP,2,EZE,Demorado".to_string(),
+        ];
+        let despues = [
+            "AEP,1,EZE,Aterrizado".to_string(),
+            "AEP,2,EZE,Demorado".to_string(),
+        ];
+
+        //Act
+        let mutaciones = mutaciones_vista(&base, ("POR_DESTINO", &vista), &antes, &despues);
+
+        //Assert
+        assert_eq!(
+            mutaciones,
+            ["UPDATE POR_DESTINO SET ESTADO = 'Aterrizado' WHERE DESTINO = 'EZE' AND ORIGEN = 'AEP' AND ID_VUELO = 1"]
+        );
+    }
+
+    #[test]
+    fn test_mutaciones_de_textos_con_comillas_o_guiones_se_vuelven_a_parsear_igual() {
+        //Arrange
+        let base = vuelos();
+        let vista = por_destino(&base);
+        let antes = ["'O''Hare',1,EZE,En curso".to_string()];
+        let despues = ["'O''Hare',1,EZE,Hola -- x".to_string()];
+
+        //Act
+        let mutaciones = mutaciones_vista(&base, ("POR_DESTINO", &vista), &antes, &despues);
+        let consulta = procesar_consulta(&mutaciones[0]).unwrap();
+
+        //Assert
+        let Sentencia::Update { asignaciones, .. } = consulta.get_sentencia() else {
+            panic!("se esperaba un UPDATE: {}", mutaciones[0]);
+        };
+        assert_eq!(
+            asignaciones[0].valor,
+            Termino::Texto("Hola -- x".to_string())
+        );
+        assert_eq!(
+            consulta.get_where().valor("ORIGEN"),
+            Some("'O''Hare'".to_string())
+        );
+    }
+}
@@ -4,11 +4,37 @@ use std::net::TcpStream;
 use std::path::Path;
 use std::sync::Arc;
 
-use rustls::{ClientConnection, ServerName, StreamOwned};
-use seguridad::create_client_config;
+use protocolo::serial_deserial::cassandra::autenticacion::Credenciales;
+use protocolo::serial_deserial::cassandra::deserializador_server_cliente::deserializar_respuesta;
+use protocolo::serial_deserial::cassandra::serializador_cliente_server::{
+    serializar_auth_response_client_server, serializar_startup_client_server,
+};
+use protocolo::serial_deserial::compresion::Compresion;
+use rustls::{ClientConnection, StreamOwned};
+use seguridad::{config_cliente, direccion_y_nombre};
 
-pub fn connect_to_server() -> Result<StreamOwned<ClientConnection, TcpStream>, String> {
-    let config = Arc::new(create_client_config()?);
+use super::sending::send_request;
+
+type Conexion = StreamOwned<ClientConnection, TcpStream>;
+
+const READY: i8 = 0x02;
+const AUTHENTICATE: i8 = 0x03;
+const AUTH_CHALLENGE: i8 = 0x0E;
+const AUTH_SUCCESS: i8 = 0x10;
+// Cantidad de AUTH_CHALLENGE que se responden antes de abandonar la autenticación
+const MAX_RONDAS_AUTENTICACION: usize = 3;
+
+/// Se conecta al servidor. Si están definidas las variables de entorno
+/// USUARIO_CASSANDRA y PASSWORD_CASSANDRA antes se autentica con ellas
+pub fn connect_to_server() -> Result<Conexion, String> {
+    match Credenciales::desde_entorno() {
+        Some(credenciales) => iniciar_sesion(None, Some(&credenciales)),
+        None => conectar_a_un_nodo(),
+    }
+}
+
+fn conectar_a_un_nodo() -> Result<Conexion, String> {
+    let config = config_cliente()?;
 
     let archivo = File::open(Path::new(
         "cliente-servidor/src/client_services/seeds_client.txt",
@@ -16,10 +42,8 @@ pub fn connect_to_server() -> Result<StreamOwned<ClientConnection, TcpStream>, S
     .map_err(|_| "Error al abrir el archivo".to_string())?;
     let reader = io::BufReader::new(archivo);
     for line in reader.lines() {
-        let node_address = line.map_err(|_| "Error al leer la linea.".to_string())?;
-        let domain = "localhost";
-        let server_name = ServerName::try_from(domain)
-            .map_err(|_| format!("Nombre de dominio inválido: {}", domain))?;
+        let line = line.map_err(|_| "Error al leer la linea.".to_string())?;
+        let (node_address, server_name) = direccion_y_nombre(&line)?;
 
         if let Ok(stream) = TcpStream::connect(&node_address) {
             let client_conn = ClientConnection::new(Arc::clone(&config), server_name)
@@ -30,3 +54,70 @@ pub fn connect_to_server() -> Result<StreamOwned<ClientConnection, TcpStream>, S
     }
     Err("No se pudo conectar a ningun nodo".to_string())
 }
+
+/// Se conecta al servidor y, si se indica una compresión, la negocia enviando
+/// un STARTUP con la opción COMPRESSION y esperando el READY. A partir de ahí
+/// los frames de esa conexión viajan con el body comprimido. Si el servidor
+/// requiere autenticación se usan las credenciales de las variables de entorno
+pub fn connect_to_server_con_compresion(
+    compresion: Option<&Compresion>,
+) -> Result<Conexion, String> {
+    let credenciales = Credenciales::desde_entorno();
+    if compresion.is_none() && credenciales.is_none() {
+        return conectar_a_un_nodo();
+    }
+    iniciar_sesion(compresion, credenciales.as_ref())
+}
+
+/// Se conecta al servidor autenticándose con las credenciales indicadas
+pub fn connect_to_server_con_credenciales(
+    compresion: Option<&Compresion>,
+    credenciales: &Credenciales,
+) -> Result<Conexion, String> {
+    iniciar_sesion(compresion, Some(credenciales))
+}
+
+/// Se conecta al servidor y envía el STARTUP. Si el servidor responde READY la
+/// conexión ya se puede usar, si responde AUTHENTICATE se autentica con las
+/// credenciales indicadas, que en ese caso son obligatorias
+pub fn iniciar_sesion(
+    compresion: Option<&Compresion>,
+    credenciales: Option<&Credenciales>,
+) -> Result<Conexion, String> {
+    let mut conexion = conectar_a_un_nodo()?;
+    send_request(&mut conexion, serializar_startup_client_server(compresion))
+        .map_err(|_| "Fallo en la conexion al enviar el STARTUP.".to_string())?;
+    match deserializar_respuesta(&mut conexion, None)? {
+        (_, READY) => Ok(conexion),
+        (_, AUTHENTICATE) => {
+            let credenciales =
+                credenciales.ok_or("El servidor requiere usuario y contraseña.".to_string())?;
+            autenticar(&mut conexion, credenciales, compresion)?;
+            Ok(conexion)
+        }
+        _ => Err("El servidor no respondió READY al STARTUP.".to_string()),
+    }
+}
+
+// Envía las credenciales como un token SASL PLAIN en el AUTH_RESPONSE, volviéndolas
+// a enviar si el servidor responde con un AUTH_CHALLENGE, hasta recibir el AUTH_SUCCESS
+fn autenticar(
+    conexion: &mut Conexion,
+    credenciales: &Credenciales,
+    compresion: Option<&Compresion>,
+) -> Result<(), String> {
+    let token = credenciales.token_plain();
+    for _ in 0..MAX_RONDAS_AUTENTICACION {
+        send_request(
+            conexion,
+            serializar_auth_response_client_server(&token, compresion),
+        )
+        .map_err(|_| "Fallo en la conexion al enviar el AUTH_RESPONSE.".to_string())?;
+        match deserializar_respuesta(&mut *conexion, compresion)? {
+            (_, AUTH_SUCCESS) => return Ok(()),
+            (_, AUTH_CHALLENGE) => continue,
+            _ => return Err("Respuesta inesperada durante la autenticación.".to_string()),
+        }
+    }
+    Err("El servidor no aceptó las credenciales.".to_string())
+}
@@ -0,0 +1,66 @@
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use protocolo::serial_deserial::cassandra::{
+    deserializador_server_cliente::{deserializar_evento, deserializar_respuesta},
+    eventos::{Evento, Mutacion, TipoEvento},
+    serializador_cliente_server::{
+        serializar_register_client_server, serializar_suscribir_cdc_client_server,
+    },
+};
+
+use super::{connection::connect_to_server, sending::send_request};
+
+const READY: i8 = 0x02;
+
+/// Registra al cliente en un nodo para recibir los tipos de eventos indicados
+/// y devuelve el canal por el cual van a ir llegando. Un hilo queda escuchando
+/// la conexión y deja de hacerlo cuando el nodo la cierra o cuando se descarta
+/// el Receiver devuelto
+pub fn suscribirse_a_eventos(tipos: &[TipoEvento]) -> Result<Receiver<Evento>, String> {
+    let mut conexion = connect_to_server()?;
+    send_request(&mut conexion, serializar_register_client_server(tipos))
+        .map_err(|_| "Fallo en la conexion al enviar el REGISTER.".to_string())?;
+    if deserializar_respuesta(&mut conexion, None)?.1 != READY {
+        return Err("El servidor no respondió READY al REGISTER.".to_string());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(evento) = deserializar_evento(&mut conexion, None) {
+            if tx.send(evento).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(rx)
+}
+
+/// Se suscribe al CDC de una tabla y devuelve el canal por el cual van a ir
+/// llegando sus mutaciones en todo el cluster. Si se indica un offset (un
+/// momento de escritura en microsegundos) primero llegan las que siguen en los
+/// logs a partir de él, si no solo las nuevas
+pub fn suscribirse_a_cambios(
+    tabla: &str,
+    desde: Option<u64>,
+) -> Result<Receiver<Mutacion>, String> {
+    let mut conexion = connect_to_server()?;
+    send_request(
+        &mut conexion,
+        serializar_suscribir_cdc_client_server(tabla, desde),
+    )
+    .map_err(|_| "Fallo en la conexion al enviar la suscripción al CDC.".to_string())?;
+    if deserializar_respuesta(&mut conexion, None)?.1 != READY {
+        return Err("El servidor no respondió READY a la suscripción al CDC.".to_string());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(Evento::RowChange(mutacion)) = deserializar_evento(&mut conexion, None) {
+            if tx.send(mutacion).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(rx)
+}
@@ -0,0 +1,82 @@
+use std::collections::VecDeque;
+
+use protocolo::serial_deserial::{
+    cassandra::{filas::Fila, serializador_cliente_server::query_paginada_to_bytes_client_server},
+    compresion::Compresion,
+    level_consistency::LevelConsistency,
+};
+
+use super::{
+    connection::connect_to_server_con_compresion, receiving::receive_rows, sending::send_request,
+};
+
+pub const PAGE_SIZE_DEFAULT: i32 = 100;
+
+/// Iterador sobre las filas del resultado de una consulta que el servidor
+/// devuelve por páginas. Cuando se terminan las filas de la página actual
+/// pide la siguiente enviando el paging_state recibido, de modo que quien
+/// lo usa recorre el resultado completo sin manejar la paginación
+pub struct FilasPaginadas {
+    consulta: String,
+    consistencia: LevelConsistency,
+    page_size: i32,
+    paging_state: Option<Vec<u8>>,
+    compresion: Option<Compresion>,
+    filas: VecDeque<Fila>,
+    terminado: bool,
+}
+
+impl FilasPaginadas {
+    /// Crea el iterador, la primera página se pide recién al recorrerlo
+    pub fn new(consulta: String, consistencia: LevelConsistency, page_size: i32) -> Self {
+        FilasPaginadas {
+            consulta,
+            consistencia,
+            page_size,
+            paging_state: None,
+            compresion: None,
+            filas: VecDeque::new(),
+            terminado: false,
+        }
+    }
+
+    /// Pide que cada página viaje comprimida con el algoritmo indicado
+    pub fn con_compresion(mut self, compresion: Compresion) -> Self {
+        self.compresion = Some(compresion);
+        self
+    }
+
+    fn pedir_pagina(&mut self) -> Result<(), String> {
+        let consulta_serializada = query_paginada_to_bytes_client_server(
+            &self.consulta,
+            self.consistencia.clone(),
+            self.page_size,
+            self.paging_state.take(),
+            self.compresion.as_ref(),
+        );
+        let mut conexion = connect_to_server_con_compresion(self.compresion.as_ref())?;
+        send_request(&mut conexion, consulta_serializada)
+            .map_err(|_| "Fallo en la conexion al enviar la query.".to_string())?;
+        let (resultado, _) = receive_rows(&mut conexion, self.compresion.as_ref())
+            .map_err(|_| "No se pudo recibir respuesta".to_string())?;
+
+        self.paging_state = resultado.metadata.paging_state;
+        self.terminado = self.paging_state.is_none();
+        self.filas.extend(resultado.filas);
+        Ok(())
+    }
+}
+
+impl Iterator for FilasPaginadas {
+    type Item = Result<Fila, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.filas.is_empty() && !self.terminado {
+            if let Err(e) = self.pedir_pagina() {
+                self.terminado = true;
+                return Some(Err(e));
+            }
+        }
+        self.filas.pop_front().map(Ok)
+    }
+}
@@ -1,11 +1,23 @@
 use std::net::TcpStream;
 
-use protocolo::serial_deserial::cassandra::deserializador_server_cliente::deserializar_respuesta;
+use protocolo::serial_deserial::cassandra::deserializador_server_cliente::{
+    deserializar_respuesta, deserializar_respuesta_tipada,
+};
+use protocolo::serial_deserial::cassandra::filas::ResultadoFilas;
+use protocolo::serial_deserial::compresion::Compresion;
 use rustls::{ClientConnection, StreamOwned};
 
 pub fn receive_response(
     socket: &mut StreamOwned<ClientConnection, TcpStream>,
 ) -> Result<(Vec<String>, i8), Box<dyn std::error::Error>> {
-    let (deserialized_response, tipo) = deserializar_respuesta(socket)?;
+    let (deserialized_response, tipo) = deserializar_respuesta(socket, None)?;
     Ok((deserialized_response, tipo))
 }
+
+pub fn receive_rows(
+    socket: &mut StreamOwned<ClientConnection, TcpStream>,
+    compresion: Option<&Compresion>,
+) -> Result<(ResultadoFilas, i8), Box<dyn std::error::Error>> {
+    let (resultado, tipo) = deserializar_respuesta_tipada(socket, compresion)?;
+    Ok((resultado, tipo))
+}
@@ -10,8 +10,8 @@ use protocolo::serial_deserial::{
     cassandra::serializador_cliente_server::query_to_bytes_client_server,
     level_consistency::LevelConsistency,
 };
-use rustls::{ClientConnection, ServerName, StreamOwned};
-use seguridad::create_client_config;
+use rustls::{ClientConnection, StreamOwned};
+use seguridad::{config_cliente, direccion_y_nombre};
 
 use crate::client_services::sending::send_request;
 
@@ -51,6 +51,34 @@ pub fn construir_consulta_create_table(
     consulta
 }
 
+/// Arma un CREATE MATERIALIZED VIEW de la vista con las columnas de la tabla base
+/// indicadas, cuya clave primaria son la partition key y las columnas de clustering
+pub fn construir_consulta_create_view(
+    vista: String,
+    tabla_base: String,
+    campos: Vec<String>,
+    partition_key: String,
+    clustering_colum: Vec<String>,
+) -> String {
+    let clave: Vec<String> = [partition_key.to_string()]
+        .into_iter()
+        .chain(clustering_colum.iter().cloned())
+        .collect();
+    let no_nulas: Vec<String> = clave
+        .iter()
+        .map(|columna| format!("{} IS NOT NULL", columna))
+        .collect();
+    format!(
+        "CREATE MATERIALIZED VIEW IF NOT EXISTS {} AS SELECT {} FROM {} WHERE {} PRIMARY KEY (({}), {})",
+        vista,
+        campos.join(", "),
+        tabla_base,
+        no_nulas.join(" AND "),
+        partition_key,
+        clustering_colum.join(", ")
+    )
+}
+
 pub fn send_query(consulta: String, consistencia: LevelConsistency) {
     let mut conexion = conectarse_al_servidor();
 
@@ -66,16 +94,14 @@ pub fn send_query(consulta: String, consistencia: LevelConsistency) {
 }
 
 pub fn conectarse_al_servidor() -> Result<StreamOwned<ClientConnection, TcpStream>, String> {
-    let config = Arc::new(create_client_config()?);
+    let config = config_cliente()?;
 
     let archivo = File::open(Path::new("cliente-servidor/src/client_services/seeds.txt"))
         .map_err(|_| "Error al abrir el archivo".to_string())?;
     let reader = io::BufReader::new(archivo);
     for line in reader.lines() {
-        let node_address = line.map_err(|_| "Error al leer la linea.".to_string())?;
-        let domain = "localhost";
-        let server_name = ServerName::try_from(domain)
-            .map_err(|_| format!("Nombre de dominio inválido: {}", domain))?;
+        let line = line.map_err(|_| "Error al leer la linea.".to_string())?;
+        let (node_address, server_name) = direccion_y_nombre(&line)?;
 
         if let Ok(stream) = TcpStream::connect(&node_address) {
             let client_conn = ClientConnection::new(Arc::clone(&config), server_name)
@@ -2,10 +2,13 @@ pub mod funciones;
 
 pub mod client_services {
     pub mod connection;
+    pub mod eventos;
+    pub mod paginacion;
     pub mod receiving;
     pub mod sending;
 }
 
 pub mod servidor {
+    pub mod auditoria;
     pub mod server;
 }
@@ -1,3 +1,4 @@
+use std::time::Duration;
 use std::{env, thread};
 
 use std::sync::{Arc, Mutex};
@@ -7,6 +8,10 @@ use cliente_servidor::servidor::server::{
     abrir_puerto_gossip, abrir_puerto_interconexion_nodos, run_server,
 };
 use cliente_servidor::servidor::server::{PUERTO_CLIENTE, PUERTO_GOSSIP, PUERTO_INTERNODOS};
+use seguridad::recarga::vigilar_certificados;
+
+// Cada cuánto se revisa si cambiaron los certificados para recargarlos
+const INTERVALO_VIGILANCIA_CERTIFICADOS: Duration = Duration::from_secs(10);
 
 fn main() -> Result<(), String> {
     let args: Vec<String> = env::args().collect();
@@ -41,6 +46,7 @@ fn main() -> Result<(), String> {
         },
     );
 
+    vigilar_certificados(INTERVALO_VIGILANCIA_CERTIFICADOS);
     Nodo::iniciar_gossip(nodo_gossip2);
 
     handle1.join().unwrap();
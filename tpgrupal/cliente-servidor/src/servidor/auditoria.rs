@@ -0,0 +1,379 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use protocolo::{
+    parser_cql::type_cql::TypeCQL, serial_deserial::level_consistency::LevelConsistency,
+};
+
+pub const VARIABLE_CATEGORIAS: &str = "AUDITORIA_CATEGORIAS";
+pub const VARIABLE_ARCHIVO: &str = "AUDITORIA_ARCHIVO";
+pub const VARIABLE_TAMANIO_MAXIMO: &str = "AUDITORIA_TAMANIO_MAXIMO";
+pub const VARIABLE_ARCHIVOS: &str = "AUDITORIA_ARCHIVOS";
+pub const VARIABLE_OCULTAR_VALORES: &str = "AUDITORIA_OCULTAR_VALORES";
+
+const TAMANIO_MAXIMO_POR_DEFECTO: u64 = 10 * 1024 * 1024;
+const ARCHIVOS_POR_DEFECTO: usize = 5;
+const VALOR_OCULTO: &str = "?";
+/// Operación con la que se registran las consultas que no se pudieron parsear
+pub const OPERACION_FALLIDA: &str = "REQUEST_FAILURE";
+
+/// Categorías de operaciones que se pueden auditar
+#[derive(Debug, Clone, PartialEq)]
+pub enum CategoriaAuditoria {
+    Ddl,
+    Dml,
+    Select,
+    Auth,
+    Error, // Consultas que fallaron antes de saber qué operación eran, como al parsearlas
+}
+
+impl CategoriaAuditoria {
+    pub fn create(nombre: &str) -> Result<Self, String> {
+        match nombre.trim().to_uppercase().as_str() {
+            "DDL" => Ok(CategoriaAuditoria::Ddl),
+            "DML" => Ok(CategoriaAuditoria::Dml),
+            "SELECT" => Ok(CategoriaAuditoria::Select),
+            "AUTH" => Ok(CategoriaAuditoria::Auth),
+            "ERROR" => Ok(CategoriaAuditoria::Error),
+            _ => Err(format!("Categoría de auditoría no soportada: {}", nombre)),
+        }
+    }
+
+    /// Devuelve la categoría a la que pertenece un tipo de consulta
+    pub fn de_consulta(tipo: &TypeCQL) -> Self {
+        match tipo {
+            TypeCQL::Select | TypeCQL::Describe => CategoriaAuditoria::Select,
+            TypeCQL::Insert | TypeCQL::Update | TypeCQL::Delete => CategoriaAuditoria::Dml,
+            TypeCQL::CreateTable
+            | TypeCQL::CreateKeyspace
+            | TypeCQL::AlterTable
+            | TypeCQL::AlterKeyspace
+            | TypeCQL::DropTable
+            | TypeCQL::DropKeyspace
+            | TypeCQL::CreateType
+            | TypeCQL::DropType
+            | TypeCQL::CreateView
+            | TypeCQL::DropView
+            | TypeCQL::Truncate
+            | TypeCQL::Use => CategoriaAuditoria::Ddl,
+            TypeCQL::CreateRole
+            | TypeCQL::AlterRole
+            | TypeCQL::DropRole
+            | TypeCQL::Grant
+            | TypeCQL::Revoke
+            | TypeCQL::ListPermissions => CategoriaAuditoria::Auth,
+        }
+    }
+
+    pub fn nombre(&self) -> &str {
+        match self {
+            CategoriaAuditoria::Ddl => "DDL",
+            CategoriaAuditoria::Dml => "DML",
+            CategoriaAuditoria::Select => "SELECT",
+            CategoriaAuditoria::Auth => "AUTH",
+            CategoriaAuditoria::Error => "ERROR",
+        }
+    }
+}
+
+/// Una operación de un cliente a registrar en el log de auditoría
+pub struct EntradaAuditoria<'a> {
+    pub origen: &'a str, // Dirección desde la que se conectó el cliente
+    pub usuario: Option<&'a str>,
+    pub keyspace: &'a str,
+    pub categoria: CategoriaAuditoria,
+    pub operacion: String,
+    pub sentencia: &'a str,
+    pub consistencia: Option<&'a LevelConsistency>,
+    pub resultado: Result<(), &'a str>,
+    pub latencia: Duration,
+}
+
+/// Log de auditoría de las operaciones de los clientes de un nodo. Se configura con
+/// variables de entorno: si AUDITORIA_CATEGORIAS no está definida no se audita nada.
+/// Cuando el archivo supera el tamaño máximo se rota a archivo.1, archivo.2, etc.
+pub struct Auditoria {
+    categorias: Vec<CategoriaAuditoria>,
+    ruta: String,
+    tamanio_maximo: u64,
+    cantidad_archivos: usize,
+    ocultar_valores: bool,
+    archivo: Mutex<Option<File>>,
+}
+
+impl Auditoria {
+    /// Lee la configuración de las variables de entorno. El archivo por defecto es
+    /// logs/auditoria_<ip>.log, para que cada nodo tenga el suyo
+    pub fn desde_entorno(ip: &str) -> Result<Self, String> {
+        let categorias = match env::var(VARIABLE_CATEGORIAS) {
+            Ok(categorias) if categorias.trim().eq_ignore_ascii_case("TODAS") => vec![
+                CategoriaAuditoria::Ddl,
+                CategoriaAuditoria::Dml,
+                CategoriaAuditoria::Select,
+                CategoriaAuditoria::Auth,
+                CategoriaAuditoria::Error,
+            ],
+            Ok(categorias) => categorias
+                .split(',')
+                .filter(|categoria| !categoria.trim().is_empty())
+                .map(CategoriaAuditoria::create)
+                .collect::<Result<Vec<CategoriaAuditoria>, String>>()?,
+            Err(_) => vec![],
+        };
+        Ok(Auditoria {
+            categorias,
+            ruta: env::var(VARIABLE_ARCHIVO).unwrap_or(format!("logs/auditoria_{}.log", ip)),
+            tamanio_maximo: numero_de_entorno(VARIABLE_TAMANIO_MAXIMO)
+                .unwrap_or(TAMANIO_MAXIMO_POR_DEFECTO),
+            cantidad_archivos: numero_de_entorno(VARIABLE_ARCHIVOS)
+                .map(|cantidad| cantidad as usize)
+                .unwrap_or(ARCHIVOS_POR_DEFECTO),
+            ocultar_valores: env::var(VARIABLE_OCULTAR_VALORES)
+                .is_ok_and(|valor| valor.trim().eq_ignore_ascii_case("true")),
+            archivo: Mutex::new(None),
+        })
+    }
+
+    /// Registra la operación si su categoría está habilitada. Los errores al escribir
+    /// el log se informan pero no interrumpen la operación del cliente
+    pub fn registrar(&self, entrada: EntradaAuditoria) {
+        if !self.categorias.contains(&entrada.categoria) {
+            return;
+        }
+        let linea = self.formatear(&entrada);
+        if let Err(e) = self.escribir(&linea) {
+            eprintln!("No se pudo escribir el log de auditoría: {}", e);
+        }
+    }
+
+    fn formatear(&self, entrada: &EntradaAuditoria) -> String {
+        let fecha = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|fecha| fecha.as_millis())
+            .unwrap_or_default();
+        // Las sentencias de roles se ocultan siempre, para no guardar contraseñas, y
+        // también las que no se pudieron parsear, que pueden ser de roles
+        let sentencia = if self.ocultar_valores
+            || matches!(
+                entrada.categoria,
+                CategoriaAuditoria::Auth | CategoriaAuditoria::Error
+            ) {
+            ocultar_valores(entrada.sentencia)
+        } else {
+            entrada.sentencia.to_string()
+        };
+        let consistencia = entrada
+            .consistencia
+            .map(|consistencia| consistencia.to_string())
+            .unwrap_or("-".to_string());
+        let resultado = match entrada.resultado {
+            Ok(()) => "OK".to_string(),
+            Err(e) => format!("ERROR ({})", e),
+        };
+        format!(
+            "fecha:{}|origen:{}|usuario:{}|keyspace:{}|categoria:{}|operacion:{}|consistencia:{}|resultado:{}|latencia_ms:{}|sentencia:{}\n",
+            fecha,
+            entrada.origen,
+            entrada.usuario.unwrap_or("-"),
+            entrada.keyspace,
+            entrada.categoria.nombre(),
+            entrada.operacion,
+            consistencia,
+            resultado.replace('\n', " "),
+            entrada.latencia.as_millis(),
+            sentencia.replace('\n', " ")
+        )
+    }
+
+    fn escribir(&self, linea: &str) -> Result<(), String> {
+        let mut archivo = self
+            .archivo
+            .lock()
+            .map_err(|_| "No se pudo acceder al archivo.".to_string())?;
+        let tamanio = fs::metadata(&self.ruta).map(|m| m.len()).unwrap_or(0);
+        if tamanio > 0 && tamanio + linea.len() as u64 > self.tamanio_maximo {
+            *archivo = None;
+            self.rotar();
+        }
+        if archivo.is_none() {
+            *archivo = Some(self.abrir()?);
+        }
+        if let Some(archivo) = archivo.as_mut() {
+            archivo
+                .write_all(linea.as_bytes())
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn abrir(&self) -> Result<File, String> {
+        if let Some(directorio) = Path::new(&self.ruta).parent() {
+            fs::create_dir_all(directorio).map_err(|e| e.to_string())?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.ruta)
+            .map_err(|e| e.to_string())
+    }
+
+    // archivo.N-1 pasa a archivo.N y así hasta archivo, que pasa a archivo.1.
+    // El más antiguo se pisa, así se conservan a lo sumo `cantidad_archivos` rotados
+    fn rotar(&self) {
+        for numero in (1..self.cantidad_archivos).rev() {
+            let _ = fs::rename(
+                format!("{}.{}", self.ruta, numero),
+                format!("{}.{}", self.ruta, numero + 1),
+            );
+        }
+        if self.cantidad_archivos == 0 {
+            let _ = fs::remove_file(&self.ruta);
+        } else {
+            let _ = fs::rename(&self.ruta, format!("{}.1", self.ruta));
+        }
+    }
+}
+
+fn numero_de_entorno(variable: &str) -> Option<u64> {
+    env::var(variable).ok()?.trim().parse().ok()
+}
+
+/// Reemplaza por '?' los textos entre comillas simples y los números sueltos
+/// de una sentencia, dejando los nombres de tablas y columnas
+pub fn ocultar_valores(sentencia: &str) -> String {
+    let mut resultado = String::with_capacity(sentencia.len());
+    let mut caracteres = sentencia.chars().peekable();
+    let mut anterior_es_identificador = false;
+    while let Some(caracter) = caracteres.next() {
+        if caracter == '\'' {
+            // Dentro de un texto, '' es una comilla escapada
+            while let Some(siguiente) = caracteres.next() {
+                if siguiente == '\'' && caracteres.next_if_eq(&'\'').is_none() {
+                    break;
+                }
+            }
+            resultado.push_str(VALOR_OCULTO);
+            anterior_es_identificador = false;
+        } else if caracter.is_ascii_digit() && !anterior_es_identificador {
+            while caracteres
+                .next_if(|siguiente| siguiente.is_ascii_digit() || *siguiente == '.')
+                .is_some()
+            {}
+            resultado.push_str(VALOR_OCULTO);
+        } else {
+            resultado.push(caracter);
+            anterior_es_identificador = caracter.is_alphanumeric() || caracter == '_';
+        }
+    }
+    resultado
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Auditoría de las categorías indicadas en un archivo nuevo del directorio temporal
+    fn auditoria(nombre: &str, categorias: Vec<CategoriaAuditoria>, tamanio: u64) -> Auditoria {
+        let directorio =
+            env::temp_dir().join(format!("auditoria_{}_{}", nombre, std::process::id()));
+        let _ = fs::remove_dir_all(&directorio);
+        Auditoria {
+            categorias,
+            ruta: directorio
+                .join("auditoria.log")
+                .to_string_lossy()
+                .to_string(),
+            tamanio_maximo: tamanio,
+            cantidad_archivos: 2,
+            ocultar_valores: false,
+            archivo: Mutex::new(None),
+        }
+    }
+
+    fn entrada(categoria: CategoriaAuditoria, sentencia: &str) -> EntradaAuditoria<'_> {
+        EntradaAuditoria {
+            origen: "172.18.0.10:50000",
+            usuario: Some("cassandra"),
+            keyspace: "Aerolineas",
+            categoria,
+            operacion: "UPDATE".to_string(),
+            sentencia,
+            consistencia: None,
+            resultado: Ok(()),
+            latencia: Duration::from_millis(3),
+        }
+    }
+
+    #[test]
+    fn test_ocultar_valores_de_una_sentencia() {
+        //Arrange
+        let sentencia = "UPDATE VUELOS2 SET ESTADO = 'O''Hare', ALTITUD = 11000.5 WHERE ID = 12";
+
+        //Act
+        let oculta = ocultar_valores(sentencia);
+
+        //Assert
+        assert_eq!(
+            oculta,
+            "UPDATE VUELOS2 SET ESTADO = ?, ALTITUD = ? WHERE ID = ?"
+        );
+    }
+
+    #[test]
+    fn test_solo_se_registran_las_categorias_habilitadas() {
+        //Arrange
+        let auditoria = auditoria("categorias", vec![CategoriaAuditoria::Dml], 1024);
+
+        //Act
+        auditoria.registrar(entrada(CategoriaAuditoria::Select, "SELECT * FROM VUELOS"));
+        auditoria.registrar(entrada(CategoriaAuditoria::Dml, "DELETE FROM VUELOS"));
+        auditoria.registrar(entrada(CategoriaAuditoria::Auth, "CREATE ROLE x"));
+
+        //Assert
+        let log = fs::read_to_string(&auditoria.ruta).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        assert!(log.contains("categoria:DML"));
+        assert!(log.ends_with("sentencia:DELETE FROM VUELOS\n"));
+    }
+
+    #[test]
+    fn test_rotar_conserva_a_lo_sumo_la_cantidad_de_archivos() {
+        //Arrange
+        let auditoria = auditoria("rotar", vec![CategoriaAuditoria::Dml], 10);
+
+        //Act
+        for numero in 0..4 {
+            let sentencia = format!("DELETE FROM VUELOS WHERE ID = {}", numero);
+            auditoria.registrar(entrada(CategoriaAuditoria::Dml, &sentencia));
+        }
+
+        //Assert
+        let contenido = |sufijo: &str| fs::read_to_string(format!("{}{}", auditoria.ruta, sufijo));
+        assert!(contenido("").unwrap().contains("ID = 3"));
+        assert!(contenido(".1").unwrap().contains("ID = 2"));
+        assert!(contenido(".2").unwrap().contains("ID = 1"));
+        assert!(contenido(".3").is_err());
+    }
+
+    #[test]
+    fn test_categoria_de_cada_consulta() {
+        assert_eq!(
+            CategoriaAuditoria::de_consulta(&TypeCQL::Truncate),
+            CategoriaAuditoria::Ddl
+        );
+        assert_eq!(
+            CategoriaAuditoria::de_consulta(&TypeCQL::ListPermissions),
+            CategoriaAuditoria::Auth
+        );
+        assert_eq!(
+            CategoriaAuditoria::create("error"),
+            Ok(CategoriaAuditoria::Error)
+        );
+    }
+}
@@ -1,68 +1,119 @@
 use std::{
-    fs::{self, File, OpenOptions},
+    collections::HashMap,
     io::Write,
     net::{TcpListener, TcpStream},
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, MutexGuard},
     thread::{self},
+    time::{Duration, Instant},
 };
 
 use bdd::{
-    endpoint_data::EndpointData,
-    keyspace::Keyspace,
+    coordinador::digest,
+    endpoint_data::{schema_gossip, EndpointData},
     node_status::NodeStatus,
     nodo::{obtener_nombre_servicio, Nodo},
-    procesamiento_data::{hashear, obtener_row},
-    tabla::Tabla,
+    procesamiento_data::{hashear, paginar_filas},
+    roles::KEYSPACE_AUTH,
+    tablas_sistema::KEYSPACE_SYSTEM_SCHEMA,
 };
 use protocolo::{
     parser_cql::{
+        condicion_where::CONSULTA_INVALIDA,
         consulta::Consulta,
-        parseo_consulta::{
-            obtener_headers_table, obtener_tipo_strategy_y_replication, procesar_consulta,
-        },
+        lexer::{ERROR_SINTAXIS, ERROR_SYNTAX},
+        parseo_consulta::procesar_consulta,
         type_cql::TypeCQL,
     },
     serial_deserial::{
         cassandra::{
+            autenticacion::{
+                autenticador_configurado, Credenciales, ERROR_BAD_CREDENTIALS, ERROR_UNAUTHORIZED,
+                NO_AUTORIZADO,
+            },
             deserializador_cliente_server::deserializar_consulta,
+            eventos::{CambioSchema, Evento, TipoEvento},
+            paginacion::{EstadoPaginacion, Pagina, ParametrosQuery},
             serializador_server_cliente::{
-                result_to_bytes_server_client, serializar_ready_server_client,
+                result_to_bytes_server_client, serializar_auth_challenge_server_client,
+                serializar_auth_success_server_client, serializar_authenticate_server_client,
+                serializar_error_server_client, serializar_evento_server_client,
+                serializar_ready_server_client, serializar_schema_change_server_client,
+                serializar_set_keyspace_server_client, serializar_void_server_client,
             },
         },
+        compresion::Compresion,
         gossip::{
             deserializador_gossip::deserializar_gossip, serializador_gossip::serializar_gossip,
             type_message::TypeGossip,
         },
         intra_nodos::{
+            cdc::{separar_suscripcion_cdc, serializar_mutacion_cdc, SUSCRIBIR_CDC_NODO},
             deserializador_nodo_envio::deserializar_envio_nodos,
+            pagina::{separar_pagina, PAGINA},
+            paxos::{es_fase_paxos, separar_ballot, serializar_respuesta_paxos},
+            schema::{
+                es_mensaje_schema, serializar_respuesta_schema, MutacionSchema, MUTACION_SCHEMA,
+            },
             serializador_nodo_respuesta::serializar_respuesta_nodos,
         },
-        level_consistency::LevelConsistency,
+        level_consistency::{
+            LevelConsistency, CONSISTENCIA_INVALIDA, ERROR_INVALID, ERROR_PROTOCOLO,
+            ERROR_READ_FAILURE, ERROR_READ_TIMEOUT, ERROR_SERVIDOR, ERROR_UNAVAILABLE,
+            ERROR_WRITE_FAILURE, ERROR_WRITE_TIMEOUT, ESCRITURA_FALLIDA, LECTURA_FALLIDA,
+            NO_DISPONIBLE, TIEMPO_AGOTADO_ESCRITURA, TIEMPO_AGOTADO_LECTURA,
+        },
     },
 };
-use rustls::{ServerConnection, StreamOwned};
-use seguridad::create_server_config;
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use seguridad::{config_servidor_clientes, config_servidor_internodos};
+
+use super::auditoria::{Auditoria, CategoriaAuditoria, EntradaAuditoria, OPERACION_FALLIDA};
 
 const REPLICA: u8 = 1;
+const DIGEST: u8 = 2;
+const LIDER_CONTADOR: u8 = 6;
 pub const PUERTO_CLIENTE: &str = "9042";
 pub const PUERTO_INTERNODOS: &str = "9043";
 pub const PUERTO_GOSSIP: &str = "9044";
 
 const STARTUP: i8 = 0x01;
+const REGISTER: i8 = 0x0B;
+const AUTH_RESPONSE: i8 = 0x0F;
+const SUSCRIBIR_CDC: i8 = 0x20;
+// Cantidad de AUTH_CHALLENGE que se envían antes de dar por fallida la autenticación
+const MAX_RONDAS_AUTENTICACION: usize = 3;
+// Tiempo que se espera a que los nodos vivos acuerden el schema luego de un cambio
+const ESPERA_ACUERDO_SCHEMA: Duration = Duration::from_secs(10);
 
 pub fn run_server(node_address: String, nodo: Arc<Mutex<Nodo>>) -> Result<(), String> {
+    let ip = nodo
+        .lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())?
+        .ip
+        .to_string();
+    let auditoria = Arc::new(Auditoria::desde_entorno(&ip)?);
     match TcpListener::bind(&node_address) {
         Ok(listener) => {
-            let server_config = Arc::new(create_server_config()?);
             for connection in listener.incoming() {
                 match connection {
                     Ok(socket) => {
+                        // Se pide en cada conexión para usar los certificados recargados.
+                        // Si no se puede armar se rechaza solo esta conexión
+                        let server_config = match config_servidor_clientes() {
+                            Ok(server_config) => server_config,
+                            Err(e) => {
+                                eprintln!("No se pudo aceptar la conexión TLS: {}", e);
+                                continue;
+                            }
+                        };
                         let nodo = Arc::clone(&nodo);
-                        let server_config = Arc::clone(&server_config);
+                        let auditoria = Arc::clone(&auditoria);
                         thread::spawn(move || {
-                            let server_conn = ServerConnection::new(server_config).unwrap();
+                            let Some(server_conn) = conexion_tls(server_config) else {
+                                return;
+                            };
                             let mut tls_stream = StreamOwned::new(server_conn, socket);
-                            let _ = handle_client_request(nodo, &mut tls_stream);
+                            let _ = handle_client_request(nodo, &auditoria, &mut tls_stream);
                         });
                     }
                     Err(e) => return Err(format!("Error al aceptar la conexión: {}", e)),
@@ -80,14 +131,23 @@ pub fn abrir_puerto_interconexion_nodos(
 ) -> Result<(), String> {
     match TcpListener::bind(&address) {
         Ok(listener) => {
-            let server_config = Arc::new(create_server_config()?);
             for connection in listener.incoming() {
                 match connection {
                     Ok(socket) => {
-                        let server_config = Arc::clone(&server_config);
+                        // Se pide en cada conexión para usar los certificados recargados.
+                        // Si no se puede armar se rechaza solo esta conexión
+                        let server_config = match config_servidor_internodos() {
+                            Ok(server_config) => server_config,
+                            Err(e) => {
+                                eprintln!("No se pudo aceptar la conexión TLS: {}", e);
+                                continue;
+                            }
+                        };
                         let nodo = Arc::clone(&nodo);
                         thread::spawn(move || {
-                            let server_conn = ServerConnection::new(server_config).unwrap();
+                            let Some(server_conn) = conexion_tls(server_config) else {
+                                return;
+                            };
                             let mut tls_stream = StreamOwned::new(server_conn, socket);
                             let _ = handle_node_request(nodo, &mut tls_stream);
                         });
@@ -104,13 +164,22 @@ pub fn abrir_puerto_interconexion_nodos(
 pub fn abrir_puerto_gossip(address: String, nodo: Arc<Mutex<Nodo>>) -> Result<(), String> {
     match TcpListener::bind(&address) {
         Ok(listener) => {
-            let server_config = Arc::new(create_server_config()?);
             for connection in listener.incoming() {
                 match connection {
                     Ok(socket) => {
-                        let server_config = Arc::clone(&server_config);
+                        // Se pide en cada conexión para usar los certificados recargados.
+                        // Si no se puede armar se rechaza solo esta conexión
+                        let server_config = match config_servidor_internodos() {
+                            Ok(server_config) => server_config,
+                            Err(e) => {
+                                eprintln!("No se pudo aceptar la conexión TLS: {}", e);
+                                continue;
+                            }
+                        };
                         let nodo = Arc::clone(&nodo);
-                        let server_conn = ServerConnection::new(server_config).unwrap();
+                        let Some(server_conn) = conexion_tls(server_config) else {
+                            continue;
+                        };
                         let mut tls_stream = StreamOwned::new(server_conn, socket);
                         let _ = handle_gossip(nodo, &mut tls_stream);
                     }
@@ -123,51 +192,175 @@ pub fn abrir_puerto_gossip(address: String, nodo: Arc<Mutex<Nodo>>) -> Result<()
     }
 }
 
+// Arma el lado servidor de una conexión TLS. Si no se puede se descarta solo esa
+// conexión y el servidor sigue aceptando las demás
+fn conexion_tls(server_config: Arc<ServerConfig>) -> Option<ServerConnection> {
+    ServerConnection::new(server_config)
+        .inspect_err(|e| eprintln!("No se pudo aceptar la conexión TLS: {}", e))
+        .ok()
+}
+
 fn handle_node_request(
     nodo: Arc<Mutex<Nodo>>,
     socket: &mut StreamOwned<ServerConnection, TcpStream>,
 ) -> Result<(), String> {
     let (deserializada, resp, cons) = deserializar_envio_nodos(&mut *socket)?;
+    if es_fase_paxos(resp) {
+        return paxos(&nodo, socket, &deserializada, resp);
+    }
+    if es_mensaje_schema(resp) {
+        return schema(&nodo, socket, &deserializada, resp);
+    }
+    if resp == PAGINA {
+        return pagina(&nodo, socket, &deserializada);
+    }
+    if resp == SUSCRIBIR_CDC_NODO {
+        return cdc_nodo(&nodo, socket, &deserializada);
+    }
     let mut consulta = procesar_consulta(&deserializada)?;
 
-    if let Ok(mut nodo_guard) = nodo.lock() {
-        match consulta.get_type() {
-            TypeCQL::Insert => {
-                insert(&mut nodo_guard, socket, &mut consulta, resp, cons)?;
-            }
-            TypeCQL::Select => {
-                select(&mut nodo_guard, socket, &mut consulta, resp)?;
-            }
-            TypeCQL::Delete => {
-                delete(&mut nodo_guard, socket, &mut consulta, resp, cons)?;
-            }
-            TypeCQL::Update => {
-                update(&mut nodo_guard, socket, &mut consulta, resp, cons)?;
-            }
-            TypeCQL::CreateTable => {
-                create_table(&mut nodo_guard, socket, &mut consulta)?;
-            }
-            TypeCQL::CreateKeyspace => {
-                create_keyspace(&mut nodo_guard, socket, &mut consulta)?;
-            }
+    // Las escrituras bloquean al nodo solo mientras lo usan, las que coordina este
+    // nodo no lo bloquean mientras esperan a las demás réplicas
+    match consulta.get_type() {
+        TypeCQL::Insert => {
+            insert(&nodo, socket, &mut consulta, resp, cons)?;
+        }
+        TypeCQL::Select => {
+            select(&mut *bloquear(&nodo)?, socket, &mut consulta, resp)?;
+        }
+        TypeCQL::Delete => {
+            delete(&nodo, socket, &mut consulta, resp, cons)?;
+        }
+        TypeCQL::Update => {
+            update(&nodo, socket, &mut consulta, resp, cons)?;
+        }
+        TypeCQL::CreateTable
+        | TypeCQL::CreateKeyspace
+        | TypeCQL::AlterTable
+        | TypeCQL::AlterKeyspace
+        | TypeCQL::DropTable
+        | TypeCQL::DropKeyspace
+        | TypeCQL::CreateType
+        | TypeCQL::DropType
+        | TypeCQL::CreateView
+        | TypeCQL::DropView
+        | TypeCQL::Truncate => {
+            ddl(&mut *bloquear(&nodo)?, socket, &consulta)?;
+        }
+        TypeCQL::CreateRole
+        | TypeCQL::AlterRole
+        | TypeCQL::DropRole
+        | TypeCQL::Grant
+        | TypeCQL::Revoke
+        | TypeCQL::ListPermissions => {
+            // El coordinador las traduce a consultas sobre las tablas de system_auth
+            return Err("Las consultas de roles no se envían entre nodos.".to_string());
+        }
+        TypeCQL::Use => {
+            return Err("El USE no se envía entre nodos.".to_string());
         }
+        TypeCQL::Describe => {
+            // Cada nodo describe el schema que conoce sin consultar a los demás
+            return Err("El DESCRIBE no se envía entre nodos.".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Atiende una fase de Paxos de una transacción liviana que coordina otro nodo
+fn paxos(
+    nodo: &Mutex<Nodo>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    mensaje: &str,
+    fase: u8,
+) -> Result<(), String> {
+    let (ballot, sentencia) = separar_ballot(mensaje)?;
+    let campos = nodo
+        .lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())?
+        .atender_paxos(fase, ballot, sentencia)?;
+    socket
+        .write_all(&serializar_respuesta_paxos(&campos))
+        .map_err(|_| "Error al escribir en el socket.".to_string())
+}
+
+/// Responde las filas de este nodo de una página de un recorrido de todas las
+/// particiones que coordina otro nodo
+fn pagina(
+    nodo: &Mutex<Nodo>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    mensaje: &str,
+) -> Result<(), String> {
+    let (pagina, consulta_cql) = separar_pagina(mensaje)?;
+    let consulta = procesar_consulta(consulta_cql)?;
+    let mut nodo_guard = nodo
+        .lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())?;
+    let filas = match nodo_guard.get_tabla(consulta.get_tabla())? {
+        Some(tabla) => {
+            tabla.select_pagina(consulta.get_where(), &consulta.get_seleccion(), &pagina)?
+        }
+        None => vec![],
+    };
+    let filas: Vec<&str> = filas.iter().map(|fila| fila.as_str()).collect();
+    socket
+        .write_all(&serializar_respuesta_nodos(filas, 0x0002))
+        .map_err(|_| "Error al escribir en el socket.".to_string())
+}
+
+/// Envía las mutaciones del log CDC local de una tabla al nodo que se suscribió a
+/// ellas, hasta que cierre la conexión
+fn cdc_nodo(
+    nodo: &Mutex<Nodo>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    mensaje: &str,
+) -> Result<(), String> {
+    let (tabla, desde) = separar_suscripcion_cdc(mensaje)?;
+    let (pendientes, mutaciones) = nodo
+        .lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())?
+        .suscribir_cdc_local(tabla, desde)?;
+    for mutacion in pendientes.into_iter().chain(mutaciones) {
+        socket
+            .write_all(&serializar_mutacion_cdc(&mutacion))
+            .map_err(|_| "El nodo cerró la suscripción al CDC.".to_string())?;
     }
     Ok(())
 }
 
+/// Aplica una mutación del schema que envía su coordinador, o le responde con el
+/// historial de mutaciones del schema de este nodo a otro que se está sincronizando
+fn schema(
+    nodo: &Mutex<Nodo>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    mensaje: &str,
+    tipo: u8,
+) -> Result<(), String> {
+    let mut nodo_guard = nodo
+        .lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())?;
+    let respuesta = match tipo {
+        MUTACION_SCHEMA => {
+            nodo_guard.aplicar_mutacion_schema(MutacionSchema::desde_texto(mensaje)?)?;
+            serializar_respuesta_nodos(vec!["ACK"], 0x0002)
+        }
+        _ => serializar_respuesta_schema(&nodo_guard.historial_schema.mutaciones()),
+    };
+    socket
+        .write_all(&respuesta)
+        .map_err(|_| "Error al escribir en el socket.".to_string())
+}
+
 fn insert(
-    nodo_guard: &mut Nodo,
+    nodo: &Mutex<Nodo>,
     socket: &mut StreamOwned<ServerConnection, TcpStream>,
     consulta: &mut Consulta,
     responsabilidad: u8,
     consistencia: u16,
 ) -> Result<(), String> {
     if responsabilidad == REPLICA {
-        let tabla = consulta.get_tabla();
-        let query = consulta.get_query();
-        nodo_guard.insertar_a_tabla(tabla.to_string(), obtener_row(query));
-        nodo_guard.persistir_insert(tabla.to_string(), obtener_row(query));
-        nodo_guard.timestamp += 1;
+        let mut nodo_guard = bloquear(nodo)?;
+        nodo_guard.aplicar_escritura(consulta)?;
         println!("Se han insertado los datos en la replica {}", nodo_guard.ip);
         println!("- - - - - - - - - - - - - - - - - - - - - - - - - - - -");
         // Luego de haber insertado en la tabla de esta réplica y persistir los datos en su
@@ -180,7 +373,7 @@ fn insert(
 
         return Ok(());
     }
-    nodo_guard.execute_query(consulta, LevelConsistency::create(consistencia))?;
+    ejecutar_sin_bloquear(nodo, consulta, LevelConsistency::create(consistencia)?)?;
     Ok(())
 }
 
@@ -190,20 +383,23 @@ fn select(
     consulta: &mut Consulta,
     responsabilidad: u8,
 ) -> Result<(), String> {
-    let keyspace = nodo_guard.get_key()?;
-    let tabla_elegida = keyspace.tablas.get(consulta.get_tabla());
+    let tabla_elegida = nodo_guard.get_tabla(consulta.get_tabla())?;
     let condicion = consulta.get_where();
     let mut respuesta: Vec<String> = Vec::new();
 
-    if let Some(&tabla_elegida) = tabla_elegida.as_ref() {
-        let auxiliar = tabla_elegida.select(condicion, consulta.get_query().to_string());
+    if let Some(tabla_elegida) = tabla_elegida {
+        let auxiliar = tabla_elegida.select(condicion, &consulta.get_seleccion());
         for linea in auxiliar.iter() {
             if !respuesta.contains(linea) {
                 respuesta.push(linea.to_string());
             }
         }
     }
-    if responsabilidad == REPLICA {
+    if responsabilidad == DIGEST {
+        // El coordinador solo necesita el digest, para compararlo con el de los datos
+        respuesta = vec![digest(&respuesta)];
+    }
+    if responsabilidad == REPLICA || responsabilidad == DIGEST {
         // Si bien la lógica es similar al caso en que no sea una réplica el que reciba
         // la consulta, en este caso también es necesario devolver el timestamp del nodo
         // ya que luego será usado para el read repair
@@ -220,17 +416,14 @@ fn select(
 }
 
 fn delete(
-    nodo_guard: &mut Nodo,
+    nodo: &Mutex<Nodo>,
     socket: &mut StreamOwned<ServerConnection, TcpStream>,
     consulta: &mut Consulta,
     responsabilidad: u8,
     consistencia: u16,
 ) -> Result<(), String> {
     if responsabilidad == REPLICA {
-        let tabla = consulta.get_tabla();
-        nodo_guard.eliminar_en_tabla(tabla.to_string(), consulta.get_where());
-        nodo_guard.persistir_delete(tabla.to_string())?;
-        nodo_guard.timestamp += 1;
+        bloquear(nodo)?.aplicar_escritura(consulta)?;
         // Luego de haber eliminado en la tabla de esta réplica y persistir los datos en su archivo
         // es necesario devolver un acknowledge al nodo responsable
         let ack_serializado =
@@ -241,26 +434,34 @@ fn delete(
 
         return Ok(());
     }
-    nodo_guard.execute_query(consulta, LevelConsistency::create(consistencia))?;
+    ejecutar_sin_bloquear(nodo, consulta, LevelConsistency::create(consistencia)?)?;
     Ok(())
 }
 
 fn update(
-    nodo_guard: &mut Nodo,
+    nodo: &Mutex<Nodo>,
     socket: &mut StreamOwned<ServerConnection, TcpStream>,
     consulta: &mut Consulta,
     responsabilidad: u8,
     consistencia: u16,
 ) -> Result<(), String> {
+    if responsabilidad == LIDER_CONTADOR {
+        // El coordinador espera a que el líder reúna los ACKs de las réplicas, o su error
+        let respuesta =
+            match ejecutar_sin_bloquear(nodo, consulta, LevelConsistency::create(consistencia)?) {
+                Ok(_) => "ACK".to_string(),
+                Err(e) => e,
+            };
+        socket
+            .write_all(&serializar_respuesta_nodos(
+                vec![respuesta.as_str()],
+                0x0002,
+            ))
+            .map_err(|_| "Error al escribir en el socket.".to_string())?;
+        return Ok(());
+    }
     if responsabilidad == REPLICA {
-        let tabla = consulta.get_tabla();
-        nodo_guard.update_en_tabla(
-            tabla.to_string(),
-            consulta.get_where(),
-            consulta.get_query(),
-        );
-        nodo_guard.persistir_update(tabla.to_string())?;
-        nodo_guard.timestamp += 1;
+        bloquear(nodo)?.aplicar_escritura(consulta)?;
         // Luego de haber actualizado en la tabla de esta réplica y persistir los datos en su archivo
         // es necesario devolver un acknowledge al nodo responsable
         let ack_serializado =
@@ -271,52 +472,36 @@ fn update(
 
         return Ok(());
     }
-    nodo_guard.execute_query(consulta, LevelConsistency::create(consistencia))?;
+    ejecutar_sin_bloquear(nodo, consulta, LevelConsistency::create(consistencia)?)?;
     Ok(())
 }
 
-fn create_table(
-    nodo_guard: &mut Nodo,
-    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+/// Ejecuta una consulta que coordina este nodo bloqueándolo solo mientras la prepara:
+/// lo que falta enviar a las demás réplicas o nodos se envía después de liberarlo, así
+/// una réplica lenta no frena a las demás consultas. Las fases de Paxos que le tocan a
+/// este nodo como réplica vuelven a bloquearlo mientras las atiende
+fn ejecutar_sin_bloquear(
+    nodo: &Mutex<Nodo>,
     consulta: &mut Consulta,
-) -> Result<(), String> {
-    let headers = obtener_headers_table(consulta.get_query());
-    let tabla = consulta.get_tabla();
-    let tabla_nueva = Tabla::new(tabla.to_string(), headers.clone());
-
-    let keyspace_actual = nodo_guard.keyspace_actual.to_string();
-    let keyspace = nodo_guard
-        .keyspaces
-        .get_mut(&keyspace_actual)
-        .ok_or("No pudo crearse la tabla correctamente.".to_string())?;
-    keyspace.tablas.insert(tabla.to_string(), tabla_nueva);
+    consistencia: LevelConsistency,
+) -> Result<Option<Vec<String>>, String> {
+    let ejecucion = bloquear(nodo)?.preparar_consulta(consulta, consistencia)?;
+    ejecucion
+        .terminar(|fase, ballot, sentencia| bloquear(nodo)?.atender_paxos(fase, ballot, sentencia))
+}
 
-    let path = format!(
-        "bdd/src/{}/{}_{}.csv",
-        keyspace_actual, tabla, nodo_guard.ip
-    );
-    nodo_guard.persistir_tabla_nueva(path);
-    nodo_guard.timestamp += 1;
-    let ack_serializado = serializar_respuesta_nodos(vec![String::from("ACK").as_str()], 0x0002);
-    socket
-        .write_all(&ack_serializado)
-        .map_err(|_| "Error al escribir en el socket.".to_string())?;
-    Ok(())
+fn bloquear(nodo: &Mutex<Nodo>) -> Result<MutexGuard<'_, Nodo>, String> {
+    nodo.lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())
 }
 
-fn create_keyspace(
+/// Aplica en este nodo la sentencia DDL o el TRUNCATE que le envía el coordinador
+fn ddl(
     nodo_guard: &mut Nodo,
     socket: &mut StreamOwned<ServerConnection, TcpStream>,
-    consulta: &mut Consulta,
+    consulta: &Consulta,
 ) -> Result<(), String> {
-    let (strategy, replication_factor) = obtener_tipo_strategy_y_replication(consulta.get_query());
-    if strategy != "SimpleStrategy" {
-        return Err("No pudo crearse el keyspace correctamente.".to_string());
-    }
-    let tabla = consulta.get_tabla();
-    let k = Keyspace::new(tabla.to_string(), replication_factor, strategy);
-    nodo_guard.keyspaces.insert(tabla.to_string(), k);
-    nodo_guard.timestamp += 1;
+    nodo_guard.aplicar_ddl(consulta)?;
     let ack_serializado = serializar_respuesta_nodos(vec![String::from("ACK").as_str()], 0x0002);
     socket
         .write_all(&ack_serializado)
@@ -356,13 +541,7 @@ fn handle_gossip(
                 {
                     mis_desactualizados.push_str(&format!("{}:{}:{} ", ip, gen, ver));
                 } else {
-                    mis_actualizados.push_str(&format!(
-                        "{}:{}:{}:{} ",
-                        ip,
-                        metadata_propia.heartbeat_state.generation,
-                        metadata_propia.heartbeat_state.version,
-                        metadata_propia.application_state.status
-                    ));
+                    mis_actualizados.push_str(&format!("{} ", metadata_propia.estado_gossip(ip)));
                 }
             } else {
                 mis_desactualizados.push_str(&format!("{}:{}:{} ", ip, gen, ver));
@@ -371,13 +550,7 @@ fn handle_gossip(
 
         for (ip, metadata) in nodo_guard.metadata_nodos.iter() {
             if !deserializada.contains(ip) {
-                mis_actualizados.push_str(&format!(
-                    "{}:{}:{}:{} ",
-                    ip,
-                    metadata.heartbeat_state.generation,
-                    metadata.heartbeat_state.version,
-                    metadata.application_state.status
-                ));
+                mis_actualizados.push_str(&format!("{} ", metadata.estado_gossip(ip)));
             }
         }
 
@@ -418,18 +591,24 @@ fn handle_gossip(
             .map_err(|_| "No se pudo convertir a u32".to_string())?;
         let state_str = ip_gen_ver_status[3];
         let state = NodeStatus::create(state_str);
+        let schema = schema_gossip(&ip_gen_ver_status)?;
         let mut nodo_guard = nodo.lock().unwrap();
         let hash_result = hashear(ip)?;
         let already_exists = nodo_guard.nodos.contains_key(&hash_result);
         if let Some(metadata_propia) = nodo_guard.metadata_nodos.get_mut(ip) {
             metadata_propia.heartbeat_state.version = ver;
             metadata_propia.heartbeat_state.generation = gen;
+            metadata_propia.application_state.schema = schema;
             let delete_ip = metadata_propia.application_state.status == NodeStatus::Normal
                 && state == NodeStatus::Down;
+            let cambio_estado = metadata_propia.application_state.status != state;
             metadata_propia.application_state.status = state.clone();
             if nodo_guard.ip == ip {
                 continue;
             }
+            if cambio_estado {
+                nodo_guard.notificar_estado_nodo(ip, &state);
+            }
             if delete_ip {
                 let hash_result = hashear(ip)?;
                 nodo_guard.nodos.remove(&hash_result);
@@ -440,27 +619,7 @@ fn handle_gossip(
 
                 //Hay que eliminar el nodo de la lista de nodos que ya tengo en mi metadata (nodo que recibe)
 
-                if let Some(keyspace) = nodo_guard.keyspaces.get(&nodo_guard.keyspace_actual) {
-                    let tablas: Vec<_> = keyspace.tablas.keys().cloned().collect();
-                    for tabla in tablas {
-                        if tabla == "AEROPUERTOS" {
-                            continue;
-                        }
-
-                        let path = format!(
-                            "bdd/src/{}/{}_{}.csv",
-                            nodo_guard.keyspace_actual, tabla, ip
-                        );
-                        if fs::metadata(&path).is_ok() {
-                            OpenOptions::new()
-                                .write(true)
-                                .truncate(true)
-                                .open(&path)
-                                .map_err(|e| format!("Error al abrir el archivo: {}", e))?;
-                        }
-                    }
-                }
-
+                nodo_guard.vaciar_archivos_nodo(ip)?;
                 nodo_guard.actualizar_replicas();
             }
             if !already_exists && state == NodeStatus::Normal {
@@ -469,25 +628,15 @@ fn handle_gossip(
             continue;
         }
 
-        let endpoint = EndpointData::new(gen, ver, state);
+        let endpoint = EndpointData::new(gen, ver, state).con_schema(schema);
 
         nodo_guard.metadata_nodos.insert(ip.to_string(), endpoint);
         let hash_result = hashear(ip)?;
         nodo_guard.nodos.insert(hash_result, ip.to_string());
         nodo_guard.actualizar_replicas();
-        let keyspace_path = format!("bdd/src/{}", nodo_guard.keyspace_actual);
-        if fs::metadata(keyspace_path).is_ok() {
-            if let Some(keyspace) = nodo_guard.keyspaces.get(&nodo_guard.keyspace_actual) {
-                for tabla in keyspace.tablas.keys() {
-                    let path = format!(
-                        "bdd/src/{}/{}_{}.csv",
-                        nodo_guard.keyspace_actual, tabla, ip
-                    );
-                    if fs::metadata(&path).is_err() && File::create(path).is_err() {
-                        println!("Hubo un error de creacion.");
-                    }
-                }
-            }
+        nodo_guard.notificar_nodo_nuevo(ip);
+        if let Err(e) = nodo_guard.crear_archivos_nodo(ip) {
+            println!("Hubo un error de creacion: {}", e);
         }
     }
     socket
@@ -499,18 +648,293 @@ fn handle_gossip(
 
 fn handle_client_request(
     nodo: Arc<Mutex<Nodo>>,
+    auditoria: &Auditoria,
     socket: &mut StreamOwned<ServerConnection, TcpStream>,
 ) -> Result<(), String> {
-    let (deserialized_request, consistency, tipo) = deserializar_consulta(&mut *socket)?;
-    startup_server_client(socket, tipo)?;
-    let mut nodo_guard = nodo.lock().unwrap();
-    let mut consulta = procesar_consulta(&deserialized_request)?;
-    let option_vector = nodo_guard
-        .execute_query(&mut consulta, LevelConsistency::create(consistency))
-        .map_err(|e| format!("No se ha podido ejecutar la consulta, debido a {}.", e))?;
+    let origen = socket
+        .sock
+        .peer_addr()
+        .map(|direccion| direccion.to_string())
+        .unwrap_or("-".to_string());
+    let (mut deserialized_request, mut parametros, mut tipo) =
+        deserializar_consulta(&mut *socket, None)?;
+    let autenticador = autenticador_configurado();
+    if autenticador.is_some() && tipo != STARTUP {
+        let mensaje = "Se requiere autenticación, primero hay que enviar un STARTUP.";
+        escribir_error(socket, ERROR_UNAUTHORIZED, mensaje, None)?;
+        return Err(mensaje.to_string());
+    }
+    let compresion = startup_server_client(socket, tipo, &deserialized_request, autenticador)?;
+    let mut usuario = None;
+    if tipo == STARTUP {
+        if autenticador.is_some() {
+            usuario = Some(autenticar_server_client(
+                &nodo,
+                (auditoria, &origen),
+                socket,
+                compresion.as_ref(),
+            )?);
+        }
+        // Luego del READY (o del AUTH_SUCCESS) el cliente envía la consulta por la
+        // misma conexión, comprimida con el algoritmo negociado en el STARTUP
+        (deserialized_request, parametros, tipo) =
+            deserializar_consulta(&mut *socket, compresion.as_ref())?;
+    }
+    if tipo == REGISTER {
+        return register_server_client(
+            nodo,
+            socket,
+            (&deserialized_request, usuario.as_ref()),
+            compresion.as_ref(),
+        );
+    }
+    if tipo == SUSCRIBIR_CDC {
+        return suscribir_cdc_server_client(
+            nodo,
+            socket,
+            (&deserialized_request, usuario.as_ref()),
+            compresion.as_ref(),
+        );
+    }
+    // La conexión queda abierta para las consultas siguientes, con el keyspace que
+    // se haya elegido con USE, hasta que el cliente la cierre
+    let mut keyspace = None;
+    loop {
+        // Los errores de la consulta ya se le respondieron al cliente con un ERROR, y la
+        // sesión sigue con las consultas siguientes
+        let _ = atender_consulta(
+            &nodo,
+            (auditoria, &origen),
+            socket,
+            (&deserialized_request, &parametros),
+            (usuario.as_ref(), &mut keyspace),
+            compresion.as_ref(),
+        );
+        match deserializar_consulta(&mut *socket, compresion.as_ref()) {
+            Ok((consulta, parametros_consulta, _)) => {
+                (deserialized_request, parametros) = (consulta, parametros_consulta)
+            }
+            Err(_) => return Ok(()),
+        }
+    }
+}
+
+/// Atiende una consulta de la sesión de un cliente y la registra en la auditoría, aun
+/// si no se pudo parsear o sus parámetros son inválidos. Si la consulta no indica el
+/// keyspace de su tabla se usa el elegido en la sesión. Si la consulta falla se le
+/// responde al cliente con un ERROR, con código Server error si el error no
+/// corresponde a ningún otro
+fn atender_consulta(
+    nodo: &Mutex<Nodo>,
+    (auditoria, origen): (&Auditoria, &str),
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    (consulta_cql, parametros): (&str, &ParametrosQuery),
+    (usuario, keyspace_sesion): (Option<&String>, &mut Option<String>),
+    compresion: Option<&Compresion>,
+) -> Result<(), String> {
+    let inicio = Instant::now();
+    // Los errores de sintaxis llegan al cliente con la línea y la columna en que se encontraron
+    let consulta = procesar_consulta(consulta_cql)
+        .and_then(|consulta| consulta.con_keyspace(keyspace_sesion.as_deref()))
+        .inspect_err(|e| {
+            let codigo = match e.starts_with(ERROR_SINTAXIS) {
+                true => ERROR_SYNTAX,
+                false => ERROR_INVALID,
+            };
+            let _ = escribir_error(socket, codigo, e, compresion);
+            auditoria.registrar(EntradaAuditoria {
+                origen,
+                usuario: usuario.map(|usuario| usuario.as_str()),
+                keyspace: keyspace_sesion.as_deref().unwrap_or("-"),
+                categoria: CategoriaAuditoria::Error,
+                operacion: OPERACION_FALLIDA.to_string(),
+                sentencia: consulta_cql,
+                consistencia: None,
+                resultado: Err(e),
+                latencia: inicio.elapsed(),
+            });
+        })?
+        .con_rol(usuario.cloned());
+    let keyspace = keyspace_consulta(nodo, &consulta);
+    let parametros = LevelConsistency::create(parametros.consistencia)
+        .and_then(|consistencia| Ok((consistencia, pagina_pedida(parametros)?)))
+        .inspect_err(|e| {
+            let _ = escribir_error(socket, ERROR_PROTOCOLO, e, compresion);
+            auditoria.registrar(EntradaAuditoria {
+                origen,
+                usuario: consulta.get_rol(),
+                keyspace: &keyspace,
+                categoria: CategoriaAuditoria::de_consulta(consulta.get_type()),
+                operacion: consulta.get_type().to_string(),
+                sentencia: consulta.get_consulta_explicita(),
+                consistencia: None,
+                resultado: Err(e),
+                latencia: inicio.elapsed(),
+            });
+        });
+    let (consistencia, pagina) = parametros?;
+    let mut consulta = consulta.con_pagina(pagina);
+    let resultado = match consulta.get_type() {
+        TypeCQL::Use => usar_keyspace(nodo, socket, &consulta, keyspace_sesion, compresion),
+        TypeCQL::CreateKeyspace
+        | TypeCQL::AlterKeyspace
+        | TypeCQL::DropKeyspace
+        | TypeCQL::CreateTable
+        | TypeCQL::AlterTable
+        | TypeCQL::DropTable
+        | TypeCQL::CreateType
+        | TypeCQL::DropType
+        | TypeCQL::CreateView
+        | TypeCQL::DropView => cambiar_schema(
+            nodo,
+            socket,
+            &mut consulta,
+            consistencia.clone(),
+            compresion,
+        ),
+        _ => ejecutar_consulta_cliente(
+            nodo,
+            socket,
+            &mut consulta,
+            consistencia.clone(),
+            compresion,
+        ),
+    };
+    auditoria.registrar(EntradaAuditoria {
+        origen,
+        usuario: consulta.get_rol(),
+        keyspace: &keyspace,
+        categoria: CategoriaAuditoria::de_consulta(consulta.get_type()),
+        operacion: consulta.get_type().to_string(),
+        sentencia: consulta.get_consulta_explicita(),
+        consistencia: Some(&consistencia),
+        resultado: resultado.as_ref().map(|_| ()).map_err(|e| e.as_str()),
+        latencia: inicio.elapsed(),
+    });
+    if let Err(e) = &resultado {
+        let codigo = codigo_error(e).unwrap_or(ERROR_SERVIDOR);
+        let _ = escribir_error(socket, codigo, e, compresion);
+    }
+    resultado
+}
+
+/// Elige el keyspace de la sesión del cliente con un USE, respondiéndole con un
+/// RESULT de tipo SET_KEYSPACE. Devuelve Err si el keyspace no existe
+fn usar_keyspace(
+    nodo: &Mutex<Nodo>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    consulta: &Consulta,
+    keyspace_sesion: &mut Option<String>,
+    compresion: Option<&Compresion>,
+) -> Result<(), String> {
+    let keyspace = consulta.get_tabla();
+    let existe = nodo
+        .lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())?
+        .keyspaces
+        .contains_key(keyspace);
+    if !existe {
+        return Err(format!(
+            "{}: el keyspace {} no existe.",
+            CONSULTA_INVALIDA, keyspace
+        ));
+    }
+    *keyspace_sesion = Some(keyspace.to_string());
+    socket
+        .write_all(&serializar_set_keyspace_server_client(keyspace, compresion))
+        .map_err(|_| "Error al escribir en el socket.".to_string())
+}
+
+/// Ejecuta una sentencia DDL de un cliente y, si cambió el schema, espera a que los
+/// nodos vivos anuncien por gossip la nueva versión antes de responderle con un
+/// RESULT de tipo SCHEMA_CHANGE. Si no se alcanza el acuerdo a tiempo se le responde
+/// igual, y los nodos que falten obtienen el cambio al sincronizar su schema
+fn cambiar_schema(
+    nodo: &Mutex<Nodo>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    consulta: &mut Consulta,
+    consistencia: LevelConsistency,
+    compresion: Option<&Compresion>,
+) -> Result<(), String> {
+    let (version_previa, version, (keyspace, tabla), ejecucion) = {
+        let mut nodo_guard = nodo
+            .lock()
+            .map_err(|_| "No se pudo acceder al nodo.".to_string())?;
+        let version_previa = nodo_guard.historial_schema.version();
+        let ejecucion = nodo_guard.preparar_consulta(consulta, consistencia)?;
+        let (keyspace, tabla) = nodo_guard.separar_keyspace(consulta.get_tabla());
+        // Los cambios de un tipo se anuncian como cambios de su keyspace
+        let objetivo = match consulta.get_sentencia() {
+            sentencia if sentencia.tabla().is_some() => (keyspace, Some(tabla.to_string())),
+            sentencia if sentencia.udt().is_some() => (keyspace, None),
+            _ => (consulta.get_tabla().to_string(), None),
+        };
+        (
+            version_previa,
+            nodo_guard.historial_schema.version(),
+            objetivo,
+            ejecucion,
+        )
+    };
+    // La sentencia se envía a los demás nodos sin tener el nodo bloqueado
+    ejecucion.terminar(|fase, ballot, sentencia| {
+        bloquear(nodo)?.atender_paxos(fase, ballot, sentencia)
+    })?;
+    if version == version_previa {
+        // Con IF NOT EXISTS o IF EXISTS la sentencia puede no haber cambiado nada
+        return socket
+            .write_all(&serializar_void_server_client(compresion))
+            .map_err(|_| "Error al escribir en el socket.".to_string());
+    }
+    // Se espera sin mantener el bloqueo, para que el gossip pueda actualizar las versiones
+    let inicio = Instant::now();
+    while !nodo.lock().is_ok_and(|nodo| nodo.schema_acordado()) {
+        if inicio.elapsed() >= ESPERA_ACUERDO_SCHEMA {
+            println!(
+                "No se alcanzó el acuerdo del schema luego de {}",
+                consulta.get_type()
+            );
+            break;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    let cambio = match consulta.get_type() {
+        TypeCQL::CreateKeyspace | TypeCQL::CreateTable | TypeCQL::CreateView => {
+            CambioSchema::Created
+        }
+        TypeCQL::AlterKeyspace | TypeCQL::AlterTable | TypeCQL::CreateType | TypeCQL::DropType => {
+            CambioSchema::Updated
+        }
+        _ => CambioSchema::Dropped,
+    };
+    let respuesta =
+        serializar_schema_change_server_client(&cambio, &keyspace, tabla.as_deref(), compresion);
+    socket
+        .write_all(&respuesta)
+        .map_err(|_| "Error al escribir en el socket.".to_string())
+}
+
+/// Ejecuta la consulta de un cliente y le responde con las filas del resultado, si
+/// las hay. Devuelve Err si su rol no tiene permiso para ejecutarla, no hay
+/// suficientes réplicas vivas o el nivel de consistencia no se puede usar en ella
+fn ejecutar_consulta_cliente(
+    nodo: &Mutex<Nodo>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    consulta: &mut Consulta,
+    consistencia: LevelConsistency,
+    compresion: Option<&Compresion>,
+) -> Result<(), String> {
+    let option_vector = ejecutar_sin_bloquear(nodo, consulta, consistencia)?;
     if let Some(vector) = option_vector {
+        let mut metadata = nodo
+            .lock()
+            .map_err(|_| "No se pudo acceder al nodo.".to_string())?
+            .metadata_consulta(consulta)?;
+        let (vector, siguiente) = obtener_pagina(vector, consulta.get_pagina())?;
+        metadata.paging_state = siguiente.map(|estado| estado.serializar());
         let vec_strs: Vec<&str> = vector.iter().map(|s| s.as_str()).collect();
-        let respuesta_serializada = result_to_bytes_server_client(vec_strs, 0x0002)?;
+        let respuesta_serializada =
+            result_to_bytes_server_client(vec_strs, 0x0002, &metadata, compresion)?;
         // Crear la conexion al nodo que nos pidio informacion.
         socket
             .write_all(&respuesta_serializada)
@@ -519,22 +943,266 @@ fn handle_client_request(
     Ok(())
 }
 
+/// Devuelve el código del ERROR con el que se le responde al cliente según el prefijo
+/// del mensaje de error de la consulta, si corresponde a uno
+fn codigo_error(error: &str) -> Option<i32> {
+    if error.starts_with(NO_AUTORIZADO) {
+        Some(ERROR_UNAUTHORIZED)
+    } else if error.starts_with(NO_DISPONIBLE) {
+        Some(ERROR_UNAVAILABLE)
+    } else if error.starts_with(TIEMPO_AGOTADO_ESCRITURA) {
+        Some(ERROR_WRITE_TIMEOUT)
+    } else if error.starts_with(ESCRITURA_FALLIDA) {
+        Some(ERROR_WRITE_FAILURE)
+    } else if error.starts_with(TIEMPO_AGOTADO_LECTURA) {
+        Some(ERROR_READ_TIMEOUT)
+    } else if error.starts_with(LECTURA_FALLIDA) {
+        Some(ERROR_READ_FAILURE)
+    } else if error.starts_with(CONSISTENCIA_INVALIDA) || error.starts_with(CONSULTA_INVALIDA) {
+        Some(ERROR_INVALID)
+    } else {
+        None
+    }
+}
+
+/// Devuelve el keyspace sobre el que opera una consulta, para la auditoría
+fn keyspace_consulta(nodo: &Mutex<Nodo>, consulta: &Consulta) -> String {
+    match consulta.get_type() {
+        TypeCQL::CreateKeyspace | TypeCQL::AlterKeyspace | TypeCQL::DropKeyspace | TypeCQL::Use => {
+            consulta.get_tabla().to_string()
+        }
+        TypeCQL::Describe if consulta.get_sentencia().tabla().is_none() => {
+            match consulta.get_tabla() {
+                "" => KEYSPACE_SYSTEM_SCHEMA.to_string(),
+                keyspace => keyspace.to_string(),
+            }
+        }
+        _ if consulta
+            .get_sentencia()
+            .tabla()
+            .or(consulta.get_sentencia().udt())
+            .is_some() =>
+        {
+            nodo.lock()
+                .map(|nodo| nodo.separar_keyspace(consulta.get_tabla()).0)
+                .unwrap_or_default()
+        }
+        _ => KEYSPACE_AUTH.to_string(),
+    }
+}
+
+/// Página del resultado que pidió el cliente: el tamaño y la posición de su
+/// paging_state, si la indica
+fn pagina_pedida(parametros: &ParametrosQuery) -> Result<Option<Pagina>, String> {
+    let Some(page_size) = parametros.page_size else {
+        return Ok(None);
+    };
+    let desde = match &parametros.paging_state {
+        Some(paging_state) => Some(EstadoPaginacion::deserializar(paging_state)?),
+        None => None,
+    };
+    Ok(Some(Pagina {
+        tamanio: page_size.max(1) as usize,
+        desde,
+    }))
+}
+
+/// Si el cliente pidió el resultado paginado devuelve solo la página que
+/// sigue a su paging_state y la posición desde la cual retomar la próxima,
+/// en caso contrario devuelve todas las filas
+fn obtener_pagina(
+    filas: Vec<String>,
+    pagina: Option<&Pagina>,
+) -> Result<(Vec<String>, Option<EstadoPaginacion>), String> {
+    let Some(pagina) = pagina else {
+        return Ok((filas, None));
+    };
+    paginar_filas(filas, pagina.tamanio as i32, pagina.desde.as_ref())
+}
+
+/// Registra al cliente a los tipos de eventos pedidos y le responde READY, a partir
+/// de ahí la conexión queda abierta y se le envía cada evento que genere el nodo
+/// hasta que el cliente la cierre. Los ROW_CHANGE solo se le envían de las tablas
+/// que su rol puede leer
+fn register_server_client(
+    nodo: Arc<Mutex<Nodo>>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    (tipos, usuario): (&str, Option<&String>),
+    compresion: Option<&Compresion>,
+) -> Result<(), String> {
+    let tipos = TipoEvento::desde_register(tipos)?;
+    let eventos = nodo
+        .lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())?
+        .suscriptores
+        .suscribir(tipos);
+    socket
+        .write_all(&serializar_ready_server_client())
+        .map_err(|_| "Error al escribir en el socket.".to_string())?;
+
+    let mut tablas_autorizadas = HashMap::new();
+    for evento in eventos {
+        if let Evento::RowChange(mutacion) = &evento {
+            let autorizada = match tablas_autorizadas.get(&mutacion.tabla) {
+                Some(autorizada) => *autorizada,
+                None => {
+                    let autorizada = nodo
+                        .lock()
+                        .map_err(|_| "No se pudo acceder al nodo.".to_string())?
+                        .autorizar_lectura(&mutacion.tabla, usuario)
+                        .is_ok();
+                    tablas_autorizadas.insert(mutacion.tabla.to_string(), autorizada);
+                    autorizada
+                }
+            };
+            if !autorizada {
+                continue;
+            }
+        }
+        let evento_serializado = serializar_evento_server_client(&evento, compresion);
+        socket
+            .write_all(&evento_serializado)
+            .map_err(|_| "El cliente cerró la conexión de eventos.".to_string())?;
+    }
+    Ok(())
+}
+
+/// Suscribe al cliente a los logs CDC de la tabla pedida en todos los nodos, si su rol
+/// la puede leer, y le responde READY. Luego le envía como eventos ROW_CHANGE las
+/// mutaciones de los logs a partir del offset pedido y, a medida que ocurren, las
+/// nuevas, hasta que el cliente cierre la conexión
+fn suscribir_cdc_server_client(
+    nodo: Arc<Mutex<Nodo>>,
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    (suscripcion, usuario): (&str, Option<&String>),
+    compresion: Option<&Compresion>,
+) -> Result<(), String> {
+    let (tabla, offset) = suscripcion
+        .split_once(", ")
+        .ok_or("Suscripción al CDC mal formada.".to_string())?;
+    let offset = offset
+        .parse::<i64>()
+        .map_err(|_| "Offset de la suscripción al CDC inválido.".to_string())?;
+    let suscripcion = nodo
+        .lock()
+        .map_err(|_| "No se pudo acceder al nodo.".to_string())
+        .and_then(|mut nodo| {
+            nodo.autorizar_lectura(tabla, usuario)?;
+            nodo.suscribir_cdc(tabla, u64::try_from(offset).ok())
+        });
+    let mutaciones = suscripcion.inspect_err(|e| {
+        let codigo = codigo_error(e).unwrap_or(ERROR_INVALID);
+        let _ = escribir_error(socket, codigo, e, compresion);
+    })?;
+    socket
+        .write_all(&serializar_ready_server_client())
+        .map_err(|_| "Error al escribir en el socket.".to_string())?;
+
+    for mutacion in mutaciones {
+        let evento_serializado =
+            serializar_evento_server_client(&Evento::RowChange(mutacion), compresion);
+        socket
+            .write_all(&evento_serializado)
+            .map_err(|_| "El cliente cerró la conexión del CDC.".to_string())?;
+    }
+    Ok(())
+}
+
+/// Si la consulta es un STARTUP responde con READY, o con AUTHENTICATE si el nodo
+/// requiere autenticación, y devuelve la compresión que el cliente pidió en la
+/// opción COMPRESSION, si es que pidió alguna
 fn startup_server_client(
     socket: &mut StreamOwned<ServerConnection, TcpStream>,
     tipo: i8,
-) -> Result<(), String> {
+    opciones: &str,
+    autenticador: Option<&str>,
+) -> Result<Option<Compresion>, String> {
     match tipo {
         STARTUP => {
             println!("Autenticación: Servidor recibe STARTUP del cliente");
-            let ready = serializar_ready_server_client();
-            match socket.write_all(&ready) {
+            let compresion = Compresion::desde_startup(opciones)?;
+            let respuesta = match autenticador {
+                Some(autenticador) => serializar_authenticate_server_client(autenticador),
+                None => serializar_ready_server_client(),
+            };
+            match socket.write_all(&respuesta) {
                 Ok(_) => {
-                    println!("Servidor envía el READY al cliente");
-                    Ok(())
+                    println!("Servidor envía el READY o AUTHENTICATE al cliente");
+                    Ok(compresion)
                 }
                 Err(_) => Err("Error al escribir en el socket.".to_string()),
             }
         }
-        _ => Ok(()),
+        _ => Ok(None),
     }
 }
+
+/// Recibe los AUTH_RESPONSE del cliente hasta que envíe un token SASL PLAIN y verifica
+/// sus credenciales. Si son correctas responde AUTH_SUCCESS y devuelve el usuario con
+/// el que se autenticó, si no responde con un ERROR de credenciales incorrectas
+fn autenticar_server_client(
+    nodo: &Arc<Mutex<Nodo>>,
+    (auditoria, origen): (&Auditoria, &str),
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    compresion: Option<&Compresion>,
+) -> Result<String, String> {
+    for _ in 0..MAX_RONDAS_AUTENTICACION {
+        let (token, _, tipo) = deserializar_consulta(&mut *socket, compresion)?;
+        if tipo != AUTH_RESPONSE {
+            let mensaje = "Se esperaba un AUTH_RESPONSE.";
+            escribir_error(socket, ERROR_UNAUTHORIZED, mensaje, compresion)?;
+            return Err(mensaje.to_string());
+        }
+        if token.is_empty() {
+            // El cliente no envió sus credenciales, se le vuelven a pedir
+            socket
+                .write_all(&serializar_auth_challenge_server_client(None, compresion))
+                .map_err(|_| "Error al escribir en el socket.".to_string())?;
+            continue;
+        }
+        let credenciales = Credenciales::desde_token_plain(token.as_bytes())?;
+        let inicio = Instant::now();
+        let resultado = nodo
+            .lock()
+            .map_err(|_| "No se pudo acceder al nodo.".to_string())?
+            .autenticar(&credenciales);
+        auditoria.registrar(EntradaAuditoria {
+            origen,
+            usuario: Some(&credenciales.usuario),
+            keyspace: KEYSPACE_AUTH,
+            categoria: CategoriaAuditoria::Auth,
+            operacion: "LOGIN".to_string(),
+            sentencia: "",
+            consistencia: None,
+            resultado: resultado.as_ref().map(|_| ()).map_err(|e| e.as_str()),
+            latencia: inicio.elapsed(),
+        });
+        return match resultado {
+            Ok(rol) => {
+                socket
+                    .write_all(&serializar_auth_success_server_client(compresion))
+                    .map_err(|_| "Error al escribir en el socket.".to_string())?;
+                println!("Cliente autenticado con el rol {}", rol.nombre);
+                Ok(rol.nombre)
+            }
+            Err(e) => {
+                escribir_error(socket, ERROR_BAD_CREDENTIALS, &e, compresion)?;
+                Err(e)
+            }
+        };
+    }
+    let mensaje = "El cliente no envió sus credenciales.";
+    escribir_error(socket, ERROR_BAD_CREDENTIALS, mensaje, compresion)?;
+    Err(mensaje.to_string())
+}
+
+fn escribir_error(
+    socket: &mut StreamOwned<ServerConnection, TcpStream>,
+    codigo: i32,
+    mensaje: &str,
+    compresion: Option<&Compresion>,
+) -> Result<(), String> {
+    socket
+        .write_all(&serializar_error_server_client(codigo, mensaje, compresion))
+        .map_err(|_| "Error al escribir en el socket.".to_string())
+}
@@ -20,11 +20,9 @@ use interfaz::{handler::*, vuelo::Vuelo};
 /// - `o_number`: Coordenadas del aeropuerto de origen.
 /// - `d_number`: Coordenadas del aeropuerto de destino.
 pub fn gestionar_vuelo(vuelo: &mut Vuelo, o_number: (f32, f32), d_number: (f32, f32)) {
+    // VUELOS_DESTINO es una vista de VUELOS_ORIGEN que mantiene la base de datos
     let query_insert_origen = construir_consulta_insert(vuelo, "VUELOS_ORIGEN".to_string());
-    let query_insert_destino = construir_consulta_insert(vuelo, "VUELOS_DESTINO".to_string());
-    console_send_query(query_insert_origen, LevelConsistency::Strong);
-
-    console_send_query(query_insert_destino, LevelConsistency::Strong);
+    console_send_query(query_insert_origen, LevelConsistency::Quorum);
 
     let variacion_en_x = d_number.0 - o_number.0;
     let variacion_en_y = d_number.1 - o_number.1;
@@ -169,7 +167,7 @@ pub fn obtener_aeropuertos() -> (Vec<String>, Vec<String>) {
     while lineas_seleccionadas_aeropuertos.is_empty() {
         let resultado = ejecutar_consulta(
             consulta_cql_aeropuertos.to_string(),
-            LevelConsistency::Strong,
+            LevelConsistency::Quorum,
         );
         lineas_seleccionadas_aeropuertos = resultado.unwrap_or_default();
     }
@@ -188,11 +186,8 @@ pub fn obtener_aeropuertos() -> (Vec<String>, Vec<String>) {
 
 fn send_update_general(vuelo: &Vuelo) {
     let query_update_origen = construir_consulta_update_consola(vuelo, "VUELOS_ORIGEN".to_string());
-    let query_update_destino =
-        construir_consulta_update_consola(vuelo, "VUELOS_DESTINO".to_string());
 
-    console_send_query(query_update_origen, LevelConsistency::Weak);
-    console_send_query(query_update_destino, LevelConsistency::Weak);
+    console_send_query(query_update_origen, LevelConsistency::One);
 }
 
 fn send_update_estado(vuelo: &Vuelo) {
@@ -202,23 +197,14 @@ fn send_update_estado(vuelo: &Vuelo) {
         "ESTADO_VUELO".to_string(),
         "Arrived".to_string(),
     );
-    let update_destino_estado = construir_consulta_update(
-        vuelo,
-        "VUELOS_DESTINO".to_string(),
-        "ESTADO_VUELO".to_string(),
-        "Arrived".to_string(),
-    );
 
-    console_send_query(update_origen_estado, LevelConsistency::Weak);
-    console_send_query(update_destino_estado, LevelConsistency::Weak);
+    console_send_query(update_origen_estado, LevelConsistency::One);
 }
 
 fn send_delete(vuelo: &Vuelo) {
     let query_delete_origen = construir_consulta_delete(vuelo, "VUELOS_ORIGEN".to_string());
-    let query_delete_destino = construir_consulta_delete(vuelo, "VUELOS_DESTINO".to_string());
 
-    console_send_query(query_delete_origen, LevelConsistency::Strong);
-    console_send_query(query_delete_destino, LevelConsistency::Strong);
+    console_send_query(query_delete_origen, LevelConsistency::Quorum);
 }
 
 pub fn console_send_query(consulta: String, consistencia: LevelConsistency) {
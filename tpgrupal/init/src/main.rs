@@ -5,7 +5,8 @@ use std::{
 };
 
 use cliente_servidor::funciones::{
-    construir_consulta_create_keyspace, construir_consulta_create_table, send_query,
+    construir_consulta_create_keyspace, construir_consulta_create_table,
+    construir_consulta_create_view, send_query,
 };
 use protocolo::serial_deserial::level_consistency::LevelConsistency;
 use std::thread::sleep;
@@ -19,7 +20,7 @@ fn main() {
 
     crear_tablas_vuelo_origen();
 
-    crear_tabla_vuelo_destino();
+    crear_vista_vuelos_destino();
 }
 
 fn crear_keyspace_aerolineas() {
@@ -29,7 +30,7 @@ fn crear_keyspace_aerolineas() {
         3,
     );
 
-    send_query(query, LevelConsistency::Strong);
+    send_query(query, LevelConsistency::Quorum);
 }
 
 fn crear_tabla_aeropuertos() {
@@ -44,8 +45,8 @@ fn crear_tabla_aeropuertos() {
     let tipos = [
         "INT".to_string(),
         "TEXT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
+        "FLOAT".to_string(),
+        "FLOAT".to_string(),
     ]
     .to_vec();
 
@@ -60,7 +61,7 @@ fn crear_tabla_aeropuertos() {
         clustering_colum,
     );
 
-    send_query(query, LevelConsistency::Strong);
+    send_query(query, LevelConsistency::Quorum);
 }
 
 fn insertar_aeropuertos() {
@@ -74,7 +75,7 @@ fn insertar_aeropuertos() {
         }
         let campos = line.split(",").collect::<Vec<&str>>();
         let query = format!("INSERT INTO AEROPUERTOS (ID_AEROPUERTO, NOMBRE, LATITUD, LONGITUD) VALUES ({}, {}, {}, {})", campos[0], campos[1], campos[2], campos[3]);
-        send_query(query, LevelConsistency::Strong);
+        send_query(query, LevelConsistency::Quorum);
         sleep(time::Duration::from_millis(75));
     }
 }
@@ -97,14 +98,14 @@ fn crear_tablas_vuelo_origen() {
     let tipos = [
         "TEXT".to_string(),
         "TEXT".to_string(),
-        "INT".to_string(),
         "TEXT".to_string(),
         "TEXT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
+        "TEXT".to_string(),
+        "FLOAT".to_string(),
+        "FLOAT".to_string(),
+        "FLOAT".to_string(),
+        "FLOAT".to_string(),
+        "FLOAT".to_string(),
     ]
     .to_vec();
 
@@ -119,10 +120,11 @@ fn crear_tablas_vuelo_origen() {
         clustering_colum,
     );
 
-    send_query(crear_tabla_aeropuertos, LevelConsistency::Strong);
+    send_query(crear_tabla_aeropuertos, LevelConsistency::Quorum);
 }
 
-fn crear_tabla_vuelo_destino() {
+// VUELOS_DESTINO es una vista de VUELOS_ORIGEN que mantiene la base de datos
+fn crear_vista_vuelos_destino() {
     let campos = [
         "DESTINO".to_string(),
         "FECHA".to_string(),
@@ -137,30 +139,15 @@ fn crear_tabla_vuelo_destino() {
     ]
     .to_vec();
 
-    let tipos = [
-        "TEXT".to_string(),
-        "TEXT".to_string(),
-        "INT".to_string(),
-        "TEXT".to_string(),
-        "TEXT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
-        "INT".to_string(),
-    ]
-    .to_vec();
+    let clustering_colum = ["ID_VUELO".to_string(), "ORIGEN".to_string()].to_vec();
 
-    let partition_key = ["DESTINO".to_string()].to_vec();
-    let clustering_colum = ["ID_VUELO".to_string()].to_vec();
-
-    let crear_tabla_aeropuertos = construir_consulta_create_table(
+    let query = construir_consulta_create_view(
         "VUELOS_DESTINO".to_string(),
+        "VUELOS_ORIGEN".to_string(),
         campos,
-        tipos,
-        partition_key,
+        "DESTINO".to_string(),
         clustering_colum,
     );
 
-    send_query(crear_tabla_aeropuertos, LevelConsistency::Strong);
+    send_query(query, LevelConsistency::Quorum);
 }
@@ -1,12 +1,14 @@
-use cliente_servidor::client_services::connection::connect_to_server;
+use cliente_servidor::client_services::connection::{connect_to_server, iniciar_sesion};
+use cliente_servidor::client_services::paginacion::{FilasPaginadas, PAGE_SIZE_DEFAULT};
 use cliente_servidor::client_services::receiving::receive_response;
 use cliente_servidor::client_services::sending::send_request;
-use protocolo::serial_deserial::cassandra::serializador_cliente_server::{
-    query_to_bytes_client_server, serializar_startup_client_server,
-};
+use protocolo::serial_deserial::cassandra::autenticacion::Credenciales;
+use protocolo::serial_deserial::cassandra::filas::Fila;
+use protocolo::serial_deserial::cassandra::serializador_cliente_server::query_to_bytes_client_server;
+use protocolo::serial_deserial::compresion::Compresion;
 use protocolo::serial_deserial::level_consistency::LevelConsistency;
+use protocolo::serial_deserial::valor_cql::ValorCQL;
 
-const READY: i8 = 0x02;
 use crate::vuelo::Vuelo;
 
 /// Crea una consulta INSERT con el formato de CQL para insertar
@@ -20,11 +22,26 @@ pub fn construir_consulta_insert(vuelo: &Vuelo, tabla: String) -> String {
     consulta
 }
 
+/// Inserta un vuelo nuevo en VUELOS_ORIGEN con una transacción liviana (IF NOT EXISTS),
+/// para que dos operadores no puedan crear vuelos con el mismo ID_VUELO. VUELOS_DESTINO
+/// es una vista de VUELOS_ORIGEN, así que la base de datos lo agrega también en ella.
+/// Devuelve si el vuelo se agregó.
+pub fn insertar_vuelo_nuevo(vuelo: &Vuelo) -> Result<bool, String> {
+    // Esta consulta es QUORUM porque permite editar el estado de un vuelo, en este caso agregando uno nuevo
+    let consulta_origen = format!(
+        "{} IF NOT EXISTS",
+        construir_consulta_insert(vuelo, "VUELOS_ORIGEN".to_string())
+    );
+    let filas = ejecutar_consulta_filas(consulta_origen, LevelConsistency::Quorum)?;
+    Ok(filas.first().and_then(|fila| fila.get("[applied]")) == Some(&ValorCQL::Boolean(true)))
+}
+
 /// Crea una consulta SELECT con el formato de CQL para obtener
 /// información de la base de datos.
 pub fn construir_consulta_select(tabla: String, aeropuerto: String, fecha: String) -> String {
     //Dependiendo de si la consulta es para aeropuertos o para vuelos ya sé cuál es la forma de la consulta en cada caso
     //Hago la distinción y le envío la consulta al serializador para que luego entonces pueda ser enviada al servidor
+    //FECHA no es parte de la clave primaria de las tablas de vuelos, por eso se filtra con ALLOW FILTERING
     let consulta;
     if tabla == "AEROPUERTOS" {
         consulta = "SELECT * FROM AEROPUERTOS".to_string();
@@ -33,7 +50,7 @@ pub fn construir_consulta_select(tabla: String, aeropuerto: String, fecha: Strin
             consulta = "SELECT * FROM VUELOS_ORIGEN".to_string();
         } else {
             consulta = format!(
-                "SELECT * FROM VUELOS_ORIGEN WHERE ORIGEN = {} AND FECHA = {}",
+                "SELECT * FROM VUELOS_ORIGEN WHERE ORIGEN = {} AND FECHA = {} ALLOW FILTERING",
                 aeropuerto, fecha
             );
         }
@@ -41,7 +58,7 @@ pub fn construir_consulta_select(tabla: String, aeropuerto: String, fecha: Strin
         consulta = "SELECT * FROM VUELOS_DESTINO".to_string();
     } else {
         consulta = format!(
-            "SELECT * FROM VUELOS_DESTINO WHERE DESTINO = {} AND FECHA = {}",
+            "SELECT * FROM VUELOS_DESTINO WHERE DESTINO = {} AND FECHA = {} ALLOW FILTERING",
             aeropuerto, fecha
         );
     }
@@ -115,7 +132,7 @@ pub fn ejecutar_consulta(
     consulta: String,
     tipo_consistencia: LevelConsistency,
 ) -> Result<Vec<String>, String> {
-    // Hacer la diferencia entre una consulta con consistencia Strong y Weak
+    // Hacer la diferencia entre consultas con distintos niveles de consistencia
     // Para eso puede recibirse un parámetro en la función que indique la
     // consistencia que esa consulta tiene que tener y luego se envía como siempre
 
@@ -136,29 +153,37 @@ pub fn ejecutar_consulta(
     }
 }
 
+/// Igual que `ejecutar_consulta` pero devuelve las filas con sus valores tipados,
+/// permitiendo acceder a cada uno por el nombre de su columna. El resultado se
+/// pide al servidor por páginas, que se recorren hasta obtener todas las filas.
+///
+/// # Parameters
+///
+/// - `consulta`: Un String con la consulta que se desea realizar.
+/// - `tipo_consistencia`: Corresponde al nivel de consistencia que se desea que tenga la consulta.
+///
+/// # Returns
+///
+/// Devuelve un Result con un vector de filas (en caso de ser exitosa) y un String
+/// en caso de que ocurra algún error.
+pub fn ejecutar_consulta_filas(
+    consulta: String,
+    tipo_consistencia: LevelConsistency,
+) -> Result<Vec<Fila>, String> {
+    FilasPaginadas::new(consulta, tipo_consistencia, PAGE_SIZE_DEFAULT)
+        .con_compresion(Compresion::Lz4)
+        .collect()
+}
+
 /// Se encarga de enviar el mensaje de inicio de conexión con la base de datos,
-/// para que luego el cliente pueda enviar consultas
+/// autenticándose con las credenciales de las variables de entorno USUARIO_CASSANDRA
+/// y PASSWORD_CASSANDRA si el servidor lo requiere, para verificar que luego el
+/// cliente pueda enviar consultas
 pub fn ejecutar_startup() {
-    let startup = serializar_startup_client_server();
-    let mut conexion = match connect_to_server() {
-        Ok(c) => c,
-        Err(_) => {
-            println!("No se pudo recibir respuesta");
-            return;
-        }
-    };
-    let _ = send_request(&mut conexion, startup);
     println!("Autenticación: Cliente envía STARTUP al servidor");
-    let respuesta = receive_response(&mut conexion);
-    match respuesta {
-        Ok(respuesta) => {
-            if respuesta.1 == READY {
-                println!("Cliente recibe READY");
-            } else {
-                println!("La autenticación falló");
-            }
-        }
-        Err(_) => println!("No se pudo recibir respuesta"),
+    match iniciar_sesion(None, Credenciales::desde_entorno().as_ref()) {
+        Ok(_) => println!("Cliente recibe READY o AUTH_SUCCESS"),
+        Err(e) => println!("La autenticación falló: {}", e),
     }
 }
 
@@ -196,7 +221,7 @@ mod tests {
         );
         assert_eq!(
             consulta,
-            "SELECT * FROM VUELOS_ORIGEN WHERE ORIGEN = MAD AND FECHA = 2021-06-01"
+            "SELECT * FROM VUELOS_ORIGEN WHERE ORIGEN = MAD AND FECHA = 2021-06-01 ALLOW FILTERING"
         );
     }
 
@@ -4,6 +4,7 @@ use eframe::egui::{viewport, CentralPanel, Context};
 use eframe::{App, Frame};
 use interfaz::aeropuerto::Aeropuerto;
 use interfaz::{aeropuerto, handler, plugins, vuelo};
+use protocolo::serial_deserial::cassandra::filas::Fila;
 use protocolo::serial_deserial::cassandra::serializador_cliente_server::query_to_bytes_client_server;
 use protocolo::serial_deserial::level_consistency::LevelConsistency;
 use rand::Rng;
@@ -40,6 +41,23 @@ fn main() -> Result<(), eframe::Error> {
     )
 }
 
+/// Arma un vuelo a partir de una fila de VUELOS_ORIGEN o VUELOS_DESTINO,
+/// como ambas tablas tienen las mismas columnas se accede por nombre
+fn vuelo_desde_fila(fila: &Fila) -> vuelo::Vuelo {
+    vuelo::Vuelo {
+        origen: fila.get_string("ORIGEN"),
+        fecha: fila.get_string("FECHA"),
+        id: fila.get_string("ID_VUELO"),
+        destino: fila.get_string("DESTINO"),
+        estado_vuelo: fila.get_string("ESTADO_VUELO"),
+        velocidad_actual: fila.get_f32("VELOCIDAD_ACTUAL").unwrap_or_default(),
+        altitud_actual: fila.get_f32("ALTITUD_ACTUAL").unwrap_or_default(),
+        latitud_actual: fila.get_f32("LATITUD_ACTUAL").unwrap_or_default(),
+        longitud_actual: fila.get_f32("LONGITUD_ACTUAL").unwrap_or_default(),
+        combustible: fila.get_f32("COMBUSTIBLE").unwrap_or_default(),
+    }
+}
+
 struct MyApp {
     tiles: Option<HttpTiles>,
     map_memory: MapMemory,
@@ -54,6 +72,7 @@ struct MyApp {
     my_position: Position,
     label_mostrar: bool,
     label_tiempo: Option<Instant>,
+    label_texto: String,
     vuelo_nuevo: vuelo::Vuelo,
     aeropuerto_seleccionado: Option<Aeropuerto>,
     fecha_seleccionada: Option<NaiveDate>,
@@ -75,8 +94,8 @@ impl Default for MyApp {
         let (sender, receiver) = mpsc::channel();
 
         // Antes de la primer consulta a la base de datos tenemos que primero autenticarnos para que nos autorice a poder hacer consultas
-        // Como no es necesario una autenticación con usuario y contraseña en cuanto se envíe el mensaje de STARTUP y se reciba el mensaje
-        // de READY como respuesta ya se pueden empezar a hacer consultas
+        // Si el servidor no requiere usuario y contraseña responde READY al STARTUP, si no responde AUTHENTICATE y el cliente envía
+        // las credenciales de USUARIO_CASSANDRA y PASSWORD_CASSANDRA, que también se usan en cada conexión siguiente
         handler::ejecutar_startup();
 
         // Lanza un hilo separado para realizar las consultas a la base de datos
@@ -93,10 +112,12 @@ impl Default for MyApp {
             //sé en qué columna está cada valor, eso uso para crear los aeropuertos y luego guardarlos en el vector de aeropuertos de
             //la estructura
 
-            let resultado =
-                handler::ejecutar_consulta(consulta_cql_aeropuertos, LevelConsistency::Strong);
-            let lineas_seleccionadas_aeropuertos: Vec<String> = match resultado {
-                // Esta consulta es Strong porque implica
+            let resultado = handler::ejecutar_consulta_filas(
+                consulta_cql_aeropuertos,
+                LevelConsistency::Quorum,
+            );
+            let filas_aeropuertos: Vec<Fila> = match resultado {
+                // Esta consulta es QUORUM porque implica
                 // consultar por un estado, en este caso de
                 // un aeropuerto
                 Ok(lineas) => lineas,
@@ -107,13 +128,12 @@ impl Default for MyApp {
             };
 
             let mut aeropuertos = Vec::new();
-            for linea in lineas_seleccionadas_aeropuertos.iter() {
-                let campos: Vec<&str> = linea.split(",").collect();
+            for fila in filas_aeropuertos.iter() {
                 let aeropuerto = aeropuerto::Aeropuerto {
-                    id: campos[0].parse().unwrap(),
-                    nombre: campos[1].to_string(),
-                    latitud: campos[2].parse().unwrap(),
-                    longitud: campos[3].parse().unwrap(),
+                    id: fila.get_i32("ID_AEROPUERTO").unwrap_or_default() as u32,
+                    nombre: fila.get_string("NOMBRE"),
+                    latitud: fila.get_f32("LATITUD").unwrap_or_default(),
+                    longitud: fila.get_f32("LONGITUD").unwrap_or_default(),
                     fue_clickeado: false,
                 };
                 aeropuertos.push(aeropuerto);
@@ -122,15 +142,15 @@ impl Default for MyApp {
 
             loop {
                 let consulta_cql_vuelos = handler::construir_consulta_select(
-                    // Esta consulta es Weak porque es una consulta que se realiza continuamente y se usa
+                    // Esta consulta es ONE porque es una consulta que se realiza continuamente y se usa
                     "VUELOS_ORIGEN".to_string(), // hacer un seguimiento de los vuelos, cuando estos están en curso
                     "".to_string(),
                     "".to_string(),
                 );
 
                 let resultado =
-                    handler::ejecutar_consulta(consulta_cql_vuelos, LevelConsistency::Weak);
-                let lineas_seleccionadas_vuelos: Vec<String> = match resultado {
+                    handler::ejecutar_consulta_filas(consulta_cql_vuelos, LevelConsistency::One);
+                let filas_vuelos: Vec<Fila> = match resultado {
                     Ok(lineas) => lineas,
                     Err(_) => {
                         println!("Error al obtener la información de los vuelos en curso.");
@@ -138,21 +158,8 @@ impl Default for MyApp {
                     }
                 };
                 let mut vuelos = Vec::new();
-                for linea in lineas_seleccionadas_vuelos.iter() {
-                    let campos: Vec<&str> = linea.split(",").collect();
-                    let vuelo = vuelo::Vuelo {
-                        origen: campos[0].to_string(),
-                        fecha: campos[1].to_string(),
-                        id: campos[2].parse().unwrap(),
-                        destino: campos[3].to_string(),
-                        estado_vuelo: campos[4].to_string(),
-                        velocidad_actual: campos[5].parse().unwrap(),
-                        altitud_actual: campos[6].parse().unwrap(),
-                        latitud_actual: campos[7].parse().unwrap(),
-                        longitud_actual: campos[8].parse().unwrap(),
-                        combustible: campos[9].parse().unwrap(),
-                    };
-                    vuelos.push(vuelo);
+                for fila in filas_vuelos.iter() {
+                    vuelos.push(vuelo_desde_fila(fila));
                 }
                 sender.send(Message::Vuelos(vuelos)).unwrap();
 
@@ -174,6 +181,7 @@ impl Default for MyApp {
             vuelos_fecha: vec![],
             label_mostrar: false,
             label_tiempo: None,
+            label_texto: String::new(),
             vuelo_nuevo: vuelo::Vuelo {
                 id: "".to_string(),
                 origen: "".to_string(),
@@ -337,18 +345,17 @@ impl App for MyApp {
                 if self.subventana_vuelos {
                     if !self.consulta_lista {
                         let consulta_cql_vuelos_origen_fecha = handler::construir_consulta_select(
-                            // Esta consulta es Strong porque es una consulta de vuelos que
+                            // Esta consulta es QUORUM porque es una consulta de vuelos que
                             "VUELOS_ORIGEN".to_string(), // se hace al hacer click en un aeropuerto y seleccionar una fecha
                             self.aeropuerto_seleccionado.clone().unwrap().nombre.clone(), // por lo que no implica vuelos en curso
                             self.fecha_seleccionada.unwrap().to_string(),
                         );
 
-                        let resultado = handler::ejecutar_consulta(
+                        let resultado = handler::ejecutar_consulta_filas(
                             consulta_cql_vuelos_origen_fecha,
-                            LevelConsistency::Strong,
+                            LevelConsistency::Quorum,
                         );
-                        let lineas_seleccionadas_vuelos_origen_fecha: Vec<String> = match resultado
-                        {
+                        let filas_vuelos_origen_fecha: Vec<Fila> = match resultado {
                             Ok(lineas) => lineas,
                             Err(_) => {
                                 println!("Error al obtener los vuelos salientes del aeropuerto seleccionado.");
@@ -356,57 +363,31 @@ impl App for MyApp {
                             }
                         };
                         self.vuelos_fecha.clear();
-                        for linea in lineas_seleccionadas_vuelos_origen_fecha.iter() {
-                            let campos: Vec<&str> = linea.split(",").collect();
-                            let vuelo = vuelo::Vuelo {
-                                origen: campos[0].to_string(),
-                                fecha: campos[1].to_string(),
-                                id: campos[2].to_string(),
-                                destino: campos[3].to_string(),
-                                estado_vuelo: campos[4].to_string(),
-                                velocidad_actual: campos[5].parse().unwrap(),
-                                altitud_actual: campos[6].parse().unwrap(),
-                                latitud_actual: campos[7].parse().unwrap(),
-                                longitud_actual: campos[8].parse().unwrap(),
-                                combustible: campos[9].parse().unwrap(),
-                            };
-                            self.vuelos_fecha.push(vuelo);
+                        for fila in filas_vuelos_origen_fecha.iter() {
+                            self.vuelos_fecha.push(vuelo_desde_fila(fila));
                         }
 
                         let consulta_cql_vuelos_destino_fecha = handler::construir_consulta_select(
-                            // Esta consulta es Strong porque es una consulta de vuelos que
+                            // Esta consulta es QUORUM porque es una consulta de vuelos que
                             "VUELOS_DESTINO".to_string(), // se hace al hacer click en un aeropuerto y seleccionar una fecha
                             self.aeropuerto_seleccionado.clone().unwrap().nombre.clone(), // por lo que no implica vuelos en curso
                             self.fecha_seleccionada.unwrap().to_string(),
                         );
 
-                        let resultado = handler::ejecutar_consulta(
+                        let resultado = handler::ejecutar_consulta_filas(
                             consulta_cql_vuelos_destino_fecha,
-                            LevelConsistency::Strong,
+                            LevelConsistency::Quorum,
                         );
 
-                        let consulta_cql_vuelos_destino_fecha: Vec<String> = match resultado {
+                        let filas_vuelos_destino_fecha: Vec<Fila> = match resultado {
                             Ok(lineas) => lineas,
                             Err(_) => {
                                 println!("Error al obtener los vuelos entrantes del aeropuerto seleccionado.");
                                 return;
                             }
                         };
-                        for linea in consulta_cql_vuelos_destino_fecha.iter() {
-                            let campos: Vec<&str> = linea.split(",").collect();
-                            let vuelo = vuelo::Vuelo {
-                                destino: campos[0].to_string(),
-                                fecha: campos[1].to_string(),
-                                id: campos[2].to_string(),
-                                origen: campos[3].to_string(),
-                                estado_vuelo: campos[4].to_string(),
-                                velocidad_actual: campos[5].parse().unwrap(),
-                                altitud_actual: campos[6].parse().unwrap(),
-                                latitud_actual: campos[7].parse().unwrap(),
-                                longitud_actual: campos[8].parse().unwrap(),
-                                combustible: campos[9].parse().unwrap(),
-                            };
-                            self.vuelos_fecha.push(vuelo);
+                        for fila in filas_vuelos_destino_fecha.iter() {
+                            self.vuelos_fecha.push(vuelo_desde_fila(fila));
                         }
                         self.consulta_lista = true;
                     }
@@ -484,42 +465,15 @@ impl App for MyApp {
                                         //llamarse al handler para que este arme la consulta y la envíe al servidor para
                                         //que este la procese y la almacene en la base de datos.
 
-                                        // Como se va a insertar un vuelo nuevo entonces en el siguiente frame deben actualizarse los vuelos
-                                        let consulta1 = handler::construir_consulta_insert(
-                                            &self.vuelo_nuevo,
-                                            "VUELOS_ORIGEN".to_string(),
-                                        );
-
-                                        let consulta2 = handler::construir_consulta_insert(
-                                            &self.vuelo_nuevo,
-                                            "VUELOS_DESTINO".to_string(),
-                                        );
-
-                                        let consulta_serializada1 = query_to_bytes_client_server(
-                                            // Esta consulta es Strong porque es una consulta que permite
-                                            &consulta1, // editar el estado de un vuelo, en este caso agregando uno nuevo
-                                            LevelConsistency::Strong,
-                                            0x00,
-                                        );
-
-                                        let consulta_serializada2 = query_to_bytes_client_server(
-                                            // Esta consulta es Strong porque es una consulta que permite
-                                            &consulta2, // editar el estado de un vuelo, en este caso agregando uno nuevo
-                                            LevelConsistency::Strong,
-                                            0x00,
-                                        );
-
-                                        let mut conexion = connect_to_server();
-
-                                        if let Ok(ref mut socket) = conexion {
-                                            let _ = send_request(socket, consulta_serializada1);
-                                        }
-
-                                        let mut conexion2 = connect_to_server();
-
-                                        if let Ok(ref mut socket) = conexion2 {
-                                            let _ = send_request(socket, consulta_serializada2);
-                                        }
+                                        // Como se va a insertar un vuelo nuevo entonces en el siguiente frame deben actualizarse los vuelos.
+                                        // Si otro operador ya creó un vuelo con el mismo ID la transacción no se aplica
+                                        self.label_texto =
+                                            match handler::insertar_vuelo_nuevo(&self.vuelo_nuevo) {
+                                                Ok(true) => "Vuelo agregado.",
+                                                Ok(false) => "Ya existe un vuelo con ese ID.",
+                                                Err(_) => "No se pudo agregar el vuelo.",
+                                            }
+                                            .to_string();
                                     }
                                 }
                             });
@@ -527,7 +481,7 @@ impl App for MyApp {
                             if self.label_mostrar {
                                 if let Some(tiempo) = self.label_tiempo {
                                     if tiempo.elapsed() < Duration::from_secs(3) {
-                                        ui.label("Vuelo agregado.");
+                                        ui.label(&self.label_texto);
                                     } else {
                                         self.label_mostrar = false;
                                     }
@@ -645,41 +599,21 @@ impl App for MyApp {
                                                     estado_vuelo.clone(),
                                                 );
 
-                                            let consulta_update_estado2 =
-                                                handler::construir_consulta_update(
-                                                    &vuelo_seleccionado,
-                                                    "VUELOS_DESTINO".to_string(),
-                                                    "ESTADO_VUELO".to_string(),
-                                                    estado_vuelo.clone(),
-                                                );
-
                                             let consulta_serializada_estado1 =
                                                 query_to_bytes_client_server(
-                                                    // Esta consulta es Strong porque es una consulta que permite
+                                                    // Esta consulta es QUORUM porque es una consulta que permite
                                                     &consulta_update_estado1, // editar el estado de un vuelo, en este caso actualizando uno
-                                                    LevelConsistency::Strong, // ya existente
+                                                    LevelConsistency::Quorum, // ya existente
                                                     0x00,
                                                 );
 
-                                            let consulta_serializada_estado2 =
-                                                query_to_bytes_client_server(
-                                                    // Esta consulta es Strong porque es una consulta que permite
-                                                    &consulta_update_estado2, // editar el estado de un vuelo, en este caso actualizando uno
-                                                    LevelConsistency::Strong, // ya existente
-                                                    0x00,
-                                                );
 
                                             let mut conexion = connect_to_server();
 
+                                            // VUELOS_DESTINO se actualiza sola, es una vista de VUELOS_ORIGEN
                                             if let Ok(ref mut socket) = conexion {
                                                 let _ = send_request(socket, consulta_serializada_estado1);
                                             }
-
-                                            let mut conexion2 = connect_to_server();
-
-                                            if let Ok(ref mut socket) = conexion2 {
-                                                let _ = send_request(socket, consulta_serializada_estado2);
-                                            }
                                         }
                                     }
                                 }
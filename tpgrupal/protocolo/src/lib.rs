@@ -1,20 +1,35 @@
 pub mod parser_cql {
+    pub mod ast;
+    pub mod condicion_lwt;
     pub mod condicion_where;
     pub mod consulta;
+    pub mod lexer;
+    pub mod opciones_rol;
     pub mod parseo_consulta;
+    pub mod parser;
+    pub mod permisos;
     pub mod type_cql;
 }
 
 pub mod serial_deserial {
     pub mod cassandra {
+        pub mod autenticacion;
         pub mod deserializador_cliente_server;
         pub mod deserializador_server_cliente;
+        pub mod eventos;
+        pub mod filas;
+        pub mod metadata_rows;
+        pub mod paginacion;
         pub mod serializador_cliente_server;
         pub mod serializador_server_cliente;
     }
     pub mod intra_nodos {
+        pub mod cdc;
         pub mod deserializador_nodo_envio;
         pub mod deserializador_nodo_respuesta;
+        pub mod pagina;
+        pub mod paxos;
+        pub mod schema;
         pub mod serializador_nodo_envio;
         pub mod serializador_nodo_respuesta;
     }
@@ -23,5 +38,9 @@ pub mod serial_deserial {
         pub mod serializador_gossip;
         pub mod type_message;
     }
+    pub mod compresion;
+    pub mod lector_bytes;
     pub mod level_consistency;
+    pub mod tipo_columna;
+    pub mod valor_cql;
 }
@@ -0,0 +1,553 @@
+use std::fmt;
+
+use super::{condicion_lwt::CondicionLwt, type_cql::TypeCQL};
+
+/// Valor de una sentencia, tal como se escribió en la consulta
+#[derive(Debug, Clone, PartialEq)]
+pub enum Termino {
+    Texto(String), // Sin las comillas y con las comillas escapadas ya resueltas
+    Numero(String),
+    Booleano(bool),
+    Nulo,
+    Incremento(String, i64), // columna + delta, en la asignación de un contador
+    SinComillas(String), // Valor sin comillas, que puede tener espacios (En curso) o guiones (2024-11-20)
+    Tupla(Vec<Termino>), // Lista de valores entre paréntesis, como la de un IN
+    Lista(Vec<Termino>), // [a, b]
+    Conjunto(Vec<Termino>), // {a, b}
+    Mapa(Vec<(Termino, Termino)>), // {clave: valor, ...}; también el {} vacío
+    Agregado(String, Box<Termino>), // columna + colección, en la asignación de una colección
+    Quitado(String, Box<Termino>), // columna - colección
+    Antepuesto(Box<Termino>, String), // lista + columna, que agrega al principio de la lista
+}
+
+impl fmt::Display for Termino {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Termino::Texto(texto) => write!(f, "'{}'", texto.replace('\'', "''")),
+            Termino::Numero(numero) => write!(f, "{}", numero),
+            Termino::Booleano(valor) => write!(f, "{}", valor),
+            Termino::Nulo => write!(f, "null"),
+            Termino::Incremento(columna, delta) if *delta < 0 => {
+                write!(f, "{} - {}", columna, delta.unsigned_abs())
+            }
+            Termino::Incremento(columna, delta) => write!(f, "{} + {}", columna, delta),
+            Termino::SinComillas(valor) => write!(f, "{}", valor),
+            Termino::Tupla(valores) => {
+                let valores = valores.iter().map(|valor| valor.to_string());
+                write!(f, "({})", valores.collect::<Vec<String>>().join(", "))
+            }
+            Termino::Lista(elementos) => write!(f, "[{}]", unir_terminos(elementos)),
+            Termino::Conjunto(elementos) => write!(f, "{{{}}}", unir_terminos(elementos)),
+            Termino::Mapa(pares) => {
+                let pares = pares
+                    .iter()
+                    .map(|(clave, valor)| format!("{}: {}", clave, valor));
+                write!(f, "{{{}}}", pares.collect::<Vec<String>>().join(", "))
+            }
+            Termino::Agregado(columna, valor) => write!(f, "{} + {}", columna, valor),
+            Termino::Quitado(columna, valor) => write!(f, "{} - {}", columna, valor),
+            Termino::Antepuesto(valor, columna) => write!(f, "{} + {}", valor, columna),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operador {
+    Igual,
+    Distinto,
+    Menor,
+    MenorIgual,
+    Mayor,
+    MayorIgual,
+    In,
+    Contains,
+    ContainsKey,
+}
+
+impl Operador {
+    pub fn desde_simbolo(simbolo: &str) -> Option<Self> {
+        match simbolo {
+            "=" => Some(Operador::Igual),
+            "!=" => Some(Operador::Distinto),
+            "<" => Some(Operador::Menor),
+            "<=" => Some(Operador::MenorIgual),
+            ">" => Some(Operador::Mayor),
+            ">=" => Some(Operador::MayorIgual),
+            _ => None,
+        }
+    }
+
+    /// Indica si el operador restringe un rango de valores
+    pub fn es_rango(&self) -> bool {
+        matches!(
+            self,
+            Operador::Menor | Operador::MenorIgual | Operador::Mayor | Operador::MayorIgual
+        )
+    }
+}
+
+impl fmt::Display for Operador {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operador = match self {
+            Operador::Igual => "=",
+            Operador::Distinto => "!=",
+            Operador::Menor => "<",
+            Operador::MenorIgual => "<=",
+            Operador::Mayor => ">",
+            Operador::MayorIgual => ">=",
+            Operador::In => "IN",
+            Operador::Contains => "CONTAINS",
+            Operador::ContainsKey => "CONTAINS KEY",
+        };
+        write!(f, "{}", operador)
+    }
+}
+
+/// Comparación de una columna con un valor en el WHERE. Con `token` se compara
+/// el token de la partition key, como en `token(ORIGEN) > 100`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relacion {
+    pub columna: String,
+    pub token: bool,
+    pub operador: Operador,
+    pub valor: Termino, // Una Tupla con los valores si el operador es IN
+}
+
+impl fmt::Display for Relacion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.token {
+            true => write!(
+                f,
+                "token({}) {} {}",
+                self.columna, self.operador, self.valor
+            ),
+            false => write!(f, "{} {} {}", self.columna, self.operador, self.valor),
+        }
+    }
+}
+
+fn unir_terminos(terminos: &[Termino]) -> String {
+    let terminos = terminos.iter().map(|termino| termino.to_string());
+    terminos.collect::<Vec<String>>().join(", ")
+}
+
+/// Asignación de un valor a una columna en el SET de un UPDATE
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asignacion {
+    pub columna: String,
+    pub elemento: Option<Termino>, // Clave de un map o posición de una lista: m['k'] = v
+    pub campo: Option<String>,     // Campo de un UDT: pos.lat = v
+    pub valor: Termino,
+}
+
+impl Asignacion {
+    /// La columna con el elemento o el campo que se asigna, como se escribe en la consulta
+    pub fn destino(&self) -> String {
+        match (&self.elemento, &self.campo) {
+            (Some(elemento), _) => format!("{}[{}]", self.columna, elemento),
+            (None, Some(campo)) => format!("{}.{}", self.columna, campo),
+            (None, None) => self.columna.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Asignacion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.destino(), self.valor)
+    }
+}
+
+/// Expresión de la lista de columnas de un SELECT
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expresion {
+    Columna(String),
+    Campo(String, String), // Campo de una columna de un UDT: pos.lat
+    WriteTime(String),     // Momento de la última escritura de la columna, en microsegundos
+    Ttl(String),           // Segundos que le quedan a la columna antes de expirar
+    Count(Option<String>), // COUNT(*) si no tiene columna
+    Min(String),
+    Max(String),
+    Sum(String),
+    Avg(String),
+}
+
+impl Expresion {
+    /// Indica si es una función de agregación, que resume las filas de cada grupo
+    pub fn es_agregado(&self) -> bool {
+        matches!(
+            self,
+            Expresion::Count(_)
+                | Expresion::Min(_)
+                | Expresion::Max(_)
+                | Expresion::Sum(_)
+                | Expresion::Avg(_)
+        )
+    }
+
+    /// Columna sobre la que se calcula la expresión, salvo en COUNT(*)
+    pub fn columna(&self) -> Option<&str> {
+        match self {
+            Expresion::Count(columna) => columna.as_deref(),
+            Expresion::Columna(columna)
+            | Expresion::Campo(columna, _)
+            | Expresion::WriteTime(columna)
+            | Expresion::Ttl(columna)
+            | Expresion::Min(columna)
+            | Expresion::Max(columna)
+            | Expresion::Sum(columna)
+            | Expresion::Avg(columna) => Some(columna),
+        }
+    }
+}
+
+impl fmt::Display for Expresion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let funcion = match self {
+            Expresion::Columna(columna) => return write!(f, "{}", columna),
+            Expresion::Campo(columna, campo) => return write!(f, "{}.{}", columna, campo),
+            Expresion::Count(None) => return write!(f, "count(*)"),
+            Expresion::WriteTime(_) => "writetime",
+            Expresion::Ttl(_) => "ttl",
+            Expresion::Count(_) => "count",
+            Expresion::Min(_) => "min",
+            Expresion::Max(_) => "max",
+            Expresion::Sum(_) => "sum",
+            Expresion::Avg(_) => "avg",
+        };
+        write!(f, "{}({})", funcion, self.columna().unwrap_or_default())
+    }
+}
+
+/// Columna que devuelve un SELECT, con su alias si se indicó con AS
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    pub expresion: Expresion,
+    pub alias: Option<String>,
+}
+
+impl Selector {
+    /// Nombre de la columna en el resultado
+    pub fn nombre(&self) -> String {
+        match &self.alias {
+            Some(alias) => alias.to_string(),
+            None => self.expresion.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.alias {
+            Some(alias) => write!(f, "{} AS {}", self.expresion, alias),
+            None => write!(f, "{}", self.expresion),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orden {
+    Ascendente,
+    Descendente,
+}
+
+/// Qué devuelve un SELECT de las filas que cumplen el WHERE y en qué orden
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Seleccion {
+    pub distinct: bool,
+    pub selectores: Vec<Selector>, // Vacío si se seleccionan todas las columnas con *
+    pub agrupamiento: Vec<String>, // GROUP BY sobre un prefijo de la clave primaria
+    pub orden: Vec<(String, Orden)>, // ORDER BY sobre columnas de clustering
+    pub limite_por_particion: Option<usize>,
+    pub limite: Option<usize>,
+}
+
+impl Seleccion {
+    /// Indica si el SELECT devuelve las filas completas, como están en la tabla
+    pub fn filas_completas(&self) -> bool {
+        self.selectores.is_empty() && !self.distinct && !self.agrupa()
+    }
+
+    /// Indica si el SELECT resume las filas en grupos, con GROUP BY o con funciones de
+    /// agregación sobre todas las filas
+    pub fn agrupa(&self) -> bool {
+        !self.agrupamiento.is_empty()
+            || self
+                .selectores
+                .iter()
+                .any(|selector| selector.expresion.es_agregado())
+    }
+}
+
+impl fmt::Display for Seleccion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.distinct {
+            write!(f, "DISTINCT ")?;
+        }
+        if self.selectores.is_empty() {
+            return write!(f, "*");
+        }
+        let selectores = self.selectores.iter().map(|selector| selector.to_string());
+        write!(f, "{}", selectores.collect::<Vec<String>>().join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefinicionColumna {
+    pub nombre: String,
+    pub tipo: String,
+}
+
+impl fmt::Display for DefinicionColumna {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.nombre, self.tipo)
+    }
+}
+
+/// Cambio de las columnas de una tabla en un ALTER TABLE
+#[derive(Debug, Clone, PartialEq)]
+pub enum CambioTabla {
+    Agregar(Vec<DefinicionColumna>),
+    Quitar(Vec<String>),
+    Renombrar(Vec<(String, String)>), // Nombre actual y nombre nuevo de cada columna
+}
+
+impl fmt::Display for CambioTabla {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (cambio, columnas) = match self {
+            CambioTabla::Agregar(columnas) => (
+                "ADD",
+                columnas
+                    .iter()
+                    .map(|columna| format!("{} {}", columna.nombre, columna.tipo))
+                    .collect::<Vec<String>>(),
+            ),
+            CambioTabla::Quitar(columnas) => ("DROP", columnas.to_vec()),
+            CambioTabla::Renombrar(columnas) => {
+                let renombres = columnas
+                    .iter()
+                    .map(|(columna, nueva)| format!("{} TO {}", columna, nueva));
+                let renombres = renombres.collect::<Vec<String>>().join(" AND ");
+                return write!(f, "RENAME {}", renombres);
+            }
+        };
+        write!(f, "{} ({})", cambio, columnas.join(", "))
+    }
+}
+
+/// Lo que muestra un DESCRIBE: los keyspaces, las tablas, o la definición de un
+/// keyspace con sus tablas o de una tabla
+#[derive(Debug, Clone, PartialEq)]
+pub enum Descripcion {
+    Keyspaces,
+    Tablas,
+    Keyspace(String),
+    Tabla(String),
+}
+
+/// Sentencia CQL ya parseada
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sentencia {
+    Select {
+        tabla: String,
+        seleccion: Seleccion,
+        condiciones: Vec<Relacion>,
+        permitir_filtrado: bool, // ALLOW FILTERING
+    },
+    Insert {
+        tabla: String,
+        columnas: Vec<String>,
+        valores: Vec<Termino>,
+        condicion_lwt: Option<CondicionLwt>,
+        momento: Option<i64>, // USING TIMESTAMP, en microsegundos
+    },
+    Update {
+        tabla: String,
+        asignaciones: Vec<Asignacion>,
+        condiciones: Vec<Relacion>,
+        condicion_lwt: Option<CondicionLwt>,
+        momento: Option<i64>,
+    },
+    Delete {
+        tabla: String,
+        columnas: Vec<Asignacion>, // Columnas o elementos borrados, como asignaciones de null
+        condiciones: Vec<Relacion>,
+        condicion_lwt: Option<CondicionLwt>,
+        momento: Option<i64>,
+    },
+    CreateTable {
+        tabla: String,
+        columnas: Vec<DefinicionColumna>,
+        clave_particion: Vec<String>,
+        clustering: Vec<String>,
+        si_no_existe: bool, // IF NOT EXISTS
+    },
+    CreateKeyspace {
+        keyspace: String,
+        replicacion: Vec<(Termino, Termino)>,
+        si_no_existe: bool,
+    },
+    CreateType {
+        tipo: String, // Puede estar calificado con su keyspace: keyspace.tipo
+        campos: Vec<DefinicionColumna>,
+        si_no_existe: bool,
+    },
+    CreateView {
+        vista: String,
+        tabla_base: String,
+        columnas: Vec<String>, // Vacías si se seleccionan todas las de la tabla base
+        clave_particion: Vec<String>,
+        clustering: Vec<String>,
+        si_no_existe: bool,
+    },
+    AlterTable {
+        tabla: String,
+        cambio: CambioTabla,
+    },
+    AlterKeyspace {
+        keyspace: String,
+        replicacion: Vec<(Termino, Termino)>,
+    },
+    Drop {
+        tipo: TypeCQL, // DROP TABLE, DROP KEYSPACE, DROP TYPE o DROP MATERIALIZED VIEW
+        nombre: String,
+        si_existe: bool, // IF EXISTS
+    },
+    Truncate {
+        tabla: String,
+    },
+    Use {
+        keyspace: String,
+    },
+    Describe {
+        descripcion: Descripcion,
+    },
+    Rol {
+        tipo: TypeCQL, // CREATE ROLE, ALTER ROLE o DROP ROLE
+        nombre: String,
+        si_corresponde: bool, // IF NOT EXISTS en el CREATE, IF EXISTS en el DROP
+        opciones: Vec<(String, Termino)>,
+    },
+    Permisos {
+        tipo: TypeCQL,   // GRANT, REVOKE o LIST PERMISSIONS
+        permiso: String, // ALL PERMISSIONS, SELECT, MODIFY PERMISSION, ...
+        recurso: Option<String>,
+        rol: Option<String>,
+    },
+}
+
+impl Sentencia {
+    pub fn tipo(&self) -> TypeCQL {
+        match self {
+            Sentencia::Select { .. } => TypeCQL::Select,
+            Sentencia::Insert { .. } => TypeCQL::Insert,
+            Sentencia::Update { .. } => TypeCQL::Update,
+            Sentencia::Delete { .. } => TypeCQL::Delete,
+            Sentencia::CreateTable { .. } => TypeCQL::CreateTable,
+            Sentencia::CreateKeyspace { .. } => TypeCQL::CreateKeyspace,
+            Sentencia::CreateType { .. } => TypeCQL::CreateType,
+            Sentencia::CreateView { .. } => TypeCQL::CreateView,
+            Sentencia::AlterTable { .. } => TypeCQL::AlterTable,
+            Sentencia::AlterKeyspace { .. } => TypeCQL::AlterKeyspace,
+            Sentencia::Truncate { .. } => TypeCQL::Truncate,
+            Sentencia::Use { .. } => TypeCQL::Use,
+            Sentencia::Describe { .. } => TypeCQL::Describe,
+            Sentencia::Drop { tipo, .. }
+            | Sentencia::Rol { tipo, .. }
+            | Sentencia::Permisos { tipo, .. } => tipo.clone(),
+        }
+    }
+
+    /// Tabla sobre la que opera la sentencia, tal como se escribió en la consulta,
+    /// o ninguna si opera sobre un keyspace, un rol o permisos. Las sentencias de
+    /// vistas materializadas operan sobre la vista
+    pub fn tabla(&self) -> Option<&str> {
+        match self {
+            Sentencia::Select { tabla, .. }
+            | Sentencia::Insert { tabla, .. }
+            | Sentencia::Update { tabla, .. }
+            | Sentencia::Delete { tabla, .. }
+            | Sentencia::CreateTable { tabla, .. }
+            | Sentencia::AlterTable { tabla, .. }
+            | Sentencia::CreateView { vista: tabla, .. }
+            | Sentencia::Truncate { tabla }
+            | Sentencia::Describe {
+                descripcion: Descripcion::Tabla(tabla),
+            } => Some(tabla),
+            Sentencia::Drop {
+                tipo: TypeCQL::DropTable | TypeCQL::DropView,
+                nombre,
+                ..
+            } => Some(nombre),
+            _ => None,
+        }
+    }
+
+    /// Tabla base de la vista materializada que crea la sentencia
+    pub fn tabla_base(&self) -> Option<&str> {
+        match self {
+            Sentencia::CreateView { tabla_base, .. } => Some(tabla_base),
+            _ => None,
+        }
+    }
+
+    /// UDT que crea o elimina la sentencia, tal como se escribió en la consulta
+    pub fn udt(&self) -> Option<&str> {
+        match self {
+            Sentencia::CreateType { tipo, .. }
+            | Sentencia::Drop {
+                tipo: TypeCQL::DropType,
+                nombre: tipo,
+                ..
+            } => Some(tipo),
+            _ => None,
+        }
+    }
+
+    /// Condiciones del WHERE de la sentencia, vacías si no tiene
+    pub fn condiciones(&self) -> &[Relacion] {
+        match self {
+            Sentencia::Select { condiciones, .. }
+            | Sentencia::Update { condiciones, .. }
+            | Sentencia::Delete { condiciones, .. } => condiciones,
+            _ => &[],
+        }
+    }
+
+    /// Asignaciones del UPDATE; las columnas y los elementos que borra un DELETE
+    /// se asignan a null
+    pub fn asignaciones(&self) -> &[Asignacion] {
+        match self {
+            Sentencia::Update { asignaciones, .. } => asignaciones,
+            Sentencia::Delete { columnas, .. } => columnas,
+            _ => &[],
+        }
+    }
+
+    /// Momento de una escritura indicado con USING TIMESTAMP
+    pub fn momento(&self) -> Option<i64> {
+        match self {
+            Sentencia::Insert { momento, .. }
+            | Sentencia::Update { momento, .. }
+            | Sentencia::Delete { momento, .. } => *momento,
+            _ => None,
+        }
+    }
+
+    /// Columnas, orden y límites de un SELECT
+    pub fn seleccion(&self) -> Option<&Seleccion> {
+        match self {
+            Sentencia::Select { seleccion, .. } => Some(seleccion),
+            _ => None,
+        }
+    }
+
+    /// Columnas de clustering de un CREATE TABLE o de un CREATE MATERIALIZED VIEW, vacías
+    /// en las demás sentencias
+    pub fn clustering(&self) -> &[String] {
+        match self {
+            Sentencia::CreateTable { clustering, .. }
+            | Sentencia::CreateView { clustering, .. } => clustering,
+            _ => &[],
+        }
+    }
+}
@@ -0,0 +1,103 @@
+/// Condición de una transacción liviana (lightweight transaction): la parte IF de
+/// un INSERT, UPDATE o DELETE, que el coordinador evalúa en una ronda de Paxos
+#[derive(Debug, PartialEq, Clone)]
+pub enum CondicionLwt {
+    NoExiste,                        // IF NOT EXISTS
+    Existe,                          // IF EXISTS
+    Columnas(Vec<(String, String)>), // IF columna = valor [AND columna = valor ...]
+}
+
+impl CondicionLwt {
+    /// Evalúa la condición sobre la fila actual de la clave, si la hay, con sus
+    /// valores en el orden de los headers de la tabla
+    pub fn se_cumple(&self, headers: &[String], fila: Option<&str>) -> bool {
+        match (self, fila) {
+            (CondicionLwt::NoExiste, fila) => fila.is_none(),
+            (CondicionLwt::Existe, fila) => fila.is_some(),
+            (CondicionLwt::Columnas(_), None) => false,
+            (CondicionLwt::Columnas(columnas), Some(fila)) => {
+                let valores = fila.split(",").collect::<Vec<&str>>();
+                columnas.iter().all(|(columna, esperado)| {
+                    headers
+                        .iter()
+                        .position(|header| header == columna)
+                        .and_then(|indice| valores.get(indice))
+                        .is_some_and(|valor| mismo_valor(valor, esperado))
+                })
+            }
+        }
+    }
+}
+
+// Los valores se comparan sin comillas y, si ambos son números, por su valor numérico
+fn mismo_valor(valor: &str, esperado: &str) -> bool {
+    let valor = valor.trim().trim_matches('\'');
+    let esperado = esperado.trim().trim_matches('\'');
+    match (valor.parse::<f64>(), esperado.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => valor == esperado,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser_cql::parseo_consulta::procesar_consulta;
+
+    #[test]
+    fn test_parsear_condicion_lwt() {
+        //Arrange
+        let insert =
+            "INSERT INTO VUELOS_ORIGEN (ORIGEN, ID_VUELO) VALUES ('AEP IF X', VUE1) IF NOT EXISTS";
+        let update = "UPDATE VUELOS_ORIGEN SET COMBUSTIBLE = 50 WHERE ORIGEN = AEP AND ID_VUELO = VUE1 IF ESTADO_VUELO = 'En curso' AND COMBUSTIBLE = 80";
+        let delete = "DELETE FROM VUELOS_ORIGEN WHERE ORIGEN = AEP";
+
+        //Act
+        let consulta_insert = procesar_consulta(insert).unwrap();
+        let consulta_update = procesar_consulta(update).unwrap();
+        let consulta_delete = procesar_consulta(delete).unwrap();
+
+        //Assert
+        assert_eq!(
+            consulta_insert.get_query(),
+            "(ORIGEN, ID_VUELO) VALUES ('AEP IF X', VUE1)"
+        );
+        assert_eq!(
+            consulta_insert.get_condicion_lwt(),
+            Some(&CondicionLwt::NoExiste)
+        );
+        assert_eq!(
+            consulta_update.get_condicion_lwt(),
+            Some(&CondicionLwt::Columnas(vec![
+                ("ESTADO_VUELO".to_string(), "'En curso'".to_string()),
+                ("COMBUSTIBLE".to_string(), "80".to_string()),
+            ]))
+        );
+        assert_eq!(
+            consulta_update.get_where().valor("ID_VUELO"),
+            Some("VUE1".to_string())
+        );
+        assert_eq!(consulta_delete.get_condicion_lwt(), None);
+    }
+
+    #[test]
+    fn test_condicion_lwt_se_cumple() {
+        //Arrange
+        let headers = vec![
+            "ORIGEN".to_string(),
+            "ID_VUELO".to_string(),
+            "COMBUSTIBLE".to_string(),
+        ];
+        let fila = Some("AEP,VUE1,80.0");
+        let condicion = CondicionLwt::Columnas(vec![("COMBUSTIBLE".to_string(), "80".to_string())]);
+
+        //Act
+        let cumple = condicion.se_cumple(&headers, fila);
+
+        //Assert
+        assert!(cumple);
+        assert!(!CondicionLwt::NoExiste.se_cumple(&headers, fila));
+        assert!(CondicionLwt::NoExiste.se_cumple(&headers, None));
+        assert!(!condicion.se_cumple(&headers, None));
+    }
+}
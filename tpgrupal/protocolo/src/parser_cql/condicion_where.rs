@@ -1,6 +1,84 @@
-#[derive(Debug, PartialEq, Clone)]
+use std::fmt;
+
+use super::ast::{Operador, Relacion, Termino};
+
+pub const CONSULTA_INVALIDA: &str = "Consulta inválida";
+
+/// Relaciones del WHERE de una sentencia, unidas por AND. Vacía si no tiene WHERE
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct CondicionWhere {
-    pub condicion1: String,
-    pub operador_logico: Option<String>,
-    pub condicion2: String,
+    pub relaciones: Vec<Relacion>,
+}
+
+impl CondicionWhere {
+    pub fn new(relaciones: Vec<Relacion>) -> Self {
+        CondicionWhere { relaciones }
+    }
+
+    /// Condición `columna = valor AND ...` con las columnas y valores indicados
+    pub fn igualdades(valores: &[(&str, &str)]) -> Self {
+        let relaciones = valores
+            .iter()
+            .map(|(columna, valor)| Relacion {
+                columna: columna.to_string(),
+                token: false,
+                operador: Operador::Igual,
+                valor: Termino::SinComillas(valor.to_string()),
+            })
+            .collect();
+        CondicionWhere { relaciones }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.relaciones.is_empty()
+    }
+
+    /// Valores a los que se restringe la columna con = o IN, tal como se escribieron,
+    /// o None si la condición no la restringe así
+    pub fn valores(&self, columna: &str) -> Option<Vec<String>> {
+        self.relaciones
+            .iter()
+            .filter(|relacion| relacion.columna == columna && !relacion.token)
+            .find_map(|relacion| match (&relacion.operador, &relacion.valor) {
+                (Operador::Igual, valor) => Some(vec![valor.to_string()]),
+                (Operador::In, Termino::Tupla(valores)) => {
+                    Some(valores.iter().map(|valor| valor.to_string()).collect())
+                }
+                _ => None,
+            })
+    }
+
+    /// Valor de la columna si la condición la restringe a uno solo con =
+    pub fn valor(&self, columna: &str) -> Option<String> {
+        self.relaciones
+            .iter()
+            .find(|relacion| {
+                relacion.columna == columna
+                    && !relacion.token
+                    && relacion.operador == Operador::Igual
+            })
+            .map(|relacion| relacion.valor.to_string())
+    }
+
+    /// La misma condición, con la columna restringida solo al valor indicado
+    pub fn con_valor(&self, columna: &str, valor: &str) -> Self {
+        let mut relaciones = self
+            .relaciones
+            .iter()
+            .filter(|relacion| relacion.columna != columna || relacion.token)
+            .cloned()
+            .collect::<Vec<Relacion>>();
+        relaciones.insert(
+            0,
+            CondicionWhere::igualdades(&[(columna, valor)]).relaciones[0].clone(),
+        );
+        CondicionWhere { relaciones }
+    }
+}
+
+impl fmt::Display for CondicionWhere {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let relaciones = self.relaciones.iter().map(|relacion| relacion.to_string());
+        write!(f, "{}", relaciones.collect::<Vec<String>>().join(" AND "))
+    }
 }
@@ -1,19 +1,73 @@
-use super::{condicion_where::CondicionWhere, type_cql::TypeCQL};
+use crate::serial_deserial::cassandra::paginacion::Pagina;
 
+use super::{
+    ast::{Descripcion, Expresion, Orden, Seleccion, Selector, Sentencia},
+    condicion_lwt::CondicionLwt,
+    condicion_where::CondicionWhere,
+    lexer::{tokenizar, TipoToken, Token},
+    parser::parsear,
+    type_cql::TypeCQL,
+};
+
+// Palabras después de las cuales se escribe la tabla, la vista o el UDT de una sentencia
+const PREVIAS_TABLA: [&str; 8] = [
+    "FROM", "INTO", "UPDATE", "TABLE", "TRUNCATE", "EXISTS", "TYPE", "VIEW",
+];
+
+/// Consulta de un cliente o de otro nodo. La sentencia parseada es `sentencia`; los
+/// campos tabla, tipo, query, condicion_where y condicion_lwt se derivan de ella con
+/// el formato de texto que usan los módulos que todavía no trabajan sobre el AST
 #[derive(Debug, Clone)]
 pub struct Consulta {
     pub consulta_explicita: String,
+    pub sentencia: Sentencia,
     pub tabla: String,
     pub tipo: TypeCQL,
     pub query: String,
     pub condicion_where: CondicionWhere,
+    pub rol: Option<String>, // Rol de la sesión que envió la consulta, si hay autenticación
+    pub keyspace: Option<String>, // Keyspace elegido con USE en la sesión que envió la consulta
+    pub condicion_lwt: Option<CondicionLwt>, // Condición IF de las transacciones livianas
+    pub pagina: Option<Pagina>, // Página pedida por el cliente, si pagina el resultado
 }
 
 impl Consulta {
+    /// Arma la consulta a partir de su sentencia parseada
+    pub fn desde_sentencia(consulta_cql: &str, sentencia: Sentencia) -> Self {
+        let condicion_where = CondicionWhere::new(sentencia.condiciones().to_vec());
+        let condicion_lwt = match &sentencia {
+            Sentencia::Insert { condicion_lwt, .. }
+            | Sentencia::Update { condicion_lwt, .. }
+            | Sentencia::Delete { condicion_lwt, .. } => condicion_lwt.clone(),
+            _ => None,
+        };
+        Consulta {
+            consulta_explicita: consulta_cql.to_string(),
+            tabla: tabla_sentencia(&sentencia),
+            tipo: sentencia.tipo(),
+            query: query_sentencia(&sentencia),
+            condicion_where,
+            rol: None,
+            keyspace: None,
+            condicion_lwt,
+            pagina: None,
+            sentencia,
+        }
+    }
+
     pub fn get_consulta_explicita(&self) -> &str {
         &self.consulta_explicita
     }
 
+    pub fn get_sentencia(&self) -> &Sentencia {
+        &self.sentencia
+    }
+
+    /// Columnas, orden y límites del SELECT; en las demás sentencias, las filas completas
+    pub fn get_seleccion(&self) -> Seleccion {
+        self.sentencia.seleccion().cloned().unwrap_or_default()
+    }
+
     pub fn get_tabla(&self) -> &str {
         &self.tabla
     }
@@ -29,4 +83,393 @@ impl Consulta {
     pub fn get_where(&self) -> &CondicionWhere {
         &self.condicion_where
     }
+
+    pub fn get_rol(&self) -> Option<&str> {
+        self.rol.as_deref()
+    }
+
+    pub fn get_keyspace(&self) -> Option<&str> {
+        self.keyspace.as_deref()
+    }
+
+    pub fn get_condicion_lwt(&self) -> Option<&CondicionLwt> {
+        self.condicion_lwt.as_ref()
+    }
+
+    pub fn get_pagina(&self) -> Option<&Pagina> {
+        self.pagina.as_ref()
+    }
+
+    /// Momento de la escritura indicado con USING TIMESTAMP
+    pub fn get_momento(&self) -> Option<i64> {
+        self.sentencia.momento()
+    }
+
+    /// Sentencia de escritura sin su condición IF, que es la que se propone en
+    /// una ronda de Paxos
+    pub fn sentencia_sin_condicion(&self) -> String {
+        let condiciones = unir(self.sentencia.condiciones(), " AND ");
+        let condicion_where = match condiciones.is_empty() {
+            true => String::new(),
+            false => format!(" WHERE {}", condiciones),
+        };
+        let momento = match self.get_momento() {
+            Some(momento) => format!(" USING TIMESTAMP {}", momento),
+            None => String::new(),
+        };
+        match self.tipo {
+            TypeCQL::Insert => format!("INSERT INTO {} {}{}", self.tabla, self.query, momento),
+            TypeCQL::Update => format!(
+                "UPDATE {}{} SET {}{}",
+                self.tabla, momento, self.query, condicion_where
+            ),
+            TypeCQL::Delete => format!(
+                "DELETE {}FROM {}{}{}",
+                columnas_delete(&self.sentencia),
+                self.tabla,
+                momento,
+                condicion_where
+            ),
+            _ => self.consulta_explicita.to_string(),
+        }
+    }
+
+    /// El SELECT restringido a una sola partición: la partition key igual al valor
+    /// indicado en lugar del IN con el que se leen varias particiones
+    pub fn en_particion(&self, clave_particion: &str, valor: &str) -> Consulta {
+        let condicion_where = self.condicion_where.con_valor(clave_particion, valor);
+        let mut sentencia = self.sentencia.clone();
+        if let Sentencia::Select { condiciones, .. } = &mut sentencia {
+            *condiciones = condicion_where.relaciones.clone();
+        }
+        Consulta {
+            consulta_explicita: texto_select(&sentencia),
+            sentencia,
+            condicion_where,
+            ..self.clone()
+        }
+    }
+
+    /// El SELECT con las columnas indicadas agregadas al final de las seleccionadas
+    pub fn con_columnas(&self, columnas: &[String]) -> Consulta {
+        let mut sentencia = self.sentencia.clone();
+        if let Sentencia::Select { seleccion, .. } = &mut sentencia {
+            seleccion
+                .selectores
+                .extend(columnas.iter().map(|columna| Selector {
+                    expresion: Expresion::Columna(columna.to_string()),
+                    alias: None,
+                }));
+        }
+        Consulta {
+            consulta_explicita: texto_select(&sentencia),
+            sentencia,
+            ..self.clone()
+        }
+    }
+
+    /// Asocia la consulta al rol de la sesión, para que se autorice con sus permisos
+    pub fn con_rol(mut self, rol: Option<String>) -> Self {
+        self.rol = rol;
+        self
+    }
+
+    /// Indica la página del resultado que pidió el cliente
+    pub fn con_pagina(mut self, pagina: Option<Pagina>) -> Self {
+        self.pagina = pagina;
+        self
+    }
+
+    /// Califica la tabla, o el UDT, de la consulta con el keyspace elegido con USE en la
+    /// sesión, si no lo indica ya, para que los demás nodos la ejecuten sobre el mismo
+    /// keyspace. En un CREATE MATERIALIZED VIEW califica también la tabla base. La
+    /// consulta guarda el keyspace para resolver los demás nombres sin calificar, como
+    /// el recurso de un GRANT
+    pub fn con_keyspace(self, keyspace: Option<&str>) -> Result<Self, String> {
+        let Some(keyspace) = keyspace else {
+            return Ok(self);
+        };
+        let nombres = [
+            self.sentencia.tabla().or(self.sentencia.udt()),
+            self.sentencia.tabla_base(),
+        ];
+        let mut consulta_cql = self.consulta_explicita.to_string();
+        for nombre in nombres.into_iter().flatten() {
+            if nombre.contains('.') {
+                continue;
+            }
+            let tokens = tokenizar(&consulta_cql)?;
+            if let Some(inicio) = posicion_tabla(&tokens, nombre) {
+                consulta_cql.insert_str(inicio, &format!("{}.", keyspace));
+            }
+        }
+        let mut consulta = match consulta_cql == self.consulta_explicita {
+            true => self,
+            false => {
+                Consulta::desde_sentencia(&consulta_cql, parsear(&consulta_cql)?).con_rol(self.rol)
+            }
+        };
+        consulta.keyspace = Some(keyspace.to_string());
+        Ok(consulta)
+    }
+
+    /// Agrega USING TIMESTAMP con el momento indicado a una escritura que no lo tiene,
+    /// para que todas las réplicas la ordenen igual respecto de las demás
+    pub fn con_momento(self, momento: i64) -> Result<Self, String> {
+        if !matches!(
+            self.tipo,
+            TypeCQL::Insert | TypeCQL::Update | TypeCQL::Delete
+        ) || self.get_momento().is_some()
+        {
+            return Ok(self);
+        }
+        let mut tokens = tokenizar(&self.consulta_explicita)?;
+        if tokens.last().is_some_and(|token| token.es_simbolo(";")) {
+            tokens.pop();
+        }
+        let using = format!("USING TIMESTAMP {}", momento);
+        let mut consulta_cql = self.consulta_explicita.to_string();
+        match posicion_momento(&tokens, &self.tipo) {
+            Some(inicio) => consulta_cql.insert_str(inicio, &format!("{} ", using)),
+            None => {
+                let fin = tokens.last().map(|token| token.fin).unwrap_or_default();
+                consulta_cql.insert_str(fin, &format!(" {}", using));
+            }
+        }
+        let sentencia = parsear(&consulta_cql)?;
+        Ok(Consulta {
+            keyspace: self.keyspace,
+            ..Consulta::desde_sentencia(&consulta_cql, sentencia).con_rol(self.rol)
+        })
+    }
+}
+
+// Columnas y elementos que borra un DELETE, seguidos de un espacio si hay alguno
+fn columnas_delete(sentencia: &Sentencia) -> String {
+    let columnas = sentencia
+        .asignaciones()
+        .iter()
+        .map(|asignacion| asignacion.destino())
+        .collect::<Vec<String>>();
+    match columnas.is_empty() {
+        true => String::new(),
+        false => format!("{} ", columnas.join(", ")),
+    }
+}
+
+// Byte en el que va USING TIMESTAMP: antes del SET de un UPDATE y antes del WHERE o
+// del IF de un DELETE. Ninguno si va al final, como en el INSERT
+fn posicion_momento(tokens: &[Token], tipo: &TypeCQL) -> Option<usize> {
+    let siguientes: &[&str] = match tipo {
+        TypeCQL::Update => &["SET"],
+        TypeCQL::Delete => &["WHERE", "IF"],
+        _ => &[],
+    };
+    // Después de la tabla, que sigue al UPDATE o al FROM
+    let desde = match tipo {
+        TypeCQL::Delete => tokens.iter().position(|token| token.es_palabra("FROM"))? + 2,
+        _ => 2,
+    };
+    tokens.iter().skip(desde).find_map(|token| {
+        siguientes
+            .iter()
+            .any(|palabra| token.es_palabra(palabra))
+            .then_some(token.inicio)
+    })
+}
+
+// Byte en el que empieza el nombre de la tabla: el primer nombre igual al de la tabla
+// que sigue a la palabra que la introduce y no está calificado con un keyspace
+fn posicion_tabla(tokens: &[Token], tabla: &str) -> Option<usize> {
+    (1..tokens.len()).find_map(|indice| {
+        let nombra_tabla = matches!(
+            &tokens[indice].tipo,
+            TipoToken::Identificador(nombre) | TipoToken::IdentificadorEntreComillas(nombre)
+                if nombre == tabla
+        );
+        let introduce_tabla = PREVIAS_TABLA
+            .iter()
+            .any(|palabra| tokens[indice - 1].es_palabra(palabra));
+        let calificada = tokens
+            .get(indice + 1)
+            .is_some_and(|token| token.es_simbolo("."));
+        (nombra_tabla && introduce_tabla && !calificada).then_some(tokens[indice].inicio)
+    })
+}
+
+// La tabla de la sentencia; en las de keyspaces el keyspace, en las de UDT el tipo y
+// en las de roles y permisos el rol (vacío si un LIST no lo indica)
+fn tabla_sentencia(sentencia: &Sentencia) -> String {
+    if let Some(tabla) = sentencia.tabla() {
+        return tabla.to_string();
+    }
+    match sentencia {
+        Sentencia::CreateKeyspace { keyspace, .. }
+        | Sentencia::AlterKeyspace { keyspace, .. }
+        | Sentencia::Use { keyspace }
+        | Sentencia::Describe {
+            descripcion: Descripcion::Keyspace(keyspace),
+        } => keyspace.to_string(),
+        Sentencia::CreateType { tipo, .. } => tipo.to_string(),
+        Sentencia::Drop { nombre, .. } | Sentencia::Rol { nombre, .. } => nombre.to_string(),
+        Sentencia::Permisos { rol, .. } => rol.clone().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+// El resto de la sentencia como texto: las columnas del SELECT, "(columnas) VALUES
+// (valores)" del INSERT, las asignaciones del UPDATE, la definición de las columnas
+// del CREATE TABLE, los campos del CREATE TYPE, la definición de la vista del CREATE
+// MATERIALIZED VIEW, el cambio de un ALTER TABLE, la replicación del CREATE y el ALTER
+// KEYSPACE, las opciones de un rol y "permiso [ON recurso]" de las sentencias de
+// permisos. Vacío en las sentencias que solo indican su tabla o su keyspace
+fn query_sentencia(sentencia: &Sentencia) -> String {
+    match sentencia {
+        Sentencia::Select { seleccion, .. } => seleccion.to_string(),
+        Sentencia::Insert {
+            columnas, valores, ..
+        } => format!("({}) VALUES ({})", columnas.join(", "), unir(valores, ", ")),
+        Sentencia::Update { asignaciones, .. } => unir(asignaciones, ", "),
+        Sentencia::Delete { .. }
+        | Sentencia::Drop { .. }
+        | Sentencia::Truncate { .. }
+        | Sentencia::Use { .. }
+        | Sentencia::Describe { .. } => String::new(),
+        Sentencia::CreateTable { .. } => definicion_tabla(sentencia),
+        Sentencia::CreateType { campos, .. } => format!("({})", unir(campos, ", ")),
+        Sentencia::CreateView { .. } => definicion_vista(sentencia),
+        Sentencia::AlterTable { cambio, .. } => cambio.to_string(),
+        Sentencia::CreateKeyspace { replicacion, .. }
+        | Sentencia::AlterKeyspace { replicacion, .. } => {
+            format!("{{{}}}", unir_pares(replicacion, ": ", ", "))
+        }
+        Sentencia::Rol { opciones, .. } => unir_pares(opciones, " = ", " AND "),
+        Sentencia::Permisos {
+            permiso, recurso, ..
+        } => recurso.as_ref().map_or(permiso.to_string(), |recurso| {
+            format!("{} ON {}", permiso, recurso)
+        }),
+    }
+}
+
+// SELECT columnas FROM tabla [WHERE ...] [GROUP BY ...] [ORDER BY ...]
+// [PER PARTITION LIMIT n] [LIMIT n] [ALLOW FILTERING]
+fn texto_select(sentencia: &Sentencia) -> String {
+    let Sentencia::Select {
+        tabla,
+        seleccion,
+        condiciones,
+        permitir_filtrado,
+    } = sentencia
+    else {
+        return String::new();
+    };
+    let mut texto = format!("SELECT {} FROM {}", seleccion, tabla);
+    if !condiciones.is_empty() {
+        texto.push_str(&format!(" WHERE {}", unir(condiciones, " AND ")));
+    }
+    texto.push_str(&clausulas_select(seleccion));
+    if *permitir_filtrado {
+        texto.push_str(" ALLOW FILTERING");
+    }
+    texto
+}
+
+fn clausulas_select(seleccion: &Seleccion) -> String {
+    let mut texto = String::new();
+    if !seleccion.agrupamiento.is_empty() {
+        texto.push_str(&format!(" GROUP BY {}", seleccion.agrupamiento.join(", ")));
+    }
+    if !seleccion.orden.is_empty() {
+        let orden = seleccion.orden.iter().map(|(columna, orden)| match orden {
+            Orden::Ascendente => format!("{} ASC", columna),
+            Orden::Descendente => format!("{} DESC", columna),
+        });
+        texto.push_str(&format!(
+            " ORDER BY {}",
+            orden.collect::<Vec<String>>().join(", ")
+        ));
+    }
+    if let Some(limite) = seleccion.limite_por_particion {
+        texto.push_str(&format!(" PER PARTITION LIMIT {}", limite));
+    }
+    if let Some(limite) = seleccion.limite {
+        texto.push_str(&format!(" LIMIT {}", limite));
+    }
+    texto
+}
+
+// (columna tipo, ..., PRIMARY KEY ((pk, ...), ck, ...))
+fn definicion_tabla(sentencia: &Sentencia) -> String {
+    let Sentencia::CreateTable {
+        columnas,
+        clave_particion,
+        clustering,
+        ..
+    } = sentencia
+    else {
+        return String::new();
+    };
+    let mut definiciones = columnas.iter().map(ToString::to_string).collect::<Vec<_>>();
+    definiciones.push(format!(
+        "PRIMARY KEY {}",
+        clave_primaria(clave_particion, clustering)
+    ));
+    format!("({})", definiciones.join(", "))
+}
+
+// AS SELECT columnas FROM tabla WHERE columna IS NOT NULL AND ... PRIMARY KEY (...)
+fn definicion_vista(sentencia: &Sentencia) -> String {
+    let Sentencia::CreateView {
+        tabla_base,
+        columnas,
+        clave_particion,
+        clustering,
+        ..
+    } = sentencia
+    else {
+        return String::new();
+    };
+    let columnas = match columnas.is_empty() {
+        true => "*".to_string(),
+        false => columnas.join(", "),
+    };
+    let no_nulas = clave_particion
+        .iter()
+        .chain(clustering)
+        .map(|columna| format!("{} IS NOT NULL", columna));
+    format!(
+        "AS SELECT {} FROM {} WHERE {} PRIMARY KEY {}",
+        columnas,
+        tabla_base,
+        no_nulas.collect::<Vec<String>>().join(" AND "),
+        clave_primaria(clave_particion, clustering)
+    )
+}
+
+// ((pk, ...), ck, ...)
+fn clave_primaria(clave_particion: &[String], clustering: &[String]) -> String {
+    let clave_particion = format!("({})", clave_particion.join(", "));
+    let clave_primaria = [vec![clave_particion], clustering.to_vec()].concat();
+    format!("({})", clave_primaria.join(", "))
+}
+
+fn unir<T: ToString>(elementos: &[T], separador: &str) -> String {
+    elementos
+        .iter()
+        .map(|elemento| elemento.to_string())
+        .collect::<Vec<String>>()
+        .join(separador)
+}
+
+fn unir_pares<K: ToString, V: ToString>(
+    pares: &[(K, V)],
+    asignacion: &str,
+    separador: &str,
+) -> String {
+    pares
+        .iter()
+        .map(|(clave, valor)| format!("{}{}{}", clave.to_string(), asignacion, valor.to_string()))
+        .collect::<Vec<String>>()
+        .join(separador)
 }
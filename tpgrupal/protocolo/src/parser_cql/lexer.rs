@@ -0,0 +1,295 @@
+use std::fmt;
+
+pub const ERROR_SYNTAX: i32 = 0x2000;
+pub const ERROR_SINTAXIS: &str = "Error de sintaxis";
+
+/// Posición de un token en la consulta, contando líneas y columnas desde 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Posicion {
+    pub linea: usize,
+    pub columna: usize,
+}
+
+impl fmt::Display for Posicion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "línea {}, columna {}", self.linea, self.columna)
+    }
+}
+
+/// Error de sintaxis en una posición de la consulta
+pub fn error_sintaxis(posicion: Posicion, mensaje: &str) -> String {
+    format!("{} en la {}: {}", ERROR_SINTAXIS, posicion, mensaje)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoToken {
+    Identificador(String),
+    IdentificadorEntreComillas(String), // "Nombre", que conserva mayúsculas y espacios
+    Texto(String),                      // 'valor', sin las comillas y con '' como '
+    Numero(String),
+    Simbolo(String), // Operadores y puntuación: = < > <= >= != + - * / ( ) , . ; { } [ ] :
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub tipo: TipoToken,
+    pub posicion: Posicion,
+    pub inicio: usize, // Byte de la consulta en el que empieza el token
+    pub fin: usize,    // Byte de la consulta en el que termina el token
+}
+
+impl Token {
+    /// Indica si el token es la palabra clave, sin distinguir mayúsculas
+    pub fn es_palabra(&self, palabra: &str) -> bool {
+        matches!(&self.tipo, TipoToken::Identificador(nombre) if nombre.eq_ignore_ascii_case(palabra))
+    }
+
+    pub fn es_simbolo(&self, simbolo: &str) -> bool {
+        matches!(&self.tipo, TipoToken::Simbolo(s) if s == simbolo)
+    }
+
+    /// Descripción del token para los mensajes de error
+    pub fn descripcion(&self) -> String {
+        match &self.tipo {
+            TipoToken::Identificador(nombre) | TipoToken::Numero(nombre) => nombre.to_string(),
+            TipoToken::IdentificadorEntreComillas(nombre) => format!("\"{}\"", nombre),
+            TipoToken::Texto(texto) => format!("'{}'", texto),
+            TipoToken::Simbolo(simbolo) => simbolo.to_string(),
+        }
+    }
+}
+
+const SIMBOLOS_DOBLES: [&str; 3] = ["<=", ">=", "!="];
+
+/// Separa la consulta en tokens, ignorando los espacios y los comentarios
+/// (`-- ...`, `// ...` y `/* ... */`)
+pub fn tokenizar(consulta_cql: &str) -> Result<Vec<Token>, String> {
+    let caracteres = consulta_cql.char_indices().collect::<Vec<(usize, char)>>();
+    let mut tokens = vec![];
+    let mut lector = Lector {
+        caracteres: &caracteres,
+        actual: 0,
+        posicion: Posicion {
+            linea: 1,
+            columna: 1,
+        },
+        largo: consulta_cql.len(),
+    };
+
+    loop {
+        lector.saltar_espacios_y_comentarios()?;
+        let (posicion, inicio) = (lector.posicion, lector.byte());
+        let Some(tipo) = lector.leer_token()? else {
+            break;
+        };
+        tokens.push(Token {
+            tipo,
+            posicion,
+            inicio,
+            fin: lector.byte(),
+        });
+    }
+    Ok(tokens)
+}
+
+// Recorre los caracteres de la consulta llevando la línea y la columna
+struct Lector<'a> {
+    caracteres: &'a [(usize, char)],
+    actual: usize,
+    posicion: Posicion,
+    largo: usize,
+}
+
+impl Lector<'_> {
+    fn ver(&self, adelanto: usize) -> Option<char> {
+        self.caracteres
+            .get(self.actual + adelanto)
+            .map(|(_, caracter)| *caracter)
+    }
+
+    fn byte(&self) -> usize {
+        self.caracteres
+            .get(self.actual)
+            .map(|(byte, _)| *byte)
+            .unwrap_or(self.largo)
+    }
+
+    fn avanzar(&mut self) -> Option<char> {
+        let caracter = self.ver(0)?;
+        self.actual += 1;
+        if caracter == '\n' {
+            self.posicion.linea += 1;
+            self.posicion.columna = 1;
+        } else {
+            self.posicion.columna += 1;
+        }
+        Some(caracter)
+    }
+
+    fn saltar_espacios_y_comentarios(&mut self) -> Result<(), String> {
+        loop {
+            match (self.ver(0), self.ver(1)) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.avanzar();
+                }
+                (Some('-'), Some('-')) | (Some('/'), Some('/')) => self.saltar_linea(),
+                (Some('/'), Some('*')) => self.saltar_comentario(self.posicion)?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    // Lee el token que empieza en el caracter actual, si quedan caracteres
+    fn leer_token(&mut self) -> Result<Option<TipoToken>, String> {
+        let posicion = self.posicion;
+        let tipo = match self.ver(0) {
+            None => return Ok(None),
+            Some('\'') => TipoToken::Texto(self.leer_entre_comillas('\'', posicion)?),
+            Some('"') => {
+                TipoToken::IdentificadorEntreComillas(self.leer_entre_comillas('"', posicion)?)
+            }
+            Some(c) if c.is_ascii_digit() => TipoToken::Numero(self.leer_numero()),
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                TipoToken::Identificador(self.leer_palabra())
+            }
+            Some(c) => TipoToken::Simbolo(self.leer_simbolo(c)),
+        };
+        Ok(Some(tipo))
+    }
+
+    fn leer_simbolo(&mut self, caracter: char) -> String {
+        self.avanzar();
+        let doble = format!("{}{}", caracter, self.ver(0).unwrap_or(' '));
+        if SIMBOLOS_DOBLES.contains(&doble.as_str()) {
+            self.avanzar();
+            return doble;
+        }
+        caracter.to_string()
+    }
+
+    fn saltar_linea(&mut self) {
+        while let Some(caracter) = self.avanzar() {
+            if caracter == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn saltar_comentario(&mut self, inicio: Posicion) -> Result<(), String> {
+        self.avanzar();
+        self.avanzar();
+        loop {
+            match self.avanzar() {
+                Some('*') if self.ver(0) == Some('/') => {
+                    self.avanzar();
+                    return Ok(());
+                }
+                Some(_) => continue,
+                None => return Err(error_sintaxis(inicio, "comentario sin cerrar")),
+            }
+        }
+    }
+
+    // Lee un texto o un identificador entre comillas, donde la comilla se escapa
+    // escribiéndola dos veces
+    fn leer_entre_comillas(&mut self, comilla: char, inicio: Posicion) -> Result<String, String> {
+        self.avanzar();
+        let mut contenido = String::new();
+        loop {
+            match self.avanzar() {
+                Some(c) if c == comilla && self.ver(0) == Some(comilla) => {
+                    self.avanzar();
+                    contenido.push(comilla);
+                }
+                Some(c) if c == comilla => return Ok(contenido),
+                Some(c) => contenido.push(c),
+                None => {
+                    let mensaje = match comilla {
+                        '\'' => "texto sin cerrar",
+                        _ => "identificador sin cerrar",
+                    };
+                    return Err(error_sintaxis(inicio, mensaje));
+                }
+            }
+        }
+    }
+
+    // Dígitos con una parte decimal y un exponente opcionales
+    fn leer_numero(&mut self) -> String {
+        let mut numero = self.leer_mientras(|c| c.is_ascii_digit());
+        if self.ver(0) == Some('.') && self.ver(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.avanzar();
+            numero.push('.');
+            numero.push_str(&self.leer_mientras(|c| c.is_ascii_digit()));
+        }
+        let signo = matches!(self.ver(1), Some('+') | Some('-'));
+        let digito_exponente = self.ver(if signo { 2 } else { 1 });
+        if matches!(self.ver(0), Some('e') | Some('E'))
+            && digito_exponente.is_some_and(|c| c.is_ascii_digit())
+        {
+            numero.push(self.avanzar().unwrap_or('e'));
+            if signo {
+                numero.push(self.avanzar().unwrap_or('+'));
+            }
+            numero.push_str(&self.leer_mientras(|c| c.is_ascii_digit()));
+        }
+        numero
+    }
+
+    fn leer_palabra(&mut self) -> String {
+        self.leer_mientras(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    fn leer_mientras(&mut self, condicion: impl Fn(char) -> bool) -> String {
+        let mut leido = String::new();
+        while let Some(caracter) = self.ver(0).filter(|c| condicion(*c)) {
+            self.avanzar();
+            leido.push(caracter);
+        }
+        leido
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizar() {
+        //Arrange
+        let consulta = "SELECT * FROM \"Vuelos\" -- comentario\nWHERE NOMBRE = 'O''Hare AND co' /* otro */ AND ALTITUD >= -1.5e3;";
+
+        //Act
+        let tipos = tokenizar(consulta)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.tipo)
+            .collect::<Vec<TipoToken>>();
+
+        //Assert
+        assert_eq!(
+            tipos,
+            vec![
+                TipoToken::Identificador("SELECT".to_string()),
+                TipoToken::Simbolo("*".to_string()),
+                TipoToken::Identificador("FROM".to_string()),
+                TipoToken::IdentificadorEntreComillas("Vuelos".to_string()),
+                TipoToken::Identificador("WHERE".to_string()),
+                TipoToken::Identificador("NOMBRE".to_string()),
+                TipoToken::Simbolo("=".to_string()),
+                TipoToken::Texto("O'Hare AND co".to_string()),
+                TipoToken::Identificador("AND".to_string()),
+                TipoToken::Identificador("ALTITUD".to_string()),
+                TipoToken::Simbolo(">=".to_string()),
+                TipoToken::Simbolo("-".to_string()),
+                TipoToken::Numero("1.5e3".to_string()),
+                TipoToken::Simbolo(";".to_string()),
+            ]
+        );
+        let error = tokenizar("SELECT *\nFROM t WHERE a = 'sin cerrar").unwrap_err();
+        assert_eq!(
+            error,
+            "Error de sintaxis en la línea 2, columna 18: texto sin cerrar"
+        );
+    }
+}
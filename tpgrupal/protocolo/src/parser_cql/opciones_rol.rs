@@ -0,0 +1,72 @@
+use super::consulta::Consulta;
+use crate::serial_deserial::tipo_columna::quitar_comillas;
+
+/// Opciones de una sentencia CREATE ROLE / ALTER ROLE / DROP ROLE. Las
+/// que no se indicaron en la sentencia quedan en None
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OpcionesRol {
+    pub password: Option<String>,
+    pub login: Option<bool>,
+    pub superuser: Option<bool>,
+    pub si_corresponde: bool, // IF NOT EXISTS en el CREATE, IF EXISTS en el DROP
+}
+
+impl OpcionesRol {
+    /// Obtiene las opciones de la consulta, con el formato
+    /// "PASSWORD = 'valor' AND LOGIN = true AND SUPERUSER = false"
+    pub fn desde_consulta(consulta: &Consulta) -> Result<Self, String> {
+        let sentencia = consulta.get_consulta_explicita().split(" WITH ").next();
+        let mut opciones = OpcionesRol {
+            si_corresponde: sentencia.is_some_and(|sentencia| sentencia.contains(" IF ")),
+            ..Default::default()
+        };
+        for opcion in consulta.get_query().split(" AND ") {
+            if opcion.trim().is_empty() {
+                continue;
+            }
+            let (clave, valor) = opcion
+                .split_once('=')
+                .ok_or(format!("Opción de rol mal formada: {}", opcion))?;
+            let valor = quitar_comillas(valor.trim());
+            match clave.trim().to_uppercase().as_str() {
+                "PASSWORD" => opciones.password = Some(valor.to_string()),
+                "LOGIN" => opciones.login = Some(parsear_booleano(valor)?),
+                "SUPERUSER" => opciones.superuser = Some(parsear_booleano(valor)?),
+                _ => return Err(format!("Opción de rol no soportada: {}", clave.trim())),
+            }
+        }
+        Ok(opciones)
+    }
+}
+
+fn parsear_booleano(valor: &str) -> Result<bool, String> {
+    valor
+        .to_lowercase()
+        .parse::<bool>()
+        .map_err(|_| format!("Se esperaba true o false: {}", valor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser_cql::parseo_consulta::procesar_consulta;
+    use crate::parser_cql::type_cql::TypeCQL;
+
+    #[test]
+    fn test_opciones_create_role() {
+        //Arrange
+        let query = "CREATE ROLE IF NOT EXISTS operador WITH PASSWORD = 'secreta' AND LOGIN = true";
+
+        //Act
+        let consulta = procesar_consulta(query).unwrap();
+        let opciones = OpcionesRol::desde_consulta(&consulta).unwrap();
+
+        //Assert
+        assert_eq!(consulta.get_type(), &TypeCQL::CreateRole);
+        assert_eq!(consulta.get_tabla(), "operador");
+        assert_eq!(opciones.password, Some("secreta".to_string()));
+        assert_eq!(opciones.login, Some(true));
+        assert_eq!(opciones.superuser, None);
+        assert!(opciones.si_corresponde);
+    }
+}
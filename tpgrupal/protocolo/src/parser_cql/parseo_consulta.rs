@@ -1,164 +1,13 @@
-use crate::parser_cql::condicion_where::CondicionWhere;
+use crate::parser_cql::ast::Termino;
 use crate::parser_cql::consulta::Consulta;
-use crate::parser_cql::type_cql::TypeCQL;
-
-fn parsear_insert(consulta_cql: &str) -> Consulta {
-    let partes_insert: Vec<&str> = consulta_cql.split_whitespace().collect();
-
-    let tabla = partes_insert[2].to_string();
-
-    let columna_comienzo = consulta_cql.find('(').unwrap_or(0);
-    let columna_fin = consulta_cql.find(')').unwrap_or(0);
-    let columnas_str = &consulta_cql[columna_comienzo..columna_fin + 1];
-
-    let valores_comienzo = consulta_cql.find("VALUES").unwrap_or(0) + "VALUES".len();
-    let valores_str = consulta_cql[valores_comienzo..].trim();
-
-    let query = [columnas_str, "VALUES", valores_str];
-    let q = query.join(" ");
-
-    Consulta {
-        consulta_explicita: consulta_cql.to_string(),
-        tabla,
-        tipo: TypeCQL::Insert,
-        query: q,
-        condicion_where: CondicionWhere {
-            condicion1: String::new(),
-            operador_logico: None,
-            condicion2: String::new(),
-        },
-    }
-}
-
-fn parsear_select(consulta_cql: &str) -> Consulta {
-    let partes_select: Vec<&str> = consulta_cql.split_whitespace().collect();
-    let from_index = partes_select.iter().position(|&x| x == "FROM").unwrap_or(0);
-    let tabla = partes_select[from_index + 1].to_string();
-
-    let columnas: String = partes_select[1..from_index].join(" ");
-
-    let mut cond1 = String::new();
-    let mut oper: Option<String> = None;
-    let mut cond2 = String::new();
-
-    if consulta_cql.contains("WHERE") {
-        let x: Vec<&str> = consulta_cql.split("WHERE").collect();
-        let y: Vec<&str> = x[1].split("AND").collect();
-        cond1 = y[0].trim().to_string();
-        if y.len() > 1 {
-            cond2 = y[1].trim().to_string();
-            oper = Some("AND".to_string());
-        }
-    };
-    let condicion_where = CondicionWhere {
-        condicion1: cond1,
-        operador_logico: oper,
-        condicion2: cond2,
-    };
-
-    Consulta {
-        consulta_explicita: consulta_cql.to_string(),
-        tabla,
-        tipo: TypeCQL::Select,
-        query: columnas,
-        condicion_where,
-    }
-}
-
-fn parsear_update(consulta_cql: &str) -> Consulta {
-    let cql_primer_mitad: Vec<&str> = consulta_cql.split("WHERE").collect();
-    let espaciado: Vec<&str> = cql_primer_mitad[0].split_whitespace().collect();
-
-    let tabla = espaciado[1].to_string();
-
-    let query = espaciado[3..].join(" ");
-
-    let mut cond1 = String::new();
-    let mut oper: Option<String> = None;
-    let mut cond2 = String::new();
-
-    if cql_primer_mitad.len() > 1 {
-        let x: Vec<&str> = consulta_cql.split("WHERE").collect();
-        let y: Vec<&str> = x[1].split("AND").collect();
-        cond1 = y[0].trim().to_string();
-        if y.len() > 1 {
-            cond2 = y[1].trim().to_string();
-            oper = Some("AND".to_string());
-        }
-    };
-    let condicion_where = CondicionWhere {
-        condicion1: cond1,
-        operador_logico: oper,
-        condicion2: cond2,
-    };
-
-    Consulta {
-        consulta_explicita: consulta_cql.to_string(),
-        tabla,
-        tipo: TypeCQL::Update,
-        query,
-        condicion_where,
-    }
-}
-
-fn parsear_delete(consulta_cql: &str) -> Consulta {
-    let partes_delete: Vec<&str> = consulta_cql.split_whitespace().collect();
-    let tabla = partes_delete[2].to_string();
-
-    let mut cond1 = String::new();
-    let mut oper: Option<String> = None;
-    let mut cond2 = String::new();
-
-    if consulta_cql.contains("WHERE") {
-        let x: Vec<&str> = consulta_cql.split("WHERE").collect();
-        let y: Vec<&str> = x[1].split("AND").collect();
-        cond1 = y[0].trim().to_string();
-        if y.len() > 1 {
-            cond2 = y[1].trim().to_string();
-            oper = Some("AND".to_string());
-        }
-    };
-    let condicion_where = CondicionWhere {
-        condicion1: cond1,
-        operador_logico: oper,
-        condicion2: cond2,
-    };
-
-    Consulta {
-        consulta_explicita: consulta_cql.to_string(),
-        tabla,
-        tipo: TypeCQL::Delete,
-        query: "".to_string(),
-        condicion_where,
-    }
-}
-
-fn parsear_create_table(consulta_cql: &str) -> Consulta {
-    let splited = consulta_cql.split_whitespace().collect::<Vec<&str>>();
-    let tabla = splited[2].to_string();
-
-    let query = (splited[3..]).to_vec().join(" ");
-
-    let condicion_where = CondicionWhere {
-        condicion1: String::new(),
-        operador_logico: None,
-        condicion2: String::new(),
-    };
-
-    Consulta {
-        consulta_explicita: consulta_cql.to_string(),
-        tabla,
-        tipo: TypeCQL::CreateTable,
-        query,
-        condicion_where,
-    }
-}
+use crate::parser_cql::parser::parsear;
+use crate::serial_deserial::tipo_columna::{separar_parametros, TipoColumna, TipoUsuario};
 
 pub fn obtener_headers_table(query: &str) -> Vec<String> {
     let mut headers = vec![];
 
     let contenido_query = &query[1..query.len() - 1];
-    let campos = contenido_query.split(',').collect::<Vec<&str>>();
+    let campos = separar_parametros(contenido_query);
 
     for mut campo in campos {
         campo = campo.trim();
@@ -182,6 +31,42 @@ pub fn obtener_headers_table(query: &str) -> Vec<String> {
     mover_al_inicio(headers)
 }
 
+/// Devuelve el tipo CQL de cada columna de la tabla en el mismo orden
+/// en que `obtener_headers_table` devuelve los headers. Las columnas pueden
+/// ser de los UDT indicados, los del keyspace de la tabla
+pub fn obtener_tipos_table(
+    query: &str,
+    headers: &[String],
+    udts: &[TipoUsuario],
+) -> Result<Vec<TipoColumna>, String> {
+    let contenido_query = &query[1..query.len() - 1];
+    let mut tipos_por_columna: Vec<(String, TipoColumna)> = vec![];
+
+    for campo in separar_parametros(contenido_query) {
+        let campo = campo.trim();
+        if campo.starts_with("PRIMARY KEY") {
+            continue;
+        }
+        if let Some((nombre, tipo)) = campo.split_once(char::is_whitespace) {
+            tipos_por_columna.push((
+                nombre.to_string(),
+                TipoColumna::create_con_udts(tipo, udts)?,
+            ));
+        }
+    }
+
+    let mut tipos = vec![];
+    for header in headers {
+        let tipo = tipos_por_columna
+            .iter()
+            .find(|(nombre, _)| nombre == header)
+            .map(|(_, tipo)| tipo.clone())
+            .ok_or(format!("La columna {} no tiene un tipo definido", header))?;
+        tipos.push(tipo);
+    }
+    Ok(tipos)
+}
+
 fn mover_al_inicio(headers: Vec<String>) -> Vec<String> {
     let mut headers_ordenados: Vec<String> = Vec::with_capacity(headers.len());
     headers_ordenados.push(headers[headers.len() - 1].to_string());
@@ -193,77 +78,118 @@ fn mover_al_inicio(headers: Vec<String>) -> Vec<String> {
     headers_ordenados
 }
 
-fn parsear_create_keyspace(consulta_cql: &str) -> Consulta {
-    let splited = consulta_cql.split_whitespace().collect::<Vec<&str>>();
-    let option_index = splited.iter().position(|&x| x == "=").unwrap_or(0);
-
-    let tabla = splited[2].to_string();
-    let query = splited[(option_index + 1)..].join(" ");
+/// Devuelve los incrementos de contadores de las asignaciones de un UPDATE
+/// (`c = c + 1` o `c = c - 1`), o ninguno si no incrementa contadores. Un
+/// UPDATE no puede mezclar incrementos con asignaciones de otros valores
+pub fn obtener_incrementos(query_update: &str) -> Result<Vec<(String, i64)>, String> {
+    let mut incrementos = vec![];
+    let asignaciones = query_update.split(", ").collect::<Vec<&str>>();
+    for asignacion in &asignaciones {
+        let Some((columna, valor)) = asignacion.split_once(" = ") else {
+            continue;
+        };
+        let columna = columna.trim();
+        let partes = valor.split_whitespace().collect::<Vec<&str>>();
+        let signo = match partes.as_slice() {
+            [c, "+", _] if *c == columna => 1,
+            [c, "-", _] if *c == columna => -1,
+            _ => continue,
+        };
+        let delta = partes[2].parse::<i64>().map_err(|_| {
+            format!(
+                "Incremento inválido del contador {}: {}",
+                columna, partes[2]
+            )
+        })?;
+        incrementos.push((columna.to_string(), signo * delta));
+    }
+    if !incrementos.is_empty() && incrementos.len() != asignaciones.len() {
+        return Err(
+            "Un UPDATE no puede mezclar incrementos de contadores con otras asignaciones."
+                .to_string(),
+        );
+    }
+    Ok(incrementos)
+}
 
-    let condicion_where = CondicionWhere {
-        condicion1: String::new(),
-        operador_logico: None,
-        condicion2: String::new(),
+/// Estrategia y factor de replicación del mapa `replication` de un CREATE o ALTER
+/// KEYSPACE, con las claves en cualquier orden. Tiene que indicar las dos, y el
+/// factor tiene que ser al menos 1
+pub fn obtener_tipo_strategy_y_replication(
+    replicacion: &[(Termino, Termino)],
+) -> Result<(String, usize), String> {
+    let valor = |clave: &str| {
+        replicacion
+            .iter()
+            .find(|(otra, _)| texto_termino(otra) == clave)
+            .map(|(_, valor)| texto_termino(valor))
+            .ok_or(format!(
+                "La replicación del keyspace no indica '{}'.",
+                clave
+            ))
     };
-
-    Consulta {
-        consulta_explicita: consulta_cql.to_string(),
-        tabla,
-        tipo: TypeCQL::CreateKeyspace,
-        query,
-        condicion_where,
+    let strategy = valor("class")?;
+    let number = valor("replication_factor")?;
+    match number.parse::<usize>() {
+        Ok(number) if number >= 1 => Ok((strategy, number)),
+        _ => Err(format!(
+            "Factor de replicación inválido: {}, tiene que ser un entero mayor a 0.",
+            number
+        )),
     }
 }
 
-pub fn obtener_tipo_strategy_y_replication(query: &str) -> (String, usize) {
-    let splited = query.split(",").collect::<Vec<&str>>();
-    let class_strategy = splited[0].split(":").collect::<Vec<&str>>();
-    let repli_number = splited[1].split(":").collect::<Vec<&str>>();
-
-    let strategy = class_strategy[1].trim().replace("'", "").to_string();
-
-    let number = repli_number[1]
-        .trim()
-        .replace("}", "")
-        .parse::<usize>()
-        .unwrap_or(1);
-
-    (strategy, number)
+// Texto de una clave o un valor del mapa, sin las comillas con las que se lo escribió
+fn texto_termino(termino: &Termino) -> String {
+    match termino {
+        Termino::Texto(texto) | Termino::Numero(texto) | Termino::SinComillas(texto) => {
+            texto.to_string()
+        }
+        otro => otro.to_string(),
+    }
 }
 
+/// Parsea una consulta CQL. Los módulos que todavía no usan el AST de la sentencia
+/// trabajan con los campos de texto de la consulta
 pub fn procesar_consulta(consulta_cql: &str) -> Result<Consulta, String> {
-    let partes: Vec<&str> = consulta_cql.split_whitespace().collect();
-    let res = match partes[0].to_uppercase().as_str() {
-        "SELECT" => parsear_select(consulta_cql),
-        "INSERT" => parsear_insert(consulta_cql),
-        "UPDATE" => parsear_update(consulta_cql),
-        "DELETE" => parsear_delete(consulta_cql),
-        "CREATE" => match partes[1].to_uppercase().as_str() {
-            "TABLE" => parsear_create_table(consulta_cql),
-            "KEYSPACE" => parsear_create_keyspace(consulta_cql),
-            _ => return Err("Consulta CQL no soportada".to_string()),
-        },
-        _ => return Err("Consulta CQL no soportada".to_string()),
-    };
-    Ok(res)
+    let sentencia = parsear(consulta_cql)?;
+    Ok(Consulta::desde_sentencia(consulta_cql, sentencia))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser_cql::ast::{Operador, Relacion, Sentencia};
+    use crate::parser_cql::condicion_lwt::CondicionLwt;
+    use crate::parser_cql::condicion_where::CondicionWhere;
+    use crate::parser_cql::type_cql::TypeCQL;
+
+    // WHERE ORIGEN = 'AEROPUERTO JORGE NEWBERY' AND ID_VUELO = 123
+    fn condicion_origen_e_id() -> CondicionWhere {
+        CondicionWhere::new(vec![
+            Relacion {
+                columna: "ORIGEN".to_string(),
+                token: false,
+                operador: Operador::Igual,
+                valor: Termino::Texto("AEROPUERTO JORGE NEWBERY".to_string()),
+            },
+            Relacion {
+                columna: "ID_VUELO".to_string(),
+                token: false,
+                operador: Operador::Igual,
+                valor: Termino::Numero("123".to_string()),
+            },
+        ])
+    }
 
     #[test]
     fn test_parsear_insert() {
         //Arrange
         let query = "INSERT INTO tabla_ejemplo (ID_VUELO, ORIGEN, DESTINO, COMBUSTIBLE) VALUES (5, 'Buenos Aires', 'Rio de Janeiro', 99)";
-        let cond = CondicionWhere {
-            condicion1: String::new(),
-            operador_logico: None,
-            condicion2: String::new(),
-        };
+        let cond = CondicionWhere::default();
 
         //Act
-        let consulta = parsear_insert(query);
+        let consulta = procesar_consulta(query).unwrap();
 
         //Assert
         assert!(consulta.tabla == "tabla_ejemplo");
@@ -285,14 +211,10 @@ mod tests {
     fn test_parsear_select() {
         //Arrange
         let query = "SELECT ID_VUELO, ORIGEN, DESTINO, COMBUSTIBLE FROM tabla_ejemplo WHERE ORIGEN = 'AEROPUERTO JORGE NEWBERY' AND ID_VUELO = 123";
-        let cond = CondicionWhere {
-            condicion1: "ORIGEN = 'AEROPUERTO JORGE NEWBERY'".to_string(),
-            operador_logico: Some("AND".to_string()),
-            condicion2: "ID_VUELO = 123".to_string(),
-        };
+        let cond = condicion_origen_e_id();
 
         //Act
-        let consulta = parsear_select(query);
+        let consulta = procesar_consulta(query).unwrap();
 
         //Assert
         assert!(consulta.tabla == "tabla_ejemplo");
@@ -314,14 +236,10 @@ mod tests {
     fn test_parsear_update() {
         //Arrange
         let query = "UPDATE tabla_ejemplo SET COMBUSTIBLE = 50 WHERE ORIGEN = 'AEROPUERTO JORGE NEWBERY' AND ID_VUELO = 123";
-        let cond = CondicionWhere {
-            condicion1: "ORIGEN = 'AEROPUERTO JORGE NEWBERY'".to_string(),
-            operador_logico: Some("AND".to_string()),
-            condicion2: "ID_VUELO = 123".to_string(),
-        };
+        let cond = condicion_origen_e_id();
 
         //Act
-        let consulta = parsear_update(query);
+        let consulta = procesar_consulta(query).unwrap();
 
         //Assert
         assert!(consulta.tabla == "tabla_ejemplo");
@@ -343,14 +261,10 @@ mod tests {
     fn test_parsear_delete() {
         //Arrange
         let query = "DELETE FROM tabla_ejemplo WHERE ORIGEN = 'AEROPUERTO JORGE NEWBERY' AND ID_VUELO = 123";
-        let cond = CondicionWhere {
-            condicion1: "ORIGEN = 'AEROPUERTO JORGE NEWBERY'".to_string(),
-            operador_logico: Some("AND".to_string()),
-            condicion2: "ID_VUELO = 123".to_string(),
-        };
+        let cond = condicion_origen_e_id();
 
         //Act
-        let consulta = parsear_delete(query);
+        let consulta = procesar_consulta(query).unwrap();
 
         //Assert
         assert!(consulta.tabla == "tabla_ejemplo");
@@ -381,18 +295,56 @@ mod tests {
         assert!(consulta.is_err());
     }
 
+    #[test]
+    fn test_procesar_escritura_con_condicion_lwt() {
+        //Arrange
+        let insert = "INSERT INTO VUELOS_ORIGEN (ORIGEN, FECHA, ID_VUELO) VALUES (AEP, 2024-11-20, VUE1) IF NOT EXISTS";
+        let update = "UPDATE VUELOS_ORIGEN SET COMBUSTIBLE = 50 WHERE ORIGEN = AEP IF NOT EXISTS";
+
+        //Act
+        let consulta = procesar_consulta(insert).unwrap();
+        let consulta_invalida = procesar_consulta(update);
+
+        //Assert
+        assert!(consulta.get_query() == "(ORIGEN, FECHA, ID_VUELO) VALUES (AEP, 2024-11-20, VUE1)");
+        assert!(consulta.get_consulta_explicita() == insert);
+        assert!(consulta.get_condicion_lwt() == Some(&CondicionLwt::NoExiste));
+        assert!(consulta_invalida.is_err());
+    }
+
+    #[test]
+    fn test_obtener_incrementos() {
+        //Arrange
+        let consulta = procesar_consulta(
+            "UPDATE MOVIMIENTOS SET SALIDAS = SALIDAS + 1, LLEGADAS = LLEGADAS - 2 WHERE AEROPUERTO = AEP",
+        )
+        .unwrap();
+
+        //Act
+        let incrementos = obtener_incrementos(consulta.get_query());
+        let sin_incrementos = obtener_incrementos("COMBUSTIBLE = 50");
+        let mezclados = obtener_incrementos("SALIDAS = SALIDAS + 1, COMBUSTIBLE = 50");
+
+        //Assert
+        assert_eq!(
+            incrementos,
+            Ok(vec![
+                ("SALIDAS".to_string(), 1),
+                ("LLEGADAS".to_string(), -2)
+            ])
+        );
+        assert_eq!(sin_incrementos, Ok(vec![]));
+        assert!(mezclados.is_err());
+    }
+
     #[test]
     fn test_parsear_create_table() {
         //Arrange
-        let query = "CREATE TABLE tabla_ejemplo (id_usuario UUID, nombre TEXT, edad INT, PRIMARY KEY ((id_usuario), nombre)";
-        let cond = CondicionWhere {
-            condicion1: String::new(),
-            operador_logico: None,
-            condicion2: String::new(),
-        };
+        let query = "CREATE TABLE tabla_ejemplo (id_usuario UUID, nombre TEXT, edad INT, PRIMARY KEY ((id_usuario), nombre))";
+        let cond = CondicionWhere::default();
 
         //Act
-        let consulta = parsear_create_table(query);
+        let consulta = procesar_consulta(query).unwrap();
 
         //Assert
         assert!(consulta.tabla == "tabla_ejemplo");
@@ -402,11 +354,11 @@ mod tests {
 
         assert!(
             consulta.query
-                == "(id_usuario UUID, nombre TEXT, edad INT, PRIMARY KEY ((id_usuario), nombre)"
+                == "(id_usuario UUID, nombre TEXT, edad INT, PRIMARY KEY ((id_usuario), nombre))"
         );
         assert!(
             consulta.get_query()
-                == "(id_usuario UUID, nombre TEXT, edad INT, PRIMARY KEY ((id_usuario), nombre)"
+                == "(id_usuario UUID, nombre TEXT, edad INT, PRIMARY KEY ((id_usuario), nombre))"
         );
 
         assert!(consulta.consulta_explicita == query);
@@ -415,4 +367,77 @@ mod tests {
         assert!(consulta.condicion_where == cond);
         assert!(consulta.get_where() == &cond);
     }
+
+    #[test]
+    fn test_calificar_tabla_con_keyspace_de_la_sesion() {
+        //Arrange
+        let select = procesar_consulta("SELECT * FROM VUELOS WHERE ORIGEN = 'AEP'").unwrap();
+        let truncate = procesar_consulta("TRUNCATE VUELOS").unwrap();
+        let calificada = procesar_consulta("DELETE FROM Otro.VUELOS WHERE ID = 1").unwrap();
+
+        //Act
+        let select = select.con_keyspace(Some("Aerolineas")).unwrap();
+        let truncate = truncate.con_keyspace(Some("Aerolineas")).unwrap();
+        let calificada = calificada.con_keyspace(Some("Aerolineas")).unwrap();
+
+        //Assert
+        assert_eq!(select.get_tabla(), "Aerolineas.VUELOS");
+        assert_eq!(
+            select.get_consulta_explicita(),
+            "SELECT * FROM Aerolineas.VUELOS WHERE ORIGEN = 'AEP'"
+        );
+        assert_eq!(
+            truncate.get_consulta_explicita(),
+            "TRUNCATE Aerolineas.VUELOS"
+        );
+        assert_eq!(calificada.get_tabla(), "Otro.VUELOS");
+    }
+
+    #[test]
+    fn test_calificar_vista_y_tabla_base_con_keyspace_de_la_sesion() {
+        //Arrange
+        let vista = procesar_consulta(
+            "CREATE MATERIALIZED VIEW POR_DESTINO AS SELECT * FROM VUELOS WHERE DESTINO IS NOT NULL AND ID IS NOT NULL PRIMARY KEY ((DESTINO), ID)",
+        )
+        .unwrap();
+
+        //Act
+        let vista = vista.con_keyspace(Some("Aerolineas")).unwrap();
+
+        //Assert
+        assert_eq!(vista.get_tabla(), "Aerolineas.POR_DESTINO");
+        assert_eq!(
+            vista.get_sentencia().tabla_base(),
+            Some("Aerolineas.VUELOS")
+        );
+    }
+
+    #[test]
+    fn test_replicacion_del_keyspace() {
+        //Arrange
+        let replicacion = |consulta: &str| match parsear(consulta) {
+            Ok(Sentencia::AlterKeyspace { replicacion, .. }) => replicacion,
+            _ => vec![],
+        };
+        let alter = "ALTER KEYSPACE k WITH replication = ";
+
+        //Act
+        let invertida = obtener_tipo_strategy_y_replication(&replicacion(&format!(
+            "{}{{'replication_factor': 3, 'class': 'SimpleStrategy'}}",
+            alter
+        )));
+        let sin_factor = obtener_tipo_strategy_y_replication(&replicacion(&format!(
+            "{}{{'class': 'SimpleStrategy'}}",
+            alter
+        )));
+        let factor_cero = obtener_tipo_strategy_y_replication(&replicacion(&format!(
+            "{}{{'class': 'SimpleStrategy', 'replication_factor': 0}}",
+            alter
+        )));
+
+        //Assert
+        assert_eq!(invertida, Ok(("SimpleStrategy".to_string(), 3)));
+        assert!(sin_factor.is_err());
+        assert!(factor_cero.is_err());
+    }
 }
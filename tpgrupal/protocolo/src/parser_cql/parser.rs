@@ -0,0 +1,1601 @@
+use super::{
+    ast::{
+        Asignacion, CambioTabla, DefinicionColumna, Descripcion, Expresion, Operador, Orden,
+        Relacion, Seleccion, Selector, Sentencia, Termino,
+    },
+    condicion_lwt::CondicionLwt,
+    lexer::{error_sintaxis, tokenizar, Posicion, TipoToken, Token},
+    type_cql::TypeCQL,
+};
+
+// Palabras que terminan el valor de una relación del WHERE
+const FIN_RELACION: [&str; 7] = ["AND", "IF", "GROUP", "ORDER", "PER", "LIMIT", "ALLOW"];
+
+// Tokens [inicio, fin) de cada elemento de una lista separada por comas
+type Rangos = Vec<(usize, usize)>;
+
+// Columnas de la partition key y de clustering
+type ClavePrimaria = (Vec<String>, Vec<String>);
+
+// Funciones de un selector que reciben una columna, con la expresión que construyen
+type Funcion = (&'static str, fn(String) -> Expresion);
+
+const FUNCIONES: [Funcion; 6] = [
+    ("WRITETIME", Expresion::WriteTime),
+    ("TTL", Expresion::Ttl),
+    ("MIN", Expresion::Min),
+    ("MAX", Expresion::Max),
+    ("SUM", Expresion::Sum),
+    ("AVG", Expresion::Avg),
+];
+
+/// Parsea una sentencia CQL, que puede terminar en punto y coma. Los errores
+/// de sintaxis indican la línea y la columna en la que se encontraron
+pub fn parsear(consulta_cql: &str) -> Result<Sentencia, String> {
+    let mut tokens = tokenizar(consulta_cql)?;
+    if tokens.last().is_some_and(|token| token.es_simbolo(";")) {
+        tokens.pop();
+    }
+    let mut parser = Parser {
+        consulta: consulta_cql,
+        tokens,
+        actual: 0,
+        fin: posicion_final(consulta_cql),
+    };
+    let sentencia = parser.sentencia()?;
+    if parser.ver().is_some() {
+        return Err(parser.esperado("el fin de la sentencia"));
+    }
+    Ok(sentencia)
+}
+
+// Posición siguiente al último caracter de la consulta, para los errores en
+// los que la consulta termina antes de lo esperado
+fn posicion_final(consulta_cql: &str) -> Posicion {
+    let mut posicion = Posicion {
+        linea: 1,
+        columna: 1,
+    };
+    for caracter in consulta_cql.trim_end().chars() {
+        if caracter == '\n' {
+            posicion.linea += 1;
+            posicion.columna = 1;
+        } else {
+            posicion.columna += 1;
+        }
+    }
+    posicion
+}
+
+// Parser descendente recursivo sobre los tokens de una sentencia
+struct Parser<'a> {
+    consulta: &'a str,
+    tokens: Vec<Token>,
+    actual: usize,
+    fin: Posicion,
+}
+
+impl Parser<'_> {
+    fn sentencia(&mut self) -> Result<Sentencia, String> {
+        let Some(token) = self.avanzar() else {
+            return Err(self.esperado("una sentencia"));
+        };
+        let palabra = match &token.tipo {
+            TipoToken::Identificador(palabra) => palabra.to_uppercase(),
+            _ => String::new(),
+        };
+        match palabra.as_str() {
+            "SELECT" => self.select(),
+            "INSERT" => self.insert(),
+            "UPDATE" => self.update(),
+            "DELETE" => self.delete(),
+            "CREATE" => self.create(),
+            "ALTER" => self.alter(),
+            "DROP" => self.drop(),
+            "TRUNCATE" => self.truncate(),
+            "USE" => Ok(Sentencia::Use {
+                keyspace: self.nombre()?,
+            }),
+            "DESCRIBE" | "DESC" => self.describe(),
+            "GRANT" => self.permisos(TypeCQL::Grant),
+            "REVOKE" => self.permisos(TypeCQL::Revoke),
+            "LIST" => self.permisos(TypeCQL::ListPermissions),
+            _ => Err(error_sintaxis(
+                token.posicion,
+                &format!("sentencia no soportada: {}", token.descripcion()),
+            )),
+        }
+    }
+
+    fn create(&mut self) -> Result<Sentencia, String> {
+        if self.opcional_palabra("TABLE") {
+            self.create_table()
+        } else if self.opcional_palabra("KEYSPACE") {
+            self.create_keyspace()
+        } else if self.opcional_palabra("TYPE") {
+            self.create_type()
+        } else if self.opcional_palabra("MATERIALIZED") {
+            self.palabra("VIEW")?;
+            self.create_view()
+        } else if self.opcional_palabra("ROLE") {
+            self.rol(TypeCQL::CreateRole)
+        } else {
+            Err(self.esperado("TABLE, KEYSPACE, TYPE, MATERIALIZED VIEW o ROLE"))
+        }
+    }
+
+    fn alter(&mut self) -> Result<Sentencia, String> {
+        if self.opcional_palabra("TABLE") {
+            self.alter_table()
+        } else if self.opcional_palabra("KEYSPACE") {
+            // ALTER KEYSPACE nombre WITH replication = {'clave': valor, ...}
+            let keyspace = self.nombre()?;
+            let replicacion = self.replicacion()?;
+            Ok(Sentencia::AlterKeyspace {
+                keyspace,
+                replicacion,
+            })
+        } else if self.opcional_palabra("ROLE") {
+            self.rol(TypeCQL::AlterRole)
+        } else {
+            Err(self.esperado("TABLE, KEYSPACE o ROLE"))
+        }
+    }
+
+    // DROP TABLE [IF EXISTS] tabla, DROP KEYSPACE [IF EXISTS] nombre, DROP TYPE
+    // [IF EXISTS] tipo, DROP MATERIALIZED VIEW [IF EXISTS] vista o DROP ROLE
+    fn drop(&mut self) -> Result<Sentencia, String> {
+        let tipo = if self.opcional_palabra("TABLE") {
+            TypeCQL::DropTable
+        } else if self.opcional_palabra("KEYSPACE") {
+            TypeCQL::DropKeyspace
+        } else if self.opcional_palabra("TYPE") {
+            TypeCQL::DropType
+        } else if self.opcional_palabra("MATERIALIZED") {
+            self.palabra("VIEW")?;
+            TypeCQL::DropView
+        } else if self.opcional_palabra("ROLE") {
+            return self.rol(TypeCQL::DropRole);
+        } else {
+            return Err(self.esperado("TABLE, KEYSPACE, TYPE, MATERIALIZED VIEW o ROLE"));
+        };
+        let si_existe = self.si_existe()?;
+        let nombre = match tipo {
+            TypeCQL::DropKeyspace => self.nombre()?,
+            _ => self.nombre_tabla()?,
+        };
+        Ok(Sentencia::Drop {
+            tipo,
+            nombre,
+            si_existe,
+        })
+    }
+
+    // SELECT [DISTINCT] (* | selector, ...) FROM tabla [WHERE relacion AND ...]
+    // [GROUP BY columna, ...] [ORDER BY columna [ASC | DESC], ...]
+    // [PER PARTITION LIMIT n] [LIMIT n] [ALLOW FILTERING]
+    fn select(&mut self) -> Result<Sentencia, String> {
+        let distinct = self.opcional_palabra("DISTINCT");
+        let selectores = match self.opcional_simbolo("*") {
+            true => vec![],
+            false => self.lista(|parser| parser.selector())?,
+        };
+        self.palabra("FROM")?;
+        let tabla = self.nombre_tabla()?;
+        let condiciones = self.where_opcional()?;
+        let agrupamiento = self.agrupamiento()?;
+        let orden = self.orden()?;
+        let (limite_por_particion, limite) = self.limites()?;
+        let permitir_filtrado = self.opcional_palabra("ALLOW");
+        if permitir_filtrado {
+            self.palabra("FILTERING")?;
+        }
+        let seleccion = Seleccion {
+            distinct,
+            selectores,
+            agrupamiento,
+            orden,
+            limite_por_particion,
+            limite,
+        };
+        Ok(Sentencia::Select {
+            tabla,
+            seleccion,
+            condiciones,
+            permitir_filtrado,
+        })
+    }
+
+    // Una expresión con un alias opcional: AS alias
+    fn selector(&mut self) -> Result<Selector, String> {
+        let expresion = self.expresion()?;
+        let alias = match self.opcional_palabra("AS") {
+            true => Some(self.nombre()?),
+            false => None,
+        };
+        Ok(Selector { expresion, alias })
+    }
+
+    // columna, columna.campo de un UDT, COUNT(*) o una función sobre una columna:
+    // WRITETIME, TTL, COUNT, MIN, MAX, SUM o AVG
+    fn expresion(&mut self) -> Result<Expresion, String> {
+        if self.llamada("COUNT") {
+            if self.opcional_simbolo("*") {
+                self.simbolo(")")?;
+                return Ok(Expresion::Count(None));
+            }
+            return Ok(Expresion::Count(Some(self.argumento()?)));
+        }
+        for (funcion, expresion) in FUNCIONES {
+            if self.llamada(funcion) {
+                return Ok(expresion(self.argumento()?));
+            }
+        }
+        let columna = self.nombre()?;
+        if self.opcional_simbolo(".") {
+            return Ok(Expresion::Campo(columna, self.nombre()?));
+        }
+        Ok(Expresion::Columna(columna))
+    }
+
+    // GROUP BY columna, ...
+    fn agrupamiento(&mut self) -> Result<Vec<String>, String> {
+        if !self.opcional_palabra("GROUP") {
+            return Ok(vec![]);
+        }
+        self.palabra("BY")?;
+        self.lista(|parser| parser.nombre())
+    }
+
+    // ORDER BY columna [ASC | DESC], ...
+    fn orden(&mut self) -> Result<Vec<(String, Orden)>, String> {
+        if !self.opcional_palabra("ORDER") {
+            return Ok(vec![]);
+        }
+        self.palabra("BY")?;
+        self.lista(|parser| {
+            let columna = parser.nombre()?;
+            if parser.opcional_palabra("DESC") {
+                return Ok((columna, Orden::Descendente));
+            }
+            parser.opcional_palabra("ASC");
+            Ok((columna, Orden::Ascendente))
+        })
+    }
+
+    // [PER PARTITION LIMIT n] [LIMIT n]
+    fn limites(&mut self) -> Result<(Option<usize>, Option<usize>), String> {
+        let mut limite_por_particion = None;
+        if self.opcional_palabra("PER") {
+            self.palabra("PARTITION")?;
+            self.palabra("LIMIT")?;
+            limite_por_particion = Some(self.entero()?);
+        }
+        let limite = match self.opcional_palabra("LIMIT") {
+            true => Some(self.entero()?),
+            false => None,
+        };
+        Ok((limite_por_particion, limite))
+    }
+
+    // INSERT INTO tabla (columna, ...) VALUES (valor, ...) [IF NOT EXISTS]
+    // [USING TIMESTAMP n]
+    fn insert(&mut self) -> Result<Sentencia, String> {
+        self.palabra("INTO")?;
+        let tabla = self.nombre_tabla()?;
+        self.simbolo("(")?;
+        let columnas = self.lista(|parser| parser.nombre())?;
+        self.simbolo(")")?;
+        let posicion_valores = self.posicion();
+        let valores = self.valores()?;
+        if valores.len() != columnas.len() {
+            return Err(error_sintaxis(
+                posicion_valores,
+                &format!(
+                    "se indicaron {} columnas y {} valores",
+                    columnas.len(),
+                    valores.len()
+                ),
+            ));
+        }
+        let condicion_lwt = self.condicion_lwt(TypeCQL::Insert)?;
+        Ok(Sentencia::Insert {
+            tabla,
+            columnas,
+            valores,
+            condicion_lwt,
+            momento: self.momento()?,
+        })
+    }
+
+    // VALUES (valor, ...), donde un valor omitido queda vacío como las celdas nulas
+    // de las filas
+    fn valores(&mut self) -> Result<Vec<Termino>, String> {
+        self.palabra("VALUES")?;
+        self.simbolo("(")?;
+        let valores = self.lista(|parser| {
+            Ok(parser
+                .termino_opcional(|token| token.es_simbolo(",") || token.es_simbolo(")"))
+                .unwrap_or(Termino::SinComillas(String::new())))
+        })?;
+        self.simbolo(")")?;
+        Ok(valores)
+    }
+
+    // UPDATE tabla [USING TIMESTAMP n] SET columna = valor, ... [WHERE relacion AND ...]
+    // [IF ...]
+    fn update(&mut self) -> Result<Sentencia, String> {
+        let tabla = self.nombre_tabla()?;
+        let momento = self.momento()?;
+        self.palabra("SET")?;
+        let asignaciones = self.lista(|parser| parser.asignacion())?;
+        let condiciones = self.where_opcional()?;
+        let condicion_lwt = self.condicion_lwt(TypeCQL::Update)?;
+        Ok(Sentencia::Update {
+            tabla,
+            asignaciones,
+            condiciones,
+            condicion_lwt,
+            momento,
+        })
+    }
+
+    // DELETE [columna | columna[elemento] | columna.campo, ...] FROM tabla
+    // [USING TIMESTAMP n] [WHERE relacion AND ...] [IF ...]
+    fn delete(&mut self) -> Result<Sentencia, String> {
+        let mut columnas = vec![];
+        if !self.opcional_palabra("FROM") {
+            columnas = self.lista(|parser| parser.destino())?;
+            self.palabra("FROM")?;
+        }
+        let tabla = self.nombre_tabla()?;
+        let momento = self.momento()?;
+        let condiciones = self.where_opcional()?;
+        let condicion_lwt = self.condicion_lwt(TypeCQL::Delete)?;
+        Ok(Sentencia::Delete {
+            tabla,
+            columnas,
+            condiciones,
+            condicion_lwt,
+            momento,
+        })
+    }
+
+    // USING TIMESTAMP n, el momento en microsegundos con el que se ordena la escritura
+    fn momento(&mut self) -> Result<Option<i64>, String> {
+        if !self.opcional_palabra("USING") {
+            return Ok(None);
+        }
+        self.palabra("TIMESTAMP")?;
+        let momento = match self.ver().map(|token| &token.tipo) {
+            Some(TipoToken::Numero(numero)) => numero.parse::<i64>().ok(),
+            _ => None,
+        };
+        let momento = momento.ok_or_else(|| self.esperado("un timestamp"))?;
+        self.actual += 1;
+        Ok(Some(momento))
+    }
+
+    // TRUNCATE [TABLE] tabla
+    // DESCRIBE (KEYSPACES | TABLES | KEYSPACE keyspace | TABLE tabla), o DESC
+    fn describe(&mut self) -> Result<Sentencia, String> {
+        let descripcion = if self.opcional_palabra("KEYSPACES") {
+            Descripcion::Keyspaces
+        } else if self.opcional_palabra("TABLES") {
+            Descripcion::Tablas
+        } else if self.opcional_palabra("KEYSPACE") {
+            Descripcion::Keyspace(self.nombre()?)
+        } else if self.opcional_palabra("TABLE") {
+            Descripcion::Tabla(self.nombre_tabla()?)
+        } else {
+            return Err(self.esperado("KEYSPACES, TABLES, KEYSPACE o TABLE"));
+        };
+        Ok(Sentencia::Describe { descripcion })
+    }
+
+    fn truncate(&mut self) -> Result<Sentencia, String> {
+        self.opcional_palabra("TABLE");
+        let tabla = self.nombre_tabla()?;
+        Ok(Sentencia::Truncate { tabla })
+    }
+
+    // CREATE TABLE [IF NOT EXISTS] tabla (columna tipo [PRIMARY KEY], ...,
+    // [PRIMARY KEY ((pk, ...), ck, ...)])
+    fn create_table(&mut self) -> Result<Sentencia, String> {
+        let si_no_existe = self.si_no_existe()?;
+        let posicion_tabla = self.posicion();
+        let tabla = self.nombre_tabla()?;
+        self.simbolo("(")?;
+        let (columnas, clave_primaria) = self.definiciones()?;
+        self.simbolo(")")?;
+        let Some((clave_particion, clustering)) = clave_primaria else {
+            return Err(error_sintaxis(
+                posicion_tabla,
+                &format!("la tabla {} no tiene PRIMARY KEY", tabla),
+            ));
+        };
+        if let Some(columna) = clave_particion
+            .iter()
+            .chain(&clustering)
+            .find(|clave| !columnas.iter().any(|columna| &columna.nombre == *clave))
+        {
+            return Err(error_sintaxis(
+                posicion_tabla,
+                &format!("la columna {} de la PRIMARY KEY no está definida", columna),
+            ));
+        }
+        Ok(Sentencia::CreateTable {
+            tabla,
+            columnas,
+            clave_particion,
+            clustering,
+            si_no_existe,
+        })
+    }
+
+    // ALTER TABLE tabla ADD columna tipo | ADD (columna tipo, ...) | DROP columna |
+    // DROP (columna, ...) | RENAME columna TO columna [AND columna TO columna ...]
+    fn alter_table(&mut self) -> Result<Sentencia, String> {
+        let tabla = self.nombre_tabla()?;
+        let cambio = if self.opcional_palabra("ADD") {
+            CambioTabla::Agregar(self.uno_o_lista(|parser| {
+                let nombre = parser.nombre()?;
+                let tipo = parser.tipo_columna()?;
+                Ok(DefinicionColumna { nombre, tipo })
+            })?)
+        } else if self.opcional_palabra("DROP") {
+            CambioTabla::Quitar(self.uno_o_lista(|parser| parser.nombre())?)
+        } else if self.opcional_palabra("RENAME") {
+            CambioTabla::Renombrar(self.renombres()?)
+        } else {
+            return Err(self.esperado("ADD, DROP o RENAME"));
+        };
+        Ok(Sentencia::AlterTable { tabla, cambio })
+    }
+
+    // columna TO columna [AND columna TO columna ...]
+    fn renombres(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut renombres = vec![];
+        loop {
+            let columna = self.nombre()?;
+            self.palabra("TO")?;
+            renombres.push((columna, self.nombre()?));
+            if !self.opcional_palabra("AND") {
+                return Ok(renombres);
+            }
+        }
+    }
+
+    // Definiciones de las columnas, con la PRIMARY KEY en una de ellas o al final
+    fn definiciones(&mut self) -> Result<(Vec<DefinicionColumna>, Option<ClavePrimaria>), String> {
+        let (mut columnas, mut clave_primaria) = (vec![], None);
+        loop {
+            let posicion = self.posicion();
+            let clave = match self.opcional_palabra("PRIMARY") {
+                true => {
+                    self.palabra("KEY")?;
+                    Some(self.clave_primaria()?)
+                }
+                false => {
+                    let (columna, es_clave) = self.definicion_columna()?;
+                    let clave = es_clave.then(|| (vec![columna.nombre.to_string()], vec![]));
+                    columnas.push(columna);
+                    clave
+                }
+            };
+            if clave.is_some() && clave_primaria.is_some() {
+                return Err(error_sintaxis(
+                    posicion,
+                    "la PRIMARY KEY se definió dos veces",
+                ));
+            }
+            clave_primaria = clave_primaria.or(clave);
+            if !self.opcional_simbolo(",") {
+                return Ok((columnas, clave_primaria));
+            }
+        }
+    }
+
+    // columna tipo [PRIMARY KEY]
+    fn definicion_columna(&mut self) -> Result<(DefinicionColumna, bool), String> {
+        let nombre = self.nombre()?;
+        let tipo = self.tipo_columna()?;
+        let es_clave = self.opcional_palabra("PRIMARY");
+        if es_clave {
+            self.palabra("KEY")?;
+        }
+        Ok((DefinicionColumna { nombre, tipo }, es_clave))
+    }
+
+    // ((pk, ...), ck, ...) o (pk, ck, ...)
+    fn clave_primaria(&mut self) -> Result<ClavePrimaria, String> {
+        self.simbolo("(")?;
+        let clave_particion = match self.opcional_simbolo("(") {
+            true => {
+                let clave_particion = self.lista(|parser| parser.nombre())?;
+                self.simbolo(")")?;
+                clave_particion
+            }
+            false => vec![self.nombre()?],
+        };
+        let mut clustering = vec![];
+        while self.opcional_simbolo(",") {
+            clustering.push(self.nombre()?);
+        }
+        self.simbolo(")")?;
+        Ok((clave_particion, clustering))
+    }
+
+    // El tipo de una columna puede tener parámetros, como map<text, int>
+    fn tipo_columna(&mut self) -> Result<String, String> {
+        let mut profundidad = 0;
+        let inicio = self.actual;
+        while let Some(token) = self.ver() {
+            if profundidad == 0
+                && (token.es_simbolo(",") || token.es_simbolo(")") || token.es_palabra("PRIMARY"))
+            {
+                break;
+            }
+            if token.es_simbolo("<") || token.es_simbolo("(") {
+                profundidad += 1;
+            } else if token.es_simbolo(">") || token.es_simbolo(")") {
+                profundidad -= 1;
+            }
+            self.actual += 1;
+        }
+        if inicio == self.actual {
+            return Err(self.esperado("el tipo de la columna"));
+        }
+        Ok(self.texto_tokens(inicio, self.actual))
+    }
+
+    // CREATE KEYSPACE [IF NOT EXISTS] nombre WITH replication = {'clave': valor, ...}
+    fn create_keyspace(&mut self) -> Result<Sentencia, String> {
+        let si_no_existe = self.si_no_existe()?;
+        let keyspace = self.nombre()?;
+        let replicacion = self.replicacion()?;
+        Ok(Sentencia::CreateKeyspace {
+            keyspace,
+            replicacion,
+            si_no_existe,
+        })
+    }
+
+    // CREATE TYPE [IF NOT EXISTS] [keyspace.]tipo (campo tipo, ...)
+    fn create_type(&mut self) -> Result<Sentencia, String> {
+        let si_no_existe = self.si_no_existe()?;
+        let tipo = self.nombre_tabla()?;
+        self.simbolo("(")?;
+        let campos = self.lista(|parser| {
+            let nombre = parser.nombre()?;
+            let tipo = parser.tipo_columna()?;
+            Ok(DefinicionColumna { nombre, tipo })
+        })?;
+        self.simbolo(")")?;
+        Ok(Sentencia::CreateType {
+            tipo,
+            campos,
+            si_no_existe,
+        })
+    }
+
+    // CREATE MATERIALIZED VIEW [IF NOT EXISTS] vista AS SELECT (* | columna, ...) FROM
+    // tabla WHERE columna IS NOT NULL [AND ...] PRIMARY KEY ((pk), ck, ...), donde cada
+    // columna de la clave primaria de la vista tiene que ser IS NOT NULL
+    fn create_view(&mut self) -> Result<Sentencia, String> {
+        let si_no_existe = self.si_no_existe()?;
+        let posicion_vista = self.posicion();
+        let vista = self.nombre_tabla()?;
+        self.palabra("AS")?;
+        self.palabra("SELECT")?;
+        let columnas = match self.opcional_simbolo("*") {
+            true => vec![],
+            false => self.lista(|parser| parser.nombre())?,
+        };
+        self.palabra("FROM")?;
+        let tabla_base = self.nombre_tabla()?;
+        let no_nulas = self.no_nulas()?;
+        self.palabra("PRIMARY")?;
+        self.palabra("KEY")?;
+        let (clave_particion, clustering) = self.clave_primaria()?;
+        let mut clave_primaria = clave_particion.iter().chain(&clustering);
+        if let Some(columna) = clave_primaria.find(|c| !no_nulas.contains(c)) {
+            let mensaje = format!("la clave {} tiene que ser IS NOT NULL", columna);
+            return Err(error_sintaxis(posicion_vista, &mensaje));
+        }
+        Ok(Sentencia::CreateView {
+            vista,
+            tabla_base,
+            columnas,
+            clave_particion,
+            clustering,
+            si_no_existe,
+        })
+    }
+
+    // WHERE columna IS NOT NULL [AND columna IS NOT NULL ...]
+    fn no_nulas(&mut self) -> Result<Vec<String>, String> {
+        self.palabra("WHERE")?;
+        let mut columnas = vec![];
+        loop {
+            columnas.push(self.nombre()?);
+            self.palabra("IS")?;
+            self.palabra("NOT")?;
+            self.palabra("NULL")?;
+            if !self.opcional_palabra("AND") {
+                return Ok(columnas);
+            }
+        }
+    }
+
+    // WITH replication = {'clave': valor, ...}
+    fn replicacion(&mut self) -> Result<Vec<(Termino, Termino)>, String> {
+        self.palabra("WITH")?;
+        self.palabra("replication")?;
+        self.simbolo("=")?;
+        self.simbolo("{")?;
+        let replicacion = self.lista(|parser| {
+            let clave = parser.termino(|token| token.es_simbolo(":"))?;
+            parser.simbolo(":")?;
+            let valor = parser.termino(|token| token.es_simbolo(",") || token.es_simbolo("}"))?;
+            Ok((clave, valor))
+        })?;
+        self.simbolo("}")?;
+        Ok(replicacion)
+    }
+
+    // CREATE ROLE [IF NOT EXISTS] nombre [WITH opcion = valor AND ...],
+    // ALTER ROLE nombre [WITH ...] y DROP ROLE [IF EXISTS] nombre
+    fn rol(&mut self, tipo: TypeCQL) -> Result<Sentencia, String> {
+        let si_corresponde = match tipo {
+            TypeCQL::CreateRole => self.si_no_existe()?,
+            TypeCQL::DropRole => self.si_existe()?,
+            _ => false,
+        };
+        let nombre = self.nombre_rol()?;
+        let mut opciones = vec![];
+        if tipo != TypeCQL::DropRole && self.opcional_palabra("WITH") {
+            loop {
+                let opcion = self.nombre()?;
+                self.simbolo("=")?;
+                opciones.push((opcion, self.termino(|token| token.es_palabra("AND"))?));
+                if !self.opcional_palabra("AND") {
+                    break;
+                }
+            }
+        }
+        Ok(Sentencia::Rol {
+            tipo,
+            nombre,
+            si_corresponde,
+            opciones,
+        })
+    }
+
+    // GRANT permiso ON recurso TO rol, REVOKE permiso ON recurso FROM rol y
+    // LIST permiso [ON recurso] [OF rol] [NORECURSIVE]
+    fn permisos(&mut self, tipo: TypeCQL) -> Result<Sentencia, String> {
+        let permiso = self.permiso()?;
+        let recurso = match tipo {
+            TypeCQL::ListPermissions if !self.opcional_palabra("ON") => None,
+            TypeCQL::ListPermissions => Some(self.recurso()?),
+            _ => {
+                self.palabra("ON")?;
+                Some(self.recurso()?)
+            }
+        };
+        let rol = self.rol_permisos(&tipo)?;
+        Ok(Sentencia::Permisos {
+            tipo,
+            permiso,
+            recurso,
+            rol,
+        })
+    }
+
+    // ALL [PERMISSIONS] o permiso [PERMISSION]
+    fn permiso(&mut self) -> Result<String, String> {
+        if self.opcional_palabra("ALL") {
+            self.opcional_palabra("PERMISSIONS");
+            return Ok("ALL PERMISSIONS".to_string());
+        }
+        let permiso = self.nombre()?.to_uppercase();
+        self.opcional_palabra("PERMISSION");
+        Ok(permiso)
+    }
+
+    // TO rol en el GRANT, FROM rol en el REVOKE y [OF rol] [NORECURSIVE] en el LIST
+    fn rol_permisos(&mut self, tipo: &TypeCQL) -> Result<Option<String>, String> {
+        match tipo {
+            TypeCQL::Grant => self.palabra("TO")?,
+            TypeCQL::Revoke => self.palabra("FROM")?,
+            _ => {
+                let rol = match self.opcional_palabra("OF") {
+                    true => Some(self.nombre_rol()?),
+                    false => None,
+                };
+                self.opcional_palabra("NORECURSIVE");
+                return Ok(rol);
+            }
+        }
+        Ok(Some(self.nombre_rol()?))
+    }
+
+    // ALL KEYSPACES, KEYSPACE nombre o [TABLE] tabla
+    fn recurso(&mut self) -> Result<String, String> {
+        if self.opcional_palabra("ALL") {
+            self.palabra("KEYSPACES")?;
+            return Ok("ALL KEYSPACES".to_string());
+        }
+        if self.opcional_palabra("KEYSPACE") {
+            return Ok(format!("KEYSPACE {}", self.nombre()?));
+        }
+        self.opcional_palabra("TABLE");
+        Ok(format!("TABLE {}", self.nombre_tabla()?))
+    }
+
+    fn where_opcional(&mut self) -> Result<Vec<Relacion>, String> {
+        if !self.opcional_palabra("WHERE") {
+            return Ok(vec![]);
+        }
+        let mut condiciones = vec![self.relacion()?];
+        while self.opcional_palabra("AND") {
+            condiciones.push(self.relacion()?);
+        }
+        Ok(condiciones)
+    }
+
+    // columna operador valor, token(columna) operador valor, columna IN (valor, ...)
+    // o columna CONTAINS [KEY] valor, donde el valor termina en el siguiente AND, IF
+    // o ALLOW
+    fn relacion(&mut self) -> Result<Relacion, String> {
+        let token = self.llamada("token");
+        let columna = match token {
+            true => self.argumento()?,
+            false => self.nombre()?,
+        };
+        let operador = self.operador()?;
+        let valor = match operador {
+            Operador::In => self.tupla()?,
+            _ => self.termino(|token| FIN_RELACION.iter().any(|fin| token.es_palabra(fin)))?,
+        };
+        Ok(Relacion {
+            columna,
+            token,
+            operador,
+            valor,
+        })
+    }
+
+    fn operador(&mut self) -> Result<Operador, String> {
+        if self.opcional_palabra("IN") {
+            return Ok(Operador::In);
+        }
+        if self.opcional_palabra("CONTAINS") {
+            return Ok(match self.opcional_palabra("KEY") {
+                true => Operador::ContainsKey,
+                false => Operador::Contains,
+            });
+        }
+        let operador = match self.ver().map(|token| &token.tipo) {
+            Some(TipoToken::Simbolo(simbolo)) => Operador::desde_simbolo(simbolo),
+            _ => None,
+        };
+        let operador = operador.ok_or_else(|| self.esperado("un operador de comparación"))?;
+        self.actual += 1;
+        Ok(operador)
+    }
+
+    // (valor, ...), la lista de valores de un IN
+    fn tupla(&mut self) -> Result<Termino, String> {
+        self.simbolo("(")?;
+        let valores = self.lista(|parser| parser.termino(|token| token.es_simbolo(",")))?;
+        self.simbolo(")")?;
+        Ok(Termino::Tupla(valores))
+    }
+
+    // columna = valor, columna = columna + n / columna - n para los contadores,
+    // columna = columna + colección / columna - colección / lista + columna para las
+    // colecciones, columna[elemento] = valor para un elemento de un map o una lista y
+    // columna.campo = valor para un campo de un UDT
+    fn asignacion(&mut self) -> Result<Asignacion, String> {
+        let mut asignacion = self.destino()?;
+        self.simbolo("=")?;
+        let inicio = self.actual;
+        let valor = self.termino(|token| {
+            token.es_simbolo(",") || token.es_palabra("WHERE") || token.es_palabra("IF")
+        })?;
+        let columna = &asignacion.columna;
+        asignacion.valor = match self.incremento(columna, inicio)? {
+            Some(delta) => Termino::Incremento(columna.to_string(), delta),
+            None => self.operacion_coleccion(columna, inicio).unwrap_or(valor),
+        };
+        Ok(asignacion)
+    }
+
+    // columna, columna[elemento] o columna.campo, a la que se le asigna null hasta que
+    // se lea su valor
+    fn destino(&mut self) -> Result<Asignacion, String> {
+        let columna = self.nombre()?;
+        let elemento = self.subindice()?;
+        let campo = match elemento.is_none() && self.opcional_simbolo(".") {
+            true => Some(self.nombre()?),
+            false => None,
+        };
+        Ok(Asignacion {
+            columna,
+            elemento,
+            campo,
+            valor: Termino::Nulo,
+        })
+    }
+
+    // [elemento], la clave de un map o la posición de una lista
+    fn subindice(&mut self) -> Result<Option<Termino>, String> {
+        if !self.opcional_simbolo("[") {
+            return Ok(None);
+        }
+        let elemento = self.termino(|token| token.es_simbolo("]"))?;
+        self.simbolo("]")?;
+        Ok(Some(elemento))
+    }
+
+    // El valor que empieza en el token `inicio`, si agrega o quita elementos de la
+    // colección de la columna
+    fn operacion_coleccion(&self, columna: &str, inicio: usize) -> Option<Termino> {
+        let tokens = &self.tokens[inicio..self.actual];
+        let es_columna = |token: &Token| matches!(&token.tipo, TipoToken::Identificador(nombre) if nombre == columna);
+        let coleccion = |inicio: usize, fin: usize| {
+            let literal = self.literal_coleccion(inicio, fin)?;
+            Some(Box::new(literal))
+        };
+        if tokens.len() > 2 && es_columna(&tokens[0]) {
+            let literal = coleccion(inicio + 2, self.actual)?;
+            return match (tokens[1].es_simbolo("+"), tokens[1].es_simbolo("-")) {
+                (true, _) => Some(Termino::Agregado(columna.to_string(), literal)),
+                (_, true) => Some(Termino::Quitado(columna.to_string(), literal)),
+                _ => None,
+            };
+        }
+        let [.., mas, ultimo] = tokens else {
+            return None;
+        };
+        (mas.es_simbolo("+") && es_columna(ultimo))
+            .then(|| coleccion(inicio, self.actual - 2))?
+            .map(|literal| Termino::Antepuesto(literal, columna.to_string()))
+    }
+
+    // Delta del valor que empieza en el token `inicio`, si es columna + n o columna - n
+    fn incremento(&self, columna: &str, inicio: usize) -> Result<Option<i64>, String> {
+        let [otra, operador, numero] = &self.tokens[inicio..self.actual] else {
+            return Ok(None);
+        };
+        let signo = match (operador.es_simbolo("+"), operador.es_simbolo("-")) {
+            (true, _) => 1,
+            (_, true) => -1,
+            _ => return Ok(None),
+        };
+        match (&otra.tipo, &numero.tipo) {
+            (TipoToken::Identificador(otra), TipoToken::Numero(delta)) if otra == columna => {
+                let delta = delta.parse::<i64>().map_err(|_| {
+                    error_sintaxis(
+                        numero.posicion,
+                        &format!("incremento inválido del contador {}: {}", columna, delta),
+                    )
+                })?;
+                Ok(Some(signo * delta))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // IF NOT EXISTS, IF EXISTS o IF columna = valor AND ...
+    fn condicion_lwt(&mut self, tipo: TypeCQL) -> Result<Option<CondicionLwt>, String> {
+        let posicion = self.posicion();
+        if !self.opcional_palabra("IF") {
+            return Ok(None);
+        }
+        let condicion = if self.opcional_palabra("NOT") {
+            self.palabra("EXISTS")?;
+            CondicionLwt::NoExiste
+        } else if self.opcional_palabra("EXISTS") {
+            CondicionLwt::Existe
+        } else {
+            CondicionLwt::Columnas(self.condiciones_lwt()?)
+        };
+        let valida = match tipo {
+            TypeCQL::Insert => condicion == CondicionLwt::NoExiste,
+            _ => condicion != CondicionLwt::NoExiste,
+        };
+        if !valida {
+            return Err(error_sintaxis(
+                posicion,
+                &format!("la condición IF no se puede usar en un {}", tipo),
+            ));
+        }
+        Ok(Some(condicion))
+    }
+
+    // columna = valor AND ..., de una condición IF sobre los valores de la fila
+    fn condiciones_lwt(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut columnas = vec![];
+        loop {
+            let posicion = self.posicion();
+            let relacion = self.relacion()?;
+            if relacion.operador != Operador::Igual || relacion.token {
+                return Err(error_sintaxis(
+                    posicion,
+                    "la condición IF solo admite comparaciones con =",
+                ));
+            }
+            columnas.push((relacion.columna, relacion.valor.to_string()));
+            if !self.opcional_palabra("AND") {
+                return Ok(columnas);
+            }
+        }
+    }
+
+    // Un valor ocupa todos los tokens hasta el que lo termina, fuera de paréntesis,
+    // corchetes y llaves
+    fn termino_opcional(&mut self, es_fin: impl Fn(&Token) -> bool) -> Option<Termino> {
+        let mut profundidad = 0;
+        let inicio = self.actual;
+        while let Some(token) = self.ver() {
+            if profundidad == 0 && es_fin(token) {
+                break;
+            }
+            if ["(", "[", "{"]
+                .iter()
+                .any(|simbolo| token.es_simbolo(simbolo))
+            {
+                profundidad += 1;
+            } else if [")", "]", "}"]
+                .iter()
+                .any(|simbolo| token.es_simbolo(simbolo))
+            {
+                if profundidad == 0 {
+                    break;
+                }
+                profundidad -= 1;
+            }
+            self.actual += 1;
+        }
+        (inicio < self.actual).then(|| self.clasificar(inicio, self.actual))
+    }
+
+    // Si el valor es un único literal se interpreta su tipo; si no, se conserva
+    // el texto tal como se escribió
+    fn clasificar(&self, inicio: usize, fin: usize) -> Termino {
+        if let Some(coleccion) = self.literal_coleccion(inicio, fin) {
+            return coleccion;
+        }
+        match &self.tokens[inicio..fin] {
+            [token] => match &token.tipo {
+                TipoToken::Texto(texto) => Termino::Texto(texto.to_string()),
+                TipoToken::Numero(numero) => Termino::Numero(numero.to_string()),
+                TipoToken::Identificador(palabra) => match palabra.to_lowercase().as_str() {
+                    "true" => Termino::Booleano(true),
+                    "false" => Termino::Booleano(false),
+                    "null" => Termino::Nulo,
+                    _ => Termino::SinComillas(palabra.to_string()),
+                },
+                _ => Termino::SinComillas(self.texto_tokens(inicio, fin)),
+            },
+            [signo, numero] if signo.es_simbolo("-") && signo.fin == numero.inicio => {
+                match &numero.tipo {
+                    TipoToken::Numero(numero) => Termino::Numero(format!("-{}", numero)),
+                    _ => Termino::SinComillas(self.texto_tokens(inicio, fin)),
+                }
+            }
+            _ => Termino::SinComillas(self.texto_tokens(inicio, fin)),
+        }
+    }
+
+    // [a, b], {a, b}, {clave: valor, ...} o (a, b) si los tokens son exactamente un
+    // literal de una colección o de una tupla; {} es un map vacío, y también el literal
+    // de un UDT, {campo: valor, ...}
+    fn literal_coleccion(&self, inicio: usize, fin: usize) -> Option<Termino> {
+        let (primero, ultimo) = (
+            self.tokens.get(inicio)?,
+            self.tokens.get(fin.checked_sub(1)?)?,
+        );
+        let es_lista = primero.es_simbolo("[") && ultimo.es_simbolo("]");
+        let es_tupla = primero.es_simbolo("(") && ultimo.es_simbolo(")");
+        if !(es_lista || es_tupla || primero.es_simbolo("{") && ultimo.es_simbolo("}")) {
+            return None;
+        }
+        let elementos = self.separar_elementos(inicio + 1, fin - 1, ",")?;
+        let terminos = elementos.iter().map(|&(i, f)| self.clasificar(i, f));
+        if es_lista {
+            return Some(Termino::Lista(terminos.collect()));
+        }
+        if es_tupla {
+            return Some(Termino::Tupla(terminos.collect()));
+        }
+        match self.pares_mapa(&elementos) {
+            Some(pares) if !pares.is_empty() || elementos.is_empty() => Some(Termino::Mapa(pares)),
+            _ => Some(Termino::Conjunto(
+                elementos
+                    .iter()
+                    .map(|&(i, f)| self.clasificar(i, f))
+                    .collect(),
+            )),
+        }
+    }
+
+    // Clave y valor de cada elemento, si todos son clave: valor
+    fn pares_mapa(&self, elementos: &Rangos) -> Option<Vec<(Termino, Termino)>> {
+        elementos
+            .iter()
+            .map(
+                |&(i, f)| match self.separar_elementos(i, f, ":")?.as_slice() {
+                    [(i, f), (j, g)] => Some((self.clasificar(*i, *f), self.clasificar(*j, *g))),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    // Rangos de tokens entre los separadores que no están dentro de otros paréntesis,
+    // corchetes o llaves; ninguno si los delimitadores no cierran dentro del rango
+    fn separar_elementos(&self, inicio: usize, fin: usize, separador: &str) -> Option<Rangos> {
+        let (mut rangos, mut desde, mut profundidad) = (vec![], inicio, 0);
+        for indice in inicio..fin {
+            let token = &self.tokens[indice];
+            if ["(", "[", "{"]
+                .iter()
+                .any(|simbolo| token.es_simbolo(simbolo))
+            {
+                profundidad += 1;
+            } else if [")", "]", "}"]
+                .iter()
+                .any(|simbolo| token.es_simbolo(simbolo))
+            {
+                profundidad = usize::checked_sub(profundidad, 1)?;
+            } else if profundidad == 0 && token.es_simbolo(separador) {
+                rangos.push((desde, indice));
+                desde = indice + 1;
+            }
+        }
+        if profundidad != 0 || rangos.iter().any(|(i, f)| i == f) {
+            return None;
+        }
+        if desde < fin {
+            rangos.push((desde, fin));
+        }
+        Some(rangos)
+    }
+
+    fn termino(&mut self, es_fin: impl Fn(&Token) -> bool) -> Result<Termino, String> {
+        match self.termino_opcional(es_fin) {
+            Some(termino) => Ok(termino),
+            None => Err(self.esperado("un valor")),
+        }
+    }
+
+    // Texto de la consulta entre el primer y el último token indicados
+    fn texto_tokens(&self, primero: usize, fin: usize) -> String {
+        self.consulta[self.tokens[primero].inicio..self.tokens[fin - 1].fin].to_string()
+    }
+
+    fn lista<T>(
+        &mut self,
+        mut elemento: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let mut elementos = vec![elemento(self)?];
+        while self.opcional_simbolo(",") {
+            elementos.push(elemento(self)?);
+        }
+        Ok(elementos)
+    }
+
+    // Un elemento suelto o varios entre paréntesis
+    fn uno_o_lista<T>(
+        &mut self,
+        mut elemento: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        if !self.opcional_simbolo("(") {
+            return Ok(vec![elemento(self)?]);
+        }
+        let elementos = self.lista(elemento)?;
+        self.simbolo(")")?;
+        Ok(elementos)
+    }
+
+    fn si_no_existe(&mut self) -> Result<bool, String> {
+        if !self.opcional_palabra("IF") {
+            return Ok(false);
+        }
+        self.palabra("NOT")?;
+        self.palabra("EXISTS")?;
+        Ok(true)
+    }
+
+    fn si_existe(&mut self) -> Result<bool, String> {
+        if !self.opcional_palabra("IF") {
+            return Ok(false);
+        }
+        self.palabra("EXISTS")?;
+        Ok(true)
+    }
+
+    // Avanza sobre `funcion(` si es lo que sigue en la consulta
+    fn llamada(&mut self, funcion: &str) -> bool {
+        let es_llamada = self.ver().is_some_and(|token| token.es_palabra(funcion))
+            && self
+                .tokens
+                .get(self.actual + 1)
+                .is_some_and(|token| token.es_simbolo("("));
+        if es_llamada {
+            self.actual += 2;
+        }
+        es_llamada
+    }
+
+    // La columna que recibe una función, seguida del paréntesis que la cierra
+    fn argumento(&mut self) -> Result<String, String> {
+        let columna = self.nombre()?;
+        self.simbolo(")")?;
+        Ok(columna)
+    }
+
+    fn entero(&mut self) -> Result<usize, String> {
+        let entero = match self.ver().map(|token| &token.tipo) {
+            Some(TipoToken::Numero(numero)) => numero.parse::<usize>().ok().filter(|n| *n > 0),
+            _ => None,
+        };
+        let entero = entero.ok_or_else(|| self.esperado("un entero positivo"))?;
+        self.actual += 1;
+        Ok(entero)
+    }
+
+    fn nombre(&mut self) -> Result<String, String> {
+        match self.ver().map(|token| &token.tipo) {
+            Some(TipoToken::Identificador(nombre))
+            | Some(TipoToken::IdentificadorEntreComillas(nombre)) => {
+                let nombre = nombre.to_string();
+                self.actual += 1;
+                Ok(nombre)
+            }
+            _ => Err(self.esperado("un nombre")),
+        }
+    }
+
+    // Una tabla puede estar calificada con su keyspace: keyspace.tabla
+    fn nombre_tabla(&mut self) -> Result<String, String> {
+        let nombre = self.nombre()?;
+        if self.opcional_simbolo(".") {
+            return Ok(format!("{}.{}", nombre, self.nombre()?));
+        }
+        Ok(nombre)
+    }
+
+    // El nombre de un rol también puede escribirse entre comillas simples
+    fn nombre_rol(&mut self) -> Result<String, String> {
+        if let Some(TipoToken::Texto(nombre)) = self.ver().map(|token| &token.tipo) {
+            let nombre = nombre.to_string();
+            self.actual += 1;
+            return Ok(nombre);
+        }
+        self.nombre()
+    }
+
+    fn ver(&self) -> Option<&Token> {
+        self.tokens.get(self.actual)
+    }
+
+    fn avanzar(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.actual).cloned()?;
+        self.actual += 1;
+        Some(token)
+    }
+
+    fn posicion(&self) -> Posicion {
+        self.ver().map(|token| token.posicion).unwrap_or(self.fin)
+    }
+
+    fn opcional_palabra(&mut self, palabra: &str) -> bool {
+        let es_palabra = self.ver().is_some_and(|token| token.es_palabra(palabra));
+        if es_palabra {
+            self.actual += 1;
+        }
+        es_palabra
+    }
+
+    fn opcional_simbolo(&mut self, simbolo: &str) -> bool {
+        let es_simbolo = self.ver().is_some_and(|token| token.es_simbolo(simbolo));
+        if es_simbolo {
+            self.actual += 1;
+        }
+        es_simbolo
+    }
+
+    fn palabra(&mut self, palabra: &str) -> Result<(), String> {
+        match self.opcional_palabra(palabra) {
+            true => Ok(()),
+            false => Err(self.esperado(&palabra.to_uppercase())),
+        }
+    }
+
+    fn simbolo(&mut self, simbolo: &str) -> Result<(), String> {
+        match self.opcional_simbolo(simbolo) {
+            true => Ok(()),
+            false => Err(self.esperado(simbolo)),
+        }
+    }
+
+    fn esperado(&self, esperado: &str) -> String {
+        match self.ver() {
+            Some(token) => error_sintaxis(
+                token.posicion,
+                &format!(
+                    "se esperaba {}, pero se encontró {}",
+                    esperado,
+                    token.descripcion()
+                ),
+            ),
+            None => error_sintaxis(
+                self.fin,
+                &format!("se esperaba {}, pero terminó la consulta", esperado),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsear_valores_con_palabras_clave() {
+        //Arrange
+        let consulta = "SELECT NOMBRE FROM WHEREHOUSE WHERE NOMBRE = 'SANDY AND CO' AND ID = -3;";
+
+        //Act
+        let sentencia = parsear(consulta).unwrap();
+
+        //Assert
+        assert_eq!(
+            sentencia,
+            Sentencia::Select {
+                tabla: "WHEREHOUSE".to_string(),
+                seleccion: Seleccion {
+                    selectores: vec![Selector {
+                        expresion: Expresion::Columna("NOMBRE".to_string()),
+                        alias: None,
+                    }],
+                    ..Default::default()
+                },
+                condiciones: vec![
+                    Relacion {
+                        columna: "NOMBRE".to_string(),
+                        token: false,
+                        operador: Operador::Igual,
+                        valor: Termino::Texto("SANDY AND CO".to_string()),
+                    },
+                    Relacion {
+                        columna: "ID".to_string(),
+                        token: false,
+                        operador: Operador::Igual,
+                        valor: Termino::Numero("-3".to_string()),
+                    },
+                ],
+                permitir_filtrado: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parsear_insert_con_valores_sin_comillas() {
+        //Arrange
+        let consulta = "INSERT INTO vuelos.ORIGEN (ORIGEN, FECHA, ESTADO) VALUES (AEP, 2024-11-20, En curso) IF NOT EXISTS";
+
+        //Act
+        let sentencia = parsear(consulta).unwrap();
+
+        //Assert
+        assert_eq!(
+            sentencia,
+            Sentencia::Insert {
+                tabla: "vuelos.ORIGEN".to_string(),
+                columnas: vec![
+                    "ORIGEN".to_string(),
+                    "FECHA".to_string(),
+                    "ESTADO".to_string()
+                ],
+                valores: vec![
+                    Termino::SinComillas("AEP".to_string()),
+                    Termino::SinComillas("2024-11-20".to_string()),
+                    Termino::SinComillas("En curso".to_string()),
+                ],
+                condicion_lwt: Some(CondicionLwt::NoExiste),
+                momento: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parsear_asignaciones_de_colecciones() {
+        //Arrange
+        let consulta = "UPDATE vuelos USING TIMESTAMP 10 SET gates = gates + {'A4'}, \
+                        fuel['tramo'] = 12.5, escalas = ['EZE'] + escalas WHERE id = 1";
+
+        //Act
+        let sentencia = parsear(consulta).unwrap();
+
+        //Assert
+        let asignaciones = sentencia.asignaciones();
+        assert!(matches!(
+            &asignaciones[0].valor,
+            Termino::Agregado(columna, literal)
+                if columna == "gates" && **literal == Termino::Conjunto(vec![Termino::Texto("A4".to_string())])
+        ));
+        assert_eq!(asignaciones[1].to_string(), "fuel['tramo'] = 12.5");
+        assert!(
+            matches!(&asignaciones[2].valor, Termino::Antepuesto(_, columna) if columna == "escalas")
+        );
+        assert_eq!(asignaciones[2].to_string(), "escalas = ['EZE'] + escalas");
+        assert_eq!(sentencia.momento(), Some(10));
+    }
+
+    #[test]
+    fn test_parsear_literales_y_borrado_de_elementos() {
+        //Arrange
+        let consulta = "DELETE fuel['tramo'], gates FROM vuelos WHERE id = 1";
+        let mapa = "INSERT INTO vuelos (id, fuel) VALUES (1, {'a': 1, 'b': -2})";
+
+        //Act
+        let sentencia = parsear(consulta).unwrap();
+        let insert = parsear(mapa).unwrap();
+
+        //Assert
+        let columnas = sentencia.asignaciones();
+        assert_eq!(columnas[0].to_string(), "fuel['tramo'] = null");
+        assert_eq!(columnas[1].to_string(), "gates = null");
+        let Sentencia::Insert { valores, .. } = insert else {
+            panic!("se esperaba un INSERT");
+        };
+        assert_eq!(valores[1].to_string(), "{'a': 1, 'b': -2}");
+        assert!(matches!(&valores[1], Termino::Mapa(pares) if pares.len() == 2));
+    }
+
+    #[test]
+    fn test_parsear_where_con_in_rangos_token_y_contains() {
+        //Arrange
+        let consulta = "SELECT * FROM VUELOS WHERE ORIGEN IN ('AEP', EZE) AND FECHA >= '2024-11-01' AND token(ORIGEN) > -5 AND ESCALAS CONTAINS KEY 'MAD' ALLOW FILTERING";
+
+        //Act
+        let sentencia = parsear(consulta).unwrap();
+
+        //Assert
+        let Sentencia::Select {
+            condiciones,
+            permitir_filtrado,
+            ..
+        } = sentencia
+        else {
+            panic!("Se esperaba un SELECT");
+        };
+        assert!(permitir_filtrado);
+        assert_eq!(
+            condiciones[0].valor,
+            Termino::Tupla(vec![
+                Termino::Texto("AEP".to_string()),
+                Termino::SinComillas("EZE".to_string()),
+            ])
+        );
+        assert_eq!(
+            condiciones
+                .iter()
+                .map(|relacion| relacion.to_string())
+                .collect::<Vec<String>>(),
+            vec![
+                "ORIGEN IN ('AEP', EZE)",
+                "FECHA >= '2024-11-01'",
+                "token(ORIGEN) > -5",
+                "ESCALAS CONTAINS KEY 'MAD'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parsear_select_con_orden_limites_y_selectores() {
+        //Arrange
+        let consulta = "SELECT DISTINCT ORIGEN, WRITETIME(ESTADO) AS ESCRITO, ttl(ESTADO) FROM VUELOS WHERE ORIGEN = AEP ORDER BY ID_VUELO DESC PER PARTITION LIMIT 2 LIMIT 10";
+
+        //Act
+        let sentencia = parsear(consulta).unwrap();
+
+        //Assert
+        let seleccion = sentencia.seleccion().unwrap();
+        assert!(seleccion.distinct);
+        assert_eq!(
+            seleccion.selectores[1].expresion,
+            Expresion::WriteTime("ESTADO".to_string())
+        );
+        assert_eq!(seleccion.selectores[1].nombre(), "ESCRITO");
+        assert_eq!(seleccion.selectores[2].nombre(), "ttl(ESTADO)");
+        assert_eq!(
+            seleccion.orden,
+            vec![("ID_VUELO".to_string(), Orden::Descendente)]
+        );
+        assert_eq!(seleccion.limite_por_particion, Some(2));
+        assert_eq!(seleccion.limite, Some(10));
+        assert!(parsear("SELECT * FROM VUELOS LIMIT -1").is_err());
+    }
+
+    #[test]
+    fn test_parsear_select_con_agregados_y_group_by() {
+        //Arrange
+        let consulta = "SELECT ORIGEN, AVG(COMBUSTIBLE) AS PROMEDIO, COUNT(*) FROM VUELOS WHERE ESTADO = 'En vuelo' GROUP BY ORIGEN ALLOW FILTERING";
+
+        //Act
+        let sentencia = parsear(consulta).unwrap();
+
+        //Assert
+        let seleccion = sentencia.seleccion().unwrap();
+        assert!(seleccion.agrupa());
+        assert_eq!(seleccion.agrupamiento, vec!["ORIGEN".to_string()]);
+        assert_eq!(
+            seleccion.selectores[1].expresion,
+            Expresion::Avg("COMBUSTIBLE".to_string())
+        );
+        assert_eq!(seleccion.selectores[2].nombre(), "count(*)");
+        assert_eq!(
+            seleccion.to_string(),
+            "ORIGEN, avg(COMBUSTIBLE) AS PROMEDIO, count(*)"
+        );
+    }
+
+    #[test]
+    fn test_parsear_create_y_drop_con_if_exists() {
+        //Arrange
+        let crear =
+            "CREATE TABLE IF NOT EXISTS Aerolineas.PILOTOS (ID INT PRIMARY KEY, NOMBRE TEXT)";
+        let eliminar = "DROP KEYSPACE IF EXISTS Aerolineas";
+
+        //Act
+        let sentencia_crear = parsear(crear).unwrap();
+        let sentencia_eliminar = parsear(eliminar).unwrap();
+
+        //Assert
+        assert!(matches!(
+            sentencia_crear,
+            Sentencia::CreateTable {
+                si_no_existe: true,
+                ..
+            }
+        ));
+        assert_eq!(sentencia_crear.tabla(), Some("Aerolineas.PILOTOS"));
+        assert_eq!(
+            sentencia_eliminar,
+            Sentencia::Drop {
+                tipo: TypeCQL::DropKeyspace,
+                nombre: "Aerolineas".to_string(),
+                si_existe: true,
+            }
+        );
+        assert_eq!(sentencia_eliminar.tabla(), None);
+    }
+
+    #[test]
+    fn test_parsear_tipos_definidos_por_el_usuario() {
+        //Arrange
+        let crear = "CREATE TYPE IF NOT EXISTS Aerolineas.POSICION (LAT DOUBLE, LON DOUBLE)";
+        let seleccionar = "SELECT POS.LAT FROM VUELOS WHERE ID = 1";
+        let actualizar = "UPDATE VUELOS SET POS.LAT = 12.5, ESCALA = ('EZE', 2) WHERE ID = 1";
+
+        //Act
+        let sentencia_crear = parsear(crear).unwrap();
+        let sentencia_seleccionar = parsear(seleccionar).unwrap();
+        let sentencia_actualizar = parsear(actualizar).unwrap();
+
+        //Assert
+        assert_eq!(sentencia_crear.udt(), Some("Aerolineas.POSICION"));
+        assert_eq!(sentencia_crear.tipo(), TypeCQL::CreateType);
+        assert_eq!(
+            sentencia_seleccionar.seleccion().unwrap().selectores[0].expresion,
+            Expresion::Campo("POS".to_string(), "LAT".to_string())
+        );
+        let asignaciones = sentencia_actualizar.asignaciones();
+        assert_eq!(asignaciones[0].to_string(), "POS.LAT = 12.5");
+        assert_eq!(asignaciones[1].to_string(), "ESCALA = ('EZE', 2)");
+    }
+
+    #[test]
+    fn test_parsear_vistas_materializadas() {
+        //Arrange
+        let crear = "CREATE MATERIALIZED VIEW VUELOS_DESTINO AS SELECT DESTINO, ID_VUELO, ORIGEN FROM VUELOS_ORIGEN WHERE DESTINO IS NOT NULL AND ID_VUELO IS NOT NULL AND ORIGEN IS NOT NULL PRIMARY KEY ((DESTINO), ID_VUELO, ORIGEN)";
+        let eliminar = "DROP MATERIALIZED VIEW IF EXISTS VUELOS_DESTINO";
+        let sin_no_nula = "CREATE MATERIALIZED VIEW V AS SELECT * FROM T WHERE A IS NOT NULL PRIMARY KEY ((A), B)";
+
+        //Act
+        let sentencia_crear = parsear(crear).unwrap();
+        let sentencia_eliminar = parsear(eliminar).unwrap();
+
+        //Assert
+        assert_eq!(sentencia_crear.tipo(), TypeCQL::CreateView);
+        assert_eq!(sentencia_crear.tabla(), Some("VUELOS_DESTINO"));
+        assert_eq!(sentencia_crear.tabla_base(), Some("VUELOS_ORIGEN"));
+        assert_eq!(sentencia_crear.clustering(), ["ID_VUELO", "ORIGEN"]);
+        assert_eq!(sentencia_eliminar.tipo(), TypeCQL::DropView);
+        assert_eq!(sentencia_eliminar.tabla(), Some("VUELOS_DESTINO"));
+        assert!(parsear(sin_no_nula).is_err());
+    }
+
+    #[test]
+    fn test_parsear_describe() {
+        //Arrange
+        let tabla = "DESCRIBE TABLE Aerolineas.VUELOS_ORIGEN";
+        let keyspaces = "desc keyspaces;";
+
+        //Act
+        let sentencia_tabla = parsear(tabla).unwrap();
+        let sentencia_keyspaces = parsear(keyspaces).unwrap();
+
+        //Assert
+        assert_eq!(sentencia_tabla.tabla(), Some("Aerolineas.VUELOS_ORIGEN"));
+        assert_eq!(
+            sentencia_keyspaces,
+            Sentencia::Describe {
+                descripcion: Descripcion::Keyspaces
+            }
+        );
+        assert!(parsear("DESCRIBE VUELOS").is_err());
+    }
+
+    #[test]
+    fn test_parsear_alter_table() {
+        //Arrange
+        let agregar = "ALTER TABLE PILOTOS ADD (LICENCIA TEXT, HORAS INT)";
+        let renombrar = "ALTER TABLE PILOTOS RENAME ID TO ID_PILOTO AND NOMBRE TO APELLIDO";
+
+        //Act
+        let sentencia_agregar = parsear(agregar).unwrap();
+        let sentencia_renombrar = parsear(renombrar).unwrap();
+
+        //Assert
+        let Sentencia::AlterTable { tabla, cambio } = sentencia_agregar else {
+            panic!("Se esperaba un ALTER TABLE");
+        };
+        assert_eq!(tabla, "PILOTOS");
+        assert_eq!(cambio.to_string(), "ADD (LICENCIA TEXT, HORAS INT)");
+        let Sentencia::AlterTable { cambio, .. } = sentencia_renombrar else {
+            panic!("Se esperaba un ALTER TABLE");
+        };
+        assert_eq!(
+            cambio,
+            CambioTabla::Renombrar(vec![
+                ("ID".to_string(), "ID_PILOTO".to_string()),
+                ("NOMBRE".to_string(), "APELLIDO".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_errores_de_sintaxis() {
+        //Arrange
+        let sin_from = "SELECT * VUELOS";
+        let incompleta = "UPDATE VUELOS SET\n  COMBUSTIBLE =";
+        let valores_de_mas = "INSERT INTO VUELOS (ID) VALUES (1, 2)";
+
+        //Act
+        let error_sin_from = parsear(sin_from).unwrap_err();
+        let error_incompleta = parsear(incompleta).unwrap_err();
+        let error_valores = parsear(valores_de_mas).unwrap_err();
+
+        //Assert
+        assert_eq!(
+            error_sin_from,
+            "Error de sintaxis en la línea 1, columna 10: se esperaba FROM, pero se encontró VUELOS"
+        );
+        assert_eq!(
+            error_incompleta,
+            "Error de sintaxis en la línea 2, columna 16: se esperaba un valor, pero terminó la consulta"
+        );
+        assert_eq!(
+            error_valores,
+            "Error de sintaxis en la línea 1, columna 25: se indicaron 1 columnas y 2 valores"
+        );
+    }
+}
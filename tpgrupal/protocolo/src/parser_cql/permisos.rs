@@ -0,0 +1,194 @@
+use super::consulta::Consulta;
+
+const RECURSO_DATOS: &str = "data";
+
+/// Permisos que se le pueden otorgar a un rol sobre un recurso
+#[derive(Debug, Clone, PartialEq)]
+pub enum Permiso {
+    Select,
+    Modify,
+    Create,
+    Drop,
+    Alter,
+    Authorize,
+}
+
+impl Permiso {
+    pub const TODOS: [Permiso; 6] = [
+        Permiso::Select,
+        Permiso::Modify,
+        Permiso::Create,
+        Permiso::Drop,
+        Permiso::Alter,
+        Permiso::Authorize,
+    ];
+
+    /// Crea el permiso a partir de su nombre en CQL
+    pub fn create(nombre: &str) -> Result<Self, String> {
+        match nombre.trim().to_uppercase().as_str() {
+            "SELECT" => Ok(Permiso::Select),
+            "MODIFY" => Ok(Permiso::Modify),
+            "CREATE" => Ok(Permiso::Create),
+            "DROP" => Ok(Permiso::Drop),
+            "ALTER" => Ok(Permiso::Alter),
+            "AUTHORIZE" => Ok(Permiso::Authorize),
+            _ => Err(format!("Permiso no soportado: {}", nombre)),
+        }
+    }
+
+    /// Devuelve el nombre del permiso en CQL
+    pub fn nombre(&self) -> &str {
+        match self {
+            Permiso::Select => "SELECT",
+            Permiso::Modify => "MODIFY",
+            Permiso::Create => "CREATE",
+            Permiso::Drop => "DROP",
+            Permiso::Alter => "ALTER",
+            Permiso::Authorize => "AUTHORIZE",
+        }
+    }
+}
+
+/// Recurso sobre el que se otorgan permisos. Un permiso sobre un recurso
+/// vale también para los recursos que contiene: todos los keyspaces
+/// contienen a cada keyspace y cada keyspace a sus tablas
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recurso {
+    TodosLosKeyspaces,
+    Keyspace(String),
+    Tabla(String, String), // (keyspace, tabla)
+}
+
+impl Recurso {
+    /// Crea el recurso a partir de su forma en CQL: ALL KEYSPACES, KEYSPACE nombre
+    /// o [TABLE] tabla, donde la tabla puede estar calificada con su keyspace
+    pub fn desde_cql(texto: &str, keyspace_actual: &str) -> Result<Self, String> {
+        let partes = texto.split_whitespace().collect::<Vec<&str>>();
+        match partes[..] {
+            ["ALL", "KEYSPACES"] => Ok(Recurso::TodosLosKeyspaces),
+            ["KEYSPACE", keyspace] => Ok(Recurso::Keyspace(keyspace.to_string())),
+            ["TABLE", tabla] | [tabla] => Ok(Recurso::de_tabla(tabla, keyspace_actual)),
+            _ => Err(format!("Recurso no soportado: {}", texto)),
+        }
+    }
+
+    /// Devuelve el recurso de una tabla, que puede estar calificada con su keyspace
+    pub fn de_tabla(tabla: &str, keyspace_actual: &str) -> Self {
+        match tabla.split_once('.') {
+            Some((keyspace, tabla)) => Recurso::Tabla(keyspace.to_string(), tabla.to_string()),
+            None => Recurso::Tabla(keyspace_actual.to_string(), tabla.to_string()),
+        }
+    }
+
+    /// Devuelve el nombre con el que se guarda el recurso: "data",
+    /// "data/keyspace" o "data/keyspace/tabla"
+    pub fn nombre(&self) -> String {
+        match self {
+            Recurso::TodosLosKeyspaces => RECURSO_DATOS.to_string(),
+            Recurso::Keyspace(keyspace) => format!("{}/{}", RECURSO_DATOS, keyspace),
+            Recurso::Tabla(keyspace, tabla) => format!("{}/{}/{}", RECURSO_DATOS, keyspace, tabla),
+        }
+    }
+
+    /// Crea el recurso a partir del nombre con el que se guarda
+    pub fn desde_nombre(nombre: &str) -> Result<Self, String> {
+        match nombre.split('/').collect::<Vec<&str>>()[..] {
+            [RECURSO_DATOS] => Ok(Recurso::TodosLosKeyspaces),
+            [RECURSO_DATOS, keyspace] => Ok(Recurso::Keyspace(keyspace.to_string())),
+            [RECURSO_DATOS, keyspace, tabla] => {
+                Ok(Recurso::Tabla(keyspace.to_string(), tabla.to_string()))
+            }
+            _ => Err(format!("Recurso inválido: {}", nombre)),
+        }
+    }
+
+    /// Devuelve el recurso junto con los recursos que lo contienen
+    pub fn jerarquia(&self) -> Vec<Recurso> {
+        match self {
+            Recurso::TodosLosKeyspaces => vec![Recurso::TodosLosKeyspaces],
+            Recurso::Keyspace(_) => vec![self.clone(), Recurso::TodosLosKeyspaces],
+            Recurso::Tabla(keyspace, _) => vec![
+                self.clone(),
+                Recurso::Keyspace(keyspace.to_string()),
+                Recurso::TodosLosKeyspaces,
+            ],
+        }
+    }
+}
+
+/// Contenido de una sentencia GRANT, REVOKE o LIST PERMISSIONS: los permisos,
+/// el recurso (opcional en el LIST) y el rol (opcional en el LIST, con OF)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentenciaPermisos {
+    pub permisos: Vec<Permiso>,
+    pub recurso: Option<Recurso>,
+    pub rol: Option<String>,
+}
+
+impl SentenciaPermisos {
+    /// Obtiene la sentencia de la consulta, que tiene el rol como tabla y
+    /// "PERMISO [ON recurso]" como query, donde el permiso puede ser ALL
+    pub fn desde_consulta(consulta: &Consulta, keyspace_actual: &str) -> Result<Self, String> {
+        let (permisos, recurso) = match consulta.get_query().split_once(" ON ") {
+            Some((permisos, recurso)) => (permisos, Some(recurso)),
+            None => (consulta.get_query(), None),
+        };
+        let permisos = match permisos.split_whitespace().collect::<Vec<&str>>()[..] {
+            ["ALL"] | ["ALL", "PERMISSIONS"] => Permiso::TODOS.to_vec(),
+            [permiso] | [permiso, "PERMISSION"] => vec![Permiso::create(permiso)?],
+            _ => return Err(format!("Permisos no soportados: {}", permisos)),
+        };
+        let recurso = match recurso {
+            Some(recurso) => Some(Recurso::desde_cql(recurso, keyspace_actual)?),
+            None => None,
+        };
+        let rol = Some(consulta.get_tabla().to_string()).filter(|rol| !rol.is_empty());
+        Ok(SentenciaPermisos {
+            permisos,
+            recurso,
+            rol,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser_cql::parseo_consulta::procesar_consulta;
+    use crate::parser_cql::type_cql::TypeCQL;
+
+    #[test]
+    fn test_grant_sobre_tabla_sin_keyspace() {
+        //Arrange
+        let query = "GRANT MODIFY ON TABLE VUELOS_ORIGEN TO consola";
+
+        //Act
+        let consulta = procesar_consulta(query).unwrap();
+        let sentencia = SentenciaPermisos::desde_consulta(&consulta, "Aerolineas").unwrap();
+
+        //Assert
+        assert_eq!(consulta.get_type(), &TypeCQL::Grant);
+        assert_eq!(sentencia.permisos, vec![Permiso::Modify]);
+        let recurso = sentencia.recurso.unwrap();
+        assert_eq!(recurso.nombre(), "data/Aerolineas/VUELOS_ORIGEN");
+        assert_eq!(Recurso::desde_nombre(&recurso.nombre()).unwrap(), recurso);
+        assert!(recurso.jerarquia().contains(&Recurso::TodosLosKeyspaces));
+        assert_eq!(sentencia.rol, Some("consola".to_string()));
+    }
+
+    #[test]
+    fn test_grant_sobre_tabla_del_keyspace_de_la_sesion() {
+        //Arrange
+        let query = "GRANT SELECT ON VUELOS_ORIGEN TO consola";
+        let consulta = procesar_consulta(query).unwrap();
+
+        //Act
+        let consulta = consulta.con_keyspace(Some("Vuelos")).unwrap();
+        let keyspace = consulta.get_keyspace().unwrap();
+        let sentencia = SentenciaPermisos::desde_consulta(&consulta, keyspace).unwrap();
+
+        //Assert
+        let recurso = sentencia.recurso.unwrap();
+        assert_eq!(recurso.nombre(), "data/Vuelos/VUELOS_ORIGEN");
+    }
+}
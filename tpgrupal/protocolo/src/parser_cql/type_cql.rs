@@ -8,6 +8,23 @@ pub enum TypeCQL {
     Delete,
     CreateTable,
     CreateKeyspace,
+    AlterTable,
+    AlterKeyspace,
+    DropTable,
+    DropKeyspace,
+    CreateType,
+    DropType,
+    CreateView,
+    DropView,
+    Truncate,
+    Use,
+    Describe,
+    CreateRole,
+    AlterRole,
+    DropRole,
+    Grant,
+    Revoke,
+    ListPermissions,
 }
 
 impl fmt::Display for TypeCQL {
@@ -19,6 +36,23 @@ impl fmt::Display for TypeCQL {
             TypeCQL::Delete => write!(f, "DELETE"),
             TypeCQL::CreateTable => write!(f, "CREATE TABLE"),
             TypeCQL::CreateKeyspace => write!(f, "CREATE KEYSPACE"),
+            TypeCQL::AlterTable => write!(f, "ALTER TABLE"),
+            TypeCQL::AlterKeyspace => write!(f, "ALTER KEYSPACE"),
+            TypeCQL::DropTable => write!(f, "DROP TABLE"),
+            TypeCQL::DropKeyspace => write!(f, "DROP KEYSPACE"),
+            TypeCQL::CreateType => write!(f, "CREATE TYPE"),
+            TypeCQL::DropType => write!(f, "DROP TYPE"),
+            TypeCQL::CreateView => write!(f, "CREATE MATERIALIZED VIEW"),
+            TypeCQL::DropView => write!(f, "DROP MATERIALIZED VIEW"),
+            TypeCQL::Truncate => write!(f, "TRUNCATE"),
+            TypeCQL::Use => write!(f, "USE"),
+            TypeCQL::Describe => write!(f, "DESCRIBE"),
+            TypeCQL::CreateRole => write!(f, "CREATE ROLE"),
+            TypeCQL::AlterRole => write!(f, "ALTER ROLE"),
+            TypeCQL::DropRole => write!(f, "DROP ROLE"),
+            TypeCQL::Grant => write!(f, "GRANT"),
+            TypeCQL::Revoke => write!(f, "REVOKE"),
+            TypeCQL::ListPermissions => write!(f, "LIST PERMISSIONS"),
         }
     }
 }
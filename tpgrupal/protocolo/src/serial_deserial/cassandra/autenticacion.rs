@@ -0,0 +1,93 @@
+use std::env;
+
+pub const PASSWORD_AUTHENTICATOR: &str = "org.apache.cassandra.auth.PasswordAuthenticator";
+pub const VARIABLE_AUTENTICADOR: &str = "AUTENTICADOR";
+pub const VARIABLE_USUARIO: &str = "USUARIO_CASSANDRA";
+pub const VARIABLE_PASSWORD: &str = "PASSWORD_CASSANDRA";
+
+pub const ERROR_BAD_CREDENTIALS: i32 = 0x0100;
+pub const ERROR_UNAUTHORIZED: i32 = 0x2100;
+
+/// Prefijo de los mensajes de error de las consultas que el rol de la sesión no
+/// tiene permiso de ejecutar, con el que el servidor las responde con un ERROR Unauthorized
+pub const NO_AUTORIZADO: &str = "No autorizado";
+
+/// Devuelve el autenticador configurado en la variable de entorno AUTENTICADOR.
+/// Solo se soporta PasswordAuthenticator, si no está definida (o tiene otro
+/// valor, como AllowAllAuthenticator) los clientes no necesitan autenticarse
+pub fn autenticador_configurado() -> Option<&'static str> {
+    let nombre = env::var(VARIABLE_AUTENTICADOR).ok()?;
+    match nombre.trim() {
+        "PasswordAuthenticator" | PASSWORD_AUTHENTICATOR => Some(PASSWORD_AUTHENTICATOR),
+        _ => None,
+    }
+}
+
+/// Usuario y contraseña con los que un cliente se autentica
+#[derive(Debug, Clone, PartialEq)]
+pub struct Credenciales {
+    pub usuario: String,
+    pub password: String,
+}
+
+impl Credenciales {
+    pub fn new(usuario: &str, password: &str) -> Self {
+        Credenciales {
+            usuario: usuario.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    /// Lee las credenciales de las variables de entorno USUARIO_CASSANDRA y
+    /// PASSWORD_CASSANDRA, si alguna no está definida devuelve None
+    pub fn desde_entorno() -> Option<Self> {
+        let usuario = env::var(VARIABLE_USUARIO).ok()?;
+        let password = env::var(VARIABLE_PASSWORD).ok()?;
+        Some(Credenciales::new(&usuario, &password))
+    }
+
+    /// Arma el token SASL PLAIN que se envía en el AUTH_RESPONSE: \0usuario\0password
+    pub fn token_plain(&self) -> Vec<u8> {
+        let mut token = vec![0u8];
+        token.extend(self.usuario.as_bytes());
+        token.push(0);
+        token.extend(self.password.as_bytes());
+        token
+    }
+
+    /// Obtiene las credenciales de un token SASL PLAIN, ignorando la identidad
+    /// de autorización que puede venir antes del primer \0
+    pub fn desde_token_plain(token: &[u8]) -> Result<Self, String> {
+        let partes = token.split(|byte| *byte == 0).collect::<Vec<&[u8]>>();
+        let [_, usuario, password] = partes[..] else {
+            return Err("Token de autenticación mal formado.".to_string());
+        };
+        let usuario = String::from_utf8(usuario.to_vec())
+            .map_err(|_| "El usuario no es un texto válido.".to_string())?;
+        let password = String::from_utf8(password.to_vec())
+            .map_err(|_| "La contraseña no es un texto válido.".to_string())?;
+        Ok(Credenciales { usuario, password })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_plain_ida_y_vuelta() {
+        //Arrange
+        let credenciales = Credenciales::new("cassandra", "una contraseña");
+
+        //Act
+        let token = credenciales.token_plain();
+
+        //Assert
+        assert_eq!(token[0], 0);
+        assert_eq!(
+            Credenciales::desde_token_plain(&token).unwrap(),
+            credenciales
+        );
+        assert!(Credenciales::desde_token_plain(b"sin separadores").is_err());
+    }
+}
@@ -1,9 +1,21 @@
 use std::io::Read;
 
+use super::paginacion::ParametrosQuery;
+use crate::serial_deserial::compresion::{descomprimir_body, Compresion};
+use crate::serial_deserial::lector_bytes::LectorBytes;
+
 const QUERY: i8 = 0x07;
 const STARTUP: i8 = 0x01;
+const REGISTER: i8 = 0x0B;
+const AUTH_RESPONSE: i8 = 0x0F;
+const SUSCRIBIR_CDC: i8 = 0x20;
 
-pub fn deserializar_consulta<T: Read>(mut stream: T) -> Result<(String, u16, i8), String> {
+/// Deserializa una consulta del cliente, si su header tiene el flag de
+/// compresión el body se descomprime con la compresión negociada
+pub fn deserializar_consulta<T: Read>(
+    mut stream: T,
+    compresion: Option<&Compresion>,
+) -> Result<(String, ParametrosQuery, i8), String> {
     let mut header = [0u8; 9];
     stream
         .read_exact(&mut header)
@@ -17,44 +29,65 @@ pub fn deserializar_consulta<T: Read>(mut stream: T) -> Result<(String, u16, i8)
     stream
         .read_exact(&mut body)
         .map_err(|_| "Cantidad incorrecta de bytes en el body del mensaje.".to_string())?;
+    let body = descomprimir_body(header[1], body, compresion)?;
 
-    let (consulta_cql, consistency) = match op_code {
+    let (consulta_cql, parametros) = match op_code {
         QUERY => deserializar_body_query(&body)?,
         STARTUP => deserializar_body_startup(&body)?,
+        REGISTER => deserializar_body_register(&body)?,
+        AUTH_RESPONSE => deserializar_body_auth_response(&body)?,
+        SUSCRIBIR_CDC => deserializar_body_suscribir_cdc(&body)?,
         _ => return Err("Tipo de consulta no soportado.".to_string()),
     };
 
-    Ok((consulta_cql, consistency, op_code))
+    Ok((consulta_cql, parametros, op_code))
 }
 
-fn deserializar_body_query(body: &[u8]) -> Result<(String, u16), String> {
-    let length_query = i32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+fn deserializar_body_query(body: &[u8]) -> Result<(String, ParametrosQuery), String> {
+    let mut lector = LectorBytes::new(body);
+    let query_string = lector.leer_long_string()?;
+    let parametros = ParametrosQuery::deserializar(&mut lector)?;
+    Ok((query_string, parametros))
+}
 
-    let query = &body[4..(length_query as usize + 4)];
+// Las opciones del STARTUP se devuelven con el formato "OPCION - valor, OPCION - valor"
+fn deserializar_body_startup(body: &[u8]) -> Result<(String, ParametrosQuery), String> {
+    let mut lector = LectorBytes::new(body);
+    let cant_opciones = lector.leer_short()?;
+    let mut opciones = vec![];
+    for _ in 0..cant_opciones {
+        let clave = lector.leer_string()?;
+        let valor = lector.leer_string()?;
+        opciones.push(format!("{} - {}", clave, valor));
+    }
+    Ok((opciones.join(", "), ParametrosQuery::new(0x00)))
+}
 
-    let consistency = &body[(length_query as usize + 4)..(length_query as usize + 4 + 2)];
-    let consist = u16::from_be_bytes([consistency[0], consistency[1]]);
-    let query_string = match String::from_utf8(query.to_vec()) {
-        Ok(query) => query,
-        Err(_) => return Err("No se pudo convertir el body a una String válida".to_string()),
-    };
-    Ok((query_string, consist))
+// Los tipos de eventos del REGISTER se devuelven con el formato "TIPO, TIPO"
+fn deserializar_body_register(body: &[u8]) -> Result<(String, ParametrosQuery), String> {
+    let mut lector = LectorBytes::new(body);
+    let cant_tipos = lector.leer_short()?;
+    let mut tipos = vec![];
+    for _ in 0..cant_tipos {
+        tipos.push(lector.leer_string()?);
+    }
+    Ok((tipos.join(", "), ParametrosQuery::new(0x00)))
 }
 
-fn deserializar_body_startup(body: &[u8]) -> Result<(String, u16), String> {
-    let len_cql_version = u16::from_be_bytes([body[2], body[3]]) as usize;
-    let cql_version = &body[4..(len_cql_version + 4)];
-    let cql_version_string = match String::from_utf8(cql_version.to_vec()) {
-        Ok(mensaje) => mensaje,
-        Err(_) => return Err("No se pudo convertir el body a una String válida".to_string()),
-    };
-    let len_version =
-        u16::from_be_bytes([body[4 + len_cql_version], body[4 + len_cql_version + 1]]) as usize;
-    let version = &body[(4 + len_cql_version + 2)..(4 + len_cql_version + 2 + len_version)];
-    let version_string = match String::from_utf8(version.to_vec()) {
-        Ok(mensaje) => mensaje,
-        Err(_) => return Err("No se pudo convertir el body a una String válida".to_string()),
-    };
-    let mensaje = format!("{} - {}", cql_version_string, version_string);
-    Ok((mensaje, 0x00))
+// El token del AUTH_RESPONSE se devuelve tal cual, vacío si el cliente no envió ninguno
+fn deserializar_body_auth_response(body: &[u8]) -> Result<(String, ParametrosQuery), String> {
+    let mut lector = LectorBytes::new(body);
+    let token = lector.leer_bytes()?.unwrap_or_default();
+    let token = String::from_utf8(token.to_vec())
+        .map_err(|_| "El token de autenticación no es un texto válido.".to_string())?;
+    Ok((token, ParametrosQuery::new(0x00)))
+}
+
+// La suscripción al CDC se devuelve con el formato "TABLA, offset", donde
+// un offset negativo indica que solo se quieren los cambios nuevos
+fn deserializar_body_suscribir_cdc(body: &[u8]) -> Result<(String, ParametrosQuery), String> {
+    let mut lector = LectorBytes::new(body);
+    let tabla = lector.leer_string()?;
+    let offset = lector.leer_long()?;
+    Ok((format!("{}, {}", tabla, offset), ParametrosQuery::new(0x00)))
 }
@@ -1,13 +1,95 @@
 use std::io::Read;
 use std::mem::size_of;
+use std::sync::Arc;
 
-const RESULT: i8 = 0x08;
+use super::eventos::{deserializar_schema_change, Evento};
+use super::filas::{Fila, ResultadoFilas};
+use super::metadata_rows::MetadataRows;
+use crate::serial_deserial::compresion::{descomprimir_body, Compresion};
+use crate::serial_deserial::lector_bytes::LectorBytes;
+use crate::serial_deserial::tipo_columna::TipoColumna;
+use crate::serial_deserial::valor_cql::ValorCQL;
+
+const ERROR: i8 = 0x00;
 const READY: i8 = 0x02;
+const AUTHENTICATE: i8 = 0x03;
+const RESULT: i8 = 0x08;
+const EVENT: i8 = 0x0C;
+const AUTH_CHALLENGE: i8 = 0x0E;
+const AUTH_SUCCESS: i8 = 0x10;
 
 const VOID: i32 = 0x0001;
 const ROWS: i32 = 0x0002;
+const SET_KEYSPACE: i32 = 0x0003;
+const SCHEMA_CHANGE: i32 = 0x0005;
+
+/// Deserializa la respuesta del servidor devolviendo cada fila como
+/// un texto con sus valores separados por comas
+pub fn deserializar_respuesta<T: Read>(
+    stream: T,
+    compresion: Option<&Compresion>,
+) -> Result<(Vec<String>, i8), String> {
+    let (resultado, op_code) = deserializar_respuesta_tipada(stream, compresion)?;
+    let lineas = resultado.filas.iter().map(|fila| fila.to_linea()).collect();
+    Ok((lineas, op_code))
+}
+
+/// Deserializa la respuesta del servidor devolviendo las filas con sus
+/// valores tipados según la metadata enviada por el servidor. Si el header
+/// tiene el flag de compresión el body se descomprime con la compresión negociada.
+/// Los mensajes de la autenticación no tienen filas, solo se devuelve su op_code,
+/// y un ERROR se devuelve como Err con el código y el mensaje del servidor
+pub fn deserializar_respuesta_tipada<T: Read>(
+    stream: T,
+    compresion: Option<&Compresion>,
+) -> Result<(ResultadoFilas, i8), String> {
+    let (op_code, body) = leer_frame(stream, compresion)?;
 
-pub fn deserializar_respuesta<T: Read>(mut stream: T) -> Result<(Vec<String>, i8), String> {
+    let res = match op_code {
+        RESULT => {
+            let mut lector = LectorBytes::new(&body);
+            match lector.leer_int()? {
+                VOID => ResultadoFilas::vacio(),
+                ROWS => deserializar_body_rows(&mut lector)?,
+                // El resultado de un USE solo indica el keyspace elegido
+                SET_KEYSPACE => {
+                    lector.leer_string()?;
+                    ResultadoFilas::vacio()
+                }
+                // El resultado de un cambio del schema solo indica qué cambió
+                SCHEMA_CHANGE => {
+                    let cambio = lector.leer_string()?;
+                    deserializar_schema_change(&mut lector, &cambio)?;
+                    ResultadoFilas::vacio()
+                }
+                _ => return Err("Tipo de respuesta no soportada.".to_string()),
+            }
+        }
+        READY | AUTHENTICATE | AUTH_CHALLENGE | AUTH_SUCCESS => ResultadoFilas::vacio(),
+        ERROR => return Err(deserializar_body_error(&body)?),
+        _ => return Err("Tipo de respuesta no soportada.".to_string()),
+    };
+
+    Ok((res, op_code))
+}
+
+/// Deserializa un EVENT enviado por el servidor a un cliente registrado
+pub fn deserializar_evento<T: Read>(
+    stream: T,
+    compresion: Option<&Compresion>,
+) -> Result<Evento, String> {
+    let (op_code, body) = leer_frame(stream, compresion)?;
+    if op_code != EVENT {
+        return Err("Se esperaba un mensaje EVENT.".to_string());
+    }
+    Evento::deserializar(&mut LectorBytes::new(&body))
+}
+
+// Lee un frame completo y devuelve su op_code y su body ya descomprimido
+fn leer_frame<T: Read>(
+    mut stream: T,
+    compresion: Option<&Compresion>,
+) -> Result<(i8, Vec<u8>), String> {
     let mut header = [0u8; 9];
     stream
         .read_exact(&mut header)
@@ -20,33 +102,41 @@ pub fn deserializar_respuesta<T: Read>(mut stream: T) -> Result<(Vec<String>, i8
     stream
         .read_exact(&mut body)
         .map_err(|_| "Cantidad incorrecta de bytes en el body del mensaje.".to_string())?;
-
-    let res: Vec<String> = match op_code {
-        RESULT => {
-            let kind_respuesta = i32::from_be_bytes([body[0], body[1], body[2], body[3]]);
-            match kind_respuesta {
-                VOID => vec![],
-                ROWS => deserializar_body_rows(&body)?,
-                _ => return Err("Tipo de respuesta no soportada.".to_string()),
-            }
-        }
-        READY => vec![],
-        _ => return Err("Tipo de respuesta no soportada.".to_string()),
-    };
-
-    Ok((res, op_code))
+    let body = descomprimir_body(header[1], body, compresion)?;
+    Ok((op_code, body))
 }
 
-fn deserializar_body_rows(body: &[u8]) -> Result<Vec<String>, String> {
-    let column_count = i32::from_be_bytes([body[8], body[9], body[10], body[11]]);
+// <codigo><mensaje>, se devuelve como el texto del error
+fn deserializar_body_error(body: &[u8]) -> Result<String, String> {
+    let mut lector = LectorBytes::new(body);
+    let codigo = lector.leer_int()?;
+    let mensaje = lector.leer_string()?;
+    Ok(format!("Error {:#06x} del servidor: {}", codigo, mensaje))
+}
 
-    // Considero que no fue enviado el <paging_state>, ya que es opcional
-    let rows_count = i32::from_be_bytes([body[12], body[13], body[14], body[15]]);
+fn deserializar_body_rows(lector: &mut LectorBytes) -> Result<ResultadoFilas, String> {
+    let (metadata, column_count) = MetadataRows::deserializar(lector)?;
+    let rows_count = lector.leer_int()?;
 
-    // i = size_of(Kind) + size_of(Flags) + size_of(Rows_count) + size_of(Column_count)
-    let i = size_of::<i32>() + size_of::<i32>() + size_of::<i32>() + size_of::<i32>();
+    // Si el servidor no envió metadata los valores se interpretan como texto
+    let tipos: Vec<TipoColumna> = match metadata.columnas.is_empty() {
+        true => vec![TipoColumna::Text; column_count as usize],
+        false => metadata.columnas.iter().map(|c| c.tipo.clone()).collect(),
+    };
+    let nombres = Arc::new(metadata.nombres_columnas());
 
-    obtener_rows_body(i, rows_count, column_count, body)
+    let mut filas = Vec::with_capacity(rows_count.max(0) as usize);
+    for _ in 0..rows_count {
+        let mut valores = Vec::with_capacity(tipos.len());
+        for tipo in &tipos {
+            valores.push(match lector.leer_bytes()? {
+                Some(bytes) => tipo.deserializar_valor(bytes)?,
+                None => ValorCQL::Nulo,
+            });
+        }
+        filas.push(Fila::new(Arc::clone(&nombres), valores));
+    }
+    Ok(ResultadoFilas { metadata, filas })
 }
 
 pub fn obtener_rows_body(
@@ -104,3 +194,72 @@ fn elementos_sueltos_a_filas_de_elementos(
 
     rows_content
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial_deserial::cassandra::metadata_rows::ColumnaSpec;
+    use crate::serial_deserial::cassandra::serializador_server_cliente::result_to_bytes_server_client;
+    use crate::serial_deserial::compresion::FLAG_COMPRESSION;
+
+    #[test]
+    fn test_deserializar_filas_tipadas_por_nombre_de_columna() {
+        //Arrange
+        let columnas = vec![
+            ColumnaSpec {
+                nombre: "ID_AEROPUERTO".to_string(),
+                tipo: TipoColumna::Int,
+            },
+            ColumnaSpec {
+                nombre: "NOMBRE".to_string(),
+                tipo: TipoColumna::Text,
+            },
+            ColumnaSpec {
+                nombre: "LATITUD".to_string(),
+                tipo: TipoColumna::Float,
+            },
+        ];
+        let metadata = MetadataRows::new("Aerolineas", "AEROPUERTOS", columnas);
+        let frame = result_to_bytes_server_client(
+            vec!["1,AEROPUERTO EZEIZA,-34.8222", "2,AEROPARQUE,"],
+            ROWS,
+            &metadata,
+            None,
+        )
+        .unwrap();
+
+        //Act
+        let (resultado, op_code) = deserializar_respuesta_tipada(&frame[..], None).unwrap();
+
+        //Assert
+        assert_eq!(op_code, RESULT);
+        assert_eq!(resultado.metadata, metadata);
+        assert_eq!(resultado.filas[0].get_i32("ID_AEROPUERTO"), Some(1));
+        assert_eq!(resultado.filas[0].get_string("NOMBRE"), "AEROPUERTO EZEIZA");
+        assert_eq!(resultado.filas[0].get_f32("LATITUD"), Some(-34.8222));
+        assert!(resultado.filas[1].get("LATITUD").unwrap().es_nulo());
+    }
+
+    #[test]
+    fn test_deserializar_pagina_comprimida_con_paging_state() {
+        //Arrange
+        let columnas = vec![ColumnaSpec {
+            nombre: "ORIGEN".to_string(),
+            tipo: TipoColumna::Text,
+        }];
+        let mut metadata = MetadataRows::new("Aerolineas", "VUELOS_ORIGEN", columnas);
+        metadata.paging_state = Some(vec![0, 0, 0, 7]);
+        let compresion = Compresion::Lz4;
+        let frame =
+            result_to_bytes_server_client(vec!["AEP"], ROWS, &metadata, Some(&compresion)).unwrap();
+
+        //Act
+        let (resultado, _) = deserializar_respuesta_tipada(&frame[..], Some(&compresion)).unwrap();
+
+        //Assert
+        assert_eq!(frame[1], FLAG_COMPRESSION);
+        assert!(resultado.has_more_pages());
+        assert_eq!(resultado.metadata.paging_state, Some(vec![0, 0, 0, 7]));
+        assert_eq!(resultado.filas[0].get_string("ORIGEN"), "AEP");
+    }
+}
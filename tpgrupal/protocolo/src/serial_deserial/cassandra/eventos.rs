@@ -0,0 +1,385 @@
+use std::net::{IpAddr, SocketAddr};
+
+use crate::serial_deserial::lector_bytes::{escribir_string, LectorBytes};
+
+/// Tipos de eventos a los que un cliente se puede registrar con REGISTER.
+/// ROW_CHANGE no es parte del protocolo de Cassandra, lo agregamos para
+/// notificar las mutaciones de las filas de las tablas (CDC)
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoEvento {
+    TopologyChange,
+    StatusChange,
+    SchemaChange,
+    RowChange,
+}
+
+impl TipoEvento {
+    /// Crea el tipo de evento a partir de su nombre en el protocolo
+    pub fn create(nombre: &str) -> Result<Self, String> {
+        match nombre.trim() {
+            "TOPOLOGY_CHANGE" => Ok(TipoEvento::TopologyChange),
+            "STATUS_CHANGE" => Ok(TipoEvento::StatusChange),
+            "SCHEMA_CHANGE" => Ok(TipoEvento::SchemaChange),
+            "ROW_CHANGE" => Ok(TipoEvento::RowChange),
+            _ => Err(format!("Tipo de evento no soportado: {}", nombre)),
+        }
+    }
+
+    /// Devuelve el nombre del tipo de evento en el protocolo
+    pub fn nombre(&self) -> &str {
+        match self {
+            TipoEvento::TopologyChange => "TOPOLOGY_CHANGE",
+            TipoEvento::StatusChange => "STATUS_CHANGE",
+            TipoEvento::SchemaChange => "SCHEMA_CHANGE",
+            TipoEvento::RowChange => "ROW_CHANGE",
+        }
+    }
+
+    /// Obtiene los tipos de eventos del mensaje de REGISTER deserializado
+    /// (con el formato "TIPO, TIPO")
+    pub fn desde_register(mensaje: &str) -> Result<Vec<Self>, String> {
+        mensaje
+            .split(", ")
+            .filter(|tipo| !tipo.is_empty())
+            .map(TipoEvento::create)
+            .collect()
+    }
+}
+
+/// Cambio en la topología del cluster
+#[derive(Debug, Clone, PartialEq)]
+pub enum CambioTopologia {
+    NewNode,
+    RemovedNode,
+}
+
+/// Cambio en el estado de un nodo
+#[derive(Debug, Clone, PartialEq)]
+pub enum CambioEstado {
+    Up,
+    Down,
+}
+
+/// Cambio en el schema de la base de datos
+#[derive(Debug, Clone, PartialEq)]
+pub enum CambioSchema {
+    Created,
+    Updated,
+    Dropped,
+}
+
+/// Tipo de mutación que sufrió una fila de una tabla
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoMutacion {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl TipoMutacion {
+    /// Crea el tipo de mutación a partir de su nombre
+    pub fn create(nombre: &str) -> Result<Self, String> {
+        match nombre {
+            "INSERT" => Ok(TipoMutacion::Insert),
+            "UPDATE" => Ok(TipoMutacion::Update),
+            "DELETE" => Ok(TipoMutacion::Delete),
+            _ => Err(format!("Tipo de mutación no soportado: {}", nombre)),
+        }
+    }
+
+    /// Devuelve el nombre del tipo de mutación
+    pub fn nombre(&self) -> &str {
+        match self {
+            TipoMutacion::Insert => "INSERT",
+            TipoMutacion::Update => "UPDATE",
+            TipoMutacion::Delete => "DELETE",
+        }
+    }
+}
+
+/// Mutación de una fila registrada en el log CDC de una tabla, con los valores
+/// de la fila luego del cambio (en un DELETE, los de la fila eliminada). Su
+/// offset es el momento en microsegundos de la escritura, que es el mismo en
+/// todas las réplicas que la aplican
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutacion {
+    pub offset: u64,
+    pub tabla: String,
+    pub tipo: TipoMutacion,
+    pub columnas: Vec<String>,
+    pub valores: Vec<String>,
+}
+
+impl Mutacion {
+    /// Identificador de la mutación en el cluster: las réplicas registran la misma
+    /// escritura con el mismo offset y los mismos valores
+    pub fn id(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.offset,
+            self.tabla,
+            self.tipo.nombre(),
+            self.valores.join(",")
+        )
+    }
+
+    /// Devuelve el valor de la columna pedida, o None si no existe
+    pub fn get(&self, columna: &str) -> Option<&str> {
+        let indice = self.columnas.iter().position(|c| c == columna)?;
+        self.valores.get(indice).map(|v| v.as_str())
+    }
+
+    // <tipo><tabla><offset><columnas><valores>, las columnas y valores como [string list]
+    fn serializar(&self, body: &mut Vec<u8>) {
+        escribir_string(body, self.tipo.nombre());
+        escribir_string(body, &self.tabla);
+        body.extend((self.offset as i64).to_be_bytes());
+        escribir_string_list(body, &self.columnas);
+        escribir_string_list(body, &self.valores);
+    }
+
+    fn deserializar(lector: &mut LectorBytes, tipo: &str) -> Result<Self, String> {
+        Ok(Mutacion {
+            tipo: TipoMutacion::create(tipo)?,
+            tabla: lector.leer_string()?,
+            offset: lector.leer_long()? as u64,
+            columnas: leer_string_list(lector)?,
+            valores: leer_string_list(lector)?,
+        })
+    }
+}
+
+/// Evento que el servidor envía a los clientes registrados, en el stream -1
+#[derive(Debug, Clone, PartialEq)]
+pub enum Evento {
+    TopologyChange {
+        cambio: CambioTopologia,
+        direccion: SocketAddr,
+    },
+    StatusChange {
+        cambio: CambioEstado,
+        direccion: SocketAddr,
+    },
+    SchemaChange {
+        cambio: CambioSchema,
+        keyspace: String,
+        tabla: Option<String>,
+    },
+    RowChange(Mutacion),
+}
+
+impl Evento {
+    /// Devuelve el tipo del evento
+    pub fn tipo(&self) -> TipoEvento {
+        match self {
+            Evento::TopologyChange { .. } => TipoEvento::TopologyChange,
+            Evento::StatusChange { .. } => TipoEvento::StatusChange,
+            Evento::SchemaChange { .. } => TipoEvento::SchemaChange,
+            Evento::RowChange(_) => TipoEvento::RowChange,
+        }
+    }
+
+    /// Serializa el body del EVENT: <tipo><cambio> seguido de la dirección
+    /// del nodo o del keyspace y la tabla afectados según el tipo
+    pub fn serializar(&self) -> Vec<u8> {
+        let mut body = vec![];
+        escribir_string(&mut body, self.tipo().nombre());
+        match self {
+            Evento::TopologyChange { cambio, direccion } => {
+                escribir_string(&mut body, nombre_cambio_topologia(cambio));
+                escribir_inet(&mut body, direccion);
+            }
+            Evento::StatusChange { cambio, direccion } => {
+                escribir_string(&mut body, nombre_cambio_estado(cambio));
+                escribir_inet(&mut body, direccion);
+            }
+            Evento::SchemaChange {
+                cambio,
+                keyspace,
+                tabla,
+            } => serializar_schema_change(&mut body, cambio, keyspace, tabla.as_deref()),
+            Evento::RowChange(mutacion) => mutacion.serializar(&mut body),
+        }
+        body
+    }
+
+    /// Deserializa el body de un EVENT
+    pub fn deserializar(lector: &mut LectorBytes) -> Result<Self, String> {
+        let tipo = TipoEvento::create(&lector.leer_string()?)?;
+        let cambio = lector.leer_string()?;
+        let evento = match tipo {
+            TipoEvento::TopologyChange => Evento::TopologyChange {
+                cambio: crear_cambio_topologia(&cambio)?,
+                direccion: leer_inet(lector)?,
+            },
+            TipoEvento::StatusChange => Evento::StatusChange {
+                cambio: crear_cambio_estado(&cambio)?,
+                direccion: leer_inet(lector)?,
+            },
+            TipoEvento::SchemaChange => deserializar_schema_change(lector, &cambio)?,
+            TipoEvento::RowChange => Evento::RowChange(Mutacion::deserializar(lector, &cambio)?),
+        };
+        Ok(evento)
+    }
+}
+
+fn nombre_cambio_topologia(cambio: &CambioTopologia) -> &str {
+    match cambio {
+        CambioTopologia::NewNode => "NEW_NODE",
+        CambioTopologia::RemovedNode => "REMOVED_NODE",
+    }
+}
+
+fn crear_cambio_topologia(nombre: &str) -> Result<CambioTopologia, String> {
+    match nombre {
+        "NEW_NODE" => Ok(CambioTopologia::NewNode),
+        "REMOVED_NODE" => Ok(CambioTopologia::RemovedNode),
+        _ => Err(format!("Cambio de topología no soportado: {}", nombre)),
+    }
+}
+
+fn nombre_cambio_estado(cambio: &CambioEstado) -> &str {
+    match cambio {
+        CambioEstado::Up => "UP",
+        CambioEstado::Down => "DOWN",
+    }
+}
+
+fn crear_cambio_estado(nombre: &str) -> Result<CambioEstado, String> {
+    match nombre {
+        "UP" => Ok(CambioEstado::Up),
+        "DOWN" => Ok(CambioEstado::Down),
+        _ => Err(format!("Cambio de estado no soportado: {}", nombre)),
+    }
+}
+
+fn nombre_cambio_schema(cambio: &CambioSchema) -> &str {
+    match cambio {
+        CambioSchema::Created => "CREATED",
+        CambioSchema::Updated => "UPDATED",
+        CambioSchema::Dropped => "DROPPED",
+    }
+}
+
+fn crear_cambio_schema(nombre: &str) -> Result<CambioSchema, String> {
+    match nombre {
+        "CREATED" => Ok(CambioSchema::Created),
+        "UPDATED" => Ok(CambioSchema::Updated),
+        "DROPPED" => Ok(CambioSchema::Dropped),
+        _ => Err(format!("Cambio de schema no soportado: {}", nombre)),
+    }
+}
+
+// <cambio><target><keyspace>[<tabla>], el target es KEYSPACE o TABLE
+/// Serializa un cambio del schema: <change_type><target><options>, que comparten el
+/// EVENT y el RESULT de tipo SCHEMA_CHANGE
+pub fn serializar_schema_change(
+    body: &mut Vec<u8>,
+    cambio: &CambioSchema,
+    keyspace: &str,
+    tabla: Option<&str>,
+) {
+    escribir_string(body, nombre_cambio_schema(cambio));
+    match tabla {
+        Some(tabla) => {
+            escribir_string(body, "TABLE");
+            escribir_string(body, keyspace);
+            escribir_string(body, tabla);
+        }
+        None => {
+            escribir_string(body, "KEYSPACE");
+            escribir_string(body, keyspace);
+        }
+    }
+}
+
+/// Deserializa un cambio del schema del que ya se leyó el <change_type>
+pub fn deserializar_schema_change(
+    lector: &mut LectorBytes,
+    cambio: &str,
+) -> Result<Evento, String> {
+    let cambio = crear_cambio_schema(cambio)?;
+    let target = lector.leer_string()?;
+    let keyspace = lector.leer_string()?;
+    let tabla = match target.as_str() {
+        "KEYSPACE" => None,
+        _ => Some(lector.leer_string()?),
+    };
+    Ok(Evento::SchemaChange {
+        cambio,
+        keyspace,
+        tabla,
+    })
+}
+
+fn escribir_string_list(body: &mut Vec<u8>, lista: &[String]) {
+    body.extend((lista.len() as u16).to_be_bytes());
+    for elemento in lista {
+        escribir_string(body, elemento);
+    }
+}
+
+fn leer_string_list(lector: &mut LectorBytes) -> Result<Vec<String>, String> {
+    let largo = lector.leer_short()?;
+    (0..largo).map(|_| lector.leer_string()).collect()
+}
+
+// [inet]: un byte con el largo de la dirección, la dirección y el puerto como [int]
+fn escribir_inet(body: &mut Vec<u8>, direccion: &SocketAddr) {
+    match direccion.ip() {
+        IpAddr::V4(ip) => {
+            body.push(4);
+            body.extend(ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            body.push(16);
+            body.extend(ip.octets());
+        }
+    }
+    body.extend((direccion.port() as i32).to_be_bytes());
+}
+
+fn leer_inet(lector: &mut LectorBytes) -> Result<SocketAddr, String> {
+    let largo = lector.leer_byte()? as usize;
+    let bytes = lector.leer_n(largo)?;
+    let ip = match largo {
+        4 => IpAddr::from(<[u8; 4]>::try_from(bytes).map_err(|_| "Dirección inválida.")?),
+        16 => IpAddr::from(<[u8; 16]>::try_from(bytes).map_err(|_| "Dirección inválida.")?),
+        _ => return Err("Dirección inválida.".to_string()),
+    };
+    let puerto = lector.leer_int()? as u16;
+    Ok(SocketAddr::new(ip, puerto))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializar_y_deserializar_eventos() {
+        let eventos = [
+            Evento::StatusChange {
+                cambio: CambioEstado::Down,
+                direccion: "127.0.0.3:9042".parse().unwrap(),
+            },
+            Evento::SchemaChange {
+                cambio: CambioSchema::Created,
+                keyspace: "Aerolineas".to_string(),
+                tabla: Some("VUELOS_ORIGEN".to_string()),
+            },
+            Evento::RowChange(Mutacion {
+                offset: 42,
+                tabla: "VUELOS_ORIGEN".to_string(),
+                tipo: TipoMutacion::Update,
+                columnas: vec!["ORIGEN".to_string(), "ID_VUELO".to_string()],
+                valores: vec!["AEP".to_string(), "VUE123".to_string()],
+            }),
+        ];
+
+        for evento in eventos {
+            let body = evento.serializar();
+            let deserializado = Evento::deserializar(&mut LectorBytes::new(&body)).unwrap();
+            assert_eq!(deserializado, evento);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use super::metadata_rows::MetadataRows;
+use crate::serial_deserial::valor_cql::ValorCQL;
+
+/// Fila de un resultado con sus valores ya tipados, permite acceder
+/// a cada valor por el nombre de su columna
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fila {
+    columnas: Arc<Vec<String>>,
+    valores: Vec<ValorCQL>,
+}
+
+impl Fila {
+    /// Constructor de una fila, recibe los nombres de las columnas
+    /// (compartidos entre todas las filas del resultado) y sus valores
+    pub fn new(columnas: Arc<Vec<String>>, valores: Vec<ValorCQL>) -> Self {
+        Fila { columnas, valores }
+    }
+
+    /// Devuelve el valor de la columna pedida, o None si no existe
+    pub fn get(&self, columna: &str) -> Option<&ValorCQL> {
+        let indice = self.columnas.iter().position(|c| c == columna)?;
+        self.valores.get(indice)
+    }
+
+    /// Devuelve el valor de la columna como texto, vacío si no existe
+    pub fn get_string(&self, columna: &str) -> String {
+        self.get(columna).map(|v| v.to_string()).unwrap_or_default()
+    }
+
+    /// Devuelve el valor de la columna como entero de 32 bits
+    pub fn get_i32(&self, columna: &str) -> Option<i32> {
+        self.get(columna)?.as_i32()
+    }
+
+    /// Devuelve el valor de la columna como flotante de 32 bits
+    pub fn get_f32(&self, columna: &str) -> Option<f32> {
+        self.get(columna)?.as_f32()
+    }
+
+    /// Devuelve los valores de la fila en el orden de las columnas
+    pub fn valores(&self) -> &[ValorCQL] {
+        &self.valores
+    }
+
+    /// Devuelve la fila con el formato de texto separado por comas
+    /// que se usaba antes de tipar los resultados
+    pub fn to_linea(&self) -> String {
+        let valores: Vec<String> = self.valores.iter().map(|v| v.to_string()).collect();
+        valores.join(",")
+    }
+}
+
+/// Resultado de una consulta de tipo Rows: la metadata de sus
+/// columnas y las filas con los valores tipados
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultadoFilas {
+    pub metadata: MetadataRows,
+    pub filas: Vec<Fila>,
+}
+
+impl ResultadoFilas {
+    /// Resultado sin columnas ni filas, usado para las respuestas VOID y READY
+    pub fn vacio() -> Self {
+        ResultadoFilas {
+            metadata: MetadataRows::new("", "", vec![]),
+            filas: vec![],
+        }
+    }
+
+    /// Indica si el servidor tiene más páginas de este resultado
+    pub fn has_more_pages(&self) -> bool {
+        self.metadata.has_more_pages()
+    }
+}
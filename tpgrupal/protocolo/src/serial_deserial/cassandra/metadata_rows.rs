@@ -0,0 +1,98 @@
+use crate::serial_deserial::lector_bytes::{escribir_bytes, escribir_string, LectorBytes};
+use crate::serial_deserial::tipo_columna::TipoColumna;
+
+const FLAG_GLOBAL_TABLES_SPEC: i32 = 0x0001;
+const FLAG_HAS_MORE_PAGES: i32 = 0x0002;
+const FLAG_NO_METADATA: i32 = 0x0004;
+
+/// Especificación de una columna de un resultado: su nombre y su tipo CQL
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnaSpec {
+    pub nombre: String,
+    pub tipo: TipoColumna,
+}
+
+/// Metadata de un resultado de tipo Rows, como todas las columnas
+/// pertenecen a la misma tabla se envía una única especificación
+/// global de keyspace y tabla. Si el resultado está paginado y quedan
+/// filas por enviar incluye el paging_state para pedir la próxima página
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataRows {
+    pub keyspace: String,
+    pub tabla: String,
+    pub columnas: Vec<ColumnaSpec>,
+    pub paging_state: Option<Vec<u8>>,
+}
+
+impl MetadataRows {
+    /// Constructor de la metadata de un resultado
+    pub fn new(keyspace: &str, tabla: &str, columnas: Vec<ColumnaSpec>) -> Self {
+        MetadataRows {
+            keyspace: keyspace.to_string(),
+            tabla: tabla.to_string(),
+            columnas,
+            paging_state: None,
+        }
+    }
+
+    /// Devuelve los nombres de las columnas en el orden del resultado
+    pub fn nombres_columnas(&self) -> Vec<String> {
+        self.columnas.iter().map(|c| c.nombre.to_string()).collect()
+    }
+
+    /// Indica si quedan páginas del resultado por pedir
+    pub fn has_more_pages(&self) -> bool {
+        self.paging_state.is_some()
+    }
+
+    /// Serializa la metadata:
+    /// <flags><columns_count>[<paging_state>]<global_table_spec><col_spec_i>
+    pub fn serializar(&self) -> Vec<u8> {
+        let mut buffer = vec![];
+        let mut flags = FLAG_GLOBAL_TABLES_SPEC;
+        if self.has_more_pages() {
+            flags |= FLAG_HAS_MORE_PAGES;
+        }
+        buffer.extend(flags.to_be_bytes());
+        buffer.extend((self.columnas.len() as i32).to_be_bytes());
+        if let Some(paging_state) = &self.paging_state {
+            escribir_bytes(&mut buffer, Some(paging_state));
+        }
+        escribir_string(&mut buffer, &self.keyspace);
+        escribir_string(&mut buffer, &self.tabla);
+        for columna in &self.columnas {
+            escribir_string(&mut buffer, &columna.nombre);
+            columna.tipo.serializar_option(&mut buffer);
+        }
+        buffer
+    }
+
+    /// Deserializa la metadata de un resultado de tipo Rows, si el servidor
+    /// indicó que no se envía metadata las columnas quedan vacías
+    pub fn deserializar(lector: &mut LectorBytes) -> Result<(Self, i32), String> {
+        let flags = lector.leer_int()?;
+        let columns_count = lector.leer_int()?;
+        let mut metadata = MetadataRows::new("", "", vec![]);
+        if flags & FLAG_HAS_MORE_PAGES != 0 {
+            metadata.paging_state = lector.leer_bytes()?.map(|bytes| bytes.to_vec());
+        }
+        if flags & FLAG_NO_METADATA != 0 {
+            return Ok((metadata, columns_count));
+        }
+        let global = flags & FLAG_GLOBAL_TABLES_SPEC != 0;
+        if global {
+            metadata.keyspace = lector.leer_string()?;
+            metadata.tabla = lector.leer_string()?;
+        }
+        for _ in 0..columns_count {
+            if !global {
+                metadata.keyspace = lector.leer_string()?;
+                metadata.tabla = lector.leer_string()?;
+            }
+            let nombre = lector.leer_string()?;
+            let tipo = TipoColumna::deserializar_option(lector)?;
+            metadata.columnas.push(ColumnaSpec { nombre, tipo });
+        }
+        Ok((metadata, columns_count))
+    }
+}
@@ -0,0 +1,135 @@
+use crate::serial_deserial::lector_bytes::{escribir_bytes, escribir_string, LectorBytes};
+
+pub const FLAG_PAGE_SIZE: u8 = 0x04;
+pub const FLAG_WITH_PAGING_STATE: u8 = 0x08;
+
+/// Parámetros que acompañan a una consulta QUERY: la consistencia pedida
+/// y, si el cliente quiere recibir el resultado por páginas, el tamaño de
+/// página y el paging_state devuelto por la página anterior
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParametrosQuery {
+    pub consistencia: u16,
+    pub page_size: Option<i32>,
+    pub paging_state: Option<Vec<u8>>,
+}
+
+impl ParametrosQuery {
+    /// Constructor de los parámetros de una consulta sin paginación
+    pub fn new(consistencia: u16) -> Self {
+        ParametrosQuery {
+            consistencia,
+            page_size: None,
+            paging_state: None,
+        }
+    }
+
+    /// Devuelve el byte de flags correspondiente a los parámetros presentes
+    pub fn flags(&self) -> u8 {
+        let mut flags = 0x00;
+        if self.page_size.is_some() {
+            flags |= FLAG_PAGE_SIZE;
+        }
+        if self.paging_state.is_some() {
+            flags |= FLAG_WITH_PAGING_STATE;
+        }
+        flags
+    }
+
+    /// Serializa: <consistency><flags>[<result_page_size>][<paging_state>]
+    pub fn serializar(&self, buffer: &mut Vec<u8>) {
+        buffer.extend(self.consistencia.to_be_bytes());
+        buffer.push(self.flags());
+        if let Some(page_size) = self.page_size {
+            buffer.extend(page_size.to_be_bytes());
+        }
+        if let Some(paging_state) = &self.paging_state {
+            escribir_bytes(buffer, Some(paging_state));
+        }
+    }
+
+    /// Deserializa los parámetros, si el cliente no envió el byte de flags
+    /// se considera que la consulta no es paginada
+    pub fn deserializar(lector: &mut LectorBytes) -> Result<Self, String> {
+        let mut parametros = ParametrosQuery::new(lector.leer_short()?);
+        if !lector.quedan_bytes() {
+            return Ok(parametros);
+        }
+        let flags = lector.leer_byte()?;
+        if flags & FLAG_PAGE_SIZE != 0 {
+            parametros.page_size = Some(lector.leer_int()?);
+        }
+        if flags & FLAG_WITH_PAGING_STATE != 0 {
+            parametros.paging_state = lector.leer_bytes()?.map(|bytes| bytes.to_vec());
+        }
+        Ok(parametros)
+    }
+}
+
+/// Posición desde la cual se retoma la lectura de un resultado paginado:
+/// el token de la partition key de la última fila enviada y la fila en sí,
+/// que desempata entre las filas de una misma partición. El cliente solo
+/// la conoce como un paging_state opaco
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EstadoPaginacion {
+    pub token: u32,
+    pub clustering: String,
+}
+
+impl EstadoPaginacion {
+    /// Serializa la posición como el paging_state que se envía al cliente
+    pub fn serializar(&self) -> Vec<u8> {
+        let mut buffer = vec![];
+        buffer.extend(self.token.to_be_bytes());
+        escribir_string(&mut buffer, &self.clustering);
+        buffer
+    }
+
+    /// Reconstruye la posición a partir del paging_state enviado por el cliente
+    pub fn deserializar(paging_state: &[u8]) -> Result<Self, String> {
+        let mut lector = LectorBytes::new(paging_state);
+        let token = lector.leer_int()? as u32;
+        let clustering = lector.leer_string()?;
+        Ok(EstadoPaginacion { token, clustering })
+    }
+}
+
+/// Página de un recorrido de todas las particiones que se pide a cada nodo: la
+/// cantidad de filas y la posición después de la cual empiezan
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pagina {
+    pub tamanio: usize,
+    pub desde: Option<EstadoPaginacion>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parametros_paginados_ida_y_vuelta() {
+        //Arrange
+        let estado = EstadoPaginacion {
+            token: 123456,
+            clustering: "AEP,2024-11-20,VUE123".to_string(),
+        };
+        let parametros = ParametrosQuery {
+            consistencia: 0x0004,
+            page_size: Some(50),
+            paging_state: Some(estado.serializar()),
+        };
+        let mut buffer = vec![];
+
+        //Act
+        parametros.serializar(&mut buffer);
+        let deserializados = ParametrosQuery::deserializar(&mut LectorBytes::new(&buffer)).unwrap();
+
+        //Assert
+        assert_eq!(buffer[2], FLAG_PAGE_SIZE | FLAG_WITH_PAGING_STATE);
+        assert_eq!(deserializados, parametros);
+        let paging_state = deserializados.paging_state.unwrap();
+        assert_eq!(
+            EstadoPaginacion::deserializar(&paging_state).unwrap(),
+            estado
+        );
+    }
+}
@@ -1,3 +1,7 @@
+use super::eventos::TipoEvento;
+use super::paginacion::ParametrosQuery;
+use crate::serial_deserial::compresion::{comprimir_body, Compresion, OPCION_COMPRESSION};
+use crate::serial_deserial::lector_bytes::{escribir_bytes, escribir_string};
 use crate::serial_deserial::level_consistency::LevelConsistency;
 
 const FLAGS_HEADER_DEFAULT: u8 = 0x00;
@@ -5,23 +9,54 @@ const VERSION_CLIENT: u8 = 0x04;
 
 const QUERY: i8 = 0x07;
 const STARTUP: i8 = 0x01;
+const REGISTER: i8 = 0x0B;
+const AUTH_RESPONSE: i8 = 0x0F;
+const SUSCRIBIR_CDC: i8 = 0x20;
 
 pub fn query_to_bytes_client_server(
     consulta_cql: &str,
     consistencia: LevelConsistency,
     flags_query: u8,
 ) -> Vec<u8> {
-    let stream_id: u16 = 0x00;
-
     let body = serializar_body_query(consulta_cql, consistencia.valor(), flags_query);
+    serializar_frame(VERSION_CLIENT, 0x00, QUERY, body, None)
+}
+
+/// Serializa una consulta pidiendo que el resultado se devuelva por páginas
+/// de `page_size` filas, a partir del paging_state de la página anterior.
+/// Si en el STARTUP de la conexión se negoció una compresión el body viaja comprimido
+pub fn query_paginada_to_bytes_client_server(
+    consulta_cql: &str,
+    consistencia: LevelConsistency,
+    page_size: i32,
+    paging_state: Option<Vec<u8>>,
+    compresion: Option<&Compresion>,
+) -> Vec<u8> {
+    let parametros = ParametrosQuery {
+        consistencia: consistencia.valor(),
+        page_size: Some(page_size),
+        paging_state,
+    };
+    let mut body = vec![];
+    body.extend((consulta_cql.len() as i32).to_be_bytes());
+    body.extend(consulta_cql.as_bytes());
+    parametros.serializar(&mut body);
+    serializar_frame(VERSION_CLIENT, 0x00, QUERY, body, compresion)
+}
+
+/// Arma un frame con su header, comprimiendo el body y marcando el flag de
+/// compresión en el header si hay una compresión negociada
+pub fn serializar_frame(
+    version: u8,
+    stream_id: u16,
+    op_code: i8,
+    body: Vec<u8>,
+    compresion: Option<&Compresion>,
+) -> Vec<u8> {
+    let (flag_compresion, body) = comprimir_body(body, compresion);
+    let flags = FLAGS_HEADER_DEFAULT | flag_compresion;
 
-    let header = serializar_header(
-        VERSION_CLIENT,
-        FLAGS_HEADER_DEFAULT,
-        stream_id,
-        QUERY,
-        body.len() as i32,
-    );
+    let header = serializar_header(version, flags, stream_id, op_code, body.len() as i32);
 
     let mut frame = header;
     frame.extend(body);
@@ -56,36 +91,55 @@ fn serializar_body_query(consulta_cql: &str, consistency: u16, flags_query: u8)
     result
 }
 
-pub fn serializar_startup_client_server() -> Vec<u8> {
-    let stream_id: u16 = 0x00;
+/// Serializa el STARTUP, si se indica una compresión se agrega la opción
+/// COMPRESSION para negociarla. El STARTUP en sí nunca se comprime
+pub fn serializar_startup_client_server(compresion: Option<&Compresion>) -> Vec<u8> {
+    let body = serializar_body_startup(compresion);
+    serializar_frame(VERSION_CLIENT, 0x00, STARTUP, body, None)
+}
 
-    let body = serializar_body_startup();
+/// Serializa un REGISTER con los tipos de eventos que el cliente quiere recibir
+pub fn serializar_register_client_server(tipos: &[TipoEvento]) -> Vec<u8> {
+    let mut body = vec![];
+    body.extend((tipos.len() as u16).to_be_bytes());
+    for tipo in tipos {
+        escribir_string(&mut body, tipo.nombre());
+    }
+    serializar_frame(VERSION_CLIENT, 0x00, REGISTER, body, None)
+}
 
-    let header = serializar_header(
-        VERSION_CLIENT,
-        FLAGS_HEADER_DEFAULT,
-        stream_id,
-        STARTUP,
-        body.len() as i32,
-    );
+/// Serializa el AUTH_RESPONSE con el token de autenticación del cliente, que
+/// viaja comprimido si en el STARTUP se negoció una compresión
+pub fn serializar_auth_response_client_server(
+    token: &[u8],
+    compresion: Option<&Compresion>,
+) -> Vec<u8> {
+    let mut body = vec![];
+    escribir_bytes(&mut body, Some(token));
+    serializar_frame(VERSION_CLIENT, 0x00, AUTH_RESPONSE, body, compresion)
+}
 
-    let mut frame = header;
-    frame.extend(body);
-    frame
+/// Serializa el pedido de suscripción al log CDC de una tabla. No es parte
+/// del protocolo de Cassandra: se usa un opcode propio y el body es
+/// <tabla><offset>, donde sin offset solo se reciben los cambios nuevos
+pub fn serializar_suscribir_cdc_client_server(tabla: &str, desde: Option<u64>) -> Vec<u8> {
+    let mut body = vec![];
+    escribir_string(&mut body, tabla);
+    body.extend(desde.map_or(-1, |offset| offset as i64).to_be_bytes());
+    serializar_frame(VERSION_CLIENT, 0x00, SUSCRIBIR_CDC, body, None)
 }
 
-fn serializar_body_startup() -> Vec<u8> {
-    let cant_opciones: u16 = 0x01;
-    let cql_version = "CQL_VERSION".as_bytes();
-    let len_cql_version = cql_version.len() as u16;
-    let version = "3.0.0".as_bytes();
-    let len_version = version.len() as u16;
+fn serializar_body_startup(compresion: Option<&Compresion>) -> Vec<u8> {
+    let mut opciones = vec![("CQL_VERSION", "3.0.0")];
+    if let Some(compresion) = compresion {
+        opciones.push((OPCION_COMPRESSION, compresion.nombre()));
+    }
     let mut string_map: Vec<u8> = vec![];
-    string_map.extend(cant_opciones.to_be_bytes());
-    string_map.extend(len_cql_version.to_be_bytes());
-    string_map.extend(cql_version);
-    string_map.extend(len_version.to_be_bytes());
-    string_map.extend(version);
+    string_map.extend((opciones.len() as u16).to_be_bytes());
+    for (clave, valor) in opciones {
+        escribir_string(&mut string_map, clave);
+        escribir_string(&mut string_map, valor);
+    }
 
     string_map
 }
@@ -103,7 +157,7 @@ mod tests {
         let len_bytes_query = query.len();
 
         //Act
-        let serializada = query_to_bytes_client_server(query, LevelConsistency::Strong, 0x00);
+        let serializada = query_to_bytes_client_server(query, LevelConsistency::Quorum, 0x00);
 
         //Assert HEADER
         assert!(serializada[0] == VERSION_CLIENT);
@@ -125,7 +179,7 @@ mod tests {
             u16::from_be_bytes([
                 serializada[len_bytes_query + 13],
                 serializada[len_bytes_query + 14]
-            ]) == LevelConsistency::Strong.valor()
+            ]) == LevelConsistency::Quorum.valor()
         );
         assert!(serializada[len_bytes_query + 15] == 0x00);
     }
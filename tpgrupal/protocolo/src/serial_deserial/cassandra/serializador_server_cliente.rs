@@ -1,99 +1,166 @@
-use super::serializador_cliente_server::serializar_header;
+use super::eventos::{serializar_schema_change, CambioSchema, Evento};
+use super::metadata_rows::MetadataRows;
+use super::serializador_cliente_server::serializar_frame;
+use crate::serial_deserial::compresion::Compresion;
+use crate::serial_deserial::lector_bytes::{escribir_bytes, escribir_string};
 
-const FLAGS_HEADER_DEFAULT: u8 = 0x00;
-const FLAGS_METADATA_DEFAULT: i32 = 0x0004;
 const VERSION_SERVER: u8 = 0x84;
 
-const RESULT: i8 = 0x08;
+const ERROR: i8 = 0x00;
 const READY: i8 = 0x02;
+const AUTHENTICATE: i8 = 0x03;
+const RESULT: i8 = 0x08;
+const EVENT: i8 = 0x0C;
+const AUTH_CHALLENGE: i8 = 0x0E;
+const AUTH_SUCCESS: i8 = 0x10;
+
+// Los eventos se envían en el stream -1, reservado para ellos
+const STREAM_EVENTOS: i16 = -1;
 
 const VOID: i32 = 0x0001;
 const ROWS: i32 = 0x0002;
+const SET_KEYSPACE: i32 = 0x0003;
+const SCHEMA_CHANGE: i32 = 0x0005;
 
-pub fn result_to_bytes_server_client(respuesta: Vec<&str>, kind: i32) -> Result<Vec<u8>, String> {
-    let stream_id: u16 = 0x00;
-
-    let body = serializar_body_result(respuesta, kind, FLAGS_METADATA_DEFAULT)?;
-
-    let header = serializar_header(
+pub fn result_to_bytes_server_client(
+    respuesta: Vec<&str>,
+    kind: i32,
+    metadata: &MetadataRows,
+    compresion: Option<&Compresion>,
+) -> Result<Vec<u8>, String> {
+    let body = serializar_body_result(respuesta, kind, metadata)?;
+    Ok(serializar_frame(
         VERSION_SERVER,
-        FLAGS_HEADER_DEFAULT,
-        stream_id,
+        0x00,
         RESULT,
-        body.len() as i32,
-    );
-
-    let mut frame = header;
-    frame.extend(body);
-    Ok(frame)
+        body,
+        compresion,
+    ))
 }
 
 fn serializar_body_result(
     respuesta: Vec<&str>,
     kind: i32,
-    flags_body: i32,
+    metadata: &MetadataRows,
 ) -> Result<Vec<u8>, String> {
     let mut res = vec![];
     res.extend(kind.to_be_bytes());
 
     match kind {
         VOID => res.extend([0u8, 0]),
-        ROWS => res.extend(procesar_rows(respuesta, flags_body)?),
+        ROWS => res.extend(procesar_rows(respuesta, metadata)?),
         _ => res.extend([0u8, 0]),
     };
     Ok(res)
 }
 
-//El formato de la respuesta es: cada linea un renglon y cada renglon separa elementos por comas
-fn procesar_rows(respuesta: Vec<&str>, flags_body: i32) -> Result<Vec<u8>, String> {
-    let mut body: Vec<u8> = vec![];
-    body.extend(flags_body.to_be_bytes());
-
-    if respuesta.is_empty() {
-        body.extend((0_i32).to_be_bytes());
-        body.extend((0_i32).to_be_bytes());
-        return Ok(body);
-    }
-    let row_count = respuesta.len() as i32;
-    let columns_count = respuesta[0].split(",").collect::<Vec<&str>>().len() as i32;
-
-    let mut rows_content: Vec<u8> = Vec::with_capacity((row_count * columns_count) as usize);
-
-    for iter in respuesta.iter().take(row_count as usize) {
-        let mut row_i_bytes: Vec<u8> = vec![];
-        let row_i_datos = iter;
-        let datos = row_i_datos.split(",").collect::<Vec<&str>>();
-        for e in datos {
+//El formato de la respuesta es: cada linea un renglon y cada renglon separa elementos por comas,
+//cada elemento se serializa en el formato binario del tipo de su columna según la metadata.
+//La última columna puede contener comas, como la sentencia CREATE de un DESCRIBE
+fn procesar_rows(respuesta: Vec<&str>, metadata: &MetadataRows) -> Result<Vec<u8>, String> {
+    let mut body: Vec<u8> = metadata.serializar();
+    body.extend((respuesta.len() as i32).to_be_bytes());
+
+    for row_i_datos in respuesta.iter() {
+        let datos = row_i_datos
+            .splitn(metadata.columnas.len().max(1), ",")
+            .collect::<Vec<&str>>();
+        if datos.len() != metadata.columnas.len() {
+            return Err("La fila no coincide con las columnas del resultado.".to_string());
+        }
+        for (e, columna) in datos.iter().zip(metadata.columnas.iter()) {
             if e.is_empty() {
-                row_i_bytes.extend((-1_i32).to_be_bytes());
+                escribir_bytes(&mut body, None);
             } else {
-                row_i_bytes.extend((e.len() as i32).to_be_bytes());
-                row_i_bytes.extend(e.as_bytes());
+                escribir_bytes(&mut body, Some(&columna.tipo.serializar_valor(e)?));
             }
         }
-        rows_content.extend(row_i_bytes);
     }
-
-    body.extend(columns_count.to_be_bytes());
-    body.extend(row_count.to_be_bytes());
-    body.extend(rows_content);
     Ok(body)
 }
 
+/// Serializa el RESULT VOID de una sentencia que se ejecutó sin devolver filas ni
+/// cambiar el schema, como un CREATE ... IF NOT EXISTS de algo que ya existía
+pub fn serializar_void_server_client(compresion: Option<&Compresion>) -> Vec<u8> {
+    let body = VOID.to_be_bytes().to_vec();
+    serializar_frame(VERSION_SERVER, 0x00, RESULT, body, compresion)
+}
+
+/// Serializa el RESULT de un USE, que indica el keyspace que quedó elegido en la sesión
+pub fn serializar_set_keyspace_server_client(
+    keyspace: &str,
+    compresion: Option<&Compresion>,
+) -> Vec<u8> {
+    let mut body = SET_KEYSPACE.to_be_bytes().to_vec();
+    escribir_string(&mut body, keyspace);
+    serializar_frame(VERSION_SERVER, 0x00, RESULT, body, compresion)
+}
+
+/// Serializa el RESULT de una sentencia que cambió el schema, con el cambio, el
+/// keyspace y, si el cambio fue sobre una tabla, la tabla
+pub fn serializar_schema_change_server_client(
+    cambio: &CambioSchema,
+    keyspace: &str,
+    tabla: Option<&str>,
+    compresion: Option<&Compresion>,
+) -> Vec<u8> {
+    let mut body = SCHEMA_CHANGE.to_be_bytes().to_vec();
+    serializar_schema_change(&mut body, cambio, keyspace, tabla);
+    serializar_frame(VERSION_SERVER, 0x00, RESULT, body, compresion)
+}
+
+/// Serializa el READY, que nunca se comprime aunque se haya negociado compresión
 pub fn serializar_ready_server_client() -> Vec<u8> {
-    let stream_id: u16 = 0x00;
+    serializar_frame(VERSION_SERVER, 0x00, READY, vec![], None)
+}
 
-    let body: Vec<u8> = vec![];
+/// Serializa un ERROR con su código y un mensaje que lo describe
+pub fn serializar_error_server_client(
+    codigo: i32,
+    mensaje: &str,
+    compresion: Option<&Compresion>,
+) -> Vec<u8> {
+    let mut body = codigo.to_be_bytes().to_vec();
+    escribir_string(&mut body, mensaje);
+    serializar_frame(VERSION_SERVER, 0x00, ERROR, body, compresion)
+}
 
-    let header = serializar_header(
+/// Serializa el AUTHENTICATE con el que se responde al STARTUP cuando el
+/// servidor requiere que el cliente se autentique con el autenticador indicado
+pub fn serializar_authenticate_server_client(autenticador: &str) -> Vec<u8> {
+    let mut body = vec![];
+    escribir_string(&mut body, autenticador);
+    serializar_frame(VERSION_SERVER, 0x00, AUTHENTICATE, body, None)
+}
+
+/// Serializa un AUTH_CHALLENGE, que le pide al cliente otro AUTH_RESPONSE
+pub fn serializar_auth_challenge_server_client(
+    token: Option<&[u8]>,
+    compresion: Option<&Compresion>,
+) -> Vec<u8> {
+    let mut body = vec![];
+    escribir_bytes(&mut body, token);
+    serializar_frame(VERSION_SERVER, 0x00, AUTH_CHALLENGE, body, compresion)
+}
+
+/// Serializa el AUTH_SUCCESS que indica que el cliente quedó autenticado
+pub fn serializar_auth_success_server_client(compresion: Option<&Compresion>) -> Vec<u8> {
+    let mut body = vec![];
+    escribir_bytes(&mut body, None);
+    serializar_frame(VERSION_SERVER, 0x00, AUTH_SUCCESS, body, compresion)
+}
+
+/// Serializa un EVENT para un cliente registrado, en el stream reservado a los eventos
+pub fn serializar_evento_server_client(
+    evento: &Evento,
+    compresion: Option<&Compresion>,
+) -> Vec<u8> {
+    let stream_id = STREAM_EVENTOS as u16;
+    serializar_frame(
         VERSION_SERVER,
-        FLAGS_HEADER_DEFAULT,
         stream_id,
-        READY,
-        body.len() as i32,
-    );
-
-    let mut frame = header;
-    frame.extend(body);
-    frame
+        EVENT,
+        evento.serializar(),
+        compresion,
+    )
 }
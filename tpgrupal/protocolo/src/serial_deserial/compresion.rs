@@ -0,0 +1,181 @@
+use std::env;
+use std::sync::OnceLock;
+
+pub const FLAG_COMPRESSION: u8 = 0x01;
+pub const OPCION_COMPRESSION: &str = "COMPRESSION";
+pub const VARIABLE_COMPRESION_INTERNODOS: &str = "COMPRESION_INTERNODOS";
+/// Tamaño máximo de un frame, el mismo que usa Cassandra por defecto (256 MB). Un body
+/// comprimido no puede declarar que ocupa más al descomprimirse
+pub const TAMANIO_MAXIMO_FRAME: usize = 256 * 1024 * 1024;
+
+// La variable de entorno se lee una sola vez, con el primer mensaje entre nodos
+static COMPRESION_INTERNODOS: OnceLock<Option<Compresion>> = OnceLock::new();
+
+/// Algoritmos de compresión del body de los frames que se pueden negociar
+/// con la opción COMPRESSION del STARTUP
+#[derive(Debug, Clone, PartialEq)]
+pub enum Compresion {
+    Lz4,
+    Snappy,
+}
+
+impl Compresion {
+    /// Crea el algoritmo a partir de su nombre en la opción COMPRESSION
+    pub fn create(nombre: &str) -> Result<Self, String> {
+        match nombre.trim().to_lowercase().as_str() {
+            "lz4" => Ok(Compresion::Lz4),
+            "snappy" => Ok(Compresion::Snappy),
+            _ => Err(format!("Compresión no soportada: {}", nombre)),
+        }
+    }
+
+    /// Devuelve el nombre del algoritmo tal como se envía en el STARTUP
+    pub fn nombre(&self) -> &str {
+        match self {
+            Compresion::Lz4 => "lz4",
+            Compresion::Snappy => "snappy",
+        }
+    }
+
+    /// Busca la opción COMPRESSION en el mensaje de STARTUP deserializado
+    /// (con el formato "OPCION - valor, OPCION - valor")
+    pub fn desde_startup(mensaje: &str) -> Result<Option<Self>, String> {
+        for opcion in mensaje.split(", ") {
+            if let Some(nombre) = opcion.strip_prefix(&format!("{} - ", OPCION_COMPRESSION)) {
+                return Compresion::create(nombre).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Devuelve la compresión configurada para el tráfico entre nodos en la
+    /// variable de entorno COMPRESION_INTERNODOS, si no está definida ese
+    /// tráfico viaja sin comprimir
+    pub fn internodos() -> Option<Self> {
+        COMPRESION_INTERNODOS
+            .get_or_init(|| {
+                let nombre = env::var(VARIABLE_COMPRESION_INTERNODOS).ok()?;
+                Compresion::create(&nombre).ok()
+            })
+            .clone()
+    }
+
+    /// Comprime un body. En LZ4 se antepone el largo original como un [int],
+    /// tal como lo define el protocolo
+    pub fn comprimir(&self, body: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Compresion::Lz4 => {
+                let mut comprimido = (body.len() as i32).to_be_bytes().to_vec();
+                comprimido.extend(lz4_flex::block::compress(body));
+                Ok(comprimido)
+            }
+            Compresion::Snappy => snap::raw::Encoder::new()
+                .compress_vec(body)
+                .map_err(|e| format!("Error al comprimir con snappy: {}", e)),
+        }
+    }
+
+    /// Descomprime un body comprimido con este algoritmo. Falla si el largo que
+    /// declara el body descomprimido supera el tamaño máximo de un frame, sin
+    /// reservar la memoria
+    pub fn descomprimir(&self, body: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Compresion::Lz4 => {
+                let largo = body
+                    .get(..4)
+                    .ok_or("Cantidad incorrecta de bytes en el body del mensaje.")?;
+                let largo = i32::from_be_bytes([largo[0], largo[1], largo[2], largo[3]]);
+                let largo = validar_largo(usize::try_from(largo).ok())?;
+                lz4_flex::block::decompress(&body[4..], largo)
+                    .map_err(|e| format!("Error al descomprimir con lz4: {}", e))
+            }
+            Compresion::Snappy => {
+                validar_largo(snap::raw::decompress_len(body).ok())?;
+                snap::raw::Decoder::new()
+                    .decompress_vec(body)
+                    .map_err(|e| format!("Error al descomprimir con snappy: {}", e))
+            }
+        }
+    }
+}
+
+// El largo descomprimido que declara un body, si es válido y entra en un frame
+fn validar_largo(largo: Option<usize>) -> Result<usize, String> {
+    match largo {
+        Some(largo) if largo <= TAMANIO_MAXIMO_FRAME => Ok(largo),
+        _ => Err(format!(
+            "El body descomprimido supera el tamaño máximo de un frame ({} bytes).",
+            TAMANIO_MAXIMO_FRAME
+        )),
+    }
+}
+
+/// Comprime el body de un frame si hay una compresión negociada y devuelve
+/// los flags del header que le corresponden. Si no se pudo comprimir el
+/// body se envía tal cual, sin el flag de compresión
+pub fn comprimir_body(body: Vec<u8>, compresion: Option<&Compresion>) -> (u8, Vec<u8>) {
+    match compresion.map(|c| c.comprimir(&body)) {
+        Some(Ok(comprimido)) => (FLAG_COMPRESSION, comprimido),
+        _ => (0x00, body),
+    }
+}
+
+/// Descomprime el body de un frame recibido si su header tiene el flag de
+/// compresión, lo cual solo es válido si antes se negoció un algoritmo
+pub fn descomprimir_body(
+    flags: u8,
+    body: Vec<u8>,
+    compresion: Option<&Compresion>,
+) -> Result<Vec<u8>, String> {
+    if flags & FLAG_COMPRESSION == 0 {
+        return Ok(body);
+    }
+    match compresion {
+        Some(compresion) => compresion.descomprimir(&body),
+        None => Err("Se recibió un body comprimido sin haber negociado compresión.".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comprimir_y_descomprimir_body() {
+        let body = "AEP,2024-11-20,VUE123,EZE,En vuelo,800,11000,-34.5,-58.4,70"
+            .repeat(20)
+            .into_bytes();
+
+        for compresion in [Compresion::Lz4, Compresion::Snappy] {
+            let (flags, comprimido) = comprimir_body(body.clone(), Some(&compresion));
+
+            assert_eq!(flags, FLAG_COMPRESSION);
+            assert!(comprimido.len() < body.len());
+            let descomprimido = descomprimir_body(flags, comprimido, Some(&compresion)).unwrap();
+            assert_eq!(descomprimido, body);
+        }
+    }
+
+    #[test]
+    fn test_largo_descomprimido_mayor_al_maximo_de_un_frame() {
+        let mut enorme = i32::MAX.to_be_bytes().to_vec();
+        enorme.extend([0x10, 0x41]);
+        let mut negativo = (-1i32).to_be_bytes().to_vec();
+        negativo.extend([0x10, 0x41]);
+        // Snappy declara el largo como un varint al principio del body
+        let snappy_enorme = vec![0xff, 0xff, 0xff, 0xff, 0x0f, 0x00];
+
+        assert!(Compresion::Lz4.descomprimir(&enorme).is_err());
+        assert!(Compresion::Lz4.descomprimir(&negativo).is_err());
+        assert!(Compresion::Snappy.descomprimir(&snappy_enorme).is_err());
+    }
+
+    #[test]
+    fn test_body_comprimido_sin_compresion_negociada() {
+        assert!(descomprimir_body(FLAG_COMPRESSION, vec![1, 2, 3], None).is_err());
+        assert_eq!(
+            Compresion::desde_startup("CQL_VERSION - 3.0.0, COMPRESSION - snappy").unwrap(),
+            Some(Compresion::Snappy)
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::Read;
+
+use super::serializador_nodo_envio::{serializar_envio_nodos, serializar_header_nodos};
+use crate::serial_deserial::{
+    cassandra::eventos::{Evento, Mutacion},
+    compresion::{comprimir_body, descomprimir_body, Compresion},
+    lector_bytes::LectorBytes,
+    level_consistency::LevelConsistency,
+};
+
+// Suscripción al log CDC local de otro nodo, que viaja en el byte de responsabilidad
+// de los mensajes entre nodos. La conexión queda abierta y por ella llegan las mutaciones
+pub const SUSCRIBIR_CDC_NODO: u8 = 10;
+
+// Offset que indica que solo se quieren las mutaciones nuevas
+const SIN_OFFSET: &str = "-";
+
+/// Serializa la suscripción al log CDC de la tabla en otro nodo: el offset desde el
+/// cual se quieren las mutaciones del log, si se indica, seguido de la tabla
+pub fn serializar_suscripcion_cdc(tabla: &str, desde: Option<u64>) -> Vec<u8> {
+    let desde = desde.map_or(SIN_OFFSET.to_string(), |offset| offset.to_string());
+    serializar_envio_nodos(
+        &format!("{} {}", desde, tabla),
+        LevelConsistency::One,
+        SUSCRIBIR_CDC_NODO,
+    )
+}
+
+/// Separa la tabla y el offset de una suscripción al log CDC de este nodo
+pub fn separar_suscripcion_cdc(mensaje: &str) -> Result<(&str, Option<u64>), String> {
+    let (desde, tabla) = mensaje
+        .split_once(' ')
+        .ok_or("Suscripción al CDC sin tabla.".to_string())?;
+    let desde = match desde {
+        SIN_OFFSET => None,
+        offset => Some(
+            offset
+                .parse::<u64>()
+                .map_err(|_| format!("Offset de la suscripción al CDC inválido: {}", offset))?,
+        ),
+    };
+    Ok((tabla, desde))
+}
+
+/// Serializa una mutación del log CDC que un nodo le envía al que se suscribió a él,
+/// con el mismo body que el evento ROW_CHANGE
+pub fn serializar_mutacion_cdc(mutacion: &Mutacion) -> Vec<u8> {
+    let body = Evento::RowChange(mutacion.clone()).serializar();
+    let (flag_compresion, body) = comprimir_body(body, Compresion::internodos().as_ref());
+    let mut frame = serializar_header_nodos(flag_compresion, 0x00, body.len() as i32);
+    frame.extend(body);
+    frame
+}
+
+/// Lee la próxima mutación que envía el nodo al que se suscribió al log CDC
+pub fn leer_mutacion_cdc<T: Read>(mut stream: T) -> Result<Mutacion, String> {
+    let mut header = [0u8; 7];
+    stream
+        .read_exact(&mut header)
+        .map_err(|_| "Se cerró la suscripción al CDC del nodo.".to_string())?;
+    let body_len = i32::from_be_bytes([header[3], header[4], header[5], header[6]]) as usize;
+    let mut body = vec![0u8; body_len];
+    stream
+        .read_exact(&mut body)
+        .map_err(|_| "Cantidad incorrecta de bytes en el body del mensaje.".to_string())?;
+    let body = descomprimir_body(header[0], body, Compresion::internodos().as_ref())?;
+    match Evento::deserializar(&mut LectorBytes::new(&body))? {
+        Evento::RowChange(mutacion) => Ok(mutacion),
+        _ => Err("El nodo envió un evento que no es una mutación.".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial_deserial::cassandra::eventos::TipoMutacion;
+    use crate::serial_deserial::intra_nodos::deserializador_nodo_envio::deserializar_envio_nodos;
+
+    #[test]
+    fn test_suscripcion_cdc_ida_y_vuelta() {
+        //Arrange
+        let serializada = serializar_suscripcion_cdc("Aerolineas.VUELOS", Some(42));
+
+        //Act
+        let (mensaje, responsabilidad, _) = deserializar_envio_nodos(&serializada[..]).unwrap();
+        let (tabla, desde) = separar_suscripcion_cdc(&mensaje).unwrap();
+
+        //Assert
+        assert_eq!(responsabilidad, SUSCRIBIR_CDC_NODO);
+        assert_eq!(tabla, "Aerolineas.VUELOS");
+        assert_eq!(desde, Some(42));
+        assert_eq!(
+            separar_suscripcion_cdc("- Aerolineas.VUELOS").unwrap(),
+            ("Aerolineas.VUELOS", None)
+        );
+        assert!(separar_suscripcion_cdc("x Aerolineas.VUELOS").is_err());
+    }
+
+    #[test]
+    fn test_mutaciones_cdc_se_leen_en_orden() {
+        //Arrange
+        let mutaciones: Vec<Mutacion> = (1..=2)
+            .map(|offset| Mutacion {
+                offset,
+                tabla: "Aerolineas.VUELOS".to_string(),
+                tipo: TipoMutacion::Update,
+                columnas: vec!["ID_VUELO".to_string(), "ESTADO".to_string()],
+                valores: vec![offset.to_string(), "En curso".to_string()],
+            })
+            .collect();
+        let stream: Vec<u8> = mutaciones
+            .iter()
+            .flat_map(serializar_mutacion_cdc)
+            .collect();
+        let mut lector = &stream[..];
+
+        //Act
+        let primera = leer_mutacion_cdc(&mut lector).unwrap();
+        let segunda = leer_mutacion_cdc(&mut lector).unwrap();
+        let fin = leer_mutacion_cdc(&mut lector);
+
+        //Assert
+        assert_eq!(vec![primera, segunda], mutaciones);
+        assert!(fin.is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use std::io::Read;
 
+use crate::serial_deserial::compresion::{descomprimir_body, Compresion};
+
 pub fn deserializar_envio_nodos<T: Read>(mut stream: T) -> Result<(String, u8, u16), String> {
     let mut header = [0u8; 7];
     stream
@@ -11,6 +13,7 @@ pub fn deserializar_envio_nodos<T: Read>(mut stream: T) -> Result<(String, u8, u
     stream
         .read_exact(&mut body)
         .map_err(|_| "Cantidad incorrecta de bytes en el body del mensaje.".to_string())?;
+    let body = descomprimir_body(header[0], body, Compresion::internodos().as_ref())?;
 
     let (query, responsabilidad, consistencia) = deserializar_body(&body)?;
     Ok((query, responsabilidad, consistencia))
@@ -2,6 +2,7 @@ use std::io::Read;
 use std::mem::size_of;
 
 use crate::serial_deserial::cassandra::deserializador_server_cliente::obtener_rows_body;
+use crate::serial_deserial::compresion::{descomprimir_body, Compresion};
 
 const VOID: i32 = 0x0001;
 const ROWS: i32 = 0x0002;
@@ -17,6 +18,7 @@ pub fn deserializar_respuesta_nodos<T: Read>(mut stream: T) -> Result<Vec<String
     stream
         .read_exact(&mut body)
         .map_err(|_| "Cantidad incorrecta de bytes en el body del mensaje.".to_string())?;
+    let body = descomprimir_body(header[0], body, Compresion::internodos().as_ref())?;
     let kind_respuesta = i32::from_be_bytes([body[0], body[1], body[2], body[3]]);
     match kind_respuesta {
         VOID => Ok([].to_vec()),
@@ -0,0 +1,113 @@
+use super::serializador_nodo_envio::serializar_envio_nodos;
+use crate::serial_deserial::{
+    cassandra::paginacion::{EstadoPaginacion, Pagina},
+    level_consistency::LevelConsistency,
+};
+
+// Lectura de una página de un recorrido de todas las particiones, que viaja en el
+// byte de responsabilidad de los mensajes entre nodos
+pub const PAGINA: u8 = 9;
+
+// Posición que indica que la página es la primera del recorrido
+const SIN_POSICION: &str = "-";
+
+/// Serializa el pedido de una página de un SELECT: el tamaño, la posición desde la
+/// cual se retoma (su paging_state en hexadecimal) y la consulta
+pub fn serializar_pagina(pagina: &Pagina, consulta_cql: &str) -> Vec<u8> {
+    let desde = match &pagina.desde {
+        Some(desde) => desde
+            .serializar()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+        None => SIN_POSICION.to_string(),
+    };
+    serializar_envio_nodos(
+        &format!("{} {} {}", pagina.tamanio, desde, consulta_cql),
+        LevelConsistency::One,
+        PAGINA,
+    )
+}
+
+/// Separa la página pedida de la consulta de un mensaje de lectura de una página
+pub fn separar_pagina(mensaje: &str) -> Result<(Pagina, &str), String> {
+    let mut partes = mensaje.splitn(3, ' ');
+    let (Some(tamanio), Some(desde), Some(consulta)) =
+        (partes.next(), partes.next(), partes.next())
+    else {
+        return Err("Pedido de página sin consulta.".to_string());
+    };
+    let tamanio = tamanio
+        .parse::<usize>()
+        .map_err(|_| format!("Tamaño de página inválido: {}", tamanio))?;
+    let desde = match desde {
+        SIN_POSICION => None,
+        hexadecimal => Some(EstadoPaginacion::deserializar(&desde_hexadecimal(
+            hexadecimal,
+        )?)?),
+    };
+    Ok((Pagina { tamanio, desde }, consulta))
+}
+
+fn desde_hexadecimal(texto: &str) -> Result<Vec<u8>, String> {
+    (0..texto.len())
+        .step_by(2)
+        .map(|inicio| {
+            texto
+                .get(inicio..inicio + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or(format!("Posición de página inválida: {}", texto))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial_deserial::intra_nodos::deserializador_nodo_envio::deserializar_envio_nodos;
+
+    #[test]
+    fn test_pedido_de_pagina_ida_y_vuelta() {
+        //Arrange
+        let pagina = Pagina {
+            tamanio: 50,
+            desde: Some(EstadoPaginacion {
+                token: 3_000_000_000,
+                clustering: "AEP,'O''Hare',En curso".to_string(),
+            }),
+        };
+        let consulta = "SELECT * FROM Aerolineas.VUELOS WHERE ESTADO = 'En curso' ALLOW FILTERING";
+
+        //Act
+        let serializado = serializar_pagina(&pagina, consulta);
+        let (mensaje, responsabilidad, _) = deserializar_envio_nodos(&serializado[..]).unwrap();
+        let (recibida, consulta_recibida) = separar_pagina(&mensaje).unwrap();
+
+        //Assert
+        assert_eq!(responsabilidad, PAGINA);
+        assert_eq!(recibida, pagina);
+        assert_eq!(consulta_recibida, consulta);
+    }
+
+    #[test]
+    fn test_primera_pagina_y_pedidos_invalidos() {
+        //Arrange
+        let primera = "10 - SELECT * FROM VUELOS";
+
+        //Act
+        let (pagina, consulta) = separar_pagina(primera).unwrap();
+
+        //Assert
+        assert_eq!(
+            pagina,
+            Pagina {
+                tamanio: 10,
+                desde: None
+            }
+        );
+        assert_eq!(consulta, "SELECT * FROM VUELOS");
+        assert!(separar_pagina("10 -").is_err());
+        assert!(separar_pagina("diez - SELECT * FROM VUELOS").is_err());
+        assert!(separar_pagina("10 0g SELECT * FROM VUELOS").is_err());
+    }
+}
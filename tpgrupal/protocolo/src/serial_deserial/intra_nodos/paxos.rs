@@ -0,0 +1,78 @@
+use super::serializador_nodo_envio::serializar_envio_nodos;
+use super::serializador_nodo_respuesta::serializar_respuesta_nodos;
+use crate::serial_deserial::level_consistency::LevelConsistency;
+
+// Fases de Paxos, que viajan en el byte de responsabilidad de los mensajes entre nodos
+pub const PREPARAR: u8 = 3;
+pub const PROPONER: u8 = 4;
+pub const CONFIRMAR: u8 = 5;
+
+pub const PROMESA: &str = "PROMESA";
+pub const ACEPTADA: &str = "ACEPTADA";
+pub const RECHAZO: &str = "RECHAZO";
+
+// Los campos de una respuesta pueden tener comas (filas y consultas), así que
+// viajan en una única fila separados por saltos de línea
+const SEPARADOR_CAMPOS: &str = "\n";
+
+/// Indica si la responsabilidad de un mensaje entre nodos es una fase de Paxos
+pub fn es_fase_paxos(responsabilidad: u8) -> bool {
+    (PREPARAR..=CONFIRMAR).contains(&responsabilidad)
+}
+
+/// Serializa un mensaje de una fase de Paxos: el ballot seguido de la sentencia
+/// (sin la condición IF) que se propone
+pub fn serializar_paxos(fase: u8, ballot: u64, sentencia: &str) -> Vec<u8> {
+    serializar_envio_nodos(
+        &format!("{} {}", ballot, sentencia),
+        LevelConsistency::Serial,
+        fase,
+    )
+}
+
+/// Separa el ballot de la sentencia de un mensaje de una fase de Paxos
+pub fn separar_ballot(mensaje: &str) -> Result<(u64, &str), String> {
+    let (ballot, sentencia) = mensaje
+        .split_once(' ')
+        .ok_or("Mensaje de Paxos sin sentencia.".to_string())?;
+    let ballot = ballot
+        .parse::<u64>()
+        .map_err(|_| format!("Ballot de Paxos inválido: {}", ballot))?;
+    Ok((ballot, sentencia))
+}
+
+/// Serializa la respuesta de una réplica a una fase de Paxos
+pub fn serializar_respuesta_paxos(campos: &[String]) -> Vec<u8> {
+    serializar_respuesta_nodos(vec![campos.join(SEPARADOR_CAMPOS).as_str()], 0x0002)
+}
+
+/// Devuelve los campos de la respuesta de una réplica a una fase de Paxos
+pub fn campos_respuesta_paxos(respuesta: &[String]) -> Vec<String> {
+    respuesta
+        .join(",")
+        .split(SEPARADOR_CAMPOS)
+        .map(|campo| campo.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separar_ballot() {
+        //Arrange
+        let mensaje = "4611686018427387904 UPDATE VUELOS SET COMBUSTIBLE = 50 WHERE ORIGEN = AEP";
+
+        //Act
+        let (ballot, sentencia) = separar_ballot(mensaje).unwrap();
+
+        //Assert
+        assert_eq!(ballot, 1 << 62);
+        assert_eq!(
+            sentencia,
+            "UPDATE VUELOS SET COMBUSTIBLE = 50 WHERE ORIGEN = AEP"
+        );
+        assert!(separar_ballot("INSERT INTO VUELOS").is_err());
+    }
+}
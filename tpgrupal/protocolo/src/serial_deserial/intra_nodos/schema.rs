@@ -0,0 +1,127 @@
+use std::fmt;
+
+use super::serializador_nodo_envio::serializar_envio_nodos;
+use super::serializador_nodo_respuesta::serializar_respuesta_nodos;
+use crate::serial_deserial::level_consistency::LevelConsistency;
+
+// Mutación del schema que el coordinador le envía a los demás nodos para que la apliquen
+pub const MUTACION_SCHEMA: u8 = 7;
+// Pedido del historial de mutaciones del schema de otro nodo, para sincronizarse con él
+pub const PEDIR_SCHEMA: u8 = 8;
+
+const SEPARADOR_MUTACIONES: &str = "\n";
+
+/// Sentencia DDL aplicada en el cluster, identificada por el momento en microsegundos
+/// en que la aplicó su coordinador y la IP de este, que la ordenan entre las demás
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutacionSchema {
+    pub momento: i64,
+    pub coordinador: String,
+    pub sentencia: String,
+}
+
+impl MutacionSchema {
+    pub fn new(momento: i64, coordinador: &str, sentencia: &str) -> Self {
+        MutacionSchema {
+            momento,
+            coordinador: coordinador.to_string(),
+            // Las mutaciones de un historial viajan separadas por saltos de línea
+            sentencia: sentencia.replace(['\n', '\r'], " "),
+        }
+    }
+
+    /// Identificador de la mutación, que es único en el cluster
+    pub fn id(&self) -> (i64, String) {
+        (self.momento, self.coordinador.clone())
+    }
+
+    /// Crea la mutación a partir de su texto: <momento> <coordinador> <sentencia>
+    pub fn desde_texto(texto: &str) -> Result<Self, String> {
+        let mut partes = texto.splitn(3, ' ');
+        let (Some(momento), Some(coordinador), Some(sentencia)) =
+            (partes.next(), partes.next(), partes.next())
+        else {
+            return Err(format!("Mutación del schema inválida: {}", texto));
+        };
+        let momento = momento
+            .parse::<i64>()
+            .map_err(|_| format!("Momento de la mutación del schema inválido: {}", momento))?;
+        Ok(MutacionSchema::new(momento, coordinador, sentencia))
+    }
+}
+
+impl fmt::Display for MutacionSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.momento, self.coordinador, self.sentencia
+        )
+    }
+}
+
+pub fn es_mensaje_schema(responsabilidad: u8) -> bool {
+    responsabilidad == MUTACION_SCHEMA || responsabilidad == PEDIR_SCHEMA
+}
+
+pub fn serializar_mutacion_schema(mutacion: &MutacionSchema) -> Vec<u8> {
+    serializar_envio_nodos(
+        &mutacion.to_string(),
+        LevelConsistency::All,
+        MUTACION_SCHEMA,
+    )
+}
+
+pub fn serializar_pedido_schema() -> Vec<u8> {
+    serializar_envio_nodos("", LevelConsistency::One, PEDIR_SCHEMA)
+}
+
+pub fn serializar_respuesta_schema(mutaciones: &[MutacionSchema]) -> Vec<u8> {
+    let historial = mutaciones
+        .iter()
+        .map(|mutacion| mutacion.to_string())
+        .collect::<Vec<String>>()
+        .join(SEPARADOR_MUTACIONES);
+    serializar_respuesta_nodos(vec![historial.as_str()], 0x0002)
+}
+
+pub fn mutaciones_respuesta_schema(respuesta: &[String]) -> Result<Vec<MutacionSchema>, String> {
+    respuesta
+        .join(",")
+        .split(SEPARADOR_MUTACIONES)
+        .filter(|linea| !linea.is_empty())
+        .map(MutacionSchema::desde_texto)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial_deserial::intra_nodos::deserializador_nodo_respuesta::deserializar_respuesta_nodos;
+
+    #[test]
+    fn test_historial_del_schema_entre_nodos() {
+        //Arrange
+        let mutaciones = vec![
+            MutacionSchema::new(1, "172.18.0.2", "CREATE KEYSPACE K WITH replication = {}"),
+            MutacionSchema::new(
+                2,
+                "172.18.0.3",
+                "CREATE TABLE K.T (A int,\nB text, PRIMARY KEY (A))",
+            ),
+        ];
+
+        //Act
+        let respuesta = serializar_respuesta_schema(&mutaciones);
+        let filas = deserializar_respuesta_nodos(respuesta.as_slice()).unwrap();
+        let recibidas = mutaciones_respuesta_schema(&filas).unwrap();
+
+        //Assert
+        assert_eq!(recibidas, mutaciones);
+        assert_eq!(
+            recibidas[1].sentencia,
+            "CREATE TABLE K.T (A int, B text, PRIMARY KEY (A))"
+        );
+        assert!(MutacionSchema::desde_texto("CREATE TABLE").is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::serial_deserial::compresion::{comprimir_body, Compresion};
 use crate::serial_deserial::level_consistency::LevelConsistency;
 
 const FLAGS_HEADER_DEFAULT: u8 = 0x00;
@@ -10,8 +11,13 @@ pub fn serializar_envio_nodos(
     let stream_id: u16 = 0x00;
 
     let body = serializar_body_query_nodos(consulta_cql, consistencia.valor(), responsabilidad);
+    let (flag_compresion, body) = comprimir_body(body, Compresion::internodos().as_ref());
 
-    let header = serializar_header_nodos(FLAGS_HEADER_DEFAULT, stream_id, body.len() as i32);
+    let header = serializar_header_nodos(
+        FLAGS_HEADER_DEFAULT | flag_compresion,
+        stream_id,
+        body.len() as i32,
+    );
 
     let mut frame = header;
     frame.extend(body);
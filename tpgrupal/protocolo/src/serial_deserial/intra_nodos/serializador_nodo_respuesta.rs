@@ -1,4 +1,5 @@
 use super::serializador_nodo_envio::serializar_header_nodos;
+use crate::serial_deserial::compresion::{comprimir_body, Compresion};
 
 const FLAGS_HEADER_DEFAULT: u8 = 0x00;
 
@@ -9,9 +10,14 @@ pub fn serializar_respuesta_nodos(respuesta: Vec<&str>, kind: i32) -> Vec<u8> {
     let stream_id: u16 = 0x00;
 
     let body = serializar_body_result(respuesta, kind);
+    let (flag_compresion, body) = comprimir_body(body, Compresion::internodos().as_ref());
     let lenght = body.len();
 
-    let header = serializar_header_nodos(FLAGS_HEADER_DEFAULT, stream_id, lenght as i32);
+    let header = serializar_header_nodos(
+        FLAGS_HEADER_DEFAULT | flag_compresion,
+        stream_id,
+        lenght as i32,
+    );
 
     let mut frame = header;
     frame.extend(body);
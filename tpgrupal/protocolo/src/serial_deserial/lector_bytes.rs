@@ -0,0 +1,99 @@
+/// Estructura que recorre un body recibido leyendo los tipos
+/// de datos de la notación del protocolo de Cassandra ([int],
+/// [short], [string], [bytes]) y avanzando su posición
+pub struct LectorBytes<'a> {
+    body: &'a [u8],
+    posicion: usize,
+}
+
+impl<'a> LectorBytes<'a> {
+    /// Constructor de la estructura, recibe el body a recorrer
+    pub fn new(body: &'a [u8]) -> Self {
+        LectorBytes { body, posicion: 0 }
+    }
+
+    /// Devuelve los próximos n bytes del body
+    pub fn leer_n(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let fin = self.posicion + n;
+        if fin > self.body.len() {
+            return Err("Cantidad incorrecta de bytes en el body del mensaje.".to_string());
+        }
+        let bytes = &self.body[self.posicion..fin];
+        self.posicion = fin;
+        Ok(bytes)
+    }
+
+    /// Lee un [byte]
+    pub fn leer_byte(&mut self) -> Result<u8, String> {
+        Ok(self.leer_n(1)?[0])
+    }
+
+    /// Lee un [short] (2 bytes sin signo)
+    pub fn leer_short(&mut self) -> Result<u16, String> {
+        let bytes = self.leer_n(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Lee un [int] (4 bytes con signo)
+    pub fn leer_int(&mut self) -> Result<i32, String> {
+        let bytes = self.leer_n(4)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Lee un [long] (8 bytes con signo)
+    pub fn leer_long(&mut self) -> Result<i64, String> {
+        let bytes = self.leer_n(8)?;
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(bytes);
+        Ok(i64::from_be_bytes(buffer))
+    }
+
+    /// Lee un [string]: un [short] con el largo seguido de los bytes en UTF-8
+    pub fn leer_string(&mut self) -> Result<String, String> {
+        let largo = self.leer_short()? as usize;
+        let bytes = self.leer_n(largo)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| "No se pudo convertir el body a una String válida".to_string())
+    }
+
+    /// Lee un [long string]: un [int] con el largo seguido de los bytes en UTF-8
+    pub fn leer_long_string(&mut self) -> Result<String, String> {
+        let largo = self.leer_int()?.max(0) as usize;
+        let bytes = self.leer_n(largo)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| "No se pudo convertir el body a una String válida".to_string())
+    }
+
+    /// Lee un [bytes]: un [int] con el largo seguido de los bytes, si el
+    /// largo es negativo el valor es nulo y se devuelve None
+    pub fn leer_bytes(&mut self) -> Result<Option<&'a [u8]>, String> {
+        let largo = self.leer_int()?;
+        if largo < 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.leer_n(largo as usize)?))
+    }
+
+    /// Indica si quedan bytes sin leer en el body
+    pub fn quedan_bytes(&self) -> bool {
+        self.posicion < self.body.len()
+    }
+}
+
+/// Agrega al buffer un [string] con el formato del protocolo
+pub fn escribir_string(buffer: &mut Vec<u8>, valor: &str) {
+    buffer.extend((valor.len() as u16).to_be_bytes());
+    buffer.extend(valor.as_bytes());
+}
+
+/// Agrega al buffer un [bytes] con el formato del protocolo, si
+/// el valor es None se escribe el largo -1 que representa un nulo
+pub fn escribir_bytes(buffer: &mut Vec<u8>, valor: Option<&[u8]>) {
+    match valor {
+        Some(bytes) => {
+            buffer.extend((bytes.len() as i32).to_be_bytes());
+            buffer.extend(bytes);
+        }
+        None => buffer.extend((-1_i32).to_be_bytes()),
+    }
+}
@@ -1,21 +1,211 @@
+use std::fmt;
+
+pub const ERROR_SERVIDOR: i32 = 0x0000;
+pub const ERROR_UNAVAILABLE: i32 = 0x1000;
+pub const ERROR_WRITE_TIMEOUT: i32 = 0x1100;
+pub const ERROR_READ_TIMEOUT: i32 = 0x1200;
+pub const ERROR_READ_FAILURE: i32 = 0x1300;
+pub const ERROR_WRITE_FAILURE: i32 = 0x1500;
+pub const ERROR_INVALID: i32 = 0x2200;
+pub const ERROR_PROTOCOLO: i32 = 0x000A;
+
+/// Prefijo de los mensajes de error de las consultas que no se pueden ejecutar porque
+/// no hay suficientes réplicas vivas, con el que el servidor responde un ERROR Unavailable
+pub const NO_DISPONIBLE: &str = "No disponible";
+/// Prefijo de los mensajes de error de las escrituras cuyas réplicas no confirmaron
+/// a tiempo, con el que el servidor responde un ERROR WriteTimeout
+pub const TIEMPO_AGOTADO_ESCRITURA: &str = "Tiempo de escritura agotado";
+/// Prefijo de los mensajes de error de las escrituras que fallaron en demasiadas
+/// réplicas, con el que el servidor responde un ERROR WriteFailure
+pub const ESCRITURA_FALLIDA: &str = "Escritura fallida";
+/// Prefijo de los mensajes de error de las lecturas cuyas réplicas no respondieron
+/// a tiempo, con el que el servidor responde un ERROR ReadTimeout
+pub const TIEMPO_AGOTADO_LECTURA: &str = "Tiempo de lectura agotado";
+/// Prefijo de los mensajes de error de las lecturas que fallaron en demasiadas
+/// réplicas, con el que el servidor responde un ERROR ReadFailure
+pub const LECTURA_FALLIDA: &str = "Lectura fallida";
+/// Prefijo de los mensajes de error de los niveles de consistencia que no se pueden
+/// usar en la operación, con el que el servidor responde un ERROR Invalid
+pub const CONSISTENCIA_INVALIDA: &str = "Consistencia inválida";
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum LevelConsistency {
-    Strong,
-    Weak,
+    Any,
+    One,
+    Two,
+    Three,
+    Quorum,
+    All,
+    LocalQuorum,
+    EachQuorum,
+    Serial,
+    LocalSerial,
+    LocalOne,
 }
 
 impl LevelConsistency {
-    pub fn create(n: u16) -> Self {
+    pub fn create(n: u16) -> Result<Self, String> {
         match n {
-            0x0004 => LevelConsistency::Strong,
-            _ => LevelConsistency::Weak,
+            0x0000 => Ok(LevelConsistency::Any),
+            0x0001 => Ok(LevelConsistency::One),
+            0x0002 => Ok(LevelConsistency::Two),
+            0x0003 => Ok(LevelConsistency::Three),
+            0x0004 => Ok(LevelConsistency::Quorum),
+            0x0005 => Ok(LevelConsistency::All),
+            0x0006 => Ok(LevelConsistency::LocalQuorum),
+            0x0007 => Ok(LevelConsistency::EachQuorum),
+            0x0008 => Ok(LevelConsistency::Serial),
+            0x0009 => Ok(LevelConsistency::LocalSerial),
+            0x000A => Ok(LevelConsistency::LocalOne),
+            _ => Err(format!("Nivel de consistencia desconocido: 0x{:04X}", n)),
         }
     }
 
     pub fn valor(&self) -> u16 {
         match self {
-            LevelConsistency::Strong => 0x0004, //QUORUM
-            LevelConsistency::Weak => 0x0001,   //ONE
+            LevelConsistency::Any => 0x0000,
+            LevelConsistency::One => 0x0001,
+            LevelConsistency::Two => 0x0002,
+            LevelConsistency::Three => 0x0003,
+            LevelConsistency::Quorum => 0x0004,
+            LevelConsistency::All => 0x0005,
+            LevelConsistency::LocalQuorum => 0x0006,
+            LevelConsistency::EachQuorum => 0x0007,
+            LevelConsistency::Serial => 0x0008,
+            LevelConsistency::LocalSerial => 0x0009,
+            LevelConsistency::LocalOne => 0x000A,
+        }
+    }
+
+    /// Cantidad de réplicas (contando al nodo responsable) que tienen que confirmar
+    /// una escritura. ANY se cumple con cualquier réplica, y SERIAL y LOCAL_SERIAL
+    /// no se pueden usar como consistencia de una escritura
+    pub fn requeridas_escritura(&self, factor_replicacion: usize) -> Result<usize, String> {
+        match self {
+            LevelConsistency::Serial | LevelConsistency::LocalSerial => Err(format!(
+                "{}: {} no se puede usar en escrituras.",
+                CONSISTENCIA_INVALIDA, self
+            )),
+            _ => self.requeridas(factor_replicacion),
+        }
+    }
+
+    /// Cantidad de réplicas (contando al nodo responsable) que tienen que responder
+    /// una lectura. ANY no se puede usar en lecturas
+    pub fn requeridas_lectura(&self, factor_replicacion: usize) -> Result<usize, String> {
+        match self {
+            LevelConsistency::Any => Err(format!(
+                "{}: {} no se puede usar en lecturas.",
+                CONSISTENCIA_INVALIDA, self
+            )),
+            _ => self.requeridas(factor_replicacion),
+        }
+    }
+
+    // Hay un solo datacenter, así que los niveles LOCAL_ y EACH_ equivalen a los globales
+    fn requeridas(&self, factor_replicacion: usize) -> Result<usize, String> {
+        let quorum = factor_replicacion / 2 + 1;
+        let requeridas = match self {
+            LevelConsistency::Any | LevelConsistency::One | LevelConsistency::LocalOne => 1,
+            LevelConsistency::Two => 2,
+            LevelConsistency::Three => 3,
+            LevelConsistency::All => factor_replicacion,
+            _ => quorum,
+        };
+        if requeridas > factor_replicacion {
+            return Err(no_disponible(requeridas, factor_replicacion));
         }
+        Ok(requeridas)
+    }
+}
+
+/// Mensaje de error de una consulta que necesita más réplicas de las que hay vivas
+pub fn no_disponible(requeridas: usize, vivas: usize) -> String {
+    format!(
+        "{}: se necesitan {} réplicas y hay {} vivas.",
+        NO_DISPONIBLE, requeridas, vivas
+    )
+}
+
+/// Mensaje de error de una escritura que no recibió a tiempo los ACKs requeridos
+pub fn tiempo_agotado_escritura(recibidos: usize, requeridos: usize) -> String {
+    format!(
+        "{}: se recibieron {} de {} ACKs.",
+        TIEMPO_AGOTADO_ESCRITURA, recibidos, requeridos
+    )
+}
+
+/// Mensaje de error de una escritura que falló en demasiadas réplicas para cumplir el nivel
+pub fn escritura_fallida(recibidos: usize, requeridos: usize) -> String {
+    format!(
+        "{}: se recibieron {} de {} ACKs.",
+        ESCRITURA_FALLIDA, recibidos, requeridos
+    )
+}
+
+/// Mensaje de error de una lectura que no reunió a tiempo las respuestas requeridas
+pub fn tiempo_agotado_lectura(recibidas: usize, requeridas: usize) -> String {
+    format!(
+        "{}: se recibieron {} de {} respuestas.",
+        TIEMPO_AGOTADO_LECTURA, recibidas, requeridas
+    )
+}
+
+/// Mensaje de error de una lectura que falló en demasiadas réplicas para cumplir el nivel
+pub fn lectura_fallida(recibidas: usize, requeridas: usize) -> String {
+    format!(
+        "{}: se recibieron {} de {} respuestas.",
+        LECTURA_FALLIDA, recibidas, requeridas
+    )
+}
+
+impl fmt::Display for LevelConsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelConsistency::Any => write!(f, "ANY"),
+            LevelConsistency::One => write!(f, "ONE"),
+            LevelConsistency::Two => write!(f, "TWO"),
+            LevelConsistency::Three => write!(f, "THREE"),
+            LevelConsistency::Quorum => write!(f, "QUORUM"),
+            LevelConsistency::All => write!(f, "ALL"),
+            LevelConsistency::LocalQuorum => write!(f, "LOCAL_QUORUM"),
+            LevelConsistency::EachQuorum => write!(f, "EACH_QUORUM"),
+            LevelConsistency::Serial => write!(f, "SERIAL"),
+            LevelConsistency::LocalSerial => write!(f, "LOCAL_SERIAL"),
+            LevelConsistency::LocalOne => write!(f, "LOCAL_ONE"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codigo_desconocido_es_error() {
+        //Arrange
+        let codigo = 0x0005;
+        //Act
+        let all = LevelConsistency::create(codigo);
+        let desconocido = LevelConsistency::create(0x00FF);
+        //Assert
+        assert_eq!(all, Ok(LevelConsistency::All));
+        assert!(desconocido.is_err());
+    }
+
+    #[test]
+    fn test_respuestas_requeridas_segun_factor_de_replicacion() {
+        //Arrange
+        let factor_replicacion = 4;
+        //Act
+        let quorum = LevelConsistency::Quorum.requeridas_escritura(factor_replicacion);
+        let all = LevelConsistency::All.requeridas_lectura(factor_replicacion);
+        let three = LevelConsistency::Three.requeridas_lectura(2);
+        //Assert
+        assert_eq!(quorum, Ok(3));
+        assert_eq!(all, Ok(4));
+        assert!(three.is_err_and(|e| e.starts_with(NO_DISPONIBLE)));
+        assert!(LevelConsistency::Any.requeridas_lectura(3).is_err());
+        assert!(LevelConsistency::Serial.requeridas_escritura(3).is_err());
     }
 }
@@ -0,0 +1,725 @@
+use std::fmt;
+use std::net::IpAddr;
+
+use super::lector_bytes::{escribir_bytes, escribir_string, LectorBytes};
+use super::valor_cql::ValorCQL;
+
+/// Separa los elementos de una colección en su celda: [a;b], {a;b} o {clave:valor;...},
+/// y también los de una tupla (a;b) y los campos de un UDT {campo:valor;...}. No puede
+/// ser una coma porque las filas se guardan con los valores separados por comas
+pub const SEPARADOR_ELEMENTOS: char = ';';
+const SEPARADOR_CLAVE_VALOR: char = ':';
+
+/// Enum que contiene los tipos CQL que puede tener una columna
+/// de una tabla, cada uno con su [option] id del protocolo
+#[derive(Debug, Clone, PartialEq)]
+pub enum TipoColumna {
+    Ascii,
+    BigInt,
+    Blob,
+    Boolean,
+    Counter,
+    Double,
+    Float,
+    Int,
+    Timestamp,
+    Uuid,
+    Text,
+    Inet,
+    List(Box<TipoColumna>),
+    Set(Box<TipoColumna>),
+    Map(Box<TipoColumna>, Box<TipoColumna>),
+    Tuple(Vec<TipoColumna>),
+    Udt(TipoUsuario, bool), // El tipo definido con CREATE TYPE y si es frozen
+}
+
+/// Tipo definido por el usuario con CREATE TYPE en un keyspace, con sus campos en el
+/// orden en que se definieron
+#[derive(Debug, Clone, PartialEq)]
+pub struct TipoUsuario {
+    pub keyspace: String,
+    pub nombre: String,
+    pub campos: Vec<(String, TipoColumna)>,
+}
+
+impl TipoUsuario {
+    // El tipo y el valor de cada campo, en el orden de la definición, a partir de los
+    // elementos campo:valor de una celda. Los que no aparecen quedan vacíos, y es None
+    // si algún elemento no es uno de sus campos
+    fn valores_campos<'a>(
+        &'a self,
+        elementos: &[&'a str],
+    ) -> Option<Vec<(&'a TipoColumna, &'a str)>> {
+        let pares = elementos
+            .iter()
+            .map(|elemento| clave_y_valor(elemento))
+            .collect::<Option<Vec<(&str, &str)>>>()?;
+        if pares
+            .iter()
+            .any(|(campo, _)| self.tipo_campo(campo).is_none())
+        {
+            return None;
+        }
+        let valores = self.campos.iter().map(|(campo, tipo)| {
+            let valor = pares.iter().find(|(nombre, _)| nombre == campo);
+            (tipo, valor.map(|(_, valor)| *valor).unwrap_or_default())
+        });
+        Some(valores.collect())
+    }
+
+    /// Tipo del campo, si el UDT lo tiene
+    pub fn tipo_campo(&self, campo: &str) -> Option<&TipoColumna> {
+        self.campos
+            .iter()
+            .find(|(nombre, _)| nombre == campo)
+            .map(|(_, tipo)| tipo)
+    }
+
+    /// Sentencia CREATE TYPE que define el tipo, como la muestra un DESCRIBE
+    pub fn sentencia_create(&self) -> String {
+        let campos = self
+            .campos
+            .iter()
+            .map(|(campo, tipo)| format!("    {} {}", campo, tipo))
+            .collect::<Vec<String>>();
+        format!(
+            "CREATE TYPE {}.{} (\n{}\n);",
+            self.keyspace,
+            self.nombre,
+            campos.join(",\n")
+        )
+    }
+}
+
+impl TipoColumna {
+    /// Crea un tipo a partir de su nombre en una consulta CQL
+    pub fn create(nombre: &str) -> Result<Self, String> {
+        TipoColumna::create_con_udts(nombre, &[])
+    }
+
+    /// Crea un tipo a partir de su nombre en una consulta CQL, que puede ser el de uno
+    /// de los UDT indicados, sin frozen o con frozen<nombre>
+    pub fn create_con_udts(nombre: &str, udts: &[TipoUsuario]) -> Result<Self, String> {
+        let nombre = nombre.trim();
+        if let Some(compuesto) = TipoColumna::create_compuesto(nombre, udts)? {
+            return Ok(compuesto);
+        }
+        if let Some(udt) = udts.iter().find(|udt| udt.nombre == nombre) {
+            return Ok(TipoColumna::Udt(udt.clone(), false));
+        }
+        match nombre.to_uppercase().as_str() {
+            "ASCII" => Ok(TipoColumna::Ascii),
+            "BIGINT" => Ok(TipoColumna::BigInt),
+            "BLOB" => Ok(TipoColumna::Blob),
+            "BOOLEAN" => Ok(TipoColumna::Boolean),
+            "COUNTER" => Ok(TipoColumna::Counter),
+            "DOUBLE" => Ok(TipoColumna::Double),
+            "FLOAT" => Ok(TipoColumna::Float),
+            "INT" => Ok(TipoColumna::Int),
+            "TIMESTAMP" => Ok(TipoColumna::Timestamp),
+            "UUID" => Ok(TipoColumna::Uuid),
+            "TEXT" | "VARCHAR" => Ok(TipoColumna::Text),
+            "INET" => Ok(TipoColumna::Inet),
+            _ => Err(format!("Tipo de columna no soportado: {}", nombre)),
+        }
+    }
+
+    // list<tipo>, set<tipo>, map<tipo, tipo>, tuple<tipo, ...> o frozen<udt>
+    fn create_compuesto(nombre: &str, udts: &[TipoUsuario]) -> Result<Option<Self>, String> {
+        let Some((compuesto, parametros)) = nombre.split_once('<') else {
+            return Ok(None);
+        };
+        let error = || format!("Tipo de columna no soportado: {}", nombre);
+        let parametros = parametros.trim_end().strip_suffix('>').ok_or_else(error)?;
+        let tipos = separar_parametros(parametros)
+            .into_iter()
+            .map(|tipo| TipoColumna::create_con_udts(tipo, udts))
+            .collect::<Result<Vec<TipoColumna>, String>>()?;
+        let compuesto = compuesto.trim().to_uppercase();
+        if compuesto == "FROZEN" {
+            return match tipos.as_slice() {
+                [TipoColumna::Udt(udt, _)] => Ok(Some(TipoColumna::Udt(udt.clone(), true))),
+                [tupla @ TipoColumna::Tuple(_)] => Ok(Some(tupla.clone())),
+                _ => Err(error()),
+            };
+        }
+        if !tipos.iter().all(TipoColumna::es_elemento) {
+            return Err(error());
+        }
+        let tipo = match (compuesto.as_str(), tipos.as_slice()) {
+            ("LIST", [elemento]) => TipoColumna::List(Box::new(elemento.clone())),
+            ("SET", [elemento]) => TipoColumna::Set(Box::new(elemento.clone())),
+            ("MAP", [clave, valor]) => {
+                TipoColumna::Map(Box::new(clave.clone()), Box::new(valor.clone()))
+            }
+            ("TUPLE", [_, ..]) => TipoColumna::Tuple(tipos),
+            _ => return Err(error()),
+        };
+        Ok(Some(tipo))
+    }
+
+    // Los elementos de las colecciones y de las tuplas tienen que ser tipos simples, y
+    // no pueden ser contadores
+    fn es_elemento(&self) -> bool {
+        self.es_simple() && *self != TipoColumna::Counter
+    }
+
+    /// Indica si el tipo es el de una colección: list, set o map
+    pub fn es_coleccion(&self) -> bool {
+        matches!(
+            self,
+            TipoColumna::List(_) | TipoColumna::Set(_) | TipoColumna::Map(_, _)
+        )
+    }
+
+    /// Indica si el tipo es de un único valor: ni una colección, ni una tupla, ni un UDT
+    pub fn es_simple(&self) -> bool {
+        !self.es_coleccion() && !matches!(self, TipoColumna::Tuple(_) | TipoColumna::Udt(..))
+    }
+
+    /// Indica si las escrituras de la columna se mezclan elemento por elemento, como
+    /// en las colecciones y los UDT sin frozen, en vez de reemplazar todo el valor
+    pub fn mezcla_elementos(&self) -> bool {
+        self.es_coleccion() || matches!(self, TipoColumna::Udt(_, false))
+    }
+
+    /// Crea un tipo a partir de su [option] id
+    pub fn from_id(id: u16) -> Result<Self, String> {
+        match id {
+            0x0001 => Ok(TipoColumna::Ascii),
+            0x0002 => Ok(TipoColumna::BigInt),
+            0x0003 => Ok(TipoColumna::Blob),
+            0x0004 => Ok(TipoColumna::Boolean),
+            0x0005 => Ok(TipoColumna::Counter),
+            0x0007 => Ok(TipoColumna::Double),
+            0x0008 => Ok(TipoColumna::Float),
+            0x0009 => Ok(TipoColumna::Int),
+            0x000B => Ok(TipoColumna::Timestamp),
+            0x000C => Ok(TipoColumna::Uuid),
+            0x000D => Ok(TipoColumna::Text),
+            0x0010 => Ok(TipoColumna::Inet),
+            _ => Err(format!("Tipo de columna no soportado: {:#06x}", id)),
+        }
+    }
+
+    /// Devuelve el [option] id del tipo
+    pub fn valor(&self) -> u16 {
+        match self {
+            TipoColumna::Ascii => 0x0001,
+            TipoColumna::BigInt => 0x0002,
+            TipoColumna::Blob => 0x0003,
+            TipoColumna::Boolean => 0x0004,
+            TipoColumna::Counter => 0x0005,
+            TipoColumna::Double => 0x0007,
+            TipoColumna::Float => 0x0008,
+            TipoColumna::Int => 0x0009,
+            TipoColumna::Timestamp => 0x000B,
+            TipoColumna::Uuid => 0x000C,
+            TipoColumna::Text => 0x000D,
+            TipoColumna::Inet => 0x0010,
+            TipoColumna::List(_) => 0x0020,
+            TipoColumna::Map(_, _) => 0x0021,
+            TipoColumna::Set(_) => 0x0022,
+            TipoColumna::Udt(..) => 0x0030,
+            TipoColumna::Tuple(_) => 0x0031,
+        }
+    }
+
+    /// Agrega al buffer el [option] que describe al tipo, seguido de los [option] de
+    /// sus elementos si es una colección o una tupla. El de un UDT sigue con
+    /// <keyspace><nombre><n> y el nombre y el [option] de cada campo
+    pub fn serializar_option(&self, buffer: &mut Vec<u8>) {
+        buffer.extend(self.valor().to_be_bytes());
+        match self {
+            TipoColumna::List(elemento) | TipoColumna::Set(elemento) => {
+                elemento.serializar_option(buffer)
+            }
+            TipoColumna::Map(clave, valor) => {
+                clave.serializar_option(buffer);
+                valor.serializar_option(buffer);
+            }
+            TipoColumna::Tuple(elementos) => {
+                buffer.extend((elementos.len() as u16).to_be_bytes());
+                for elemento in elementos {
+                    elemento.serializar_option(buffer);
+                }
+            }
+            TipoColumna::Udt(udt, _) => {
+                escribir_string(buffer, &udt.keyspace);
+                escribir_string(buffer, &udt.nombre);
+                buffer.extend((udt.campos.len() as u16).to_be_bytes());
+                for (campo, tipo) in &udt.campos {
+                    escribir_string(buffer, campo);
+                    tipo.serializar_option(buffer);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Lee un [option] del body y devuelve el tipo que describe
+    pub fn deserializar_option(lector: &mut LectorBytes) -> Result<Self, String> {
+        let tipo = match lector.leer_short()? {
+            0x0020 => TipoColumna::List(Box::new(TipoColumna::deserializar_option(lector)?)),
+            0x0022 => TipoColumna::Set(Box::new(TipoColumna::deserializar_option(lector)?)),
+            0x0021 => TipoColumna::Map(
+                Box::new(TipoColumna::deserializar_option(lector)?),
+                Box::new(TipoColumna::deserializar_option(lector)?),
+            ),
+            0x0031 => TipoColumna::Tuple(
+                (0..lector.leer_short()?)
+                    .map(|_| TipoColumna::deserializar_option(lector))
+                    .collect::<Result<Vec<TipoColumna>, String>>()?,
+            ),
+            0x0030 => TipoColumna::Udt(TipoUsuario::deserializar(lector)?, true),
+            id => TipoColumna::from_id(id)?,
+        };
+        Ok(tipo)
+    }
+
+    /// Convierte un valor almacenado como texto a su formato binario nativo
+    pub fn serializar_valor(&self, valor: &str) -> Result<Vec<u8>, String> {
+        let valor = valor.trim();
+        let error = || format!("El valor {} no corresponde al tipo {}", valor, self);
+        let bytes = match self {
+            TipoColumna::Ascii | TipoColumna::Text => quitar_comillas(valor).as_bytes().to_vec(),
+            TipoColumna::Tuple(_) | TipoColumna::Udt(..) => self.serializar_campos(valor)?,
+            _ if self.es_coleccion() => self.serializar_coleccion(valor)?,
+            _ => self.serializar_simple(valor).ok_or_else(error)?,
+        };
+        Ok(bytes)
+    }
+
+    // Un valor de un tipo simple que no es texto, o None si no corresponde al tipo
+    fn serializar_simple(&self, valor: &str) -> Option<Vec<u8>> {
+        let bytes = match self {
+            TipoColumna::Int => valor.parse::<i32>().ok()?.to_be_bytes().to_vec(),
+            TipoColumna::BigInt | TipoColumna::Counter | TipoColumna::Timestamp => {
+                valor.parse::<i64>().ok()?.to_be_bytes().to_vec()
+            }
+            TipoColumna::Float => valor.parse::<f32>().ok()?.to_be_bytes().to_vec(),
+            TipoColumna::Double => valor.parse::<f64>().ok()?.to_be_bytes().to_vec(),
+            TipoColumna::Boolean => vec![valor.parse::<bool>().ok()? as u8],
+            TipoColumna::Blob => deserializar_hexa(valor)?,
+            TipoColumna::Uuid => deserializar_uuid(quitar_comillas(valor))?,
+            TipoColumna::Inet => deserializar_inet(quitar_comillas(valor))?,
+            _ => return None,
+        };
+        Some(bytes)
+    }
+
+    // Cada elemento de una tupla o campo de un UDT como [bytes], en orden y sin la
+    // cantidad; los que no tienen valor son nulos
+    fn serializar_campos(&self, valor: &str) -> Result<Vec<u8>, String> {
+        let error = || format!("El valor {} no corresponde al tipo {}", valor, self);
+        let elementos = elementos_coleccion(valor);
+        let valores = match self {
+            TipoColumna::Tuple(tipos) if elementos.len() <= tipos.len() => tipos
+                .iter()
+                .zip(elementos.into_iter().chain(std::iter::repeat("")))
+                .collect::<Vec<(&TipoColumna, &str)>>(),
+            TipoColumna::Udt(udt, _) => udt.valores_campos(&elementos).ok_or_else(error)?,
+            _ => return Err(error()),
+        };
+        let mut buffer = vec![];
+        for (tipo, valor) in valores {
+            match valor.is_empty() {
+                true => escribir_bytes(&mut buffer, None),
+                false => escribir_bytes(&mut buffer, Some(&tipo.serializar_valor(valor)?)),
+            }
+        }
+        Ok(buffer)
+    }
+
+    // <n> seguido de cada elemento como [bytes], o de la clave y el valor en un map
+    fn serializar_coleccion(&self, valor: &str) -> Result<Vec<u8>, String> {
+        let elementos = elementos_coleccion(valor);
+        match self {
+            TipoColumna::Map(tipo_clave, tipo_valor) => {
+                serializar_elementos(elementos.iter().map(|elemento| {
+                    let (clave, valor) = clave_y_valor(elemento).ok_or_else(|| {
+                        format!("El valor {} no corresponde al tipo {}", elemento, self)
+                    })?;
+                    Ok(vec![
+                        tipo_clave.serializar_valor(clave)?,
+                        tipo_valor.serializar_valor(valor)?,
+                    ])
+                }))
+            }
+            TipoColumna::List(elemento) | TipoColumna::Set(elemento) => serializar_elementos(
+                elementos
+                    .iter()
+                    .map(|valor| Ok(vec![elemento.serializar_valor(valor)?])),
+            ),
+            _ => Err(format!("El tipo {} no es una colección", self)),
+        }
+    }
+
+    /// Convierte un valor en formato binario nativo al valor CQL correspondiente
+    pub fn deserializar_valor(&self, bytes: &[u8]) -> Result<ValorCQL, String> {
+        let mut lector = LectorBytes::new(bytes);
+        let valor = match self {
+            TipoColumna::Int => ValorCQL::Int(lector.leer_int()?),
+            TipoColumna::BigInt | TipoColumna::Counter => ValorCQL::BigInt(lector.leer_long()?),
+            TipoColumna::Timestamp => ValorCQL::Timestamp(lector.leer_long()?),
+            TipoColumna::Float => ValorCQL::Float(f32::from_bits(lector.leer_int()? as u32)),
+            TipoColumna::Double => ValorCQL::Double(f64::from_bits(lector.leer_long()? as u64)),
+            TipoColumna::Boolean => ValorCQL::Boolean(lector.leer_byte()? != 0),
+            TipoColumna::Blob => ValorCQL::Blob(bytes.to_vec()),
+            TipoColumna::Ascii | TipoColumna::Text => ValorCQL::Texto(
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|_| "No se pudo convertir un elemento a una String válido.")?,
+            ),
+            TipoColumna::Uuid => valor_uuid(bytes)?,
+            TipoColumna::Inet => valor_inet(bytes)?,
+            TipoColumna::List(elemento) => {
+                ValorCQL::Lista(deserializar_elementos(&mut lector, elemento)?)
+            }
+            TipoColumna::Set(elemento) => {
+                ValorCQL::Conjunto(deserializar_elementos(&mut lector, elemento)?)
+            }
+            TipoColumna::Map(tipo_clave, tipo_valor) => {
+                deserializar_pares(&mut lector, tipo_clave, tipo_valor)?
+            }
+            // Tuple y Udt
+            _ => self.deserializar_campos(&mut lector)?,
+        };
+        Ok(valor)
+    }
+
+    // Los elementos de una tupla o los campos de un UDT. Los últimos pueden faltar, y
+    // entonces son nulos
+    fn deserializar_campos(&self, lector: &mut LectorBytes) -> Result<ValorCQL, String> {
+        let tipos: Vec<&TipoColumna> = match self {
+            TipoColumna::Tuple(tipos) => tipos.iter().collect(),
+            TipoColumna::Udt(udt, _) => udt.campos.iter().map(|(_, tipo)| tipo).collect(),
+            _ => return Err(format!("El tipo {} no es una tupla ni un UDT", self)),
+        };
+        let mut valores = vec![];
+        for tipo in tipos {
+            valores.push(match lector.quedan_bytes() {
+                true => deserializar_elemento(lector, tipo)?,
+                false => ValorCQL::Nulo,
+            });
+        }
+        match self {
+            TipoColumna::Udt(udt, _) => {
+                let campos = udt.campos.iter().map(|(campo, _)| campo.to_string());
+                Ok(ValorCQL::Udt(campos.zip(valores).collect()))
+            }
+            _ => Ok(ValorCQL::Tupla(valores)),
+        }
+    }
+}
+
+impl TipoUsuario {
+    // <keyspace><nombre><n><campo 1><option 1>...<campo n><option n>
+    fn deserializar(lector: &mut LectorBytes) -> Result<Self, String> {
+        let keyspace = lector.leer_string()?;
+        let nombre = lector.leer_string()?;
+        let mut campos = vec![];
+        for _ in 0..lector.leer_short()? {
+            let campo = lector.leer_string()?;
+            campos.push((campo, TipoColumna::deserializar_option(lector)?));
+        }
+        Ok(TipoUsuario {
+            keyspace,
+            nombre,
+            campos,
+        })
+    }
+}
+
+// <n><elemento 1>...<elemento n>, donde cada elemento es un [bytes] y los de un map
+// son la clave y el valor de cada par
+fn serializar_elementos(
+    elementos: impl Iterator<Item = Result<Vec<Vec<u8>>, String>>,
+) -> Result<Vec<u8>, String> {
+    let (mut cantidad, mut cuerpo) = (0i32, vec![]);
+    for bytes in elementos {
+        for bytes in bytes? {
+            escribir_bytes(&mut cuerpo, Some(&bytes));
+        }
+        cantidad += 1;
+    }
+    let mut buffer = cantidad.to_be_bytes().to_vec();
+    buffer.extend(cuerpo);
+    Ok(buffer)
+}
+
+fn deserializar_elementos(
+    lector: &mut LectorBytes,
+    tipo: &TipoColumna,
+) -> Result<Vec<ValorCQL>, String> {
+    (0..lector.leer_int()?)
+        .map(|_| deserializar_elemento(lector, tipo))
+        .collect()
+}
+
+// <n> seguido de la clave y el valor de cada par de un map, cada uno como [bytes]
+fn deserializar_pares(
+    lector: &mut LectorBytes,
+    tipo_clave: &TipoColumna,
+    tipo_valor: &TipoColumna,
+) -> Result<ValorCQL, String> {
+    let mut pares = vec![];
+    for _ in 0..lector.leer_int()? {
+        let clave = deserializar_elemento(lector, tipo_clave)?;
+        pares.push((clave, deserializar_elemento(lector, tipo_valor)?));
+    }
+    Ok(ValorCQL::Mapa(pares))
+}
+
+fn deserializar_elemento(lector: &mut LectorBytes, tipo: &TipoColumna) -> Result<ValorCQL, String> {
+    match lector.leer_bytes()? {
+        Some(bytes) => tipo.deserializar_valor(bytes),
+        None => Ok(ValorCQL::Nulo),
+    }
+}
+
+/// Elementos de una colección tal como se guarda en su celda: [a;b], {a;b} o
+/// {clave:valor;...}, o de una tupla (a;b). Un valor que no tiene esa forma es su
+/// único elemento, y una celda vacía no tiene ninguno
+pub fn elementos_coleccion(valor: &str) -> Vec<&str> {
+    let valor = valor.trim();
+    if valor.is_empty() {
+        return vec![];
+    }
+    match valor
+        .strip_prefix(['[', '{', '('])
+        .and_then(|v| v.strip_suffix([']', '}', ')']))
+    {
+        Some(elementos) if elementos.trim().is_empty() => vec![],
+        Some(elementos) => separar_fuera_de_comillas(elementos, SEPARADOR_ELEMENTOS)
+            .into_iter()
+            .map(str::trim)
+            .collect(),
+        None => vec![valor],
+    }
+}
+
+/// Separa la clave y el valor de un elemento de un map, "clave:valor"
+pub fn clave_y_valor(elemento: &str) -> Option<(&str, &str)> {
+    let clave = *separar_fuera_de_comillas(elemento, SEPARADOR_CLAVE_VALOR).first()?;
+    let valor = elemento.get(clave.len() + 1..)?;
+    Some((clave.trim(), valor.trim()))
+}
+
+/// Separa los parámetros de un tipo en las comas, salvo las de los parámetros de otro
+/// tipo, como en map<text, float> o frozen<tuple<int, int>>
+pub fn separar_parametros(texto: &str) -> Vec<&str> {
+    let (mut parametros, mut inicio, mut profundidad) = (vec![], 0, 0);
+    for (indice, caracter) in texto.char_indices() {
+        match caracter {
+            '<' => profundidad += 1,
+            '>' => profundidad -= 1,
+            ',' if profundidad == 0 => {
+                parametros.push(&texto[inicio..indice]);
+                inicio = indice + 1;
+            }
+            _ => {}
+        }
+    }
+    parametros.push(&texto[inicio..]);
+    parametros
+}
+
+// Separa el texto en el separador, salvo dentro de los literales entre comillas
+fn separar_fuera_de_comillas(texto: &str, separador: char) -> Vec<&str> {
+    let (mut partes, mut inicio, mut entre_comillas) = (vec![], 0, false);
+    for (indice, caracter) in texto.char_indices() {
+        if caracter == '\'' {
+            entre_comillas = !entre_comillas;
+        } else if caracter == separador && !entre_comillas {
+            partes.push(&texto[inicio..indice]);
+            inicio = indice + caracter.len_utf8();
+        }
+    }
+    partes.push(&texto[inicio..]);
+    partes
+}
+
+impl fmt::Display for TipoColumna {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TipoColumna::Ascii => write!(f, "ascii"),
+            TipoColumna::BigInt => write!(f, "bigint"),
+            TipoColumna::Blob => write!(f, "blob"),
+            TipoColumna::Boolean => write!(f, "boolean"),
+            TipoColumna::Counter => write!(f, "counter"),
+            TipoColumna::Double => write!(f, "double"),
+            TipoColumna::Float => write!(f, "float"),
+            TipoColumna::Int => write!(f, "int"),
+            TipoColumna::Timestamp => write!(f, "timestamp"),
+            TipoColumna::Uuid => write!(f, "uuid"),
+            TipoColumna::Text => write!(f, "text"),
+            TipoColumna::Inet => write!(f, "inet"),
+            TipoColumna::List(elemento) => write!(f, "list<{}>", elemento),
+            TipoColumna::Set(elemento) => write!(f, "set<{}>", elemento),
+            TipoColumna::Map(clave, valor) => write!(f, "map<{}, {}>", clave, valor),
+            TipoColumna::Tuple(elementos) => {
+                let elementos = elementos.iter().map(|elemento| elemento.to_string());
+                write!(
+                    f,
+                    "tuple<{}>",
+                    elementos.collect::<Vec<String>>().join(", ")
+                )
+            }
+            TipoColumna::Udt(udt, true) => write!(f, "frozen<{}>", udt.nombre),
+            TipoColumna::Udt(udt, false) => write!(f, "{}", udt.nombre),
+        }
+    }
+}
+
+/// Quita las comillas simples que delimitan a un literal de texto
+pub fn quitar_comillas(valor: &str) -> &str {
+    valor
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .unwrap_or(valor)
+}
+
+fn deserializar_hexa(valor: &str) -> Option<Vec<u8>> {
+    let hexa = valor.strip_prefix("0x")?;
+    (0..hexa.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hexa.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Los 16 bytes de un uuid escrito como 32 dígitos hexadecimales en grupos de 8-4-4-4-12
+fn deserializar_uuid(valor: &str) -> Option<Vec<u8>> {
+    let grupos: Vec<usize> = valor.split('-').map(str::len).collect();
+    if grupos != [8, 4, 4, 4, 12] {
+        return None;
+    }
+    deserializar_hexa(&format!("0x{}", valor.replace('-', "")))
+}
+
+// Los 4 bytes de una dirección IPv4 o los 16 de una IPv6
+fn deserializar_inet(valor: &str) -> Option<Vec<u8>> {
+    match valor.parse::<IpAddr>().ok()? {
+        IpAddr::V4(ip) => Some(ip.octets().to_vec()),
+        IpAddr::V6(ip) => Some(ip.octets().to_vec()),
+    }
+}
+
+fn valor_uuid(bytes: &[u8]) -> Result<ValorCQL, String> {
+    let uuid = bytes
+        .try_into()
+        .map_err(|_| format!("Un uuid ocupa 16 bytes, no {}", bytes.len()))?;
+    Ok(ValorCQL::Uuid(uuid))
+}
+
+fn valor_inet(bytes: &[u8]) -> Result<ValorCQL, String> {
+    let ip = match bytes.len() {
+        4 => IpAddr::from(<[u8; 4]>::try_from(bytes).unwrap_or_default()),
+        16 => IpAddr::from(<[u8; 16]>::try_from(bytes).unwrap_or_default()),
+        largo => return Err(format!("Un inet ocupa 4 o 16 bytes, no {}", largo)),
+    };
+    Ok(ValorCQL::Inet(ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializar_y_deserializar_valores() {
+        let casos = [
+            (TipoColumna::Int, "42", ValorCQL::Int(42)),
+            (TipoColumna::Float, "-34.5592", ValorCQL::Float(-34.5592)),
+            (TipoColumna::Double, "11500", ValorCQL::Double(11500.0)),
+            (TipoColumna::Boolean, "true", ValorCQL::Boolean(true)),
+            (
+                TipoColumna::Inet,
+                "'127.0.0.2'",
+                ValorCQL::Inet(IpAddr::from([127, 0, 0, 2])),
+            ),
+            (
+                TipoColumna::Uuid,
+                "0000002a-0000-3000-8000-000000000000",
+                ValorCQL::Uuid([0, 0, 0, 42, 0, 0, 0x30, 0, 0x80, 0, 0, 0, 0, 0, 0, 0]),
+            ),
+            (
+                TipoColumna::Text,
+                "'AEROPUERTO JORGE NEWBERY'",
+                ValorCQL::Texto("AEROPUERTO JORGE NEWBERY".to_string()),
+            ),
+        ];
+
+        for (tipo, texto, esperado) in casos {
+            let bytes = tipo.serializar_valor(texto).unwrap();
+            assert_eq!(tipo.deserializar_valor(&bytes).unwrap(), esperado);
+        }
+    }
+
+    #[test]
+    fn test_serializar_y_deserializar_colecciones() {
+        let casos = [
+            ("list<int>", "[3;1;3]", "[3, 1, 3]"),
+            ("set<text>", "{'A4';'B;2'}", "{'A4', 'B;2'}"),
+            ("map<text, float>", "{'tramo:1':12.5}", "{'tramo:1': 12.5}"),
+        ];
+
+        for (nombre, texto, esperado) in casos {
+            let tipo = TipoColumna::create(nombre).unwrap();
+            let mut option = vec![];
+            tipo.serializar_option(&mut option);
+            let bytes = tipo.serializar_valor(texto).unwrap();
+
+            let leido = TipoColumna::deserializar_option(&mut LectorBytes::new(&option)).unwrap();
+            assert_eq!(leido, tipo);
+            assert_eq!(
+                tipo.deserializar_valor(&bytes).unwrap().to_string(),
+                esperado
+            );
+        }
+        assert!(TipoColumna::create("set<list<int>>").is_err());
+        assert!(TipoColumna::create("map<text>").is_err());
+    }
+
+    #[test]
+    fn test_serializar_y_deserializar_tuplas_y_udts() {
+        let posicion = TipoUsuario {
+            keyspace: "aerolineas".to_string(),
+            nombre: "posicion".to_string(),
+            campos: vec![
+                ("lat".to_string(), TipoColumna::Double),
+                ("lon".to_string(), TipoColumna::Double),
+            ],
+        };
+        let udts = [posicion];
+        let casos = [
+            ("tuple<text, int>", "('EZE';2)", "('EZE', 2)"),
+            (
+                "frozen<posicion>",
+                "{lat:-34.5;lon:}",
+                "{lat: -34.5, lon: null}",
+            ),
+        ];
+
+        for (nombre, texto, esperado) in casos {
+            let tipo = TipoColumna::create_con_udts(nombre, &udts).unwrap();
+            let mut option = vec![];
+            tipo.serializar_option(&mut option);
+            let bytes = tipo.serializar_valor(texto).unwrap();
+
+            let leido = TipoColumna::deserializar_option(&mut LectorBytes::new(&option)).unwrap();
+            assert_eq!(leido, tipo);
+            assert_eq!(
+                tipo.deserializar_valor(&bytes).unwrap().to_string(),
+                esperado
+            );
+        }
+        assert!(TipoColumna::create_con_udts("frozen<int>", &udts).is_err());
+    }
+
+    #[test]
+    fn test_valor_que_no_corresponde_al_tipo() {
+        assert!(TipoColumna::Int.serializar_valor("VUE123").is_err());
+        assert!(TipoColumna::Inet.serializar_valor("'127.0.0'").is_err());
+        assert!(TipoColumna::Uuid.serializar_valor("0000002a-0000").is_err());
+        assert!(TipoColumna::create("POLIGONO").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::net::IpAddr;
+
+/// Enum que representa un valor ya deserializado de una columna
+/// con el tipo nativo que le corresponde según su tipo CQL
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValorCQL {
+    Nulo,
+    Int(i32),
+    BigInt(i64),
+    Float(f32),
+    Double(f64),
+    Boolean(bool),
+    Texto(String),
+    Blob(Vec<u8>),
+    Timestamp(i64),
+    Uuid([u8; 16]),
+    Inet(IpAddr),
+    Lista(Vec<ValorCQL>),
+    Conjunto(Vec<ValorCQL>),
+    Mapa(Vec<(ValorCQL, ValorCQL)>),
+    Tupla(Vec<ValorCQL>),
+    Udt(Vec<(String, ValorCQL)>), // Nombre y valor de cada campo, en el orden del tipo
+}
+
+impl ValorCQL {
+    /// Devuelve el valor como un entero de 32 bits si es numérico
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            ValorCQL::Int(n) => Some(*n),
+            ValorCQL::BigInt(n) | ValorCQL::Timestamp(n) => i32::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+
+    /// Devuelve el valor como un entero de 64 bits si es numérico
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ValorCQL::Int(n) => Some(*n as i64),
+            ValorCQL::BigInt(n) | ValorCQL::Timestamp(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Devuelve el valor como un flotante de 32 bits si es numérico
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            ValorCQL::Float(n) => Some(*n),
+            ValorCQL::Double(n) => Some(*n as f32),
+            ValorCQL::Int(n) => Some(*n as f32),
+            ValorCQL::BigInt(n) => Some(*n as f32),
+            _ => None,
+        }
+    }
+
+    /// Devuelve el valor como un flotante de 64 bits si es numérico
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ValorCQL::Double(n) => Some(*n),
+            ValorCQL::Float(n) => Some(*n as f64),
+            ValorCQL::Int(n) => Some(*n as f64),
+            ValorCQL::BigInt(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    /// Devuelve el valor como texto si es de tipo texto
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ValorCQL::Texto(texto) => Some(texto),
+            _ => None,
+        }
+    }
+
+    /// Indica si el valor es nulo
+    pub fn es_nulo(&self) -> bool {
+        *self == ValorCQL::Nulo
+    }
+}
+
+impl fmt::Display for ValorCQL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValorCQL::Nulo => write!(f, ""),
+            ValorCQL::Int(n) => write!(f, "{}", n),
+            ValorCQL::BigInt(n) | ValorCQL::Timestamp(n) => write!(f, "{}", n),
+            ValorCQL::Float(n) => write!(f, "{}", n),
+            ValorCQL::Double(n) => write!(f, "{}", n),
+            ValorCQL::Boolean(b) => write!(f, "{}", b),
+            ValorCQL::Texto(texto) => write!(f, "{}", texto),
+            ValorCQL::Blob(bytes) => {
+                write!(f, "0x")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+            ValorCQL::Uuid(bytes) => escribir_uuid(f, bytes),
+            ValorCQL::Inet(ip) => write!(f, "{}", ip),
+            ValorCQL::Lista(elementos) => write!(f, "[{}]", unir_elementos(elementos)),
+            ValorCQL::Conjunto(elementos) => write!(f, "{{{}}}", unir_elementos(elementos)),
+            ValorCQL::Mapa(pares) => {
+                let pares = pares
+                    .iter()
+                    .map(|(clave, valor)| format!("{}: {}", elemento(clave), elemento(valor)));
+                write!(f, "{{{}}}", pares.collect::<Vec<String>>().join(", "))
+            }
+            ValorCQL::Tupla(elementos) => write!(f, "({})", unir_elementos(elementos)),
+            ValorCQL::Udt(campos) => write!(f, "{{{}}}", unir_campos(campos)),
+        }
+    }
+}
+
+// 32 dígitos hexadecimales en grupos de 8-4-4-4-12
+fn escribir_uuid(f: &mut fmt::Formatter<'_>, bytes: &[u8; 16]) -> fmt::Result {
+    for (i, byte) in bytes.iter().enumerate() {
+        if [4, 6, 8, 10].contains(&i) {
+            write!(f, "-")?;
+        }
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+fn unir_elementos(elementos: &[ValorCQL]) -> String {
+    elementos
+        .iter()
+        .map(elemento)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn unir_campos(campos: &[(String, ValorCQL)]) -> String {
+    campos
+        .iter()
+        .map(|(campo, valor)| format!("{}: {}", campo, elemento(valor)))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Dentro de una colección, una tupla o un UDT los textos se muestran entre comillas y
+// los nulos como null, como en cqlsh
+fn elemento(valor: &ValorCQL) -> String {
+    match valor {
+        ValorCQL::Texto(texto) => format!("'{}'", texto),
+        ValorCQL::Nulo => "null".to_string(),
+        otro => otro.to_string(),
+    }
+}
@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+# Genera la CA del cluster y, firmados por ella, el certificado de cada nodo
+# (seguridad/nodos/nodeX.pem, con solo su nombre de servicio y su IP como SAN), uno
+# para un nodo que corre solo fuera de Docker (seguridad/cert.pem, como localhost) y el
+# de los clientes (seguridad/cliente.pem). Las claves privadas no se versionan: se
+# generan al desplegar, antes de construir las imágenes. Si la CA ya existe se la
+# conserva y solo se renuevan los demás certificados.
+# Se ejecuta desde la raíz del proyecto: ./seguridad/generar_certificados.sh
+set -e
+
+DIR=seguridad
+DIAS=3650
+CANTIDAD_NODOS=8
+export NOMBRE SAN
+
+firmar() { # firmar <certificado> <clave> <extension>
+    openssl req -new -newkey rsa:2048 -nodes -keyout "$2" -out "$1.csr" -config "$DIR/openssl.cnf"
+    openssl x509 -req -in "$1.csr" -CA "$DIR/ca.pem" -CAkey "$DIR/ca-key.pem" -CAcreateserial \
+        -days $DIAS -sha256 -extfile "$DIR/openssl.cnf" -extensions "$3" -out "$1"
+    rm "$1.csr"
+}
+
+if [ ! -f "$DIR/ca-key.pem" ]; then
+    NOMBRE="CA del cluster" SAN=""
+    openssl req -x509 -newkey rsa:2048 -nodes -keyout "$DIR/ca-key.pem" -out "$DIR/ca.pem" \
+        -days $DIAS -sha256 -config "$DIR/openssl.cnf" -extensions ext_ca
+fi
+
+mkdir -p "$DIR/nodos"
+for i in $(seq 1 $CANTIDAD_NODOS); do
+    NOMBRE="node$i" SAN="DNS:node$i,IP:127.0.0.$i"
+    firmar "$DIR/nodos/node$i.pem" "$DIR/nodos/node$i-key.pem" ext_nodo
+done
+
+NOMBRE="localhost" SAN="DNS:localhost"
+firmar "$DIR/cert.pem" "$DIR/key.pem" ext_nodo
+
+NOMBRE="cliente" SAN=""
+firmar "$DIR/cliente.pem" "$DIR/cliente-key.pem" ext_cliente
+
+rm -f "$DIR/ca.srl"
@@ -0,0 +1,197 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rustls::Certificate;
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_VERSION: u8 = 0xA0;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const SEGUNDOS_POR_DIA: u64 = 86_400;
+
+/// Fecha de vencimiento del certificado guardado en un archivo PEM
+#[derive(Debug, Clone, PartialEq)]
+pub struct VencimientoCertificado {
+    pub ruta: String,
+    pub vence: SystemTime,
+}
+
+impl VencimientoCertificado {
+    /// Días que faltan para que venza el certificado, negativo si ya venció
+    pub fn dias_restantes(&self) -> i64 {
+        match self.vence.duration_since(SystemTime::now()) {
+            Ok(falta) => (falta.as_secs() / SEGUNDOS_POR_DIA) as i64,
+            Err(paso) => -((paso.duration().as_secs() / SEGUNDOS_POR_DIA) as i64),
+        }
+    }
+}
+
+/// Devuelve la fecha de vencimiento (notAfter) de un certificado X.509. Recorre
+/// el DER del tbsCertificate salteando versión, número de serie, algoritmo de
+/// firma y emisor hasta llegar al período de validez
+pub fn vencimiento(certificado: &Certificate) -> Result<SystemTime, String> {
+    let (certificado, _) = leer_elemento(&certificado.0, TAG_SEQUENCE)?;
+    let (tbs, _) = leer_elemento(certificado, TAG_SEQUENCE)?;
+    let mut resto = tbs;
+    if resto.first() == Some(&TAG_VERSION) {
+        resto = saltear(resto)?;
+    }
+    for _ in 0..3 {
+        resto = saltear(resto)?;
+    }
+    let (validez, _) = leer_elemento(resto, TAG_SEQUENCE)?;
+    let (tag, fecha, _) = leer_tlv(saltear(validez)?)?;
+    parsear_fecha(tag, fecha)
+}
+
+// Devuelve el tag, el contenido y lo que sigue al elemento DER del principio
+fn leer_tlv(datos: &[u8]) -> Result<(u8, &[u8], &[u8]), String> {
+    let error = || "Certificado mal formado.".to_string();
+    let (&tag, resto) = datos.split_first().ok_or_else(error)?;
+    let (&primero, mut resto) = resto.split_first().ok_or_else(error)?;
+    let largo = if primero < 0x80 {
+        primero as usize
+    } else {
+        let bytes = (primero & 0x7F) as usize;
+        if bytes > 4 || resto.len() < bytes {
+            return Err(error());
+        }
+        let (largo, siguiente) = resto.split_at(bytes);
+        resto = siguiente;
+        largo
+            .iter()
+            .fold(0, |acc, byte| (acc << 8) | *byte as usize)
+    };
+    if resto.len() < largo {
+        return Err(error());
+    }
+    let (contenido, resto) = resto.split_at(largo);
+    Ok((tag, contenido, resto))
+}
+
+fn leer_elemento(datos: &[u8], tag_esperado: u8) -> Result<(&[u8], &[u8]), String> {
+    match leer_tlv(datos)? {
+        (tag, contenido, resto) if tag == tag_esperado => Ok((contenido, resto)),
+        _ => Err("Certificado mal formado.".to_string()),
+    }
+}
+
+fn saltear(datos: &[u8]) -> Result<&[u8], String> {
+    leer_tlv(datos).map(|(_, _, resto)| resto)
+}
+
+// UTCTime (AAMMDDHHMMSSZ) o GeneralizedTime (AAAAMMDDHHMMSSZ)
+fn parsear_fecha(tag: u8, fecha: &[u8]) -> Result<SystemTime, String> {
+    let texto = std::str::from_utf8(fecha).map_err(|_| "Fecha de certificado inválida.")?;
+    let (anio, resto) = match tag {
+        TAG_UTC_TIME => match numero(texto, 0, 2)? {
+            anio if anio >= 50 => (1900 + anio, 2),
+            anio => (2000 + anio, 2),
+        },
+        TAG_GENERALIZED_TIME => (numero(texto, 0, 4)?, 4),
+        _ => return Err("Fecha de certificado inválida.".to_string()),
+    };
+    let mes = numero(texto, resto, resto + 2)?;
+    let dia = numero(texto, resto + 2, resto + 4)?;
+    let hora = numero(texto, resto + 4, resto + 6)?;
+    let minuto = numero(texto, resto + 6, resto + 8)?;
+    let segundo = numero(texto, resto + 8, resto + 10)?;
+    let segundos = dias_desde_epoch(anio, mes, dia) * SEGUNDOS_POR_DIA as i64
+        + hora * 3600
+        + minuto * 60
+        + segundo;
+    let segundos = u64::try_from(segundos).map_err(|_| "Fecha de certificado inválida.")?;
+    Ok(UNIX_EPOCH + Duration::from_secs(segundos))
+}
+
+fn numero(texto: &str, desde: usize, hasta: usize) -> Result<i64, String> {
+    texto
+        .get(desde..hasta)
+        .and_then(|digitos| digitos.parse().ok())
+        .ok_or("Fecha de certificado inválida.".to_string())
+}
+
+// Días entre el 1/1/1970 y la fecha indicada (algoritmo days_from_civil)
+fn dias_desde_epoch(anio: i64, mes: i64, dia: i64) -> i64 {
+    let anio = if mes <= 2 { anio - 1 } else { anio };
+    let era = anio.div_euclid(400);
+    let anio_de_era = anio - era * 400;
+    let dia_del_anio = (153 * ((mes + 9) % 12) + 2) / 5 + dia - 1;
+    let dia_de_era = anio_de_era * 365 + anio_de_era / 4 - anio_de_era / 100 + dia_del_anio;
+    era * 146_097 + dia_de_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_certs;
+
+    fn fixture(nombre: &str) -> Certificate {
+        let ruta = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), nombre);
+        get_certs(&ruta).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_vencimiento_en_utc_time() {
+        //Arrange
+        let certificado = fixture("vence_2030.pem");
+
+        //Act
+        let vence = vencimiento(&certificado).unwrap();
+
+        //Assert
+        // 2030-12-31 23:59:59 UTC
+        assert_eq!(vence, UNIX_EPOCH + Duration::from_secs(1_924_991_999));
+    }
+
+    #[test]
+    fn test_vencimiento_en_generalized_time() {
+        //Arrange
+        let certificado = fixture("vence_2060.pem");
+
+        //Act
+        let vence = vencimiento(&certificado).unwrap();
+
+        //Assert
+        // 2060-02-29 12:00:00 UTC
+        assert_eq!(vence, UNIX_EPOCH + Duration::from_secs(2_845_281_600));
+    }
+
+    #[test]
+    fn test_certificado_truncado_o_invalido_devuelve_error() {
+        //Arrange
+        let completo = fixture("vence_2030.pem").0;
+        let truncados = [10, completo.len() / 2, completo.len() - 1];
+
+        //Act
+        let errores = truncados
+            .iter()
+            .map(|largo| vencimiento(&Certificate(completo[..*largo].to_vec())));
+
+        //Assert
+        assert!(errores.into_iter().all(|resultado| resultado.is_err()));
+        assert!(vencimiento(&Certificate(vec![])).is_err());
+        assert!(vencimiento(&Certificate(b"no es un certificado".to_vec())).is_err());
+        assert!(vencimiento(&Certificate(vec![0x30, 0x84, 0xFF, 0xFF, 0xFF, 0xFF])).is_err());
+    }
+
+    #[test]
+    fn test_dias_restantes() {
+        //Arrange
+        let vence_en_diez_dias = VencimientoCertificado {
+            ruta: "vigente.pem".to_string(),
+            vence: SystemTime::now() + Duration::from_secs(10 * SEGUNDOS_POR_DIA + 60),
+        };
+        let vencido_hace_tres_dias = VencimientoCertificado {
+            ruta: "vencido.pem".to_string(),
+            vence: SystemTime::now() - Duration::from_secs(3 * SEGUNDOS_POR_DIA + 60),
+        };
+
+        //Act
+        let vigente = vence_en_diez_dias.dias_restantes();
+        let vencido = vencido_hace_tres_dias.dias_restantes();
+
+        //Assert
+        assert_eq!(vigente, 10);
+        assert_eq!(vencido, -3);
+    }
+}
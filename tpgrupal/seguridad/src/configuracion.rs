@@ -0,0 +1,91 @@
+use std::env;
+
+pub const VARIABLE_CA: &str = "TLS_CA";
+pub const VARIABLE_CERTIFICADO: &str = "TLS_CERTIFICADO";
+pub const VARIABLE_CLAVE: &str = "TLS_CLAVE";
+pub const VARIABLE_CERTIFICADO_CLIENTE: &str = "TLS_CERTIFICADO_CLIENTE";
+pub const VARIABLE_CLAVE_CLIENTE: &str = "TLS_CLAVE_CLIENTE";
+pub const VARIABLE_AUTENTICACION_CLIENTES: &str = "TLS_AUTENTICACION_CLIENTES";
+
+const CA_POR_DEFECTO: &str = "seguridad/ca.pem";
+const CERTIFICADO_POR_DEFECTO: &str = "seguridad/cert.pem";
+const CLAVE_POR_DEFECTO: &str = "seguridad/key.pem";
+
+/// Si el puerto de clientes (9042) pide certificado a los clientes. Entre
+/// nodos (9043 y 9044) el certificado siempre es obligatorio
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutenticacionClientes {
+    Ninguna,
+    Opcional,
+    Requerida,
+}
+
+impl AutenticacionClientes {
+    pub fn create(nombre: &str) -> Result<Self, String> {
+        match nombre.trim().to_lowercase().as_str() {
+            "ninguna" => Ok(AutenticacionClientes::Ninguna),
+            "opcional" => Ok(AutenticacionClientes::Opcional),
+            "requerida" => Ok(AutenticacionClientes::Requerida),
+            _ => Err(format!(
+                "Autenticación de clientes no soportada: {}",
+                nombre
+            )),
+        }
+    }
+}
+
+/// Rutas de los archivos PEM y modo de autenticación de clientes, leídos de
+/// las variables de entorno. Las que no están definidas toman su valor por defecto
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfiguracionTls {
+    pub ca: String,                                    // CA del cluster
+    pub certificado: String,                           // Certificado del nodo
+    pub clave: String,                                 // Clave privada del nodo
+    pub certificado_cliente: Option<(String, String)>, // (certificado, clave) de un cliente
+    pub autenticacion_clientes: AutenticacionClientes,
+}
+
+impl ConfiguracionTls {
+    pub fn desde_entorno() -> Result<Self, String> {
+        let certificado_cliente = match (
+            env::var(VARIABLE_CERTIFICADO_CLIENTE),
+            env::var(VARIABLE_CLAVE_CLIENTE),
+        ) {
+            (Ok(certificado), Ok(clave)) => Some((certificado, clave)),
+            _ => None,
+        };
+        let autenticacion_clientes = match env::var(VARIABLE_AUTENTICACION_CLIENTES) {
+            Ok(nombre) => AutenticacionClientes::create(&nombre)?,
+            Err(_) => AutenticacionClientes::Ninguna,
+        };
+        Ok(ConfiguracionTls {
+            ca: variable_o(VARIABLE_CA, CA_POR_DEFECTO),
+            certificado: variable_o(VARIABLE_CERTIFICADO, CERTIFICADO_POR_DEFECTO),
+            clave: variable_o(VARIABLE_CLAVE, CLAVE_POR_DEFECTO),
+            certificado_cliente,
+            autenticacion_clientes,
+        })
+    }
+
+    /// Devuelve las rutas de todos los archivos PEM configurados
+    pub fn archivos(&self) -> Vec<&str> {
+        let mut archivos = vec![self.ca.as_str(), &self.certificado, &self.clave];
+        if let Some((certificado, clave)) = &self.certificado_cliente {
+            archivos.extend([certificado.as_str(), clave]);
+        }
+        archivos
+    }
+
+    /// Devuelve las rutas de los certificados configurados, sin las claves
+    pub fn certificados(&self) -> Vec<&str> {
+        let mut certificados = vec![self.ca.as_str(), &self.certificado];
+        if let Some((certificado, _)) = &self.certificado_cliente {
+            certificados.push(certificado);
+        }
+        certificados
+    }
+}
+
+fn variable_o(variable: &str, por_defecto: &str) -> String {
+    env::var(variable).unwrap_or(por_defecto.to_string())
+}
@@ -0,0 +1,76 @@
+use std::num::NonZeroU32;
+
+use ring::pbkdf2::{self, PBKDF2_HMAC_SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+
+const ALGORITMO: &str = "pbkdf2_sha256";
+const ITERACIONES: u32 = 100_000;
+const LARGO_SALT: usize = 16;
+const LARGO_HASH: usize = 32;
+
+/// Calcula el hash de una contraseña con una salt aleatoria. El resultado tiene
+/// el formato "pbkdf2_sha256$iteraciones$salt$hash", con la salt y el hash en
+/// hexadecimal, y es lo único que se guarda de la contraseña
+pub fn hashear_password(password: &str) -> Result<String, String> {
+    let mut salt = [0u8; LARGO_SALT];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| "No se pudo generar la salt de la contraseña.".to_string())?;
+    Ok(hashear_con_salt(password, &salt, ITERACIONES))
+}
+
+/// Verifica una contraseña contra un hash generado con `hashear_password`
+pub fn verificar_password(password: &str, salted_hash: &str) -> bool {
+    let partes = salted_hash.split('$').collect::<Vec<&str>>();
+    let [ALGORITMO, iteraciones, salt, hash] = partes[..] else {
+        return false;
+    };
+    let (Some(iteraciones), Some(salt), Some(hash)) = (
+        iteraciones.parse().ok().and_then(NonZeroU32::new),
+        desde_hexa(salt),
+        desde_hexa(hash),
+    ) else {
+        return false;
+    };
+    pbkdf2::verify(
+        PBKDF2_HMAC_SHA256,
+        iteraciones,
+        &salt,
+        password.as_bytes(),
+        &hash,
+    )
+    .is_ok()
+}
+
+fn hashear_con_salt(password: &str, salt: &[u8], iteraciones: u32) -> String {
+    let mut hash = [0u8; LARGO_HASH];
+    let iteraciones_no_nulas = NonZeroU32::new(iteraciones).unwrap_or(NonZeroU32::MIN);
+    pbkdf2::derive(
+        PBKDF2_HMAC_SHA256,
+        iteraciones_no_nulas,
+        salt,
+        password.as_bytes(),
+        &mut hash,
+    );
+    format!(
+        "{}${}${}${}",
+        ALGORITMO,
+        iteraciones_no_nulas,
+        a_hexa(salt),
+        a_hexa(&hash)
+    )
+}
+
+fn a_hexa(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn desde_hexa(hexa: &str) -> Option<Vec<u8>> {
+    if !hexa.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hexa.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hexa.get(i..i + 2)?, 16).ok())
+        .collect()
+}
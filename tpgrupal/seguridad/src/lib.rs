@@ -1,13 +1,36 @@
+pub mod certificados;
+pub mod configuracion;
+pub mod contrasenias;
+pub mod recarga;
+
 use std::{
     fs::File,
     io::{BufReader, Seek, SeekFrom},
+    net::IpAddr,
+    sync::{Arc, RwLock},
 };
 
-use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig};
+use configuracion::{AutenticacionClientes, ConfiguracionTls};
+use rustls::server::{
+    AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, NoClientAuth,
+    WantsServerCert,
+};
+use rustls::{
+    Certificate, ClientConfig, ConfigBuilder, PrivateKey, RootCertStore, ServerConfig, ServerName,
+};
 use rustls_pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
 
-pub fn get_certs() -> Result<Vec<Certificate>, String> {
-    let archivo_cert = File::open("seguridad/cert.pem");
+// Las configuraciones se arman la primera vez que se piden y se reutilizan en
+// cada conexión, en lugar de volver a leer los archivos PEM. Solo se reemplazan
+// al recargar los certificados (ver `recarga`)
+type Cache<T> = RwLock<Option<Arc<T>>>;
+static CONFIG_SERVIDOR_CLIENTES: Cache<ServerConfig> = RwLock::new(None);
+static CONFIG_SERVIDOR_INTERNODOS: Cache<ServerConfig> = RwLock::new(None);
+static CONFIG_CLIENTE: Cache<ClientConfig> = RwLock::new(None);
+static CONFIG_CLIENTE_INTERNODOS: Cache<ClientConfig> = RwLock::new(None);
+
+pub fn get_certs(ruta: &str) -> Result<Vec<Certificate>, String> {
+    let archivo_cert = File::open(ruta);
     if archivo_cert.is_err() {
         return Err("Error al abrir los archivos de certificado y clave privada.".to_string());
     }
@@ -19,9 +42,9 @@ pub fn get_certs() -> Result<Vec<Certificate>, String> {
     Ok(cert_chain)
 }
 
-pub fn get_keys() -> Result<Vec<PrivateKey>, String> {
-    let archivo_key = File::open("seguridad/key.pem")
-        .map_err(|_| "Error al abrir el archivo de clave privada.".to_string())?;
+pub fn get_keys(ruta: &str) -> Result<Vec<PrivateKey>, String> {
+    let archivo_key =
+        File::open(ruta).map_err(|_| "Error al abrir el archivo de clave privada.".to_string())?;
     let mut key_file = BufReader::new(archivo_key);
 
     // Intentar cargar claves privadas en formato PKCS#8
@@ -41,9 +64,10 @@ pub fn get_keys() -> Result<Vec<PrivateKey>, String> {
                     keys.into_iter().map(PrivateKey).collect::<Vec<_>>()
                 }
                 Ok(_) => {
-                    return Err(
-                        "No se encontraron claves privadas en el archivo key.pem.".to_string()
-                    )
+                    return Err(format!(
+                        "No se encontraron claves privadas en el archivo {}.",
+                        ruta
+                    ))
                 }
                 Err(e) => return Err(format!("Error al cargar las claves privadas RSA: {}", e)),
             }
@@ -53,37 +77,184 @@ pub fn get_keys() -> Result<Vec<PrivateKey>, String> {
     Ok(keys)
 }
 
-pub fn create_server_config() -> Result<ServerConfig, String> {
-    let certs = get_certs();
-    let key = get_keys();
-    if let (Ok(certs), Ok(mut keys)) = (certs, key) {
-        Ok(ServerConfig::builder()
-            .with_safe_defaults()
-            .with_no_client_auth()
-            .with_single_cert(certs, keys.remove(0))
-            .expect("Failed to create server config"))
-    } else {
-        Err("Error al cargar los certificados y claves privadas".to_string())
-    }
+fn get_key(ruta: &str) -> Result<PrivateKey, String> {
+    get_keys(ruta)?.into_iter().next().ok_or(format!(
+        "No se encontraron claves privadas en el archivo {}.",
+        ruta
+    ))
 }
 
-pub fn create_client_config() -> Result<ClientConfig, String> {
+fn get_raices(ruta_ca: &str) -> Result<RootCertStore, String> {
     let mut root_cert_store = RootCertStore::empty();
-    let certs = get_certs();
-    match certs {
-        Ok(certs) => {
-            for cert in certs {
-                root_cert_store
-                    .add(&cert)
-                    .expect("Failed to add certificate to store");
-            }
-            Ok(
-                ClientConfig::builder()
-                    .with_safe_defaults()
-                    .with_root_certificates(root_cert_store)
-                    .with_no_client_auth(), // El cliente no presenta un certificado propio
-            )
+    for cert in get_certs(ruta_ca)? {
+        root_cert_store
+            .add(&cert)
+            .map_err(|e| format!("Certificado de la CA inválido: {}", e))?;
+    }
+    Ok(root_cert_store)
+}
+
+/// Configuración TLS del puerto de clientes (9042). Según TLS_AUTENTICACION_CLIENTES
+/// no les pide certificado, se los pide de forma opcional o los obliga a presentarlo,
+/// y en esos casos tiene que estar firmado por la CA del cluster
+pub fn config_servidor_clientes() -> Result<Arc<ServerConfig>, String> {
+    obtener_o_crear(&CONFIG_SERVIDOR_CLIENTES, crear_config_servidor_clientes)
+}
+
+/// Configuración TLS de los puertos entre nodos (9043 y 9044): el otro nodo
+/// siempre tiene que presentar un certificado firmado por la CA del cluster
+pub fn config_servidor_internodos() -> Result<Arc<ServerConfig>, String> {
+    obtener_o_crear(
+        &CONFIG_SERVIDOR_INTERNODOS,
+        crear_config_servidor_internodos,
+    )
+}
+
+/// Configuración TLS de los clientes (interfaz, consola) que se conectan al
+/// puerto 9042. Presentan un certificado solo si está configurado
+pub fn config_cliente() -> Result<Arc<ClientConfig>, String> {
+    obtener_o_crear(&CONFIG_CLIENTE, crear_config_cliente)
+}
+
+/// Configuración TLS con la que un nodo se conecta a otro, presentando su
+/// propio certificado
+pub fn config_cliente_internodos() -> Result<Arc<ClientConfig>, String> {
+    obtener_o_crear(&CONFIG_CLIENTE_INTERNODOS, crear_config_cliente_internodos)
+}
+
+fn obtener_o_crear<T>(
+    cache: &Cache<T>,
+    crear: fn() -> Result<Arc<T>, String>,
+) -> Result<Arc<T>, String> {
+    if let Some(config) = cache.read().ok().and_then(|config| config.clone()) {
+        return Ok(config);
+    }
+    let config = crear()?;
+    if let Ok(mut cache) = cache.write() {
+        *cache = Some(Arc::clone(&config));
+    }
+    Ok(config)
+}
+
+/// Vuelve a armar, leyendo de nuevo los archivos PEM, las configuraciones que ya se
+/// habían pedido. Solo las reemplaza si se pudieron armar todas: si falla alguna (por
+/// ejemplo porque la clave nueva todavía no se terminó de copiar) se conservan las
+/// anteriores. Las conexiones abiertas siguen con la configuración con la que se establecieron
+pub fn recargar_configuraciones() -> Result<(), String> {
+    let servidor_clientes = recrear(&CONFIG_SERVIDOR_CLIENTES, crear_config_servidor_clientes)?;
+    let servidor_internodos = recrear(
+        &CONFIG_SERVIDOR_INTERNODOS,
+        crear_config_servidor_internodos,
+    )?;
+    let cliente = recrear(&CONFIG_CLIENTE, crear_config_cliente)?;
+    let cliente_internodos = recrear(&CONFIG_CLIENTE_INTERNODOS, crear_config_cliente_internodos)?;
+    reemplazar(&CONFIG_SERVIDOR_CLIENTES, servidor_clientes);
+    reemplazar(&CONFIG_SERVIDOR_INTERNODOS, servidor_internodos);
+    reemplazar(&CONFIG_CLIENTE, cliente);
+    reemplazar(&CONFIG_CLIENTE_INTERNODOS, cliente_internodos);
+    Ok(())
+}
+
+fn recrear<T>(
+    cache: &Cache<T>,
+    crear: fn() -> Result<Arc<T>, String>,
+) -> Result<Option<Arc<T>>, String> {
+    match cache.read() {
+        Ok(config) if config.is_some() => crear().map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn reemplazar<T>(cache: &Cache<T>, nueva: Option<Arc<T>>) {
+    if let (Some(nueva), Ok(mut cache)) = (nueva, cache.write()) {
+        *cache = Some(nueva);
+    }
+}
+
+fn crear_config_servidor_clientes() -> Result<Arc<ServerConfig>, String> {
+    let config = ConfiguracionTls::desde_entorno()?;
+    let verificador = match config.autenticacion_clientes {
+        AutenticacionClientes::Ninguna => NoClientAuth::new(),
+        AutenticacionClientes::Opcional => {
+            AllowAnyAnonymousOrAuthenticatedClient::new(get_raices(&config.ca)?)
         }
-        Err(e) => Err(e),
+        AutenticacionClientes::Requerida => {
+            AllowAnyAuthenticatedClient::new(get_raices(&config.ca)?)
+        }
+    };
+    let builder = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_client_cert_verifier(verificador);
+    config_servidor(&config, builder)
+}
+
+fn crear_config_servidor_internodos() -> Result<Arc<ServerConfig>, String> {
+    let config = ConfiguracionTls::desde_entorno()?;
+    let builder = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(get_raices(&config.ca)?));
+    config_servidor(&config, builder)
+}
+
+fn config_servidor(
+    config: &ConfiguracionTls,
+    builder: ConfigBuilder<ServerConfig, WantsServerCert>,
+) -> Result<Arc<ServerConfig>, String> {
+    let server_config = builder
+        .with_single_cert(get_certs(&config.certificado)?, get_key(&config.clave)?)
+        .map_err(|e| format!("Error al crear la configuración TLS del servidor: {}", e))?;
+    Ok(Arc::new(server_config))
+}
+
+fn crear_config_cliente() -> Result<Arc<ClientConfig>, String> {
+    let config = ConfiguracionTls::desde_entorno()?;
+    let builder = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(get_raices(&config.ca)?);
+    let client_config = match &config.certificado_cliente {
+        Some((certificado, clave)) => builder
+            .with_single_cert(get_certs(certificado)?, get_key(clave)?)
+            .map_err(|e| format!("Certificado de cliente inválido: {}", e))?,
+        None => builder.with_no_client_auth(),
+    };
+    Ok(Arc::new(client_config))
+}
+
+fn crear_config_cliente_internodos() -> Result<Arc<ClientConfig>, String> {
+    let config = ConfiguracionTls::desde_entorno()?;
+    let client_config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(get_raices(&config.ca)?)
+        .with_single_cert(get_certs(&config.certificado)?, get_key(&config.clave)?)
+        .map_err(|e| format!("Certificado del nodo inválido: {}", e))?;
+    Ok(Arc::new(client_config))
+}
+
+/// Devuelve el nombre contra el que se verifica el certificado del servidor
+/// al conectarse a una dirección "host:puerto". Cada nodo tiene en su certificado
+/// solo su propio nombre, así que no se puede conectar a una IP sin indicar de qué
+/// nodo es (ver `direccion_y_nombre`)
+pub fn nombre_servidor(direccion: &str) -> Result<ServerName, String> {
+    let host = match direccion.rsplit_once(':') {
+        Some((host, _)) => host,
+        None => direccion,
+    };
+    if host.parse::<IpAddr>().is_ok() {
+        return Err(format!(
+            "No se puede verificar el certificado de {} por su IP, falta el nombre del nodo",
+            direccion
+        ));
     }
+    ServerName::try_from(host).map_err(|_| format!("Nombre de dominio inválido: {}", host))
+}
+
+/// Separa una línea "host:puerto [nombre]" de los archivos de seeds en la dirección
+/// a la que conectarse y el nombre contra el que se verifica su certificado, que es
+/// el host si no se indica otro (por ejemplo "127.0.0.1:9142 node2" para llegar a
+/// node2 por el puerto que publica Docker)
+pub fn direccion_y_nombre(linea: &str) -> Result<(String, ServerName), String> {
+    let mut partes = linea.split_whitespace();
+    let direccion = partes.next().unwrap_or_default();
+    let nombre = nombre_servidor(partes.next().unwrap_or(direccion))?;
+    Ok((direccion.to_string(), nombre))
 }
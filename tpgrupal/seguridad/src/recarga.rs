@@ -0,0 +1,96 @@
+use std::fs;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::certificados::{vencimiento, VencimientoCertificado};
+use crate::configuracion::ConfiguracionTls;
+use crate::{get_certs, recargar_configuraciones};
+
+// Con menos días que estos para el vencimiento de un certificado se avisa en cada revisión
+pub const DIAS_AVISO_VENCIMIENTO: i64 = 30;
+// Cada cuántas revisiones se vuelven a informar los vencimientos aunque no cambien los archivos
+const REVISIONES_ENTRE_INFORMES: u32 = 360;
+
+/// Inicia un hilo que cada `intervalo` revisa la fecha de modificación de los archivos
+/// PEM configurados. Si alguno cambió recarga las configuraciones TLS, que se usan para
+/// las conexiones nuevas mientras las abiertas terminan con las anteriores. Además
+/// informa cuánto falta para que venzan los certificados, para rotarlos a tiempo
+pub fn vigilar_certificados(intervalo: Duration) {
+    thread::spawn(move || {
+        let mut modificados = fechas_modificacion();
+        informar_vencimientos();
+        let mut revisiones = 0;
+        loop {
+            thread::sleep(intervalo);
+            revisiones += 1;
+            let actuales = fechas_modificacion();
+            if actuales != modificados {
+                match recargar_configuraciones() {
+                    Ok(()) => {
+                        println!("Se recargaron los certificados TLS.");
+                        modificados = actuales;
+                        revisiones = 0;
+                        informar_vencimientos();
+                    }
+                    // Se reintenta en la próxima revisión, por si la copia no había terminado
+                    Err(e) => eprintln!("No se pudieron recargar los certificados TLS: {}", e),
+                }
+            } else if revisiones % REVISIONES_ENTRE_INFORMES == 0 {
+                informar_vencimientos();
+            }
+        }
+    });
+}
+
+/// Devuelve la fecha de vencimiento de cada certificado configurado
+pub fn vencimientos_certificados() -> Result<Vec<VencimientoCertificado>, String> {
+    let config = ConfiguracionTls::desde_entorno()?;
+    let mut vencimientos = vec![];
+    for ruta in config.certificados() {
+        let certificado = get_certs(ruta)?
+            .into_iter()
+            .next()
+            .ok_or(format!("No hay certificados en el archivo {}.", ruta))?;
+        vencimientos.push(VencimientoCertificado {
+            ruta: ruta.to_string(),
+            vence: vencimiento(&certificado)?,
+        });
+    }
+    Ok(vencimientos)
+}
+
+fn informar_vencimientos() {
+    let vencimientos = match vencimientos_certificados() {
+        Ok(vencimientos) => vencimientos,
+        Err(e) => return eprintln!("No se pudo leer el vencimiento de los certificados: {}", e),
+    };
+    for vencimiento in vencimientos {
+        let dias = vencimiento.dias_restantes();
+        if dias < DIAS_AVISO_VENCIMIENTO {
+            eprintln!(
+                "ATENCIÓN: el certificado {} vence en {} días, hay que rotarlo.",
+                vencimiento.ruta, dias
+            );
+        } else {
+            println!(
+                "El certificado {} vence en {} días.",
+                vencimiento.ruta, dias
+            );
+        }
+    }
+}
+
+fn fechas_modificacion() -> Vec<Option<SystemTime>> {
+    let Ok(config) = ConfiguracionTls::desde_entorno() else {
+        return vec![];
+    };
+    config
+        .archivos()
+        .iter()
+        .map(|ruta| {
+            fs::metadata(ruta)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}